@@ -0,0 +1,53 @@
+//! Times building a string from 10k pieces with `s = s + piece` and with `join()`. Run
+//! with `cargo bench --bench join`.
+
+use std::time::{Duration, Instant};
+
+use rlox::{Interpreter, OutputBuffer, Runner};
+
+const PIECES: usize = 10_000;
+
+/// Runs `source`, checks that it printed the length of the built string and returns how
+/// long it took.
+fn time(name: &str, source: &str) -> Duration {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+
+    let start = Instant::now();
+    runner.run(name, source).expect("the benchmark runs");
+    let elapsed = start.elapsed();
+
+    assert_eq!(output.contents().trim(), (PIECES * 5).to_string());
+    elapsed
+}
+
+fn main() {
+    let concatenation = time(
+        "concatenation.lox",
+        &format!(
+            "var s = \"\";\n\
+             for (var i = 0; i < {}; i = i + 1) s = s + \"piece\";\n\
+             print s.len();\n",
+            PIECES
+        ),
+    );
+    let join = time(
+        "join.lox",
+        &format!(
+            "var pieces = [];\n\
+             for (var i = 0; i < {}; i = i + 1) pieces.push(\"piece\");\n\
+             print join(pieces, \"\").len();\n",
+            PIECES
+        ),
+    );
+
+    println!(
+        "{} pieces: s = s + piece in {:.1} ms, push + join() in {:.1} ms",
+        PIECES,
+        concatenation.as_secs_f64() * 1000.0,
+        join.as_secs_f64() * 1000.0
+    );
+}
@@ -0,0 +1,41 @@
+//! Times method calls inherited through a three-deep class chain. Run with
+//! `cargo bench --bench method_calls`.
+
+use std::time::Instant;
+
+use rlox::{Interpreter, OutputBuffer, Runner};
+
+const CALLS: usize = 1_000_000;
+
+fn main() {
+    let source = format!(
+        "class A {{ step(n) {{ return n + 1; }} }}\n\
+         class B < A {{}}\n\
+         class C < B {{}}\n\
+         var c = C();\n\
+         var total = 0;\n\
+         for (var i = 0; i < {}; i = i + 1) total = c.step(total);\n\
+         print total;\n",
+        CALLS
+    );
+
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+
+    let start = Instant::now();
+    runner
+        .run("method_calls.lox", &source)
+        .expect("the benchmark runs");
+    let elapsed = start.elapsed();
+
+    assert_eq!(output.contents().trim(), CALLS.to_string());
+    println!(
+        "{} method calls in {:.1} ms ({:.0} ns per call)",
+        CALLS,
+        elapsed.as_secs_f64() * 1000.0,
+        elapsed.as_nanos() as f64 / CALLS as f64
+    );
+}
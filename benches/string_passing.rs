@@ -0,0 +1,41 @@
+//! Times passing a 1MB string through function calls, which only bumps a reference count.
+//! Run with `cargo bench --bench string_passing`.
+
+use std::time::Instant;
+
+use rlox::{Interpreter, OutputBuffer, Runner};
+
+const CALLS: usize = 2_000;
+const BYTES: usize = 1 << 20;
+
+fn main() {
+    let source = format!(
+        "fun inner(s) {{ return s; }}\n\
+         fun outer(s) {{ return inner(s); }}\n\
+         var total = 0;\n\
+         for (var i = 0; i < {}; i = i + 1) total = total + outer(big).len();\n\
+         print total;\n",
+        CALLS
+    );
+
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.define_global("big", "x".repeat(BYTES));
+
+    let start = Instant::now();
+    runner
+        .run("string_passing.lox", &source)
+        .expect("the benchmark runs");
+    let elapsed = start.elapsed();
+
+    assert_eq!(output.contents().trim(), (CALLS * BYTES).to_string());
+    println!(
+        "{} calls passing a {} byte string in {:.1} ms",
+        CALLS,
+        BYTES,
+        elapsed.as_secs_f64() * 1000.0
+    );
+}
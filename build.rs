@@ -0,0 +1,20 @@
+//! Records the git commit the crate is built from, for `rlox::build_info()` and
+//! `lox --version`. Builds outside a git checkout, such as from a published crate, go without.
+
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+
+    let output = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output();
+    if let Ok(output) = output {
+        let hash = String::from_utf8_lossy(&output.stdout);
+        let hash = hash.trim();
+        if output.status.success() && !hash.is_empty() {
+            println!("cargo:rustc-env=RLOX_GIT_HASH={}", hash);
+        }
+    }
+}
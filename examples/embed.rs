@@ -0,0 +1,150 @@
+//! Embedding the interpreter in a host program: checking a script before running it,
+//! handing it globals and natives, capturing what it prints and reading its results back.
+//!
+//! Run it with `cargo run --example embed`; `cargo test` runs it too and checks the output.
+
+use std::fmt::Write;
+
+use rlox::{Interpreter, LanguageOptions, Literal, LoxError, NativeError, OutputBuffer, Runner};
+
+const SCRIPT: &str = r#"
+fun double(n) {
+    return n * 2;
+}
+
+print greeting + ", " + user + "!";
+print "The answer is " + answer().toString() + ".";
+var doubled = mapNumbers([1, 2, 3], double);
+print doubled;
+var total = 0;
+while (doubled.len() > 0) total = total + doubled.pop();
+"#;
+
+/// Runs the example and returns everything it reports.
+fn run() -> String {
+    let mut transcript = String::new();
+
+    // Check the script without running it.
+    rlox::compile("embed.lox", SCRIPT, LanguageOptions::default()).unwrap();
+    writeln!(transcript, "compiled embed.lox").unwrap();
+
+    // Collect what the script prints instead of sending it to stdout.
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+
+    runner.define_global("greeting", "Hello");
+    runner.define_global("user", "host");
+
+    // A native that returns a value.
+    runner.define_native("answer", 0, |_, _| Ok(Literal::Number(42.0)));
+
+    // A native that calls back into the script for each element of an array.
+    runner.define_native("mapNumbers", 2, |interpreter, args| {
+        let items = match &args[0] {
+            Literal::Array(items) => items.borrow().clone(),
+            other => {
+                return Err(NativeError::new(format!(
+                    "mapNumbers() expects an Array, not a {}.",
+                    other.type_name()
+                )))
+            }
+        };
+        let mapped = items
+            .iter()
+            .map(|item| interpreter.call_function(&args[1], std::slice::from_ref(item)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Literal::from(mapped))
+    });
+
+    runner.run("embed.lox", SCRIPT).unwrap();
+    write!(transcript, "{}", output.contents()).unwrap();
+
+    // Read a global the script defined.
+    let total = runner.interpreter().get_global_number("total").unwrap();
+    writeln!(transcript, "total = {}", total).unwrap();
+
+    // Every way a script can fail.
+    let failing = [
+        ("lex.lox", "var a = 1 # 2;"),
+        ("parse.lox", "var = 1;"),
+        ("resolve.lox", "{ var a = a; }"),
+        ("runtime.lox", "mapNumbers(1, double);"),
+        (
+            "callback.lox",
+            "fun broken(n) {\n    return n + nil;\n}\nmapNumbers([1], broken);",
+        ),
+    ];
+    let mut errors: Vec<LoxError> = failing
+        .iter()
+        .map(|(name, code)| runner.run(name, code).unwrap_err())
+        .collect();
+    // Scripts read from disk fail before they run if the file can't be read.
+    errors.push(LoxError::from(
+        std::fs::read_to_string("missing.lox").unwrap_err(),
+    ));
+
+    for error in errors {
+        // The diagnostics are there to inspect, not only to print.
+        let summary = match &error {
+            // The OS message differs between platforms.
+            LoxError::Io(e) => format!("io: {:?}", e.kind()),
+            LoxError::Lex(diagnostics) => format!("lex: {} problem(s)", diagnostics.len()),
+            LoxError::Parse(diagnostics) => format!("parse: {} problem(s)", diagnostics.len()),
+            LoxError::Resolve(diagnostics) => {
+                format!("resolve: {} problem(s)", diagnostics.len())
+            }
+            LoxError::DeniedWarnings(diagnostics) => {
+                format!("denied: {} warning(s)", diagnostics.len())
+            }
+            LoxError::Runtime(diagnostic) => {
+                format!("runtime: {} call(s) deep", diagnostic.trace.len())
+            }
+        };
+        writeln!(transcript, "{} (exit code {})", summary, error.exit_code()).unwrap();
+        if !matches!(error, LoxError::Io(_)) {
+            writeln!(transcript, "{}", error).unwrap();
+        }
+    }
+
+    transcript
+}
+
+fn main() {
+    print!("{}", run());
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn transcript() {
+        assert_eq!(
+            super::run(),
+            concat!(
+                "compiled embed.lox\n",
+                "Hello, host!\n",
+                "The answer is 42.\n",
+                "[2, 4, 6]\n",
+                "total = 12\n",
+                "lex: 1 problem(s) (exit code 65)\n",
+                "Lexer Error: lex.lox:1:11 Unexpected character '#' (U+0023).\n",
+                "parse: 1 problem(s) (exit code 65)\n",
+                "Parser Error: parse.lox:1:5 Expect variable name.\n",
+                "resolve: 1 problem(s) (exit code 65)\n",
+                "Resolver Error: resolve.lox:1:11 Can't read local variable 'a' in its own initializer (declared at line 1).\n",
+                "runtime: 2 call(s) deep (exit code 70)\n",
+                "Interpreter Error: runtime.lox:1:1 mapNumbers() expects an Array, not a Number.\n",
+                "    in <native fn mapNumbers> at runtime.lox:1:1\n",
+                "    in script at runtime.lox:1:1\n",
+                "runtime: 3 call(s) deep (exit code 70)\n",
+                "Interpreter Error: callback.lox:2:12 Operands must be two numbers or two strings.\n",
+                "    in <fn broken> at callback.lox:2:5\n",
+                "    in <native fn mapNumbers> at callback.lox:4:1\n",
+                "    in script at callback.lox:4:1\n",
+                "io: NotFound (exit code 66)\n",
+            )
+        );
+    }
+}
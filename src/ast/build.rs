@@ -0,0 +1,137 @@
+//! Constructors for building programs in Rust instead of parsing them, for tools that
+//! generate Lox. Everything built here has a span in the `<generated>` pseudo-file, which is
+//! where diagnostics about it point.
+//!
+//! ```
+//! use rlox::{BinOp, Expr, Runner, Stmt};
+//!
+//! let program = vec![
+//!     Stmt::var("x", Some(Expr::literal(20))),
+//!     Stmt::print(Expr::binary(Expr::variable("x"), BinOp::Plus, Expr::literal(22))),
+//! ];
+//! Runner::new().run_program(program).unwrap();
+//! ```
+
+use crate::lexer::{Span, Token, TokenKind};
+
+use super::{
+    expr::{BinOp, Expr, ExprKind, Literal, LogOp, UnOp},
+    stmt::{Stmt, StmtKind},
+};
+
+impl Expr {
+    pub fn generated(kind: ExprKind) -> Expr {
+        Expr {
+            kind,
+            span: Span::generated(),
+        }
+    }
+
+    pub fn literal(value: impl Into<Literal>) -> Expr {
+        Expr::generated(ExprKind::Literal(value.into()))
+    }
+
+    pub fn nil() -> Expr {
+        Expr::generated(ExprKind::Literal(Literal::Nil))
+    }
+
+    pub fn variable(name: &str) -> Expr {
+        Expr::generated(ExprKind::Variable(Token::identifier(name), None))
+    }
+
+    pub fn assign(name: &str, value: Expr) -> Expr {
+        Expr::generated(ExprKind::Assign(
+            Token::identifier(name),
+            Box::new(value),
+            None,
+        ))
+    }
+
+    pub fn binary(left: Expr, operator: BinOp, right: Expr) -> Expr {
+        Expr::generated(ExprKind::Binary(operator, Box::new(left), Box::new(right)))
+    }
+
+    pub fn logical(left: Expr, operator: LogOp, right: Expr) -> Expr {
+        Expr::generated(ExprKind::Logical(operator, Box::new(left), Box::new(right)))
+    }
+
+    pub fn unary(operator: UnOp, operand: Expr) -> Expr {
+        Expr::generated(ExprKind::Unary(operator, Box::new(operand)))
+    }
+
+    pub fn call(callee: Expr, arguments: Vec<Expr>) -> Expr {
+        Expr::generated(ExprKind::Call(Box::new(callee), arguments))
+    }
+
+    /// Reads the property `name` of `object`.
+    pub fn get(object: Expr, name: &str) -> Expr {
+        Expr::generated(ExprKind::Get(
+            Box::new(object),
+            Token::identifier(name),
+            false,
+        ))
+    }
+
+    /// Assigns `value` to the property `name` of `object`.
+    pub fn set(object: Expr, name: &str, value: Expr) -> Expr {
+        Expr::generated(ExprKind::Set(
+            Box::new(object),
+            Token::identifier(name),
+            Box::new(value),
+        ))
+    }
+}
+
+impl Stmt {
+    pub fn generated(kind: StmtKind) -> Stmt {
+        Stmt {
+            kind,
+            span: Span::generated(),
+        }
+    }
+
+    pub fn expression(expr: Expr) -> Stmt {
+        Stmt::generated(StmtKind::Expression(expr))
+    }
+
+    pub fn print(expr: Expr) -> Stmt {
+        Stmt::generated(StmtKind::Print(expr))
+    }
+
+    pub fn var(name: &str, initializer: Option<Expr>) -> Stmt {
+        Stmt::generated(StmtKind::Var(Token::identifier(name), initializer))
+    }
+
+    pub fn block(stmts: Vec<Stmt>) -> Stmt {
+        Stmt::generated(StmtKind::Block(stmts))
+    }
+
+    pub fn if_else(condition: Expr, then_branch: Stmt, else_branch: Option<Stmt>) -> Stmt {
+        Stmt::generated(StmtKind::If(
+            vec![(condition, then_branch)],
+            else_branch.map(Box::new),
+        ))
+    }
+
+    pub fn while_loop(condition: Expr, body: Stmt) -> Stmt {
+        Stmt::generated(StmtKind::While(condition, Box::new(body)))
+    }
+
+    pub fn function(name: &str, params: &[&str], body: Vec<Stmt>) -> Stmt {
+        let params = params
+            .iter()
+            .map(|param| Token::identifier(param))
+            .collect();
+        Stmt::generated(StmtKind::Function(
+            Token::identifier(name),
+            params,
+            body,
+            None,
+        ))
+    }
+
+    pub fn return_value(value: Option<Expr>) -> Stmt {
+        let keyword = Token::generated(TokenKind::Return, "return");
+        Stmt::generated(StmtKind::Return(keyword, value))
+    }
+}
@@ -0,0 +1,372 @@
+use crate::lexer::{Span, Token};
+
+use super::{
+    expr::{Expr, ExprKind, Literal},
+    pattern::{ArmBody, MatchArm, Pattern, PatternKind},
+    stmt::{Stmt, StmtKind},
+};
+
+/// A syntax tree node stripped down to what the JSON and Graphviz exports need: its type,
+/// where it came from, and its named parts.
+pub struct Node {
+    kind: &'static str,
+    span: Span,
+    fields: Vec<(&'static str, Field)>,
+}
+
+enum Field {
+    Text(String),
+    Number(f64),
+    Bool(bool),
+    Null,
+    Node(Node),
+    List(Vec<Node>),
+}
+
+impl Node {
+    fn new(kind: &'static str, span: Span) -> Self {
+        Self {
+            kind,
+            span,
+            fields: vec![],
+        }
+    }
+
+    fn field(mut self, name: &'static str, field: Field) -> Self {
+        self.fields.push((name, field));
+        self
+    }
+
+    fn text(self, name: &'static str, text: impl ToString) -> Self {
+        self.field(name, Field::Text(text.to_string()))
+    }
+
+    fn child(self, name: &'static str, node: Node) -> Self {
+        self.field(name, Field::Node(node))
+    }
+
+    fn optional(self, name: &'static str, node: Option<Node>) -> Self {
+        match node {
+            Some(node) => self.child(name, node),
+            None => self.field(name, Field::Null),
+        }
+    }
+
+    fn list(self, name: &'static str, nodes: Vec<Node>) -> Self {
+        self.field(name, Field::List(nodes))
+    }
+
+    /// The node as a JSON object with a `type`, a `span`, and one member per field.
+    pub fn to_json(&self) -> String {
+        let mut json = format!(
+            "{{\"type\":{},\"span\":{{\"line\":{},\"column\":{},\"start\":{},\"end\":{}}}",
+            json_string(self.kind),
+            self.span.line,
+            self.span.column,
+            self.span.start,
+            self.span.end
+        );
+
+        for (name, field) in &self.fields {
+            let value = match field {
+                Field::Text(text) => json_string(text),
+                Field::Number(n) if n.is_finite() => n.to_string(),
+                Field::Number(n) => json_string(&n.to_string()),
+                Field::Bool(b) => b.to_string(),
+                Field::Null => "null".into(),
+                Field::Node(node) => node.to_json(),
+                Field::List(nodes) => format!(
+                    "[{}]",
+                    nodes
+                        .iter()
+                        .map(Node::to_json)
+                        .collect::<Vec<_>>()
+                        .join(",")
+                ),
+            };
+            json.push_str(&format!(",{}:{}", json_string(name), value));
+        }
+
+        json.push('}');
+        json
+    }
+
+    /// Appends the node and its descendants to a Graphviz graph, returning the node's id.
+    fn write_dot(&self, dot: &mut String, next_id: &mut usize) -> usize {
+        let id = *next_id;
+        *next_id += 1;
+
+        let mut label = format!("{} [{}:{}]", self.kind, self.span.line, self.span.column);
+        // Field values are escaped as they go in, so the `\n` separators stay line breaks.
+        for (name, field) in &self.fields {
+            let value = match field {
+                Field::Text(text) => text.clone(),
+                Field::Number(n) => n.to_string(),
+                Field::Bool(b) => b.to_string(),
+                Field::Null | Field::Node(_) | Field::List(_) => continue,
+            };
+            label.push_str(&format!("\\n{}: {}", name, dot_escape(&value)));
+        }
+        dot.push_str(&format!("  n{} [label=\"{}\"];\n", id, label));
+
+        for (name, field) in &self.fields {
+            match field {
+                Field::Node(node) => {
+                    let child = node.write_dot(dot, next_id);
+                    dot.push_str(&format!("  n{} -> n{} [label=\"{}\"];\n", id, child, name));
+                }
+                Field::List(nodes) => {
+                    for (index, node) in nodes.iter().enumerate() {
+                        let child = node.write_dot(dot, next_id);
+                        dot.push_str(&format!(
+                            "  n{} -> n{} [label=\"{}[{}]\"];\n",
+                            id, child, name, index
+                        ));
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        id
+    }
+}
+
+/// Renders statements as a Graphviz `digraph`, one tree per statement.
+pub fn to_dot(stmts: &[Stmt]) -> String {
+    let mut dot = String::from("digraph ast {\n  node [shape=box];\n");
+    let mut next_id = 0;
+
+    for stmt in stmts {
+        stmt_node(stmt).write_dot(&mut dot, &mut next_id);
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+/// Renders statements as a JSON array of statement objects.
+pub fn to_json(stmts: &[Stmt]) -> String {
+    let stmts: Vec<String> = stmts.iter().map(|s| stmt_node(s).to_json()).collect();
+    format!("[{}]", stmts.join(","))
+}
+
+pub fn stmt_node(stmt: &Stmt) -> Node {
+    let span = stmt.span;
+
+    match &stmt.kind {
+        StmtKind::Expression(expr) => {
+            Node::new("Expression", span).child("expression", expr_node(expr))
+        }
+        StmtKind::Print(expr) => Node::new("Print", span).child("expression", expr_node(expr)),
+        StmtKind::Var(name, initializer) => Node::new("Var", span)
+            .text("name", &name.lexeme)
+            .optional("initializer", initializer.as_ref().map(expr_node)),
+        StmtKind::Unpack(names, initializer) => Node::new("Unpack", span)
+            .list("names", names.iter().map(name_node).collect())
+            .child("initializer", expr_node(initializer)),
+        StmtKind::Const(name, initializer) => Node::new("Const", span)
+            .text("name", &name.lexeme)
+            .child("initializer", expr_node(initializer)),
+        StmtKind::Block(stmts) => Node::new("Block", span).list("statements", stmt_nodes(stmts)),
+        StmtKind::If(arms, else_branch) => Node::new("If", span)
+            .list(
+                "arms",
+                arms.iter()
+                    .map(|(condition, branch)| {
+                        Node::new("Arm", condition.span.to(branch.span))
+                            .child("condition", expr_node(condition))
+                            .child("then", stmt_node(branch))
+                    })
+                    .collect(),
+            )
+            .optional("else", else_branch.as_deref().map(stmt_node)),
+        StmtKind::While(condition, body) => Node::new("While", span)
+            .child("condition", expr_node(condition))
+            .child("body", stmt_node(body)),
+        StmtKind::For(initializer, condition, increment, body) => Node::new("For", span)
+            .optional("initializer", initializer.as_deref().map(stmt_node))
+            .optional("condition", condition.as_ref().map(expr_node))
+            .optional("increment", increment.as_ref().map(expr_node))
+            .child("body", stmt_node(body)),
+        StmtKind::ForIn(name, iterable, body) => Node::new("ForIn", span)
+            .text("name", &name.lexeme)
+            .child("iterable", expr_node(iterable))
+            .child("body", stmt_node(body)),
+        StmtKind::Function(name, params, body, _) => Node::new("Function", span)
+            .text("name", &name.lexeme)
+            .list("params", params.iter().map(param_node).collect())
+            .list("body", stmt_nodes(body)),
+        StmtKind::Return(_, value) => {
+            Node::new("Return", span).optional("value", value.as_ref().map(expr_node))
+        }
+        StmtKind::Defer(body) => Node::new("Defer", span).child("body", stmt_node(body)),
+        StmtKind::Class(name, superclass, members, _) => {
+            let (fields, methods): (Vec<&Stmt>, Vec<&Stmt>) = members
+                .iter()
+                .partition(|member| matches!(member.kind, StmtKind::Var(..)));
+            Node::new("Class", span)
+                .text("name", &name.lexeme)
+                .optional("superclass", superclass.as_ref().map(expr_node))
+                .list("fields", fields.into_iter().map(stmt_node).collect())
+                .list("methods", methods.into_iter().map(stmt_node).collect())
+        }
+        StmtKind::Extend(class, methods) => Node::new("Extend", span)
+            .child("class", expr_node(class))
+            .list("methods", stmt_nodes(methods)),
+        StmtKind::Enum(name, variants) => Node::new("Enum", span)
+            .text("name", &name.lexeme)
+            .list("variants", variants.iter().map(variant_node).collect()),
+    }
+}
+
+fn stmt_nodes(stmts: &[Stmt]) -> Vec<Node> {
+    stmts.iter().map(stmt_node).collect()
+}
+
+fn param_node(param: &Token) -> Node {
+    Node::new("Param", param.span()).text("name", &param.lexeme)
+}
+
+fn name_node(name: &Token) -> Node {
+    Node::new("Name", name.span()).text("name", &name.lexeme)
+}
+
+fn variant_node(variant: &Token) -> Node {
+    Node::new("Variant", variant.span()).text("name", &variant.lexeme)
+}
+
+fn expr_node(expr: &Expr) -> Node {
+    let span = expr.span;
+
+    match &expr.kind {
+        ExprKind::Array(elements) => {
+            Node::new("Array", span).list("elements", elements.iter().map(expr_node).collect())
+        }
+        ExprKind::Map(entries) => Node::new("Map", span).list(
+            "entries",
+            entries
+                .iter()
+                .map(|(key, value)| {
+                    Node::new("Entry", key.span.to(value.span))
+                        .child("key", expr_node(key))
+                        .child("value", expr_node(value))
+                })
+                .collect(),
+        ),
+        ExprKind::Binary(op, lhs, rhs) => Node::new("Binary", span)
+            .text("operator", op)
+            .child("left", expr_node(lhs))
+            .child("right", expr_node(rhs)),
+        ExprKind::Logical(op, lhs, rhs) => Node::new("Logical", span)
+            .text("operator", op)
+            .child("left", expr_node(lhs))
+            .child("right", expr_node(rhs)),
+        ExprKind::Grouping(expr) => {
+            Node::new("Grouping", span).child("expression", expr_node(expr))
+        }
+        ExprKind::Literal(literal) => {
+            let value = match literal {
+                Literal::Number(n) => Field::Number(*n),
+                Literal::Bool(b) => Field::Bool(*b),
+                Literal::Nil => Field::Null,
+                other => Field::Text(other.to_string()),
+            };
+            Node::new("Literal", span).field("value", value)
+        }
+        ExprKind::Unary(op, expr) => Node::new("Unary", span)
+            .text("operator", op)
+            .child("operand", expr_node(expr)),
+        ExprKind::Variable(name, _) => Node::new("Variable", span).text("name", &name.lexeme),
+        ExprKind::Assign(name, value, _) => Node::new("Assign", span)
+            .text("name", &name.lexeme)
+            .child("value", expr_node(value)),
+        ExprKind::Call(callee, arguments) => Node::new("Call", span)
+            .child("callee", expr_node(callee))
+            .list("arguments", arguments.iter().map(expr_node).collect()),
+        ExprKind::Get(object, name, optional) => Node::new("Get", span)
+            .child("object", expr_node(object))
+            .text("name", &name.lexeme)
+            .field("optional", Field::Bool(*optional)),
+        ExprKind::Index(value, index) => Node::new("Index", span)
+            .child("value", expr_node(value))
+            .child("index", expr_node(index)),
+        ExprKind::Set(object, name, value) => Node::new("Set", span)
+            .child("object", expr_node(object))
+            .text("name", &name.lexeme)
+            .child("value", expr_node(value)),
+        ExprKind::This(..) => Node::new("This", span),
+        ExprKind::Super(method, _) => Node::new("Super", span).text("method", &method.lexeme),
+        ExprKind::Match(value, arms) => Node::new("Match", span)
+            .child("value", expr_node(value))
+            .list("arms", arms.iter().map(arm_node).collect()),
+        ExprKind::Tuple(values) => {
+            Node::new("Tuple", span).list("values", values.iter().map(expr_node).collect())
+        }
+    }
+}
+
+fn arm_node(arm: &MatchArm) -> Node {
+    let node = Node::new("Arm", arm.pattern.span).child("pattern", pattern_node(&arm.pattern));
+    match &arm.body {
+        ArmBody::Expr(expr) => node.child("body", expr_node(expr)),
+        ArmBody::Block(stmts) => node.list("body", stmt_nodes(stmts)),
+    }
+}
+
+fn pattern_node(pattern: &Pattern) -> Node {
+    let span = pattern.span;
+
+    match &pattern.kind {
+        PatternKind::Wildcard => Node::new("WildcardPattern", span),
+        PatternKind::Literal(literal) => {
+            let value = match literal {
+                Literal::Number(n) => Field::Number(*n),
+                Literal::Bool(b) => Field::Bool(*b),
+                Literal::Nil => Field::Null,
+                other => Field::Text(other.to_string()),
+            };
+            Node::new("LiteralPattern", span).field("value", value)
+        }
+        PatternKind::Binding(name) => Node::new("BindingPattern", span).text("name", &name.lexeme),
+        PatternKind::Value(value) => {
+            Node::new("ValuePattern", span).child("value", expr_node(value))
+        }
+        PatternKind::Array(elements) => Node::new("ArrayPattern", span)
+            .list("elements", elements.iter().map(pattern_node).collect()),
+        PatternKind::Map(entries) => Node::new("MapPattern", span).list(
+            "entries",
+            entries
+                .iter()
+                .map(|(key, pattern)| {
+                    Node::new("Entry", pattern.span)
+                        .text("key", key)
+                        .child("pattern", pattern_node(pattern))
+                })
+                .collect(),
+        ),
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut json = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -1,11 +1,21 @@
-use std::fmt::Display;
+use std::{cell::RefCell, fmt::Display, rc::Rc};
 
 use crate::{
-    interpreter::callable::LoxCallable,
-    lexer::{Token, TokenKind},
+    interpreter::{
+        callable::LoxCallable,
+        class::LoxInstance,
+        enums::{EnumValue, LoxEnum},
+        identity::LoxWeakRef,
+        map::LoxMap,
+        number::format_number,
+        pretty,
+    },
+    lexer::{Span, Token, TokenKind},
 };
 
-#[derive(Clone, Copy, Debug)]
+use super::pattern::MatchArm;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum BinOp {
     Plus,
     Minus,
@@ -63,7 +73,7 @@ impl TryFrom<TokenKind> for BinOp {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum UnOp {
     BinNeg,
     LogNeg,
@@ -95,10 +105,11 @@ impl TryFrom<TokenKind> for UnOp {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum LogOp {
     And,
     Or,
+    Coalesce,
 }
 
 impl Display for LogOp {
@@ -106,6 +117,7 @@ impl Display for LogOp {
         let result = match self {
             LogOp::And => "and",
             LogOp::Or => "or",
+            LogOp::Coalesce => "??",
         };
 
         write!(f, "{}", result)
@@ -119,6 +131,7 @@ impl TryFrom<TokenKind> for LogOp {
         let op = match value {
             TokenKind::And => Self::And,
             TokenKind::Or => Self::Or,
+            TokenKind::QuestionQuestion => Self::Coalesce,
             _ => return Err("Unmatchable token"),
         };
 
@@ -128,22 +141,99 @@ impl TryFrom<TokenKind> for LogOp {
 
 #[derive(Clone, Debug)]
 pub enum Literal {
-    String(String),
+    /// Strings are immutable, so values share one buffer instead of copying it around.
+    String(Rc<str>),
     Number(f64),
     Bool(bool),
     Callable(LoxCallable),
+    Array(Rc<RefCell<Vec<Literal>>>),
+    Map(Rc<RefCell<LoxMap>>),
+    Enum(Rc<LoxEnum>),
+    EnumValue(Rc<EnumValue>),
+    Instance(Rc<RefCell<LoxInstance>>),
+    WeakRef(Rc<LoxWeakRef>),
+    /// The values a function returned together with `return a, b;`, in order.
+    Tuple(Rc<[Literal]>),
     Nil,
 }
 
+impl Literal {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Literal::String(_) => "String",
+            Literal::Number(_) => "Number",
+            Literal::Bool(_) => "Bool",
+            Literal::Callable(LoxCallable::Class(_)) => "Class",
+            Literal::Callable(_) => "Function",
+            Literal::Array(_) => "Array",
+            Literal::Map(_) => "Map",
+            Literal::Enum(_) => "Enum",
+            Literal::EnumValue(_) => "EnumValue",
+            Literal::Instance(_) => "Instance",
+            Literal::WeakRef(_) => "WeakRef",
+            Literal::Tuple(_) => "Tuple",
+            Literal::Nil => "Nil",
+        }
+    }
+}
+
+impl From<f64> for Literal {
+    fn from(n: f64) -> Self {
+        Literal::Number(n)
+    }
+}
+
+impl From<i32> for Literal {
+    fn from(n: i32) -> Self {
+        Literal::Number(n.into())
+    }
+}
+
+impl From<bool> for Literal {
+    fn from(b: bool) -> Self {
+        Literal::Bool(b)
+    }
+}
+
+impl From<&str> for Literal {
+    fn from(s: &str) -> Self {
+        Literal::String(s.into())
+    }
+}
+
+impl From<String> for Literal {
+    fn from(s: String) -> Self {
+        Literal::String(s.into())
+    }
+}
+
+impl<T: Into<Literal>> From<Vec<T>> for Literal {
+    fn from(elements: Vec<T>) -> Self {
+        let elements = elements.into_iter().map(Into::into).collect();
+        Literal::Array(Rc::new(RefCell::new(elements)))
+    }
+}
+
+impl<T: Into<Literal>> From<Option<T>> for Literal {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Literal::Nil, Into::into)
+    }
+}
+
 impl Display for Literal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         use Literal::*;
 
         let result = match self {
-            String(s) => format!("{}", s),
-            Number(n) => n.to_string(),
+            String(s) => s.to_string(),
+            Number(n) => format_number(*n),
             Bool(b) => b.to_string(),
-            Callable(_) => "callable".to_string(),
+            Callable(callable) => callable.to_string(),
+            Array(_) | Map(_) | Tuple(_) => pretty::inline(self),
+            Enum(lox_enum) => format!("<enum {}>", lox_enum.name),
+            EnumValue(value) => format!("{}.{}", value.enum_name, value.name),
+            Instance(instance) => format!("{} instance", instance.borrow().class.name),
+            WeakRef(_) => "<WeakRef>".to_string(),
             Nil => "nil".to_string(),
         };
 
@@ -153,17 +243,96 @@ impl Display for Literal {
 
 #[derive(Clone, Debug)]
 pub enum ExprKind {
+    Array(Vec<Expr>),
+    Map(Vec<(Expr, Expr)>),
     Binary(BinOp, Box<Expr>, Box<Expr>),
     Call(Box<Expr>, Vec<Expr>),
+    Get(Box<Expr>, Token, bool),
+    /// `value[index]`: a character of a string, or an element of an array or tuple.
+    Index(Box<Expr>, Box<Expr>),
     Grouping(Box<Expr>),
     Literal(Literal),
     Logical(LogOp, Box<Expr>, Box<Expr>),
     Unary(UnOp, Box<Expr>),
-    Variable(Token),
-    Assign(Token, Box<Expr>),
+    /// The resolved scope distance, or `None` for globals.
+    Variable(Token, Option<usize>),
+    Assign(Token, Box<Expr>, Option<usize>),
+    /// Assigns the value to the named field of the object.
+    Set(Box<Expr>, Token, Box<Expr>),
+    /// The `this` keyword, with its resolved scope distance.
+    This(Token, Option<usize>),
+    /// `super.method`: the method's name and the resolved scope distance of `super`.
+    Super(Token, Option<usize>),
+    /// The value being matched and the arms to try it against, in order.
+    Match(Box<Expr>, Vec<MatchArm>),
+    /// The values of `return a, b;`, packed into a tuple. The parser makes one only there.
+    Tuple(Vec<Expr>),
 }
 
 #[derive(Clone, Debug)]
 pub struct Expr {
     pub kind: ExprKind,
+    /// From the first to the last token of the expression.
+    pub span: Span,
+}
+
+impl Expr {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+/// Expressions are equal when they have the same structure, wherever they are in the source:
+/// spans are ignored, names compare by their text and literals the way `Literal::is_same`
+/// compares them.
+impl PartialEq for Expr {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
+impl PartialEq for ExprKind {
+    fn eq(&self, other: &Self) -> bool {
+        use ExprKind::*;
+        match (self, other) {
+            (Array(a), Array(b)) => a == b,
+            (Map(a), Map(b)) => a == b,
+            (Binary(op, lhs, rhs), Binary(other_op, other_lhs, other_rhs)) => {
+                op == other_op && lhs == other_lhs && rhs == other_rhs
+            }
+            (Logical(op, lhs, rhs), Logical(other_op, other_lhs, other_rhs)) => {
+                op == other_op && lhs == other_lhs && rhs == other_rhs
+            }
+            (Call(callee, arguments), Call(other_callee, other_arguments)) => {
+                callee == other_callee && arguments == other_arguments
+            }
+            (Get(object, name, optional), Get(other_object, other_name, other_optional)) => {
+                object == other_object && name == other_name && optional == other_optional
+            }
+            (Index(value, index), Index(other_value, other_index)) => {
+                value == other_value && index == other_index
+            }
+            (Grouping(a), Grouping(b)) => a == b,
+            (Literal(a), Literal(b)) => a.is_same(b),
+            (Unary(op, operand), Unary(other_op, other_operand)) => {
+                op == other_op && operand == other_operand
+            }
+            (Variable(name, depth), Variable(other_name, other_depth))
+            | (This(name, depth), This(other_name, other_depth))
+            | (Super(name, depth), Super(other_name, other_depth)) => {
+                name == other_name && depth == other_depth
+            }
+            (Assign(name, value, depth), Assign(other_name, other_value, other_depth)) => {
+                name == other_name && value == other_value && depth == other_depth
+            }
+            (Set(object, name, value), Set(other_object, other_name, other_value)) => {
+                object == other_object && name == other_name && value == other_value
+            }
+            (Match(value, arms), Match(other_value, other_arms)) => {
+                value == other_value && arms == other_arms
+            }
+            (Tuple(a), Tuple(b)) => a == b,
+            _ => false,
+        }
+    }
 }
@@ -0,0 +1,71 @@
+//! Lowering passes, which rewrite sugar into the smaller core the parser's other
+//! constructs already cover. The interpreter runs either form.
+
+use crate::lexer::Span;
+
+use super::{
+    expr::{Expr, ExprKind, Literal},
+    stmt::{Stmt, StmtKind},
+    transform::{walk_stmt, Fold},
+};
+
+/// Rewrites every `for` loop in `stmts` into a `while` loop.
+pub fn lower_for_loops(stmts: Vec<Stmt>) -> Vec<Stmt> {
+    LowerForLoops.fold_stmts(stmts)
+}
+
+/// Turns `for (initializer; condition; increment) body` into
+///
+/// ```text
+/// { initializer; while (condition) { body; increment; } }
+/// ```
+///
+/// leaving out the outer block without an initializer and the inner one without an
+/// increment. A missing condition becomes `true`, at the `for` keyword. The new statements
+/// share the span of the loop.
+struct LowerForLoops;
+
+impl Fold for LowerForLoops {
+    fn fold_stmt(&mut self, stmt: Stmt) -> Stmt {
+        let span = stmt.span;
+        let (initializer, condition, increment, body) = match walk_stmt(self, stmt).kind {
+            StmtKind::For(initializer, condition, increment, body) => {
+                (initializer, condition, increment, body)
+            }
+            kind => return Stmt { kind, span },
+        };
+
+        let mut body = *body;
+        if let Some(increment) = increment {
+            let increment = Stmt {
+                span: increment.span,
+                kind: StmtKind::Expression(increment),
+            };
+            body = Stmt {
+                kind: StmtKind::Block(vec![body, increment]),
+                span,
+            };
+        }
+
+        let condition = condition.unwrap_or_else(|| always_true(span));
+        let mut kind = StmtKind::While(condition, Box::new(body));
+
+        if let Some(initializer) = initializer {
+            let body = Stmt { kind, span };
+            kind = StmtKind::Block(vec![*initializer, body]);
+        }
+
+        Stmt { kind, span }
+    }
+}
+
+/// `true`, spanning the `for` keyword that starts the loop spanning `loop_span`.
+fn always_true(loop_span: Span) -> Expr {
+    Expr {
+        kind: ExprKind::Literal(Literal::Bool(true)),
+        span: Span {
+            end: loop_span.start + "for".len(),
+            ..loop_span
+        },
+    }
+}
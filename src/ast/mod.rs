@@ -1,19 +1,25 @@
+pub mod build;
+pub mod export;
 pub mod expr;
+pub mod lower;
+pub mod pattern;
+pub mod sexpr;
+pub mod source;
 pub mod stmt;
+pub mod transform;
 
-use self::expr::Expr;
+use self::{
+    expr::Expr,
+    pattern::{ArmBody, Pattern, PatternKind},
+    stmt::{Stmt, StmtKind},
+};
 use std::fmt::{self, Display, Formatter};
 
 macro_rules! parenthesize {
     ( $($x:expr),* ) => {
         {
-            let mut builder = String::from('(');
-            $(
-                builder.push_str(format!(" {}", $x).as_str());
-            )*
-            builder.push_str(")");
-
-            builder
+            let parts: Vec<String> = vec![$(format!("{}", $x)),*];
+            format!("({})", parts.join(" "))
         }
     }
 }
@@ -22,20 +28,210 @@ impl Display for Expr {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         use self::expr::ExprKind::*;
         let result = match &self.kind {
+            Array(elements) => {
+                let mut builder = String::from("(array");
+                for element in elements {
+                    builder.push_str(format!(" {}", element).as_str());
+                }
+                builder.push(')');
+
+                builder
+            }
+            Map(entries) => {
+                let mut builder = String::from("(map");
+                for (key, value) in entries {
+                    builder.push_str(format!(" ({} {})", key, value).as_str());
+                }
+                builder.push(')');
+
+                builder
+            }
+            Tuple(values) => {
+                let mut builder = String::from("(tuple");
+                for value in values {
+                    builder.push_str(format!(" {}", value).as_str());
+                }
+                builder.push(')');
+
+                builder
+            }
             Binary(op, lhs, rhs) => parenthesize!(op, lhs, rhs),
             Grouping(expr) => parenthesize!("group", expr),
+            Literal(expr::Literal::String(s)) => format!("{:?}", s),
             Literal(lit) => format!("{}", lit),
             Unary(op, expr) => parenthesize!(op, expr),
-            Variable(name) => parenthesize!("var", name.lexeme.as_str()),
-            Assign(name, expr) => parenthesize!("=", name.lexeme.as_str(), expr),
+            Variable(name, _) => parenthesize!("var", name.lexeme.as_str()),
+            Assign(name, expr, _) => parenthesize!("=", name.lexeme.as_str(), expr),
             Logical(op, lhs, rhs) => parenthesize!(op, lhs, rhs),
-            Call(name, _args) => parenthesize!("call", name),
+            Call(callee, args) => {
+                let mut builder = format!("(call {}", callee);
+                for arg in args {
+                    builder.push_str(format!(" {}", arg).as_str());
+                }
+                builder.push(')');
+
+                builder
+            }
+            Get(object, name, false) => parenthesize!(".", object, name.lexeme.as_str()),
+            Get(object, name, true) => parenthesize!("?.", object, name.lexeme.as_str()),
+            Index(value, index) => parenthesize!("index", value, index),
+            Set(object, name, value) => {
+                parenthesize!("set", object, name.lexeme.as_str(), value)
+            }
+            This(..) => String::from("this"),
+            Super(method, _) => parenthesize!("super", method.lexeme.as_str()),
+            Match(value, arms) => {
+                let mut builder = format!("(match {}", value);
+                for arm in arms {
+                    let body = match &arm.body {
+                        ArmBody::Expr(expr) => expr.to_string(),
+                        ArmBody::Block(stmts) => {
+                            let stmts: Vec<String> = stmts.iter().map(Stmt::to_string).collect();
+                            format!("(block {})", stmts.join(" "))
+                        }
+                    };
+                    builder.push_str(format!(" ({} {})", arm.pattern, body).as_str());
+                }
+                builder.push(')');
+
+                builder
+            }
+        };
+
+        write!(f, "{}", result)
+    }
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let result = match &self.kind {
+            PatternKind::Wildcard => String::from("_"),
+            PatternKind::Literal(expr::Literal::String(s)) => format!("{:?}", s),
+            PatternKind::Literal(lit) => format!("{}", lit),
+            PatternKind::Binding(name) => parenthesize!("bind", name.lexeme.as_str()),
+            PatternKind::Value(value) => parenthesize!("value", value),
+            PatternKind::Array(elements) => {
+                let mut builder = String::from("(array");
+                for element in elements {
+                    builder.push_str(format!(" {}", element).as_str());
+                }
+                builder.push(')');
+
+                builder
+            }
+            PatternKind::Map(entries) => {
+                let mut builder = String::from("(map");
+                for (key, pattern) in entries {
+                    builder.push_str(format!(" ({:?} {})", key, pattern).as_str());
+                }
+                builder.push(')');
+
+                builder
+            }
+        };
+
+        write!(f, "{}", result)
+    }
+}
+
+/// A statement on one line, nested blocks and all, for the debugger to show where it paused.
+/// Unlike `sexpr::to_sexpr` it has no layout and no stability guarantee.
+impl Display for Stmt {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let result = match &self.kind {
+            StmtKind::Expression(expr) => parenthesize!("expr", expr),
+            StmtKind::Print(expr) => parenthesize!("print", expr),
+            StmtKind::Var(name, Some(initializer)) => {
+                parenthesize!("define", name.lexeme.as_str(), initializer)
+            }
+            StmtKind::Var(name, None) => parenthesize!("define", name.lexeme.as_str()),
+            StmtKind::Const(name, initializer) => {
+                parenthesize!("define-const", name.lexeme.as_str(), initializer)
+            }
+            StmtKind::Unpack(names, initializer) => {
+                let names: Vec<&str> = names.iter().map(|name| name.lexeme.as_str()).collect();
+                format!("(define-unpack ({}) {})", names.join(" "), initializer)
+            }
+            StmtKind::Block(stmts) => {
+                let mut builder = String::from("(block");
+                for stmt in stmts {
+                    builder.push_str(format!(" {}", stmt).as_str());
+                }
+                builder.push(')');
+
+                builder
+            }
+            StmtKind::If(arms, else_branch) => {
+                let mut builder = String::from("(if");
+                for (condition, branch) in arms {
+                    builder.push_str(format!(" {} {}", condition, branch).as_str());
+                }
+                if let Some(else_branch) = else_branch {
+                    builder.push_str(format!(" {}", else_branch).as_str());
+                }
+                builder.push(')');
+
+                builder
+            }
+            StmtKind::While(condition, body) => parenthesize!("while", condition, body),
+            StmtKind::For(initializer, condition, increment, body) => {
+                let clause = |clause: Option<String>| clause.unwrap_or_else(|| "_".to_string());
+                parenthesize!(
+                    "for",
+                    clause(initializer.as_ref().map(|stmt| stmt.to_string())),
+                    clause(condition.as_ref().map(|expr| expr.to_string())),
+                    clause(increment.as_ref().map(|expr| expr.to_string())),
+                    body
+                )
+            }
+            StmtKind::ForIn(name, iterable, body) => {
+                parenthesize!("for-in", name.lexeme.as_str(), iterable, body)
+            }
+            StmtKind::Function(name, params, body, _) => {
+                let params: Vec<&str> = params.iter().map(|p| p.lexeme.as_str()).collect();
+                let mut builder = format!("(fun {} ({})", name.lexeme, params.join(" "));
+                for stmt in body {
+                    builder.push_str(format!(" {}", stmt).as_str());
+                }
+                builder.push(')');
+
+                builder
+            }
+            StmtKind::Return(_, Some(value)) => parenthesize!("return", value),
+            StmtKind::Return(_, None) => String::from("(return)"),
+            StmtKind::Defer(body) => parenthesize!("defer", body),
+            StmtKind::Class(name, superclass, methods, _) => {
+                let mut builder = format!("(class {}", name.lexeme);
+                if let Some(superclass) = superclass {
+                    builder.push_str(format!(" (< {})", superclass).as_str());
+                }
+                for method in methods {
+                    builder.push_str(format!(" {}", method).as_str());
+                }
+                builder.push(')');
+
+                builder
+            }
+            StmtKind::Extend(class, methods) => {
+                let mut builder = format!("(extend {}", class);
+                for method in methods {
+                    builder.push_str(format!(" {}", method).as_str());
+                }
+                builder.push(')');
+
+                builder
+            }
+            StmtKind::Enum(name, variants) => {
+                let variants: Vec<&str> = variants.iter().map(|v| v.lexeme.as_str()).collect();
+                format!("(enum {} ({}))", name.lexeme, variants.join(" "))
+            }
         };
 
         write!(f, "{}", result)
     }
 }
 
+#[allow(dead_code)]
 pub fn pretty_print(expr: &Expr) -> String {
     format!("{}", expr)
 }
@@ -0,0 +1,80 @@
+use crate::lexer::{Span, Token};
+
+use super::{
+    expr::{Expr, Literal},
+    stmt::Stmt,
+};
+
+#[derive(Clone, Debug)]
+pub enum PatternKind {
+    /// `_`, which matches anything and binds nothing.
+    Wildcard,
+    /// Matches values equal to the literal, the way `==` compares them.
+    Literal(Literal),
+    /// Matches anything and binds it to the name.
+    Binding(Token),
+    /// A dotted name such as `Color.Red`, matching values equal to what it evaluates to.
+    Value(Expr),
+    /// Matches arrays of exactly this length whose elements match in order.
+    Array(Vec<Pattern>),
+    /// Matches maps that have every key listed, whatever else they hold.
+    Map(Vec<(String, Pattern)>),
+}
+
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    pub kind: PatternKind,
+    pub span: Span,
+}
+
+impl PartialEq for PatternKind {
+    fn eq(&self, other: &Self) -> bool {
+        use PatternKind::*;
+        match (self, other) {
+            (Wildcard, Wildcard) => true,
+            (Literal(a), Literal(b)) => a.is_same(b),
+            (Binding(a), Binding(b)) => a == b,
+            (Value(a), Value(b)) => a == b,
+            (Array(a), Array(b)) => a == b,
+            (Map(a), Map(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// Compared by structure, ignoring spans, the way `Expr` is.
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
+impl Pattern {
+    /// The names the pattern binds, in the order they appear.
+    pub fn bindings(&self) -> Vec<&Token> {
+        match &self.kind {
+            PatternKind::Wildcard | PatternKind::Literal(_) | PatternKind::Value(_) => vec![],
+            PatternKind::Binding(name) => vec![name],
+            PatternKind::Array(elements) => elements.iter().flat_map(Pattern::bindings).collect(),
+            PatternKind::Map(entries) => entries
+                .iter()
+                .flat_map(|(_, pattern)| pattern.bindings())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArmBody {
+    Expr(Expr),
+    /// A block of statements, for arms that need more than an expression. The arm's value
+    /// is nil.
+    Block(Vec<Stmt>),
+}
+
+/// One `pattern -> body` arm of a `match` expression.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub body: ArmBody,
+}
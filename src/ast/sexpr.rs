@@ -0,0 +1,772 @@
+//! The textual AST format: a specified s-expression rendering of a program that can be read
+//! back, for golden tests and tools. Unlike the `Display` output it only changes on purpose,
+//! along with `VERSION`.
+//!
+//! # Format, version 1
+//!
+//! A document is the form `(version 1)` followed by one form per top-level statement. Text
+//! from `;` to the end of a line is a comment. Spans are not part of the format.
+//!
+//! Atoms are symbols, numbers and strings:
+//!
+//! - Names and operators are bare symbols, such as `x`, `<=` or `?.`. `_` marks a missing
+//!   part.
+//! - Numbers are written the way `print` shows them: `3`, `0.5`, `-0`, `1e21`. Infinity is
+//!   `inf`.
+//! - Strings are double-quoted. `\\`, `\"`, `\n`, `\r` and `\t` are escaped, other control
+//!   characters are written as `\u{7f}`, and everything else appears as itself.
+//! - `true`, `false` and `nil` are the literals of those names.
+//!
+//! Statements:
+//!
+//! ```text
+//! (expr E)  (print E)  (define NAME)  (define NAME E)  (define-const NAME E)
+//! (block S...)  (if E S [E S]... [S])  (while E S)  (for S-or-_ E-or-_ E-or-_ S)
+//! (for-in NAME E S)  (fun NAME (PARAM...) S...)  (return)  (return E)  (enum NAME (VARIANT...))
+//! (class NAME E-or-_ MEMBER...)  (extend E (fun ...)...)
+//! ```
+//!
+//! A class's members are its fields, as `(define ...)`, and its methods, as `(fun ...)`, in
+//! the order they were declared.
+//!
+//! An `if` holds the condition and branch of itself and each `else if` chained to it, then
+//! the final `else` branch if there is one.
+//!
+//! Expressions:
+//!
+//! ```text
+//! LITERAL  (var NAME)  (= NAME E)  (group E)  (OP E E)  (- E)  (! E)  (and E E)  (or E E)
+//! (?? E E)  (call E E...)  (. E NAME)  (?. E NAME)  (index E E)  (set E NAME E)  (this)
+//! (super NAME)
+//! (array E...)  (map (E E)...)  (match E (arm P E-or-block)...)
+//! ```
+//!
+//! where `OP` is one of `+ - * / < <= > >= == !=` and a match arm body is either an
+//! expression or `(block S...)`.
+//!
+//! Patterns:
+//!
+//! ```text
+//! _  LITERAL  (bind NAME)  (value E)  (array P...)  (map ("key" P)...)
+//! ```
+//!
+//! A form that fits in 80 columns at its indentation is written on one line. Otherwise its
+//! leading atoms, and the list after them if it only holds atoms, such as a function's
+//! name and parameters, stay on the first line and every other element goes on a line of its own, indented two more spaces,
+//! with the closing parenthesis after the last one.
+//!
+//! # Resolved programs
+//!
+//! `to_resolved_sexpr` shows what the resolver made of a program, for debugging it: the name
+//! of each local variable read or assigned is followed by `@N`, where `N` is how many scopes
+//! out its binding is, and the method name of a `super` by how many scopes out `super` is.
+//! Globals keep their bare names. Each top-level statement is preceded by a `; line N`
+//! comment giving the line it starts on.
+
+use std::{fmt::Write, rc::Rc};
+
+use crate::{
+    ast::{
+        expr::{BinOp, Expr, ExprKind, Literal, LogOp, UnOp},
+        pattern::{ArmBody, MatchArm, Pattern, PatternKind},
+        stmt::{Stmt, StmtKind},
+        transform::{walk_expr, Fold},
+    },
+    interpreter::number::format_number,
+    lexer::{Span, Token, TokenKind},
+};
+
+/// The version of the format `to_sexpr` writes and `from_sexpr` reads.
+pub const VERSION: u32 = 1;
+
+const WIDTH: usize = 80;
+
+#[derive(Clone, Debug, PartialEq)]
+enum SExpr {
+    Symbol(String),
+    String(String),
+    List(Vec<SExpr>),
+}
+
+impl SExpr {
+    fn list(head: &str, rest: impl IntoIterator<Item = SExpr>) -> SExpr {
+        let mut items = vec![symbol(head)];
+        items.extend(rest);
+        SExpr::List(items)
+    }
+
+    fn flat(&self) -> String {
+        match self {
+            SExpr::Symbol(symbol) => symbol.clone(),
+            SExpr::String(s) => quote(s),
+            SExpr::List(items) => {
+                let items: Vec<String> = items.iter().map(SExpr::flat).collect();
+                format!("({})", items.join(" "))
+            }
+        }
+    }
+
+    fn write(&self, out: &mut String, indent: usize) {
+        let flat = self.flat();
+        let items = match self {
+            SExpr::List(items) if indent + flat.len() > WIDTH => items,
+            _ => {
+                out.push_str(&flat);
+                return;
+            }
+        };
+
+        let atoms = |item: &SExpr| !matches!(item, SExpr::List(_));
+        let mut leading = items.iter().take_while(|item| atoms(item)).count();
+        if let Some(SExpr::List(list)) = items.get(leading) {
+            if list.iter().all(atoms) {
+                leading += 1;
+            }
+        }
+        let head: Vec<String> = items[..leading].iter().map(SExpr::flat).collect();
+        out.push('(');
+        out.push_str(&head.join(" "));
+        for item in &items[leading..] {
+            out.push('\n');
+            out.push_str(&" ".repeat(indent + 2));
+            item.write(out, indent + 2);
+        }
+        out.push(')');
+    }
+}
+
+fn symbol(s: &str) -> SExpr {
+    SExpr::Symbol(s.to_string())
+}
+
+fn quote(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => write!(quoted, "\\u{{{:x}}}", c as u32).unwrap(),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn number(n: f64) -> String {
+    if n.is_infinite() {
+        if n > 0.0 { "inf" } else { "-inf" }.to_string()
+    } else {
+        format_number(n)
+    }
+}
+
+/// Writes resolved `stmts` in the textual AST format, annotated with where their variables
+/// were resolved to and the line each top-level statement starts on.
+pub fn to_resolved_sexpr(stmts: &[Stmt]) -> String {
+    let mut out = format!("(version {})\n", VERSION);
+    for stmt in stmts {
+        let _ = writeln!(out, "; line {}", stmt.span.line);
+        stmt_sexpr(&ShowResolution.fold_stmt(stmt.clone())).write(&mut out, 0);
+        out.push('\n');
+    }
+    out
+}
+
+/// Appends the scope distance of each resolved name to the name itself.
+struct ShowResolution;
+
+impl Fold for ShowResolution {
+    fn fold_expr(&mut self, mut expr: Expr) -> Expr {
+        match &mut expr.kind {
+            ExprKind::Variable(name, Some(depth))
+            | ExprKind::Assign(name, _, Some(depth))
+            | ExprKind::Super(name, Some(depth)) => {
+                name.lexeme = format!("{}@{}", name.lexeme, depth);
+            }
+            _ => (),
+        }
+        walk_expr(self, expr)
+    }
+}
+
+/// Writes `stmts` in the textual AST format.
+pub fn to_sexpr(stmts: &[Stmt]) -> String {
+    let mut out = format!("(version {})\n", VERSION);
+    for stmt in stmts {
+        stmt_sexpr(stmt).write(&mut out, 0);
+        out.push('\n');
+    }
+    out
+}
+
+fn name(token: &Token) -> SExpr {
+    symbol(&token.lexeme)
+}
+
+fn or_missing(sexpr: Option<SExpr>) -> SExpr {
+    sexpr.unwrap_or_else(|| symbol("_"))
+}
+
+fn stmts_sexpr(stmts: &[Stmt]) -> impl Iterator<Item = SExpr> + '_ {
+    stmts.iter().map(stmt_sexpr)
+}
+
+fn stmt_sexpr(stmt: &Stmt) -> SExpr {
+    match &stmt.kind {
+        StmtKind::Expression(expr) => SExpr::list("expr", [expr_sexpr(expr)]),
+        StmtKind::Print(expr) => SExpr::list("print", [expr_sexpr(expr)]),
+        StmtKind::Var(token, initializer) => SExpr::list(
+            "define",
+            std::iter::once(name(token)).chain(initializer.as_ref().map(expr_sexpr)),
+        ),
+        StmtKind::Const(token, initializer) => {
+            SExpr::list("define-const", [name(token), expr_sexpr(initializer)])
+        }
+        StmtKind::Unpack(names, initializer) => SExpr::list(
+            "define-unpack",
+            [
+                SExpr::List(names.iter().map(name).collect()),
+                expr_sexpr(initializer),
+            ],
+        ),
+        StmtKind::Block(stmts) => SExpr::list("block", stmts_sexpr(stmts)),
+        StmtKind::If(arms, else_branch) => SExpr::list(
+            "if",
+            arms.iter()
+                .flat_map(|(condition, branch)| [expr_sexpr(condition), stmt_sexpr(branch)])
+                .chain(else_branch.as_deref().map(stmt_sexpr)),
+        ),
+        StmtKind::While(condition, body) => {
+            SExpr::list("while", [expr_sexpr(condition), stmt_sexpr(body)])
+        }
+        StmtKind::For(initializer, condition, increment, body) => SExpr::list(
+            "for",
+            [
+                or_missing(initializer.as_deref().map(stmt_sexpr)),
+                or_missing(condition.as_ref().map(expr_sexpr)),
+                or_missing(increment.as_ref().map(expr_sexpr)),
+                stmt_sexpr(body),
+            ],
+        ),
+        StmtKind::ForIn(token, iterable, body) => SExpr::list(
+            "for-in",
+            [name(token), expr_sexpr(iterable), stmt_sexpr(body)],
+        ),
+        StmtKind::Function(token, params, body, _) => SExpr::list(
+            "fun",
+            [name(token), SExpr::List(params.iter().map(name).collect())]
+                .into_iter()
+                .chain(stmts_sexpr(body)),
+        ),
+        StmtKind::Return(_, value) => SExpr::list("return", value.as_ref().map(expr_sexpr)),
+        StmtKind::Defer(body) => SExpr::list("defer", [stmt_sexpr(body)]),
+        StmtKind::Enum(token, variants) => SExpr::list(
+            "enum",
+            [
+                name(token),
+                SExpr::List(variants.iter().map(name).collect()),
+            ],
+        ),
+        StmtKind::Class(token, superclass, methods, _) => SExpr::list(
+            "class",
+            [name(token), or_missing(superclass.as_ref().map(expr_sexpr))]
+                .into_iter()
+                .chain(stmts_sexpr(methods)),
+        ),
+        StmtKind::Extend(class, methods) => SExpr::list(
+            "extend",
+            [expr_sexpr(class)].into_iter().chain(stmts_sexpr(methods)),
+        ),
+    }
+}
+
+fn literal_sexpr(literal: &Literal) -> SExpr {
+    match literal {
+        Literal::String(s) => SExpr::String(s.to_string()),
+        Literal::Number(n) => SExpr::Symbol(number(*n)),
+        Literal::Bool(b) => SExpr::Symbol(b.to_string()),
+        Literal::Nil => symbol("nil"),
+        // The parser only makes the literals above; anything else came from elsewhere.
+        other => SExpr::Symbol(format!("#<{}>", other.type_name())),
+    }
+}
+
+fn expr_sexpr(expr: &Expr) -> SExpr {
+    match &expr.kind {
+        ExprKind::Array(elements) => SExpr::list("array", elements.iter().map(expr_sexpr)),
+        ExprKind::Map(entries) => SExpr::list(
+            "map",
+            entries
+                .iter()
+                .map(|(key, value)| SExpr::List(vec![expr_sexpr(key), expr_sexpr(value)])),
+        ),
+        ExprKind::Binary(op, lhs, rhs) => {
+            SExpr::list(&op.to_string(), [expr_sexpr(lhs), expr_sexpr(rhs)])
+        }
+        ExprKind::Call(callee, args) => SExpr::list(
+            "call",
+            std::iter::once(expr_sexpr(callee)).chain(args.iter().map(expr_sexpr)),
+        ),
+        ExprKind::Get(object, token, optional) => SExpr::list(
+            if *optional { "?." } else { "." },
+            [expr_sexpr(object), name(token)],
+        ),
+        ExprKind::Index(value, index) => {
+            SExpr::list("index", [expr_sexpr(value), expr_sexpr(index)])
+        }
+        ExprKind::Grouping(inner) => SExpr::list("group", [expr_sexpr(inner)]),
+        ExprKind::Literal(literal) => literal_sexpr(literal),
+        ExprKind::Logical(op, lhs, rhs) => {
+            SExpr::list(&op.to_string(), [expr_sexpr(lhs), expr_sexpr(rhs)])
+        }
+        ExprKind::Unary(op, operand) => SExpr::list(&op.to_string(), [expr_sexpr(operand)]),
+        ExprKind::Variable(token, _) => SExpr::list("var", [name(token)]),
+        ExprKind::Assign(token, value, _) => SExpr::list("=", [name(token), expr_sexpr(value)]),
+        ExprKind::Set(object, token, value) => {
+            SExpr::list("set", [expr_sexpr(object), name(token), expr_sexpr(value)])
+        }
+        ExprKind::This(..) => SExpr::list("this", []),
+        ExprKind::Super(method, _) => SExpr::list("super", [name(method)]),
+        ExprKind::Match(value, arms) => SExpr::list(
+            "match",
+            std::iter::once(expr_sexpr(value)).chain(arms.iter().map(|arm| {
+                let body = match &arm.body {
+                    ArmBody::Expr(expr) => expr_sexpr(expr),
+                    ArmBody::Block(stmts) => SExpr::list("block", stmts_sexpr(stmts)),
+                };
+                SExpr::list("arm", [pattern_sexpr(&arm.pattern), body])
+            })),
+        ),
+        ExprKind::Tuple(values) => SExpr::list("tuple", values.iter().map(expr_sexpr)),
+    }
+}
+
+fn pattern_sexpr(pattern: &Pattern) -> SExpr {
+    match &pattern.kind {
+        PatternKind::Wildcard => symbol("_"),
+        PatternKind::Literal(literal) => literal_sexpr(literal),
+        PatternKind::Binding(token) => SExpr::list("bind", [name(token)]),
+        PatternKind::Value(value) => SExpr::list("value", [expr_sexpr(value)]),
+        PatternKind::Array(elements) => SExpr::list("array", elements.iter().map(pattern_sexpr)),
+        PatternKind::Map(entries) => SExpr::list(
+            "map",
+            entries.iter().map(|(key, pattern)| {
+                SExpr::List(vec![SExpr::String(key.clone()), pattern_sexpr(pattern)])
+            }),
+        ),
+    }
+}
+
+/// Reads a program written in the textual AST format. Spans aren't part of the format, so
+/// everything read is located in the `<generated>` pseudo-file, and variables aren't
+/// resolved.
+pub fn from_sexpr(text: &str) -> Result<Vec<Stmt>, String> {
+    let forms = Reader::new(text).forms()?;
+    let mut forms = forms.into_iter();
+
+    match forms.next() {
+        Some(SExpr::List(items)) if items.len() == 2 && items[0] == symbol("version") => {
+            match &items[1] {
+                SExpr::Symbol(version) if *version == VERSION.to_string() => (),
+                other => {
+                    return Err(format!(
+                        "Unsupported AST format version {}; expected {}.",
+                        other.flat(),
+                        VERSION
+                    ))
+                }
+            }
+        }
+        _ => return Err("Expect the document to start with (version N).".into()),
+    }
+
+    forms.map(|form| read_stmt(&form)).collect()
+}
+
+/// Splits text into forms.
+struct Reader<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl<'a> Reader<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            chars: text.chars().peekable(),
+        }
+    }
+
+    fn forms(&mut self) -> Result<Vec<SExpr>, String> {
+        let mut forms = vec![];
+        while self.skip_space() {
+            forms.push(self.form()?);
+        }
+        Ok(forms)
+    }
+
+    /// Skips whitespace and comments, returning whether anything is left.
+    fn skip_space(&mut self) -> bool {
+        while let Some(&c) = self.chars.peek() {
+            if c == ';' {
+                while self.chars.next_if(|&c| c != '\n').is_some() {}
+            } else if c.is_whitespace() {
+                self.chars.next();
+            } else {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn form(&mut self) -> Result<SExpr, String> {
+        match self.chars.next() {
+            Some('(') => {
+                let mut items = vec![];
+                loop {
+                    if !self.skip_space() {
+                        return Err("Unclosed '('.".into());
+                    }
+                    if self.chars.next_if_eq(&')').is_some() {
+                        return Ok(SExpr::List(items));
+                    }
+                    items.push(self.form()?);
+                }
+            }
+            Some(')') => Err("Unexpected ')'.".into()),
+            Some('"') => self.string(),
+            Some(c) => {
+                let mut symbol = c.to_string();
+                while let Some(c) = self
+                    .chars
+                    .next_if(|&c| !c.is_whitespace() && !"()\";".contains(c))
+                {
+                    symbol.push(c);
+                }
+                Ok(SExpr::Symbol(symbol))
+            }
+            None => Err("Unexpected end of input.".into()),
+        }
+    }
+
+    fn string(&mut self) -> Result<SExpr, String> {
+        let mut s = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(SExpr::String(s)),
+                Some('\\') => match self.chars.next() {
+                    Some('\\') => s.push('\\'),
+                    Some('"') => s.push('"'),
+                    Some('n') => s.push('\n'),
+                    Some('r') => s.push('\r'),
+                    Some('t') => s.push('\t'),
+                    Some('u') if self.chars.next_if_eq(&'{').is_some() => {
+                        let mut hex = String::new();
+                        while let Some(c) = self.chars.next_if(|&c| c != '}') {
+                            hex.push(c);
+                        }
+                        self.chars.next();
+                        let c = u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| format!("Invalid escape '\\u{{{}}}'.", hex))?;
+                        s.push(c);
+                    }
+                    other => {
+                        return Err(format!(
+                            "Invalid escape '\\{}'.",
+                            other.map(String::from).unwrap_or_default()
+                        ))
+                    }
+                },
+                Some(c) => s.push(c),
+                None => return Err("Unterminated string.".into()),
+            }
+        }
+    }
+}
+
+fn token(kind: TokenKind, lexeme: &str) -> Token {
+    Token::generated(kind, lexeme)
+}
+
+fn read_name(form: &SExpr) -> Result<Token, String> {
+    match form {
+        SExpr::Symbol(name) if name != "_" => Ok(token(TokenKind::Identifier(name.clone()), name)),
+        other => Err(format!("Expect a name, found {}.", other.flat())),
+    }
+}
+
+fn read_names(form: &SExpr) -> Result<Vec<Token>, String> {
+    match form {
+        SExpr::List(items) => items.iter().map(read_name).collect(),
+        other => Err(format!("Expect a list of names, found {}.", other.flat())),
+    }
+}
+
+/// Splits a list form into its head symbol and the rest.
+fn split(form: &SExpr) -> Option<(&str, &[SExpr])> {
+    match form {
+        SExpr::List(items) => match items.split_first() {
+            Some((SExpr::Symbol(head), rest)) => Some((head, rest)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_missing(form: &SExpr) -> bool {
+    *form == symbol("_")
+}
+
+fn stmt(kind: StmtKind) -> Stmt {
+    Stmt::generated(kind)
+}
+
+fn expr(kind: ExprKind) -> Expr {
+    Expr::generated(kind)
+}
+
+fn boxed(form: &SExpr) -> Result<Box<Expr>, String> {
+    read_expr(form).map(Box::new)
+}
+
+fn read_stmts(forms: &[SExpr]) -> Result<Vec<Stmt>, String> {
+    forms.iter().map(read_stmt).collect()
+}
+
+fn read_stmt(form: &SExpr) -> Result<Stmt, String> {
+    let unknown = || format!("Expect a statement, found {}.", form.flat());
+    let (head, rest) = split(form).ok_or_else(unknown)?;
+
+    let kind = match (head, rest) {
+        ("expr", [e]) => StmtKind::Expression(read_expr(e)?),
+        ("print", [e]) => StmtKind::Print(read_expr(e)?),
+        ("define", [name]) => StmtKind::Var(read_name(name)?, None),
+        ("define", [name, e]) => StmtKind::Var(read_name(name)?, Some(read_expr(e)?)),
+        ("define-const", [name, e]) => StmtKind::Const(read_name(name)?, read_expr(e)?),
+        ("define-unpack", [names, e]) => StmtKind::Unpack(read_names(names)?, read_expr(e)?),
+        ("block", stmts) => StmtKind::Block(read_stmts(stmts)?),
+        ("if", [_, _, ..]) => {
+            let arms = rest.chunks_exact(2);
+            let else_branch = match arms.remainder() {
+                [else_branch] => Some(Box::new(read_stmt(else_branch)?)),
+                _ => None,
+            };
+            let arms = arms
+                .map(|arm| Ok((read_expr(&arm[0])?, read_stmt(&arm[1])?)))
+                .collect::<Result<_, String>>()?;
+            StmtKind::If(arms, else_branch)
+        }
+        ("while", [condition, body]) => {
+            StmtKind::While(read_expr(condition)?, Box::new(read_stmt(body)?))
+        }
+        ("for", [initializer, condition, increment, body]) => {
+            let optional_expr = |form: &SExpr| match is_missing(form) {
+                true => Ok(None),
+                false => read_expr(form).map(Some),
+            };
+            let initializer = match is_missing(initializer) {
+                true => None,
+                false => Some(Box::new(read_stmt(initializer)?)),
+            };
+            StmtKind::For(
+                initializer,
+                optional_expr(condition)?,
+                optional_expr(increment)?,
+                Box::new(read_stmt(body)?),
+            )
+        }
+        ("for-in", [name, iterable, body]) => StmtKind::ForIn(
+            read_name(name)?,
+            read_expr(iterable)?,
+            Box::new(read_stmt(body)?),
+        ),
+        ("fun", [name, params, body @ ..]) => StmtKind::Function(
+            read_name(name)?,
+            read_names(params)?,
+            read_stmts(body)?,
+            None,
+        ),
+        ("return", []) => StmtKind::Return(token(TokenKind::Return, "return"), None),
+        ("return", [e]) => {
+            StmtKind::Return(token(TokenKind::Return, "return"), Some(read_expr(e)?))
+        }
+        ("defer", [body]) => StmtKind::Defer(Box::new(read_stmt(body)?)),
+        ("enum", [name, variants]) => StmtKind::Enum(read_name(name)?, read_names(variants)?),
+        ("class", [name, superclass, methods @ ..]) => {
+            let superclass = match is_missing(superclass) {
+                true => None,
+                false => Some(read_expr(superclass)?),
+            };
+            StmtKind::Class(read_name(name)?, superclass, read_stmts(methods)?, None)
+        }
+        ("extend", [class, methods @ ..]) => {
+            StmtKind::Extend(read_expr(class)?, read_stmts(methods)?)
+        }
+        _ => return Err(unknown()),
+    };
+
+    Ok(stmt(kind))
+}
+
+fn read_literal(form: &SExpr) -> Option<Literal> {
+    match form {
+        SExpr::String(s) => Some(Literal::String(Rc::from(s.as_str()))),
+        SExpr::Symbol(s) => match s.as_str() {
+            "true" => Some(Literal::Bool(true)),
+            "false" => Some(Literal::Bool(false)),
+            "nil" => Some(Literal::Nil),
+            s if s.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == 'i') => {
+                s.parse().ok().map(Literal::Number)
+            }
+            _ => None,
+        },
+        SExpr::List(_) => None,
+    }
+}
+
+fn binary_op(op: &str) -> Option<BinOp> {
+    Some(match op {
+        "+" => BinOp::Plus,
+        "-" => BinOp::Minus,
+        "*" => BinOp::Multiply,
+        "/" => BinOp::Divide,
+        "<" => BinOp::Lt,
+        "<=" => BinOp::Le,
+        ">" => BinOp::Gt,
+        ">=" => BinOp::Ge,
+        "==" => BinOp::EqEq,
+        "!=" => BinOp::Ne,
+        _ => return None,
+    })
+}
+
+fn read_expr(form: &SExpr) -> Result<Expr, String> {
+    if let Some(literal) = read_literal(form) {
+        return Ok(expr(ExprKind::Literal(literal)));
+    }
+
+    let unknown = || format!("Expect an expression, found {}.", form.flat());
+    let (head, rest) = split(form).ok_or_else(unknown)?;
+
+    let kind = match (head, rest) {
+        ("array", elements) => {
+            ExprKind::Array(elements.iter().map(read_expr).collect::<Result<_, _>>()?)
+        }
+        ("tuple", values) => {
+            ExprKind::Tuple(values.iter().map(read_expr).collect::<Result<_, _>>()?)
+        }
+        ("map", entries) => ExprKind::Map(
+            entries
+                .iter()
+                .map(|entry| match entry {
+                    SExpr::List(pair) if pair.len() == 2 => {
+                        Ok((read_expr(&pair[0])?, read_expr(&pair[1])?))
+                    }
+                    other => Err(format!(
+                        "Expect a (key value) entry, found {}.",
+                        other.flat()
+                    )),
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        ("-", [operand]) => ExprKind::Unary(UnOp::BinNeg, boxed(operand)?),
+        ("!", [operand]) => ExprKind::Unary(UnOp::LogNeg, boxed(operand)?),
+        ("and", [lhs, rhs]) => ExprKind::Logical(LogOp::And, boxed(lhs)?, boxed(rhs)?),
+        ("or", [lhs, rhs]) => ExprKind::Logical(LogOp::Or, boxed(lhs)?, boxed(rhs)?),
+        ("??", [lhs, rhs]) => ExprKind::Logical(LogOp::Coalesce, boxed(lhs)?, boxed(rhs)?),
+        (op, [lhs, rhs]) if binary_op(op).is_some() => {
+            ExprKind::Binary(binary_op(op).unwrap(), boxed(lhs)?, boxed(rhs)?)
+        }
+        ("call", [callee, args @ ..]) => ExprKind::Call(
+            boxed(callee)?,
+            args.iter().map(read_expr).collect::<Result<_, _>>()?,
+        ),
+        (".", [object, name]) => ExprKind::Get(boxed(object)?, read_name(name)?, false),
+        ("?.", [object, name]) => ExprKind::Get(boxed(object)?, read_name(name)?, true),
+        ("index", [value, index]) => ExprKind::Index(boxed(value)?, boxed(index)?),
+        ("group", [inner]) => ExprKind::Grouping(boxed(inner)?),
+        ("var", [name]) => ExprKind::Variable(read_name(name)?, None),
+        // The parser never makes `=` a binary operator, so a name means assignment.
+        ("=", [name @ SExpr::Symbol(_), value]) => {
+            ExprKind::Assign(read_name(name)?, boxed(value)?, None)
+        }
+        ("=", [lhs, rhs]) => ExprKind::Binary(BinOp::Eq, boxed(lhs)?, boxed(rhs)?),
+        ("set", [object, name, value]) => {
+            ExprKind::Set(boxed(object)?, read_name(name)?, boxed(value)?)
+        }
+        ("this", []) => ExprKind::This(token(TokenKind::This, "this"), None),
+        ("super", [method]) => ExprKind::Super(read_name(method)?, None),
+        ("match", [value, arms @ ..]) => ExprKind::Match(
+            boxed(value)?,
+            arms.iter().map(read_arm).collect::<Result<_, _>>()?,
+        ),
+        _ => return Err(unknown()),
+    };
+
+    Ok(expr(kind))
+}
+
+fn read_arm(form: &SExpr) -> Result<MatchArm, String> {
+    match split(form) {
+        Some(("arm", [pattern, body])) => {
+            let body = match split(body) {
+                Some(("block", stmts)) => ArmBody::Block(read_stmts(stmts)?),
+                _ => ArmBody::Expr(read_expr(body)?),
+            };
+            Ok(MatchArm {
+                pattern: read_pattern(pattern)?,
+                body,
+            })
+        }
+        _ => Err(format!("Expect a match arm, found {}.", form.flat())),
+    }
+}
+
+fn read_pattern(form: &SExpr) -> Result<Pattern, String> {
+    let kind = if is_missing(form) {
+        PatternKind::Wildcard
+    } else if let Some(literal) = read_literal(form) {
+        PatternKind::Literal(literal)
+    } else {
+        match split(form) {
+            Some(("bind", [name])) => PatternKind::Binding(read_name(name)?),
+            Some(("value", [value])) => PatternKind::Value(read_expr(value)?),
+            Some(("array", elements)) => PatternKind::Array(
+                elements
+                    .iter()
+                    .map(read_pattern)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Some(("map", entries)) => PatternKind::Map(
+                entries
+                    .iter()
+                    .map(|entry| match entry {
+                        SExpr::List(pair) => match pair.as_slice() {
+                            [SExpr::String(key), pattern] => {
+                                Ok((key.clone(), read_pattern(pattern)?))
+                            }
+                            _ => Err(format!(
+                                "Expect a (\"key\" pattern) entry, found {}.",
+                                entry.flat()
+                            )),
+                        },
+                        other => Err(format!(
+                            "Expect a (\"key\" pattern) entry, found {}.",
+                            other.flat()
+                        )),
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            _ => return Err(format!("Expect a pattern, found {}.", form.flat())),
+        }
+    };
+
+    Ok(Pattern {
+        kind,
+        span: Span::generated(),
+    })
+}
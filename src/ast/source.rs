@@ -0,0 +1,647 @@
+//! Writing syntax trees back out as Lox source, for tools that generate or rewrite code.
+//!
+//! The source parses back into the same tree, spans aside. Parentheses are written for every
+//! `Grouping` and wherever precedence needs them, so a `Binary` multiplying a sum comes out
+//! as `(1 + 2) * 3`; those added parentheses read back as a `Grouping` of their own.
+//! Comments other than doc comments aren't part of the tree, so `to_source` loses them;
+//! `to_source_with_comments` writes them back from a `CommentMap`.
+
+use std::fmt::Write;
+
+use crate::comments::{Comment, CommentMap};
+
+use super::{
+    expr::{BinOp, Expr, ExprKind, Literal, LogOp},
+    pattern::{ArmBody, Pattern, PatternKind},
+    stmt::{Stmt, StmtKind},
+};
+
+const INDENT: &str = "  ";
+
+/// How tightly an expression binds, from assignment, the loosest, up to primary expressions.
+/// An operand that binds more loosely than its position needs is parenthesized.
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+enum Precedence {
+    Assignment,
+    Or,
+    And,
+    Equality,
+    Comparison,
+    Term,
+    Factor,
+    Unary,
+    Call,
+    Primary,
+}
+
+impl Precedence {
+    fn of(expr: &Expr) -> Precedence {
+        match &expr.kind {
+            // A tuple is only ever a whole return value, so it never needs parentheses.
+            ExprKind::Assign(..) | ExprKind::Set(..) | ExprKind::Tuple(_) => Precedence::Assignment,
+            ExprKind::Logical(LogOp::Or | LogOp::Coalesce, ..) => Precedence::Or,
+            ExprKind::Logical(LogOp::And, ..) => Precedence::And,
+            ExprKind::Binary(op, ..) => Precedence::of_binary(*op),
+            ExprKind::Unary(..) => Precedence::Unary,
+            ExprKind::Call(..) | ExprKind::Get(..) | ExprKind::Index(..) => Precedence::Call,
+            ExprKind::Array(_)
+            | ExprKind::Map(_)
+            | ExprKind::Grouping(_)
+            | ExprKind::Literal(_)
+            | ExprKind::Variable(..)
+            | ExprKind::This(..)
+            | ExprKind::Super(..)
+            | ExprKind::Match(..) => Precedence::Primary,
+        }
+    }
+
+    fn of_binary(op: BinOp) -> Precedence {
+        match op {
+            BinOp::EqEq | BinOp::Ne => Precedence::Equality,
+            BinOp::Gt | BinOp::Ge | BinOp::Lt | BinOp::Le => Precedence::Comparison,
+            BinOp::Plus | BinOp::Minus => Precedence::Term,
+            BinOp::Multiply | BinOp::Divide => Precedence::Factor,
+            BinOp::Eq => Precedence::Assignment,
+        }
+    }
+
+    /// The next tighter level, which the right operand of a left-associative operator needs.
+    fn tighter(self) -> Precedence {
+        match self {
+            Precedence::Assignment => Precedence::Or,
+            Precedence::Or => Precedence::And,
+            Precedence::And => Precedence::Equality,
+            Precedence::Equality => Precedence::Comparison,
+            Precedence::Comparison => Precedence::Term,
+            Precedence::Term => Precedence::Factor,
+            Precedence::Factor => Precedence::Unary,
+            Precedence::Unary => Precedence::Call,
+            Precedence::Call | Precedence::Primary => Precedence::Primary,
+        }
+    }
+}
+
+/// Writes `stmts` as Lox source, a statement per line and nested ones indented by two
+/// spaces.
+pub fn to_source(stmts: &[Stmt]) -> String {
+    let mut printer = Printer::default();
+    for stmt in stmts {
+        printer.stmt(stmt);
+    }
+    printer.out
+}
+
+/// Writes `stmts` like `to_source`, along with the comments `comments` attaches to them. A
+/// leading comment goes on the lines above its statement and a trailing one at the end of
+/// the line its statement ends on. Orphans are written where they were relative to the
+/// statements around them, before the closing brace of the block they were in or at the end.
+///
+/// ```
+/// let code = "// Counts.\nvar n = 1; // one\nfun f() {\n  print n;\n  // Nothing more.\n}\n";
+/// let stmts = rlox::parse("count.lox", code).unwrap();
+/// let comments = rlox::attach_comments(code, &stmts);
+/// assert_eq!(rlox::to_source_with_comments(&stmts, &comments), code);
+/// ```
+pub fn to_source_with_comments(stmts: &[Stmt], comments: &CommentMap) -> String {
+    let mut printer = Printer {
+        comments: Some(comments),
+        ..Printer::default()
+    };
+    for stmt in stmts {
+        printer.stmt(stmt);
+    }
+    printer.orphans_before(usize::MAX);
+    printer.out
+}
+
+#[derive(Default)]
+struct Printer<'a> {
+    out: String,
+    indent: usize,
+    comments: Option<&'a CommentMap>,
+    /// How many of the orphan comments have been written.
+    orphans: usize,
+    /// A trailing comment to write before the next line break, since a statement can end
+    /// with something still to come on its line, such as the `else` after a block.
+    trailing: Option<&'a Comment>,
+}
+
+impl<'a> Printer<'a> {
+    fn line_start(&mut self) {
+        for _ in 0..self.indent {
+            self.out.push_str(INDENT);
+        }
+    }
+
+    /// Ends the line, after the trailing comment waiting for it if there is one.
+    fn line_break(&mut self) {
+        if let Some(comment) = self.trailing.take() {
+            let _ = write!(self.out, " {}", comment.source());
+        }
+        self.out.push('\n');
+    }
+
+    fn newline(&mut self) {
+        self.line_break();
+        self.line_start();
+    }
+
+    /// Whether there are orphan comments left from before byte offset `end`.
+    fn has_orphans_before(&self, end: usize) -> bool {
+        self.comments
+            .and_then(|comments| comments.orphans().get(self.orphans))
+            .is_some_and(|comment| comment.span.start < end)
+    }
+
+    /// Writes the orphan comments left from before byte offset `end`, a line each.
+    fn orphans_before(&mut self, end: usize) {
+        let Some(comments) = self.comments else {
+            return;
+        };
+        while self.has_orphans_before(end) {
+            self.line_start();
+            self.out
+                .push_str(&comments.orphans()[self.orphans].source());
+            self.line_break();
+            self.orphans += 1;
+        }
+    }
+
+    /// Writes the comments on the lines above `stmt`, except for its doc comment, which is
+    /// written from the tree. Each ends with a line break followed by the indentation.
+    fn leading(&mut self, stmt: &Stmt) {
+        let Some(comments) = self.comments else {
+            return;
+        };
+        let leading = comments.leading(stmt);
+        let leading = match &stmt.kind {
+            StmtKind::Function(.., Some(_)) | StmtKind::Class(.., Some(_)) => {
+                &leading[..leading.len() - comments.doc_comments(stmt).len()]
+            }
+            _ => leading,
+        };
+        for comment in leading {
+            self.out.push_str(&comment.source());
+            self.newline();
+        }
+    }
+
+    /// Holds on to the comment trailing `stmt` until the line ends. Called both before and
+    /// after writing `stmt`, with `written` telling which: a comment on the line the
+    /// statement starts on goes at the end of its first line, as after the `{` of a function,
+    /// and one on a later line at the end of its last.
+    fn trailing(&mut self, stmt: &Stmt, written: bool) {
+        let Some(comment) = self.comments.and_then(|comments| comments.trailing(stmt)) else {
+            return;
+        };
+        if (comment.span.line == stmt.span.line) != written {
+            self.trailing = Some(comment);
+        }
+    }
+
+    fn doc(&mut self, doc: &Option<String>) {
+        let Some(doc) = doc else {
+            return;
+        };
+        for line in doc.split('\n') {
+            match line {
+                "" => self.out.push_str("///"),
+                line => {
+                    let _ = write!(self.out, "/// {}", line);
+                }
+            }
+            // Not `newline`: a comment trailing the declaration belongs after its header.
+            self.out.push('\n');
+            self.line_start();
+        }
+    }
+
+    /// Writes `stmt` on lines of its own, ending with a line break.
+    fn stmt(&mut self, stmt: &Stmt) {
+        self.orphans_before(stmt.span.start);
+        self.line_start();
+        self.leading(stmt);
+        self.trailing(stmt, false);
+        self.stmt_inline(stmt);
+        self.trailing(stmt, true);
+        self.line_break();
+    }
+
+    /// Writes `stmt` from where the output is, without a line break after it.
+    fn stmt_inline(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Expression(expr) => {
+                self.statement_expr(expr);
+                self.out.push(';');
+            }
+            StmtKind::Print(expr) => {
+                self.out.push_str("print ");
+                self.expr(expr, Precedence::Assignment);
+                self.out.push(';');
+            }
+            StmtKind::Var(name, initializer) => {
+                let _ = write!(self.out, "var {}", name.lexeme);
+                if let Some(initializer) = initializer {
+                    self.out.push_str(" = ");
+                    self.expr(initializer, Precedence::Assignment);
+                }
+                self.out.push(';');
+            }
+            StmtKind::Unpack(names, initializer) => {
+                let names: Vec<&str> = names.iter().map(|name| name.lexeme.as_str()).collect();
+                let _ = write!(self.out, "var {} = ", names.join(", "));
+                self.expr(initializer, Precedence::Assignment);
+                self.out.push(';');
+            }
+            StmtKind::Const(name, initializer) => {
+                let _ = write!(self.out, "const {} = ", name.lexeme);
+                self.expr(initializer, Precedence::Assignment);
+                self.out.push(';');
+            }
+            StmtKind::Block(stmts) => self.block(stmts, Some(stmt.span.end)),
+            StmtKind::If(arms, else_branch) => {
+                for (i, (condition, branch)) in arms.iter().enumerate() {
+                    if i > 0 {
+                        self.after_branch(&arms[i - 1].1);
+                        self.out.push_str("else ");
+                    }
+                    self.out.push_str("if (");
+                    self.expr(condition, Precedence::Assignment);
+                    self.out.push(')');
+                    self.branch(branch);
+                }
+                if let Some(else_branch) = else_branch {
+                    if let Some((_, last)) = arms.last() {
+                        self.after_branch(last);
+                    }
+                    self.out.push_str("else");
+                    self.branch(else_branch);
+                }
+            }
+            StmtKind::While(condition, body) => {
+                self.out.push_str("while (");
+                self.expr(condition, Precedence::Assignment);
+                self.out.push(')');
+                self.branch(body);
+            }
+            StmtKind::For(initializer, condition, increment, body) => {
+                self.out.push_str("for (");
+                match initializer.as_deref() {
+                    // Not at the start of a statement, so a brace here can't open a block.
+                    Some(Stmt {
+                        kind: StmtKind::Expression(expr),
+                        ..
+                    }) => {
+                        self.expr(expr, Precedence::Assignment);
+                        self.out.push(';');
+                    }
+                    Some(initializer) => self.stmt_inline(initializer),
+                    None => self.out.push(';'),
+                }
+                if let Some(condition) = condition {
+                    self.out.push(' ');
+                    self.expr(condition, Precedence::Assignment);
+                }
+                self.out.push(';');
+                if let Some(increment) = increment {
+                    self.out.push(' ');
+                    self.expr(increment, Precedence::Assignment);
+                }
+                self.out.push(')');
+                self.branch(body);
+            }
+            StmtKind::ForIn(name, iterable, body) => {
+                let _ = write!(self.out, "for ({} in ", name.lexeme);
+                self.expr(iterable, Precedence::Assignment);
+                self.out.push(')');
+                self.branch(body);
+            }
+            StmtKind::Function(name, params, body, doc) => {
+                self.doc(doc);
+                self.out.push_str("fun ");
+                self.function(&name.lexeme, params, body, stmt.span.end);
+            }
+            StmtKind::Return(_, value) => {
+                self.out.push_str("return");
+                if let Some(value) = value {
+                    self.out.push(' ');
+                    self.expr(value, Precedence::Assignment);
+                }
+                self.out.push(';');
+            }
+            StmtKind::Defer(body) => {
+                self.out.push_str("defer");
+                self.branch(body);
+            }
+            StmtKind::Enum(name, variants) => {
+                let variants: Vec<&str> = variants.iter().map(|v| v.lexeme.as_str()).collect();
+                match variants.as_slice() {
+                    [] => {
+                        let _ = write!(self.out, "enum {} {{}}", name.lexeme);
+                    }
+                    _ => {
+                        let _ = write!(
+                            self.out,
+                            "enum {} {{ {} }}",
+                            name.lexeme,
+                            variants.join(", ")
+                        );
+                    }
+                }
+            }
+            StmtKind::Class(name, superclass, members, doc) => {
+                self.doc(doc);
+                let _ = write!(self.out, "class {} ", name.lexeme);
+                if let Some(superclass) = superclass {
+                    self.out.push_str("< ");
+                    self.expr(superclass, Precedence::Primary);
+                    self.out.push(' ');
+                }
+                self.members(members, stmt.span.end);
+            }
+            StmtKind::Extend(class, methods) => {
+                self.out.push_str("extend ");
+                self.expr(class, Precedence::Primary);
+                self.out.push(' ');
+                self.members(methods, stmt.span.end);
+            }
+        }
+    }
+
+    /// Writes the body of a control-flow statement after its header: a block on the same
+    /// line, anything else indented on the next.
+    fn branch(&mut self, branch: &Stmt) {
+        match &branch.kind {
+            StmtKind::Block(stmts) => {
+                self.out.push(' ');
+                self.trailing(branch, false);
+                self.block(stmts, Some(branch.span.end));
+                self.trailing(branch, true);
+            }
+            _ => {
+                self.indent += 1;
+                self.newline();
+                self.leading(branch);
+                self.trailing(branch, false);
+                self.stmt_inline(branch);
+                self.trailing(branch, true);
+                self.indent -= 1;
+            }
+        }
+    }
+
+    /// Gets ready for an `else` after `branch`: on the line a block ended on, or on a line of
+    /// its own. A comment trailing the block keeps the line to itself.
+    fn after_branch(&mut self, branch: &Stmt) {
+        match branch.kind {
+            StmtKind::Block(_) if self.trailing.is_none() => self.out.push(' '),
+            _ => self.newline(),
+        }
+    }
+
+    /// Writes a block whose closing brace ends at byte offset `end` in the source, if it's
+    /// known, so the orphan comments from before it can go inside.
+    fn block(&mut self, stmts: &[Stmt], end: Option<usize>) {
+        let end = end.unwrap_or(0);
+        if stmts.is_empty() && !self.has_orphans_before(end) {
+            self.out.push_str("{}");
+            return;
+        }
+
+        self.out.push('{');
+        self.line_break();
+        self.indent += 1;
+        for stmt in stmts {
+            self.stmt(stmt);
+        }
+        self.orphans_before(end);
+        self.indent -= 1;
+        self.line_start();
+        self.out.push('}');
+    }
+
+    /// Writes a function's name, parameters and a body ending at byte offset `end`.
+    fn function(&mut self, name: &str, params: &[crate::lexer::Token], body: &[Stmt], end: usize) {
+        let params: Vec<&str> = params.iter().map(|p| p.lexeme.as_str()).collect();
+        let _ = write!(self.out, "{}({}) ", name, params.join(", "));
+        self.block(body, Some(end));
+    }
+
+    /// The braces of a class or extension ending at byte offset `end` and the fields and
+    /// methods between them.
+    fn members(&mut self, members: &[Stmt], end: usize) {
+        if members.is_empty() && !self.has_orphans_before(end) {
+            self.out.push_str("{}");
+            return;
+        }
+
+        self.out.push('{');
+        self.line_break();
+        self.indent += 1;
+        for member in members {
+            self.orphans_before(member.span.start);
+            self.line_start();
+            self.leading(member);
+            self.trailing(member, false);
+            match &member.kind {
+                StmtKind::Function(name, params, body, doc) => {
+                    self.doc(doc);
+                    self.function(&name.lexeme, params, body, member.span.end);
+                }
+                _ => self.stmt_inline(member),
+            }
+            self.trailing(member, true);
+            self.line_break();
+        }
+        self.orphans_before(end);
+        self.indent -= 1;
+        self.line_start();
+        self.out.push('}');
+    }
+
+    /// Writes an expression standing where a statement starts. One starting with a brace would
+    /// read as a block, so it is parenthesized.
+    fn statement_expr(&mut self, expr: &Expr) {
+        if starts_with_brace(expr) {
+            self.out.push('(');
+            self.expr(expr, Precedence::Assignment);
+            self.out.push(')');
+        } else {
+            self.expr(expr, Precedence::Assignment);
+        }
+    }
+
+    /// Writes `expr` where an expression binding at least as tightly as `min` is expected,
+    /// parenthesizing it if it binds more loosely.
+    fn expr(&mut self, expr: &Expr, min: Precedence) {
+        let precedence = Precedence::of(expr);
+        if precedence < min {
+            self.out.push('(');
+            self.expr(expr, Precedence::Assignment);
+            self.out.push(')');
+            return;
+        }
+
+        match &expr.kind {
+            ExprKind::Array(elements) => {
+                self.out.push('[');
+                self.list(elements);
+                self.out.push(']');
+            }
+            ExprKind::Tuple(values) => self.list(values),
+            ExprKind::Map(entries) => {
+                self.out.push('{');
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.expr(key, Precedence::Assignment);
+                    self.out.push_str(": ");
+                    self.expr(value, Precedence::Assignment);
+                }
+                self.out.push('}');
+            }
+            ExprKind::Binary(op, lhs, rhs) => {
+                self.expr(lhs, precedence);
+                let _ = write!(self.out, " {} ", op);
+                self.expr(rhs, precedence.tighter());
+            }
+            ExprKind::Logical(op, lhs, rhs) => {
+                self.expr(lhs, precedence);
+                let _ = write!(self.out, " {} ", op);
+                self.expr(rhs, precedence.tighter());
+            }
+            ExprKind::Unary(op, operand) => {
+                let _ = write!(self.out, "{}", op);
+                self.expr(operand, Precedence::Unary);
+            }
+            ExprKind::Call(callee, arguments) => {
+                self.expr(callee, Precedence::Call);
+                self.out.push('(');
+                self.list(arguments);
+                self.out.push(')');
+            }
+            ExprKind::Get(object, name, optional) => {
+                self.expr(object, Precedence::Call);
+                self.out.push_str(if *optional { "?." } else { "." });
+                self.out.push_str(&name.lexeme);
+            }
+            ExprKind::Index(value, index) => {
+                self.expr(value, Precedence::Call);
+                self.out.push('[');
+                self.expr(index, Precedence::Assignment);
+                self.out.push(']');
+            }
+            ExprKind::Set(object, name, value) => {
+                self.expr(object, Precedence::Call);
+                let _ = write!(self.out, ".{} = ", name.lexeme);
+                self.expr(value, Precedence::Assignment);
+            }
+            ExprKind::Assign(name, value, _) => {
+                let _ = write!(self.out, "{} = ", name.lexeme);
+                self.expr(value, Precedence::Assignment);
+            }
+            ExprKind::Grouping(inner) => {
+                self.out.push('(');
+                self.expr(inner, Precedence::Assignment);
+                self.out.push(')');
+            }
+            ExprKind::Literal(value) => self.literal(value),
+            ExprKind::Variable(name, _) => self.out.push_str(&name.lexeme),
+            ExprKind::This(..) => self.out.push_str("this"),
+            ExprKind::Super(method, _) => {
+                let _ = write!(self.out, "super.{}", method.lexeme);
+            }
+            ExprKind::Match(value, arms) => {
+                self.out.push_str("match (");
+                self.expr(value, Precedence::Assignment);
+                self.out.push_str(") {");
+                self.indent += 1;
+                for arm in arms {
+                    self.newline();
+                    self.pattern(&arm.pattern);
+                    self.out.push_str(" -> ");
+                    match &arm.body {
+                        ArmBody::Expr(body) => {
+                            self.statement_expr(body);
+                            self.out.push(';');
+                        }
+                        ArmBody::Block(stmts) => self.block(stmts, None),
+                    }
+                }
+                self.indent -= 1;
+                if !arms.is_empty() {
+                    self.newline();
+                }
+                self.out.push('}');
+            }
+        }
+    }
+
+    fn list(&mut self, exprs: &[Expr]) {
+        for (i, expr) in exprs.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.expr(expr, Precedence::Assignment);
+        }
+    }
+
+    fn literal(&mut self, value: &Literal) {
+        match value {
+            Literal::String(s) => {
+                let _ = write!(self.out, "\"{}\"", s);
+            }
+            value => {
+                let _ = write!(self.out, "{}", value);
+            }
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        match &pattern.kind {
+            PatternKind::Wildcard => self.out.push('_'),
+            PatternKind::Literal(value) => self.literal(value),
+            PatternKind::Binding(name) => self.out.push_str(&name.lexeme),
+            PatternKind::Value(value) => self.expr(value, Precedence::Call),
+            PatternKind::Array(elements) => {
+                self.out.push('[');
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.pattern(element);
+                }
+                self.out.push(']');
+            }
+            PatternKind::Map(entries) => {
+                self.out.push('{');
+                for (i, (key, pattern)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    let _ = write!(self.out, "\"{}\": ", key);
+                    self.pattern(pattern);
+                }
+                self.out.push('}');
+            }
+        }
+    }
+}
+
+/// Whether the first token of `expr` is a `{`, which it is when its leftmost operand is a map.
+fn starts_with_brace(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Map(_) => true,
+        ExprKind::Binary(_, lhs, _) | ExprKind::Logical(_, lhs, _) => {
+            Precedence::of(lhs) >= Precedence::of(expr) && starts_with_brace(lhs)
+        }
+        ExprKind::Call(object, _)
+        | ExprKind::Get(object, ..)
+        | ExprKind::Index(object, _)
+        | ExprKind::Set(object, ..) => {
+            Precedence::of(object) >= Precedence::Call && starts_with_brace(object)
+        }
+        _ => false,
+    }
+}
@@ -1,15 +1,58 @@
-use crate::lexer::Token;
+use crate::lexer::{Span, Token};
 
 use super::expr::Expr;
 
-#[derive(Clone, Debug)]
-pub enum Stmt {
+/// Compared the way `Expr` is, without regard to where anything is in the source.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StmtKind {
     Expression(Expr),
     Print(Expr),
     Var(Token, Option<Expr>),
+    /// `var a, b = value;`: the names to bind, in order, to the values of the tuple `value`.
+    Unpack(Vec<Token>, Expr),
+    Const(Token, Expr),
     Block(Vec<Stmt>),
-    If(Expr, Box<Stmt>, Box<Option<Stmt>>),
+    /// An `if` with the `else if`s chained to it: the condition and branch of each, in
+    /// order, then the final `else` branch if there is one.
+    If(Vec<(Expr, Stmt)>, Option<Box<Stmt>>),
     While(Expr, Box<Stmt>),
-    Function(Token, Vec<Token>, Vec<Stmt>),
+    /// `for (initializer; condition; increment) body`, any of the clauses left out.
+    For(Option<Box<Stmt>>, Option<Expr>, Option<Expr>, Box<Stmt>),
+    /// `for (name in value) body`: the body run once for each character of a string, or
+    /// element of an array or tuple, with `name` bound to it in a scope of its own.
+    ForIn(Token, Expr, Box<Stmt>),
+    /// The function's name, parameters and body, and its doc comment if it has one.
+    Function(Token, Vec<Token>, Vec<Stmt>, Option<String>),
     Return(Token, Option<Expr>),
+    /// `defer body`: the statement to run when the enclosing function or script finishes.
+    Defer(Box<Stmt>),
+    /// The enum's name and its variants.
+    Enum(Token, Vec<Token>),
+    /// The class's name, its superclass as a `Variable` expression, and its members: methods,
+    /// each a `Function` statement, and fields, each a `Var` statement, then its doc comment
+    /// if it has one.
+    Class(Token, Option<Expr>, Vec<Stmt>, Option<String>),
+    /// `extend`: the class to add methods to, as a `Variable` expression, and the methods,
+    /// each a `Function` statement.
+    Extend(Expr, Vec<Stmt>),
+}
+
+#[derive(Clone, Debug)]
+pub struct Stmt {
+    pub kind: StmtKind,
+    /// From the first to the last token of the statement. Statements produced by desugaring
+    /// share the span of the construct they came from.
+    pub span: Span,
+}
+
+impl Stmt {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl PartialEq for Stmt {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
 }
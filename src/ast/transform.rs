@@ -0,0 +1,189 @@
+//! Rewriting syntax trees. A pass implements `Fold`, overriding the methods for the nodes it
+//! changes; the `walk_*` functions rebuild everything else with its children folded.
+
+use super::{
+    expr::{Expr, ExprKind},
+    pattern::{ArmBody, MatchArm, Pattern, PatternKind},
+    stmt::{Stmt, StmtKind},
+};
+
+/// A pass that takes a tree apart and builds a new one. Each method defaults to folding the
+/// node's children and putting it back together unchanged.
+pub trait Fold {
+    fn fold_stmt(&mut self, stmt: Stmt) -> Stmt {
+        walk_stmt(self, stmt)
+    }
+
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        walk_expr(self, expr)
+    }
+
+    fn fold_pattern(&mut self, pattern: Pattern) -> Pattern {
+        walk_pattern(self, pattern)
+    }
+
+    fn fold_stmts(&mut self, stmts: Vec<Stmt>) -> Vec<Stmt> {
+        stmts.into_iter().map(|stmt| self.fold_stmt(stmt)).collect()
+    }
+}
+
+/// Folds the children of `stmt` and puts it back together around them.
+pub fn walk_stmt<F: Fold + ?Sized>(folder: &mut F, stmt: Stmt) -> Stmt {
+    let kind = match stmt.kind {
+        StmtKind::Expression(expr) => StmtKind::Expression(folder.fold_expr(expr)),
+        StmtKind::Print(expr) => StmtKind::Print(folder.fold_expr(expr)),
+        StmtKind::Var(name, initializer) => {
+            StmtKind::Var(name, initializer.map(|expr| folder.fold_expr(expr)))
+        }
+        StmtKind::Const(name, initializer) => StmtKind::Const(name, folder.fold_expr(initializer)),
+        StmtKind::Unpack(names, initializer) => {
+            StmtKind::Unpack(names, folder.fold_expr(initializer))
+        }
+        StmtKind::Block(stmts) => StmtKind::Block(folder.fold_stmts(stmts)),
+        StmtKind::If(arms, else_branch) => StmtKind::If(
+            arms.into_iter()
+                .map(|(condition, branch)| (folder.fold_expr(condition), folder.fold_stmt(branch)))
+                .collect(),
+            else_branch.map(|stmt| Box::new(folder.fold_stmt(*stmt))),
+        ),
+        StmtKind::While(condition, body) => StmtKind::While(
+            folder.fold_expr(condition),
+            Box::new(folder.fold_stmt(*body)),
+        ),
+        StmtKind::For(initializer, condition, increment, body) => StmtKind::For(
+            initializer.map(|stmt| Box::new(folder.fold_stmt(*stmt))),
+            condition.map(|expr| folder.fold_expr(expr)),
+            increment.map(|expr| folder.fold_expr(expr)),
+            Box::new(folder.fold_stmt(*body)),
+        ),
+        StmtKind::ForIn(name, iterable, body) => StmtKind::ForIn(
+            name,
+            folder.fold_expr(iterable),
+            Box::new(folder.fold_stmt(*body)),
+        ),
+        StmtKind::Function(name, params, body, doc) => {
+            StmtKind::Function(name, params, folder.fold_stmts(body), doc)
+        }
+        StmtKind::Return(keyword, value) => {
+            StmtKind::Return(keyword, value.map(|expr| folder.fold_expr(expr)))
+        }
+        StmtKind::Defer(body) => StmtKind::Defer(Box::new(folder.fold_stmt(*body))),
+        StmtKind::Enum(name, variants) => StmtKind::Enum(name, variants),
+        StmtKind::Class(name, superclass, methods, doc) => StmtKind::Class(
+            name,
+            superclass.map(|expr| folder.fold_expr(expr)),
+            folder.fold_stmts(methods),
+            doc,
+        ),
+        StmtKind::Extend(class, methods) => {
+            StmtKind::Extend(folder.fold_expr(class), folder.fold_stmts(methods))
+        }
+    };
+
+    Stmt {
+        kind,
+        span: stmt.span,
+    }
+}
+
+/// Folds the children of `expr` and puts it back together around them.
+pub fn walk_expr<F: Fold + ?Sized>(folder: &mut F, expr: Expr) -> Expr {
+    let kind = match expr.kind {
+        ExprKind::Array(elements) => ExprKind::Array(
+            elements
+                .into_iter()
+                .map(|element| folder.fold_expr(element))
+                .collect(),
+        ),
+        ExprKind::Map(entries) => ExprKind::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (folder.fold_expr(key), folder.fold_expr(value)))
+                .collect(),
+        ),
+        ExprKind::Tuple(values) => ExprKind::Tuple(
+            values
+                .into_iter()
+                .map(|value| folder.fold_expr(value))
+                .collect(),
+        ),
+        ExprKind::Binary(op, lhs, rhs) => ExprKind::Binary(
+            op,
+            Box::new(folder.fold_expr(*lhs)),
+            Box::new(folder.fold_expr(*rhs)),
+        ),
+        ExprKind::Call(callee, args) => ExprKind::Call(
+            Box::new(folder.fold_expr(*callee)),
+            args.into_iter().map(|arg| folder.fold_expr(arg)).collect(),
+        ),
+        ExprKind::Get(object, name, optional) => {
+            ExprKind::Get(Box::new(folder.fold_expr(*object)), name, optional)
+        }
+        ExprKind::Index(value, index) => ExprKind::Index(
+            Box::new(folder.fold_expr(*value)),
+            Box::new(folder.fold_expr(*index)),
+        ),
+        ExprKind::Grouping(inner) => ExprKind::Grouping(Box::new(folder.fold_expr(*inner))),
+        ExprKind::Logical(op, lhs, rhs) => ExprKind::Logical(
+            op,
+            Box::new(folder.fold_expr(*lhs)),
+            Box::new(folder.fold_expr(*rhs)),
+        ),
+        ExprKind::Unary(op, operand) => ExprKind::Unary(op, Box::new(folder.fold_expr(*operand))),
+        ExprKind::Assign(name, value, depth) => {
+            ExprKind::Assign(name, Box::new(folder.fold_expr(*value)), depth)
+        }
+        ExprKind::Set(object, name, value) => ExprKind::Set(
+            Box::new(folder.fold_expr(*object)),
+            name,
+            Box::new(folder.fold_expr(*value)),
+        ),
+        ExprKind::Match(value, arms) => ExprKind::Match(
+            Box::new(folder.fold_expr(*value)),
+            arms.into_iter()
+                .map(|arm| MatchArm {
+                    pattern: folder.fold_pattern(arm.pattern),
+                    body: match arm.body {
+                        ArmBody::Expr(expr) => ArmBody::Expr(folder.fold_expr(expr)),
+                        ArmBody::Block(stmts) => ArmBody::Block(folder.fold_stmts(stmts)),
+                    },
+                })
+                .collect(),
+        ),
+        kind @ (ExprKind::Literal(_)
+        | ExprKind::Variable(..)
+        | ExprKind::This(..)
+        | ExprKind::Super(..)) => kind,
+    };
+
+    Expr {
+        kind,
+        span: expr.span,
+    }
+}
+
+/// Folds the children of `pattern`, including the expressions it compares against, and puts
+/// it back together around them.
+pub fn walk_pattern<F: Fold + ?Sized>(folder: &mut F, pattern: Pattern) -> Pattern {
+    let kind = match pattern.kind {
+        PatternKind::Value(value) => PatternKind::Value(folder.fold_expr(value)),
+        PatternKind::Array(elements) => PatternKind::Array(
+            elements
+                .into_iter()
+                .map(|element| folder.fold_pattern(element))
+                .collect(),
+        ),
+        PatternKind::Map(entries) => PatternKind::Map(
+            entries
+                .into_iter()
+                .map(|(key, pattern)| (key, folder.fold_pattern(pattern)))
+                .collect(),
+        ),
+        kind @ (PatternKind::Wildcard | PatternKind::Literal(_) | PatternKind::Binding(_)) => kind,
+    };
+
+    Pattern {
+        kind,
+        span: pattern.span,
+    }
+}
@@ -1,16 +1,71 @@
-use rlox;
-use std::{env, process};
+use std::{env, path::PathBuf, process};
+
+use rlox::{
+    cli::{self, Command, Mode},
+    DiagnosticFormat,
+};
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let config = match cli::parse_args(args) {
+        Ok(Command::Run(config)) => config,
+        Ok(Command::Help) => {
+            print!("{}", cli::help());
+            return;
+        }
+        Ok(Command::Version) => {
+            println!("{}", cli::version());
+            return;
+        }
+        Err(e) => {
+            eprintln!("{}\n{}", e, cli::usage());
+            process::exit(64);
+        }
+    };
+
+    let format = config.diagnostics.print.unwrap_or(DiagnosticFormat::Plain);
+    let diagnostics = config.diagnostics;
+    let result = match config.mode {
+        Mode::Repl => rlox::run_prompt(
+            config.init,
+            config.implicit_globals,
+            config.prelude,
+            diagnostics,
+        ),
+        Mode::File(path) if config.extract_docs => rlox::print_docs(path),
+        Mode::File(path) if config.test => {
+            match rlox::test_file(path, config.capabilities, config.prelude, diagnostics) {
+                Ok(report) if !report.passed() => process::exit(1),
+                result => result.map(|_| ()),
+            }
+        }
+        Mode::File(path) if config.debug => rlox::debug_file(path, config.prelude, diagnostics),
+        Mode::File(path) => rlox::run_file(
+            path,
+            config.time,
+            config.capabilities,
+            config.prelude,
+            diagnostics,
+        ),
+        Mode::Files(paths) => rlox::run_files(
+            paths.into_iter().map(PathBuf::from).collect(),
+            config.time,
+            config.capabilities,
+            config.prelude,
+            diagnostics,
+        ),
+        Mode::Inline(code) => rlox::run_inline(
+            &code,
+            config.time,
+            config.capabilities,
+            config.prelude,
+            diagnostics,
+        ),
+    };
 
-    let args_len = args.len();
-    if args_len > 2 {
-        eprintln!("Usage: rlox [script]");
-        process::exit(64);
-    } else if args_len == 2 {
-        rlox::run_file(args[1].clone()).unwrap();
-    } else {
-        rlox::run_prompt().unwrap();
+    if let Err(e) = result {
+        eprintln!("{}", format.render_error(&e));
+        process::exit(e.exit_code());
     }
 }
@@ -0,0 +1,55 @@
+use std::{
+    env, fs,
+    io::{self, Read},
+    process,
+};
+
+use rlox::inspect::{self, Command, InspectError};
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let options = match inspect::parse_args(args) {
+        Ok(Command::Inspect(options)) => options,
+        Ok(Command::Help) => {
+            print!("{}", inspect::help());
+            return;
+        }
+        Err(e) => {
+            eprintln!("{}\n{}", e, inspect::usage());
+            process::exit(64);
+        }
+    };
+
+    let (name, code) = match &options.path {
+        Some(path) => (path.as_str(), fs::read_to_string(path)),
+        None => {
+            let mut code = String::new();
+            (
+                "<stdin>",
+                io::stdin().read_to_string(&mut code).map(|_| code),
+            )
+        }
+    };
+    let code = match code {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Could not read '{}': {}", name, e);
+            process::exit(66);
+        }
+    };
+
+    match inspect::inspect(name, &code, &options) {
+        Ok(output) => print!("{}", output),
+        Err(e) => {
+            match &e {
+                InspectError::Lox(e) => eprintln!("{}", e),
+                InspectError::NoSuchStatement { requested, count } => eprintln!(
+                    "There is no statement {}; the script has {}.",
+                    requested, count
+                ),
+            }
+            process::exit(e.exit_code());
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use crate::{Capability, CapabilitySet, DiagnosticFormat, DiagnosticOptions};
+
+/// Every option the command line accepts, with the text shown for it by `--help`.
+const OPTIONS: &[(&str, &str)] = &[
+    ("-e <code>", "Run <code> instead of a script file"),
+    ("--debug", "Run the script under the interactive debugger"),
+    (
+        "--extract-docs",
+        "Print the script's doc comments as Markdown instead of running it",
+    ),
+    (
+        "--test",
+        "Check what the script prints against its `// expect:` comments",
+    ),
+    (
+        "--time",
+        "Report parse and execution time and execution stats on stderr",
+    ),
+    ("--no-init", "Don't run the REPL startup file"),
+    (
+        "--no-prelude",
+        "Don't define the globals written in Lox, such as reduce and Queue",
+    ),
+    (
+        "--implicit-globals",
+        "Let assignments at the REPL define undefined globals",
+    ),
+    (
+        "--deny-warnings",
+        "Treat warnings as errors: don't run code that has any",
+    ),
+    (
+        "--allow <code>",
+        "Drop warnings with <code>; can be repeated",
+    ),
+    (
+        "--diagnostic-format <f>",
+        "Write warnings and errors as plain, color or json",
+    ),
+    (
+        "--allow-net",
+        "Give the script fetch() and fetchPost() (needs the http feature)",
+    ),
+    (
+        "--allow-run",
+        "Give the script exec() to run other programs",
+    ),
+    ("--allow-read", "Give the script readFile() to read files"),
+    (
+        "--allow-env",
+        "Give the script getEnv() to read environment variables",
+    ),
+    ("-h, --help", "Print this help and exit"),
+    ("-V, --version", "Print the version and exit"),
+];
+
+#[derive(Debug, PartialEq)]
+pub enum Mode {
+    Repl,
+    File(String),
+    /// Several scripts, run in order against one interpreter.
+    Files(Vec<String>),
+    Inline(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Config {
+    pub mode: Mode,
+    pub debug: bool,
+    /// Whether to print the script's doc comments rather than run it.
+    pub extract_docs: bool,
+    /// Whether to check the script's output against its `// expect:` comments.
+    pub test: bool,
+    pub time: bool,
+    pub init: bool,
+    /// Whether assigning to an undefined name at the REPL defines it.
+    pub implicit_globals: bool,
+    /// Whether the prelude runs before the code.
+    pub prelude: bool,
+    /// What the script may reach beyond the interpreter.
+    pub capabilities: CapabilitySet,
+    /// Which warnings are reported and how.
+    pub diagnostics: DiagnosticOptions,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Run(Config),
+    Help,
+    Version,
+}
+
+pub fn usage() -> &'static str {
+    "Usage: rlox [options] [script...]"
+}
+
+/// The `--help` text, built from the option table.
+pub fn help() -> String {
+    help_text(usage(), OPTIONS)
+}
+
+/// Lays out a usage line followed by a table of options and their descriptions.
+pub(crate) fn help_text(usage: &str, options: &[(&str, &str)]) -> String {
+    let width = options
+        .iter()
+        .map(|(flag, _)| flag.len())
+        .max()
+        .unwrap_or(0);
+    let mut text = format!("{}\n\nOptions:\n", usage);
+
+    for (flag, description) in options {
+        text.push_str(&format!(
+            "  {:width$}  {}\n",
+            flag,
+            description,
+            width = width
+        ));
+    }
+
+    text
+}
+
+/// What `--version` prints: the version, the commit when known, the backend and the
+/// capabilities this build can grant.
+pub fn version() -> String {
+    crate::build_info().to_string()
+}
+
+/// Parses the command-line arguments, without the program name. Errors describe what is
+/// wrong with the invocation and should be followed by the usage line.
+pub fn parse_args(args: Vec<String>) -> Result<Command, String> {
+    let mut paths = vec![];
+    let mut inline = None;
+    let mut debug = false;
+    let mut extract_docs = false;
+    let mut test = false;
+    let mut time = false;
+    let mut init = true;
+    let mut implicit_globals = false;
+    let mut prelude = true;
+    let mut capabilities = CapabilitySet::default();
+    let mut diagnostics = DiagnosticOptions::default();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "-e" => match args.next() {
+                Some(code) if inline.is_none() => inline = Some(code),
+                Some(_) => return Err("-e can only be given once.".into()),
+                None => return Err("-e needs the code to run.".into()),
+            },
+            "--debug" => debug = true,
+            "--extract-docs" => extract_docs = true,
+            "--test" => test = true,
+            "--time" => time = true,
+            "--no-init" => init = false,
+            "--implicit-globals" => implicit_globals = true,
+            "--no-prelude" => prelude = false,
+            "--allow-net" if cfg!(feature = "http") => {
+                capabilities = capabilities.with(Capability::Network)
+            }
+            "--allow-net" => {
+                return Err(
+                    "This rlox was built without the http feature, so it has no network \
+                            access to allow."
+                        .into(),
+                )
+            }
+            "--allow-run" => capabilities = capabilities.with(Capability::Processes),
+            "--allow-read" => capabilities = capabilities.with(Capability::Filesystem),
+            "--allow-env" => capabilities = capabilities.with(Capability::Environment),
+            "--deny-warnings" => diagnostics.deny_warnings = true,
+            "--allow" => match args.next() {
+                Some(code) => diagnostics.allow.push(code),
+                None => return Err("--allow needs a warning code.".into()),
+            },
+            "--diagnostic-format" => {
+                let format = match args.next().as_deref() {
+                    Some("plain") => DiagnosticFormat::Plain,
+                    Some("color") => DiagnosticFormat::Colored,
+                    Some("json") => DiagnosticFormat::Json,
+                    Some(other) => {
+                        return Err(format!(
+                            "Unknown diagnostic format '{}'; use plain, color or json.",
+                            other
+                        ))
+                    }
+                    None => return Err("--diagnostic-format needs a format.".into()),
+                };
+                diagnostics.print = Some(format);
+            }
+            flag if flag.starts_with('-') => return Err(format!("Unknown option '{}'.", flag)),
+            _ => paths.push(arg),
+        }
+    }
+
+    let mode = match (paths.len(), inline) {
+        (0, None) => Mode::Repl,
+        (0, Some(code)) => Mode::Inline(code),
+        (_, Some(_)) => return Err("-e can't be combined with a script file.".into()),
+        (1, None) => Mode::File(paths.remove(0)),
+        (_, None) => Mode::Files(paths),
+    };
+
+    match mode {
+        Mode::Repl if debug || time || capabilities != CapabilitySet::default() => {
+            return Err("--debug, --time and the --allow-* options need a script or -e.".into());
+        }
+        Mode::Files(_) if debug || extract_docs || test => {
+            return Err("--debug, --extract-docs and --test take a single script file.".into());
+        }
+        Mode::File(_) | Mode::Files(_) | Mode::Inline(_) if !init => {
+            return Err("--no-init only applies to the REPL.".into());
+        }
+        Mode::File(_) | Mode::Files(_) | Mode::Inline(_) if implicit_globals => {
+            return Err("--implicit-globals only applies to the REPL.".into());
+        }
+        Mode::Inline(_) if debug => {
+            return Err("--debug needs a script file.".into());
+        }
+        Mode::Repl | Mode::Inline(_) if extract_docs => {
+            return Err("--extract-docs needs a script file.".into());
+        }
+        Mode::Repl | Mode::Inline(_) if test => {
+            return Err("--test needs a script file.".into());
+        }
+        _ if extract_docs && (debug || time || capabilities != CapabilitySet::default()) => {
+            return Err(
+                "--extract-docs doesn't run the script, so it takes no other options.".into(),
+            );
+        }
+        _ if test && (debug || extract_docs || time) => {
+            return Err("--test can't be combined with --debug, --extract-docs or --time.".into());
+        }
+        _ if debug && capabilities != CapabilitySet::default() => {
+            return Err("--debug can't be combined with the --allow-* options.".into());
+        }
+        _ if debug && time => {
+            return Err("--debug and --time can't be combined.".into());
+        }
+        _ => (),
+    }
+
+    Ok(Command::Run(Config {
+        mode,
+        debug,
+        extract_docs,
+        test,
+        time,
+        init,
+        implicit_globals,
+        prelude,
+        capabilities,
+        diagnostics,
+    }))
+}
@@ -0,0 +1,201 @@
+//! Attaching comments to the statements they belong to, for the tools that need them back:
+//! the formatter writes them out again and the doc extractor reads doc comments from them.
+//!
+//! The lexer drops comments, so they are found again in the trivia-mode token stream and
+//! matched to statements by their spans.
+
+use std::collections::HashMap;
+
+use crate::{
+    ast::{
+        stmt::Stmt,
+        transform::{walk_stmt, Fold},
+    },
+    lexer::{tokenize_trivia, Span, Token, TokenKind},
+};
+
+/// A `//` comment.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comment {
+    /// The text after the slashes, so a doc comment's starts with the third slash.
+    pub text: String,
+    pub span: Span,
+}
+
+impl Comment {
+    /// The comment as it is written, slashes included.
+    pub fn source(&self) -> String {
+        format!("//{}", self.text)
+    }
+
+    /// The text of a `///` doc comment line, without the slashes and the space after them.
+    fn doc_line(&self) -> Option<&str> {
+        let doc = self.text.strip_prefix('/')?;
+        Some(doc.strip_prefix(' ').unwrap_or(doc))
+    }
+}
+
+/// Statement spans compared by where they start and end.
+type Key = (usize, usize);
+
+fn key(span: Span) -> Key {
+    (span.start, span.end)
+}
+
+/// The comments of a source, keyed by the spans of the statements they belong to.
+///
+/// - A comment on a line of its own leads the statement right after it, with only other
+///   comments and blank lines in between.
+/// - A comment after code on the same line trails the statement that code ends, or failing
+///   that the innermost statement starting earlier on the line.
+/// - The rest, such as a comment before a block's closing brace or at the end of the file,
+///   belong to the file as orphans.
+#[derive(Clone, Debug, Default)]
+pub struct CommentMap {
+    leading: HashMap<Key, Vec<Comment>>,
+    trailing: HashMap<Key, Comment>,
+    orphans: Vec<Comment>,
+}
+
+impl CommentMap {
+    /// The comments on the lines above `stmt`, in order.
+    pub fn leading(&self, stmt: &Stmt) -> &[Comment] {
+        self.leading.get(&key(stmt.span)).map_or(&[], Vec::as_slice)
+    }
+
+    /// The comment after `stmt` on the line it ends on.
+    pub fn trailing(&self, stmt: &Stmt) -> Option<&Comment> {
+        self.trailing.get(&key(stmt.span))
+    }
+
+    /// The comments attached to no statement, in order.
+    pub fn orphans(&self) -> &[Comment] {
+        &self.orphans
+    }
+
+    /// The doc comment of `stmt`: the `///` lines among its leading comments that end on the
+    /// line right above it, with no blank line or other comment in between, joined with line
+    /// breaks.
+    pub fn doc(&self, stmt: &Stmt) -> Option<String> {
+        let lines: Vec<&str> = self
+            .doc_comments(stmt)
+            .iter()
+            .filter_map(Comment::doc_line)
+            .collect();
+        match lines.is_empty() {
+            true => None,
+            false => Some(lines.join("\n")),
+        }
+    }
+
+    /// The leading comments of `stmt` that make up its doc comment.
+    pub fn doc_comments(&self, stmt: &Stmt) -> &[Comment] {
+        let leading = self.leading(stmt);
+        let mut line = stmt.span.line;
+        let mut first = leading.len();
+        while first > 0 {
+            let comment = &leading[first - 1];
+            if comment.span.line + 1 != line || comment.doc_line().is_none() {
+                break;
+            }
+            line = comment.span.line;
+            first -= 1;
+        }
+        &leading[first..]
+    }
+}
+
+/// Attaches the comments in `code` to `stmts`, the statements parsed from it.
+///
+/// ```
+/// let code = "// Greets.\nprint \"hi\"; // loudly\n// The end.\n";
+/// let stmts = rlox::parse("greet.lox", code).unwrap();
+/// let comments = rlox::attach_comments(code, &stmts);
+///
+/// assert_eq!(comments.leading(&stmts[0])[0].text, " Greets.");
+/// assert_eq!(comments.trailing(&stmts[0]).unwrap().text, " loudly");
+/// assert_eq!(comments.orphans()[0].text, " The end.");
+/// ```
+pub fn attach_comments(code: &str, stmts: &[Stmt]) -> CommentMap {
+    let mut statements = Statements::default();
+    statements.fold_stmts(stmts.to_vec());
+
+    let tokens: Vec<Token> = tokenize_trivia(code).collect();
+    let mut comments = CommentMap::default();
+    let mut previous: Option<&Token> = None;
+    for (index, token) in tokens.iter().enumerate() {
+        let text = match &token.value {
+            TokenKind::LineComment(text) => text,
+            TokenKind::Whitespace(_) => continue,
+            _ => {
+                previous = Some(token);
+                continue;
+            }
+        };
+        let comment = Comment {
+            text: text.clone(),
+            span: token.span(),
+        };
+
+        match previous.filter(|previous| previous.line == token.line) {
+            Some(previous) => {
+                let end = previous.start + previous.length;
+                match statements.trailed_by(end, token) {
+                    Some(stmt) => {
+                        comments.trailing.insert(stmt, comment);
+                    }
+                    None => comments.orphans.push(comment),
+                }
+            }
+            None => {
+                let next = tokens[index + 1..].iter().find(|token| {
+                    !matches!(
+                        token.value,
+                        TokenKind::LineComment(_) | TokenKind::Whitespace(_)
+                    )
+                });
+                match next.and_then(|next| statements.starts.get(&next.start)) {
+                    Some(stmt) => comments.leading.entry(*stmt).or_default().push(comment),
+                    None => comments.orphans.push(comment),
+                }
+            }
+        }
+    }
+
+    comments
+}
+
+/// Every statement in a tree, nested ones included, by where it starts and ends.
+#[derive(Default)]
+struct Statements {
+    /// The outermost statement starting at each offset.
+    starts: HashMap<usize, Key>,
+    /// The innermost statement ending at each offset.
+    ends: HashMap<usize, Key>,
+    /// Each statement's line and span, outer statements before the ones nested in them.
+    lines: Vec<(usize, Key)>,
+}
+
+impl Statements {
+    /// The statement a comment after code ending at `end` on the same line trails.
+    fn trailed_by(&self, end: usize, comment: &Token) -> Option<Key> {
+        if let Some(stmt) = self.ends.get(&end) {
+            return Some(*stmt);
+        }
+        self.lines
+            .iter()
+            .filter(|(line, (start, _))| *line == comment.line && *start < comment.start)
+            .max_by_key(|(_, (start, _))| *start)
+            .map(|(_, stmt)| *stmt)
+    }
+}
+
+impl Fold for Statements {
+    fn fold_stmt(&mut self, stmt: Stmt) -> Stmt {
+        let span = stmt.span;
+        self.starts.entry(span.start).or_insert(key(span));
+        self.ends.insert(span.end, key(span));
+        self.lines.push((span.line, key(span)));
+        walk_stmt(self, stmt)
+    }
+}
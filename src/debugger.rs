@@ -0,0 +1,351 @@
+use std::{
+    cell::RefCell,
+    collections::BTreeSet,
+    io::{self, BufRead, Write},
+    rc::Rc,
+};
+
+use crate::{
+    ast::{
+        expr::{Expr, ExprKind, Literal},
+        pattern::{ArmBody, Pattern, PatternKind},
+        stmt::Stmt,
+    },
+    diagnostics::DiagnosticSink,
+    environment::Environment,
+    interpreter::interpreter::{DebugHook, Interpreter},
+    lexer,
+    parser::{self, ParserOptions},
+    source_map::SourceMap,
+};
+
+const HELP: &str = "\
+Commands:
+  break <line>   pause before the statement on <line> (alias: b)
+  step           run until the next statement (alias: s)
+  continue       run until the next breakpoint (alias: c)
+  print <expr>   evaluate <expr> in the paused scope (alias: p)
+  backtrace      show the calls in progress (alias: bt)
+  help           show this message";
+
+/// Interactive debugger reading commands from `input` and reporting to `output`. It pauses
+/// before the first statement so breakpoints can be set.
+pub struct Debugger<R: BufRead, W: Write> {
+    input: R,
+    output: W,
+    breakpoints: BTreeSet<usize>,
+    stepping: bool,
+}
+
+impl<R: BufRead, W: Write> Debugger<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Self {
+            input,
+            output,
+            breakpoints: BTreeSet::new(),
+            stepping: true,
+        }
+    }
+
+    fn print(&mut self, interpreter: &mut Interpreter, code: &str) -> String {
+        let mut sources = SourceMap::new();
+        let file = sources.add("<debugger>", code);
+
+        let tokens = lexer::tokenize(code, file);
+        let mut sink = DiagnosticSink::default();
+        let options = ParserOptions::default();
+        let mut expr = match parser::parse_expression(tokens, &sources, options, &mut sink) {
+            Ok(expr) => expr,
+            Err(e) => return format!("Parser Error: {}", e),
+        };
+        bind_locals(&mut expr, &interpreter.environment());
+
+        match interpreter.evaluate(&expr) {
+            Ok(value) => value.to_string(),
+            Err(e) => {
+                interpreter.take_error_trace();
+                format!(
+                    "Interpreter Error: {}",
+                    e.message().unwrap_or("unexpected control flow")
+                )
+            }
+        }
+    }
+
+    fn backtrace(&self, interpreter: &Interpreter) -> String {
+        interpreter
+            .backtrace()
+            .iter()
+            .enumerate()
+            .map(|(depth, (function, span))| {
+                format!("#{} {} at line {}", depth, function, span.line)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl<R: BufRead, W: Write> Debugger<R, W> {
+    /// Takes commands until one resumes the script. Fails if the output can't be written.
+    fn pause(&mut self, interpreter: &mut Interpreter, stmt: &Stmt) -> io::Result<()> {
+        writeln!(
+            self.output,
+            "Paused at line {}: {}",
+            stmt.span.line,
+            summary(stmt)
+        )?;
+
+        loop {
+            write!(self.output, "(debug) ")?;
+            self.output.flush()?;
+
+            let mut command = String::new();
+            if self.input.read_line(&mut command).unwrap_or(0) == 0 {
+                // Nobody is left to give commands, so let the script run to completion.
+                self.detach();
+                return Ok(());
+            }
+
+            let command = command.trim();
+            let (name, argument) = match command.split_once(' ') {
+                Some((name, argument)) => (name, argument.trim()),
+                None => (command, ""),
+            };
+
+            let reply = match name {
+                "" => continue,
+                "break" | "b" => match argument.parse::<usize>() {
+                    Ok(line) => {
+                        self.breakpoints.insert(line);
+                        format!("Breakpoint set at line {}", line)
+                    }
+                    Err(_) => "Usage: break <line>".into(),
+                },
+                "step" | "s" => {
+                    self.stepping = true;
+                    return Ok(());
+                }
+                "continue" | "c" => {
+                    self.stepping = false;
+                    return Ok(());
+                }
+                "print" | "p" => self.print(interpreter, argument),
+                "backtrace" | "bt" => self.backtrace(interpreter),
+                "help" => HELP.into(),
+                _ => format!(
+                    "Unknown command '{}'. Type 'help' for a list of commands.",
+                    name
+                ),
+            };
+
+            writeln!(self.output, "{}", reply)?;
+        }
+    }
+
+    /// Stops pausing, for good.
+    fn detach(&mut self) {
+        self.stepping = false;
+        self.breakpoints.clear();
+    }
+}
+
+impl<R: BufRead, W: Write> DebugHook for Debugger<R, W> {
+    fn before_statement(&mut self, interpreter: &mut Interpreter, stmt: &Stmt) {
+        if !self.stepping && !self.breakpoints.contains(&stmt.span.line) {
+            return;
+        }
+
+        // With nowhere to report to, such as after the other end of a pipe is closed, the
+        // debugger has no use, so the script runs on without it.
+        if self.pause(interpreter, stmt).is_err() {
+            self.detach();
+        }
+    }
+}
+
+/// The statement on one line, cut short if it is long, as a function with its body is.
+fn summary(stmt: &Stmt) -> String {
+    const WIDTH: usize = 60;
+
+    let text = stmt.to_string();
+    match text.char_indices().nth(WIDTH) {
+        Some((end, _)) => format!("{} ...", text[..end].trim_end()),
+        None => text,
+    }
+}
+
+/// Points the variables in an expression typed at the prompt at the scopes they are bound
+/// in at the pause, since it never went through the resolver.
+fn bind_locals(expr: &mut Expr, environment: &Rc<RefCell<Environment>>) {
+    match &mut expr.kind {
+        ExprKind::Array(elements) | ExprKind::Tuple(elements) => {
+            for element in elements {
+                bind_locals(element, environment);
+            }
+        }
+        ExprKind::Map(entries) => {
+            for (key, value) in entries {
+                bind_locals(key, environment);
+                bind_locals(value, environment);
+            }
+        }
+        ExprKind::Binary(_, lhs, rhs) | ExprKind::Logical(_, lhs, rhs) => {
+            bind_locals(lhs, environment);
+            bind_locals(rhs, environment);
+        }
+        ExprKind::Call(callee, arguments) => {
+            bind_locals(callee, environment);
+            for argument in arguments {
+                bind_locals(argument, environment);
+            }
+        }
+        ExprKind::Get(object, _, _) => bind_locals(object, environment),
+        ExprKind::Index(value, index) => {
+            bind_locals(value, environment);
+            bind_locals(index, environment);
+        }
+        ExprKind::Set(object, _, value) => {
+            bind_locals(object, environment);
+            bind_locals(value, environment);
+        }
+        ExprKind::This(_, depth) => *depth = Environment::distance_to(environment, "this"),
+        ExprKind::Super(_, depth) => *depth = Environment::distance_to(environment, "super"),
+        ExprKind::Grouping(expr) | ExprKind::Unary(_, expr) => bind_locals(expr, environment),
+        ExprKind::Literal(_) => (),
+        ExprKind::Variable(name, depth) => {
+            *depth = Environment::distance_to(environment, &name.lexeme);
+        }
+        ExprKind::Assign(name, value, depth) => {
+            bind_locals(value, environment);
+            *depth = Environment::distance_to(environment, &name.lexeme);
+        }
+        ExprKind::Match(value, arms) => {
+            bind_locals(value, environment);
+
+            for arm in arms {
+                bind_pattern_values(&mut arm.pattern, environment);
+
+                // Stand in for the scope the arm will run in, so names the pattern binds are
+                // found there and everything else one scope further out.
+                let mut scope = Environment::with_enclosing(Rc::clone(environment));
+                for name in arm.pattern.bindings() {
+                    scope.define(name.lexeme.clone(), Literal::Nil);
+                }
+                let scope = Rc::new(RefCell::new(scope));
+
+                // Statements in block bodies are out of reach here, like any statement typed
+                // at the prompt would be.
+                if let ArmBody::Expr(body) = &mut arm.body {
+                    bind_locals(body, &scope);
+                }
+            }
+        }
+    }
+}
+
+/// Binds the `Color.Red` style values in a pattern, which are looked up outside the arm's
+/// scope.
+fn bind_pattern_values(pattern: &mut Pattern, environment: &Rc<RefCell<Environment>>) {
+    match &mut pattern.kind {
+        PatternKind::Value(value) => bind_locals(value, environment),
+        PatternKind::Array(elements) => {
+            for element in elements {
+                bind_pattern_values(element, environment);
+            }
+        }
+        PatternKind::Map(entries) => {
+            for (_, pattern) in entries {
+                bind_pattern_values(pattern, environment);
+            }
+        }
+        PatternKind::Wildcard | PatternKind::Literal(_) | PatternKind::Binding(_) => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Cursor, Write};
+
+    use super::*;
+    use crate::{OutputBuffer, Runner};
+
+    const SCRIPT: &str = "var x = 1;\nfun f(n) {\n  var y = n * 2;\n  return y;\n}\nprint f(x);\n";
+
+    /// Runs `SCRIPT` under a debugger typing `commands`, returning what the debugger wrote
+    /// and what the script printed.
+    fn debug(commands: &str) -> (String, String) {
+        let printed = OutputBuffer::new();
+        let interpreter = Interpreter::builder()
+            .output(Box::new(printed.clone()))
+            .build();
+        let mut runner = Runner::with_interpreter(interpreter);
+
+        let output = OutputBuffer::new();
+        runner.set_debug_hook(Box::new(Debugger::new(
+            Cursor::new(commands.to_string()),
+            output.clone(),
+        )));
+        runner.run("test.lox", SCRIPT).unwrap();
+        (output.contents(), printed.contents())
+    }
+
+    #[test]
+    fn breakpoints_pause_at_their_line() {
+        let (output, printed) = debug("break 4\ncontinue\nprint y\nbacktrace\ncontinue\n");
+        assert_eq!(
+            output,
+            "Paused at line 1: (define x 1)\n\
+             (debug) Breakpoint set at line 4\n\
+             (debug) Paused at line 4: (return (var y))\n\
+             (debug) 2\n\
+             (debug) #0 <fn f> at line 4\n#1 script at line 6\n\
+             (debug) "
+        );
+        assert_eq!(printed, "2\n");
+    }
+
+    #[test]
+    fn stepping_pauses_at_every_statement() {
+        let (output, _) = debug("s\ns\ns\np x + 1\nc\n");
+        let paused: Vec<&str> = output
+            .lines()
+            .filter_map(|line| line.split("Paused at line ").nth(1))
+            .collect();
+        assert_eq!(
+            paused,
+            [
+                "1: (define x 1)",
+                "2: (fun f (n) (define y (* (var n) 2)) (return (var y)))",
+                "6: (print (call (var f) (var x)))",
+                "3: (define y (* (var n) 2))",
+            ]
+        );
+        assert!(output.contains("(debug) 2\n"), "{}", output);
+    }
+
+    /// A writer whose reader has gone away.
+    struct Closed;
+
+    impl Write for Closed {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+    }
+
+    #[test]
+    fn a_closed_output_lets_the_script_run_on() {
+        let printed = OutputBuffer::new();
+        let interpreter = Interpreter::builder()
+            .output(Box::new(printed.clone()))
+            .build();
+        let mut runner = Runner::with_interpreter(interpreter);
+        runner.set_debug_hook(Box::new(Debugger::new(Cursor::new("s\n"), Closed)));
+
+        runner.run("test.lox", SCRIPT).unwrap();
+        assert_eq!(printed.contents(), "2\n");
+    }
+}
@@ -0,0 +1,251 @@
+//! Warnings and notes, which unlike errors don't stop a script from running.
+//!
+//! The parser and resolver report them to a `DiagnosticSink`, which drops the ones the
+//! options switch off, prints the rest as they come and keeps them for the host to inspect.
+
+use std::fmt::Write;
+
+use crate::{Diagnostic, LoxError, Severity};
+
+/// How diagnostics are written out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiagnosticFormat {
+    /// One line each, such as `Warning[code]: file:1:5 message`.
+    Plain,
+    /// Like `Plain`, with the severity coloured for a terminal.
+    Colored,
+    /// One JSON object per line, for tools.
+    Json,
+}
+
+impl DiagnosticFormat {
+    pub fn render(self, diagnostic: &Diagnostic) -> String {
+        match self {
+            DiagnosticFormat::Plain => format!(
+                "{}[{}]: {}",
+                diagnostic.severity, diagnostic.code, diagnostic
+            ),
+            DiagnosticFormat::Colored => {
+                let color = match diagnostic.severity {
+                    Severity::Note => 36,
+                    Severity::Warning => 33,
+                    Severity::Error => 31,
+                };
+                format!(
+                    "\x1b[1;{}m{}[{}]\x1b[0m: {}",
+                    color, diagnostic.severity, diagnostic.code, diagnostic
+                )
+            }
+            DiagnosticFormat::Json => {
+                let help = match &diagnostic.help {
+                    Some(help) => json_string(help),
+                    None => "null".to_string(),
+                };
+                format!(
+                    "{{\"severity\":{},\"code\":{},\"location\":{},\"line\":{},\"column\":{},\"message\":{},\"help\":{}}}",
+                    json_string(&diagnostic.severity.to_string().to_lowercase()),
+                    json_string(diagnostic.code),
+                    json_string(&diagnostic.location),
+                    diagnostic.line,
+                    diagnostic.column,
+                    json_string(&diagnostic.message),
+                    help
+                )
+            }
+        }
+    }
+}
+
+impl DiagnosticFormat {
+    /// Writes out `error` for the command line. Plain output is the error's own `Display`;
+    /// the other formats render each diagnostic it carries, and fall back to `Display` for
+    /// errors without any, such as runtime errors.
+    pub fn render_error(self, error: &LoxError) -> String {
+        match (self, error.diagnostics()) {
+            (DiagnosticFormat::Plain, _) | (_, []) => error.to_string(),
+            (format, diagnostics) => diagnostics
+                .iter()
+                .map(|diagnostic| format.render(diagnostic))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+/// Quotes `s` as a JSON string.
+fn json_string(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => write!(quoted, "\\u{:04x}", c as u32).unwrap(),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Which warnings and notes are kept and what happens to them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiagnosticOptions {
+    /// Turns warnings into errors: a script with any doesn't run.
+    pub deny_warnings: bool,
+    /// Codes of the warnings and notes to drop.
+    pub allow: Vec<String>,
+    /// Diagnostics less severe than this are dropped.
+    pub min_severity: Severity,
+    /// How to print diagnostics on stderr as they are reported, or `None` to only keep them.
+    pub print: Option<DiagnosticFormat>,
+}
+
+impl Default for DiagnosticOptions {
+    fn default() -> Self {
+        Self {
+            deny_warnings: false,
+            allow: vec![],
+            min_severity: Severity::Note,
+            print: Some(DiagnosticFormat::Plain),
+        }
+    }
+}
+
+/// Collects the warnings and notes reported while compiling and running code, following its
+/// options.
+///
+/// ```
+/// use rlox::{DiagnosticOptions, LoxError, Runner, Severity};
+///
+/// let code = "var x = 0;\nif (x = 1) print x;";
+/// let quiet = DiagnosticOptions {
+///     print: None,
+///     ..DiagnosticOptions::default()
+/// };
+///
+/// let mut runner = Runner::new();
+/// runner.set_diagnostic_options(quiet.clone());
+/// runner.run("warned.lox", code).unwrap();
+/// let warning = &runner.diagnostics().diagnostics()[0];
+/// assert_eq!(warning.severity, Severity::Warning);
+/// assert_eq!(warning.code, "assignment-condition");
+/// assert_eq!(warning.line, 2);
+///
+/// // Filtering by severity.
+/// let mut runner = Runner::new();
+/// runner.set_diagnostic_options(DiagnosticOptions {
+///     min_severity: Severity::Error,
+///     ..quiet.clone()
+/// });
+/// runner.run("filtered.lox", code).unwrap();
+/// assert_eq!(runner.diagnostics().count(Severity::Warning), 0);
+///
+/// // Suppressing by code.
+/// let mut runner = Runner::new();
+/// runner.set_diagnostic_options(DiagnosticOptions {
+///     allow: vec!["assignment-condition".to_string()],
+///     ..quiet.clone()
+/// });
+/// runner.run("allowed.lox", code).unwrap();
+/// assert!(runner.diagnostics().diagnostics().is_empty());
+///
+/// // Denying warnings keeps the script from running.
+/// let mut runner = Runner::new();
+/// runner.set_diagnostic_options(DiagnosticOptions {
+///     deny_warnings: true,
+///     ..quiet
+/// });
+/// match runner.run("denied.lox", code) {
+///     Err(e @ LoxError::DeniedWarnings(_)) => {
+///         assert_eq!(e.exit_code(), 65);
+///         assert_eq!(e.diagnostics()[0].code, "assignment-condition");
+///     }
+///     other => panic!("expected denied warnings, got {:?}", other),
+/// }
+/// assert!(runner.interpreter().get_global("x").is_none());
+/// ```
+#[derive(Debug, Default)]
+pub struct DiagnosticSink {
+    options: DiagnosticOptions,
+    diagnostics: Vec<Diagnostic>,
+    /// How many diagnostics there were when compiling the current code started.
+    checkpoint: usize,
+}
+
+impl DiagnosticSink {
+    pub fn new(options: DiagnosticOptions) -> Self {
+        Self {
+            options,
+            diagnostics: vec![],
+            checkpoint: 0,
+        }
+    }
+
+    pub fn options(&self) -> &DiagnosticOptions {
+        &self.options
+    }
+
+    /// Keeps `diagnostic` unless the options drop it, printing it if they say to. A denied
+    /// warning isn't printed here; it is reported as an error by `check_denied`.
+    pub fn report(&mut self, diagnostic: Diagnostic) {
+        if diagnostic.severity < self.options.min_severity
+            || self
+                .options
+                .allow
+                .iter()
+                .any(|code| code == diagnostic.code)
+        {
+            return;
+        }
+
+        let denied = self.options.deny_warnings && diagnostic.severity == Severity::Warning;
+        if let (Some(format), false) = (self.options.print, denied) {
+            eprintln!("{}", format.render(&diagnostic));
+        }
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Every diagnostic kept so far, in the order they were reported.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// How many of the diagnostics kept so far have `severity`.
+    pub fn count(&self, severity: Severity) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .count()
+    }
+
+    pub fn clear(&mut self) {
+        self.diagnostics.clear();
+        self.checkpoint = 0;
+    }
+
+    /// Marks the start of compiling a new piece of code, for `check_denied`.
+    pub(crate) fn start(&mut self) {
+        self.checkpoint = self.diagnostics.len();
+    }
+
+    /// Fails with the warnings reported since `start`, if warnings are denied and there are
+    /// any.
+    pub(crate) fn check_denied(&self) -> Result<(), LoxError> {
+        if !self.options.deny_warnings {
+            return Ok(());
+        }
+
+        let denied: Vec<Diagnostic> = self.diagnostics[self.checkpoint..]
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Warning)
+            .cloned()
+            .collect();
+        if denied.is_empty() {
+            Ok(())
+        } else {
+            Err(LoxError::DeniedWarnings(denied))
+        }
+    }
+}
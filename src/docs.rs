@@ -0,0 +1,79 @@
+//! The Markdown summary `--extract-docs` prints of what a script documents.
+
+use std::fmt::Write;
+
+use crate::{
+    ast::{
+        expr::ExprKind,
+        stmt::{Stmt, StmtKind},
+    },
+    comments::CommentMap,
+    lexer::Token,
+};
+
+/// Writes a section for each documented function and class declared at the top level of
+/// `stmts`, in order, under a title naming the script. A class gets one if it or any of its
+/// methods is documented, with a subsection for each documented method. The doc comments are
+/// taken from `comments`, the comments attached to `stmts`.
+pub fn markdown(name: &str, stmts: &[Stmt], comments: &CommentMap) -> String {
+    let mut out = format!("# {}\n", name);
+    let mut documented = false;
+
+    for stmt in stmts {
+        match &stmt.kind {
+            StmtKind::Function(name, params, ..) => {
+                let Some(doc) = comments.doc(stmt) else {
+                    continue;
+                };
+                section(&mut out, "##", &signature("fun ", name, params), &doc);
+                documented = true;
+            }
+            StmtKind::Class(class, superclass, members, _) => {
+                let doc = comments.doc(stmt);
+                let methods: Vec<_> = members
+                    .iter()
+                    .filter_map(|member| match &member.kind {
+                        StmtKind::Function(name, params, ..) => {
+                            Some((name, params, comments.doc(member)?))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                if doc.is_none() && methods.is_empty() {
+                    continue;
+                }
+
+                let heading = match superclass.as_ref().map(|superclass| &superclass.kind) {
+                    Some(ExprKind::Variable(superclass, _)) => {
+                        format!("class {} < {}", class.lexeme, superclass.lexeme)
+                    }
+                    _ => format!("class {}", class.lexeme),
+                };
+                section(&mut out, "##", &heading, doc.as_deref().unwrap_or_default());
+                for (name, params, doc) in methods {
+                    let prefix = format!("{}.", class.lexeme);
+                    section(&mut out, "###", &signature(&prefix, name, params), &doc);
+                }
+                documented = true;
+            }
+            _ => (),
+        }
+    }
+
+    if !documented {
+        out.push_str("\nNothing is documented.\n");
+    }
+    out
+}
+
+fn signature(prefix: &str, name: &Token, params: &[Token]) -> String {
+    let params: Vec<&str> = params.iter().map(|param| param.lexeme.as_str()).collect();
+    format!("{}{}({})", prefix, name.lexeme, params.join(", "))
+}
+
+fn section(out: &mut String, level: &str, heading: &str, doc: &str) {
+    write!(out, "\n{} `{}`\n", level, heading).unwrap();
+    if !doc.is_empty() {
+        write!(out, "\n{}\n", doc).unwrap();
+    }
+}
@@ -1,11 +1,42 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    fmt::{self, Debug},
+    rc::Rc,
+};
 
-use crate::{ast::expr::Literal, lexer::Token};
+use crate::{
+    ast::expr::Literal,
+    lexer::{Span, Token},
+    suggest,
+};
 
+/// How many scopes of a chain a debug print shows the bindings of, starting from the
+/// innermost. The scopes enclosing those are only counted, as closures stored in them can
+/// lead back to the scope being printed.
+pub const DEBUG_SCOPES: usize = 1;
+
+/// The number of environments alive, shared by every environment nested in the one that
+/// started the count.
+pub type EnvironmentCount = Rc<Cell<usize>>;
+
+/// A binding as it was before a journaled change: its value and whether it was a constant,
+/// or nothing if the name wasn't bound.
 #[derive(Debug)]
+struct JournalEntry {
+    name: String,
+    previous: Option<(Literal, bool)>,
+}
+
 pub struct Environment {
     enclosing: Option<Rc<RefCell<Environment>>>,
     values: HashMap<String, Literal>,
+    constants: HashSet<String>,
+    /// Where the bindings declared in source were declared, for error messages.
+    locations: HashMap<String, Span>,
+    count: Option<EnvironmentCount>,
+    /// Changes to this scope's bindings since `start_journal`, oldest first.
+    journal: Option<Vec<JournalEntry>>,
 }
 
 impl Environment {
@@ -13,23 +44,263 @@ impl Environment {
         Self {
             enclosing: None,
             values: HashMap::new(),
+            constants: HashSet::new(),
+            locations: HashMap::new(),
+            count: None,
+            journal: None,
+        }
+    }
+
+    /// An outermost environment that counts itself and every environment nested in it in
+    /// `count` for as long as they are alive.
+    pub fn counted(count: EnvironmentCount) -> Environment {
+        count.set(count.get() + 1);
+
+        Self {
+            enclosing: None,
+            values: HashMap::new(),
+            constants: HashSet::new(),
+            locations: HashMap::new(),
+            count: Some(count),
+            journal: None,
         }
     }
 
     pub fn with_enclosing(enclosing: Rc<RefCell<Environment>>) -> Environment {
+        let count = Self::join_count(&enclosing);
+
         Self {
             enclosing: Some(enclosing),
             values: HashMap::new(),
+            constants: HashSet::new(),
+            locations: HashMap::new(),
+            count,
+            journal: None,
+        }
+    }
+
+    /// Empties the environment so it can be reused as a fresh scope inside `enclosing`.
+    pub fn reset(&mut self, enclosing: Rc<RefCell<Environment>>) {
+        self.leave_count();
+        self.count = Self::join_count(&enclosing);
+        self.enclosing = Some(enclosing);
+        self.values.clear();
+        self.constants.clear();
+        self.locations.clear();
+    }
+
+    /// Drops every binding and the link to the enclosing scope, keeping the allocated
+    /// capacity for reuse. A cleared environment no longer counts as alive.
+    pub fn clear(&mut self) {
+        self.leave_count();
+        self.enclosing = None;
+        self.values.clear();
+        self.constants.clear();
+        self.locations.clear();
+    }
+
+    /// Adds one to the count `enclosing` is part of, if any, and returns it.
+    fn join_count(enclosing: &Rc<RefCell<Environment>>) -> Option<EnvironmentCount> {
+        let count = enclosing.borrow().count.clone()?;
+        count.set(count.get() + 1);
+        Some(count)
+    }
+
+    fn leave_count(&mut self) {
+        if let Some(count) = self.count.take() {
+            count.set(count.get() - 1);
+        }
+    }
+
+    /// Walks `distance` enclosing links out from `environment`. The resolver guarantees the
+    /// chain is at least that deep; if it isn't, the outermost environment is returned.
+    pub fn ancestor(
+        environment: &Rc<RefCell<Environment>>,
+        distance: usize,
+    ) -> Rc<RefCell<Environment>> {
+        let mut environment = Rc::clone(environment);
+
+        for _ in 0..distance {
+            let enclosing = match &environment.borrow().enclosing {
+                Some(enclosing) => Rc::clone(enclosing),
+                None => break,
+            };
+            environment = enclosing;
+        }
+
+        environment
+    }
+
+    /// Counts the scopes enclosing `environment`; the globals are at depth 0.
+    pub fn depth(environment: &Rc<RefCell<Environment>>) -> usize {
+        let mut depth = 0;
+        let mut environment = Rc::clone(environment);
+
+        loop {
+            let enclosing = match &environment.borrow().enclosing {
+                Some(enclosing) => Rc::clone(enclosing),
+                None => return depth,
+            };
+            environment = enclosing;
+            depth += 1;
+        }
+    }
+
+    /// Counts the enclosing links from `environment` to the innermost scope that binds
+    /// `name`, the same distance the resolver computes for a variable.
+    pub fn distance_to(environment: &Rc<RefCell<Environment>>, name: &str) -> Option<usize> {
+        let mut environment = Rc::clone(environment);
+        let mut distance = 0;
+
+        loop {
+            if environment.borrow().values.contains_key(name) {
+                return Some(distance);
+            }
+
+            let enclosing = match &environment.borrow().enclosing {
+                Some(enclosing) => Rc::clone(enclosing),
+                None => return None,
+            };
+            environment = enclosing;
+            distance += 1;
         }
     }
 
     pub fn define(&mut self, name: String, value: Literal) {
+        self.record(&name);
+        self.constants.remove(&name);
         self.values.insert(name, value);
     }
 
+    /// Defines `name` in the outermost scope of the chain, the globals, wherever this scope
+    /// is nested.
+    pub fn define_global(&mut self, name: String, value: Literal) {
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow_mut().define_global(name, value),
+            None => self.define(name, value),
+        }
+    }
+
+    pub fn define_const(&mut self, name: String, value: Literal) {
+        self.record(&name);
+        self.constants.insert(name.clone());
+        self.values.insert(name, value);
+    }
+
+    /// Defines the variable declared by `name`, remembering where.
+    pub fn define_at(&mut self, name: &Token, value: Literal) {
+        self.define(name.lexeme.clone(), value);
+        self.locations.insert(name.lexeme.clone(), name.span());
+    }
+
+    /// Defines the constant declared by `name`, remembering where.
+    pub fn define_const_at(&mut self, name: &Token, value: Literal) {
+        self.define_const(name.lexeme.clone(), value);
+        self.locations.insert(name.lexeme.clone(), name.span());
+    }
+
+    /// Where the binding of `name` in this scope was declared, if it came from source.
+    pub fn location(&self, name: &str) -> Option<Span> {
+        self.locations.get(name).copied()
+    }
+
+    /// The error for `name` not being bound anywhere `environment` can see, suggesting the
+    /// closest name that is, if one is near enough to be a likely typo.
+    pub fn undefined(environment: &Rc<RefCell<Environment>>, name: &str) -> String {
+        let mut scopes = vec![];
+        let mut scope = Some(Rc::clone(environment));
+        while let Some(current) = scope {
+            scope = current.borrow().enclosing.clone();
+            scopes.push(current);
+        }
+
+        let scopes: Vec<_> = scopes.iter().map(|scope| scope.borrow()).collect();
+        // Innermost scopes first, so a tie goes to the binding the code would see.
+        let mut names: Vec<&str> = vec![];
+        for scope in &scopes {
+            let mut own: Vec<&str> = scope
+                .values
+                .keys()
+                .map(String::as_str)
+                .filter(|name| !names.contains(name))
+                .collect();
+            own.sort_unstable();
+            names.extend(own);
+        }
+
+        let message = format!("Undefined variable '{}'.", name);
+        let Some(suggestion) = suggest::closest(name, names) else {
+            return message;
+        };
+        let location = scopes
+            .iter()
+            .find(|scope| scope.values.contains_key(suggestion))
+            .and_then(|scope| scope.location(suggestion));
+
+        match location {
+            Some(span) => format!(
+                "{} Did you mean '{}' (declared at line {})?",
+                message, suggestion, span.line
+            ),
+            None => format!("{} Did you mean '{}'?", message, suggestion),
+        }
+    }
+
+    /// Starts recording every definition and assignment made directly in this scope, so that
+    /// `roll_back` can undo them. Any earlier journal is dropped.
+    pub fn start_journal(&mut self) {
+        self.journal = Some(vec![]);
+    }
+
+    /// Stops recording, keeping every change made since `start_journal`.
+    pub fn commit(&mut self) {
+        self.journal = None;
+    }
+
+    /// Stops recording and undoes every change made since `start_journal`: names it defined
+    /// are removed again and the ones it changed get their old values back. Changes made
+    /// inside arrays, maps and instances are not undone.
+    pub fn roll_back(&mut self) {
+        let journal = self.journal.take().unwrap_or_default();
+
+        for entry in journal.into_iter().rev() {
+            match entry.previous {
+                Some((value, is_const)) => {
+                    if is_const {
+                        self.constants.insert(entry.name.clone());
+                    } else {
+                        self.constants.remove(&entry.name);
+                    }
+                    self.values.insert(entry.name, value);
+                }
+                None => {
+                    self.constants.remove(&entry.name);
+                    self.locations.remove(&entry.name);
+                    self.values.remove(&entry.name);
+                }
+            }
+        }
+    }
+
+    fn record(&mut self, name: &str) {
+        if let Some(journal) = &mut self.journal {
+            let previous = self
+                .values
+                .get(name)
+                .map(|value| (value.clone(), self.constants.contains(name)));
+            journal.push(JournalEntry {
+                name: name.to_string(),
+                previous,
+            });
+        }
+    }
+
     pub fn assign(&mut self, name: &str, value: Literal) -> Result<(), String> {
-        if self.values.contains_key(name) {
-            self.values.insert(name.to_string(), value).unwrap();
+        if self.constants.contains(name) {
+            Err(format!("Cannot assign to constant '{}'.", name))
+        } else if self.values.contains_key(name) {
+            self.record(name);
+            self.values.insert(name.to_string(), value);
             Ok(())
         } else {
             if let Some(enclosing) = &self.enclosing {
@@ -40,6 +311,18 @@ impl Environment {
         }
     }
 
+    /// The bindings made directly in this scope, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Literal)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// Looks `name` up in this scope only.
+    pub fn lookup(&self, name: &str) -> Option<&Literal> {
+        self.values.get(name)
+    }
+
     pub fn get(&self, name: &Token) -> Result<Literal, String> {
         match self.values.get(name.lexeme.as_str()) {
             Some(v) => Ok(v.clone().to_owned()),
@@ -50,3 +333,79 @@ impl Environment {
         }
     }
 }
+
+impl Environment {
+    /// Writes the bindings of `scopes` scopes of the chain starting here.
+    fn debug_scopes(&self, f: &mut fmt::Formatter<'_>, scopes: usize) -> fmt::Result {
+        let mut names: Vec<&String> = self.values.keys().collect();
+        names.sort();
+        let values = names
+            .into_iter()
+            .map(|name| (name, DebugValue(&self.values[name])));
+
+        let mut debug = f.debug_struct("Environment");
+        debug.field("values", &DebugMap(values));
+        match &self.enclosing {
+            Some(enclosing) if scopes > 1 => {
+                debug.field("enclosing", &DebugScopes(enclosing, scopes - 1))
+            }
+            Some(enclosing) => match Environment::depth(enclosing) + 1 {
+                1 => debug.field("enclosing", &format_args!("...(1 enclosing scope)")),
+                n => debug.field("enclosing", &format_args!("...({} enclosing scopes)", n)),
+            },
+            None => &mut debug,
+        };
+        debug.finish()
+    }
+}
+
+/// Shows the bindings of the innermost `DEBUG_SCOPES` scopes, callables by name only, so
+/// printing a closure doesn't print every scope it can reach.
+impl Debug for Environment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.debug_scopes(f, DEBUG_SCOPES)
+    }
+}
+
+struct DebugScopes<'a>(&'a Rc<RefCell<Environment>>, usize);
+
+impl Debug for DebugScopes<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.try_borrow() {
+            Ok(environment) => environment.debug_scopes(f, self.1),
+            Err(_) => f.write_str("<in use>"),
+        }
+    }
+}
+
+/// A bound value, with callables shown by name.
+struct DebugValue<'a>(&'a Literal);
+
+impl Debug for DebugValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Literal::Callable(callable) => write!(f, "{}", callable),
+            value => value.fmt(f),
+        }
+    }
+}
+
+/// Entries shown as a map in the order given.
+struct DebugMap<I>(I);
+
+impl<I, K, V> Debug for DebugMap<I>
+where
+    I: Iterator<Item = (K, V)> + Clone,
+    K: Debug,
+    V: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.0.clone()).finish()
+    }
+}
+
+impl Drop for Environment {
+    fn drop(&mut self) {
+        self.leave_count();
+    }
+}
@@ -0,0 +1,408 @@
+//! The error every way of running a script returns.
+//!
+//! Nothing is printed when a script fails; the error carries the diagnostics and its
+//! `Display` writes them the way the command-line tools report them.
+
+use std::{error::Error, fmt::Display, io};
+
+/// How serious a diagnostic is, from least to most.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Note,
+    Warning,
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Note => write!(f, "Note"),
+            Severity::Warning => write!(f, "Warning"),
+            Severity::Error => write!(f, "Error"),
+        }
+    }
+}
+
+/// A problem found in a script before it ran, or a warning or note about it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Names the kind of problem, such as `assignment-condition`. Warnings and notes can be
+    /// switched off by code.
+    pub code: &'static str,
+    /// Where the problem is, as `file:line:column`.
+    pub location: String,
+    pub line: usize,
+    /// The column on `line`, counted in characters from 1.
+    pub column: usize,
+    pub message: String,
+    /// How to fix or silence the problem, if there is an obvious way.
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(
+        code: &'static str,
+        location: String,
+        line: usize,
+        column: usize,
+        message: String,
+    ) -> Self {
+        Self {
+            severity: Severity::Error,
+            code,
+            location,
+            line,
+            column,
+            message,
+            help: None,
+        }
+    }
+
+    pub fn warning(
+        code: &'static str,
+        location: String,
+        line: usize,
+        column: usize,
+        message: String,
+    ) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(code, location, line, column, message)
+        }
+    }
+
+    pub fn note(
+        code: &'static str,
+        location: String,
+        line: usize,
+        column: usize,
+        message: String,
+    ) -> Self {
+        Self {
+            severity: Severity::Note,
+            ..Self::error(code, location, line, column, message)
+        }
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.location, self.message)?;
+        if let Some(help) = &self.help {
+            write!(f, " {}", help)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub type LexDiagnostic = Diagnostic;
+pub type ParseDiagnostic = Diagnostic;
+
+/// A call that was in progress when a runtime error was raised.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceFrame {
+    /// The function being run, or `script` for the top level.
+    pub function: String,
+    /// Where in that function the next call was made, as `file:line:column`.
+    pub location: String,
+}
+
+/// The error that stopped a running script.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuntimeDiagnostic {
+    pub message: String,
+    /// Where the error was raised, if it is known.
+    pub location: Option<String>,
+    /// The line the error was raised on, or 0 if it isn't known.
+    pub line: usize,
+    /// The column on `line` the error was raised at, or 0 if it isn't known.
+    pub column: usize,
+    /// The calls in progress, innermost first.
+    pub trace: Vec<TraceFrame>,
+}
+
+impl Display for RuntimeDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "{} {}", location, self.message)?,
+            None => write!(f, "{}", self.message)?,
+        }
+        for frame in &self.trace {
+            write!(f, "\n    in {} at {}", frame.function, frame.location)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Why a script failed. Embedders can match on the variant to tell a script that never ran
+/// from one that failed part way:
+///
+/// ```
+/// use std::{error::Error, io};
+///
+/// use rlox::{LoxError, Runner};
+///
+/// let mut runner = Runner::new();
+/// match runner.run("broken.lox", "var x = ;") {
+///     Err(LoxError::Parse(diagnostics)) => assert_eq!(diagnostics[0].line, 1),
+///     other => panic!("expected a parse error, got {:?}", other),
+/// }
+///
+/// let error = runner.run("failing.lox", "var a = 1;\nprint a + nil;").unwrap_err();
+/// assert_eq!(error.exit_code(), 70);
+/// assert_eq!(error.line(), 2);
+/// assert!(error.to_string().starts_with("Interpreter Error: failing.lox:2:"));
+///
+/// let error = LoxError::from(io::Error::new(io::ErrorKind::NotFound, "gone"));
+/// assert_eq!(error.exit_code(), 66);
+/// assert_eq!(error.source().unwrap().to_string(), "gone");
+/// ```
+#[derive(Debug)]
+pub enum LoxError {
+    /// The script couldn't be read.
+    Io(io::Error),
+    /// The script has characters that don't make up any token. All of them are listed.
+    Lex(Vec<LexDiagnostic>),
+    /// The script has syntax errors. All of them are listed.
+    Parse(Vec<ParseDiagnostic>),
+    /// The script parsed but misuses names, for example by reading a local in its own
+    /// initializer. All of the problems are listed.
+    Resolve(Vec<Diagnostic>),
+    /// The script had warnings while they were denied with
+    /// `DiagnosticOptions::deny_warnings`, so it wasn't run. All of them are listed.
+    DeniedWarnings(Vec<Diagnostic>),
+    /// The script started running and raised an error.
+    Runtime(RuntimeDiagnostic),
+}
+
+impl LoxError {
+    /// The process exit status for this error, following the BSD `sysexits.h` codes.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            LoxError::Io(_) => 66,
+            LoxError::Lex(_)
+            | LoxError::Parse(_)
+            | LoxError::Resolve(_)
+            | LoxError::DeniedWarnings(_) => 65,
+            LoxError::Runtime(_) => 70,
+        }
+    }
+
+    /// The line of the first problem, or 0 if there isn't one.
+    pub fn line(&self) -> usize {
+        match self {
+            LoxError::Io(_) => 0,
+            LoxError::Lex(diagnostics)
+            | LoxError::Parse(diagnostics)
+            | LoxError::Resolve(diagnostics)
+            | LoxError::DeniedWarnings(diagnostics) => diagnostics.first().map_or(0, |d| d.line),
+            LoxError::Runtime(diagnostic) => diagnostic.line,
+        }
+    }
+
+    /// The column of the first problem on its line, or 0 if there isn't one.
+    pub fn column(&self) -> usize {
+        match self {
+            LoxError::Io(_) => 0,
+            LoxError::Lex(diagnostics)
+            | LoxError::Parse(diagnostics)
+            | LoxError::Resolve(diagnostics)
+            | LoxError::DeniedWarnings(diagnostics) => diagnostics.first().map_or(0, |d| d.column),
+            LoxError::Runtime(diagnostic) => diagnostic.column,
+        }
+    }
+
+    /// The diagnostics found before the script ran, or none if it failed some other way.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        match self {
+            LoxError::Lex(diagnostics)
+            | LoxError::Parse(diagnostics)
+            | LoxError::Resolve(diagnostics)
+            | LoxError::DeniedWarnings(diagnostics) => diagnostics,
+            LoxError::Io(_) | LoxError::Runtime(_) => &[],
+        }
+    }
+}
+
+/// Writes each diagnostic on a line of its own, after `kind`.
+fn write_diagnostics(
+    f: &mut std::fmt::Formatter<'_>,
+    kind: &str,
+    diagnostics: &[Diagnostic],
+) -> std::fmt::Result {
+    for (i, diagnostic) in diagnostics.iter().enumerate() {
+        if i > 0 {
+            writeln!(f)?;
+        }
+        write!(f, "{} Error: {}", kind, diagnostic)?;
+    }
+
+    Ok(())
+}
+
+impl Display for LoxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoxError::Io(e) => write!(f, "{}", e),
+            LoxError::Lex(diagnostics) => write_diagnostics(f, "Lexer", diagnostics),
+            LoxError::Parse(diagnostics) => write_diagnostics(f, "Parser", diagnostics),
+            LoxError::Resolve(diagnostics) => write_diagnostics(f, "Resolver", diagnostics),
+            LoxError::DeniedWarnings(diagnostics) => {
+                for (i, diagnostic) in diagnostics.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "Denied Warning[{}]: {}", diagnostic.code, diagnostic)?;
+                }
+
+                Ok(())
+            }
+            LoxError::Runtime(diagnostic) => write!(f, "Interpreter Error: {}", diagnostic),
+        }
+    }
+}
+
+impl Error for LoxError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoxError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LoxError {
+    fn from(e: io::Error) -> Self {
+        LoxError::Io(e)
+    }
+}
+
+impl From<RuntimeDiagnostic> for LoxError {
+    fn from(diagnostic: RuntimeDiagnostic) -> Self {
+        LoxError::Runtime(diagnostic)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runtime() -> RuntimeDiagnostic {
+        RuntimeDiagnostic {
+            message: "Operands must be numbers.".into(),
+            location: Some("main.lox:3:9".into()),
+            line: 3,
+            column: 9,
+            trace: vec![
+                TraceFrame {
+                    function: "<fn area>".into(),
+                    location: "main.lox:3:3".into(),
+                },
+                TraceFrame {
+                    function: "script".into(),
+                    location: "main.lox:5:1".into(),
+                },
+            ],
+        }
+    }
+
+    fn syntax(line: usize, column: usize, message: &str) -> Diagnostic {
+        Diagnostic::error(
+            "syntax",
+            format!("main.lox:{}:{}", line, column),
+            line,
+            column,
+            message.into(),
+        )
+    }
+
+    /// Stands in for an embedder's function, to check that `?` converts.
+    fn read(result: Result<(), io::Error>) -> Result<(), LoxError> {
+        result?;
+        Ok(())
+    }
+
+    #[test]
+    fn io_errors_convert_and_keep_their_source() {
+        let error = read(Err(io::Error::new(io::ErrorKind::NotFound, "no such file"))).unwrap_err();
+
+        assert!(matches!(&error, LoxError::Io(e) if e.kind() == io::ErrorKind::NotFound));
+        assert_eq!(error.to_string(), "no such file");
+        assert_eq!(error.source().unwrap().to_string(), "no such file");
+        assert_eq!(
+            (error.exit_code(), error.line(), error.column()),
+            (66, 0, 0)
+        );
+        assert!(error.diagnostics().is_empty());
+    }
+
+    #[test]
+    fn runtime_diagnostics_convert() {
+        let error = LoxError::from(runtime());
+
+        assert!(matches!(&error, LoxError::Runtime(d) if d.message == runtime().message));
+        assert_eq!(
+            error.to_string(),
+            "Interpreter Error: main.lox:3:9 Operands must be numbers.\n    \
+             in <fn area> at main.lox:3:3\n    in script at main.lox:5:1"
+        );
+        assert!(error.source().is_none());
+        assert_eq!(
+            (error.exit_code(), error.line(), error.column()),
+            (70, 3, 9)
+        );
+    }
+
+    #[test]
+    fn every_diagnostic_is_listed() {
+        let error = LoxError::Parse(vec![
+            syntax(2, 5, "Expect expression."),
+            syntax(4, 1, "Expect ';' after value.").with_help("Add one."),
+        ]);
+
+        assert_eq!(
+            error.to_string(),
+            "Parser Error: main.lox:2:5 Expect expression.\n\
+             Parser Error: main.lox:4:1 Expect ';' after value. Add one."
+        );
+        assert_eq!(
+            (error.exit_code(), error.line(), error.column()),
+            (65, 2, 5)
+        );
+        assert_eq!(error.diagnostics().len(), 2);
+    }
+
+    #[test]
+    fn each_kind_names_itself() {
+        let diagnostics = || vec![syntax(1, 1, "Bad.")];
+
+        assert_eq!(
+            LoxError::Lex(diagnostics()).to_string(),
+            "Lexer Error: main.lox:1:1 Bad."
+        );
+        assert_eq!(
+            LoxError::Resolve(diagnostics()).to_string(),
+            "Resolver Error: main.lox:1:1 Bad."
+        );
+        let warning = Diagnostic::warning("shadowing", "main.lox:1:1".into(), 1, 1, "Bad.".into());
+        assert_eq!(
+            LoxError::DeniedWarnings(vec![warning]).to_string(),
+            "Denied Warning[shadowing]: main.lox:1:1 Bad."
+        );
+    }
+
+    #[test]
+    fn errors_box_into_dyn_error() {
+        let boxed: Box<dyn Error> = Box::new(LoxError::from(runtime()));
+        assert!(boxed.to_string().starts_with("Interpreter Error:"));
+        assert!(boxed.downcast_ref::<LoxError>().is_some());
+    }
+}
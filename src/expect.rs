@@ -0,0 +1,169 @@
+//! The `--test` mode: a script states what it should print in `// expect: value` comments,
+//! and running it checks the output against them.
+
+use std::fmt::{self, Display};
+
+use crate::{
+    lexer::{tokenize_trivia, TokenKind},
+    RuntimeDiagnostic,
+};
+
+/// A line a script says it prints, taken from a `// expect:` comment.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expectation {
+    /// The line the comment is on.
+    pub line: usize,
+    pub value: String,
+}
+
+/// The `// expect:` comments in `code`, in source order. The code is lexed rather than
+/// searched, so the same text inside a string literal doesn't count.
+pub fn expectations(code: &str) -> Vec<Expectation> {
+    tokenize_trivia(code)
+        .filter_map(|token| match token.value {
+            TokenKind::LineComment(text) => Some(Expectation {
+                line: token.line,
+                value: text.trim().strip_prefix("expect:")?.trim().to_string(),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// How one expectation fared.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    Passed,
+    /// The script printed `actual` where the expectation was.
+    Mismatch {
+        actual: String,
+    },
+    /// The script finished, or failed, before printing this far.
+    Missing,
+}
+
+/// The result of checking a script's output against its expectations.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestReport {
+    pub name: String,
+    pub results: Vec<(Expectation, Outcome)>,
+    /// Lines printed after the last expectation was met.
+    pub unexpected: Vec<String>,
+    /// The runtime error the script stopped with, if it did.
+    pub error: Option<RuntimeDiagnostic>,
+}
+
+impl TestReport {
+    /// Compares `output`, what the script named `name` printed, line by line with
+    /// `expectations`.
+    pub fn new(
+        name: &str,
+        expectations: Vec<Expectation>,
+        output: &str,
+        error: Option<RuntimeDiagnostic>,
+    ) -> Self {
+        let mut lines = output.lines();
+        let results = expectations
+            .into_iter()
+            .map(|expectation| {
+                let outcome = match lines.next() {
+                    Some(actual) if actual == expectation.value => Outcome::Passed,
+                    Some(actual) => Outcome::Mismatch {
+                        actual: actual.to_string(),
+                    },
+                    None => Outcome::Missing,
+                };
+                (expectation, outcome)
+            })
+            .collect();
+
+        Self {
+            name: name.to_string(),
+            results,
+            unexpected: lines.map(String::from).collect(),
+            error,
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+            && self.unexpected.is_empty()
+            && self
+                .results
+                .iter()
+                .all(|(_, outcome)| *outcome == Outcome::Passed)
+    }
+
+    fn failures(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|(_, outcome)| *outcome != Outcome::Passed)
+            .count()
+    }
+}
+
+impl Display for TestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}:", self.name)?;
+
+        let mut reported_error = false;
+        for (expectation, outcome) in &self.results {
+            match outcome {
+                Outcome::Passed => writeln!(
+                    f,
+                    "  ok    line {}: {}",
+                    expectation.line, expectation.value
+                )?,
+                Outcome::Mismatch { actual } => {
+                    writeln!(f, "  FAIL  line {}:", expectation.line)?;
+                    writeln!(f, "          expected: {}", expectation.value)?;
+                    writeln!(f, "            actual: {}", actual)?;
+                }
+                Outcome::Missing => {
+                    writeln!(f, "  FAIL  line {}:", expectation.line)?;
+                    writeln!(f, "          expected: {}", expectation.value)?;
+                    match &self.error {
+                        Some(error) if !reported_error => {
+                            reported_error = true;
+                            writeln!(f, "            actual: error: {}", describe(error))?;
+                        }
+                        Some(_) => writeln!(f, "            actual: not reached")?,
+                        None => writeln!(f, "            actual: nothing more was printed")?,
+                    }
+                }
+            }
+        }
+        for line in &self.unexpected {
+            writeln!(f, "  FAIL  unexpected output: {}", line)?;
+        }
+        if let Some(error) = self.error.as_ref().filter(|_| !reported_error) {
+            writeln!(
+                f,
+                "  FAIL  after the last expectation: error: {}",
+                describe(error)
+            )?;
+        }
+
+        let failures = self.failures();
+        write!(
+            f,
+            "{} of {} expectations passed",
+            self.results.len() - failures,
+            self.results.len()
+        )?;
+        match (self.unexpected.len(), &self.error) {
+            (0, None) => writeln!(f, "."),
+            (0, Some(_)) => writeln!(f, "; the script failed."),
+            (1, _) => writeln!(f, "; 1 line of output was unexpected."),
+            (n, _) => writeln!(f, "; {} lines of output were unexpected.", n),
+        }
+    }
+}
+
+/// The error's location and message, without the calls it was raised in.
+fn describe(error: &RuntimeDiagnostic) -> String {
+    match &error.location {
+        Some(location) => format!("{} {}", location, error.message),
+        None => error.message.clone(),
+    }
+}
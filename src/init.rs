@@ -0,0 +1,98 @@
+use std::{
+    env, fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use crate::runner::Runner;
+
+/// Where the REPL startup file lives: `$RLOX_INIT` if set, otherwise `.rloxrc` in the
+/// user's home directory.
+pub fn init_file_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("RLOX_INIT") {
+        return Some(PathBuf::from(path));
+    }
+
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(|home| Path::new(&home).join(".rloxrc"))
+}
+
+/// Runs the startup file at `path` in `runner` so its definitions are visible at the
+/// prompt. A missing file is skipped. Errors in the file are reported but never stop the
+/// REPL from starting.
+pub fn load_init_file(runner: &mut Runner, path: &Path) {
+    let code = match fs::read_to_string(path) {
+        Ok(code) => code,
+        Err(e) if e.kind() == ErrorKind::NotFound => return,
+        Err(e) => {
+            eprintln!("Could not read init file {}: {}", path.display(), e);
+            return;
+        }
+    };
+
+    if let Err(e) = runner.run(&path.display().to_string(), &code) {
+        eprintln!("{}", e);
+        eprintln!("Init file {} failed; see the errors above.", path.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{Interpreter, OutputBuffer};
+
+    /// A runner whose prints land in the returned buffer.
+    fn runner() -> (Runner, OutputBuffer) {
+        let output = OutputBuffer::new();
+        let interpreter = Interpreter::builder()
+            .output(Box::new(output.clone()))
+            .build();
+        (Runner::with_interpreter(interpreter), output)
+    }
+
+    /// Writes `code` to a fresh file in the temp directory.
+    fn init_file(name: &str, code: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("rlox-init-{}-{}.lox", name, std::process::id()));
+        fs::write(&path, code).unwrap();
+        path
+    }
+
+    #[test]
+    fn definitions_are_visible_at_the_prompt() {
+        let path = init_file(
+            "definitions",
+            "fun square(n) { return n * n; }\nconst greeting = \"hi\";",
+        );
+        let (mut runner, output) = runner();
+        load_init_file(&mut runner, &path);
+        runner.run_repl_line("print square(4);").unwrap();
+        runner.run_repl_line("print greeting;").unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(output.contents(), "16\nhi\n");
+    }
+
+    #[test]
+    fn a_missing_file_is_skipped() {
+        let (mut runner, output) = runner();
+        load_init_file(&mut runner, Path::new("/nonexistent/rlox/.rloxrc"));
+        runner.run_repl_line("print 1;").unwrap();
+
+        assert_eq!(output.contents(), "1\n");
+    }
+
+    #[test]
+    fn a_parse_error_still_reaches_the_prompt() {
+        let path = init_file("parse-error", "var loaded = true;\nfun broken( {");
+        let (mut runner, output) = runner();
+        load_init_file(&mut runner, &path);
+        runner.run_repl_line("print 1 + 1;").unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(output.contents(), "2\n");
+        assert!(runner.describe("loaded").is_none());
+    }
+}
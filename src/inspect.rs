@@ -0,0 +1,215 @@
+use crate::{
+    ast::{export, lower, sexpr, stmt::Stmt},
+    cli,
+    diagnostics::DiagnosticSink,
+    lexer,
+    parser::{self, ParserOptions},
+    resolver::{self, Shadowing},
+    runner,
+    source_map::SourceMap,
+    LoxError,
+};
+
+/// Every option `rlox-ast` accepts, with the text shown for it by `--help`.
+const OPTIONS: &[(&str, &str)] = &[
+    (
+        "--format <format>",
+        "Print the syntax tree as sexpr (the default), json or dot",
+    ),
+    (
+        "--tokens",
+        "Print the token stream instead of the syntax tree",
+    ),
+    (
+        "--stmt <n>",
+        "Print only the n-th top-level statement, from 1",
+    ),
+    (
+        "--desugar",
+        "Lower for-loops into while-loops before printing",
+    ),
+    (
+        "--resolve",
+        "Resolve the program and show each local's scope distance as name@N",
+    ),
+    ("-h, --help", "Print this help and exit"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Sexpr,
+    Json,
+    Dot,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Options {
+    /// The script to inspect, or `None` to read stdin.
+    pub path: Option<String>,
+    pub format: Format,
+    pub tokens: bool,
+    pub stmt: Option<usize>,
+    pub desugar: bool,
+    /// Run the resolver and show what it resolved each local to. Only the sexpr format
+    /// shows it.
+    pub resolve: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Inspect(Options),
+    Help,
+}
+
+#[derive(Debug)]
+pub enum InspectError {
+    /// The source didn't lex or parse.
+    Lox(LoxError),
+    /// `--stmt` asked for a statement the program doesn't have.
+    NoSuchStatement { requested: usize, count: usize },
+}
+
+impl InspectError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            InspectError::Lox(e) => e.exit_code(),
+            InspectError::NoSuchStatement { .. } => 64,
+        }
+    }
+}
+
+impl From<LoxError> for InspectError {
+    fn from(e: LoxError) -> Self {
+        InspectError::Lox(e)
+    }
+}
+
+pub fn usage() -> &'static str {
+    "Usage: rlox-ast [options] [script]"
+}
+
+pub fn help() -> String {
+    cli::help_text(usage(), OPTIONS)
+}
+
+/// Parses the `rlox-ast` command-line arguments, without the program name. A script of `-`
+/// means stdin, the same as giving none.
+pub fn parse_args(args: Vec<String>) -> Result<Command, String> {
+    let mut path = None;
+    let mut format = None;
+    let mut tokens = false;
+    let mut stmt = None;
+    let mut desugar = false;
+    let mut resolve = false;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--format" => {
+                format = Some(match args.next().as_deref() {
+                    Some("sexpr") => Format::Sexpr,
+                    Some("json") => Format::Json,
+                    Some("dot") => Format::Dot,
+                    Some(other) => return Err(format!("Unknown format '{}'.", other)),
+                    None => return Err("--format needs sexpr, json or dot.".into()),
+                })
+            }
+            "--tokens" => tokens = true,
+            "--desugar" => desugar = true,
+            "--resolve" => resolve = true,
+            "--stmt" => match args.next().map(|n| n.parse::<usize>()) {
+                Some(Ok(n)) if n > 0 => stmt = Some(n),
+                _ => return Err("--stmt needs a statement number, starting from 1.".into()),
+            },
+            "-" if path.is_none() => path = Some(None),
+            flag if flag.starts_with('-') => return Err(format!("Unknown option '{}'.", flag)),
+            _ if path.is_some() => return Err("Only one script can be inspected at a time.".into()),
+            _ => path = Some(Some(arg)),
+        }
+    }
+
+    if tokens && (format.is_some() || stmt.is_some() || desugar || resolve) {
+        return Err(
+            "--tokens can't be combined with --format, --stmt, --desugar or --resolve.".into(),
+        );
+    }
+    if resolve && format.is_some_and(|format| format != Format::Sexpr) {
+        return Err("--resolve only works with the sexpr format.".into());
+    }
+
+    Ok(Command::Inspect(Options {
+        path: path.flatten(),
+        format: format.unwrap_or(Format::Sexpr),
+        tokens,
+        stmt,
+        desugar,
+        resolve,
+    }))
+}
+
+/// Lexes and parses `code`, reporting problems as coming from `name`, and renders what
+/// `options` asks for.
+pub fn inspect(name: &str, code: &str, options: &Options) -> Result<String, InspectError> {
+    let mut sources = SourceMap::new();
+    let file = sources.add(name, code);
+    let tokens = runner::lex(&sources, file, lexer::DEFAULT_MAX_STRING_LITERAL)?;
+
+    if options.tokens {
+        return Ok(lexer::dump(code));
+    }
+
+    let mut sink = DiagnosticSink::default();
+    let mut stmts = parser::parse(
+        tokens.into_iter(),
+        &sources,
+        ParserOptions::default(),
+        &mut sink,
+    )?;
+    if options.desugar {
+        stmts = lower::lower_for_loops(stmts);
+    }
+    // Resolved before picking a statement, so it resolves in the context of the others.
+    if options.resolve {
+        resolver::resolve(&mut stmts, &sources, Shadowing::Ignore, &mut sink)?;
+    }
+    let stmts = match options.stmt {
+        Some(n) => match stmts.get(n - 1) {
+            Some(stmt) => std::slice::from_ref(stmt),
+            None => {
+                return Err(InspectError::NoSuchStatement {
+                    requested: n,
+                    count: stmts.len(),
+                })
+            }
+        },
+        None => &stmts[..],
+    };
+
+    if options.resolve {
+        return Ok(sexpr::to_resolved_sexpr(stmts));
+    }
+    Ok(render(stmts, options.format))
+}
+
+/// Reads a program in the textual AST format and writes it back out the way `inspect`
+/// would, for checking that a file round-trips.
+///
+/// ```
+/// use rlox::inspect;
+///
+/// let text = "(version 1)\n(print (+ 1 (var x)))\n";
+/// assert_eq!(inspect::reformat_sexpr(text).unwrap(), text);
+/// assert!(inspect::reformat_sexpr("(version 2)").is_err());
+/// ```
+pub fn reformat_sexpr(text: &str) -> Result<String, String> {
+    sexpr::from_sexpr(text).map(|stmts| sexpr::to_sexpr(&stmts))
+}
+
+fn render(stmts: &[Stmt], format: Format) -> String {
+    match format {
+        Format::Sexpr => sexpr::to_sexpr(stmts),
+        Format::Json => format!("{}\n", export::to_json(stmts)),
+        Format::Dot => export::to_dot(stmts),
+    }
+}
@@ -1,4 +1,4 @@
-use std::{cell::RefCell, fmt::Display, rc::Rc, time::SystemTime};
+use std::{cell::RefCell, fmt::Display, rc::Rc};
 
 use crate::{
     ast::{expr::Literal, stmt::Stmt},
@@ -6,32 +6,83 @@ use crate::{
     lexer::Token,
 };
 
-use dyn_clone::DynClone;
+use super::{
+    class::LoxClass,
+    format::format_template,
+    interpreter::Interpreter,
+    json, methods, pretty,
+    time::{format_time, parse_time},
+    InterpreterErrorKind,
+};
 
-use super::{interpreter::Interpreter, InterpreterErrorKind};
+#[derive(Clone, Copy, Debug)]
+pub enum Arity {
+    Exact(usize),
+    Range(usize, usize),
+    /// At least this many, with no upper limit.
+    AtLeast(usize),
+}
 
-pub trait Callable: ToString + Display + DynClone + std::fmt::Debug {
+impl Arity {
+    pub fn accepts(&self, count: usize) -> bool {
+        match *self {
+            Arity::Exact(n) => count == n,
+            Arity::Range(min, max) => min <= count && count <= max,
+            Arity::AtLeast(min) => min <= count,
+        }
+    }
+}
+
+impl Display for Arity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Arity::Exact(n) => write!(f, "{}", n),
+            Arity::Range(min, max) => write!(f, "{} to {}", min, max),
+            Arity::AtLeast(min) => write!(f, "at least {}", min),
+        }
+    }
+}
+
+pub trait Callable: ToString + Display + std::fmt::Debug {
     fn call(
         &self,
         interpreter: &mut Interpreter,
         args: &[Literal],
     ) -> Result<Literal, InterpreterErrorKind>;
-    fn arity(&self) -> usize;
-}
+    fn arity(&self) -> Arity;
 
-dyn_clone::clone_trait_object!(Callable);
+    /// The name the callable was declared or registered under.
+    fn name(&self) -> &str;
 
-impl<T: Callable> TryFrom<Literal> for (T,) {
-    type Error = String;
-    fn try_from(value: Literal) -> Result<Self, Self::Error> {
-        unimplemented!()
+    /// One line describing what the callable does, shown by `help`.
+    fn doc(&self) -> Option<&str> {
+        None
     }
+
+    /// Whether a call depends on nothing but its arguments and does nothing but return a
+    /// value, so the optimizer may make it before the script runs.
+    fn is_pure(&self) -> bool {
+        false
+    }
+}
+
+/// The argument at `index`. The arity check in `Interpreter::call_value` should rule out a
+/// missing one, but a native that disagrees with its own arity gets an error, not a panic.
+pub fn argument(args: &[Literal], index: usize) -> Result<&Literal, InterpreterErrorKind> {
+    args.get(index).ok_or_else(|| {
+        InterpreterErrorKind::Internal(format!(
+            "Argument {} is missing even though the call passed the arity check.",
+            index + 1
+        ))
+    })
 }
 
+/// Callables are shared rather than copied when they are passed around or stored.
 #[derive(Clone, Debug)]
 pub enum LoxCallable {
-    Function(LoxFunction),
-    Other(Box<dyn Callable>),
+    Function(Rc<LoxFunction>),
+    Class(Rc<LoxClass>),
+    Other(Rc<dyn Callable>),
 }
 
 impl Callable for LoxCallable {
@@ -42,65 +93,140 @@ impl Callable for LoxCallable {
     ) -> Result<Literal, InterpreterErrorKind> {
         match self {
             LoxCallable::Function(fun) => fun.call(interpreter, args),
+            LoxCallable::Class(class) => class.call(interpreter, args),
             LoxCallable::Other(fun) => fun.call(interpreter, args),
         }
     }
 
-    fn arity(&self) -> usize {
+    fn arity(&self) -> Arity {
         match self {
             LoxCallable::Function(fun) => fun.arity(),
+            LoxCallable::Class(class) => class.arity(),
             LoxCallable::Other(fun) => fun.arity(),
         }
     }
+
+    fn name(&self) -> &str {
+        match self {
+            Self::Function(fun) => fun.name(),
+            Self::Class(class) => class.name(),
+            Self::Other(fun) => fun.name(),
+        }
+    }
+
+    fn doc(&self) -> Option<&str> {
+        match self {
+            LoxCallable::Function(fun) => fun.doc(),
+            LoxCallable::Class(class) => class.doc(),
+            LoxCallable::Other(fun) => fun.doc(),
+        }
+    }
 }
 
 impl Display for LoxCallable {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Function(fun) => fun.fmt(f),
+            Self::Class(class) => class.fmt(f),
             Self::Other(fun) => fun.fmt(f),
         }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct LoxFunction {
-    name: String,
-    params: Vec<Token>,
-    body: Vec<Stmt>,
+    // Shared rather than owned, so binding a method to an instance doesn't copy its body.
+    name: Rc<str>,
+    /// The line the function is declared on.
+    line: usize,
+    params: Rc<[Token]>,
+    body: Rc<[Stmt]>,
     closure: Rc<RefCell<Environment>>,
+    /// Set for a class's `init` method, which always returns the instance.
+    is_initializer: bool,
+    /// The doc comment above the declaration.
+    doc: Option<Rc<str>>,
 }
 
 impl LoxFunction {
     pub fn new(
         name: String,
+        line: usize,
         params: Vec<Token>,
         body: Vec<Stmt>,
         closure: Rc<RefCell<Environment>>,
     ) -> Self {
         Self {
-            name,
-            params,
-            body,
+            name: name.into(),
+            line,
+            params: params.into(),
+            body: body.into(),
             closure,
+            is_initializer: false,
+            doc: None,
+        }
+    }
+
+    /// Gives the function the doc comment `help()` shows for it.
+    pub fn with_doc(self, doc: Option<&str>) -> Self {
+        Self {
+            doc: doc.map(Rc::from),
+            ..self
+        }
+    }
+
+    /// The line the function is declared on.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn is_initializer(&self) -> bool {
+        self.is_initializer
+    }
+
+    /// Marks the function as a class's `init` method.
+    pub fn initializer(self) -> Self {
+        Self {
+            is_initializer: true,
+            ..self
+        }
+    }
+
+    /// A copy of the method whose `this` is `instance`.
+    pub fn bind(&self, instance: Literal) -> LoxFunction {
+        let mut environment = Environment::with_enclosing(Rc::clone(&self.closure));
+        environment.define("this".into(), instance);
+
+        LoxFunction {
+            closure: Rc::new(RefCell::new(environment)),
+            ..self.clone()
         }
     }
 }
 
-impl Callable for LoxFunction {
-    fn call(
+impl LoxFunction {
+    /// The signature and declaration line, as printed by `help`.
+    fn describe(&self) -> String {
+        let params: Vec<&str> = self.params.iter().map(|p| p.lexeme.as_str()).collect();
+        format!(
+            "fun {}({}), declared at line {}",
+            self.name,
+            params.join(", "),
+            self.line
+        )
+    }
+
+    fn invoke(
         &self,
         interpreter: &mut Interpreter,
         args: &[Literal],
+        environment: Rc<RefCell<Environment>>,
     ) -> Result<Literal, InterpreterErrorKind> {
-        let closure = Rc::clone(&self.closure);
-        let mut environment = Environment::with_enclosing(closure);
-
-        for i in 0..self.params.len() {
-            environment.define(self.params[i].lexeme.clone(), args[i].clone());
+        for (param, arg) in self.params.iter().zip(args) {
+            environment.borrow_mut().define_at(param, arg.clone());
         }
 
-        match interpreter.execute_block(&self.body, Rc::new(RefCell::new(environment))) {
+        match interpreter.execute_block(&self.body, environment) {
             Err(InterpreterErrorKind::Return(value)) => Ok(if let Some(value) = value {
                 value
             } else {
@@ -110,9 +236,78 @@ impl Callable for LoxFunction {
             _ => Ok(Literal::Nil),
         }
     }
+}
 
-    fn arity(&self) -> usize {
-        self.params.len()
+impl Callable for LoxFunction {
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        let mut environment = interpreter.acquire_environment(Rc::clone(&self.closure))?;
+        interpreter.enter_defer_scope();
+        let mut result = self.invoke(interpreter, args, Rc::clone(&environment));
+
+        while let Err(InterpreterErrorKind::TailCall(function, args)) = result {
+            // The previous frame can be recycled unless a closure created in it still holds on.
+            if Rc::strong_count(&environment) == 1 {
+                environment.borrow_mut().reset(Rc::clone(&function.closure));
+            } else {
+                environment = interpreter.acquire_environment(Rc::clone(&function.closure))?;
+            }
+
+            interpreter.replace_frame_function(LoxCallable::Function(Rc::clone(&function)));
+            result = function.invoke(interpreter, &args, Rc::clone(&environment));
+        }
+
+        let failed = result.as_ref().is_err_and(|e| e.message().is_some());
+        if let Err(e) = interpreter.exit_defer_scope(failed) {
+            result = Err(e);
+        }
+        interpreter.release_environment(environment);
+
+        if self.is_initializer && result.is_ok() {
+            return self
+                .closure
+                .borrow()
+                .lookup("this")
+                .cloned()
+                .ok_or_else(|| InterpreterErrorKind::Internal("unbound initializer".into()));
+        }
+        result
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::Exact(self.params.len())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn doc(&self) -> Option<&str> {
+        self.doc.as_deref()
+    }
+}
+
+/// Leaves out the body and shows the closure as its `Debug` does, one scope deep, since the
+/// closure can hold the function itself.
+impl std::fmt::Debug for LoxFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let params: Vec<&str> = self.params.iter().map(|p| p.lexeme.as_str()).collect();
+        let mut debug = f.debug_struct("LoxFunction");
+        debug
+            .field("name", &self.name)
+            .field("line", &self.line)
+            .field("params", &params);
+        if self.is_initializer {
+            debug.field("is_initializer", &true);
+        }
+        match self.closure.try_borrow() {
+            Ok(closure) => debug.field("closure", &closure),
+            Err(_) => debug.field("closure", &format_args!("<in use>")),
+        };
+        debug.finish_non_exhaustive()
     }
 }
 
@@ -126,25 +321,688 @@ impl Display for LoxFunction {
 pub struct Clock;
 
 impl Callable for Clock {
-    fn arity(&self) -> usize {
-        0
+    fn arity(&self) -> Arity {
+        Arity::Exact(0)
+    }
+
+    fn name(&self) -> &str {
+        "clock"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Returns the number of seconds since the Unix epoch.")
     }
 
     fn call(
         &self,
-        _interpreter: &mut Interpreter,
+        interpreter: &mut Interpreter,
         _args: &[Literal],
     ) -> Result<Literal, InterpreterErrorKind> {
-        let time = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        Ok(Literal::Number(time as f64))
+        Ok(Literal::Number(interpreter.now()))
     }
 }
 
 impl Display for Clock {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "<native fn>")
+        write!(f, "<native fn clock>")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Random;
+
+impl Callable for Random {
+    fn arity(&self) -> Arity {
+        Arity::Exact(0)
+    }
+
+    fn name(&self) -> &str {
+        "random"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Returns a random number from 0 up to, but not including, 1.")
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        _args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        Ok(Literal::Number(interpreter.random()))
+    }
+}
+
+impl Display for Random {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn random>")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ReadLine;
+
+impl Callable for ReadLine {
+    fn arity(&self) -> Arity {
+        Arity::Exact(0)
+    }
+
+    fn name(&self) -> &str {
+        "readLine"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Reads a line of input without its line ending, or returns nil at the end.")
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        _args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        Ok(interpreter
+            .read_line()
+            .map_or(Literal::Nil, |line| Literal::String(line.into())))
+    }
+}
+
+impl Display for ReadLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn readLine>")
+    }
+}
+
+/// Prints a description of its argument: the signature of a function, the arity and doc line
+/// of a native, or the type and value of anything else.
+#[derive(Clone, Debug)]
+pub struct Help;
+
+impl Callable for Help {
+    fn arity(&self) -> Arity {
+        Arity::Exact(1)
+    }
+
+    fn name(&self) -> &str {
+        "help"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Describes a value: what a function takes, or the type of anything else.")
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        interpreter.print(&describe(argument(args, 0)?))?;
+        Ok(Literal::Nil)
+    }
+}
+
+/// What `help` prints for `value`. Classes list the methods their instances can call.
+/// Functions and classes declared in Lox are followed by their doc comment, which can run
+/// over several lines.
+pub(crate) fn describe(value: &Literal) -> String {
+    let (description, doc) = match value {
+        Literal::Callable(LoxCallable::Function(fun)) => (fun.describe(), fun.doc()),
+        Literal::Callable(LoxCallable::Class(class)) => {
+            let mut description = format!("{} (arity {})", class, class.arity());
+            let methods = class.method_names();
+            if !methods.is_empty() {
+                description = format!("{}, methods: {}", description, methods.join(", "));
+            }
+            (description, class.doc())
+        }
+        Literal::Callable(native) => {
+            let mut description = format!("{} (arity {})", native, native.arity());
+            if let Some(doc) = native.doc() {
+                description = format!("{}: {}", description, doc);
+            }
+            return description;
+        }
+        value => return format!("{} {}", value.type_name(), value),
+    };
+
+    match doc {
+        Some(doc) => format!("{}\n{}", description, doc),
+        None => description,
+    }
+}
+
+impl Display for Help {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn help>")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PrettyPrint;
+
+impl Callable for PrettyPrint {
+    fn arity(&self) -> Arity {
+        Arity::Range(1, 2)
+    }
+
+    fn name(&self) -> &str {
+        "prettyPrint"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Prints a value with nested arrays and maps indented, down to an optional depth.")
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        let max_depth = match args.get(1) {
+            None => pretty::DEFAULT_MAX_DEPTH,
+            Some(_) => methods::index_arg("prettyPrint", args, 1, usize::MAX)?,
+        };
+
+        interpreter.print(&pretty::pretty(argument(args, 0)?, max_depth))?;
+        Ok(Literal::Nil)
+    }
+}
+
+impl Display for PrettyPrint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn prettyPrint>")
+    }
+}
+
+/// The template and values of a `format()` or `printf()` call, formatted.
+fn format_arguments(args: &[Literal]) -> Result<String, InterpreterErrorKind> {
+    let template = match argument(args, 0)? {
+        Literal::String(template) => template,
+        other => {
+            return Err(InterpreterErrorKind::General(format!(
+                "The format template must be a String, not a {}.",
+                other.type_name()
+            )))
+        }
+    };
+
+    format_template(template, &args[1..]).map_err(InterpreterErrorKind::General)
+}
+
+#[derive(Clone, Debug)]
+pub struct Format;
+
+impl Callable for Format {
+    fn is_pure(&self) -> bool {
+        true
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::AtLeast(1)
+    }
+
+    fn name(&self) -> &str {
+        "format"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Fills the {0}, {} and {0:.2} placeholders of a template with the other arguments.")
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        Ok(Literal::String(format_arguments(args)?.into()))
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn format>")
+    }
+}
+
+/// `format()` followed by `print`.
+#[derive(Clone, Debug)]
+pub struct Printf;
+
+impl Callable for Printf {
+    fn arity(&self) -> Arity {
+        Arity::AtLeast(1)
+    }
+
+    fn name(&self) -> &str {
+        "printf"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Prints a template with its placeholders filled in, like format().")
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        let text = format_arguments(args)?;
+        interpreter.print(&text)?;
+        Ok(Literal::Nil)
+    }
+}
+
+impl Display for Printf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn printf>")
+    }
+}
+
+/// The `print` of the dialect where it is a function: its arguments separated by spaces,
+/// then a newline.
+#[derive(Clone, Debug)]
+pub struct PrintNative;
+
+impl Callable for PrintNative {
+    fn arity(&self) -> Arity {
+        Arity::AtLeast(0)
+    }
+
+    fn name(&self) -> &str {
+        "print"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Prints its arguments separated by spaces, then ends the line.")
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        let line: Vec<String> = args.iter().map(Literal::to_string).collect();
+        interpreter.print(&line.join(" "))?;
+        Ok(Literal::Nil)
+    }
+}
+
+impl Display for PrintNative {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn print>")
+    }
+}
+
+/// `print` without the newline, for building a line up piece by piece.
+#[derive(Clone, Debug)]
+pub struct WriteNative;
+
+impl Callable for WriteNative {
+    fn arity(&self) -> Arity {
+        Arity::Exact(1)
+    }
+
+    fn name(&self) -> &str {
+        "write"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Prints a value without ending the line.")
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        interpreter.write(argument(args, 0)?)?;
+        Ok(Literal::Nil)
+    }
+}
+
+impl Display for WriteNative {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn write>")
+    }
+}
+
+/// Concatenates an array of strings into one string in a single pass, instead of the new
+/// string per piece that `s = s + piece` in a loop allocates.
+#[derive(Clone, Debug)]
+pub struct Join;
+
+impl Callable for Join {
+    fn is_pure(&self) -> bool {
+        true
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::Exact(2)
+    }
+
+    fn name(&self) -> &str {
+        "join"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Joins an array of strings into one string, with a separator between them.")
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        let elements = match argument(args, 0)? {
+            Literal::Array(elements) => elements.borrow(),
+            other => {
+                return Err(InterpreterErrorKind::General(format!(
+                    "join() needs an Array to join, not a {}.",
+                    other.type_name()
+                )))
+            }
+        };
+        let separator = match argument(args, 1)? {
+            Literal::String(separator) => separator,
+            other => {
+                return Err(InterpreterErrorKind::General(format!(
+                    "join() needs a String separator, not a {}.",
+                    other.type_name()
+                )))
+            }
+        };
+
+        let mut pieces = Vec::with_capacity(elements.len());
+        for (index, element) in elements.iter().enumerate() {
+            match element {
+                Literal::String(piece) => pieces.push(piece),
+                other => {
+                    return Err(InterpreterErrorKind::General(format!(
+                        "join() can only join Strings, but element {} is a {}.",
+                        index,
+                        other.type_name()
+                    )))
+                }
+            }
+        }
+
+        let length = pieces.iter().map(|piece| piece.len()).sum::<usize>()
+            + separator.len() * pieces.len().saturating_sub(1);
+        let mut joined = String::with_capacity(length);
+        for (index, piece) in pieces.into_iter().enumerate() {
+            if index > 0 {
+                joined.push_str(separator);
+            }
+            joined.push_str(piece);
+        }
+
+        Ok(Literal::String(joined.into()))
+    }
+}
+
+impl Display for Join {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn join>")
+    }
+}
+
+/// Splits a string into its characters. Characters are Unicode scalar values, the same unit
+/// `len()` counts, so the array always has `s.len()` elements.
+#[derive(Clone, Debug)]
+pub struct Chars;
+
+impl Callable for Chars {
+    fn is_pure(&self) -> bool {
+        true
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::Exact(1)
+    }
+
+    fn name(&self) -> &str {
+        "chars"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Returns the characters of a string as an array of one-character strings.")
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        let s = match argument(args, 0)? {
+            Literal::String(s) => s,
+            other => {
+                return Err(InterpreterErrorKind::General(format!(
+                    "chars() needs a String, not a {}.",
+                    other.type_name()
+                )))
+            }
+        };
+        interpreter
+            .limits
+            .check_collection("array", s.chars().count())?;
+
+        let chars: Vec<Literal> = s.chars().map(|c| Literal::from(c.to_string())).collect();
+        Ok(chars.into())
+    }
+}
+
+impl Display for Chars {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn chars>")
+    }
+}
+
+/// Milliseconds since the Unix epoch, from the same time source as `clock()`.
+#[derive(Clone, Debug)]
+pub struct Now;
+
+impl Callable for Now {
+    fn arity(&self) -> Arity {
+        Arity::Exact(0)
+    }
+
+    fn name(&self) -> &str {
+        "now"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Returns the number of milliseconds since the Unix epoch.")
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        _args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        Ok(Literal::Number(interpreter.now_millis()))
+    }
+}
+
+impl Display for Now {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn now>")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FormatTime;
+
+impl Callable for FormatTime {
+    fn arity(&self) -> Arity {
+        Arity::Exact(2)
+    }
+
+    fn name(&self) -> &str {
+        "formatTime"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Formats epoch milliseconds as UTC with a pattern of %Y, %m, %d, %H, %M and %S.")
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        let millis = match argument(args, 0)? {
+            Literal::Number(millis) => *millis,
+            other => {
+                return Err(InterpreterErrorKind::General(format!(
+                    "formatTime() needs a Number of milliseconds, not a {}.",
+                    other.type_name()
+                )))
+            }
+        };
+        let pattern = pattern_argument("formatTime", args)?;
+
+        format_time(millis, pattern)
+            .map(|formatted| Literal::String(formatted.into()))
+            .map_err(InterpreterErrorKind::General)
+    }
+}
+
+impl Display for FormatTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn formatTime>")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ParseTime;
+
+impl Callable for ParseTime {
+    fn arity(&self) -> Arity {
+        Arity::Exact(2)
+    }
+
+    fn name(&self) -> &str {
+        "parseTime"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some(
+            "Reads a UTC time written in a formatTime() pattern, or returns nil if it doesn't fit.",
+        )
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        let text = match argument(args, 0)? {
+            Literal::String(text) => text,
+            other => {
+                return Err(InterpreterErrorKind::General(format!(
+                    "parseTime() needs a String to parse, not a {}.",
+                    other.type_name()
+                )))
+            }
+        };
+        let pattern = pattern_argument("parseTime", args)?;
+
+        parse_time(text, pattern)
+            .map(|millis| millis.map_or(Literal::Nil, Literal::Number))
+            .map_err(InterpreterErrorKind::General)
+    }
+}
+
+impl Display for ParseTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn parseTime>")
+    }
+}
+
+/// The pattern passed as the second argument to `formatTime()` or `parseTime()`.
+fn pattern_argument<'a>(
+    native: &str,
+    args: &'a [Literal],
+) -> Result<&'a str, InterpreterErrorKind> {
+    match argument(args, 1)? {
+        Literal::String(pattern) => Ok(pattern),
+        other => Err(InterpreterErrorKind::General(format!(
+            "{}() needs a String pattern, not a {}.",
+            native,
+            other.type_name()
+        ))),
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct JsonEncode;
+
+impl Callable for JsonEncode {
+    fn is_pure(&self) -> bool {
+        true
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::Exact(1)
+    }
+
+    fn name(&self) -> &str {
+        "jsonEncode"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Encodes a value made of numbers, strings, booleans, nil, arrays and maps as JSON.")
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        json::encode(argument(args, 0)?)
+            .map(|json| Literal::String(json.into()))
+            .map_err(InterpreterErrorKind::General)
+    }
+}
+
+impl Display for JsonEncode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn jsonEncode>")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct JsonDecode;
+
+impl Callable for JsonDecode {
+    fn arity(&self) -> Arity {
+        Arity::Exact(1)
+    }
+
+    fn name(&self) -> &str {
+        "jsonDecode"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Decodes JSON text into numbers, strings, booleans, nil, arrays and maps.")
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        match argument(args, 0)? {
+            Literal::String(text) => json::decode(text).map_err(InterpreterErrorKind::General),
+            other => Err(InterpreterErrorKind::General(format!(
+                "jsonDecode() needs a String of JSON, not a {}.",
+                other.type_name()
+            ))),
+        }
+    }
+}
+
+impl Display for JsonDecode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn jsonDecode>")
     }
 }
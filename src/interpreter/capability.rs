@@ -0,0 +1,141 @@
+//! The capabilities a script may be granted and the natives each one brings.
+//!
+//! Every native that reaches beyond the interpreter is registered here and nowhere else, so
+//! a preset such as `Interpreter::sandboxed()` can enumerate them and leave them out.
+
+use std::{fmt::Display, rc::Rc};
+
+use super::callable::{Callable, Clock, Now, ReadLine};
+
+/// A way for scripts to reach the world outside the interpreter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Capability {
+    /// `clock()` and `now()`.
+    Clock,
+    /// `readLine()`.
+    Stdin,
+    /// `readFile()`.
+    Filesystem,
+    /// `getEnv()`.
+    Environment,
+    /// `fetch()` and `fetchPost()`. They also need the `http` feature.
+    Network,
+    /// `exec()`.
+    Processes,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 6] = [
+        Capability::Clock,
+        Capability::Stdin,
+        Capability::Filesystem,
+        Capability::Environment,
+        Capability::Network,
+        Capability::Processes,
+    ];
+
+    /// The short name scripts and the command line know the capability by.
+    pub fn name(self) -> &'static str {
+        match self {
+            Capability::Clock => "clock",
+            Capability::Stdin => "stdin",
+            Capability::Filesystem => "fs",
+            Capability::Environment => "env",
+            Capability::Network => "net",
+            Capability::Processes => "run",
+        }
+    }
+
+    /// The names of the natives the capability defines. Network access has none in builds
+    /// without the `http` feature.
+    pub fn natives(self) -> Vec<&'static str> {
+        self.registry().into_iter().map(|(name, _)| name).collect()
+    }
+
+    /// The natives the capability defines, by the global names they are defined as.
+    pub(crate) fn registry(self) -> Vec<(&'static str, Rc<dyn Callable>)> {
+        match self {
+            Capability::Clock => vec![("clock", Rc::new(Clock)), ("now", Rc::new(Now))],
+            Capability::Stdin => vec![("readLine", Rc::new(ReadLine))],
+            Capability::Filesystem => vec![("readFile", Rc::new(super::system::ReadFile))],
+            Capability::Environment => vec![("getEnv", Rc::new(super::system::GetEnv))],
+            #[cfg(feature = "http")]
+            Capability::Network => vec![
+                ("fetch", Rc::new(super::http::Fetch)),
+                ("fetchPost", Rc::new(super::http::FetchPost)),
+            ],
+            #[cfg(not(feature = "http"))]
+            Capability::Network => vec![],
+            Capability::Processes => vec![("exec", Rc::new(super::process::Exec))],
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+impl Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The capabilities an interpreter grants. The default is what interpreters have always had:
+/// the clock and stdin, and nothing that reaches files, the environment, the network or
+/// other programs.
+///
+/// ```
+/// use rlox::{Capability, CapabilitySet};
+///
+/// let granted = CapabilitySet::default().with(Capability::Filesystem);
+/// assert!(granted.contains(Capability::Clock));
+/// assert!(granted.contains(Capability::Filesystem));
+/// assert!(!granted.without(Capability::Clock).contains(Capability::Clock));
+/// assert_eq!(CapabilitySet::none().iter().count(), 0);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CapabilitySet {
+    bits: u8,
+}
+
+impl CapabilitySet {
+    pub const fn none() -> Self {
+        Self { bits: 0 }
+    }
+
+    pub fn all() -> Self {
+        Capability::ALL
+            .into_iter()
+            .fold(Self::none(), |set, capability| set.with(capability))
+    }
+
+    pub fn with(self, capability: Capability) -> Self {
+        Self {
+            bits: self.bits | capability.bit(),
+        }
+    }
+
+    pub fn without(self, capability: Capability) -> Self {
+        Self {
+            bits: self.bits & !capability.bit(),
+        }
+    }
+
+    pub fn contains(self, capability: Capability) -> bool {
+        self.bits & capability.bit() != 0
+    }
+
+    /// The capabilities in the set, in the order of `Capability::ALL`.
+    pub fn iter(self) -> impl Iterator<Item = Capability> {
+        Capability::ALL
+            .into_iter()
+            .filter(move |capability| self.contains(*capability))
+    }
+}
+
+impl Default for CapabilitySet {
+    fn default() -> Self {
+        Self::none().with(Capability::Clock).with(Capability::Stdin)
+    }
+}
@@ -0,0 +1,237 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt::Display,
+    rc::{Rc, Weak},
+};
+
+use crate::{
+    ast::expr::{Expr, Literal},
+    environment::Environment,
+};
+
+use super::{
+    callable::{Arity, Callable, LoxCallable, LoxFunction},
+    interpreter::Interpreter,
+    map::LoxMap,
+    InterpreterErrorKind,
+};
+
+#[derive(Debug)]
+pub struct LoxClass {
+    pub name: String,
+    pub superclass: Option<Rc<LoxClass>>,
+    /// The methods declared in the class body, in the order they were declared, followed by
+    /// the ones `extend` added.
+    methods: RefCell<Vec<(String, Rc<LoxFunction>)>>,
+    /// The fields declared in the class body, in the order they were declared.
+    fields: Vec<FieldDeclaration>,
+    /// The doc comment above the class declaration.
+    doc: Option<String>,
+}
+
+/// A `var` in a class body, declaring a field every instance of the class starts out with.
+#[derive(Debug)]
+pub struct FieldDeclaration {
+    pub name: String,
+    /// Evaluated for each new instance, in `closure` with `this` bound to the instance. A
+    /// field without one starts out as nil.
+    pub initializer: Option<Expr>,
+    pub closure: Rc<RefCell<Environment>>,
+}
+
+impl LoxClass {
+    pub fn new(
+        name: String,
+        superclass: Option<Rc<LoxClass>>,
+        methods: Vec<(String, Rc<LoxFunction>)>,
+        fields: Vec<FieldDeclaration>,
+    ) -> Self {
+        Self {
+            name,
+            superclass,
+            methods: RefCell::new(methods),
+            fields,
+            doc: None,
+        }
+    }
+
+    /// Gives the class the doc comment `help()` shows for it.
+    pub fn with_doc(self, doc: Option<&str>) -> Self {
+        Self {
+            doc: doc.map(String::from),
+            ..self
+        }
+    }
+
+    /// The fields declared in this class's own body; the superclass declares its own.
+    pub fn fields(&self) -> &[FieldDeclaration] {
+        &self.fields
+    }
+
+    /// Adds `method` to the class, or replaces the one of the same name declared on it. Every
+    /// instance sees the change, as does every subclass that doesn't override the method.
+    /// Returns the method replaced, if any.
+    pub fn add_method(&self, name: String, method: Rc<LoxFunction>) -> Option<Rc<LoxFunction>> {
+        let mut methods = self.methods.borrow_mut();
+        match methods.iter_mut().find(|(existing, _)| *existing == name) {
+            Some((_, existing)) => Some(std::mem::replace(existing, method)),
+            None => {
+                methods.push((name, method));
+                None
+            }
+        }
+    }
+
+    /// Looks `name` up among this class's methods, then its superclass's.
+    pub fn find_method(&self, name: &str) -> Option<Rc<LoxFunction>> {
+        let methods = self.methods.borrow();
+        match methods.iter().find(|(method, _)| method == name) {
+            Some((_, method)) => Some(Rc::clone(method)),
+            None => self
+                .superclass
+                .as_ref()
+                .and_then(|superclass| superclass.find_method(name)),
+        }
+    }
+
+    /// The names of every method an instance can call, this class's own in declaration order
+    /// followed by the inherited ones it doesn't override.
+    pub fn method_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .methods
+            .borrow()
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        if let Some(superclass) = &self.superclass {
+            for name in superclass.method_names() {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+
+        names
+    }
+}
+
+/// Calling a class makes an instance of it, gives it the fields the class declares and then
+/// runs `init` on it, if the class has one.
+impl Callable for Rc<LoxClass> {
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        let instance = Literal::Instance(Rc::new(RefCell::new(LoxInstance::new(Rc::clone(self)))));
+        interpreter.initialize_fields(self, &instance)?;
+
+        if let Some(initializer) = interpreter.method_cache.find_method(self, "init") {
+            let initializer = LoxCallable::Function(Rc::new(initializer.bind(instance.clone())));
+            interpreter.call_value(&initializer, args)?;
+        }
+
+        Ok(instance)
+    }
+
+    fn arity(&self) -> Arity {
+        self.find_method("init")
+            .map_or(Arity::Exact(0), |initializer| initializer.arity())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn doc(&self) -> Option<&str> {
+        self.doc.as_deref()
+    }
+}
+
+impl Display for LoxClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[derive(Debug)]
+pub struct LoxInstance {
+    pub class: Rc<LoxClass>,
+    /// Fields in the order they were first set.
+    pub fields: LoxMap<String>,
+}
+
+impl LoxInstance {
+    pub fn new(class: Rc<LoxClass>) -> Self {
+        Self {
+            class,
+            fields: LoxMap::new(),
+        }
+    }
+}
+
+/// The methods classes have been asked for, so a method called over and over isn't searched
+/// for through the superclass chain every time. Has to be cleared whenever a class gains or
+/// replaces a method, since its subclasses may have cached what it had before.
+#[derive(Debug, Default)]
+pub struct MethodCache {
+    /// By the address of the class the methods were looked up on.
+    classes: HashMap<*const LoxClass, CachedMethods>,
+}
+
+#[derive(Debug)]
+struct CachedMethods {
+    /// Keeps the class's address from being reused by another class while the entry exists.
+    class: Weak<LoxClass>,
+    methods: HashMap<String, Rc<LoxFunction>>,
+}
+
+impl MethodCache {
+    /// Looks `name` up like `LoxClass::find_method`, remembering what it found.
+    pub fn find_method(&mut self, class: &Rc<LoxClass>, name: &str) -> Option<Rc<LoxFunction>> {
+        if let Some(cached) = self.classes.get(&Rc::as_ptr(class)) {
+            if let Some(method) = cached.methods.get(name) {
+                return Some(Rc::clone(method));
+            }
+        } else {
+            // A class is new to the cache far less often than a method is looked up, so this
+            // is where the entries of classes that have since been dropped are let go of.
+            self.classes
+                .retain(|_, cached| cached.class.strong_count() > 0);
+            let cached = CachedMethods {
+                class: Rc::downgrade(class),
+                methods: HashMap::new(),
+            };
+            self.classes.insert(Rc::as_ptr(class), cached);
+        }
+
+        let method = class.find_method(name)?;
+        if let Some(cached) = self.classes.get_mut(&Rc::as_ptr(class)) {
+            cached.methods.insert(name.to_string(), Rc::clone(&method));
+        }
+        Some(method)
+    }
+
+    pub fn clear(&mut self) {
+        self.classes.clear();
+    }
+}
+
+/// Reads the property `name` of `instance`: a field if it has one, otherwise a method bound to
+/// it.
+pub fn get_property(
+    instance: &Rc<RefCell<LoxInstance>>,
+    name: &str,
+    methods: &mut MethodCache,
+) -> Option<Literal> {
+    if let Some(value) = instance.borrow().fields.get(name) {
+        return Some(value.clone());
+    }
+
+    let class = Rc::clone(&instance.borrow().class);
+    let method = methods.find_method(&class, name)?;
+    let bound = method.bind(Literal::Instance(Rc::clone(instance)));
+    Some(Literal::Callable(LoxCallable::Function(Rc::new(bound))))
+}
@@ -0,0 +1,52 @@
+use std::rc::Rc;
+
+/// The namespace an `enum` declaration creates, holding its variants in declaration order.
+#[derive(Debug)]
+pub struct LoxEnum {
+    pub name: Rc<str>,
+    variants: Vec<Rc<EnumValue>>,
+}
+
+/// One variant of an enum. Variants are compared by identity, so variants with the same name
+/// in different enums, or in two runs of the same declaration, are different values.
+#[derive(Debug)]
+pub struct EnumValue {
+    pub enum_name: Rc<str>,
+    pub name: String,
+    /// The variant's position in its declaration, from 0.
+    pub ordinal: usize,
+}
+
+impl LoxEnum {
+    pub fn new(name: &str, variants: &[&str]) -> LoxEnum {
+        let name: Rc<str> = name.into();
+        let variants = variants
+            .iter()
+            .enumerate()
+            .map(|(ordinal, variant)| {
+                Rc::new(EnumValue {
+                    enum_name: Rc::clone(&name),
+                    name: variant.to_string(),
+                    ordinal,
+                })
+            })
+            .collect();
+
+        LoxEnum { name, variants }
+    }
+
+    pub fn variant(&self, name: &str) -> Option<&Rc<EnumValue>> {
+        self.variants.iter().find(|variant| variant.name == name)
+    }
+
+    pub fn variants(&self) -> &[Rc<EnumValue>] {
+        &self.variants
+    }
+
+    /// Tells whether `value` is one of this enum's own variants.
+    pub fn contains(&self, value: &Rc<EnumValue>) -> bool {
+        self.variants
+            .iter()
+            .any(|variant| Rc::ptr_eq(variant, value))
+    }
+}
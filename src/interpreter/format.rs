@@ -0,0 +1,256 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::ast::expr::Literal;
+
+use super::methods::MAX_DIGITS;
+
+/// Fills in the placeholders of a `format()` template.
+///
+/// `{0}` is replaced by the first argument, printed the way `print` would print it, and `{}`
+/// by the argument after the last one `{}` used. `{{` and `}}` stand for literal braces. A
+/// placeholder may end in a spec: `{0:.2}` prints a number with two decimal places, up to
+/// `MAX_DIGITS` of them.
+///
+/// The error message names both the template and the placeholder that could not be filled.
+pub fn format_template(template: &str, args: &[Literal]) -> Result<String, String> {
+    let mut output = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+    let mut next_auto = 0;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                output.push('{');
+            }
+            '{' => {
+                let Some(placeholder) = read_placeholder(&mut chars) else {
+                    return Err(error(template, "{", "it is never closed"));
+                };
+                let text = format!("{{{}}}", placeholder);
+                let fail = |reason: &str| error(template, &text, reason);
+
+                let (index, spec) = placeholder.split_once(':').unwrap_or((&placeholder, ""));
+                let index = if index.is_empty() {
+                    next_auto += 1;
+                    next_auto - 1
+                } else {
+                    index
+                        .parse::<usize>()
+                        .map_err(|_| fail("the index is not a whole number"))?
+                };
+                let value = args.get(index).ok_or_else(|| {
+                    fail(&format!(
+                        "there {} only {} argument{}",
+                        if args.len() == 1 { "is" } else { "are" },
+                        args.len(),
+                        if args.len() == 1 { "" } else { "s" }
+                    ))
+                })?;
+
+                output.push_str(&format_value(value, spec).map_err(|reason| fail(&reason))?);
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                output.push('}');
+            }
+            '}' => return Err(error(template, "}", "it has no matching '{'; use '}}'")),
+            c => output.push(c),
+        }
+    }
+
+    Ok(output)
+}
+
+/// The text between a `{` that has already been consumed and its closing `}`, or `None` when
+/// the template ends first.
+fn read_placeholder(chars: &mut Peekable<Chars>) -> Option<String> {
+    let mut placeholder = String::new();
+    for c in chars.by_ref() {
+        if c == '}' {
+            return Some(placeholder);
+        }
+        placeholder.push(c);
+    }
+    None
+}
+
+fn format_value(value: &Literal, spec: &str) -> Result<String, String> {
+    if spec.is_empty() {
+        return Ok(value.to_string());
+    }
+
+    let precision = spec
+        .strip_prefix('.')
+        .and_then(|digits| digits.parse::<usize>().ok())
+        .ok_or_else(|| format!("'{}' is not a spec; only '.N' decimal places are", spec))?;
+    if precision > MAX_DIGITS {
+        return Err(format!(
+            "{} decimal places is too many; the limit is {}",
+            precision, MAX_DIGITS
+        ));
+    }
+
+    match value {
+        Literal::Number(n) => Ok(format!("{:.*}", precision, n)),
+        other => Err(format!(
+            "decimal places need a Number, not a {}",
+            other.type_name()
+        )),
+    }
+}
+
+fn error(template: &str, placeholder: &str, reason: &str) -> String {
+    format!(
+        "Cannot fill '{}' in format template \"{}\": {}.",
+        placeholder, template, reason
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(template: &str, args: &[Literal]) -> Result<String, String> {
+        format_template(template, args)
+    }
+
+    fn n(value: f64) -> Literal {
+        Literal::Number(value)
+    }
+
+    fn s(value: &str) -> Literal {
+        Literal::String(value.into())
+    }
+
+    #[test]
+    fn templates_without_placeholders_are_copied() {
+        assert_eq!(fill("", &[]), Ok(String::new()));
+        assert_eq!(fill("plain text", &[n(1.0)]), Ok("plain text".into()));
+        assert_eq!(
+            fill("caf\u{e9} \u{1f600}", &[]),
+            Ok("caf\u{e9} \u{1f600}".into())
+        );
+    }
+
+    #[test]
+    fn positional_placeholders() {
+        assert_eq!(
+            fill("point ({0}, {1})", &[n(1.0), n(-2.5)]),
+            Ok("point (1, -2.5)".into())
+        );
+        assert_eq!(fill("{1}{0}{1}", &[s("a"), s("b")]), Ok("bab".into()));
+    }
+
+    #[test]
+    fn automatic_placeholders_count_only_themselves() {
+        assert_eq!(
+            fill("{} {} {0} {}", &[s("a"), s("b"), s("c")]),
+            Ok("a b a c".into())
+        );
+    }
+
+    #[test]
+    fn values_print_the_way_print_does() {
+        assert_eq!(
+            fill(
+                "{} {} {} {}",
+                &[Literal::Nil, Literal::Bool(true), n(3.0), n(0.1)]
+            ),
+            Ok("nil true 3 0.1".into())
+        );
+    }
+
+    #[test]
+    fn doubled_braces_are_literal() {
+        assert_eq!(
+            fill("{{}} {{0}} {{{0}}}", &[n(7.0)]),
+            Ok("{} {0} {7}".into())
+        );
+    }
+
+    #[test]
+    fn decimal_places() {
+        assert_eq!(fill("{:.2}", &[n(1.23456)]), Ok("1.23".into()));
+        assert_eq!(fill("{0:.0}", &[n(2.5)]), Ok("2".into()));
+        assert_eq!(fill("{0:.3}", &[n(-1.0)]), Ok("-1.000".into()));
+        assert_eq!(fill("{:.1}", &[n(f64::INFINITY)]), Ok("inf".into()));
+        assert_eq!(fill("{:.100}", &[n(1.0)]).map(|text| text.len()), Ok(102));
+    }
+
+    #[test]
+    fn unclosed_and_unopened_braces() {
+        assert_eq!(
+            fill("a {0", &[n(1.0)]),
+            Err("Cannot fill '{' in format template \"a {0\": it is never closed.".into())
+        );
+        assert_eq!(
+            fill("a } b", &[]),
+            Err(
+                "Cannot fill '}' in format template \"a } b\": it has no matching '{'; use '}}'."
+                    .into()
+            )
+        );
+    }
+
+    #[test]
+    fn bad_indexes() {
+        assert_eq!(
+            fill("{x}", &[n(1.0)]),
+            Err(
+                "Cannot fill '{x}' in format template \"{x}\": the index is not a whole number."
+                    .into()
+            )
+        );
+        assert_eq!(
+            fill("{-1}", &[n(1.0)]),
+            Err(
+                "Cannot fill '{-1}' in format template \"{-1}\": the index is not a whole number."
+                    .into()
+            )
+        );
+        assert_eq!(
+            fill("{1}", &[n(1.0)]),
+            Err("Cannot fill '{1}' in format template \"{1}\": there is only 1 argument.".into())
+        );
+        assert_eq!(
+            fill("{} {} {}", &[n(1.0), n(2.0)]),
+            Err(
+                "Cannot fill '{}' in format template \"{} {} {}\": there are only 2 arguments."
+                    .into()
+            )
+        );
+        assert_eq!(
+            fill("{}", &[]),
+            Err("Cannot fill '{}' in format template \"{}\": there are only 0 arguments.".into())
+        );
+    }
+
+    #[test]
+    fn bad_specs() {
+        for spec in ["x", "2", ".", ".-1", ".1.5"] {
+            let template = format!("{{0:{}}}", spec);
+            assert_eq!(
+                fill(&template, &[n(1.0)]),
+                Err(format!(
+                    "Cannot fill '{}' in format template \"{}\": '{}' is not a spec; \
+                     only '.N' decimal places are.",
+                    template, template, spec
+                ))
+            );
+        }
+        assert_eq!(
+            fill("{:.101}", &[n(1.0)]),
+            Err("Cannot fill '{:.101}' in format template \"{:.101}\": \
+                 101 decimal places is too many; the limit is 100."
+                .into())
+        );
+        assert_eq!(
+            fill("{:.2}", &[s("pi")]),
+            Err("Cannot fill '{:.2}' in format template \"{:.2}\": \
+                 decimal places need a Number, not a String."
+                .into())
+        );
+    }
+}
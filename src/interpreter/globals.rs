@@ -0,0 +1,55 @@
+use std::{cell::Ref, fmt::Display};
+
+use crate::{ast::expr::Literal, environment::Environment};
+
+/// A read-only view of the global scope, for hosts inspecting what a script defined.
+pub struct Globals<'a> {
+    environment: Ref<'a, Environment>,
+}
+
+impl<'a> Globals<'a> {
+    pub(crate) fn new(environment: Ref<'a, Environment>) -> Self {
+        Self { environment }
+    }
+
+    /// The name of every global, natives included, in alphabetical order.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .environment
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    pub fn get(&self, name: &str) -> Option<Literal> {
+        self.environment.lookup(name).cloned()
+    }
+}
+
+/// Why a global couldn't be read as the type the host asked for.
+#[derive(Debug, PartialEq)]
+pub enum GlobalError {
+    Undefined(String),
+    TypeMismatch {
+        name: String,
+        expected: &'static str,
+        actual: &'static str,
+    },
+}
+
+impl Display for GlobalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GlobalError::Undefined(name) => write!(f, "Undefined global '{}'.", name),
+            GlobalError::TypeMismatch {
+                name,
+                expected,
+                actual,
+            } => write!(f, "Global '{}' is a {}, not a {}.", name, actual, expected),
+        }
+    }
+}
+
+impl std::error::Error for GlobalError {}
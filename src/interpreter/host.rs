@@ -0,0 +1,250 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    fmt::Display,
+    io::{self, BufRead, Write},
+    rc::Rc,
+    time::SystemTime,
+};
+
+use super::InterpreterErrorKind;
+
+/// How large a script may let its values grow. Every limit is off unless an embedder sets it,
+/// and going over one is a runtime error naming the limit.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Limits {
+    /// The most characters a string built by concatenation may have.
+    pub max_string_length: Option<usize>,
+    /// The most elements an array, or entries a map, may have.
+    pub max_collection_len: Option<usize>,
+    /// The most environments that may be alive at once, the globals included. Every call and
+    /// block needs one while it runs, and closures keep theirs, so this bounds recursion too.
+    pub max_environments: Option<usize>,
+    /// The fuel a script gets: the most statements it may execute, each statement inside a
+    /// block or function body included. The prelude runs on a full tank of its own.
+    pub max_statements: Option<u64>,
+}
+
+impl Limits {
+    /// The limits `Interpreter::sandboxed()` sets: generous for scripts doing honest work,
+    /// while a runaway loop or allocation stops in well under a second.
+    pub const fn sandboxed() -> Self {
+        Self {
+            max_string_length: Some(1_000_000),
+            max_collection_len: Some(100_000),
+            max_environments: Some(200),
+            max_statements: Some(10_000_000),
+        }
+    }
+
+    pub(crate) fn check_string(&self, string: &str) -> Result<(), InterpreterErrorKind> {
+        let Some(max) = self.max_string_length else {
+            return Ok(());
+        };
+
+        match string.chars().count() {
+            len if len > max => Err(over_limit(
+                "max_string_length",
+                max,
+                format!("The string would be {} characters long", len),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that an array or map of `len` elements is allowed. `kind` names the collection
+    /// in the error.
+    pub(crate) fn check_collection(
+        &self,
+        kind: &str,
+        len: usize,
+    ) -> Result<(), InterpreterErrorKind> {
+        match self.max_collection_len {
+            Some(max) if len > max => Err(over_limit(
+                "max_collection_len",
+                max,
+                format!("The {} would have {} elements", kind, len),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that a script that has executed `count` statements may execute one more.
+    pub(crate) fn check_statements(&self, count: u64) -> Result<(), InterpreterErrorKind> {
+        match self.max_statements {
+            Some(max) if count >= max => Err(over_limit(
+                "max_statements",
+                max,
+                format!("The script would execute statement number {}", count + 1),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that `count` live environments are allowed.
+    pub(crate) fn check_environments(&self, count: usize) -> Result<(), InterpreterErrorKind> {
+        match self.max_environments {
+            Some(max) if count > max => Err(over_limit(
+                "max_environments",
+                max,
+                format!("There would be {} environments", count),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn over_limit(limit: &str, max: impl Display, what: String) -> InterpreterErrorKind {
+    InterpreterErrorKind::General(format!("{}, over the {} limit of {}.", what, limit, max))
+}
+
+/// Where `clock()` gets the time from.
+pub trait TimeSource {
+    /// Seconds since the Unix epoch.
+    fn now(&mut self) -> f64;
+
+    /// Milliseconds since the Unix epoch, for sources that know the time more precisely than
+    /// to the second.
+    fn now_millis(&mut self) -> f64 {
+        self.now() * 1000.0
+    }
+}
+
+/// The real time, in whole seconds.
+pub struct SystemTimeSource;
+
+impl TimeSource for SystemTimeSource {
+    fn now(&mut self) -> f64 {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as f64
+    }
+
+    fn now_millis(&mut self) -> f64 {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as f64
+    }
+}
+
+/// A clock that starts at 0 and moves forward by `step` seconds every time it is read, so
+/// scripts timing themselves print the same thing on every run.
+pub struct FrozenTimeSource {
+    next: f64,
+    step: f64,
+}
+
+impl FrozenTimeSource {
+    pub fn new(step: f64) -> Self {
+        Self { next: 0.0, step }
+    }
+}
+
+impl TimeSource for FrozenTimeSource {
+    fn now(&mut self) -> f64 {
+        let now = self.next;
+        self.next += self.step;
+        now
+    }
+}
+
+/// Where `readLine()` gets its lines from.
+pub trait InputSource {
+    /// The next line without its line ending, or `None` once the input is exhausted.
+    fn read_line(&mut self) -> Option<String>;
+}
+
+pub struct StdinInput;
+
+impl InputSource for StdinInput {
+    fn read_line(&mut self) -> Option<String> {
+        let mut line = String::new();
+        match io::stdin().lock().read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(line.trim_end_matches(['\n', '\r']).to_string()),
+        }
+    }
+}
+
+/// Hands out a fixed list of lines, in order.
+pub struct ScriptedInput {
+    lines: VecDeque<String>,
+}
+
+impl ScriptedInput {
+    pub fn new(lines: Vec<String>) -> Self {
+        Self {
+            lines: lines.into(),
+        }
+    }
+}
+
+impl InputSource for ScriptedInput {
+    fn read_line(&mut self) -> Option<String> {
+        self.lines.pop_front()
+    }
+}
+
+/// Where program output goes. It remembers whether the last thing written ended a line, so
+/// the REPL can start its prompt on a fresh one.
+pub struct Output {
+    sink: Box<dyn Write>,
+    at_line_start: bool,
+}
+
+impl Output {
+    pub fn new(sink: Box<dyn Write>) -> Self {
+        Self {
+            sink,
+            at_line_start: true,
+        }
+    }
+
+    /// Tells whether nothing has been written yet or the last write ended with a newline.
+    pub fn at_line_start(&self) -> bool {
+        self.at_line_start
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.sink.write(buf)?;
+        if let Some(last) = buf[..written].last() {
+            self.at_line_start = *last == b'\n';
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.sink.flush()
+    }
+}
+
+/// Output kept in memory, for embedders that want what a script printed. Clones share the
+/// same buffer, so one can go to `InterpreterBuilder::output` and another be read afterwards.
+#[derive(Clone, Debug, Default)]
+pub struct OutputBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl OutputBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Everything written so far, with invalid UTF-8 replaced.
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.borrow()).into_owned()
+    }
+}
+
+impl Write for OutputBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
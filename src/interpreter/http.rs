@@ -0,0 +1,363 @@
+//! `fetch()` and `fetchPost()`: a small blocking HTTP/1.1 client over plain TCP.
+//!
+//! Only `http://` URLs are supported. The natives are defined only for interpreters built with
+//! `InterpreterBuilder::allow_network`, so code from an untrusted source cannot reach the
+//! network unless the embedder asks for it.
+
+use std::{
+    cell::RefCell,
+    fmt::Display,
+    io::{ErrorKind, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use crate::ast::expr::Literal;
+
+use super::{
+    callable::{argument, Arity, Callable},
+    interpreter::Interpreter,
+    map::LoxMap,
+    InterpreterErrorKind,
+};
+
+/// How long a request may take, connecting included, when no `timeout` option is given.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+struct Request<'a> {
+    method: &'a str,
+    url: &'a str,
+    body: Option<&'a str>,
+    timeout: Duration,
+}
+
+struct Response {
+    status: u16,
+    headers: LoxMap<String>,
+    body: String,
+}
+
+impl Response {
+    fn into_literal(self) -> Literal {
+        let mut map = LoxMap::new();
+        map.insert("status".into(), Literal::Number(self.status.into()));
+        let mut headers = LoxMap::new();
+        for (name, value) in self.headers.iter() {
+            headers.insert(name.as_str().into(), value.clone());
+        }
+        map.insert(
+            "headers".into(),
+            Literal::Map(Rc::new(RefCell::new(headers))),
+        );
+        map.insert("body".into(), Literal::String(self.body.into()));
+        Literal::Map(Rc::new(RefCell::new(map)))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Fetch;
+
+impl Callable for Fetch {
+    fn arity(&self) -> Arity {
+        Arity::Range(1, 2)
+    }
+
+    fn name(&self) -> &str {
+        "fetch"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("GETs an http:// URL and returns a map of its status, headers and body.")
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        let request = Request {
+            method: "GET",
+            url: string_argument("fetch", args, 0)?,
+            body: None,
+            timeout: timeout_option("fetch", args.get(1))?,
+        };
+        send(&request)
+    }
+}
+
+impl Display for Fetch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn fetch>")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FetchPost;
+
+impl Callable for FetchPost {
+    fn arity(&self) -> Arity {
+        Arity::Range(2, 3)
+    }
+
+    fn name(&self) -> &str {
+        "fetchPost"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("POSTs a string body to an http:// URL and returns a map like fetch() does.")
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        let request = Request {
+            method: "POST",
+            url: string_argument("fetchPost", args, 0)?,
+            body: Some(string_argument("fetchPost", args, 1)?),
+            timeout: timeout_option("fetchPost", args.get(2))?,
+        };
+        send(&request)
+    }
+}
+
+impl Display for FetchPost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn fetchPost>")
+    }
+}
+
+fn string_argument<'a>(
+    native: &str,
+    args: &'a [Literal],
+    index: usize,
+) -> Result<&'a str, InterpreterErrorKind> {
+    match argument(args, index)? {
+        Literal::String(s) => Ok(s),
+        other => Err(InterpreterErrorKind::General(format!(
+            "Argument {} to {}() must be a String, got {}.",
+            index + 1,
+            native,
+            other.type_name()
+        ))),
+    }
+}
+
+/// The `timeout` from an options map, in seconds, or the default when there are no options.
+fn timeout_option(
+    native: &str,
+    options: Option<&Literal>,
+) -> Result<Duration, InterpreterErrorKind> {
+    let options = match options {
+        None => return Ok(DEFAULT_TIMEOUT),
+        Some(Literal::Map(options)) => options.borrow(),
+        Some(other) => {
+            return Err(InterpreterErrorKind::General(format!(
+                "The options for {}() must be a Map, got {}.",
+                native,
+                other.type_name()
+            )))
+        }
+    };
+
+    let mut timeout = DEFAULT_TIMEOUT;
+    for (key, value) in options.iter() {
+        match (key.to_string().as_str(), value) {
+            ("timeout", Literal::Number(seconds)) if *seconds > 0.0 && seconds.is_finite() => {
+                timeout = Duration::from_secs_f64(*seconds)
+            }
+            ("timeout", other) => {
+                return Err(InterpreterErrorKind::General(format!(
+                    "The timeout for {}() must be a positive number of seconds, got {}.",
+                    native, other
+                )))
+            }
+            (key, _) => {
+                return Err(InterpreterErrorKind::General(format!(
+                    "Unknown option '{}' for {}(); the only option is 'timeout'.",
+                    key, native
+                )))
+            }
+        }
+    }
+
+    Ok(timeout)
+}
+
+fn send(request: &Request) -> Result<Literal, InterpreterErrorKind> {
+    exchange(request)
+        .map(Response::into_literal)
+        .map_err(|reason| {
+            InterpreterErrorKind::General(format!(
+                "{} {} failed: {}.",
+                request.method, request.url, reason
+            ))
+        })
+}
+
+fn exchange(request: &Request) -> Result<Response, String> {
+    let deadline = Instant::now() + request.timeout;
+    let (host, port, path) = split_url(request.url)?;
+
+    let address = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("could not resolve '{}': {}", host, e))?
+        .next()
+        .ok_or_else(|| format!("'{}' has no addresses", host))?;
+    let mut stream = TcpStream::connect_timeout(&address, request.timeout)
+        .map_err(|e| describe_io_error(e, request.timeout))?;
+
+    let mut head = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: rlox\r\nConnection: close\r\n",
+        request.method, path, host
+    );
+    if let Some(body) = request.body {
+        head.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    }
+    head.push_str("\r\n");
+
+    stream
+        .set_write_timeout(Some(remaining(deadline, request.timeout)?))
+        .and_then(|_| stream.write_all(head.as_bytes()))
+        .and_then(|_| stream.write_all(request.body.unwrap_or_default().as_bytes()))
+        .map_err(|e| describe_io_error(e, request.timeout))?;
+
+    // The server closes the connection once it has sent the response, but each read gets only
+    // what is left of the timeout, so a server that trickles bytes can't keep it open forever.
+    let mut raw = vec![];
+    let mut buffer = [0; 8192];
+    loop {
+        stream
+            .set_read_timeout(Some(remaining(deadline, request.timeout)?))
+            .map_err(|e| describe_io_error(e, request.timeout))?;
+        match stream.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => raw.extend_from_slice(&buffer[..n]),
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(describe_io_error(e, request.timeout)),
+        }
+    }
+
+    parse_response(&raw)
+}
+
+/// Splits an `http://` URL into its host, port and path.
+fn split_url(url: &str) -> Result<(&str, u16, String), String> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or("only http:// URLs are supported")?;
+    let (authority, path) = rest.split_at(rest.find(['/', '?']).unwrap_or(rest.len()));
+    // A query right after the host still needs a path in front of it.
+    let path = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("/{}", path)
+    };
+
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (
+            host,
+            port.parse()
+                .map_err(|_| format!("'{}' is not a port number", port))?,
+        ),
+        None => (authority, 80),
+    };
+    if host.is_empty() {
+        return Err("the URL has no host".into());
+    }
+
+    Ok((host, port, path))
+}
+
+fn remaining(deadline: Instant, timeout: Duration) -> Result<Duration, String> {
+    match deadline.checked_duration_since(Instant::now()) {
+        Some(left) if !left.is_zero() => Ok(left),
+        _ => Err(timed_out(timeout)),
+    }
+}
+
+fn describe_io_error(error: std::io::Error, timeout: Duration) -> String {
+    match error.kind() {
+        ErrorKind::TimedOut | ErrorKind::WouldBlock => timed_out(timeout),
+        _ => error.to_string(),
+    }
+}
+
+fn timed_out(timeout: Duration) -> String {
+    format!("timed out after {}s", timeout.as_secs_f64())
+}
+
+fn parse_response(raw: &[u8]) -> Result<Response, String> {
+    let end_of_head = raw
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or("the response ended before its headers did")?;
+    let head = String::from_utf8_lossy(&raw[..end_of_head]);
+    let mut body = &raw[end_of_head + 4..];
+
+    let mut lines = head.split("\r\n");
+    let status_line = lines.next().unwrap_or_default();
+    let status = status_line
+        .split(' ')
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| format!("malformed status line '{}'", status_line))?;
+
+    let mut headers = LoxMap::new();
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(format!("malformed header '{}'", line));
+        };
+        let name = name.trim().to_ascii_lowercase();
+        let value = match headers.get(&name) {
+            // Repeated headers are combined the way HTTP allows them to be.
+            Some(previous) => format!("{}, {}", previous, value.trim()),
+            None => value.trim().to_string(),
+        };
+        headers.insert(name, Literal::String(value.into()));
+    }
+
+    let header = |name: &str| headers.get(name).map(|value| value.to_string());
+    let decoded;
+    if header("transfer-encoding").is_some_and(|encoding| encoding.contains("chunked")) {
+        decoded = decode_chunked(body)?;
+        body = &decoded;
+    } else if let Some(length) = header("content-length").and_then(|l| l.parse().ok()) {
+        body = body
+            .get(..length)
+            .ok_or("the response body was cut short")?;
+    }
+
+    Ok(Response {
+        status,
+        headers,
+        body: String::from_utf8_lossy(body).into_owned(),
+    })
+}
+
+fn decode_chunked(mut raw: &[u8]) -> Result<Vec<u8>, String> {
+    let mut body = vec![];
+
+    loop {
+        let end_of_size = raw
+            .windows(2)
+            .position(|window| window == b"\r\n")
+            .ok_or("a chunk size is missing")?;
+        let size = String::from_utf8_lossy(&raw[..end_of_size]);
+        // Chunk extensions after a ';' carry nothing we need.
+        let size = size.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16)
+            .map_err(|_| format!("'{}' is not a chunk size", size))?;
+        raw = &raw[end_of_size + 2..];
+
+        if size == 0 {
+            return Ok(body);
+        }
+
+        let chunk = raw.get(..size).ok_or("a chunk was cut short")?;
+        body.extend_from_slice(chunk);
+        raw = raw.get(size + 2..).ok_or("a chunk was cut short")?;
+    }
+}
@@ -0,0 +1,262 @@
+//! Natives for reasoning about object identity and lifetime: `identityHash()`, `same()` and
+//! `WeakRef()`.
+
+use std::{
+    cell::RefCell,
+    collections::hash_map::DefaultHasher,
+    fmt::Display,
+    hash::{Hash, Hasher},
+    rc::{Rc, Weak},
+};
+
+use crate::ast::expr::Literal;
+
+use super::{
+    callable::{argument, Arity, Callable, LoxCallable, LoxFunction},
+    class::{LoxClass, LoxInstance},
+    enums::{EnumValue, LoxEnum},
+    interpreter::Interpreter,
+    map::LoxMap,
+    InterpreterErrorKind,
+};
+
+/// The largest integer every number up to which a Lox number holds exactly.
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+impl Literal {
+    /// The address of the object the value refers to, or `None` for numbers, strings, bools
+    /// and nil, which have no identity of their own.
+    fn identity(&self) -> Option<*const ()> {
+        let address = match self {
+            Literal::Callable(LoxCallable::Function(function)) => Rc::as_ptr(function) as *const (),
+            Literal::Callable(LoxCallable::Class(class)) => Rc::as_ptr(class) as *const (),
+            Literal::Callable(LoxCallable::Other(native)) => Rc::as_ptr(native) as *const (),
+            Literal::Array(array) => Rc::as_ptr(array) as *const (),
+            Literal::Map(map) => Rc::as_ptr(map) as *const (),
+            Literal::Tuple(tuple) => Rc::as_ptr(tuple) as *const (),
+            Literal::Enum(lox_enum) => Rc::as_ptr(lox_enum) as *const (),
+            Literal::EnumValue(value) => Rc::as_ptr(value) as *const (),
+            Literal::Instance(instance) => Rc::as_ptr(instance) as *const (),
+            Literal::WeakRef(weak_ref) => Rc::as_ptr(weak_ref) as *const (),
+            Literal::String(_) | Literal::Number(_) | Literal::Bool(_) | Literal::Nil => {
+                return None
+            }
+        };
+
+        Some(address)
+    }
+
+    /// Whether `self` and `other` are the same object, or equal values for numbers, strings,
+    /// bools and nil. Unlike `==`, functions are the same as themselves, `NaN` is the same as
+    /// itself and `0` isn't the same as `-0`. Clones of a value are the same as it.
+    pub fn is_same(&self, other: &Literal) -> bool {
+        match (self.identity(), other.identity()) {
+            (Some(a), Some(b)) => a == b,
+            (None, None) => match (self, other) {
+                (Literal::Number(a), Literal::Number(b)) => {
+                    a.to_bits() == b.to_bits() || a.is_nan() && b.is_nan()
+                }
+                (Literal::String(a), Literal::String(b)) => a == b,
+                (Literal::Bool(a), Literal::Bool(b)) => a == b,
+                (Literal::Nil, Literal::Nil) => true,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// A number that stays the same for as long as the object the value refers to is alive,
+    /// and is shared by every value `is_same` as it. Numbers, strings and bools hash by value.
+    /// It fits a Lox number exactly.
+    pub fn identity_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        match (self.identity(), self) {
+            (Some(address), _) => (address as usize).hash(&mut hasher),
+            (None, Literal::Number(n)) if n.is_nan() => f64::NAN.to_bits().hash(&mut hasher),
+            (None, Literal::Number(n)) => n.to_bits().hash(&mut hasher),
+            (None, Literal::String(s)) => s.hash(&mut hasher),
+            (None, Literal::Bool(b)) => b.hash(&mut hasher),
+            // Nil is all there is to it.
+            (None, _) => (),
+        }
+        // Each kind of value hashes apart from the others even if their bits agree.
+        std::mem::discriminant(self).hash(&mut hasher);
+
+        hasher.finish() & MAX_SAFE_INTEGER
+    }
+}
+
+/// The object a `WeakRef` points to, held without keeping it alive.
+#[derive(Debug)]
+enum WeakTarget {
+    Function(Weak<LoxFunction>),
+    Class(Weak<LoxClass>),
+    Native(Weak<dyn Callable>),
+    Array(Weak<RefCell<Vec<Literal>>>),
+    Map(Weak<RefCell<LoxMap>>),
+    Tuple(Weak<[Literal]>),
+    Enum(Weak<LoxEnum>),
+    EnumValue(Weak<EnumValue>),
+    Instance(Weak<RefCell<LoxInstance>>),
+    WeakRef(Weak<LoxWeakRef>),
+}
+
+/// A reference to an object that doesn't keep it alive, made by `WeakRef()`. Objects are
+/// freed as soon as nothing refers to them, except those caught in a reference cycle, which
+/// are never freed; a weak reference to one of those keeps finding it.
+#[derive(Debug)]
+pub struct LoxWeakRef {
+    target: WeakTarget,
+}
+
+impl LoxWeakRef {
+    /// A weak reference to the object `value` refers to, or `None` if it is a number, string,
+    /// bool or nil.
+    pub fn new(value: &Literal) -> Option<LoxWeakRef> {
+        let target = match value {
+            Literal::Callable(LoxCallable::Function(function)) => {
+                WeakTarget::Function(Rc::downgrade(function))
+            }
+            Literal::Callable(LoxCallable::Class(class)) => WeakTarget::Class(Rc::downgrade(class)),
+            Literal::Callable(LoxCallable::Other(native)) => {
+                WeakTarget::Native(Rc::downgrade(native))
+            }
+            Literal::Array(array) => WeakTarget::Array(Rc::downgrade(array)),
+            Literal::Map(map) => WeakTarget::Map(Rc::downgrade(map)),
+            Literal::Tuple(tuple) => WeakTarget::Tuple(Rc::downgrade(tuple)),
+            Literal::Enum(lox_enum) => WeakTarget::Enum(Rc::downgrade(lox_enum)),
+            Literal::EnumValue(value) => WeakTarget::EnumValue(Rc::downgrade(value)),
+            Literal::Instance(instance) => WeakTarget::Instance(Rc::downgrade(instance)),
+            Literal::WeakRef(weak_ref) => WeakTarget::WeakRef(Rc::downgrade(weak_ref)),
+            Literal::String(_) | Literal::Number(_) | Literal::Bool(_) | Literal::Nil => {
+                return None
+            }
+        };
+
+        Some(LoxWeakRef { target })
+    }
+
+    /// The object, if something else still keeps it alive.
+    pub fn get(&self) -> Option<Literal> {
+        let value = match &self.target {
+            WeakTarget::Function(function) => {
+                Literal::Callable(LoxCallable::Function(function.upgrade()?))
+            }
+            WeakTarget::Class(class) => Literal::Callable(LoxCallable::Class(class.upgrade()?)),
+            WeakTarget::Native(native) => Literal::Callable(LoxCallable::Other(native.upgrade()?)),
+            WeakTarget::Array(array) => Literal::Array(array.upgrade()?),
+            WeakTarget::Map(map) => Literal::Map(map.upgrade()?),
+            WeakTarget::Tuple(tuple) => Literal::Tuple(tuple.upgrade()?),
+            WeakTarget::Enum(lox_enum) => Literal::Enum(lox_enum.upgrade()?),
+            WeakTarget::EnumValue(value) => Literal::EnumValue(value.upgrade()?),
+            WeakTarget::Instance(instance) => Literal::Instance(instance.upgrade()?),
+            WeakTarget::WeakRef(weak_ref) => Literal::WeakRef(weak_ref.upgrade()?),
+        };
+
+        Some(value)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct IdentityHash;
+
+impl Callable for IdentityHash {
+    fn arity(&self) -> Arity {
+        Arity::Exact(1)
+    }
+
+    fn name(&self) -> &str {
+        "identityHash"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some(
+            "Returns a number identifying an object for as long as it lives; values hash by value.",
+        )
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        Ok(Literal::Number(argument(args, 0)?.identity_hash() as f64))
+    }
+}
+
+impl Display for IdentityHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn identityHash>")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Same;
+
+impl Callable for Same {
+    fn arity(&self) -> Arity {
+        Arity::Exact(2)
+    }
+
+    fn name(&self) -> &str {
+        "same"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Tells whether two values are the very same object, or identical values.")
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        Ok(Literal::Bool(
+            argument(args, 0)?.is_same(argument(args, 1)?),
+        ))
+    }
+}
+
+impl Display for Same {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn same>")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WeakRefNative;
+
+impl Callable for WeakRefNative {
+    fn arity(&self) -> Arity {
+        Arity::Exact(1)
+    }
+
+    fn name(&self) -> &str {
+        "WeakRef"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Makes a reference to an object that doesn't keep it alive; get() returns it or nil.")
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        let value = argument(args, 0)?;
+        match LoxWeakRef::new(value) {
+            Some(weak_ref) => Ok(Literal::WeakRef(Rc::new(weak_ref))),
+            None => Err(InterpreterErrorKind::General(format!(
+                "WeakRef() needs an object, not a {}.",
+                value.type_name()
+            ))),
+        }
+    }
+}
+
+impl Display for WeakRefNative {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn WeakRef>")
+    }
+}
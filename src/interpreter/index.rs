@@ -0,0 +1,126 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::ast::expr::Literal;
+
+/// Why a value can't be used as an index or count. Displays as the end of a sentence naming
+/// what the value was for, as in "Argument 1 to 'insert' must be a non-negative integer, got
+/// 1.5."
+#[derive(Clone, Debug, PartialEq)]
+pub enum IndexError {
+    /// The value isn't a finite number without a fractional part that is at least 0.
+    NotAnInteger(String),
+    /// The value is an integer, but a larger one than allowed.
+    OutOfRange { value: String, max: usize },
+}
+
+impl Display for IndexError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            IndexError::NotAnInteger(value) => {
+                write!(f, "must be a non-negative integer, got {}", value)
+            }
+            IndexError::OutOfRange { value, max } => {
+                write!(f, "must be at most {}, got {}", max, value)
+            }
+        }
+    }
+}
+
+impl Literal {
+    /// The value as an index or count of at most `max`. Only finite numbers with no
+    /// fractional part qualify; `-0` counts as 0, while NaN, infinities and `1.5` don't.
+    pub fn as_index(&self, max: usize) -> Result<usize, IndexError> {
+        let Literal::Number(n) = self else {
+            return Err(IndexError::NotAnInteger(self.to_string()));
+        };
+        if !n.is_finite() || *n < 0.0 || n.fract() != 0.0 {
+            return Err(IndexError::NotAnInteger(self.to_string()));
+        }
+        // Compared as floats, since `max` may not be representable and a float too large
+        // for a usize would saturate when converted.
+        if *n > max as f64 || *n as usize > max {
+            return Err(IndexError::OutOfRange {
+                value: self.to_string(),
+                max,
+            });
+        }
+
+        Ok(*n as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(n: f64, max: usize) -> Result<usize, IndexError> {
+        Literal::Number(n).as_index(max)
+    }
+
+    #[test]
+    fn edge_cases() {
+        assert_eq!(index(0.0, 10), Ok(0));
+        assert_eq!(index(-0.0, 10), Ok(0));
+        assert_eq!(index(10.0, 10), Ok(10));
+        assert_eq!(index(1.5, 10), Err(IndexError::NotAnInteger("1.5".into())));
+        assert_eq!(index(-1.0, 10), Err(IndexError::NotAnInteger("-1".into())));
+        assert!(matches!(
+            index(f64::NAN, 10),
+            Err(IndexError::NotAnInteger(_))
+        ));
+        assert!(matches!(
+            index(f64::INFINITY, usize::MAX),
+            Err(IndexError::NotAnInteger(_))
+        ));
+        assert!(matches!(
+            index(1e20, usize::MAX),
+            Err(IndexError::OutOfRange { .. })
+        ));
+        assert!(matches!(
+            index(11.0, 10),
+            Err(IndexError::OutOfRange { max: 10, .. })
+        ));
+        assert!(matches!(
+            Literal::Nil.as_index(10),
+            Err(IndexError::NotAnInteger(_))
+        ));
+    }
+
+    /// Throws numbers from all over the range at `as_index` and checks each answer against
+    /// the rules.
+    #[test]
+    fn fuzzed_numbers_follow_the_rules() {
+        let mut rng = fastrand::Rng::with_seed(191);
+        for _ in 0..100_000 {
+            let n = match rng.u8(0..4) {
+                // Any bit pattern: NaNs, infinities, subnormals, huge and tiny numbers.
+                0 => f64::from_bits(rng.u64(..)),
+                // Small integers, and halves of them.
+                1 => rng.i32(-50..50) as f64 / if rng.bool() { 1.0 } else { 2.0 },
+                // Around the edge of what a Number holds exactly.
+                2 => (rng.u64(..1 << 54) as f64) * if rng.bool() { 1.0 } else { -1.0 },
+                _ => rng.f64() * 100.0,
+            };
+            let max = match rng.u8(0..3) {
+                0 => rng.usize(..100),
+                1 => rng.usize(..),
+                _ => usize::MAX,
+            };
+
+            let integral = n.is_finite() && n >= 0.0 && n.trunc() == n;
+            match index(n, max) {
+                Ok(i) => {
+                    assert!(integral, "{} was accepted", n);
+                    assert!(i <= max, "{} was accepted for max {}", n, max);
+                    assert_eq!(i as f64, n);
+                }
+                Err(IndexError::NotAnInteger(_)) => assert!(!integral, "{} was rejected", n),
+                Err(IndexError::OutOfRange { max: reported, .. }) => {
+                    assert!(integral);
+                    assert_eq!(reported, max);
+                    assert!(n > max as f64 || n as usize > max);
+                }
+            }
+        }
+    }
+}
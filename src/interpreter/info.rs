@@ -0,0 +1,153 @@
+//! What the interpreter knows about its own build: `version()`, `interpreterInfo()` and
+//! `lox --version`.
+
+use std::{cell::RefCell, fmt::Display, rc::Rc};
+
+use crate::ast::expr::Literal;
+
+use super::{
+    callable::{Arity, Callable},
+    capability::{Capability, CapabilitySet},
+    interpreter::Interpreter,
+    map::LoxMap,
+    InterpreterErrorKind,
+};
+
+/// The crate version, as in `Cargo.toml`.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// How this build of the interpreter was made.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    /// The abbreviated hash of the commit built from, when it was built from a git checkout.
+    pub git_hash: Option<&'static str>,
+    /// How scripts are run. Always `"tree-walk"`: there is no bytecode VM yet.
+    pub backend: &'static str,
+    /// The capabilities this build can grant, leaving out network access in builds without
+    /// the `http` feature.
+    pub capabilities: CapabilitySet,
+}
+
+/// Describes this build of the interpreter.
+///
+/// ```
+/// let info = rlox::build_info();
+/// assert_eq!(info.version, rlox::VERSION);
+/// assert_eq!(info.backend, "tree-walk");
+/// assert!(info.to_string().starts_with(&format!("rlox {}", rlox::VERSION)));
+/// ```
+pub fn build_info() -> BuildInfo {
+    let capabilities = Capability::ALL
+        .into_iter()
+        .filter(|capability| !capability.natives().is_empty())
+        .fold(CapabilitySet::none(), CapabilitySet::with);
+
+    BuildInfo {
+        version: VERSION,
+        git_hash: option_env!("RLOX_GIT_HASH"),
+        backend: "tree-walk",
+        capabilities,
+    }
+}
+
+/// The names of the capabilities in `set`, the way scripts and the command line know them.
+fn capability_names(set: CapabilitySet) -> Vec<&'static str> {
+    set.iter().map(Capability::name).collect()
+}
+
+/// The first line has the version and, when known, the commit; the others the backend and
+/// the capabilities.
+impl Display for BuildInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "rlox {}", self.version)?;
+        if let Some(hash) = self.git_hash {
+            write!(f, " ({})", hash)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "backend: {}", self.backend)?;
+        write!(
+            f,
+            "capabilities: {}",
+            capability_names(self.capabilities).join(", ")
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Version;
+
+impl Callable for Version {
+    fn is_pure(&self) -> bool {
+        true
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::Exact(0)
+    }
+
+    fn name(&self) -> &str {
+        "version"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Returns the interpreter's version, such as \"0.1.0\".")
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        _args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        Ok(VERSION.into())
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn version>")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct InterpreterInfo;
+
+impl Callable for InterpreterInfo {
+    fn arity(&self) -> Arity {
+        Arity::Exact(0)
+    }
+
+    fn name(&self) -> &str {
+        "interpreterInfo"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some(
+            "Returns a map with the interpreter's version, gitHash (or nil), backend and the \
+             capabilities this script was granted.",
+        )
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        _args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        let info = build_info();
+        let mut map = LoxMap::new();
+        map.insert("version".into(), info.version.into());
+        map.insert("gitHash".into(), info.git_hash.into());
+        map.insert("backend".into(), info.backend.into());
+        map.insert(
+            "capabilities".into(),
+            capability_names(interpreter.capabilities()).into(),
+        );
+        Ok(Literal::Map(Rc::new(RefCell::new(map))))
+    }
+}
+
+impl Display for InterpreterInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn interpreterInfo>")
+    }
+}
@@ -1,108 +1,962 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashSet,
+    fmt::Display,
+    io::{self, Write},
+    rc::Rc,
+};
 
 use crate::{
     ast::{
         expr::{BinOp, Expr, ExprKind, Literal, LogOp, UnOp},
-        stmt::Stmt,
+        pattern::{ArmBody, MatchArm, Pattern, PatternKind},
+        stmt::{Stmt, StmtKind},
     },
-    environment::Environment,
+    environment::{Environment, EnvironmentCount},
+    lexer::{Span, Token},
+    source_map::SourceMap,
+    Diagnostic,
 };
 
-use super::callable::{Callable, Clock, LoxCallable, LoxFunction};
+use super::{
+    callable::{
+        Callable, Chars, Format, FormatTime, Help, Join, JsonDecode, JsonEncode, LoxCallable,
+        LoxFunction, ParseTime, PrettyPrint, Printf, Random, WriteNative,
+    },
+    capability::{Capability, CapabilitySet},
+    class::{self, FieldDeclaration, LoxClass, MethodCache},
+    enums::LoxEnum,
+    globals::{GlobalError, Globals},
+    host::{
+        FrozenTimeSource, InputSource, Limits, Output, ScriptedInput, StdinInput, SystemTimeSource,
+        TimeSource,
+    },
+    identity::{IdentityHash, Same, WeakRefNative},
+    info::{InterpreterInfo, Version},
+    map::{HashableLiteral, LoxMap},
+    methods,
+    native::{HostFunction, NativeError},
+    reflect::{ClassName, Fields, FnArity, FnName, GetField, Methods, SetField},
+    stats::ExecStats,
+};
 
 #[derive(Debug)]
 pub enum InterpreterErrorKind {
     General(String),
+    /// A `General` error tagged with the innermost expression it was raised in.
+    Located(String, Span),
+    /// A state the interpreter should never get into, such as a node the parser never
+    /// produces. It is reported like any runtime error instead of aborting the process.
+    Internal(String),
     Return(Option<Literal>),
+    TailCall(Rc<LoxFunction>, Vec<Literal>),
 }
 
+impl InterpreterErrorKind {
+    /// The message of a runtime error, or `None` for the variants used for control flow.
+    pub fn message(&self) -> Option<&str> {
+        match self {
+            InterpreterErrorKind::General(message)
+            | InterpreterErrorKind::Located(message, _)
+            | InterpreterErrorKind::Internal(message) => Some(message),
+            _ => None,
+        }
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            InterpreterErrorKind::Located(_, span) => Some(*span),
+            _ => None,
+        }
+    }
+
+    /// Attaches `span` to a `General` error; anything else is returned as is.
+    fn locate(self, span: Span) -> Self {
+        match self {
+            InterpreterErrorKind::General(message) => InterpreterErrorKind::Located(message, span),
+            other => other,
+        }
+    }
+}
+
+/// Gets control before every statement when a script runs under the debugger.
+pub trait DebugHook {
+    /// Called before executing `stmt`. The hook may inspect and evaluate code in the
+    /// interpreter; execution resumes once it returns.
+    fn before_statement(&mut self, interpreter: &mut Interpreter, stmt: &Stmt);
+}
+
+/// A call that hasn't returned yet.
+#[derive(Clone, Debug)]
+pub struct CallFrame {
+    /// Kept as the callee itself, which is cheap to clone, and only named when a backtrace
+    /// is asked for.
+    pub function: LoxCallable,
+    /// The statement in the caller the call was made from.
+    pub span: Span,
+}
+
+/// A statement registered by `defer`, with the scope it was registered in.
+struct Deferred {
+    body: Stmt,
+    environment: Rc<RefCell<Environment>>,
+}
+
+/// How the interpreter handles operands of the wrong type.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strictness {
+    /// They are runtime errors.
+    #[default]
+    Strict,
+    /// Some of them are warnings instead, and the operation goes on with a stand-in result:
+    /// nil counts as 0 in arithmetic with a number, a number added to a string is converted
+    /// to text, and a comparison of mixed types is false. Each place in the code is warned
+    /// about once.
+    Lenient,
+}
+
+/// Upper bound on how many spare call environments are kept around for reuse.
+const ENVIRONMENT_POOL_SIZE: usize = 64;
+
 pub struct Interpreter {
     environment: Rc<RefCell<Environment>>,
-    pub globals: Rc<RefCell<Environment>>,
+    pub(crate) globals: Rc<RefCell<Environment>>,
+    environment_pool: Vec<Rc<RefCell<Environment>>>,
+    pub(crate) method_cache: MethodCache,
+    /// The statement being executed.
+    span: Span,
+    call_stack: Vec<CallFrame>,
+    /// The statements deferred by each function call and script in progress, innermost last,
+    /// each in the order they were registered.
+    deferred: Vec<Vec<Deferred>>,
+    /// The calls that were in progress when the current runtime error was raised.
+    error_trace: Option<Vec<(String, Span)>>,
+    /// The work done since the stats were last taken.
+    stats: ExecStats,
+    debug_hook: Option<Box<dyn DebugHook>>,
+    /// Where `print` and the natives that produce output write to.
+    output: Output,
+    time: Box<dyn TimeSource>,
+    rng: fastrand::Rng,
+    input: Box<dyn InputSource>,
+    pub(crate) limits: Limits,
+    /// The statements executed against `limits.max_statements`.
+    fuel_used: u64,
+    capabilities: CapabilitySet,
+    /// How many environments are in use, kept only when `limits` caps it. Pooled ones
+    /// aren't counted.
+    environment_count: Option<EnvironmentCount>,
+    /// Everything run so far. Functions outlive the code that defined them, so their
+    /// source has to stay around to report errors raised in them.
+    pub(crate) sources: SourceMap,
+    /// Warnings about code that ran, waiting for the runner to report them.
+    warnings: Vec<Diagnostic>,
+    /// Whether assigning to an undefined name outside any function defines it as a global.
+    implicit_globals: bool,
+    strictness: Strictness,
+    /// The expressions already warned about for a lenient operation.
+    lenient_warned: HashSet<Span>,
+    /// Whether a runner taking this interpreter should run the prelude in it first.
+    pub(crate) prelude: bool,
 }
 
-impl Interpreter {
-    pub fn new() -> Self {
-        let mut globals = Environment::new();
+/// Configures an `Interpreter`. Anything left unset talks to the real world: the system
+/// clock, a randomly seeded generator, stdin and stdout.
+#[derive(Default)]
+pub struct InterpreterBuilder {
+    time: Option<Box<dyn TimeSource>>,
+    seed: Option<u64>,
+    input: Option<Box<dyn InputSource>>,
+    output: Option<Box<dyn Write>>,
+    capabilities: CapabilitySet,
+    limits: Limits,
+    no_prelude: bool,
+}
 
-        let clock = Clock {};
+impl InterpreterBuilder {
+    /// Makes the clock start at the Unix epoch and advance by one second each time `clock()`
+    /// or `now()` reads it.
+    pub fn frozen_time(mut self) -> Self {
+        self.time = Some(Box::new(FrozenTimeSource::new(1.0)));
+        self
+    }
 
-        globals.define(
-            "clock".into(),
-            Literal::Callable(LoxCallable::Other(Box::new(clock))),
-        );
+    /// Defines the `fetch()` and `fetchPost()` natives, giving scripts access to the network.
+    /// Leave this off for code you don't trust.
+    #[cfg(feature = "http")]
+    pub fn allow_network(self) -> Self {
+        self.allow(Capability::Network)
+    }
+
+    /// Defines the `exec()` native, letting scripts run other programs. Leave this off for
+    /// code you don't trust.
+    pub fn allow_processes(self) -> Self {
+        self.allow(Capability::Processes)
+    }
+
+    /// Defines the natives `capability` brings, on top of what is granted already.
+    pub fn allow(mut self, capability: Capability) -> Self {
+        self.capabilities = self.capabilities.with(capability);
+        self
+    }
+
+    /// Leaves out the natives `capability` brings.
+    pub fn deny(mut self, capability: Capability) -> Self {
+        self.capabilities = self.capabilities.without(capability);
+        self
+    }
+
+    /// Grants exactly `capabilities`, replacing what was granted before. Network access is
+    /// ignored in builds without the `http` feature.
+    pub fn capabilities(mut self, capabilities: CapabilitySet) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Caps how long strings and how large collections and scope nesting may grow, and how
+    /// many statements a script may execute.
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets the fuel scripts get, the most statements they may execute, leaving the other
+    /// limits as they are. `None` lets them run for as long as they like.
+    pub fn fuel(mut self, max_statements: Option<u64>) -> Self {
+        self.limits.max_statements = max_statements;
+        self
+    }
+
+    /// Leaves out the prelude, the globals such as `reduce` and `Queue` that are written in
+    /// Lox and otherwise defined before any code runs. Startup gets cheaper and scripts see
+    /// only the natives.
+    pub fn no_prelude(mut self) -> Self {
+        self.no_prelude = true;
+        self
+    }
+
+    /// Makes `random()` produce the same sequence on every run.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Makes `readLine()` return `lines` in order, then nil.
+    pub fn inputs(mut self, lines: Vec<String>) -> Self {
+        self.input = Some(Box::new(ScriptedInput::new(lines)));
+        self
+    }
+
+    pub fn output(mut self, output: Box<dyn Write>) -> Self {
+        self.output = Some(output);
+        self
+    }
+
+    pub fn build(self) -> Interpreter {
+        let environment_count = self.limits.max_environments.map(|_| Rc::new(Cell::new(0)));
+        let mut globals = match &environment_count {
+            Some(count) => Environment::counted(Rc::clone(count)),
+            None => Environment::new(),
+        };
+
+        let natives: [(&str, Rc<dyn Callable>); 24] = [
+            ("chars", Rc::new(Chars)),
+            ("className", Rc::new(ClassName)),
+            ("fields", Rc::new(Fields)),
+            ("fnArity", Rc::new(FnArity)),
+            ("fnName", Rc::new(FnName)),
+            ("format", Rc::new(Format)),
+            ("formatTime", Rc::new(FormatTime)),
+            ("getField", Rc::new(GetField)),
+            ("help", Rc::new(Help)),
+            ("identityHash", Rc::new(IdentityHash)),
+            ("interpreterInfo", Rc::new(InterpreterInfo)),
+            ("join", Rc::new(Join)),
+            ("jsonDecode", Rc::new(JsonDecode)),
+            ("jsonEncode", Rc::new(JsonEncode)),
+            ("methods", Rc::new(Methods)),
+            ("parseTime", Rc::new(ParseTime)),
+            ("prettyPrint", Rc::new(PrettyPrint)),
+            ("printf", Rc::new(Printf)),
+            ("random", Rc::new(Random)),
+            ("same", Rc::new(Same)),
+            ("setField", Rc::new(SetField)),
+            ("version", Rc::new(Version)),
+            ("write", Rc::new(WriteNative)),
+            ("WeakRef", Rc::new(WeakRefNative)),
+        ];
+        for (name, native) in natives {
+            globals.define(name.into(), Literal::Callable(LoxCallable::Other(native)));
+        }
+
+        for capability in self.capabilities.iter() {
+            for (name, native) in capability.registry() {
+                globals.define(name.into(), Literal::Callable(LoxCallable::Other(native)));
+            }
+        }
 
         let globals = Rc::new(RefCell::new(globals));
         let environment = Rc::clone(&globals);
 
-        Self {
+        Interpreter {
             environment,
             globals,
+            environment_pool: vec![],
+            method_cache: MethodCache::default(),
+            span: Span::default(),
+            call_stack: vec![],
+            deferred: vec![],
+            error_trace: None,
+            stats: ExecStats::default(),
+            debug_hook: None,
+            output: Output::new(self.output.unwrap_or_else(|| Box::new(io::stdout()))),
+            time: self.time.unwrap_or_else(|| Box::new(SystemTimeSource)),
+            rng: self
+                .seed
+                .map_or_else(fastrand::Rng::new, fastrand::Rng::with_seed),
+            input: self.input.unwrap_or_else(|| Box::new(StdinInput)),
+            limits: self.limits,
+            fuel_used: 0,
+            capabilities: self.capabilities,
+            environment_count,
+            sources: SourceMap::new(),
+            warnings: vec![],
+            implicit_globals: false,
+            strictness: Strictness::default(),
+            lenient_warned: HashSet::new(),
+            prelude: !self.no_prelude,
         }
     }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self::builder().build()
+    }
+
+    pub fn builder() -> InterpreterBuilder {
+        InterpreterBuilder::default()
+    }
+
+    /// A builder for an interpreter fit for code you don't trust: none of the natives that
+    /// reach beyond the interpreter, not even the clock, and `Limits::sandboxed()`. Further
+    /// builder calls can grant back what the code needs:
+    ///
+    /// ```
+    /// use rlox::{Capability, Interpreter, Runner};
+    ///
+    /// let interpreter = Interpreter::sandboxed()
+    ///     .allow(Capability::Filesystem)
+    ///     .fuel(Some(50_000_000))
+    ///     .build();
+    /// assert!(interpreter.get_global("readFile").is_some());
+    /// assert!(interpreter.get_global("clock").is_none());
+    ///
+    /// let mut runner = Runner::with_interpreter(Interpreter::sandboxed().build());
+    /// assert!(runner.run("spin.lox", "while (true) {}").is_err());
+    /// ```
+    pub fn sandboxed() -> InterpreterBuilder {
+        Self::builder()
+            .capabilities(CapabilitySet::none())
+            .limits(Limits::sandboxed())
+    }
+
+    /// A builder for an interpreter with every capability there is, as the command line grants
+    /// with all of its `--allow-*` options, and no limits.
+    pub fn full() -> InterpreterBuilder {
+        Self::builder().capabilities(CapabilitySet::all())
+    }
+
+    /// What the interpreter grants scripts.
+    pub fn capabilities(&self) -> CapabilitySet {
+        self.capabilities
+    }
+
+    /// Gives the script about to run a full tank of fuel.
+    pub(crate) fn refuel(&mut self) {
+        self.fuel_used = 0;
+    }
+
+    /// The global scope, where everything a script defines at the top level ends up.
+    pub fn globals(&self) -> Globals<'_> {
+        Globals::new(self.globals.borrow())
+    }
+
+    /// Binds `name` in the global scope, replacing whatever was bound to it before.
+    pub fn define_global(&mut self, name: &str, value: impl Into<Literal>) {
+        self.globals
+            .borrow_mut()
+            .define(name.to_string(), value.into());
+    }
+
+    /// Lets assignments to undefined names define them as globals, as long as they happen
+    /// outside any function. Inside a function, assigning to an undefined name is an error
+    /// either way.
+    pub fn set_implicit_globals(&mut self, enabled: bool) {
+        self.implicit_globals = enabled;
+    }
+
+    /// Decides whether operands of the wrong type fail the script or, for the operations
+    /// `Strictness::Lenient` lists, are warned about and worked around.
+    pub fn set_strictness(&mut self, strictness: Strictness) {
+        self.strictness = strictness;
+    }
+
+    /// Defines the global `name` as a native that takes `arity` arguments and runs `fun`.
+    pub fn define_native(
+        &mut self,
+        name: &str,
+        arity: usize,
+        fun: impl Fn(&mut Interpreter, &[Literal]) -> Result<Literal, NativeError> + 'static,
+    ) {
+        let native = HostFunction::new(name, arity, Box::new(fun));
+        self.define_global(name, Literal::Callable(LoxCallable::Other(Rc::new(native))));
+    }
+
+    /// Like `define_native`, for a native whose result depends only on its arguments and that
+    /// has no other effect. Runners folding constants may call it before the script runs, in
+    /// the initializers of the variables a script opens with.
+    pub fn define_pure_native(
+        &mut self,
+        name: &str,
+        arity: usize,
+        fun: impl Fn(&mut Interpreter, &[Literal]) -> Result<Literal, NativeError> + 'static,
+    ) {
+        let native = HostFunction::new(name, arity, Box::new(fun)).pure();
+        self.define_global(name, Literal::Callable(LoxCallable::Other(Rc::new(native))));
+    }
+
+    /// Calls `callee`, a function, class or native from a script, with `arguments`. Meant for
+    /// natives calling back into the script that called them.
+    pub fn call_function(
+        &mut self,
+        callee: &Literal,
+        arguments: &[Literal],
+    ) -> Result<Literal, NativeError> {
+        match callee {
+            Literal::Callable(callable) => {
+                self.call_value(callable, arguments).map_err(NativeError)
+            }
+            other => Err(NativeError::new(format!(
+                "Can only call functions and classes, not a {}.",
+                other.type_name()
+            ))),
+        }
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Literal> {
+        self.globals().get(name)
+    }
+
+    pub fn get_global_number(&self, name: &str) -> Result<f64, GlobalError> {
+        self.typed_global(name, "Number", |value| match value {
+            Literal::Number(n) => Some(*n),
+            _ => None,
+        })
+    }
+
+    pub fn get_global_string(&self, name: &str) -> Result<String, GlobalError> {
+        self.typed_global(name, "String", |value| match value {
+            Literal::String(s) => Some(s.to_string()),
+            _ => None,
+        })
+    }
+
+    pub fn get_global_bool(&self, name: &str) -> Result<bool, GlobalError> {
+        self.typed_global(name, "Bool", |value| match value {
+            Literal::Bool(b) => Some(*b),
+            _ => None,
+        })
+    }
+
+    /// Reads the global `name` through `extract`, which returns `None` if the value isn't of
+    /// type `expected`.
+    fn typed_global<T>(
+        &self,
+        name: &str,
+        expected: &'static str,
+        extract: impl FnOnce(&Literal) -> Option<T>,
+    ) -> Result<T, GlobalError> {
+        let value = self
+            .get_global(name)
+            .ok_or_else(|| GlobalError::Undefined(name.to_string()))?;
+
+        extract(&value).ok_or_else(|| GlobalError::TypeMismatch {
+            name: name.to_string(),
+            expected,
+            actual: value.type_name(),
+        })
+    }
+
+    /// The current time according to the interpreter's time source, in seconds.
+    pub fn now(&mut self) -> f64 {
+        self.time.now()
+    }
+
+    /// The current time according to the interpreter's time source, in milliseconds.
+    pub fn now_millis(&mut self) -> f64 {
+        self.time.now_millis()
+    }
+
+    /// A number in `[0, 1)` from the interpreter's random number generator.
+    pub fn random(&mut self) -> f64 {
+        self.rng.f64()
+    }
+
+    pub fn read_line(&mut self) -> Option<String> {
+        self.input.read_line()
+    }
+
+    /// Sends program output to `output` instead of stdout.
+    pub fn set_output(&mut self, output: Box<dyn Write>) {
+        self.output = Output::new(output);
+    }
+
+    /// Writes `value` and a newline to the output.
+    pub fn print(&mut self, value: &dyn Display) -> Result<(), InterpreterErrorKind> {
+        writeln!(self.output, "{}", value)
+            .map_err(|e| InterpreterErrorKind::General(format!("Could not write output: {}", e)))
+    }
+
+    /// Writes `value` to the output as it is, without ending the line.
+    pub fn write(&mut self, value: &dyn Display) -> Result<(), InterpreterErrorKind> {
+        write!(self.output, "{}", value)
+            .map_err(|e| InterpreterErrorKind::General(format!("Could not write output: {}", e)))
+    }
+
+    /// Pushes out anything still buffered in the output, so it lands before whatever is
+    /// written to stderr next.
+    pub fn flush_output(&mut self) {
+        // A broken output was already reported by the write that failed.
+        let _ = self.output.flush();
+    }
+
+    /// Ends the output's current line unless it is already at the start of one.
+    pub fn finish_output_line(&mut self) {
+        if !self.output.at_line_start() {
+            let _ = writeln!(self.output);
+        }
+        self.flush_output();
+    }
+
+    pub fn set_debug_hook(&mut self, hook: Box<dyn DebugHook>) {
+        self.debug_hook = Some(hook);
+    }
+
+    /// The work done since the stats were last taken.
+    pub fn stats(&self) -> &ExecStats {
+        &self.stats
+    }
+
+    /// Hands over the work done so far, starting the counts over from zero.
+    pub fn take_stats(&mut self) -> ExecStats {
+        std::mem::take(&mut self.stats)
+    }
+
+    /// The scope the interpreter is currently executing in.
+    pub fn environment(&self) -> Rc<RefCell<Environment>> {
+        Rc::clone(&self.environment)
+    }
+
+    /// The calls in progress, innermost first, each paired with the statement it is
+    /// currently executing. The last entry is the top-level script.
+    pub fn backtrace(&self) -> Vec<(String, Span)> {
+        let mut span = self.span;
+        let mut trace = vec![];
+
+        for frame in self.call_stack.iter().rev() {
+            trace.push((frame.function.to_string(), span));
+            span = frame.span;
+        }
+        trace.push(("script".into(), span));
+
+        trace
+    }
+
+    /// The backtrace from where the runtime error now propagating was raised. Taking it
+    /// clears it for the next error.
+    pub fn take_error_trace(&mut self) -> Vec<(String, Span)> {
+        self.error_trace.take().unwrap_or_else(|| self.backtrace())
+    }
+
+    /// Renames the innermost frame after a tail call replaced the function running in it.
+    pub fn replace_frame_function(&mut self, function: LoxCallable) {
+        // The frame is reused, but the call is a call all the same.
+        self.stats.calls += 1;
+        if let Some(frame) = self.call_stack.last_mut() {
+            frame.function = function;
+        }
+    }
+
+    /// Hands out an empty environment nested in `enclosing`, recycling a pooled one if
+    /// possible.
+    pub fn acquire_environment(
+        &mut self,
+        enclosing: Rc<RefCell<Environment>>,
+    ) -> Result<Rc<RefCell<Environment>>, InterpreterErrorKind> {
+        self.start_environment()?;
+
+        Ok(match self.environment_pool.pop() {
+            Some(environment) => {
+                environment.borrow_mut().reset(enclosing);
+                log_event!(
+                    "reusing environment at depth {}",
+                    Environment::depth(&environment)
+                );
+                environment
+            }
+            None => {
+                let environment = Rc::new(RefCell::new(Environment::with_enclosing(enclosing)));
+                log_event!(
+                    "created environment at depth {}",
+                    Environment::depth(&environment)
+                );
+                environment
+            }
+        })
+    }
+
+    /// Makes sure one more environment can be created without going over `max_environments`,
+    /// counting it in the stats.
+    fn start_environment(&mut self) -> Result<(), InterpreterErrorKind> {
+        self.stats.environments += 1;
+        match &self.environment_count {
+            Some(count) => self.limits.check_environments(count.get() + 1),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns a call environment to the pool. Environments that are still referenced
+    /// elsewhere, i.e. captured by a closure, are left alone.
+    pub fn release_environment(&mut self, environment: Rc<RefCell<Environment>>) {
+        if Rc::strong_count(&environment) != 1
+            || self.environment_pool.len() >= ENVIRONMENT_POOL_SIZE
+        {
+            return;
+        }
+
+        environment.borrow_mut().clear();
+        self.environment_pool.push(environment);
+    }
+
+    /// The function `fun name(params) { body }` declared in the current environment.
+    fn function(
+        &self,
+        name: &Token,
+        params: &[Token],
+        body: &[Stmt],
+        doc: Option<&str>,
+    ) -> Literal {
+        let func = LoxFunction::new(
+            name.lexeme.clone(),
+            name.line,
+            params.to_vec(),
+            body.to_vec(),
+            Rc::clone(&self.environment),
+        )
+        .with_doc(doc);
+
+        Literal::Callable(LoxCallable::Function(Rc::new(func)))
+    }
+
+    /// Defines the functions and classes `stmts` declares before any of `stmts` runs, so the
+    /// code may use them above their declarations. A class is only defined early when it has
+    /// no superclass or its superclass is defined early too, as its superclass could otherwise
+    /// name something that doesn't exist yet.
+    ///
+    /// Returns what each statement was defined as, for `define_hoisted` to bind again where
+    /// the declaration is.
+    pub(crate) fn hoist(
+        &mut self,
+        stmts: &[Stmt],
+    ) -> Result<Vec<Option<Literal>>, InterpreterErrorKind> {
+        let mut classes = HashSet::new();
+        let mut hoisted = Vec::with_capacity(stmts.len());
+        for stmt in stmts {
+            let name = match &stmt.kind {
+                StmtKind::Function(name, params, body, doc) => {
+                    let func = self.function(name, params, body, doc.as_deref());
+                    self.environment.borrow_mut().define_at(name, func);
+                    name
+                }
+                StmtKind::Class(name, superclass, methods, doc) => {
+                    let hoistable = match superclass.as_ref().map(|expr| &expr.kind) {
+                        None => true,
+                        Some(ExprKind::Variable(superclass, _)) => {
+                            classes.contains(&superclass.lexeme)
+                        }
+                        Some(_) => false,
+                    };
+                    if !hoistable {
+                        hoisted.push(None);
+                        continue;
+                    }
+                    self.define_class(name, superclass.as_ref(), methods, doc.as_deref())?;
+                    classes.insert(name.lexeme.clone());
+                    name
+                }
+                _ => {
+                    hoisted.push(None);
+                    continue;
+                }
+            };
+            let value = self
+                .environment
+                .borrow()
+                .get(name)
+                .map_err(InterpreterErrorKind::General)?;
+            log_event!("[line {}] hoisted '{}'", name.line, name.lexeme);
+            hoisted.push(Some(value));
+        }
+
+        Ok(hoisted)
+    }
+
+    /// Binds the name `stmt` declares to `value`, what `hoist` defined it as, again. A
+    /// variable of the same name assigned in between is overwritten, just as running the
+    /// declaration would.
+    pub(crate) fn define_hoisted(&mut self, stmt: &Stmt, value: Literal) {
+        if let StmtKind::Function(name, ..) | StmtKind::Class(name, ..) = &stmt.kind {
+            self.environment.borrow_mut().define_at(name, value);
+        }
+    }
+
+    /// Starts collecting the statements deferred by a function call or script that is about
+    /// to run.
+    pub(crate) fn enter_defer_scope(&mut self) {
+        self.deferred.push(vec![]);
+    }
+
+    /// Whether the innermost function call or script has deferred statements waiting to run.
+    fn has_pending_defers(&self) -> bool {
+        self.deferred.last().is_some_and(|scope| !scope.is_empty())
+    }
+
+    /// Runs what the function call or script that just finished deferred, then stops
+    /// collecting for it. See `run_deferred`.
+    pub(crate) fn exit_defer_scope(&mut self, failed: bool) -> Result<(), InterpreterErrorKind> {
+        let result = self.run_deferred(failed);
+        self.deferred.pop();
+        result
+    }
+
+    /// Runs the statements deferred in the innermost scope, the last registered first. Each
+    /// runs even if one before it failed.
+    ///
+    /// If the code that deferred them `failed`, its error is the one that propagates, and any
+    /// error a deferred statement raises is only reported alongside it. Otherwise the first
+    /// such error is returned, with the rest reported. The span and backtrace of the error
+    /// being propagated are kept, rather than replaced by those of the deferred statements.
+    pub(crate) fn run_deferred(&mut self, failed: bool) -> Result<(), InterpreterErrorKind> {
+        let mut error = None;
+        let mut propagating = failed.then(|| (self.error_trace.take(), self.span));
+
+        while let Some(deferred) = self.deferred.last_mut().and_then(Vec::pop) {
+            let previous = std::mem::replace(&mut self.environment, deferred.environment);
+            let result = self.execute(&deferred.body);
+            self.environment = previous;
+
+            let Err(e) = result else {
+                continue;
+            };
+            let Some(message) = e.message() else {
+                continue;
+            };
+            if propagating.is_none() {
+                propagating = Some((self.error_trace.take(), self.span));
+                error = Some(e);
+                continue;
+            }
+
+            let span = e.span().unwrap_or(self.span);
+            self.error_trace = None;
+            self.warnings.push(
+                Diagnostic::error(
+                    "deferred-error",
+                    self.sources.location(span),
+                    span.line,
+                    span.column,
+                    format!("Deferred statement failed: {}", message),
+                )
+                .with_help(
+                    "It ran while another error was propagating; that error is the one reported \
+                     as the failure.",
+                ),
+            );
+        }
+
+        if let Some((trace, span)) = propagating {
+            self.error_trace = trace;
+            self.span = span;
+        }
+        error.map_or(Ok(()), Err)
+    }
 
     pub fn execute(&mut self, stmt: &Stmt) -> Result<(), InterpreterErrorKind> {
-        match stmt {
-            Stmt::Print(expr) => {
-                let value = self.evaluate(&expr)?;
-                println!("{}", value);
+        self.span = stmt.span;
+        self.stats.statements += 1;
+        self.limits
+            .check_statements(self.fuel_used)
+            .map_err(|e| e.locate(stmt.span))?;
+        self.fuel_used += 1;
+
+        // Blocks only group other statements, so there is nothing to stop at.
+        if !matches!(stmt.kind, StmtKind::Block(_)) {
+            if let Some(mut hook) = self.debug_hook.take() {
+                hook.before_statement(self, stmt);
+                self.debug_hook = Some(hook);
+            }
+        }
+
+        match &stmt.kind {
+            StmtKind::Print(expr) => {
+                let value = self.evaluate(expr)?;
+                self.print(&value)?;
             }
-            Stmt::Expression(expr) => {
-                self.evaluate(&expr)?;
+            StmtKind::Expression(expr) => {
+                self.evaluate(expr)?;
             }
-            Stmt::Var(name, initializer) => {
+            StmtKind::Var(name, initializer) => {
                 let value = if let Some(initializer) = initializer {
                     self.evaluate(initializer)?
                 } else {
                     Literal::Nil
                 };
 
-                self.environment
-                    .borrow_mut()
-                    .define(name.lexeme.clone(), value);
-            }
-            Stmt::Block(stmts) => {
-                let environment = Rc::clone(&self.environment);
-                self.execute_block(
-                    &stmts,
-                    Rc::new(RefCell::new(Environment::with_enclosing(environment))),
-                )?;
-            }
-            Stmt::If(condition, then_stmt, else_stmt) => {
-                if is_truthy(&self.evaluate(condition)?) {
-                    self.execute(&then_stmt)?;
-                } else {
-                    if let Some(stmt) = else_stmt.as_ref() {
-                        self.execute(&stmt)?;
+                self.environment.borrow_mut().define_at(name, value);
+            }
+            StmtKind::Const(name, initializer) => {
+                let value = self.evaluate(initializer)?;
+
+                self.environment.borrow_mut().define_const_at(name, value);
+            }
+            StmtKind::Unpack(names, initializer) => {
+                let values = match self.evaluate(initializer)? {
+                    Literal::Tuple(values) if values.len() == names.len() => values,
+                    Literal::Tuple(values) => {
+                        return Err(InterpreterErrorKind::General(format!(
+                            "Expected {} values to unpack, but got {}.",
+                            names.len(),
+                            values.len()
+                        ))
+                        .locate(initializer.span))
+                    }
+                    other => {
+                        return Err(InterpreterErrorKind::General(format!(
+                            "Only tuples can be unpacked, but tried to unpack a {}.",
+                            other.type_name()
+                        ))
+                        .locate(initializer.span))
+                    }
+                };
+
+                for (name, value) in names.iter().zip(values.iter()) {
+                    self.environment.borrow_mut().define_at(name, value.clone());
+                }
+            }
+            StmtKind::Class(name, superclass, methods, doc) => {
+                self.define_class(name, superclass.as_ref(), methods, doc.as_deref())?
+            }
+            StmtKind::Extend(class, methods) => self.extend_class(class, methods)?,
+            StmtKind::Enum(name, variants) => {
+                let variants: Vec<&str> = variants.iter().map(|v| v.lexeme.as_str()).collect();
+                let value = Literal::Enum(Rc::new(LoxEnum::new(&name.lexeme, &variants)));
+
+                self.environment.borrow_mut().define_const_at(name, value);
+            }
+            StmtKind::Block(stmts) => {
+                self.start_environment().map_err(|e| e.locate(stmt.span))?;
+                let enclosing = Rc::clone(&self.environment);
+                let environment = Rc::new(RefCell::new(Environment::with_enclosing(enclosing)));
+                log_event!(
+                    "created environment at depth {}",
+                    Environment::depth(&environment)
+                );
+                self.execute_block(stmts, environment)?;
+            }
+            StmtKind::If(arms, else_stmt) => {
+                let mut taken = None;
+                for (condition, branch) in arms {
+                    if is_truthy(&self.evaluate(condition)?) {
+                        taken = Some(branch);
+                        break;
                     }
                 }
+
+                if let Some(stmt) = taken.or(else_stmt.as_deref()) {
+                    self.execute(stmt)?;
+                }
             }
-            Stmt::While(condition, body) => {
+            StmtKind::While(condition, body) => {
                 while is_truthy(&self.evaluate(condition)?) {
                     self.execute(body)?;
                 }
             }
-            Stmt::Function(name, params, body) => {
-                let func = LoxFunction::new(
-                    name.lexeme.clone(),
-                    params.to_vec(),
-                    body.to_vec(),
-                    Rc::clone(&self.environment),
-                );
-
-                self.environment.borrow_mut().define(
-                    name.lexeme.clone(),
-                    Literal::Callable(LoxCallable::Function(func)),
+            StmtKind::For(initializer, condition, increment, body) => match initializer {
+                Some(initializer) => self.scoped(stmt.span, |interpreter| {
+                    interpreter.execute(initializer)?;
+                    interpreter.run_for_loop(
+                        stmt.span,
+                        condition.as_ref(),
+                        increment.as_ref(),
+                        body,
+                    )
+                })?,
+                None => {
+                    self.run_for_loop(stmt.span, condition.as_ref(), increment.as_ref(), body)?
+                }
+            },
+            StmtKind::ForIn(name, iterable, body) => {
+                let iterable = self.evaluate(iterable)?;
+                for item in elements(&iterable).map_err(|e| e.locate(stmt.span))? {
+                    self.scoped(stmt.span, |interpreter| {
+                        interpreter.environment.borrow_mut().define_at(name, item);
+                        interpreter.execute(body)
+                    })?;
+                }
+            }
+            StmtKind::Function(name, params, body, doc) => {
+                log_event!(
+                    "[line {}] defined function '{}' with {} parameters",
+                    name.line,
+                    name.lexeme,
+                    params.len()
                 );
+                let func = self.function(name, params, body, doc.as_deref());
+                self.environment.borrow_mut().define_at(name, func);
             }
-            Stmt::Return(_, value) => {
+            StmtKind::Return(_, value) => {
                 let value = match value {
+                    Some(Expr {
+                        kind: ExprKind::Call(callee, arguments),
+                        ..
+                    }) => return self.tail_call(callee, arguments),
                     Some(value) => Some(self.evaluate(value)?),
                     _ => None,
                 };
 
                 return Err(InterpreterErrorKind::Return(value));
             }
+            StmtKind::Defer(body) => {
+                let deferred = Deferred {
+                    body: (**body).clone(),
+                    environment: Rc::clone(&self.environment),
+                };
+                match self.deferred.last_mut() {
+                    Some(scope) => scope.push(deferred),
+                    None => {
+                        return Err(InterpreterErrorKind::Internal(
+                            "defer outside any function or script".into(),
+                        ))
+                    }
+                }
+            }
         };
 
         Ok(())
@@ -134,159 +988,921 @@ impl Interpreter {
         }
     }
 
+    /// Runs `run` in a new environment enclosed by the current one, the way a block at `span`
+    /// runs its statements.
+    fn scoped(
+        &mut self,
+        span: Span,
+        run: impl FnOnce(&mut Self) -> Result<(), InterpreterErrorKind>,
+    ) -> Result<(), InterpreterErrorKind> {
+        self.start_environment().map_err(|e| e.locate(span))?;
+        let enclosing = Rc::clone(&self.environment);
+        let environment = Rc::new(RefCell::new(Environment::with_enclosing(enclosing)));
+        let previous = std::mem::replace(&mut self.environment, environment);
+
+        let result = run(self);
+        self.environment = previous;
+
+        result
+    }
+
+    /// Runs the loop of a `for` statement at `span`, after its initializer. Each pass runs the
+    /// body and increment in an environment of their own, as the loop's lowered form does.
+    fn run_for_loop(
+        &mut self,
+        span: Span,
+        condition: Option<&Expr>,
+        increment: Option<&Expr>,
+        body: &Stmt,
+    ) -> Result<(), InterpreterErrorKind> {
+        while match condition {
+            Some(condition) => is_truthy(&self.evaluate(condition)?),
+            None => true,
+        } {
+            match increment {
+                Some(increment) => self.scoped(span, |interpreter| {
+                    interpreter.execute(body)?;
+                    interpreter.evaluate(increment).map(drop)
+                })?,
+                None => self.execute(body)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gives `instance` the fields declared by `class` and its superclasses, the superclass's
+    /// first, evaluating each initializer in turn so later ones see the fields set before
+    /// them.
+    pub(crate) fn initialize_fields(
+        &mut self,
+        class: &LoxClass,
+        instance: &Literal,
+    ) -> Result<(), InterpreterErrorKind> {
+        if let Some(superclass) = &class.superclass {
+            self.initialize_fields(superclass, instance)?;
+        }
+
+        for field in class.fields() {
+            let value = match &field.initializer {
+                Some(initializer) => {
+                    let mut environment = Environment::with_enclosing(Rc::clone(&field.closure));
+                    environment.define("this".into(), instance.clone());
+                    let environment = Rc::new(RefCell::new(environment));
+
+                    let previous = std::mem::replace(&mut self.environment, environment);
+                    let value = self.evaluate(initializer);
+                    self.environment = previous;
+                    value?
+                }
+                None => Literal::Nil,
+            };
+
+            if let Literal::Instance(instance) = instance {
+                instance
+                    .borrow_mut()
+                    .fields
+                    .insert(field.name.clone(), value);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn define_class(
+        &mut self,
+        name: &Token,
+        superclass: Option<&Expr>,
+        methods: &[Stmt],
+        doc: Option<&str>,
+    ) -> Result<(), InterpreterErrorKind> {
+        let superclass = match superclass {
+            Some(expr) => match self.evaluate(expr)? {
+                Literal::Callable(LoxCallable::Class(superclass)) => Some(superclass),
+                other => {
+                    let snippet = self.sources.snippet(expr.span);
+                    let subject = match snippet.as_str() {
+                        "" => "it".to_string(),
+                        snippet => format!("`{}`", snippet),
+                    };
+                    return Err(InterpreterErrorKind::General(format!(
+                        "Superclass must be a class, but {} is a {}.",
+                        subject,
+                        other.type_name()
+                    ))
+                    .locate(expr.span));
+                }
+            },
+            None => None,
+        };
+
+        // Methods see `super` in a scope of its own between the class's scope and theirs.
+        let closure = match &superclass {
+            Some(superclass) => {
+                let mut environment = Environment::with_enclosing(Rc::clone(&self.environment));
+                environment.define(
+                    "super".into(),
+                    Literal::Callable(LoxCallable::Class(Rc::clone(superclass))),
+                );
+                Rc::new(RefCell::new(environment))
+            }
+            None => Rc::clone(&self.environment),
+        };
+
+        let functions = methods
+            .iter()
+            .filter_map(|method| method_function(method, &closure))
+            .map(|(name, function)| (name.lexeme.clone(), function))
+            .collect();
+        let fields = methods
+            .iter()
+            .filter_map(|member| match &member.kind {
+                StmtKind::Var(name, initializer) => Some(FieldDeclaration {
+                    name: name.lexeme.clone(),
+                    initializer: initializer.clone(),
+                    closure: Rc::clone(&closure),
+                }),
+                _ => None,
+            })
+            .collect();
+
+        let class = LoxClass::new(name.lexeme.clone(), superclass, functions, fields).with_doc(doc);
+        self.environment
+            .borrow_mut()
+            .define_at(name, Literal::Callable(LoxCallable::Class(Rc::new(class))));
+
+        Ok(())
+    }
+
+    /// Adds `methods` to the class `class` evaluates to, warning about each one that replaces
+    /// a method the class already had.
+    fn extend_class(&mut self, class: &Expr, methods: &[Stmt]) -> Result<(), InterpreterErrorKind> {
+        let class = match self.evaluate(class)? {
+            Literal::Callable(LoxCallable::Class(class)) => class,
+            other => {
+                let name = match &class.kind {
+                    ExprKind::Variable(name, _) => name.lexeme.clone(),
+                    _ => self.sources.snippet(class.span),
+                };
+                return Err(InterpreterErrorKind::General(format!(
+                    "Can only extend a class, but '{}' is a {}.",
+                    name,
+                    other.type_name()
+                ))
+                .locate(class.span));
+            }
+        };
+
+        // The methods see `super` in a scope of their own, like a class's do. The resolver
+        // can't know whether the class has a superclass, so the scope is always there.
+        let superclass = match &class.superclass {
+            Some(superclass) => Literal::Callable(LoxCallable::Class(Rc::clone(superclass))),
+            None => Literal::Nil,
+        };
+        let mut closure = Environment::with_enclosing(Rc::clone(&self.environment));
+        closure.define("super".into(), superclass);
+        let closure = Rc::new(RefCell::new(closure));
+
+        // Subclasses may have cached the methods the class had until now.
+        self.method_cache.clear();
+
+        for method in methods {
+            let Some((name, function)) = method_function(method, &closure) else {
+                continue;
+            };
+            if let Some(replaced) = class.add_method(name.lexeme.clone(), function) {
+                let span = name.span();
+                self.warnings.push(Diagnostic::warning(
+                    "extension-replaces-method",
+                    self.sources.location(span),
+                    span.line,
+                    span.column,
+                    format!(
+                        "Extension replaces method '{}' of class {}, declared at line {}.",
+                        name.lexeme,
+                        class.name,
+                        replaced.line()
+                    ),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Warns that the expression at `span` failed with `message` and went on by `action`,
+    /// unless it was warned about before.
+    fn warn_lenient(&mut self, span: Span, message: String, action: &str) {
+        if !self.lenient_warned.insert(span) {
+            return;
+        }
+        let warning = Diagnostic::warning(
+            "lenient-operation",
+            self.sources.location(span),
+            span.line,
+            span.column,
+            message,
+        )
+        .with_help(format!("Lenient mode went on by {}.", action));
+        self.warnings.push(warning);
+    }
+
+    /// Evaluates `expr` ahead of the code it is in, for the optimizer, forgetting any error
+    /// so the code can raise it again when it runs. The work isn't counted against the limits
+    /// or in the stats, and an expression that warns gives no value, so that it warns when it
+    /// runs instead.
+    pub(crate) fn evaluate_ahead(&mut self, expr: &Expr) -> Option<Literal> {
+        let stats = self.stats;
+        let fuel_used = self.fuel_used;
+        let warnings = self.warnings.len();
+        let lenient_warned = self.lenient_warned.clone();
+
+        let value = self.evaluate(expr).ok();
+        let warned = self.warnings.len() > warnings;
+
+        self.error_trace = None;
+        self.stats = stats;
+        self.fuel_used = fuel_used;
+        self.warnings.truncate(warnings);
+        self.lenient_warned = lenient_warned;
+        value.filter(|_| !warned)
+    }
+
+    /// Hands over the warnings about code that ran since they were last taken.
+    pub(crate) fn take_warnings(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Names the expression at `span` for an error message about its value, or nothing if its
+    /// source text wasn't kept.
+    fn expression_source(&self, span: Span) -> String {
+        match self.sources.snippet(span).as_str() {
+            "" => String::new(),
+            snippet => format!(" (from expression `{}`)", snippet),
+        }
+    }
+
+    /// Turns a failed lookup of `name` into an error, suggesting a similar name in scope if
+    /// `name` isn't bound at all.
+    /// Whether assigning to `name`, which failed, should define it as a global instead.
+    fn defines_implicitly(&self, name: &Token, depth: Option<usize>) -> bool {
+        self.implicit_globals
+            && depth.is_none()
+            && self.call_stack.is_empty()
+            // Assigning to a global constant fails too, but the name is taken.
+            && self.globals.borrow().lookup(&name.lexeme).is_none()
+    }
+
+    fn lookup_error(
+        &self,
+        name: &Token,
+        depth: Option<usize>,
+        error: String,
+    ) -> InterpreterErrorKind {
+        let environment = self.environment_at(depth);
+        if Environment::distance_to(&environment, &name.lexeme).is_some() {
+            return InterpreterErrorKind::General(error);
+        }
+
+        InterpreterErrorKind::General(Environment::undefined(&self.environment, &name.lexeme))
+    }
+
+    /// Looks `method` up on the superclass bound `depth` scopes out, bound to the `this` one
+    /// scope further in.
+    fn evaluate_super(
+        &mut self,
+        method: &Token,
+        depth: Option<usize>,
+    ) -> Result<Literal, InterpreterErrorKind> {
+        let depth = depth.ok_or_else(|| {
+            InterpreterErrorKind::Internal("'super' was not resolved to a scope".into())
+        })?;
+        let superclass = Environment::ancestor(&self.environment, depth)
+            .borrow()
+            .lookup("super")
+            .cloned();
+        let this = Environment::ancestor(&self.environment, depth.saturating_sub(1))
+            .borrow()
+            .lookup("this")
+            .cloned();
+
+        match (superclass, this) {
+            // Only an extension's methods can find `super` unbound.
+            (Some(Literal::Nil), _) => Err(InterpreterErrorKind::General(
+                "Can't use 'super' in a class with no superclass.".into(),
+            )),
+            (Some(Literal::Callable(LoxCallable::Class(superclass))), Some(this)) => {
+                match self.method_cache.find_method(&superclass, &method.lexeme) {
+                    Some(function) => Ok(Literal::Callable(LoxCallable::Function(Rc::new(
+                        function.bind(this),
+                    )))),
+                    None => Err(InterpreterErrorKind::General(format!(
+                        "Undefined property '{}' on superclass {}.",
+                        method.lexeme, superclass.name
+                    ))),
+                }
+            }
+            _ => Err(InterpreterErrorKind::Internal(
+                "'super' or 'this' is missing from its scope".into(),
+            )),
+        }
+    }
+
+    /// Evaluates the body of the first arm whose pattern `value` matches, with the pattern's
+    /// bindings in a scope of their own. Running out of arms is an error rather than nil, so a
+    /// forgotten case doesn't go unnoticed.
+    fn evaluate_match(
+        &mut self,
+        value: &Expr,
+        arms: &[MatchArm],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        let value = self.evaluate(value)?;
+
+        for arm in arms {
+            let mut bindings = vec![];
+            if !self.matches_pattern(&arm.pattern, &value, &mut bindings)? {
+                continue;
+            }
+
+            self.start_environment()?;
+            let enclosing = Rc::clone(&self.environment);
+            let environment = Rc::new(RefCell::new(Environment::with_enclosing(enclosing)));
+            for (name, value) in bindings {
+                environment.borrow_mut().define(name, value);
+            }
+
+            return match &arm.body {
+                ArmBody::Expr(body) => {
+                    let previous = std::mem::replace(&mut self.environment, environment);
+                    let result = self.evaluate(body);
+                    self.environment = previous;
+                    result
+                }
+                ArmBody::Block(stmts) => {
+                    self.execute_block(stmts, environment)?;
+                    Ok(Literal::Nil)
+                }
+            };
+        }
+
+        Err(InterpreterErrorKind::General(format!(
+            "No match arm matches the {} {}.",
+            value.type_name(),
+            value
+        )))
+    }
+
+    /// Tells whether `value` fits `pattern`, adding what the pattern binds to `bindings` as it
+    /// goes. After a failed match `bindings` may hold some of the names anyway.
+    fn matches_pattern(
+        &mut self,
+        pattern: &Pattern,
+        value: &Literal,
+        bindings: &mut Vec<(String, Literal)>,
+    ) -> Result<bool, InterpreterErrorKind> {
+        let matched = match (&pattern.kind, value) {
+            (PatternKind::Wildcard, _) => true,
+            (PatternKind::Literal(literal), value) => is_equal(literal, value),
+            (PatternKind::Value(expected), value) => is_equal(&self.evaluate(expected)?, value),
+            (PatternKind::Binding(name), value) => {
+                bindings.push((name.lexeme.clone(), value.clone()));
+                true
+            }
+            (PatternKind::Array(patterns), Literal::Array(elements)) => {
+                let elements = elements.borrow().clone();
+                if patterns.len() != elements.len() {
+                    return Ok(false);
+                }
+                for (pattern, element) in patterns.iter().zip(elements.iter()) {
+                    if !self.matches_pattern(pattern, element, bindings)? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+            (PatternKind::Map(entries), Literal::Map(map)) => {
+                for (key, pattern) in entries {
+                    let value = map
+                        .borrow()
+                        .get(&HashableLiteral::from(key.as_str()))
+                        .cloned();
+                    match value {
+                        Some(value) if self.matches_pattern(pattern, &value, bindings)? => (),
+                        _ => return Ok(false),
+                    }
+                }
+                true
+            }
+            _ => false,
+        };
+
+        Ok(matched)
+    }
+
+    /// Evaluates an expression standing in for a whole statement, such as one typed at the
+    /// REPL, so errors are traced back to it rather than to the last statement executed.
+    pub fn evaluate_top_level(&mut self, expr: &Expr) -> Result<Literal, InterpreterErrorKind> {
+        self.span = expr.span;
+        self.evaluate(expr)
+    }
+
     pub fn evaluate(&mut self, expr: &Expr) -> Result<Literal, InterpreterErrorKind> {
+        self.stats.expressions += 1;
+        self.evaluate_expr(expr).map_err(|e| e.locate(expr.span))
+    }
+
+    fn evaluate_expr(&mut self, expr: &Expr) -> Result<Literal, InterpreterErrorKind> {
         let lit = match &expr.kind {
             ExprKind::Literal(l) => l.clone(),
-            ExprKind::Grouping(expr) => self.evaluate(&expr)?,
-            ExprKind::Variable(name) => match self.environment.borrow_mut().get(name) {
+            ExprKind::Grouping(expr) => self.evaluate(expr)?,
+            ExprKind::Variable(name, depth) => match self.environment_at(*depth).borrow().get(name)
+            {
                 Ok(val) => val,
-                Err(e) => return Err(InterpreterErrorKind::General(format!("{}", e))),
+                Err(e) => return Err(self.lookup_error(name, *depth, e)),
             },
-            ExprKind::Assign(name, expr) => {
-                let value = self.evaluate(&expr)?;
-                if let Err(e) = self
-                    .environment
+            ExprKind::Assign(name, expr, depth) => {
+                let value = self.evaluate(expr)?;
+                let assigned = self
+                    .environment_at(*depth)
                     .borrow_mut()
-                    .assign(name.lexeme.as_str(), value.clone())
-                {
-                    return Err(InterpreterErrorKind::General(e));
+                    .assign(name.lexeme.as_str(), value.clone());
+                if let Err(e) = assigned {
+                    if !self.defines_implicitly(name, *depth) {
+                        return Err(self.lookup_error(name, *depth, e));
+                    }
+                    self.environment
+                        .borrow_mut()
+                        .define_global(name.lexeme.clone(), value.clone());
                 }
 
                 value
             }
-            ExprKind::Call(callee, arguments) => {
-                let callee_v = if let Literal::Callable(callable) = self.evaluate(callee)? {
-                    callable
-                } else {
-                    return Err(InterpreterErrorKind::General(
-                        "Can only call functions and classes".into(),
-                    ));
+            ExprKind::Call(..) | ExprKind::Get(..) => {
+                self.evaluate_chain(expr)?.unwrap_or(Literal::Nil)
+            }
+            ExprKind::Match(value, arms) => self.evaluate_match(value, arms)?,
+            ExprKind::Index(value, index) => {
+                let value = self.evaluate(value)?;
+                let index = self.evaluate(index)?;
+                element_at(&value, &index)?
+            }
+            ExprKind::Set(object, name, value) => {
+                let instance = match self.evaluate(object)? {
+                    Literal::Instance(instance) => instance,
+                    other => {
+                        return Err(InterpreterErrorKind::General(format!(
+                            "Only instances have fields, but tried to set '{}' on a {}.",
+                            name.lexeme,
+                            other.type_name()
+                        )))
+                    }
                 };
+                let value = self.evaluate(value)?;
 
-                if arguments.len() != callee_v.arity() {
-                    return Err(InterpreterErrorKind::General(format!(
-                        "Expected {} arguments but got {}.",
-                        callee_v.arity(),
-                        arguments.len()
-                    )));
+                instance
+                    .borrow_mut()
+                    .fields
+                    .insert(name.lexeme.clone(), value.clone());
+                value
+            }
+            ExprKind::This(keyword, depth) => {
+                match self.environment_at(*depth).borrow().get(keyword) {
+                    Ok(val) => val,
+                    Err(e) => return Err(InterpreterErrorKind::General(e)),
                 }
+            }
+            ExprKind::Super(method, depth) => self.evaluate_super(method, *depth)?,
+            ExprKind::Array(elements) => {
+                let mut elements_v = Vec::with_capacity(elements.len());
+                for element in elements {
+                    elements_v.push(self.evaluate(element)?);
+                }
+                self.limits.check_collection("array", elements_v.len())?;
 
-                let mut arguments_v = Vec::with_capacity(arguments.len());
-                for argument in arguments {
-                    arguments_v.push(self.evaluate(argument)?);
+                Literal::Array(Rc::new(RefCell::new(elements_v)))
+            }
+            ExprKind::Map(entries) => {
+                let mut map = LoxMap::new();
+                for (key, value) in entries {
+                    let key = HashableLiteral::new(&self.evaluate(key)?)
+                        .map_err(InterpreterErrorKind::General)?;
+                    let value = self.evaluate(value)?;
+                    map.insert(key, value);
                 }
+                self.limits.check_collection("map", map.len())?;
 
-                callee_v.call(self, &arguments_v)?
+                Literal::Map(Rc::new(RefCell::new(map)))
+            }
+            ExprKind::Tuple(values) => {
+                let mut values_v = Vec::with_capacity(values.len());
+                for value in values {
+                    values_v.push(self.evaluate(value)?);
+                }
+
+                Literal::Tuple(values_v.into())
             }
             ExprKind::Unary(op, expr) => {
-                let right = self.evaluate(&expr)?;
+                let right = self.evaluate(expr)?;
 
-                match op {
-                    UnOp::BinNeg => {
-                        if let Literal::Number(n) = right {
-                            Literal::Number(-n)
-                        } else {
-                            return Err(InterpreterErrorKind::General(
-                                "Operand must be a number".into(),
-                            ));
-                        }
+                match (op, &right) {
+                    (UnOp::BinNeg, Literal::Number(n)) => Literal::Number(-n),
+                    (UnOp::BinNeg, Literal::Nil) if self.strictness == Strictness::Lenient => {
+                        self.warn_lenient(
+                            expr.span,
+                            "Operand of '-' must be a number, but it is nil.".into(),
+                            "treating nil as 0",
+                        );
+                        Literal::Number(0.0)
                     }
-                    UnOp::LogNeg => Literal::Bool(!is_truthy(&right)),
+                    (UnOp::BinNeg, other) => {
+                        return Err(InterpreterErrorKind::Located(
+                            format!(
+                                "Operand of '-' must be a number, but it is a {}{}.",
+                                other.type_name(),
+                                self.expression_source(expr.span)
+                            ),
+                            expr.span,
+                        ))
+                    }
+                    (UnOp::LogNeg, _) => Literal::Bool(!is_truthy(&right)),
                 }
             }
             ExprKind::Logical(op, lhs, rhs) => {
-                let left = self.evaluate(&lhs)?;
+                let left = self.evaluate(lhs)?;
 
                 match op {
                     LogOp::And if !is_truthy(&left) => left,
                     LogOp::Or if is_truthy(&left) => left,
+                    LogOp::Coalesce if !matches!(left, Literal::Nil) => left,
                     _ => self.evaluate(rhs)?,
                 }
             }
             ExprKind::Binary(op, lhs, rhs) => {
-                let left = self.evaluate(&lhs)?;
-                let right = self.evaluate(&rhs)?;
+                let left = self.evaluate(lhs)?;
+                let right = self.evaluate(rhs)?;
 
-                match op {
-                    BinOp::Minus => match (left, right) {
-                        (Literal::Number(n1), Literal::Number(n2)) => Literal::Number(n1 - n2),
-                        _ => {
-                            return Err(InterpreterErrorKind::General(
-                                "Operands must be numbers.".into(),
-                            ))
-                        }
-                    },
-                    BinOp::Plus => match (left, right) {
-                        (Literal::Number(n1), Literal::Number(n2)) => Literal::Number(n1 + n2),
-                        (Literal::String(s1), Literal::String(s2)) => {
-                            Literal::String(format!("{}{}", s1, s2))
-                        }
-                        _ => {
-                            return Err(InterpreterErrorKind::General(
-                                "Operands must be two numbers or two strings.".into(),
-                            ))
-                        }
-                    },
-                    BinOp::Multiply => match (left, right) {
-                        (Literal::Number(n1), Literal::Number(n2)) => Literal::Number(n1 * n2),
-                        _ => {
-                            return Err(InterpreterErrorKind::General(
-                                "Operands must be numbers.".into(),
-                            ))
-                        }
-                    },
-                    BinOp::Divide => match (left, right) {
-                        (Literal::Number(n1), Literal::Number(n2)) => Literal::Number(n1 / n2),
-                        _ => {
-                            return Err(InterpreterErrorKind::General(
-                                "Operands must be numbers.".into(),
-                            ))
-                        }
-                    },
-                    BinOp::Gt => match (left, right) {
-                        (Literal::Number(n1), Literal::Number(n2)) => Literal::Bool(n1 > n2),
-                        _ => {
-                            return Err(InterpreterErrorKind::General(
-                                "Operands must be numbers.".into(),
-                            ))
-                        }
-                    },
-                    BinOp::Ge => match (left, right) {
-                        (Literal::Number(n1), Literal::Number(n2)) => Literal::Bool(n1 >= n2),
-                        _ => {
-                            return Err(InterpreterErrorKind::General(
-                                "Operands must be numbers.".into(),
-                            ))
-                        }
-                    },
-                    BinOp::Lt => match (left, right) {
-                        (Literal::Number(n1), Literal::Number(n2)) => Literal::Bool(n1 < n2),
-                        _ => {
-                            return Err(InterpreterErrorKind::General(
-                                "Operands must be numbers.".into(),
-                            ))
-                        }
-                    },
-                    BinOp::Le => match (left, right) {
-                        (Literal::Number(n1), Literal::Number(n2)) => Literal::Bool(n1 <= n2),
-                        _ => {
-                            return Err(InterpreterErrorKind::General(
-                                "Operands must be numbers.".into(),
-                            ))
-                        }
-                    },
-                    BinOp::EqEq => Literal::Bool(is_equal(&left, &right)),
-                    BinOp::Ne => Literal::Bool(!is_equal(&left, &right)),
-                    _ => unimplemented!(),
+                let result = match binary(*op, &left, &right) {
+                    Ok(result) => result,
+                    Err(InterpreterErrorKind::General(message))
+                        if self.strictness == Strictness::Lenient =>
+                    {
+                        let Some((result, action)) = lenient_binary(*op, &left, &right) else {
+                            return Err(InterpreterErrorKind::General(message));
+                        };
+                        self.warn_lenient(expr.span, message, action);
+                        result
+                    }
+                    Err(e) => return Err(e),
+                };
+                if let Literal::String(s) = &result {
+                    self.limits.check_string(s)?;
                 }
+                result
             }
         };
 
         Ok(lit)
     }
+
+    /// Returns the environment a resolved variable lives in: `depth` scopes out from the
+    /// current one, or the globals for unresolved names.
+    fn environment_at(&self, depth: Option<usize>) -> Rc<RefCell<Environment>> {
+        match depth {
+            Some(depth) => Environment::ancestor(&self.environment, depth),
+            None => Rc::clone(&self.globals),
+        }
+    }
+
+    /// Evaluates a chain of calls and property accesses. Returns `None` when an optional
+    /// access (`?.`) found nil, which skips the remainder of the chain.
+    fn evaluate_chain(&mut self, expr: &Expr) -> Result<Option<Literal>, InterpreterErrorKind> {
+        self.evaluate_link(expr).map_err(|e| e.locate(expr.span))
+    }
+
+    fn evaluate_link(&mut self, expr: &Expr) -> Result<Option<Literal>, InterpreterErrorKind> {
+        let lit = match &expr.kind {
+            ExprKind::Call(callee, arguments) => {
+                let (callee_v, arguments_v) = match self.evaluate_call(callee, arguments)? {
+                    Some(call) => call,
+                    None => return Ok(None),
+                };
+
+                self.call_value(&callee_v, &arguments_v)?
+            }
+            ExprKind::Get(object, name, optional) => {
+                let object = match self.evaluate_chain(object)? {
+                    Some(Literal::Nil) if *optional => return Ok(None),
+                    Some(Literal::Nil) => {
+                        return Err(InterpreterErrorKind::General(format!(
+                            "Attempted to read property '{}' of nil{}.",
+                            name.lexeme,
+                            self.expression_source(expr.span)
+                        )))
+                    }
+                    Some(object) => object,
+                    None => return Ok(None),
+                };
+
+                if let Literal::Instance(instance) = &object {
+                    return match class::get_property(instance, &name.lexeme, &mut self.method_cache)
+                    {
+                        Some(value) => Ok(Some(value)),
+                        None => Err(InterpreterErrorKind::General(format!(
+                            "Undefined property '{}' on {} instance.",
+                            name.lexeme,
+                            instance.borrow().class.name
+                        ))),
+                    };
+                }
+                if let Literal::Enum(lox_enum) = &object {
+                    if let Some(variant) = lox_enum.variant(&name.lexeme) {
+                        return Ok(Some(Literal::EnumValue(Rc::clone(variant))));
+                    }
+                    if !methods::is_enum_method(&name.lexeme) {
+                        return Err(InterpreterErrorKind::General(format!(
+                            "Enum {} has no variant '{}'.",
+                            lox_enum.name, name.lexeme
+                        )));
+                    }
+                }
+                let method = methods::find_method(&object, name.lexeme.as_str())?;
+
+                Literal::Callable(LoxCallable::Other(Rc::new(method)))
+            }
+            _ => self.evaluate(expr)?,
+        };
+
+        Ok(Some(lit))
+    }
+
+    /// Evaluates the callee and arguments of a call, or returns `None` if the callee
+    /// short-circuited through `?.`.
+    fn evaluate_call(
+        &mut self,
+        callee: &Expr,
+        arguments: &[Expr],
+    ) -> Result<Option<(LoxCallable, Vec<Literal>)>, InterpreterErrorKind> {
+        let callee_v = match self.evaluate_chain(callee)? {
+            Some(Literal::Callable(callable)) => callable,
+            Some(Literal::Nil) => {
+                return Err(InterpreterErrorKind::General(format!(
+                    "Attempted to call nil{}.",
+                    self.expression_source(callee.span)
+                )))
+            }
+            Some(_) => {
+                return Err(InterpreterErrorKind::General(
+                    "Can only call functions and classes".into(),
+                ))
+            }
+            None => return Ok(None),
+        };
+
+        let mut arguments_v = Vec::with_capacity(arguments.len());
+        for argument in arguments {
+            arguments_v.push(self.evaluate(argument)?);
+        }
+
+        Ok(Some((callee_v, arguments_v)))
+    }
+
+    /// Handles `return f(...)`. Calls to Lox functions are handed back to the enclosing
+    /// LoxFunction::call as a TailCall so they run in its loop instead of growing the stack.
+    /// A function with statements still deferred makes an ordinary call instead, since those
+    /// have to run after the value it returns is computed.
+    fn tail_call(&mut self, callee: &Expr, arguments: &[Expr]) -> Result<(), InterpreterErrorKind> {
+        let (callee_v, arguments_v) = match self.evaluate_call(callee, arguments)? {
+            Some(call) => call,
+            None => return Err(InterpreterErrorKind::Return(None)),
+        };
+
+        match callee_v {
+            // An initializer has to return its instance, which only its own call arranges.
+            LoxCallable::Function(function)
+                if function.arity().accepts(arguments_v.len())
+                    && !function.is_initializer()
+                    && !self.has_pending_defers() =>
+            {
+                Err(InterpreterErrorKind::TailCall(function, arguments_v))
+            }
+            callee_v => {
+                let value = self.call_value(&callee_v, &arguments_v)?;
+                Err(InterpreterErrorKind::Return(Some(value)))
+            }
+        }
+    }
+
+    pub fn call_value(
+        &mut self,
+        callee: &LoxCallable,
+        arguments: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        if !callee.arity().accepts(arguments.len()) {
+            return Err(InterpreterErrorKind::General(format!(
+                "Expected {} arguments but got {}.",
+                callee.arity(),
+                arguments.len()
+            )));
+        }
+
+        log_event!(
+            "[line {}] calling {} (arity {}) with {} arguments",
+            self.span.line,
+            callee,
+            callee.arity(),
+            arguments.len()
+        );
+        self.call_stack.push(CallFrame {
+            function: callee.clone(),
+            span: self.span,
+        });
+        self.stats.calls += 1;
+        self.stats.max_call_depth = self.stats.max_call_depth.max(self.call_stack.len() as u64);
+        let result = callee.call(self, arguments);
+
+        if let Some(e) = result.as_ref().err().and_then(|e| e.message()) {
+            log_event!("error propagating out of {}: {}", callee, e);
+            // The innermost call is the first to see the error, with the whole stack intact.
+            if self.error_trace.is_none() {
+                self.error_trace = Some(self.backtrace());
+            }
+        }
+        if let Some(frame) = self.call_stack.pop() {
+            self.span = frame.span;
+        }
+
+        result
+    }
+}
+
+/// Applies a binary operator to two evaluated operands without taking ownership of them, so
+/// only the result gets allocated.
+fn binary(op: BinOp, left: &Literal, right: &Literal) -> Result<Literal, InterpreterErrorKind> {
+    // Arithmetic and comparisons on numbers are by far the most common case.
+    if let (Literal::Number(n1), Literal::Number(n2)) = (left, right) {
+        let (n1, n2) = (*n1, *n2);
+
+        return Ok(match op {
+            BinOp::Plus => Literal::Number(n1 + n2),
+            BinOp::Minus => Literal::Number(n1 - n2),
+            BinOp::Multiply => Literal::Number(n1 * n2),
+            BinOp::Divide => Literal::Number(n1 / n2),
+            BinOp::Gt => Literal::Bool(n1 > n2),
+            BinOp::Ge => Literal::Bool(n1 >= n2),
+            BinOp::Lt => Literal::Bool(n1 < n2),
+            BinOp::Le => Literal::Bool(n1 <= n2),
+            BinOp::EqEq => Literal::Bool(n1 == n2),
+            BinOp::Ne => Literal::Bool(n1 != n2),
+            BinOp::Eq => return Err(assignment_as_operator()),
+        });
+    }
+
+    let lit = match op {
+        BinOp::Plus => match (left, right) {
+            (Literal::String(s1), Literal::String(s2)) => {
+                let mut concatenated = String::with_capacity(s1.len() + s2.len());
+                concatenated.push_str(s1);
+                concatenated.push_str(s2);
+                Literal::String(concatenated.into())
+            }
+            _ => {
+                return Err(InterpreterErrorKind::General(
+                    "Operands must be two numbers or two strings.".into(),
+                ))
+            }
+        },
+        BinOp::Minus
+        | BinOp::Multiply
+        | BinOp::Divide
+        | BinOp::Gt
+        | BinOp::Ge
+        | BinOp::Lt
+        | BinOp::Le => return Err(operands_must_be_numbers(op, left, right)),
+        BinOp::EqEq => Literal::Bool(is_equal(left, right)),
+        BinOp::Ne => Literal::Bool(!is_equal(left, right)),
+        BinOp::Eq => return Err(assignment_as_operator()),
+    };
+
+    Ok(lit)
+}
+
+/// What lenient mode makes of a binary operation `binary` rejected, and how to describe
+/// that, or `None` if it rejects it too.
+fn lenient_binary(op: BinOp, left: &Literal, right: &Literal) -> Option<(Literal, &'static str)> {
+    let arithmetic = matches!(
+        op,
+        BinOp::Plus | BinOp::Minus | BinOp::Multiply | BinOp::Divide
+    );
+    match (left, right) {
+        (Literal::Nil, Literal::Number(_)) if arithmetic => {
+            let result = binary(op, &Literal::Number(0.0), right).ok()?;
+            Some((result, "treating nil as 0"))
+        }
+        (Literal::Number(_), Literal::Nil) if arithmetic => {
+            let result = binary(op, left, &Literal::Number(0.0)).ok()?;
+            Some((result, "treating nil as 0"))
+        }
+        (Literal::String(_), Literal::Number(_)) | (Literal::Number(_), Literal::String(_))
+            if op == BinOp::Plus =>
+        {
+            let result = Literal::String(format!("{}{}", left, right).into());
+            Some((result, "converting the number to a string"))
+        }
+        _ if matches!(op, BinOp::Gt | BinOp::Ge | BinOp::Lt | BinOp::Le)
+            && left.type_name() != right.type_name() =>
+        {
+            Some((Literal::Bool(false), "comparing the values as false"))
+        }
+        _ => None,
+    }
+}
+
+/// The error for `op` applied to operands that aren't both numbers, naming the ones that
+/// aren't. A comparison of a Bool is most likely a chained comparison like `0 < x < 10`.
+fn operands_must_be_numbers(op: BinOp, left: &Literal, right: &Literal) -> InterpreterErrorKind {
+    let mut message = match (left, right) {
+        (Literal::Number(_), other) => format!(
+            "Operands must be numbers, but the right operand is a {}.",
+            other.type_name()
+        ),
+        (other, Literal::Number(_)) => format!(
+            "Operands must be numbers, but the left operand is a {}.",
+            other.type_name()
+        ),
+        _ => format!(
+            "Operands must be numbers, but they are a {} and a {}.",
+            left.type_name(),
+            right.type_name()
+        ),
+    };
+
+    let comparison = matches!(op, BinOp::Gt | BinOp::Ge | BinOp::Lt | BinOp::Le);
+    if comparison && matches!(left, Literal::Bool(_)) {
+        message.push_str(" Comparisons can't be chained; join them with 'and' instead.");
+    }
+    InterpreterErrorKind::General(message)
+}
+
+/// The element of `value` at `index`: a one-character string for a string, counting in
+/// characters the way `len()` does, or an element of an array or tuple.
+fn element_at(value: &Literal, index: &Literal) -> Result<Literal, InterpreterErrorKind> {
+    match value {
+        Literal::String(s) => {
+            let index = checked_index(value, index, s.chars().count())?;
+            Ok(Literal::from(
+                s.chars().skip(index).take(1).collect::<String>(),
+            ))
+        }
+        Literal::Array(elements) => {
+            let elements = elements.borrow();
+            Ok(elements[checked_index(value, index, elements.len())?].clone())
+        }
+        Literal::Tuple(values) => Ok(values[checked_index(value, index, values.len())?].clone()),
+        other => Err(InterpreterErrorKind::General(format!(
+            "Can only index a String, Array or Tuple, not a {}.",
+            other.type_name()
+        ))),
+    }
 }
 
-fn is_truthy(val: &Literal) -> bool {
+/// `index` as a position in `value`, which has `len` elements.
+fn checked_index(
+    value: &Literal,
+    index: &Literal,
+    len: usize,
+) -> Result<usize, InterpreterErrorKind> {
+    if len == 0 {
+        return Err(InterpreterErrorKind::General(format!(
+            "Can't index an empty {}.",
+            value.type_name()
+        )));
+    }
+
+    index
+        .as_index(len - 1)
+        .map_err(|error| InterpreterErrorKind::General(format!("Index {}.", error)))
+}
+
+/// What a `for`-`in` loop over `value` goes through: the characters of a string, or the
+/// elements an array or tuple has when the loop starts.
+fn elements(value: &Literal) -> Result<Vec<Literal>, InterpreterErrorKind> {
+    match value {
+        Literal::String(s) => Ok(s.chars().map(|c| Literal::from(c.to_string())).collect()),
+        Literal::Array(elements) => Ok(elements.borrow().clone()),
+        Literal::Tuple(values) => Ok(values.to_vec()),
+        other => Err(InterpreterErrorKind::General(format!(
+            "Can only loop over a String, Array or Tuple, not a {}.",
+            other.type_name()
+        ))),
+    }
+}
+
+/// The parser turns `=` into an assignment, so it never reaches `binary` as an operator.
+fn assignment_as_operator() -> InterpreterErrorKind {
+    InterpreterErrorKind::Internal("'=' reached the evaluator as a binary operator.".into())
+}
+
+pub fn is_truthy(val: &Literal) -> bool {
     match val {
         Literal::Nil => false,
         Literal::Bool(b) => *b,
@@ -294,12 +1910,48 @@ fn is_truthy(val: &Literal) -> bool {
     }
 }
 
-fn is_equal(lhs: &Literal, rhs: &Literal) -> bool {
+pub fn is_equal(lhs: &Literal, rhs: &Literal) -> bool {
     match (lhs, rhs) {
         (Literal::Nil, Literal::Nil) => true,
         (Literal::Number(n1), Literal::Number(n2)) => n1 == n2,
         (Literal::String(s1), Literal::String(s2)) => s1 == s2,
         (Literal::Bool(b1), Literal::Bool(b2)) => b1 == b2,
+        (Literal::Array(a1), Literal::Array(a2)) => Rc::ptr_eq(a1, a2),
+        (Literal::Map(m1), Literal::Map(m2)) => Rc::ptr_eq(m1, m2),
+        (Literal::Tuple(t1), Literal::Tuple(t2)) => {
+            t1.len() == t2.len() && t1.iter().zip(t2.iter()).all(|(a, b)| is_equal(a, b))
+        }
+        (Literal::Enum(e1), Literal::Enum(e2)) => Rc::ptr_eq(e1, e2),
+        (Literal::EnumValue(v1), Literal::EnumValue(v2)) => Rc::ptr_eq(v1, v2),
+        (Literal::Instance(i1), Literal::Instance(i2)) => Rc::ptr_eq(i1, i2),
+        (Literal::WeakRef(w1), Literal::WeakRef(w2)) => Rc::ptr_eq(w1, w2),
         _ => false,
     }
 }
+
+/// The function a method declared in a class or extension body makes, with `closure` as its
+/// enclosing scope, along with the method's name.
+fn method_function<'a>(
+    method: &'a Stmt,
+    closure: &Rc<RefCell<Environment>>,
+) -> Option<(&'a Token, Rc<LoxFunction>)> {
+    let StmtKind::Function(name, params, body, doc) = &method.kind else {
+        return None;
+    };
+
+    let function = LoxFunction::new(
+        name.lexeme.clone(),
+        name.line,
+        params.to_vec(),
+        body.to_vec(),
+        Rc::clone(closure),
+    )
+    .with_doc(doc.as_deref());
+    let function = if name.lexeme == "init" {
+        function.initializer()
+    } else {
+        function
+    };
+
+    Some((name, Rc::new(function)))
+}
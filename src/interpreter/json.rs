@@ -0,0 +1,436 @@
+//! The mapping between Lox values and JSON text behind `jsonEncode()` and `jsonDecode()`.
+//!
+//! Numbers, strings, booleans and nil map to their JSON counterparts, arrays to arrays and maps
+//! to objects. Objects decode into maps that keep the order their keys were written in.
+
+use std::{cell::RefCell, rc::Rc};
+
+use crate::ast::expr::Literal;
+
+use super::{
+    map::{HashableLiteral, LoxMap},
+    number::format_number,
+};
+
+/// How deeply arrays and objects may nest, in either direction, before giving up rather than
+/// overflowing the stack.
+const MAX_DEPTH: usize = 512;
+
+/// Encodes `value` as compact JSON. The error names the path to the value that has no JSON
+/// form, such as `$.config.handler`.
+pub fn encode(value: &Literal) -> Result<String, String> {
+    let mut encoder = Encoder {
+        json: String::new(),
+        path: vec![],
+        open: vec![],
+    };
+    encoder.value(value)?;
+    Ok(encoder.json)
+}
+
+/// Decodes JSON text into a Lox value. The error gives the byte offset where the text stopped
+/// making sense.
+pub fn decode(json: &str) -> Result<Literal, String> {
+    let mut decoder = Decoder {
+        json: json.as_bytes(),
+        position: 0,
+        depth: 0,
+    };
+
+    decoder.skip_whitespace();
+    let value = decoder.value()?;
+    decoder.skip_whitespace();
+
+    if decoder.position < json.len() {
+        return Err(decoder.error("unexpected text after the JSON value"));
+    }
+    Ok(value)
+}
+
+enum PathSegment {
+    Index(usize),
+    Key(String),
+}
+
+struct Encoder {
+    json: String,
+    path: Vec<PathSegment>,
+    /// The arrays and maps being encoded, to catch one that contains itself.
+    open: Vec<*const ()>,
+}
+
+impl Encoder {
+    fn value(&mut self, value: &Literal) -> Result<(), String> {
+        match value {
+            Literal::Nil => self.json.push_str("null"),
+            Literal::Bool(b) => self.json.push_str(&b.to_string()),
+            Literal::Number(n) if n.is_finite() => self.json.push_str(&format_number(*n)),
+            Literal::Number(n) => {
+                return Err(self.error(&format!("{} has no JSON representation", format_number(*n))))
+            }
+            Literal::String(s) => self.string(s),
+            Literal::Array(elements) => {
+                self.array(Rc::as_ptr(elements) as *const (), &elements.borrow())?
+            }
+            // JSON has no tuples; one is encoded as the array of its values.
+            Literal::Tuple(values) => self.array(Rc::as_ptr(values) as *const (), values)?,
+            Literal::Map(entries) => {
+                self.enter(Rc::as_ptr(entries) as *const ())?;
+                self.json.push('{');
+                for (index, (key, value)) in entries.borrow().iter().enumerate() {
+                    let HashableLiteral::String(key) = key else {
+                        return Err(self.error(&format!(
+                            "the number key {} has no JSON representation; JSON keys are strings",
+                            key
+                        )));
+                    };
+                    if index > 0 {
+                        self.json.push(',');
+                    }
+                    self.string(key);
+                    self.json.push(':');
+                    self.path.push(PathSegment::Key(key.clone()));
+                    self.value(value)?;
+                    self.path.pop();
+                }
+                self.json.push('}');
+                self.open.pop();
+            }
+            Literal::Callable(_) => {
+                return Err(self.error("a function has no JSON representation"));
+            }
+            Literal::Enum(_) | Literal::EnumValue(_) => {
+                return Err(self.error("an enum has no JSON representation"));
+            }
+            Literal::Instance(_) => {
+                return Err(self.error("an instance has no JSON representation"));
+            }
+            Literal::WeakRef(_) => {
+                return Err(self.error("a weak reference has no JSON representation"));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn array(&mut self, address: *const (), elements: &[Literal]) -> Result<(), String> {
+        self.enter(address)?;
+        self.json.push('[');
+        for (index, element) in elements.iter().enumerate() {
+            if index > 0 {
+                self.json.push(',');
+            }
+            self.path.push(PathSegment::Index(index));
+            self.value(element)?;
+            self.path.pop();
+        }
+        self.json.push(']');
+        self.open.pop();
+        Ok(())
+    }
+
+    fn enter(&mut self, container: *const ()) -> Result<(), String> {
+        if self.open.contains(&container) {
+            return Err(self.error("the value contains itself"));
+        }
+        if self.open.len() == MAX_DEPTH {
+            return Err(self.error(&format!("values nest deeper than {} levels", MAX_DEPTH)));
+        }
+
+        self.open.push(container);
+        Ok(())
+    }
+
+    fn string(&mut self, s: &str) {
+        self.json.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => self.json.push_str("\\\""),
+                '\\' => self.json.push_str("\\\\"),
+                '\n' => self.json.push_str("\\n"),
+                '\r' => self.json.push_str("\\r"),
+                '\t' => self.json.push_str("\\t"),
+                c if (c as u32) < 0x20 => self.json.push_str(&format!("\\u{:04x}", c as u32)),
+                c => self.json.push(c),
+            }
+        }
+        self.json.push('"');
+    }
+
+    fn error(&self, reason: &str) -> String {
+        let mut path = String::from("$");
+        for segment in &self.path {
+            match segment {
+                PathSegment::Index(index) => path.push_str(&format!("[{}]", index)),
+                PathSegment::Key(key) if is_identifier(key) => {
+                    path.push('.');
+                    path.push_str(key);
+                }
+                PathSegment::Key(key) => path.push_str(&format!("[{:?}]", key)),
+            }
+        }
+
+        format!("Cannot encode {} as JSON: {}.", path, reason)
+    }
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+struct Decoder<'a> {
+    json: &'a [u8],
+    position: usize,
+    depth: usize,
+}
+
+impl Decoder<'_> {
+    fn value(&mut self) -> Result<Literal, String> {
+        match self.peek() {
+            Some(b'{') => self.nested(Self::object),
+            Some(b'[') => self.nested(Self::array),
+            Some(b'"') => Ok(Literal::String(self.string()?.into())),
+            Some(b't') => self.keyword("true", Literal::Bool(true)),
+            Some(b'f') => self.keyword("false", Literal::Bool(false)),
+            Some(b'n') => self.keyword("null", Literal::Nil),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("expected a JSON value")),
+            None => Err(self.error("the text ended where a value was expected")),
+        }
+    }
+
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<Literal, String>,
+    ) -> Result<Literal, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error(&format!("values nest deeper than {} levels", MAX_DEPTH)));
+        }
+
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn array(&mut self) -> Result<Literal, String> {
+        self.position += 1;
+        let mut elements = vec![];
+
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+        } else {
+            loop {
+                self.skip_whitespace();
+                elements.push(self.value()?);
+                self.skip_whitespace();
+
+                match self.peek() {
+                    Some(b',') => self.position += 1,
+                    Some(b']') => {
+                        self.position += 1;
+                        break;
+                    }
+                    _ => return Err(self.error("expected ',' or ']' in array")),
+                }
+            }
+        }
+
+        Ok(Literal::Array(Rc::new(RefCell::new(elements))))
+    }
+
+    fn object(&mut self) -> Result<Literal, String> {
+        self.position += 1;
+        let mut entries = LoxMap::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+        } else {
+            loop {
+                self.skip_whitespace();
+                if self.peek() != Some(b'"') {
+                    return Err(self.error("expected a string key in object"));
+                }
+                let key = self.string()?;
+
+                self.skip_whitespace();
+                if self.peek() != Some(b':') {
+                    return Err(self.error("expected ':' after object key"));
+                }
+                self.position += 1;
+                self.skip_whitespace();
+                entries.insert(key.into(), self.value()?);
+                self.skip_whitespace();
+
+                match self.peek() {
+                    Some(b',') => self.position += 1,
+                    Some(b'}') => {
+                        self.position += 1;
+                        break;
+                    }
+                    _ => return Err(self.error("expected ',' or '}' in object")),
+                }
+            }
+        }
+
+        Ok(Literal::Map(Rc::new(RefCell::new(entries))))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.position += 1;
+        let mut bytes = vec![];
+
+        loop {
+            match self.peek() {
+                None => return Err(self.error("unterminated string")),
+                Some(b'"') => {
+                    self.position += 1;
+                    break;
+                }
+                Some(b'\\') => {
+                    self.position += 1;
+                    let escaped = match self.peek() {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => {
+                            self.position += 1;
+                            let c = self.unicode_escape()?;
+                            let mut buffer = [0; 4];
+                            bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+                            continue;
+                        }
+                        _ => return Err(self.error("invalid escape sequence")),
+                    };
+                    self.position += 1;
+                    bytes.push(escaped as u8);
+                }
+                Some(byte) if byte < 0x20 => {
+                    return Err(self.error("control characters must be escaped in strings"))
+                }
+                Some(byte) => {
+                    self.position += 1;
+                    bytes.push(byte);
+                }
+            }
+        }
+
+        // The input came from a `&str` and only whole characters were copied, so this can only
+        // fail if that reasoning is wrong.
+        String::from_utf8(bytes).map_err(|_| self.error("invalid UTF-8 in string"))
+    }
+
+    /// The character of a `\u` escape whose `\u` has been consumed, combining a surrogate pair
+    /// when the first escape is a high surrogate.
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.hex4()?;
+        let code = if (0xD800..0xDC00).contains(&high) {
+            if self.json.get(self.position..self.position + 2) != Some(b"\\u") {
+                return Err(self.error("expected a low surrogate after a high surrogate"));
+            }
+            self.position += 2;
+            let low = self.hex4()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(self.error("expected a low surrogate after a high surrogate"));
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+
+        char::from_u32(code).ok_or_else(|| self.error("unpaired surrogate in \\u escape"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self
+            .json
+            .get(self.position..self.position + 4)
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("expected four hex digits after \\u"))?;
+        let code = u32::from_str_radix(digits, 16).map_err(|_| self.error("invalid \\u escape"))?;
+
+        self.position += 4;
+        Ok(code)
+    }
+
+    fn number(&mut self) -> Result<Literal, String> {
+        let start = self.position;
+
+        self.eat(b'-');
+        match self.peek() {
+            Some(b'0') => self.position += 1,
+            Some(b'1'..=b'9') => self.digits(),
+            _ => return Err(self.error("expected a digit")),
+        }
+        if self.eat(b'.') {
+            if !self.peek().is_some_and(|b| b.is_ascii_digit()) {
+                return Err(self.error("expected a digit after the decimal point"));
+            }
+            self.digits();
+        }
+        if self.eat(b'e') || self.eat(b'E') {
+            if !self.eat(b'+') {
+                self.eat(b'-');
+            }
+            if !self.peek().is_some_and(|b| b.is_ascii_digit()) {
+                return Err(self.error("expected a digit in the exponent"));
+            }
+            self.digits();
+        }
+
+        std::str::from_utf8(&self.json[start..self.position])
+            .ok()
+            .and_then(|text| text.parse().ok())
+            .map(Literal::Number)
+            .ok_or_else(|| self.error("invalid number"))
+    }
+
+    fn digits(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.position += 1;
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Literal) -> Result<Literal, String> {
+        if self.json[self.position..].starts_with(keyword.as_bytes()) {
+            self.position += keyword.len();
+            Ok(value)
+        } else {
+            Err(self.error("expected a JSON value"))
+        }
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let matched = self.peek() == Some(byte);
+        if matched {
+            self.position += 1;
+        }
+        matched
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.json.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.position += 1;
+        }
+    }
+
+    fn error(&self, reason: &str) -> String {
+        format!("Invalid JSON at byte {}: {}.", self.position, reason)
+    }
+}
@@ -0,0 +1,136 @@
+use std::{
+    borrow::Borrow,
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    hash::Hash,
+};
+
+use crate::ast::expr::Literal;
+
+/// A value usable as a map key: a string or a number. Numbers are keys by value, so `1` and
+/// `1.0` are the same key, as are `0` and `-0`, but the number `1` and the string `"1"` are
+/// different keys. NaN can't be a key, since it isn't equal to anything, itself included.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum HashableLiteral {
+    String(String),
+    /// The bits of the number, with `-0` stored as `0`.
+    Number(u64),
+}
+
+impl HashableLiteral {
+    /// The key for `value`, or why it can't be one.
+    pub fn new(value: &Literal) -> Result<HashableLiteral, String> {
+        match value {
+            Literal::String(s) => Ok(HashableLiteral::String(s.to_string())),
+            Literal::Number(n) if n.is_nan() => Err(
+                "NaN can't be a map key: it isn't equal to itself, so it could never be found \
+                 again."
+                    .into(),
+            ),
+            // Adding 0 turns -0 into 0 and leaves every other number alone.
+            Literal::Number(n) => Ok(HashableLiteral::Number((n + 0.0).to_bits())),
+            other => Err(format!(
+                "Map keys must be Strings or Numbers, got {}.",
+                other.type_name()
+            )),
+        }
+    }
+
+    pub fn to_literal(&self) -> Literal {
+        match self {
+            HashableLiteral::String(s) => Literal::String(s.as_str().into()),
+            HashableLiteral::Number(bits) => Literal::Number(f64::from_bits(*bits)),
+        }
+    }
+}
+
+impl From<&str> for HashableLiteral {
+    fn from(s: &str) -> Self {
+        HashableLiteral::String(s.to_string())
+    }
+}
+
+impl From<String> for HashableLiteral {
+    fn from(s: String) -> Self {
+        HashableLiteral::String(s)
+    }
+}
+
+/// Strings as they are, numbers as Lox prints them.
+impl Display for HashableLiteral {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            HashableLiteral::String(s) => write!(f, "{}", s),
+            HashableLiteral::Number(_) => write!(f, "{}", self.to_literal()),
+        }
+    }
+}
+
+/// A map that iterates in insertion order. Lox maps are keyed by `HashableLiteral`; the
+/// fields of instances are keyed by name.
+#[derive(Clone, Debug)]
+pub struct LoxMap<K = HashableLiteral> {
+    keys: Vec<K>,
+    values: HashMap<K, Literal>,
+}
+
+impl<K> Default for LoxMap<K> {
+    fn default() -> Self {
+        Self {
+            keys: vec![],
+            values: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash> LoxMap<K> {
+    pub fn new() -> LoxMap<K> {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&Literal>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.values.get(key)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.values.contains_key(key)
+    }
+
+    /// Inserts or overwrites `key`. Overwriting keeps the key's original position.
+    pub fn insert(&mut self, key: K, value: Literal) {
+        if self.values.insert(key.clone(), value).is_none() {
+            self.keys.push(key);
+        }
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<Literal>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let value = self.values.remove(key)?;
+        self.keys.retain(|k| k.borrow() != key);
+
+        Some(value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &Literal)> {
+        self.keys.iter().map(move |key| (key, &self.values[key]))
+    }
+}
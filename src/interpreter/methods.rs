@@ -0,0 +1,1000 @@
+use std::{cell::RefCell, cmp::Ordering, fmt::Display, rc::Rc};
+
+use crate::ast::expr::Literal;
+
+use super::{
+    callable::{argument, Arity, Callable, LoxCallable},
+    enums::{EnumValue, LoxEnum},
+    identity::LoxWeakRef,
+    interpreter::{is_equal, is_truthy, Interpreter},
+    map::{HashableLiteral, LoxMap},
+    number::format_number,
+    InterpreterErrorKind,
+};
+
+type MethodFn = fn(&mut Interpreter, &Literal, &[Literal]) -> Result<Literal, InterpreterErrorKind>;
+
+#[derive(Debug)]
+pub struct PrimitiveMethod {
+    name: &'static str,
+    arity: Arity,
+    /// One line describing the method, shown by `help`.
+    doc: &'static str,
+    fun: MethodFn,
+}
+
+const STRING_METHODS: &[PrimitiveMethod] = &[
+    PrimitiveMethod {
+        name: "len",
+        arity: Arity::Exact(0),
+        doc: "Returns the number of characters in the string.",
+        fun: string_len,
+    },
+    PrimitiveMethod {
+        name: "split",
+        arity: Arity::Exact(1),
+        doc: "Splits the string on every occurrence of a separator.",
+        fun: string_split,
+    },
+    PrimitiveMethod {
+        name: "trim",
+        arity: Arity::Exact(0),
+        doc: "Returns the string without leading and trailing whitespace.",
+        fun: string_trim,
+    },
+    PrimitiveMethod {
+        name: "contains",
+        arity: Arity::Exact(1),
+        doc: "Tells whether the string contains another string.",
+        fun: string_contains,
+    },
+    PrimitiveMethod {
+        name: "replace",
+        arity: Arity::Exact(2),
+        doc: "Replaces every occurrence of a string with another.",
+        fun: string_replace,
+    },
+];
+
+const NUMBER_METHODS: &[PrimitiveMethod] = &[
+    PrimitiveMethod {
+        name: "floor",
+        arity: Arity::Exact(0),
+        doc: "Rounds the number down to an integer.",
+        fun: number_floor,
+    },
+    PrimitiveMethod {
+        name: "abs",
+        arity: Arity::Exact(0),
+        doc: "Returns the absolute value of the number.",
+        fun: number_abs,
+    },
+    PrimitiveMethod {
+        name: "toString",
+        arity: Arity::Range(0, 1),
+        doc: "Formats the number as a string, in the given base from 2 to 36 if there is one.",
+        fun: number_to_string,
+    },
+    PrimitiveMethod {
+        name: "toFixed",
+        arity: Arity::Exact(1),
+        doc: "Formats the number with exactly the given number of decimal places.",
+        fun: number_to_fixed,
+    },
+    PrimitiveMethod {
+        name: "toPrecision",
+        arity: Arity::Exact(1),
+        doc: "Formats the number with the given number of significant digits.",
+        fun: number_to_precision,
+    },
+];
+
+const ARRAY_METHODS: &[PrimitiveMethod] = &[
+    PrimitiveMethod {
+        name: "len",
+        arity: Arity::Exact(0),
+        doc: "Returns the number of elements.",
+        fun: array_len,
+    },
+    PrimitiveMethod {
+        name: "push",
+        arity: Arity::Exact(1),
+        doc: "Appends an element to the end.",
+        fun: array_push,
+    },
+    PrimitiveMethod {
+        name: "pop",
+        arity: Arity::Exact(0),
+        doc: "Removes and returns the last element.",
+        fun: array_pop,
+    },
+    PrimitiveMethod {
+        name: "insert",
+        arity: Arity::Exact(2),
+        doc: "Inserts an element at an index.",
+        fun: array_insert,
+    },
+    PrimitiveMethod {
+        name: "remove",
+        arity: Arity::Exact(1),
+        doc: "Removes and returns the element at an index.",
+        fun: array_remove,
+    },
+    PrimitiveMethod {
+        name: "indexOf",
+        arity: Arity::Exact(1),
+        doc: "Returns the index of the first element equal to a value, or -1.",
+        fun: array_index_of,
+    },
+    PrimitiveMethod {
+        name: "sort",
+        arity: Arity::Range(0, 1),
+        doc: "Sorts the array in place, optionally with a comparison function.",
+        fun: array_sort,
+    },
+    PrimitiveMethod {
+        name: "map",
+        arity: Arity::Exact(1),
+        doc: "Returns a new array with a function applied to every element.",
+        fun: array_map,
+    },
+    PrimitiveMethod {
+        name: "filter",
+        arity: Arity::Exact(1),
+        doc: "Returns a new array with the elements a function returns true for.",
+        fun: array_filter,
+    },
+];
+
+const MAP_METHODS: &[PrimitiveMethod] = &[
+    PrimitiveMethod {
+        name: "len",
+        arity: Arity::Exact(0),
+        doc: "Returns the number of entries.",
+        fun: map_len,
+    },
+    PrimitiveMethod {
+        name: "get",
+        arity: Arity::Exact(1),
+        doc: "Returns the value stored under a key, or nil.",
+        fun: map_get,
+    },
+    PrimitiveMethod {
+        name: "set",
+        arity: Arity::Exact(2),
+        doc: "Stores a value under a key.",
+        fun: map_set,
+    },
+    PrimitiveMethod {
+        name: "has",
+        arity: Arity::Exact(1),
+        doc: "Tells whether a key is present.",
+        fun: map_has,
+    },
+    PrimitiveMethod {
+        name: "remove",
+        arity: Arity::Exact(1),
+        doc: "Removes a key and returns its value.",
+        fun: map_remove,
+    },
+    PrimitiveMethod {
+        name: "keys",
+        arity: Arity::Exact(0),
+        doc: "Returns the keys as an array.",
+        fun: map_keys,
+    },
+    PrimitiveMethod {
+        name: "values",
+        arity: Arity::Exact(0),
+        doc: "Returns the values as an array.",
+        fun: map_values,
+    },
+    PrimitiveMethod {
+        name: "merge",
+        arity: Arity::Exact(1),
+        doc: "Returns a new map with the entries of both maps, preferring the argument's.",
+        fun: map_merge,
+    },
+];
+
+const ENUM_METHODS: &[PrimitiveMethod] = &[
+    PrimitiveMethod {
+        name: "values",
+        arity: Arity::Exact(0),
+        doc: "Returns the variants as an array, in the order they were declared.",
+        fun: enum_values,
+    },
+    PrimitiveMethod {
+        name: "contains",
+        arity: Arity::Exact(1),
+        doc: "Tells whether a value is one of the enum's variants.",
+        fun: enum_contains,
+    },
+];
+
+const ENUM_VALUE_METHODS: &[PrimitiveMethod] = &[
+    PrimitiveMethod {
+        name: "name",
+        arity: Arity::Exact(0),
+        doc: "Returns the variant's name without its enum's.",
+        fun: enum_value_name,
+    },
+    PrimitiveMethod {
+        name: "ordinal",
+        arity: Arity::Exact(0),
+        doc: "Returns the variant's position in its enum, from 0.",
+        fun: enum_value_ordinal,
+    },
+];
+
+const WEAK_REF_METHODS: &[PrimitiveMethod] = &[PrimitiveMethod {
+    name: "get",
+    arity: Arity::Exact(0),
+    doc: "Returns the object, or nil once nothing else keeps it alive.",
+    fun: weak_ref_get,
+}];
+
+/// Tells whether `name` is a method of enums, and so can't also name one of their variants.
+pub fn is_enum_method(name: &str) -> bool {
+    ENUM_METHODS.iter().any(|method| method.name == name)
+}
+
+/// Looks up `name` in the method table of the receiver's type and binds it to the receiver.
+pub fn find_method(receiver: &Literal, name: &str) -> Result<BoundMethod, InterpreterErrorKind> {
+    let methods = match receiver {
+        Literal::String(_) => STRING_METHODS,
+        Literal::Number(_) => NUMBER_METHODS,
+        Literal::Array(_) => ARRAY_METHODS,
+        Literal::Map(_) => MAP_METHODS,
+        Literal::Enum(_) => ENUM_METHODS,
+        Literal::EnumValue(_) => ENUM_VALUE_METHODS,
+        Literal::WeakRef(_) => WEAK_REF_METHODS,
+        _ => &[],
+    };
+
+    match methods.iter().find(|method| method.name == name) {
+        Some(method) => Ok(BoundMethod {
+            receiver: receiver.clone(),
+            method,
+        }),
+        None => Err(InterpreterErrorKind::General(format!(
+            "{} has no method '{}'",
+            receiver.type_name(),
+            name
+        ))),
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BoundMethod {
+    receiver: Literal,
+    method: &'static PrimitiveMethod,
+}
+
+impl Callable for BoundMethod {
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        (self.method.fun)(interpreter, &self.receiver, args)
+    }
+
+    fn arity(&self) -> Arity {
+        self.method.arity
+    }
+
+    fn name(&self) -> &str {
+        self.method.name
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some(self.method.doc)
+    }
+}
+
+impl Display for BoundMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native method {}>", self.method.name)
+    }
+}
+
+fn receiver_str(receiver: &Literal) -> Result<&str, InterpreterErrorKind> {
+    match receiver {
+        Literal::String(s) => Ok(s),
+        _ => Err(InterpreterErrorKind::Internal(format!(
+            "string method bound to {}",
+            receiver.type_name()
+        ))),
+    }
+}
+
+fn receiver_number(receiver: &Literal) -> Result<f64, InterpreterErrorKind> {
+    match receiver {
+        Literal::Number(n) => Ok(*n),
+        _ => Err(InterpreterErrorKind::Internal(format!(
+            "number method bound to {}",
+            receiver.type_name()
+        ))),
+    }
+}
+
+fn receiver_array(receiver: &Literal) -> Result<&Rc<RefCell<Vec<Literal>>>, InterpreterErrorKind> {
+    match receiver {
+        Literal::Array(elements) => Ok(elements),
+        _ => Err(InterpreterErrorKind::Internal(format!(
+            "array method bound to {}",
+            receiver.type_name()
+        ))),
+    }
+}
+
+fn receiver_map(receiver: &Literal) -> Result<&Rc<RefCell<LoxMap>>, InterpreterErrorKind> {
+    match receiver {
+        Literal::Map(entries) => Ok(entries),
+        _ => Err(InterpreterErrorKind::Internal(format!(
+            "map method bound to {}",
+            receiver.type_name()
+        ))),
+    }
+}
+
+fn receiver_enum(receiver: &Literal) -> Result<&Rc<LoxEnum>, InterpreterErrorKind> {
+    match receiver {
+        Literal::Enum(lox_enum) => Ok(lox_enum),
+        _ => Err(InterpreterErrorKind::Internal(format!(
+            "enum method bound to {}",
+            receiver.type_name()
+        ))),
+    }
+}
+
+fn receiver_enum_value(receiver: &Literal) -> Result<&Rc<EnumValue>, InterpreterErrorKind> {
+    match receiver {
+        Literal::EnumValue(value) => Ok(value),
+        _ => Err(InterpreterErrorKind::Internal(format!(
+            "enum value method bound to {}",
+            receiver.type_name()
+        ))),
+    }
+}
+
+fn receiver_weak_ref(receiver: &Literal) -> Result<&Rc<LoxWeakRef>, InterpreterErrorKind> {
+    match receiver {
+        Literal::WeakRef(weak_ref) => Ok(weak_ref),
+        _ => Err(InterpreterErrorKind::Internal(format!(
+            "weak reference method bound to {}",
+            receiver.type_name()
+        ))),
+    }
+}
+
+fn string_arg<'a>(
+    method: &str,
+    args: &'a [Literal],
+    index: usize,
+) -> Result<&'a str, InterpreterErrorKind> {
+    match argument(args, index)? {
+        Literal::String(s) => Ok(s),
+        other => Err(InterpreterErrorKind::General(format!(
+            "Argument {} to '{}' must be a String, got {}.",
+            index + 1,
+            method,
+            other.type_name()
+        ))),
+    }
+}
+
+fn callable_arg<'a>(
+    method: &str,
+    args: &'a [Literal],
+    index: usize,
+) -> Result<&'a LoxCallable, InterpreterErrorKind> {
+    match argument(args, index)? {
+        Literal::Callable(callable) => Ok(callable),
+        other => Err(InterpreterErrorKind::General(format!(
+            "Argument {} to '{}' must be a Function, got {}.",
+            index + 1,
+            method,
+            other.type_name()
+        ))),
+    }
+}
+
+/// The argument at `index` as a map key.
+fn key_arg(args: &[Literal], index: usize) -> Result<HashableLiteral, InterpreterErrorKind> {
+    HashableLiteral::new(argument(args, index)?).map_err(InterpreterErrorKind::General)
+}
+
+/// The argument at `index` as an index or count of at most `max`, following the rules of
+/// `Literal::as_index`.
+pub fn index_arg(
+    method: &str,
+    args: &[Literal],
+    index: usize,
+    max: usize,
+) -> Result<usize, InterpreterErrorKind> {
+    argument(args, index)?.as_index(max).map_err(|error| {
+        InterpreterErrorKind::General(format!("Argument {} to '{}' {}.", index + 1, method, error))
+    })
+}
+
+/// Like `index_arg`, for an argument that must also be at least the start of `allowed`.
+fn ranged_arg(
+    method: &str,
+    args: &[Literal],
+    index: usize,
+    allowed: std::ops::RangeInclusive<usize>,
+) -> Result<usize, InterpreterErrorKind> {
+    let value = index_arg(method, args, index, *allowed.end())?;
+    if value < *allowed.start() {
+        return Err(InterpreterErrorKind::General(format!(
+            "Argument {} to '{}' must be at least {}, got {}.",
+            index + 1,
+            method,
+            allowed.start(),
+            value
+        )));
+    }
+    Ok(value)
+}
+
+fn string_len(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    _args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    Ok(Literal::Number(
+        receiver_str(receiver)?.chars().count() as f64
+    ))
+}
+
+fn string_split(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    let s = receiver_str(receiver)?;
+    let separator = string_arg("split", args, 0)?;
+
+    let parts = if separator.is_empty() {
+        s.chars()
+            .map(|c| Literal::String(c.to_string().into()))
+            .collect()
+    } else {
+        s.split(separator)
+            .map(|part| Literal::String(part.into()))
+            .collect()
+    };
+
+    Ok(Literal::Array(Rc::new(RefCell::new(parts))))
+}
+
+fn string_trim(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    _args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    Ok(Literal::String(receiver_str(receiver)?.trim().into()))
+}
+
+fn string_contains(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    let needle = string_arg("contains", args, 0)?;
+    Ok(Literal::Bool(receiver_str(receiver)?.contains(needle)))
+}
+
+fn string_replace(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    let from = string_arg("replace", args, 0)?;
+    let to = string_arg("replace", args, 1)?;
+    Ok(Literal::String(
+        receiver_str(receiver)?.replace(from, to).into(),
+    ))
+}
+
+fn number_floor(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    _args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    Ok(Literal::Number(receiver_number(receiver)?.floor()))
+}
+
+fn number_abs(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    _args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    Ok(Literal::Number(receiver_number(receiver)?.abs()))
+}
+
+/// The most digits `toFixed`, `toPrecision` and a `format()` spec will produce.
+pub(super) const MAX_DIGITS: usize = 100;
+
+/// Up to this magnitude, a Number can hold every integer exactly.
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+fn number_to_string(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    if args.is_empty() {
+        return Ok(Literal::String(
+            format_number(receiver_number(receiver)?).into(),
+        ));
+    }
+
+    let n = finite_receiver("toString", receiver)?;
+    let base = ranged_arg("toString", args, 0, 2..=36)?;
+    if n.fract() != 0.0 {
+        return Err(InterpreterErrorKind::General(format!(
+            "Only integers can be formatted in another base, got {}.",
+            format_number(n)
+        )));
+    }
+    if n.abs() > MAX_SAFE_INTEGER {
+        return Err(InterpreterErrorKind::General(format!(
+            "{} is too large to format exactly in another base; the limit is {}.",
+            n, MAX_SAFE_INTEGER
+        )));
+    }
+
+    let mut magnitude = n.abs() as u64;
+    let mut digits = vec![];
+    loop {
+        digits.push(std::char::from_digit(
+            (magnitude % base as u64) as u32,
+            base as u32,
+        ));
+        magnitude /= base as u64;
+        if magnitude == 0 {
+            break;
+        }
+    }
+
+    let mut formatted: String = digits.into_iter().flatten().rev().collect();
+    if n < 0.0 {
+        formatted.insert(0, '-');
+    }
+    Ok(Literal::String(formatted.into()))
+}
+
+/// Rounds to the nearest value with that many decimal places. The exact binary value of the
+/// number is what gets rounded, and a tie goes to the even digit, so `2.5.toFixed(0)` is "2"
+/// and `1.005.toFixed(2)` is "1.00" (1.005 is stored as slightly less than that).
+fn number_to_fixed(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    let n = finite_receiver("toFixed", receiver)?;
+    let digits = ranged_arg("toFixed", args, 0, 0..=MAX_DIGITS)?;
+
+    // Negative zero would otherwise print as "-0".
+    let n = if n == 0.0 { 0.0 } else { n };
+    Ok(Literal::String(format!("{:.*}", digits, n).into()))
+}
+
+/// Uses plain notation when the exponent fits in the digits asked for, and `1.23e+25`
+/// notation for numbers too large or too small for that, rounding like `toFixed`.
+fn number_to_precision(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    let n = finite_receiver("toPrecision", receiver)?;
+    let significant = ranged_arg("toPrecision", args, 0, 1..=MAX_DIGITS)?;
+
+    let n = if n == 0.0 { 0.0 } else { n };
+    // Rounding can carry into a new leading digit, so the exponent is read from the rounded
+    // scientific form rather than computed from the number.
+    let scientific = format!("{:.*e}", significant - 1, n);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or_default();
+
+    let formatted = if exponent < -6 || exponent >= significant as i32 {
+        format!(
+            "{}e{}{}",
+            mantissa,
+            if exponent < 0 { '-' } else { '+' },
+            exponent.abs()
+        )
+    } else {
+        let decimals = (significant as i32 - 1 - exponent).max(0) as usize;
+        format!("{:.*}", decimals, n)
+    };
+
+    Ok(Literal::String(formatted.into()))
+}
+
+/// The receiver of a number formatting method, which has no sensible output for NaN or an
+/// infinity.
+fn finite_receiver(method: &str, receiver: &Literal) -> Result<f64, InterpreterErrorKind> {
+    let n = receiver_number(receiver)?;
+    if n.is_finite() {
+        Ok(n)
+    } else {
+        Err(InterpreterErrorKind::General(format!(
+            "Cannot use '{}' on {}; it needs a finite number.",
+            method,
+            format_number(n)
+        )))
+    }
+}
+
+fn array_len(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    _args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    Ok(Literal::Number(
+        receiver_array(receiver)?.borrow().len() as f64
+    ))
+}
+
+fn array_push(
+    interpreter: &mut Interpreter,
+    receiver: &Literal,
+    args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    let mut elements = receiver_array(receiver)?.borrow_mut();
+    interpreter
+        .limits
+        .check_collection("array", elements.len() + 1)?;
+
+    elements.push(argument(args, 0)?.clone());
+    Ok(Literal::Nil)
+}
+
+fn array_pop(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    _args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    match receiver_array(receiver)?.borrow_mut().pop() {
+        Some(element) => Ok(element),
+        None => Err(InterpreterErrorKind::General(
+            "Can't pop from an empty array.".into(),
+        )),
+    }
+}
+
+fn array_insert(
+    interpreter: &mut Interpreter,
+    receiver: &Literal,
+    args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    let mut elements = receiver_array(receiver)?.borrow_mut();
+    let index = index_arg("insert", args, 0, elements.len())?;
+
+    interpreter
+        .limits
+        .check_collection("array", elements.len() + 1)?;
+
+    elements.insert(index, argument(args, 1)?.clone());
+    Ok(Literal::Nil)
+}
+
+fn array_remove(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    let mut elements = receiver_array(receiver)?.borrow_mut();
+    if elements.is_empty() {
+        return Err(InterpreterErrorKind::General(
+            "Can't remove from an empty array.".into(),
+        ));
+    }
+    let index = index_arg("remove", args, 0, elements.len() - 1)?;
+
+    Ok(elements.remove(index))
+}
+
+fn array_index_of(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    let target = argument(args, 0)?;
+    let position = receiver_array(receiver)?
+        .borrow()
+        .iter()
+        .position(|element| is_equal(element, target));
+
+    Ok(Literal::Number(match position {
+        Some(index) => index as f64,
+        None => -1.0,
+    }))
+}
+
+fn array_sort(
+    interpreter: &mut Interpreter,
+    receiver: &Literal,
+    args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    let comparator = match args.first() {
+        Some(_) => Some(callable_arg("sort", args, 0)?),
+        None => None,
+    };
+
+    // Sort a copy so the comparator is free to read the array while we work.
+    let elements = receiver_array(receiver)?.borrow().clone();
+    let elements = merge_sort(elements, &mut |a, b| match comparator {
+        Some(comparator) => compare_with(interpreter, comparator, a, b),
+        None => compare_default(a, b),
+    })?;
+
+    *receiver_array(receiver)?.borrow_mut() = elements;
+    Ok(Literal::Nil)
+}
+
+/// A stable merge sort that stops at the first error `compare` returns. Unlike
+/// `slice::sort_by`, it doesn't mind a comparator that isn't a total order, such as one that
+/// answers at random or meets a NaN; the result is then just in some order.
+fn merge_sort<E>(
+    mut elements: Vec<Literal>,
+    compare: &mut impl FnMut(&Literal, &Literal) -> Result<Ordering, E>,
+) -> Result<Vec<Literal>, E> {
+    if elements.len() <= 1 {
+        return Ok(elements);
+    }
+
+    let right = elements.split_off(elements.len() / 2);
+    let left = merge_sort(elements, compare)?;
+    let right = merge_sort(right, compare)?;
+
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    while let (Some(a), Some(b)) = (left.peek(), right.peek()) {
+        // Taking from the left on a tie keeps equal elements in their original order.
+        if compare(a, b)? == Ordering::Greater {
+            merged.extend(right.next());
+        } else {
+            merged.extend(left.next());
+        }
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+
+fn compare_with(
+    interpreter: &mut Interpreter,
+    comparator: &LoxCallable,
+    a: &Literal,
+    b: &Literal,
+) -> Result<Ordering, InterpreterErrorKind> {
+    match interpreter.call_value(comparator, &[a.clone(), b.clone()])? {
+        Literal::Number(n) => Ok(n.partial_cmp(&0.0).unwrap_or(Ordering::Equal)),
+        other => Err(InterpreterErrorKind::General(format!(
+            "Sort comparator must return a Number, got {}.",
+            other.type_name()
+        ))),
+    }
+}
+
+fn compare_default(a: &Literal, b: &Literal) -> Result<Ordering, InterpreterErrorKind> {
+    match (a, b) {
+        (Literal::Number(n1), Literal::Number(n2)) => {
+            Ok(n1.partial_cmp(n2).unwrap_or(Ordering::Equal))
+        }
+        (Literal::String(s1), Literal::String(s2)) => Ok(s1.cmp(s2)),
+        _ => Err(InterpreterErrorKind::General(format!(
+            "Can't sort {} and {} without a comparator.",
+            a.type_name(),
+            b.type_name()
+        ))),
+    }
+}
+
+fn array_map(
+    interpreter: &mut Interpreter,
+    receiver: &Literal,
+    args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    let function = callable_arg("map", args, 0)?;
+    let elements = receiver_array(receiver)?.borrow().clone();
+
+    let mut mapped = Vec::with_capacity(elements.len());
+    for element in elements {
+        mapped.push(interpreter.call_value(function, &[element])?);
+    }
+
+    Ok(Literal::Array(Rc::new(RefCell::new(mapped))))
+}
+
+fn array_filter(
+    interpreter: &mut Interpreter,
+    receiver: &Literal,
+    args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    let predicate = callable_arg("filter", args, 0)?;
+    let elements = receiver_array(receiver)?.borrow().clone();
+
+    let mut kept = vec![];
+    for element in elements {
+        if is_truthy(&interpreter.call_value(predicate, std::slice::from_ref(&element))?) {
+            kept.push(element);
+        }
+    }
+
+    Ok(Literal::Array(Rc::new(RefCell::new(kept))))
+}
+
+fn map_len(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    _args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    Ok(Literal::Number(
+        receiver_map(receiver)?.borrow().len() as f64
+    ))
+}
+
+fn map_get(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    let key = key_arg(args, 0)?;
+    let value = receiver_map(receiver)?.borrow().get(&key).cloned();
+
+    Ok(value.unwrap_or(Literal::Nil))
+}
+
+fn map_set(
+    interpreter: &mut Interpreter,
+    receiver: &Literal,
+    args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    let key = key_arg(args, 0)?;
+    let mut entries = receiver_map(receiver)?.borrow_mut();
+    if !entries.contains_key(&key) {
+        interpreter
+            .limits
+            .check_collection("map", entries.len() + 1)?;
+    }
+
+    entries.insert(key, argument(args, 1)?.clone());
+
+    Ok(Literal::Nil)
+}
+
+fn map_has(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    let key = key_arg(args, 0)?;
+    Ok(Literal::Bool(
+        receiver_map(receiver)?.borrow().contains_key(&key),
+    ))
+}
+
+fn map_remove(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    let key = key_arg(args, 0)?;
+    let value = receiver_map(receiver)?.borrow_mut().remove(&key);
+
+    Ok(value.unwrap_or(Literal::Nil))
+}
+
+fn map_keys(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    _args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    let keys = receiver_map(receiver)?
+        .borrow()
+        .iter()
+        .map(|(key, _)| key.to_literal())
+        .collect();
+
+    Ok(Literal::Array(Rc::new(RefCell::new(keys))))
+}
+
+fn map_values(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    _args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    let values = receiver_map(receiver)?
+        .borrow()
+        .iter()
+        .map(|(_, value)| value.clone())
+        .collect();
+
+    Ok(Literal::Array(Rc::new(RefCell::new(values))))
+}
+
+fn map_merge(
+    interpreter: &mut Interpreter,
+    receiver: &Literal,
+    args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    let other = match argument(args, 0)? {
+        Literal::Map(other) => other,
+        other => {
+            return Err(InterpreterErrorKind::General(format!(
+                "Argument 1 to 'merge' must be a Map, got {}.",
+                other.type_name()
+            )))
+        }
+    };
+
+    let mut merged = receiver_map(receiver)?.borrow().clone();
+    for (key, value) in other.borrow().iter() {
+        merged.insert(key.clone(), value.clone());
+    }
+    interpreter.limits.check_collection("map", merged.len())?;
+
+    Ok(Literal::Map(Rc::new(RefCell::new(merged))))
+}
+
+fn enum_values(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    _args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    let values = receiver_enum(receiver)?
+        .variants()
+        .iter()
+        .map(|variant| Literal::EnumValue(Rc::clone(variant)))
+        .collect();
+
+    Ok(Literal::Array(Rc::new(RefCell::new(values))))
+}
+
+fn enum_contains(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    let contains = match argument(args, 0)? {
+        Literal::EnumValue(value) => receiver_enum(receiver)?.contains(value),
+        _ => false,
+    };
+
+    Ok(Literal::Bool(contains))
+}
+
+fn enum_value_name(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    _args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    Ok(Literal::String(
+        receiver_enum_value(receiver)?.name.as_str().into(),
+    ))
+}
+
+fn enum_value_ordinal(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    _args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    Ok(Literal::Number(
+        receiver_enum_value(receiver)?.ordinal as f64,
+    ))
+}
+
+fn weak_ref_get(
+    _interpreter: &mut Interpreter,
+    receiver: &Literal,
+    _args: &[Literal],
+) -> Result<Literal, InterpreterErrorKind> {
+    Ok(receiver_weak_ref(receiver)?.get().into())
+}
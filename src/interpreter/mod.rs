@@ -1,8 +1,27 @@
 pub mod callable;
+pub mod capability;
+pub mod class;
+pub mod enums;
+pub mod format;
+pub mod globals;
+pub mod host;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod identity;
+pub mod index;
+pub mod info;
+#[allow(clippy::module_inception)]
 pub mod interpreter;
+pub mod json;
+pub mod map;
+pub mod methods;
+pub mod native;
+pub mod number;
+pub mod pretty;
+pub mod process;
+pub mod reflect;
+pub mod stats;
+pub mod system;
+pub mod time;
 
 pub use interpreter::InterpreterErrorKind;
-
-use crate::{ast::stmt::Stmt, environment::Environment};
-
-use self::interpreter::Interpreter;
@@ -0,0 +1,111 @@
+//! Natives defined by the embedding program rather than built into the interpreter.
+
+use std::fmt::{Debug, Display};
+
+use crate::ast::expr::Literal;
+
+use super::{
+    callable::{Arity, Callable},
+    interpreter::Interpreter,
+    InterpreterErrorKind,
+};
+
+/// The body of a host-defined native: it gets the interpreter, to call back into Lox, and the
+/// arguments, whose number has already been checked.
+pub type NativeFn = dyn Fn(&mut Interpreter, &[Literal]) -> Result<Literal, NativeError>;
+
+/// Why a host-defined native failed. Made from a message it becomes a runtime error raised at
+/// the call; an error from `Interpreter::call_function` passes through as it was raised, with
+/// its location and backtrace.
+#[derive(Debug)]
+pub struct NativeError(pub(crate) InterpreterErrorKind);
+
+impl NativeError {
+    pub fn new(message: impl Into<String>) -> Self {
+        NativeError(InterpreterErrorKind::General(message.into()))
+    }
+}
+
+impl From<String> for NativeError {
+    fn from(message: String) -> Self {
+        NativeError::new(message)
+    }
+}
+
+impl From<&str> for NativeError {
+    fn from(message: &str) -> Self {
+        NativeError::new(message)
+    }
+}
+
+impl Display for NativeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.0.message().unwrap_or("native function failed")
+        )
+    }
+}
+
+/// A native registered with `Interpreter::define_native`.
+pub struct HostFunction {
+    name: String,
+    arity: usize,
+    fun: Box<NativeFn>,
+    pure: bool,
+}
+
+impl HostFunction {
+    pub fn new(name: &str, arity: usize, fun: Box<NativeFn>) -> Self {
+        Self {
+            name: name.to_string(),
+            arity,
+            fun,
+            pure: false,
+        }
+    }
+
+    /// Marks the native as pure: see `Callable::is_pure`.
+    pub fn pure(self) -> Self {
+        Self { pure: true, ..self }
+    }
+}
+
+impl Callable for HostFunction {
+    fn arity(&self) -> Arity {
+        Arity::Exact(self.arity)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn is_pure(&self) -> bool {
+        self.pure
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        (self.fun)(interpreter, args).map_err(|NativeError(e)| e)
+    }
+}
+
+impl Debug for HostFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HostFunction")
+            .field("name", &self.name)
+            .field("arity", &self.arity)
+            .field("pure", &self.pure)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Display for HostFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn {}>", self.name)
+    }
+}
@@ -0,0 +1,34 @@
+//! How numbers are written out. Everything that shows a script a number as text goes through
+//! `format_number`: `print`, `toString`, JSON and the numbers quoted in error
+//! messages. Rust's float formatting never consults the locale, so neither does this.
+
+/// The magnitude from which numbers are written with an exponent.
+const EXPONENT_FROM: f64 = 1e21;
+
+/// Writes `n` the way `print` shows it:
+///
+/// - Whole numbers have no fractional part: `3`, `-12`, and `-0` for negative zero.
+/// - Other numbers take the fewest digits that read back as the same number, so `0.1 + 0.2`
+///   is `0.30000000000000004`.
+/// - Numbers of magnitude 1e21 and above are written with an exponent: `1e21`, `-1.5e300`.
+///   Smaller ones never are, so `0.0000001` stays as it is.
+/// - The values that aren't finite are `NaN`, `inf` and `-inf`.
+///
+/// Parsing what this writes with `str::parse::<f64>` gives back `n`, and so does reading it
+/// as a Lox number literal when `n` is finite.
+///
+/// ```
+/// use rlox::format_number;
+///
+/// assert_eq!(format_number(2.0), "2");
+/// assert_eq!(format_number(0.1 + 0.2), "0.30000000000000004");
+/// assert_eq!(format_number(1e21), "1e21");
+/// assert_eq!(format_number(-0.0), "-0");
+/// ```
+pub fn format_number(n: f64) -> String {
+    if n.is_finite() && n.abs() >= EXPONENT_FROM {
+        format!("{:e}", n)
+    } else {
+        n.to_string()
+    }
+}
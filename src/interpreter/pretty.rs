@@ -0,0 +1,147 @@
+//! Rendering of arrays and maps, both the one-line form `print` uses and the indented form
+//! `prettyPrint()` uses.
+//!
+//! An array or map that contains itself, directly or through others, is written as
+//! `<cycle>` where it reappears instead of being followed forever.
+
+use std::rc::Rc;
+
+use crate::ast::expr::Literal;
+
+use super::map::HashableLiteral;
+
+/// How many levels of nesting `prettyPrint()` shows when no depth is given.
+pub const DEFAULT_MAX_DEPTH: usize = 10;
+
+/// The one-line form of `value`, as `print` writes it.
+pub fn inline(value: &Literal) -> String {
+    let mut printer = Printer {
+        output: String::new(),
+        open: vec![],
+        indented: false,
+        max_depth: usize::MAX,
+    };
+    printer.value(value, 0);
+    printer.output
+}
+
+/// `value` over several lines, with the elements of each array and map on lines of their own,
+/// indented two spaces per level. Strings inside arrays and maps are quoted, and arrays and
+/// maps nested deeper than `max_depth` are shown as `[...]` and `{...}`. Map entries keep the
+/// order they were inserted in.
+pub fn pretty(value: &Literal, max_depth: usize) -> String {
+    let mut printer = Printer {
+        output: String::new(),
+        open: vec![],
+        indented: true,
+        max_depth,
+    };
+    printer.value(value, 0);
+    printer.output
+}
+
+struct Printer {
+    output: String,
+    /// The arrays and maps being written, to catch one that contains itself.
+    open: Vec<*const ()>,
+    indented: bool,
+    max_depth: usize,
+}
+
+impl Printer {
+    fn value(&mut self, value: &Literal, depth: usize) {
+        match value {
+            Literal::String(s) if self.indented && depth > 0 => {
+                self.output.push_str(&format!("{:?}", s))
+            }
+            Literal::Array(elements) => {
+                let container = Rc::as_ptr(elements) as *const ();
+                let elements = elements.borrow();
+                self.container(
+                    container,
+                    depth,
+                    ('[', ']'),
+                    elements.len(),
+                    |printer, index| printer.value(&elements[index], depth + 1),
+                );
+            }
+            Literal::Map(entries) => {
+                let container = Rc::as_ptr(entries) as *const ();
+                let entries = entries.borrow();
+                let entries: Vec<_> = entries.iter().collect();
+                self.container(
+                    container,
+                    depth,
+                    ('{', '}'),
+                    entries.len(),
+                    |printer, index| {
+                        let (key, value) = entries[index];
+                        if let (true, HashableLiteral::String(key)) = (printer.indented, key) {
+                            printer.output.push_str(&format!("{:?}: ", key));
+                        } else {
+                            printer.output.push_str(&format!("{}: ", key));
+                        }
+                        printer.value(value, depth + 1)
+                    },
+                );
+            }
+            Literal::Tuple(values) => {
+                let container = Rc::as_ptr(values) as *const ();
+                self.container(
+                    container,
+                    depth,
+                    ('(', ')'),
+                    values.len(),
+                    |printer, index| printer.value(&values[index], depth + 1),
+                );
+            }
+            other => self.output.push_str(&other.to_string()),
+        }
+    }
+
+    /// Writes an array or map of `len` items between `open` and `close`, calling `item` to
+    /// write each.
+    fn container(
+        &mut self,
+        container: *const (),
+        depth: usize,
+        (open, close): (char, char),
+        len: usize,
+        mut item: impl FnMut(&mut Self, usize),
+    ) {
+        if self.open.contains(&container) {
+            self.output.push_str("<cycle>");
+            return;
+        }
+        if len == 0 {
+            self.output.push(open);
+            self.output.push(close);
+            return;
+        }
+        if depth >= self.max_depth {
+            self.output.push_str(&format!("{}...{}", open, close));
+            return;
+        }
+
+        self.open.push(container);
+        self.output.push(open);
+        for index in 0..len {
+            if self.indented {
+                if index > 0 {
+                    self.output.push(',');
+                }
+                self.output.push('\n');
+                self.output.push_str(&"  ".repeat(depth + 1));
+            } else if index > 0 {
+                self.output.push_str(", ");
+            }
+            item(self, index);
+        }
+        if self.indented {
+            self.output.push('\n');
+            self.output.push_str(&"  ".repeat(depth));
+        }
+        self.output.push(close);
+        self.open.pop();
+    }
+}
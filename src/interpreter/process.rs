@@ -0,0 +1,233 @@
+//! `exec()`: runs a program and collects what it printed.
+//!
+//! The native is defined only for interpreters built with `InterpreterBuilder::allow_processes`.
+//! The program and its arguments go straight to the OS, never through a shell, so nothing in
+//! them is interpolated.
+
+use std::{
+    cell::RefCell,
+    fmt::Display,
+    io::{self, Read},
+    process::{Child, Command, Stdio},
+    rc::Rc,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::ast::expr::Literal;
+
+use super::{
+    callable::{argument, Arity, Callable},
+    interpreter::Interpreter,
+    map::LoxMap,
+    InterpreterErrorKind,
+};
+
+/// The most either output stream may hold before the program is stopped.
+const MAX_OUTPUT: usize = 10 * 1024 * 1024;
+
+/// How often a program with a timeout is checked on.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Clone, Debug)]
+pub struct Exec;
+
+impl Callable for Exec {
+    fn arity(&self) -> Arity {
+        Arity::Range(2, 3)
+    }
+
+    fn name(&self) -> &str {
+        "exec"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Runs a program with an array of arguments and returns its status, stdout and stderr.")
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        let program = match argument(args, 0)? {
+            Literal::String(program) => program.to_string(),
+            other => {
+                return Err(error(format!(
+                    "exec() needs a String program, not a {}.",
+                    other.type_name()
+                )))
+            }
+        };
+        let mut command = Command::new(&program);
+
+        match argument(args, 1)? {
+            Literal::Array(arguments) => {
+                for (index, argument) in arguments.borrow().iter().enumerate() {
+                    match argument {
+                        Literal::String(argument) => command.arg(&**argument),
+                        other => {
+                            return Err(error(format!(
+                                "exec() arguments must be Strings, but argument {} is a {}.",
+                                index,
+                                other.type_name()
+                            )))
+                        }
+                    };
+                }
+            }
+            other => {
+                return Err(error(format!(
+                    "exec() needs its arguments as an Array, not a {}.",
+                    other.type_name()
+                )))
+            }
+        }
+
+        let timeout = apply_options(&mut command, args.get(2))?;
+        run(&program, command, timeout)
+            .map_err(|reason| error(format!("exec(\"{}\") failed: {}.", program, reason)))
+    }
+}
+
+impl Display for Exec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn exec>")
+    }
+}
+
+fn error(message: String) -> InterpreterErrorKind {
+    InterpreterErrorKind::General(message)
+}
+
+/// Applies an options map of `timeout` (in seconds) and `cwd` to `command`, returning the
+/// timeout if there is one.
+fn apply_options(
+    command: &mut Command,
+    options: Option<&Literal>,
+) -> Result<Option<Duration>, InterpreterErrorKind> {
+    let options = match options {
+        None => return Ok(None),
+        Some(Literal::Map(options)) => options.borrow(),
+        Some(other) => {
+            return Err(error(format!(
+                "The options for exec() must be a Map, got {}.",
+                other.type_name()
+            )))
+        }
+    };
+
+    let mut timeout = None;
+    for (key, value) in options.iter() {
+        match (key.to_string().as_str(), value) {
+            ("timeout", Literal::Number(seconds)) if *seconds > 0.0 && seconds.is_finite() => {
+                timeout = Some(Duration::from_secs_f64(*seconds))
+            }
+            ("timeout", other) => {
+                return Err(error(format!(
+                    "The timeout for exec() must be a positive number of seconds, got {}.",
+                    other
+                )))
+            }
+            ("cwd", Literal::String(directory)) => {
+                command.current_dir(&**directory);
+            }
+            ("cwd", other) => {
+                return Err(error(format!(
+                    "The cwd for exec() must be a String, got {}.",
+                    other.type_name()
+                )))
+            }
+            (key, _) => {
+                return Err(error(format!(
+                    "Unknown option '{}' for exec(); the options are 'timeout' and 'cwd'.",
+                    key
+                )))
+            }
+        }
+    }
+
+    Ok(timeout)
+}
+
+fn run(program: &str, mut command: Command, timeout: Option<Duration>) -> Result<Literal, String> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("could not start '{}': {}", program, e))?;
+
+    // Both streams are drained at once so a program that fills one pipe while we wait on the
+    // other can't deadlock.
+    let stdout = collect(child.stdout.take());
+    let stderr = collect(child.stderr.take());
+
+    let status = wait(&mut child, timeout);
+    let stdout = join(stdout);
+    let stderr = join(stderr);
+    let status = status?;
+    let (stdout, stderr) = (stdout?, stderr?);
+
+    let mut map = LoxMap::new();
+    map.insert(
+        "status".into(),
+        // A program killed by a signal has no exit code.
+        status
+            .code()
+            .map_or(Literal::Nil, |code| Literal::Number(code.into())),
+    );
+    map.insert("stdout".into(), Literal::String(stdout.into()));
+    map.insert("stderr".into(), Literal::String(stderr.into()));
+    Ok(Literal::Map(Rc::new(RefCell::new(map))))
+}
+
+/// Reads a pipe to its end on another thread, up to `MAX_OUTPUT` bytes. Past that the pipe is
+/// closed, so a program that keeps printing is stopped by a broken pipe.
+fn collect(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<io::Result<Vec<u8>>> {
+    thread::spawn(move || {
+        let mut output = vec![];
+        if let Some(pipe) = pipe {
+            pipe.take(MAX_OUTPUT as u64 + 1).read_to_end(&mut output)?;
+        }
+        Ok(output)
+    })
+}
+
+fn join(reader: JoinHandle<io::Result<Vec<u8>>>) -> Result<String, String> {
+    let output = reader
+        .join()
+        .map_err(|_| "the thread reading its output panicked".to_string())?
+        .map_err(|e| format!("could not read its output: {}", e))?;
+
+    if output.len() > MAX_OUTPUT {
+        return Err(format!(
+            "it printed more than {} MB",
+            MAX_OUTPUT / 1024 / 1024
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
+
+fn wait(child: &mut Child, timeout: Option<Duration>) -> Result<std::process::ExitStatus, String> {
+    let Some(timeout) = timeout else {
+        return child
+            .wait()
+            .map_err(|e| format!("could not wait for it: {}", e));
+    };
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Ok(status),
+            Ok(None) if Instant::now() < deadline => thread::sleep(POLL_INTERVAL),
+            Ok(None) => {
+                // Killing can only fail if it exited in the meantime, which is fine too.
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("timed out after {}s", timeout.as_secs_f64()));
+            }
+            Err(e) => return Err(format!("could not wait for it: {}", e)),
+        }
+    }
+}
@@ -0,0 +1,319 @@
+//! Natives that look into instances, classes and functions: `fields()`, `methods()`,
+//! `getField()`, `setField()`, `className()`, `fnName()` and `fnArity()`.
+
+use std::{cell::RefCell, fmt::Display, rc::Rc};
+
+use crate::ast::expr::Literal;
+
+use super::{
+    callable::{argument, Arity, Callable, LoxCallable},
+    class::LoxInstance,
+    interpreter::Interpreter,
+    InterpreterErrorKind,
+};
+
+fn instance_argument<'a>(
+    native: &str,
+    args: &'a [Literal],
+) -> Result<&'a Rc<RefCell<LoxInstance>>, InterpreterErrorKind> {
+    match argument(args, 0)? {
+        Literal::Instance(instance) => Ok(instance),
+        other => Err(InterpreterErrorKind::General(format!(
+            "{}() needs an instance, not a {}.",
+            native,
+            other.type_name()
+        ))),
+    }
+}
+
+fn field_name_argument<'a>(
+    native: &str,
+    args: &'a [Literal],
+) -> Result<&'a str, InterpreterErrorKind> {
+    match argument(args, 1)? {
+        Literal::String(name) => Ok(name),
+        other => Err(InterpreterErrorKind::General(format!(
+            "{}() needs the field name as a String, not a {}.",
+            native,
+            other.type_name()
+        ))),
+    }
+}
+
+fn callable_argument<'a>(
+    native: &str,
+    args: &'a [Literal],
+) -> Result<&'a LoxCallable, InterpreterErrorKind> {
+    match argument(args, 0)? {
+        Literal::Callable(callable) => Ok(callable),
+        other => Err(InterpreterErrorKind::General(format!(
+            "{}() needs a function or class, not a {}.",
+            native,
+            other.type_name()
+        ))),
+    }
+}
+
+fn names(names: impl IntoIterator<Item = String>) -> Literal {
+    let names = names.into_iter().map(Literal::from).collect();
+    Literal::Array(Rc::new(RefCell::new(names)))
+}
+
+#[derive(Clone, Debug)]
+pub struct Fields;
+
+impl Callable for Fields {
+    fn arity(&self) -> Arity {
+        Arity::Exact(1)
+    }
+
+    fn name(&self) -> &str {
+        "fields"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Returns the names of an instance's fields, in the order they were first set.")
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        let instance = instance_argument("fields", args)?.borrow();
+        Ok(names(instance.fields.iter().map(|(name, _)| name.clone())))
+    }
+}
+
+impl Display for Fields {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn fields>")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Methods;
+
+impl Callable for Methods {
+    fn arity(&self) -> Arity {
+        Arity::Exact(1)
+    }
+
+    fn name(&self) -> &str {
+        "methods"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Returns the names of the methods of a class or instance, inherited ones last.")
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        let method_names = match argument(args, 0)? {
+            Literal::Callable(LoxCallable::Class(class)) => class.method_names(),
+            Literal::Instance(instance) => instance.borrow().class.method_names(),
+            other => {
+                return Err(InterpreterErrorKind::General(format!(
+                    "methods() needs a class or an instance, not a {}.",
+                    other.type_name()
+                )))
+            }
+        };
+
+        Ok(names(method_names))
+    }
+}
+
+impl Display for Methods {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn methods>")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GetField;
+
+impl Callable for GetField {
+    fn arity(&self) -> Arity {
+        Arity::Range(2, 3)
+    }
+
+    fn name(&self) -> &str {
+        "getField"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Returns the named field of an instance, or the default if it has no such field.")
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        let instance = instance_argument("getField", args)?.borrow();
+        let name = field_name_argument("getField", args)?;
+
+        match (instance.fields.get(name), args.get(2)) {
+            (Some(value), _) => Ok(value.clone()),
+            (None, Some(default)) => Ok(default.clone()),
+            (None, None) => Err(InterpreterErrorKind::General(format!(
+                "{} instance has no field '{}'.",
+                instance.class.name, name
+            ))),
+        }
+    }
+}
+
+impl Display for GetField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn getField>")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SetField;
+
+impl Callable for SetField {
+    fn arity(&self) -> Arity {
+        Arity::Exact(3)
+    }
+
+    fn name(&self) -> &str {
+        "setField"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Sets the named field of an instance and returns the value.")
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        let instance = instance_argument("setField", args)?;
+        let name = field_name_argument("setField", args)?;
+        let value = argument(args, 2)?;
+
+        instance
+            .borrow_mut()
+            .fields
+            .insert(name.to_string(), value.clone());
+        Ok(value.clone())
+    }
+}
+
+impl Display for SetField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn setField>")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ClassName;
+
+impl Callable for ClassName {
+    fn arity(&self) -> Arity {
+        Arity::Exact(1)
+    }
+
+    fn name(&self) -> &str {
+        "className"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Returns the name of an instance's class.")
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        let instance = instance_argument("className", args)?.borrow();
+        Ok(Literal::from(instance.class.name.as_str()))
+    }
+}
+
+impl Display for ClassName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn className>")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FnName;
+
+impl Callable for FnName {
+    fn arity(&self) -> Arity {
+        Arity::Exact(1)
+    }
+
+    fn name(&self) -> &str {
+        "fnName"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Returns the name a function, class or native was declared or registered under.")
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        Ok(Literal::from(callable_argument("fnName", args)?.name()))
+    }
+}
+
+impl Display for FnName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn fnName>")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FnArity;
+
+impl Callable for FnArity {
+    fn arity(&self) -> Arity {
+        Arity::Exact(1)
+    }
+
+    fn name(&self) -> &str {
+        "fnArity"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some(
+            "Returns how many arguments a function takes, or [min, max] for a native that \
+             takes a varying number, with max nil if there is no limit.",
+        )
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        let bounds = match callable_argument("fnArity", args)?.arity() {
+            Arity::Exact(n) => return Ok(Literal::Number(n as f64)),
+            Arity::Range(min, max) => {
+                vec![Literal::Number(min as f64), Literal::Number(max as f64)]
+            }
+            Arity::AtLeast(min) => vec![Literal::Number(min as f64), Literal::Nil],
+        };
+
+        Ok(Literal::Array(Rc::new(RefCell::new(bounds))))
+    }
+}
+
+impl Display for FnArity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn fnArity>")
+    }
+}
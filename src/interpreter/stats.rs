@@ -0,0 +1,27 @@
+use std::fmt::{self, Display, Formatter};
+
+/// Counts of the work an interpreter has done, for embedders sizing what scripts need. The
+/// counts keep growing across runs until taken with `Interpreter::take_stats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExecStats {
+    /// Statements executed, each statement inside a block or function body included.
+    pub statements: u64,
+    /// Expressions evaluated, every subexpression included.
+    pub expressions: u64,
+    /// Calls of functions, methods, classes and natives, tail calls included.
+    pub calls: u64,
+    /// The most calls that were in progress at once.
+    pub max_call_depth: u64,
+    /// Scopes set up for blocks, calls and match arms.
+    pub environments: u64,
+}
+
+impl Display for ExecStats {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "statements: {}, expressions: {}, calls: {}, max call depth: {}, environments: {}",
+            self.statements, self.expressions, self.calls, self.max_call_depth, self.environments
+        )
+    }
+}
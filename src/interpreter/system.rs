@@ -0,0 +1,102 @@
+//! `readFile()` and `getEnv()`: reading files and environment variables.
+//!
+//! The natives are defined only for interpreters granted `Capability::Filesystem` and
+//! `Capability::Environment`.
+
+use std::{env, fmt::Display, fs};
+
+use crate::ast::expr::Literal;
+
+use super::{
+    callable::{argument, Arity, Callable},
+    interpreter::Interpreter,
+    InterpreterErrorKind,
+};
+
+/// The String argument `native` takes first, described as `what` in the error for anything
+/// else.
+fn string_argument<'a>(
+    native: &str,
+    what: &str,
+    args: &'a [Literal],
+) -> Result<&'a str, InterpreterErrorKind> {
+    match argument(args, 0)? {
+        Literal::String(value) => Ok(value),
+        other => Err(InterpreterErrorKind::General(format!(
+            "{}() needs a String {}, not a {}.",
+            native,
+            what,
+            other.type_name()
+        ))),
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ReadFile;
+
+impl Callable for ReadFile {
+    fn arity(&self) -> Arity {
+        Arity::Exact(1)
+    }
+
+    fn name(&self) -> &str {
+        "readFile"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Returns the contents of the UTF-8 text file at a path.")
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        let path = string_argument("readFile", "path", args)?;
+        match fs::read_to_string(path) {
+            Ok(contents) => Ok(Literal::String(contents.into())),
+            Err(e) => Err(InterpreterErrorKind::General(format!(
+                "readFile(\"{}\") failed: {}.",
+                path, e
+            ))),
+        }
+    }
+}
+
+impl Display for ReadFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn readFile>")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GetEnv;
+
+impl Callable for GetEnv {
+    fn arity(&self) -> Arity {
+        Arity::Exact(1)
+    }
+
+    fn name(&self) -> &str {
+        "getEnv"
+    }
+
+    fn doc(&self) -> Option<&str> {
+        Some("Returns the value of an environment variable, or nil if it isn't set.")
+    }
+
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        args: &[Literal],
+    ) -> Result<Literal, InterpreterErrorKind> {
+        let name = string_argument("getEnv", "name", args)?;
+        Ok(env::var(name).map_or(Literal::Nil, |value| Literal::String(value.into())))
+    }
+}
+
+impl Display for GetEnv {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn getEnv>")
+    }
+}
@@ -0,0 +1,343 @@
+//! Calendar arithmetic for `formatTime()` and `parseTime()`. Times are milliseconds since the
+//! Unix epoch and are always read and written in UTC.
+
+use super::number::format_number;
+
+const MILLIS_PER_SECOND: i64 = 1000;
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// A moment broken down into its calendar fields.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct DateTime {
+    year: i64,
+    month: i64,
+    day: i64,
+    hour: i64,
+    minute: i64,
+    second: i64,
+}
+
+impl DateTime {
+    fn from_millis(millis: i64) -> Self {
+        let seconds = millis.div_euclid(MILLIS_PER_SECOND);
+        let days = seconds.div_euclid(SECONDS_PER_DAY);
+        let second_of_day = seconds.rem_euclid(SECONDS_PER_DAY);
+        let (year, month, day) = civil_from_days(days);
+
+        Self {
+            year,
+            month,
+            day,
+            hour: second_of_day / 3600,
+            minute: second_of_day / 60 % 60,
+            second: second_of_day % 60,
+        }
+    }
+
+    fn to_millis(self) -> i64 {
+        let days = days_from_civil(self.year, self.month, self.day);
+        let seconds = days * SECONDS_PER_DAY + self.hour * 3600 + self.minute * 60 + self.second;
+        seconds * MILLIS_PER_SECOND
+    }
+}
+
+/// One piece of a parsed time pattern.
+enum Piece {
+    Literal(char),
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+}
+
+fn compile(pattern: &str) -> Result<Vec<Piece>, String> {
+    let mut pieces = vec![];
+    let mut chars = pattern.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            pieces.push(Piece::Literal(c));
+            continue;
+        }
+
+        pieces.push(match chars.next() {
+            Some('Y') => Piece::Year,
+            Some('m') => Piece::Month,
+            Some('d') => Piece::Day,
+            Some('H') => Piece::Hour,
+            Some('M') => Piece::Minute,
+            Some('S') => Piece::Second,
+            Some('%') => Piece::Literal('%'),
+            Some(other) => {
+                return Err(format!(
+                    "Unknown directive '%{}' in time pattern \"{}\"; use %Y %m %d %H %M %S or %%.",
+                    other, pattern
+                ))
+            }
+            None => {
+                return Err(format!(
+                    "Time pattern \"{}\" ends in a lone '%'; write '%%' for a percent sign.",
+                    pattern
+                ))
+            }
+        });
+    }
+
+    Ok(pieces)
+}
+
+/// Formats `millis` after the Unix epoch with `pattern`. Every field but the year is printed
+/// as two digits, and the year as at least four.
+pub fn format_time(millis: f64, pattern: &str) -> Result<String, String> {
+    let pieces = compile(pattern)?;
+    let time = DateTime::from_millis(to_whole_millis(millis)?);
+
+    let mut output = String::new();
+    for piece in pieces {
+        match piece {
+            Piece::Literal(c) => output.push(c),
+            Piece::Year if time.year < 0 => output.push_str(&format!("-{:04}", -time.year)),
+            Piece::Year => output.push_str(&format!("{:04}", time.year)),
+            Piece::Month => output.push_str(&format!("{:02}", time.month)),
+            Piece::Day => output.push_str(&format!("{:02}", time.day)),
+            Piece::Hour => output.push_str(&format!("{:02}", time.hour)),
+            Piece::Minute => output.push_str(&format!("{:02}", time.minute)),
+            Piece::Second => output.push_str(&format!("{:02}", time.second)),
+        }
+    }
+
+    Ok(output)
+}
+
+/// Reads `text` back with `pattern`, expecting exactly the digits `format_time` writes. Fields
+/// the pattern leaves out are taken from midnight, January 1st 1970.
+///
+/// Returns `Ok(None)` when `text` doesn't match or names a date that doesn't exist, and an
+/// error only when the pattern itself is invalid.
+pub fn parse_time(text: &str, pattern: &str) -> Result<Option<f64>, String> {
+    let pieces = compile(pattern)?;
+    let mut time = DateTime {
+        year: 1970,
+        month: 1,
+        day: 1,
+        hour: 0,
+        minute: 0,
+        second: 0,
+    };
+
+    let mut rest = text;
+    for piece in pieces {
+        let (field, width) = match piece {
+            Piece::Literal(c) => match rest.strip_prefix(c) {
+                Some(after) => {
+                    rest = after;
+                    continue;
+                }
+                None => return Ok(None),
+            },
+            Piece::Year => (&mut time.year, 4),
+            Piece::Month => (&mut time.month, 2),
+            Piece::Day => (&mut time.day, 2),
+            Piece::Hour => (&mut time.hour, 2),
+            Piece::Minute => (&mut time.minute, 2),
+            Piece::Second => (&mut time.second, 2),
+        };
+
+        match take_digits(rest, width) {
+            Some((value, after)) => {
+                *field = value;
+                rest = after;
+            }
+            None => return Ok(None),
+        }
+    }
+
+    let valid = rest.is_empty()
+        && (1..=12).contains(&time.month)
+        && (1..=days_in_month(time.year, time.month)).contains(&time.day)
+        && time.hour < 24
+        && time.minute < 60
+        && time.second < 60;
+
+    Ok(valid.then(|| time.to_millis() as f64))
+}
+
+fn take_digits(text: &str, width: usize) -> Option<(i64, &str)> {
+    let digits = text.get(..width)?;
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some((digits.parse().ok()?, &text[width..]))
+}
+
+fn to_whole_millis(millis: f64) -> Result<i64, String> {
+    // About 285,000 years either side of 1970, far beyond any date worth formatting.
+    const LIMIT: f64 = 9e15;
+
+    if millis.is_finite() && millis.abs() <= LIMIT {
+        Ok(millis.floor() as i64)
+    } else {
+        Err(format!(
+            "{} is not a time that can be formatted.",
+            format_number(millis)
+        ))
+    }
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar, after Howard Hinnant's
+/// `days_from_civil`.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The inverse of `days_from_civil`, as `(year, month, day)`.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400;
+
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: f64 = (SECONDS_PER_DAY * MILLIS_PER_SECOND) as f64;
+    const FULL: &str = "%Y-%m-%d %H:%M:%S";
+
+    fn parse(text: &str) -> Option<f64> {
+        parse_time(text, FULL).unwrap()
+    }
+
+    fn format(millis: f64) -> String {
+        format_time(millis, FULL).unwrap()
+    }
+
+    #[test]
+    fn leap_years() {
+        for (year, leap) in [
+            (1970, false),
+            (2000, true),
+            (1900, false),
+            (2024, true),
+            (2023, false),
+        ] {
+            assert_eq!(is_leap_year(year), leap, "{}", year);
+        }
+        assert_eq!(
+            parse("2024-02-29 00:00:00").map(format),
+            Some("2024-02-29 00:00:00".to_string())
+        );
+        assert_eq!(parse("2023-02-29 00:00:00"), None);
+        assert_eq!(parse("1900-02-29 00:00:00"), None);
+        assert!(parse("2000-02-29 00:00:00").is_some());
+    }
+
+    #[test]
+    fn the_day_after_the_end_of_each_month() {
+        for (last, next) in [
+            ("2023-01-31", "2023-02-01"),
+            ("2023-02-28", "2023-03-01"),
+            ("2024-02-29", "2024-03-01"),
+            ("2023-04-30", "2023-05-01"),
+            ("2023-12-31", "2024-01-01"),
+        ] {
+            let millis = parse_time(last, "%Y-%m-%d").unwrap().unwrap();
+            assert_eq!(format_time(millis + DAY, "%Y-%m-%d").unwrap(), next);
+        }
+        assert_eq!(parse_time("2023-04-31", "%Y-%m-%d").unwrap(), None);
+        assert_eq!(parse_time("2023-13-01", "%Y-%m-%d").unwrap(), None);
+        assert_eq!(parse_time("2023-00-10", "%Y-%m-%d").unwrap(), None);
+    }
+
+    #[test]
+    fn times_around_the_epoch() {
+        assert_eq!(format(0.0), "1970-01-01 00:00:00");
+        assert_eq!(format(-1.0), "1969-12-31 23:59:59");
+        assert_eq!(format(1999.0), "1970-01-01 00:00:01");
+        assert_eq!(parse("1970-01-01 00:00:00"), Some(0.0));
+        assert_eq!(parse("1969-12-31 23:59:59"), Some(-1000.0));
+    }
+
+    #[test]
+    fn parsing_undoes_formatting() {
+        for millis in [
+            0.0,
+            951_782_400_000.0,
+            1_700_000_000_000.0,
+            -2_208_988_800_000.0,
+        ] {
+            assert_eq!(parse(&format(millis)), Some(millis));
+        }
+    }
+
+    #[test]
+    fn fields_left_out_start_from_the_epoch() {
+        assert_eq!(parse_time("12:30", "%H:%M").unwrap(), Some(45_000_000.0));
+        assert_eq!(parse_time("at 100%", "at 100%%").unwrap(), Some(0.0));
+    }
+
+    #[test]
+    fn mismatches_are_none() {
+        assert_eq!(parse("2024-01-01"), None);
+        assert_eq!(parse("2024-01-01 00:00:00 extra"), None);
+        assert_eq!(parse("2024-1-01 00:00:00"), None);
+        assert_eq!(parse("2024-01-01 24:00:00"), None);
+        assert_eq!(parse("2024-01-01 00:60:00"), None);
+    }
+
+    #[test]
+    fn invalid_patterns_are_errors() {
+        assert_eq!(
+            format_time(0.0, "%Y/%q"),
+            Err("Unknown directive '%q' in time pattern \"%Y/%q\"; \
+                 use %Y %m %d %H %M %S or %%."
+                .into())
+        );
+        assert_eq!(
+            parse_time("2024", "%Y%"),
+            Err("Time pattern \"%Y%\" ends in a lone '%'; write '%%' for a percent sign.".into())
+        );
+    }
+
+    #[test]
+    fn unformattable_times_are_errors() {
+        assert_eq!(
+            format_time(f64::NAN, FULL),
+            Err("NaN is not a time that can be formatted.".into())
+        );
+        assert!(format_time(1e16, FULL).is_err());
+    }
+}
@@ -1,20 +1,74 @@
 use std::str::Chars;
 
+use crate::source_map::FileId;
+
 pub struct Cursor<'a> {
-    initial_len: usize,
+    file: FileId,
+    /// Byte offset of the input in the whole source.
+    base: usize,
+    input: &'a str,
     chars: Chars<'a>,
+    line: usize,
+    /// Characters consumed on the current line.
+    line_chars: usize,
+    /// Whether whitespace and comments become tokens rather than being skipped.
+    keep_trivia: bool,
+    /// The most bytes a string literal may take between its quotes.
+    max_string_literal: usize,
 }
 
 pub const EOF_CHAR: char = '\0';
 
 impl<'a> Cursor<'a> {
-    pub fn new(input: &'a str) -> Cursor<'a> {
+    pub fn new(input: &'a str, file: FileId) -> Cursor<'a> {
+        Self::at(input, file, 0, 1, 1)
+    }
+
+    /// A cursor over a piece of a longer source, which starts at byte offset `start`, on
+    /// `line` and `column`.
+    pub fn at(
+        input: &'a str,
+        file: FileId,
+        start: usize,
+        line: usize,
+        column: usize,
+    ) -> Cursor<'a> {
         Cursor {
-            initial_len: input.len(),
+            file,
+            base: start,
+            input,
             chars: input.chars(),
+            line,
+            line_chars: column - 1,
+            keep_trivia: false,
+            max_string_literal: super::DEFAULT_MAX_STRING_LITERAL,
+        }
+    }
+
+    /// Makes string literals longer than `max` bytes lex as `StringTooLong`.
+    pub fn limiting_strings_to(self, max: usize) -> Self {
+        Self {
+            max_string_literal: max,
+            ..self
+        }
+    }
+
+    pub fn max_string_literal(&self) -> usize {
+        self.max_string_literal
+    }
+
+    /// Makes the cursor hand out whitespace and comments as tokens of their own.
+    pub fn keeping_trivia(self) -> Self {
+        Self {
+            keep_trivia: true,
+            ..self
         }
     }
 
+    pub fn keeps_trivia(&self) -> bool {
+        self.keep_trivia
+    }
+
     pub fn first(&self) -> char {
         self.chars.clone().next().unwrap_or(EOF_CHAR)
     }
@@ -25,27 +79,114 @@ impl<'a> Cursor<'a> {
         iter.next().unwrap_or(EOF_CHAR)
     }
 
+    pub fn third(&self) -> char {
+        let mut iter = self.chars.clone();
+        iter.next();
+        iter.next();
+        iter.next().unwrap_or(EOF_CHAR)
+    }
+
     pub fn is_eof(&self) -> bool {
         self.chars.as_str().is_empty()
     }
 
-    pub fn len_consumed(&self) -> usize {
-        self.initial_len - self.chars.as_str().len()
+    pub fn file(&self) -> FileId {
+        self.file
     }
 
-    pub fn reset_len_consumed(&mut self) {
-        self.initial_len = self.chars.as_str().len()
+    /// Byte offset of the next character in the source.
+    pub fn pos(&self) -> usize {
+        self.base + self.input.len() - self.chars.as_str().len()
+    }
+
+    /// The text consumed since byte offset `start` in the source.
+    pub fn text_since(&self, start: usize) -> String {
+        self.input[start - self.base..self.pos() - self.base].to_string()
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// 1-based column of the next character, counted in characters.
+    pub fn column(&self) -> usize {
+        self.line_chars + 1
     }
 
     pub fn bump(&mut self) -> Option<char> {
         let c = self.chars.next()?;
 
+        match c {
+            // `\r\n` and a lone `\r` end a line just like `\n` does.
+            '\n' => self.new_line(),
+            '\r' if self.first() != '\n' => self.new_line(),
+            '\r' => (),
+            _ => self.line_chars += 1,
+        }
+
         Some(c)
     }
 
+    fn new_line(&mut self) {
+        self.line += 1;
+        self.line_chars = 0;
+    }
+
+    /// Whether `c` ends a line: `\n`, or `\r` on its own or before `\n`.
+    pub fn is_line_end(c: char) -> bool {
+        c == '\n' || c == '\r'
+    }
+
     pub fn eat_while(&mut self, mut predicate: impl FnMut(char) -> bool) {
         while predicate(self.first()) && !self.is_eof() {
             self.bump();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The line and column after consuming each character of `input`.
+    fn positions(input: &str) -> Vec<(usize, usize)> {
+        let mut cursor = Cursor::new(input, FileId::default());
+        let mut positions = vec![];
+        while cursor.bump().is_some() {
+            positions.push((cursor.line(), cursor.column()));
+        }
+        positions
+    }
+
+    #[test]
+    fn tabs_are_one_column() {
+        assert_eq!(positions("\ta\t"), [(1, 2), (1, 3), (1, 4)]);
+    }
+
+    #[test]
+    fn line_breaks_reset_the_column() {
+        assert_eq!(
+            positions("a\nb\r\nc\rd"),
+            [
+                (1, 2),
+                (2, 1),
+                (2, 2),
+                (2, 2),
+                (3, 1),
+                (3, 2),
+                (4, 1),
+                (4, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn characters_are_counted_rather_than_bytes() {
+        assert_eq!(positions("é😀x"), [(1, 2), (1, 3), (1, 4)]);
+
+        let mut cursor = Cursor::new("é😀x", FileId::default());
+        cursor.bump();
+        cursor.bump();
+        assert_eq!(cursor.pos(), 6);
+    }
+}
@@ -0,0 +1,83 @@
+//! Doc comments: runs of `///` lines right above a declaration.
+//!
+//! The lexer drops comments, so instead of carrying them through the token stream the parser
+//! asks for the doc comment of a declaration once it knows where the declaration starts.
+
+/// The doc comment ending on the line above byte offset `start` in `source`, if the
+/// declaration starting there has one. Only `///` lines count, each with the slashes and a
+/// space after them removed, and they are joined with line breaks. A blank line, ordinary
+/// comment or code between them and the declaration leaves it undocumented, as does code
+/// before it on its own line.
+pub fn doc_comment(source: &str, start: usize) -> Option<String> {
+    let (mut above, line_start) = split_last_line(source.get(..start)?);
+    if !line_start.trim().is_empty() {
+        return None;
+    }
+
+    // Walked upwards a line at a time, since the source may be large and have many
+    // declarations.
+    let mut lines = vec![];
+    while let Some(text) = above {
+        let (rest, line) = split_last_line(text);
+        let Some(doc) = line.trim().strip_prefix("///") else {
+            break;
+        };
+        lines.push(doc.strip_prefix(' ').unwrap_or(doc));
+        above = rest;
+    }
+    if lines.is_empty() {
+        return None;
+    }
+
+    lines.reverse();
+    Some(lines.join("\n"))
+}
+
+/// Splits the last line off `text`: what comes before its line break, if it has one, and the
+/// line itself.
+fn split_last_line(text: &str) -> (Option<&str>, &str) {
+    match text.rfind(['\n', '\r']) {
+        Some(end) => {
+            let before = &text[..end];
+            let before = match &text[end..] {
+                rest if rest.starts_with('\n') => before.strip_suffix('\r').unwrap_or(before),
+                _ => before,
+            };
+            (Some(before), &text[end + 1..])
+        }
+        None => (None, text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc_of(source: &str, declaration: &str) -> Option<String> {
+        doc_comment(source, source.find(declaration).unwrap())
+    }
+
+    #[test]
+    fn lines_are_joined() {
+        assert_eq!(
+            doc_of("/// Adds.\n///   Indented.\n///\nfun add() {}", "fun"),
+            Some("Adds.\n  Indented.\n".into())
+        );
+        assert_eq!(
+            doc_of("  ///Tight.\r\n/// CRLF.\r  fun f() {}", "fun"),
+            Some("Tight.\nCRLF.".into())
+        );
+    }
+
+    #[test]
+    fn only_comments_right_above_count() {
+        assert_eq!(doc_of("/// Gone.\n\nfun f() {}", "fun"), None);
+        assert_eq!(doc_of("/// Gone.\n// note\nfun f() {}", "fun"), None);
+        assert_eq!(doc_of("/// Gone.\nvar x = 1; fun f() {}", "fun"), None);
+        assert_eq!(doc_of("fun f() {}", "fun"), None);
+        assert_eq!(
+            doc_of("/// Old.\nvar x;\n/// New.\nfun f() {}", "fun"),
+            Some("New.".into())
+        );
+    }
+}
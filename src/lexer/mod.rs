@@ -1,10 +1,20 @@
+use unicode_ident::{is_xid_continue, is_xid_start};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::source_map::FileId;
+
 use self::cursor::{Cursor, EOF_CHAR};
 
 mod cursor;
+mod docs;
+mod stream;
 
-pub enum LexingError {
-    UnidentifiedToken,
-}
+pub use self::{docs::doc_comment, stream::tokenize_reader};
+
+/// The most bytes a string literal may take between its quotes unless the runner is told
+/// otherwise. Longer literals are almost always generated data that belongs in a file of its
+/// own.
+pub const DEFAULT_MAX_STRING_LITERAL: usize = 16 * 1024 * 1024;
 
 #[derive(Debug, Clone)]
 pub enum TokenKind {
@@ -12,7 +22,10 @@ pub enum TokenKind {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
+    Colon,
     Dot,
     Minus,
     Plus,
@@ -28,6 +41,9 @@ pub enum TokenKind {
     Ge,
     Lt,
     Le,
+    QuestionQuestion,
+    QuestionDot,
+    Arrow,
 
     Identifier(String),
     String(String),
@@ -35,11 +51,16 @@ pub enum TokenKind {
 
     And,
     Class,
+    Const,
+    Defer,
     Else,
+    Enum,
+    Extend,
     False,
     Fun,
     For,
     If,
+    Match,
     Nil,
     Or,
     Print,
@@ -50,26 +71,233 @@ pub enum TokenKind {
     Var,
     While,
 
-    Whitespace,
+    /// A run of whitespace, only produced by `tokenize_trivia`.
+    Whitespace(String),
+    /// A `//` comment, holding its text after the slashes. Only produced by
+    /// `tokenize_trivia`.
+    LineComment(String),
     Unknown,
+    UnterminatedString,
+    /// A string literal longer than the lexer allows, with its length and the limit in bytes.
+    StringTooLong {
+        length: usize,
+        max: usize,
+    },
     Eof,
 }
 
+/// A region of the source: byte offsets `start..end` into `file`, and the line and column
+/// `start` is at.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub file: FileId,
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    /// The span of code built in Rust rather than parsed from source.
+    pub fn generated() -> Span {
+        Span {
+            file: FileId::GENERATED,
+            ..Span::default()
+        }
+    }
+
+    /// The span from the start of `self` to the end of `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            end: other.end.max(self.end),
+            ..self
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Token {
     pub value: TokenKind,
     pub length: usize,
+    /// The token's text. It is left empty for string literals, whose value is in `value` and
+    /// whose text is in the source at `span()`; a third copy of a large literal would only
+    /// waste memory.
     pub lexeme: String,
+    /// The line the token starts on.
+    pub line: usize,
+    /// Byte offset of the token in the source.
+    pub start: usize,
+    pub column: usize,
+    pub file: FileId,
+}
+
+/// Tokens are equal when they are of the same kind and have the same text, wherever they are.
+impl PartialEq for Token {
+    fn eq(&self, other: &Self) -> bool {
+        std::mem::discriminant(&self.value) == std::mem::discriminant(&other.value)
+            && self.lexeme == other.lexeme
+    }
 }
 
-pub fn tokenize(input: &str) -> impl Iterator<Item = Token> + '_ {
-    let mut cursor = Cursor::new(input);
+impl Token {
+    /// A token of generated code, with `lexeme` as its text.
+    pub fn generated(value: TokenKind, lexeme: &str) -> Token {
+        Token {
+            value,
+            length: lexeme.len(),
+            lexeme: lexeme.to_string(),
+            line: 0,
+            start: 0,
+            column: 0,
+            file: FileId::GENERATED,
+        }
+    }
+
+    /// The token of the name `name` in generated code.
+    pub fn identifier(name: &str) -> Token {
+        Token::generated(TokenKind::Identifier(name.to_string()), name)
+    }
+
+    pub fn span(&self) -> Span {
+        Span {
+            file: self.file,
+            start: self.start,
+            end: self.start + self.length,
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    /// The token's kind and where it starts, such as `Identifier("foo") at line 3, col 7`.
+    pub fn describe(&self) -> String {
+        format!(
+            "{:?} at line {}, col {}",
+            self.value, self.line, self.column
+        )
+    }
+}
+
+/// Describes the problem with a token the lexer couldn't make sense of, if there is one.
+pub fn error_message(token: &Token) -> Option<String> {
+    match token.value {
+        TokenKind::Unknown => {
+            let c = token.lexeme.chars().next().unwrap_or(EOF_CHAR);
+            Some(format!(
+                "Unexpected character '{}' (U+{:04X}).",
+                c, c as u32
+            ))
+        }
+        TokenKind::UnterminatedString => Some("Unterminated string.".into()),
+        TokenKind::StringTooLong { length, max } => Some(format!(
+            "String literal is {} bytes long, over the limit of {} bytes. Load data this \
+             large from a file instead.",
+            length, max
+        )),
+        _ => None,
+    }
+}
+
+/// Lexes `input` and lays the tokens out as a table, one per line, with where each starts,
+/// its kind and its text.
+///
+/// ```text
+/// LINE:COL  TOKEN       LEXEME
+/// 1:1       Print       print
+/// 1:7       Identifier  x
+/// ```
+pub fn dump(input: &str) -> String {
+    let rows: Vec<[String; 3]> = tokenize(input, FileId::default())
+        .map(|token| {
+            let kind = format!("{:?}", token.value);
+            let kind = kind
+                .split(['(', ' '])
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            let lexeme = match token.value {
+                // The end-of-input marker has no text worth printing.
+                TokenKind::Eof => String::new(),
+                TokenKind::String(_)
+                | TokenKind::UnterminatedString
+                | TokenKind::StringTooLong { .. } => {
+                    let text = &input[token.start..token.start + token.length];
+                    // Line breaks in strings are `\n` whatever the file uses.
+                    escape_controls(&text.replace("\r\n", "\n").replace('\r', "\n"))
+                }
+                _ => escape_controls(&token.lexeme),
+            };
+            [format!("{}:{}", token.line, token.column), kind, lexeme]
+        })
+        .collect();
+
+    let header = ["LINE:COL", "TOKEN", "LEXEME"].map(String::from);
+    let width = |column: usize| {
+        std::iter::once(&header)
+            .chain(&rows)
+            .map(|row| row[column].chars().count())
+            .max()
+            .unwrap_or_default()
+    };
+    let (position_width, kind_width) = (width(0), width(1));
+
+    std::iter::once(&header)
+        .chain(&rows)
+        .map(|[position, kind, lexeme]| {
+            let line = format!(
+                "{:position_width$}  {:kind_width$}  {}",
+                position, kind, lexeme
+            );
+            format!("{}\n", line.trim_end())
+        })
+        .collect()
+}
+
+/// `text` with its control characters escaped, so a string spanning lines stays on one row.
+fn escape_controls(text: &str) -> String {
+    text.chars()
+        .map(|c| match c.is_control() {
+            true => c.escape_debug().to_string(),
+            false => c.to_string(),
+        })
+        .collect()
+}
+
+/// Lexes `input` like `tokenize`, but keeps whitespace and comments as `Whitespace` and
+/// `LineComment` tokens instead of skipping them, for tools that work on the source as it was
+/// written. Each lexeme is the token's text exactly as it appears in `input`, so joining them
+/// gives back `input`. The parser must be given `tokenize`'s tokens instead.
+pub fn tokenize_trivia(input: &str) -> impl Iterator<Item = Token> + '_ {
+    let mut cursor = Cursor::new(input, FileId::default()).keeping_trivia();
+
+    std::iter::from_fn(move || {
+        if cursor.is_eof() {
+            return None;
+        }
+
+        let mut token = cursor.advance_token();
+        // Strings and identifiers are normalized in their lexemes, so take the raw text.
+        token.lexeme = input[token.start..token.start + token.length].to_string();
+        Some(token)
+    })
+}
+
+pub fn tokenize(input: &str, file: FileId) -> impl Iterator<Item = Token> + '_ {
+    tokenize_with_limit(input, file, DEFAULT_MAX_STRING_LITERAL)
+}
+
+/// Lexes `input` like `tokenize`, letting string literals take up to `max_string_literal`
+/// bytes between their quotes.
+pub fn tokenize_with_limit(
+    input: &str,
+    file: FileId,
+    max_string_literal: usize,
+) -> impl Iterator<Item = Token> + '_ {
+    let mut cursor = Cursor::new(input, file).limiting_strings_to(max_string_literal);
 
     std::iter::from_fn(move || {
         if cursor.is_eof() {
             None
         } else {
-            cursor.reset_len_consumed();
             Some(cursor.advance_token())
         }
     })
@@ -77,6 +305,10 @@ pub fn tokenize(input: &str) -> impl Iterator<Item = Token> + '_ {
 
 impl Cursor<'_> {
     fn advance_token(&mut self) -> Token {
+        let start = self.pos();
+        let line = self.line();
+        let column = self.column();
+
         let c = match self.bump() {
             Some(c) => c,
             None => EOF_CHAR,
@@ -87,9 +319,21 @@ impl Cursor<'_> {
             ')' => (TokenKind::RightParen, c.to_string()),
             '{' => (TokenKind::LeftBrace, c.to_string()),
             '}' => (TokenKind::RightBrace, c.to_string()),
+            '[' => (TokenKind::LeftBracket, c.to_string()),
+            ']' => (TokenKind::RightBracket, c.to_string()),
             ',' => (TokenKind::Comma, c.to_string()),
+            ':' => (TokenKind::Colon, c.to_string()),
             '.' => (TokenKind::Dot, c.to_string()),
-            '-' => (TokenKind::Minus, c.to_string()),
+            '-' => {
+                if self.first() == '>' {
+                    let mut lex = String::from(c);
+                    let c = self.bump().unwrap();
+                    lex.push(c);
+                    (TokenKind::Arrow, lex)
+                } else {
+                    (TokenKind::Minus, c.to_string())
+                }
+            }
             '+' => (TokenKind::Plus, c.to_string()),
             ';' => (TokenKind::Semicolon, c.to_string()),
             '*' => (TokenKind::Star, c.to_string()),
@@ -133,54 +377,108 @@ impl Cursor<'_> {
                     (TokenKind::Lt, c.to_string())
                 }
             }
+            '?' => {
+                if self.first() == '?' {
+                    let mut lex = String::from(c);
+                    let c = self.bump().unwrap();
+                    lex.push(c);
+                    (TokenKind::QuestionQuestion, lex)
+                } else if self.first() == '.' {
+                    let mut lex = String::from(c);
+                    let c = self.bump().unwrap();
+                    lex.push(c);
+                    (TokenKind::QuestionDot, lex)
+                } else {
+                    (TokenKind::Unknown, c.to_string())
+                }
+            }
             '/' => {
                 if self.first() == '/' {
-                    self.eat_while(|c| c != '\n');
-                    return self.advance_token();
+                    let text_start = self.pos() + 1;
+                    self.eat_while(|c| !Cursor::is_line_end(c));
+                    if !self.keeps_trivia() {
+                        return self.advance_token();
+                    }
+                    let text = self.text_since(text_start);
+                    (TokenKind::LineComment(text), self.text_since(start))
                 } else {
                     (TokenKind::Slash, c.to_string())
                 }
             }
-            // c if c.is_whitespace() => (TokenKind::Whitespace, c.to_string()),
-            c if c.is_whitespace() => return self.advance_token(),
-            '"' => self.string(),
-            c if c.is_digit(10) => self.number(c),
-            c if c.is_alphabetic() || c == '_' => self.identifier(c),
+            c if c.is_whitespace() => {
+                if !self.keeps_trivia() {
+                    return self.advance_token();
+                }
+                self.eat_while(char::is_whitespace);
+                let text = self.text_since(start);
+                (TokenKind::Whitespace(text.clone()), text)
+            }
+            '"' => (self.string(start), String::new()),
+            c if c.is_ascii_digit() => self.number(c),
+            c if is_xid_start(c) || c == '_' => self.identifier(c),
             EOF_CHAR => (TokenKind::Eof, c.to_string()),
             _ => (TokenKind::Unknown, c.to_string()),
         };
 
         Token {
             value: token_kind,
-            length: self.len_consumed(),
+            length: self.pos() - start,
             lexeme,
+            line,
+            start,
+            column,
+            file: self.file(),
         }
     }
 
-    fn string(&mut self) -> (TokenKind, String) {
+    /// Lexes a string literal whose opening quote, at `start`, was just consumed. Once one
+    /// is known to be too long, the rest of it is only skipped over.
+    fn string(&mut self, start: usize) -> TokenKind {
+        let max = self.max_string_literal();
         let mut val = String::new();
         while let Some(c) = self.bump() {
-            if c == '"' {
-                let lex = format!("\"{}\"", &val);
-                return (TokenKind::String(val), lex);
+            match c {
+                '"' => {
+                    let length = self.pos() - start - 2;
+                    return match length > max {
+                        true => TokenKind::StringTooLong { length, max },
+                        false => TokenKind::String(val),
+                    };
+                }
+                _ if val.len() > max => (),
+                // Line breaks in a string are `\n` whatever the file uses.
+                '\r' if self.first() == '\n' => (),
+                '\r' => val.push('\n'),
+                c => val.push(c),
             }
-
-            val.push(c)
         }
 
-        (TokenKind::Eof, String::new())
+        TokenKind::UnterminatedString
     }
 
     fn number(&mut self, first_digit: char) -> (TokenKind, String) {
         let mut val = String::from(first_digit);
-        while self.first().is_digit(10) {
+        while self.first().is_ascii_digit() {
             let c = self.bump().unwrap();
             val.push(c);
         }
 
-        if self.first() == '.' && self.second().is_digit(10) {
+        if self.first() == '.' && self.second().is_ascii_digit() {
             val.push(self.bump().unwrap());
-            while self.first().is_digit(10) {
+            while self.first().is_ascii_digit() {
+                let c = self.bump().unwrap();
+                val.push(c);
+            }
+        }
+
+        // An exponent, the way `print` writes numbers of magnitude 1e21 and above.
+        let signed = matches!(self.second(), '+' | '-') && self.third().is_ascii_digit();
+        if matches!(self.first(), 'e' | 'E') && (self.second().is_ascii_digit() || signed) {
+            val.push(self.bump().unwrap());
+            if signed {
+                val.push(self.bump().unwrap());
+            }
+            while self.first().is_ascii_digit() {
                 let c = self.bump().unwrap();
                 val.push(c);
             }
@@ -194,20 +492,28 @@ impl Cursor<'_> {
     fn identifier(&mut self, starting_char: char) -> (TokenKind, String) {
         let mut val = String::from(starting_char);
 
-        while self.first().is_alphanumeric() || self.first() == '_' {
+        while is_xid_continue(self.first()) {
             let c = self.bump().unwrap();
             val.push(c);
         }
 
+        // Identifiers are compared in NFC so that precomposed and combining spellings of the
+        // same name refer to the same variable.
+        let val: String = val.nfc().collect();
         let lexeme = val.clone();
         let token = match val.as_str() {
             "and" => TokenKind::And,
             "class" => TokenKind::Class,
+            "const" => TokenKind::Const,
+            "defer" => TokenKind::Defer,
             "else" => TokenKind::Else,
+            "enum" => TokenKind::Enum,
+            "extend" => TokenKind::Extend,
             "false" => TokenKind::False,
             "for" => TokenKind::For,
             "fun" => TokenKind::Fun,
             "if" => TokenKind::If,
+            "match" => TokenKind::Match,
             "nil" => TokenKind::Nil,
             "or" => TokenKind::Or,
             "print" => TokenKind::Print,
@@ -222,12 +528,4 @@ impl Cursor<'_> {
 
         (token, lexeme)
     }
-
-    fn is_part_number(c: char) -> bool {
-        match c {
-            c if c.is_digit(10) => true,
-            '.' => true,
-            _ => false,
-        }
-    }
 }
@@ -0,0 +1,116 @@
+use std::{collections::VecDeque, io, io::BufRead};
+
+use crate::source_map::FileId;
+
+use super::{
+    cursor::{Cursor, EOF_CHAR},
+    Token, TokenKind,
+};
+
+/// Tokenizes the source `reader` yields as it is read, a line at a time, so that only the
+/// line being lexed is held in memory. The tokens are the ones `tokenize` would make from
+/// the whole source, except that a single `Eof` always ends them.
+pub fn tokenize_reader<R: BufRead>(reader: R, file: FileId) -> ReaderTokens<R> {
+    ReaderTokens {
+        reader,
+        file,
+        pending: String::new(),
+        start: 0,
+        line: 1,
+        column: 1,
+        tokens: VecDeque::new(),
+        finished: false,
+    }
+}
+
+/// The tokens of a source being read, made by `tokenize_reader`. Reading stops at the first
+/// error, which is yielded in place of the rest of the tokens.
+pub struct ReaderTokens<R> {
+    reader: R,
+    file: FileId,
+    /// Source read but not yet lexed: the current line, or more when a string spans lines.
+    pending: String,
+    /// Where `pending` starts in the source: its byte offset, line and column.
+    start: usize,
+    line: usize,
+    column: usize,
+    /// Tokens lexed but not yet handed out.
+    tokens: VecDeque<Token>,
+    finished: bool,
+}
+
+impl<R: BufRead> ReaderTokens<R> {
+    /// Reads and lexes lines until there are tokens to hand out or the source ends.
+    fn fill(&mut self) -> io::Result<()> {
+        while self.tokens.is_empty() && !self.finished {
+            if self.reader.read_line(&mut self.pending)? == 0 {
+                self.finished = true;
+            }
+            self.lex_pending();
+        }
+
+        Ok(())
+    }
+
+    /// Lexes what has been read so far. A string still open at the end of it is left
+    /// pending, to be lexed again once more of the source has been read.
+    fn lex_pending(&mut self) {
+        let mut cursor = Cursor::at(&self.pending, self.file, self.start, self.line, self.column);
+        let mut tokens = vec![];
+        while !cursor.is_eof() {
+            tokens.push(cursor.advance_token());
+        }
+        let (line, column) = (cursor.line(), cursor.column());
+        let end = cursor.pos();
+
+        // Only the end of the source ends the token stream, not the end of a line.
+        if matches!(tokens.last(), Some(token) if matches!(token.value, TokenKind::Eof)) {
+            tokens.pop();
+        }
+
+        match tokens.last() {
+            Some(token)
+                if matches!(token.value, TokenKind::UnterminatedString) && !self.finished =>
+            {
+                let token = tokens.pop().unwrap();
+                self.pending.drain(..token.start - self.start);
+                self.start = token.start;
+                self.line = token.line;
+                self.column = token.column;
+            }
+            _ => {
+                self.pending.clear();
+                self.start = end;
+                self.line = line;
+                self.column = column;
+            }
+        }
+
+        self.tokens.extend(tokens);
+        if self.finished {
+            self.tokens.push_back(Token {
+                value: TokenKind::Eof,
+                length: 0,
+                lexeme: EOF_CHAR.to_string(),
+                line: self.line,
+                start: self.start,
+                column: self.column,
+                file: self.file,
+            });
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for ReaderTokens<R> {
+    type Item = io::Result<Token>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Err(e) = self.fill() {
+            self.finished = true;
+            self.tokens.clear();
+            return Some(Err(e));
+        }
+
+        self.tokens.pop_front().map(Ok)
+    }
+}
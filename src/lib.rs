@@ -1,45 +1,546 @@
+//! A tree-walking interpreter for Lox.
+//!
+//! Besides the `lox` command, the interpreter can be embedded. A host can hand values to a
+//! script as predefined globals and read back what the script defined, for example to fill
+//! in a template:
+//!
+//! ```
+//! use rlox::Runner;
+//!
+//! let mut runner = Runner::new();
+//! runner.define_global("user_name", "alice");
+//! runner.define_global("unread", 3);
+//! runner.define_global_map("config", [("greeting", "Hello")]);
+//!
+//! runner
+//!     .run(
+//!         "template.lox",
+//!         r#"var message = config.get("greeting") + ", " + user_name + "! You have " +
+//!                unread.toString() + " new messages.";"#,
+//!     )
+//!     .unwrap();
+//!
+//! assert_eq!(
+//!     runner.interpreter().get_global_string("message").unwrap(),
+//!     "Hello, alice! You have 3 new messages."
+//! );
+//! ```
+//!
+//! Globals follow a last-write-wins rule: defining a name that already exists, whether from
+//! the host or with a top-level `var` in a script, replaces its value.
+
+/// Emits a debug-level event through the `log` crate.
+#[cfg(feature = "log")]
+macro_rules! log_event {
+    ($($arg:tt)*) => {
+        log::debug!($($arg)*)
+    };
+}
+
+/// Without the `log` feature events are never formatted; the arguments are only type-checked
+/// so both builds see the same code.
+#[cfg(not(feature = "log"))]
+macro_rules! log_event {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    };
+}
+
 mod ast;
+pub mod cli;
+mod comments;
+mod debugger;
+mod diagnostics;
+mod docs;
 mod environment;
+mod error;
+mod expect;
+mod init;
+pub mod inspect;
 mod interpreter;
 mod lexer;
+mod modules;
+mod optimizer;
 mod parser;
+mod resolver;
 mod runner;
+mod source_map;
+mod suggest;
+#[cfg(feature = "testing")]
+pub mod testing;
 
-use std::{fs, io};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
 
-use runner::Runner;
+use debugger::Debugger;
+use parser::ParserOptions;
 use rustyline::{error::ReadlineError, Editor};
+use source_map::SourceMap;
 
-const HISTORY_PATH: &'static str = ".dev-data/history";
+pub use ast::{
+    expr::{BinOp, Expr, ExprKind, Literal, LogOp, UnOp},
+    lower::lower_for_loops,
+    pattern::{ArmBody, MatchArm, Pattern, PatternKind},
+    sexpr::{from_sexpr, to_sexpr},
+    source::{to_source, to_source_with_comments},
+    stmt::{Stmt, StmtKind},
+    transform::{walk_expr, walk_pattern, walk_stmt, Fold},
+};
+pub use comments::{attach_comments, Comment, CommentMap};
+pub use diagnostics::{DiagnosticFormat, DiagnosticOptions, DiagnosticSink};
+pub use error::{
+    Diagnostic, LexDiagnostic, LoxError, ParseDiagnostic, RuntimeDiagnostic, Severity, TraceFrame,
+};
+pub use expect::{Expectation, Outcome, TestReport};
+pub use interpreter::{
+    capability::{Capability, CapabilitySet},
+    globals::{GlobalError, Globals},
+    host::{Limits, OutputBuffer},
+    index::IndexError,
+    info::{build_info, BuildInfo, VERSION},
+    interpreter::{Interpreter, InterpreterBuilder, Strictness},
+    native::NativeError,
+    number::format_number,
+    stats::ExecStats,
+};
+pub use lexer::{tokenize_trivia, Span, Token, TokenKind};
+pub use parser::LanguageOptions;
+pub use runner::Runner;
 
-#[derive(Debug)]
-enum LoxErrorType {
-    LexingError,
-    ParsingError,
-    RuntimeError,
+const HISTORY_PATH: &str = ".dev-data/history";
+
+fn read_script(path: &str) -> Result<String, LoxError> {
+    fs::read_to_string(path).map_err(|e| {
+        let message = format!("Could not read '{}': {}", path, e);
+        LoxError::Io(io::Error::new(e.kind(), message))
+    })
+}
+
+/// Checks `code` without running it: lexes, parses and resolves it, allowing only what
+/// `language` allows. The error lists every problem found, reported as coming from `name`.
+///
+/// ```
+/// use rlox::{LanguageOptions, LoxError};
+///
+/// let code = "var total = 0;\nfor (var i = 0; i < 3; i = i + 1) total = total + i;";
+/// assert!(rlox::compile("config.lox", code, LanguageOptions::default()).is_ok());
+///
+/// let no_loops = LanguageOptions {
+///     for_loops: false,
+///     while_loops: false,
+///     ..LanguageOptions::default()
+/// };
+/// match rlox::compile("config.lox", code, no_loops) {
+///     Err(LoxError::Parse(diagnostics)) => {
+///         assert_eq!(diagnostics.len(), 1);
+///         assert_eq!(diagnostics[0].line, 2);
+///         assert_eq!(diagnostics[0].message, "for-loops are not allowed in this context.");
+///     }
+///     other => panic!("expected a parse error, got {:?}", other),
+/// }
+/// ```
+pub fn compile(name: &str, code: &str, language: LanguageOptions) -> Result<(), LoxError> {
+    compile_program(name, code, language, false).map(|_| ())
 }
 
+/// What `compile_program` compiled code to.
 #[derive(Debug)]
-struct LoxError {
-    error_type: LoxErrorType,
-    line: usize,
+pub struct Compiled {
+    pub stmts: Vec<Stmt>,
+    /// The comments in the code attached to `stmts`, when they were asked for.
+    pub comments: Option<CommentMap>,
+}
+
+/// Checks `code` like `compile` and returns the resolved statements. With `comments`, the
+/// comments in the code are attached to the statements as well, for tools that rewrite or
+/// document code.
+///
+/// ```
+/// use rlox::LanguageOptions;
+///
+/// let code = "// The answer.\nvar answer = 42;";
+/// let compiled = rlox::compile_program("answer.lox", code, LanguageOptions::default(), true)
+///     .unwrap();
+/// let comments = compiled.comments.unwrap();
+/// assert_eq!(comments.leading(&compiled.stmts[0])[0].text, " The answer.");
+/// ```
+pub fn compile_program(
+    name: &str,
+    code: &str,
+    language: LanguageOptions,
+    comments: bool,
+) -> Result<Compiled, LoxError> {
+    let mut sources = SourceMap::new();
+    let file = sources.add(name, code);
+    let tokens = runner::lex(&sources, file, lexer::DEFAULT_MAX_STRING_LITERAL)?;
+    let options = ParserOptions {
+        language,
+        ..ParserOptions::default()
+    };
+    let mut sink = DiagnosticSink::default();
+    let mut stmts = parser::parse(tokens.into_iter(), &sources, options, &mut sink)?;
+
+    let shadowing = resolver::Shadowing::Warn {
+        allow: language.allow_shadowing,
+    };
+    resolver::resolve(&mut stmts, &sources, shadowing, &mut sink)?;
+
+    let comments = comments.then(|| attach_comments(code, &stmts));
+    Ok(Compiled { stmts, comments })
+}
+
+/// Summarizes the doc comments in `code` as Markdown: a section for each documented function
+/// and class at the top level, headed by its signature. Doc comments are the `///` lines right
+/// above a declaration. The code is parsed but not run, and `name` titles the summary.
+///
+/// ```
+/// let code = "/// Adds two numbers.\nfun add(a, b) { return a + b; }\nfun undocumented() {}";
+/// assert_eq!(
+///     rlox::extract_docs("math.lox", code).unwrap(),
+///     "# math.lox\n\n## `fun add(a, b)`\n\nAdds two numbers.\n"
+/// );
+/// ```
+pub fn extract_docs(name: &str, code: &str) -> Result<String, LoxError> {
+    let mut sources = SourceMap::new();
+    let file = sources.add(name, code);
+    let tokens = runner::lex(&sources, file, lexer::DEFAULT_MAX_STRING_LITERAL)?;
+    let mut sink = DiagnosticSink::new(DiagnosticOptions {
+        print: None,
+        ..DiagnosticOptions::default()
+    });
+    let stmts = parser::parse(
+        tokens.into_iter(),
+        &sources,
+        ParserOptions::default(),
+        &mut sink,
+    )?;
+    let comments = attach_comments(code, &stmts);
+
+    Ok(docs::markdown(name, &stmts, &comments))
+}
+
+/// Formats `code`: parses it and writes it back out with `to_source_with_comments`, a
+/// statement per line, two spaces of indentation per level and the comments kept.
+///
+/// ```
+/// let code = "var a=1;// one\nif(a>0){print a;}";
+/// assert_eq!(
+///     rlox::format_source("a.lox", code).unwrap(),
+///     "var a = 1; // one\nif (a > 0) {\n  print a;\n}\n"
+/// );
+/// ```
+pub fn format_source(name: &str, code: &str) -> Result<String, LoxError> {
+    let stmts = parse(name, code)?;
+    let comments = attach_comments(code, &stmts);
+    Ok(to_source_with_comments(&stmts, &comments))
 }
 
-pub fn run_file(path: String) -> io::Result<()> {
-    let file_contents = fs::read_to_string(path)?;
+/// Parses `code` into statements without resolving or running it. The error lists every
+/// problem found, reported as coming from `name`. `to_source` turns the statements back into
+/// code.
+///
+/// ```
+/// let stmts = rlox::parse("example.lox", "print (1 + 2) * 3;").unwrap();
+/// assert_eq!(rlox::to_source(&stmts), "print (1 + 2) * 3;\n");
+/// assert!(rlox::parse("example.lox", "print 1 +;").is_err());
+/// ```
+pub fn parse(name: &str, code: &str) -> Result<Vec<Stmt>, LoxError> {
+    let mut sources = SourceMap::new();
+    let file = sources.add(name, code);
+    let tokens = runner::lex(&sources, file, lexer::DEFAULT_MAX_STRING_LITERAL)?;
+    let mut sink = DiagnosticSink::new(DiagnosticOptions {
+        print: None,
+        ..DiagnosticOptions::default()
+    });
+    parser::parse(
+        tokens.into_iter(),
+        &sources,
+        ParserOptions::default(),
+        &mut sink,
+    )
+}
+
+/// Evaluates `source` as a single expression, with `bindings` as its only globals besides the
+/// natives. Nothing but the expression is allowed, not even a trailing `;`. Errors point into
+/// `source`, which is reported as `<expr>`.
+///
+/// Each call starts from a fresh interpreter, so nothing the expression does outlives it.
+///
+/// ```
+/// use rlox::Literal;
+///
+/// let bindings = [("price", Literal::Number(4.0)), ("count", Literal::Number(3.0))];
+/// assert_eq!(rlox::eval_expr("price * count", &bindings).unwrap().to_string(), "12");
+/// assert!(rlox::eval_expr("var total = price;", &bindings).is_err());
+/// ```
+pub fn eval_expr(source: &str, bindings: &[(&str, Literal)]) -> Result<Literal, LoxError> {
+    evaluate(source, bindings, LanguageOptions::default())
+}
+
+/// Evaluates `source` like `eval_expr`, but rejects any call, of natives included, along with
+/// loops and declarations of functions, classes and enums. What is left can only compute a
+/// value from `bindings`.
+///
+/// ```
+/// use rlox::{Literal, LoxError};
+///
+/// let bindings = [("age", Literal::Number(42.0))];
+/// assert_eq!(rlox::eval_pure_expr("age >= 18", &bindings).unwrap().to_string(), "true");
+/// assert!(matches!(rlox::eval_pure_expr("clock() > age", &bindings), Err(LoxError::Parse(_))));
+/// ```
+pub fn eval_pure_expr(source: &str, bindings: &[(&str, Literal)]) -> Result<Literal, LoxError> {
+    let pure = LanguageOptions {
+        classes: false,
+        enums: false,
+        functions: false,
+        for_loops: false,
+        while_loops: false,
+        calls: false,
+        allow_shadowing: &[],
+    };
+    evaluate(source, bindings, pure)
+}
+
+fn evaluate(
+    source: &str,
+    bindings: &[(&str, Literal)],
+    language: LanguageOptions,
+) -> Result<Literal, LoxError> {
     let mut runner = Runner::new();
+    runner.set_language(language);
+    runner.set_diagnostic_options(DiagnosticOptions {
+        print: None,
+        ..DiagnosticOptions::default()
+    });
+    for (name, value) in bindings {
+        runner.define_global(name, value.clone());
+    }
+
+    runner.evaluate("<expr>", source)
+}
+
+/// Runs the script at `path`. With `time` set, a timing report is printed to stderr once it
+/// finishes. The script can only reach the network or run programs if `capabilities` allows
+/// it. Unless `prelude` is false, the prelude runs first.
+///
+/// Nothing is executed if the script has lexing, parsing or resolving errors; the error
+/// returned lists all of them. Warnings are handled as `diagnostics` says, which can also
+/// deny them. A runtime error stops the script and comes with a backtrace.
+pub fn run_file(
+    path: String,
+    time: bool,
+    capabilities: CapabilitySet,
+    prelude: bool,
+    diagnostics: DiagnosticOptions,
+) -> Result<(), LoxError> {
+    let file_contents = read_script(&path)?;
+    run_source(
+        &path,
+        file_contents.as_str(),
+        time,
+        build_interpreter(capabilities, prelude),
+        diagnostics,
+    )
+}
+
+/// Runs the scripts at `paths` in order against one interpreter, so each sees the globals
+/// the ones before it defined, the way `rlox lib.lox main.lox` does. The prelude runs once,
+/// before the first. Stops at the first script that fails to read, compile or run; if any
+/// were left, a note on stderr says which, and the error names the file that failed.
+pub fn run_files(
+    paths: Vec<PathBuf>,
+    time: bool,
+    capabilities: CapabilitySet,
+    prelude: bool,
+    diagnostics: DiagnosticOptions,
+) -> Result<(), LoxError> {
+    let mut runner = Runner::with_interpreter(build_interpreter(capabilities, prelude));
+    runner.set_diagnostic_options(diagnostics);
+
+    for (i, path) in paths.iter().enumerate() {
+        let name = path.to_string_lossy();
+        let result = read_script(&name).and_then(|code| {
+            if time {
+                runner.run_timed(&name, &code)
+            } else {
+                runner.run(&name, &code)
+            }
+        });
+
+        if let Err(e) = result {
+            let skipped: Vec<_> = paths[i + 1..].iter().map(|path| path.display()).collect();
+            match skipped.as_slice() {
+                [] => (),
+                [next] => eprintln!("Stopped at {}, so {} was not run.", name, next),
+                _ => eprintln!(
+                    "Stopped at {}, so {} were not run.",
+                    name,
+                    skipped
+                        .iter()
+                        .map(|path| path.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            }
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Runs `code` given directly on the command line, the same way `run_file` does.
+pub fn run_inline(
+    code: &str,
+    time: bool,
+    capabilities: CapabilitySet,
+    prelude: bool,
+    diagnostics: DiagnosticOptions,
+) -> Result<(), LoxError> {
+    let interpreter = build_interpreter(capabilities, prelude);
+    run_source("<inline>", code, time, interpreter, diagnostics)
+}
+
+fn build_interpreter(capabilities: CapabilitySet, prelude: bool) -> Interpreter {
+    let builder = Interpreter::builder().capabilities(capabilities);
+    if prelude {
+        builder.build()
+    } else {
+        builder.no_prelude().build()
+    }
+}
+
+fn run_source(
+    name: &str,
+    code: &str,
+    time: bool,
+    interpreter: Interpreter,
+    diagnostics: DiagnosticOptions,
+) -> Result<(), LoxError> {
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.set_diagnostic_options(diagnostics);
+
+    if time {
+        runner.run_timed(name, code)
+    } else {
+        runner.run(name, code)
+    }
+}
 
-    runner.run(file_contents.as_str()).unwrap();
+/// Runs the script at `path` as a test: what it prints is captured and checked against its
+/// `// expect: value` comments, one printed line each, in order. The report is printed to
+/// stdout and returned. A runtime error fails the test rather than being returned; errors
+/// that keep the script from running at all are.
+pub fn test_file(
+    path: String,
+    capabilities: CapabilitySet,
+    prelude: bool,
+    diagnostics: DiagnosticOptions,
+) -> Result<TestReport, LoxError> {
+    let code = read_script(&path)?;
+    let output = OutputBuffer::new();
+    let builder = Interpreter::builder()
+        .capabilities(capabilities)
+        .output(Box::new(output.clone()));
+    let interpreter = if prelude {
+        builder.build()
+    } else {
+        builder.no_prelude().build()
+    };
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.set_diagnostic_options(diagnostics);
 
+    let error = match runner.run(&path, &code) {
+        Ok(()) => None,
+        Err(LoxError::Runtime(diagnostic)) => Some(diagnostic),
+        Err(e) => return Err(e),
+    };
+    let report = TestReport::new(
+        &path,
+        expect::expectations(&code),
+        &output.contents(),
+        error,
+    );
+    print!("{}", report);
+    Ok(report)
+}
+
+/// Prints the Markdown summary `extract_docs` makes of the script at `path`.
+pub fn print_docs(path: String) -> Result<(), LoxError> {
+    let code = read_script(&path)?;
+    print!("{}", extract_docs(&path, &code)?);
     Ok(())
 }
 
-pub fn run_prompt() -> io::Result<()> {
+/// Runs the script at `path` under the interactive debugger, which takes its commands from
+/// stdin. Unless `prelude` is false, the prelude runs first, outside the debugger.
+pub fn debug_file(
+    path: String,
+    prelude: bool,
+    diagnostics: DiagnosticOptions,
+) -> Result<(), LoxError> {
+    let file_contents = read_script(&path)?;
+    let mut runner = Runner::with_interpreter(build_interpreter(CapabilitySet::default(), prelude));
+    runner.set_diagnostic_options(diagnostics);
+    runner.set_debug_hook(Box::new(Debugger::new(io::stdin().lock(), io::stdout())));
+
+    runner.run(&path, file_contents.as_str())
+}
+
+/// Loads the module at `path` for the REPL's `:load` and `:reload` commands, reporting what
+/// happened on stderr.
+fn load_module(runner: &mut Runner, path: impl AsRef<Path>, format: DiagnosticFormat) {
+    let path = path.as_ref();
+    let loaded_before = path
+        .canonicalize()
+        .is_ok_and(|path| runner.modules().contains(&path));
+
+    match runner.load(path) {
+        Ok(true) => eprintln!("Loaded {}.", path.display()),
+        Ok(false) => eprintln!("{} is unchanged.", path.display()),
+        Err(e) => {
+            runner.finish_output_line();
+            eprintln!("{}", format.render_error(&e));
+            if loaded_before && !matches!(e, LoxError::Runtime(_)) {
+                eprintln!(
+                    "Warning: keeping the previous version of {}.",
+                    path.display()
+                );
+            }
+        }
+    }
+}
+
+/// Starts the interactive prompt. Unless `load_init` is false, the startup file found by
+/// `init::init_file_path` is run first. Errors in what is typed are printed and the prompt
+/// carries on; only failing to save the history ends it with an error. Warnings and errors
+/// are reported as `diagnostics` says. With `implicit_globals`, assigning to an undefined name
+/// outside a function defines it. Unless `prelude` is false, the prelude runs before the
+/// startup file.
+pub fn run_prompt(
+    load_init: bool,
+    implicit_globals: bool,
+    prelude: bool,
+    diagnostics: DiagnosticOptions,
+) -> Result<(), LoxError> {
     let mut rl = Editor::<()>::new();
     rl.load_history(&HISTORY_PATH).unwrap_or_default();
-    let mut runner = Runner::new();
+    let format = diagnostics.print.unwrap_or(DiagnosticFormat::Plain);
+    let mut runner = Runner::with_interpreter(build_interpreter(CapabilitySet::default(), prelude));
+    runner.set_diagnostic_options(diagnostics);
+    runner.set_implicit_globals(implicit_globals);
+
+    if load_init {
+        if let Some(path) = init::init_file_path() {
+            init::load_init_file(&mut runner, &path);
+        }
+    }
 
     loop {
+        runner.finish_output_line();
         let readline = rl.readline(">> ");
 
         let line = match readline {
@@ -63,11 +564,59 @@ pub fn run_prompt() -> io::Result<()> {
 
         rl.add_history_entry(line.as_str());
 
-        if let Err(_e) = runner.run(line.as_str()) {
+        if line.trim() == ":reload-init" {
+            match init::init_file_path() {
+                Some(path) => init::load_init_file(&mut runner, &path),
+                None => eprintln!("No init file location; set RLOX_INIT."),
+            }
             continue;
         }
+
+        let command = |name: &str| {
+            line.trim_start()
+                .strip_prefix(name)
+                .filter(|code| code.is_empty() || code.starts_with(char::is_whitespace))
+        };
+        let result = if let Some(path) = command(":load") {
+            load_module(&mut runner, path.trim(), format);
+            continue;
+        } else if let Some(path) = command(":reload") {
+            let paths = match path.trim() {
+                "" => runner.modules(),
+                path => vec![PathBuf::from(path)],
+            };
+            for path in paths {
+                runner.invalidate(&path);
+                load_module(&mut runner, &path, format);
+            }
+            continue;
+        } else if let Some(code) = command(":time") {
+            let name = runner.next_repl_name();
+            runner.run_timed(&name, code)
+        } else if let Some(name) = command(":doc") {
+            match runner.describe(name.trim()) {
+                Some(description) => println!("{}", description),
+                None => eprintln!("Nothing named '{}' is defined.", name.trim()),
+            }
+            continue;
+        } else if let Some(code) = command(":pp") {
+            runner.run_repl_pretty(code)
+        } else {
+            runner.run_repl_line(line.as_str())
+        };
+
+        if let Err(e) = result {
+            runner.finish_output_line();
+            eprintln!("{}", format.render_error(&e));
+        }
     }
-    rl.save_history(HISTORY_PATH).unwrap();
 
-    Ok(())
+    rl.save_history(HISTORY_PATH).map_err(|e| {
+        let kind = match &e {
+            ReadlineError::Io(e) => e.kind(),
+            _ => io::ErrorKind::Other,
+        };
+        let message = format!("Could not save history to '{}': {}", HISTORY_PATH, e);
+        LoxError::Io(io::Error::new(kind, message))
+    })
 }
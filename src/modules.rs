@@ -0,0 +1,119 @@
+//! Scripts a long-lived `Runner` has loaded from disk, so loading one again only runs it
+//! if the file changed.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fs,
+    hash::{Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::SystemTime,
+};
+
+use crate::ast::stmt::Stmt;
+
+/// A loaded module: the program compiled from it and what the file looked like then.
+struct CachedModule {
+    program: Rc<Vec<Stmt>>,
+    modified: Option<SystemTime>,
+    hash: u64,
+    /// Set by `ModuleCache::invalidate` to run the module on the next load even if the file
+    /// didn't change.
+    stale: bool,
+}
+
+/// What the cache knows about a module's file compared to the version it holds.
+pub enum Freshness {
+    /// The file is unchanged and the module doesn't need to run again.
+    Fresh,
+    /// The file is unchanged, but the module was invalidated; its program can run as it is.
+    Invalidated(Rc<Vec<Stmt>>),
+    /// The file is new or changed and has to be compiled; its contents are attached.
+    Changed(String),
+}
+
+/// Modules keyed by canonical path, so different spellings of one path share an entry.
+#[derive(Default)]
+pub struct ModuleCache {
+    modules: HashMap<PathBuf, CachedModule>,
+}
+
+impl ModuleCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compares the file at `path`, already canonical, with the version of the module held.
+    /// The file is only read if its modification time changed, and then only counts as
+    /// changed if its contents did, so touching a file doesn't rerun it.
+    pub fn freshness(&self, path: &Path) -> io::Result<Freshness> {
+        let module = match self.modules.get(path) {
+            Some(module) => module,
+            None => return fs::read_to_string(path).map(Freshness::Changed),
+        };
+
+        let modified = modified_time(path);
+        if !module.stale && modified.is_some() && module.modified == modified {
+            return Ok(Freshness::Fresh);
+        }
+
+        let code = fs::read_to_string(path)?;
+        if module.hash != hash_source(&code) {
+            Ok(Freshness::Changed(code))
+        } else if module.stale {
+            Ok(Freshness::Invalidated(Rc::clone(&module.program)))
+        } else {
+            Ok(Freshness::Fresh)
+        }
+    }
+
+    /// Records `program` as the current version of the module at `path`, compiled from
+    /// `code`.
+    pub fn store(&mut self, path: PathBuf, program: Rc<Vec<Stmt>>, code: &str) {
+        let module = CachedModule {
+            program,
+            modified: modified_time(&path),
+            hash: hash_source(code),
+            stale: false,
+        };
+        self.modules.insert(path, module);
+    }
+
+    /// Clears the invalidation of the module at `path` once it has run again.
+    pub fn mark_fresh(&mut self, path: &Path) {
+        if let Some(module) = self.modules.get_mut(path) {
+            module.stale = false;
+        }
+    }
+
+    /// Makes the next load of the module at `path` run it even if the file is unchanged.
+    /// Returns whether the module was loaded at all.
+    pub fn invalidate(&mut self, path: &Path) -> bool {
+        match self.modules.get_mut(path) {
+            Some(module) => {
+                module.stale = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The paths of every loaded module, in no particular order.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.modules.keys().cloned().collect()
+    }
+}
+
+fn hash_source(code: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    code.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The modification time of the file at `path`, if the platform reports one.
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
@@ -0,0 +1,157 @@
+//! Work done on a script before it runs, leaving it doing the same with less left to do.
+//!
+//! So far that is folding the constants a script opens with: top-level variables whose
+//! initializers use nothing but literals, the constants before them and pure natives get
+//! their values before the script starts, written into the declarations as literals.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    ast::{
+        expr::{Expr, ExprKind, Literal},
+        stmt::{Stmt, StmtKind},
+    },
+    interpreter::{callable::LoxCallable, interpreter::Interpreter},
+};
+
+/// Folds the initializers of the `var` and `const` declarations `stmts` opens with, up to the
+/// first other statement or initializer that can't be folded. Past that point code runs that
+/// could change the globals the initializers read.
+///
+/// An initializer that fails, such as one calling a native with arguments it rejects, is left
+/// alone to fail when the script runs. So is one whose value is an array, map or other object,
+/// since every run of the declaration must make a new one. A number that isn't finite has no
+/// literal to be written as, so its initializer is kept too, though the value is still used
+/// to fold the initializers after it.
+pub(crate) fn fold_global_constants(stmts: &mut [Stmt], interpreter: &mut Interpreter) {
+    // A hoisted function or class of the same name would replace a native before the script
+    // starts, so the natives the script declares over aren't called ahead of time.
+    let declared: HashSet<String> = stmts
+        .iter()
+        .flat_map(|stmt| match &stmt.kind {
+            StmtKind::Var(name, _)
+            | StmtKind::Const(name, _)
+            | StmtKind::Function(name, ..)
+            | StmtKind::Class(name, ..)
+            | StmtKind::Enum(name, _) => std::slice::from_ref(name),
+            StmtKind::Unpack(names, _) => names.as_slice(),
+            _ => &[],
+        })
+        .map(|name| name.lexeme.clone())
+        .collect();
+    let mut folder = ConstantFolder {
+        interpreter,
+        declared,
+        constants: HashMap::new(),
+    };
+
+    for stmt in stmts {
+        let (name, initializer) = match &mut stmt.kind {
+            StmtKind::Var(name, Some(initializer)) | StmtKind::Const(name, initializer) => {
+                (name, initializer)
+            }
+            StmtKind::Var(name, None) => {
+                folder.constants.insert(name.lexeme.clone(), Literal::Nil);
+                continue;
+            }
+            _ => break,
+        };
+        let Some(value) = folder.fold(initializer) else {
+            break;
+        };
+        log_event!("[line {}] folded '{}' to {}", name.line, name.lexeme, value);
+
+        folder.constants.insert(name.lexeme.clone(), value.clone());
+        if matches!(value, Literal::Number(n) if !n.is_finite()) {
+            continue;
+        }
+        *initializer = Expr {
+            kind: ExprKind::Literal(value),
+            span: initializer.span,
+        };
+    }
+}
+
+struct ConstantFolder<'a> {
+    interpreter: &'a mut Interpreter,
+    /// Every name the script declares at the top level.
+    declared: HashSet<String>,
+    /// The values of the variables folded so far.
+    constants: HashMap<String, Literal>,
+}
+
+impl ConstantFolder<'_> {
+    /// The value of `expr`, if it can be worked out before the script runs.
+    fn fold(&mut self, expr: &Expr) -> Option<Literal> {
+        let expr = self.substitute(expr)?;
+        let value = self.interpreter.evaluate_ahead(&expr)?;
+        is_immutable(&value).then_some(value)
+    }
+
+    /// `expr` with the constants it reads written in as literals, if it uses nothing but
+    /// literals, operators, constants and calls to pure natives.
+    fn substitute(&self, expr: &Expr) -> Option<Expr> {
+        let kind = match &expr.kind {
+            ExprKind::Literal(value) => {
+                if !is_immutable(value) {
+                    return None;
+                }
+                ExprKind::Literal(value.clone())
+            }
+            ExprKind::Variable(name, None) => {
+                ExprKind::Literal(self.constants.get(&name.lexeme)?.clone())
+            }
+            ExprKind::Grouping(inner) => ExprKind::Grouping(Box::new(self.substitute(inner)?)),
+            ExprKind::Unary(op, operand) => {
+                ExprKind::Unary(*op, Box::new(self.substitute(operand)?))
+            }
+            ExprKind::Binary(op, lhs, rhs) => ExprKind::Binary(
+                *op,
+                Box::new(self.substitute(lhs)?),
+                Box::new(self.substitute(rhs)?),
+            ),
+            ExprKind::Logical(op, lhs, rhs) => ExprKind::Logical(
+                *op,
+                Box::new(self.substitute(lhs)?),
+                Box::new(self.substitute(rhs)?),
+            ),
+            ExprKind::Call(callee, arguments) => {
+                let ExprKind::Variable(name, None) = &callee.kind else {
+                    return None;
+                };
+                if !self.is_pure_native(&name.lexeme) {
+                    return None;
+                }
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| self.substitute(argument))
+                    .collect::<Option<Vec<_>>>()?;
+                ExprKind::Call(callee.clone(), arguments)
+            }
+            _ => return None,
+        };
+
+        Some(Expr {
+            kind,
+            span: expr.span,
+        })
+    }
+
+    fn is_pure_native(&self, name: &str) -> bool {
+        if self.declared.contains(name) {
+            return false;
+        }
+        match self.interpreter.get_global(name) {
+            Some(Literal::Callable(LoxCallable::Other(native))) => native.is_pure(),
+            _ => false,
+        }
+    }
+}
+
+/// Whether `value` can be written into the program as a literal, shared by every run of it.
+fn is_immutable(value: &Literal) -> bool {
+    matches!(
+        value,
+        Literal::Number(_) | Literal::String(_) | Literal::Bool(_) | Literal::Nil
+    )
+}
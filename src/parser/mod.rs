@@ -1,14 +1,212 @@
-use crate::{ast::stmt::Stmt, lexer::Token};
+use crate::{
+    ast::{expr::Expr, stmt::Stmt},
+    diagnostics::DiagnosticSink,
+    lexer::{Token, TokenKind},
+    source_map::SourceMap,
+    Diagnostic, LoxError,
+};
 
 use self::parser::Parser;
 
+#[allow(clippy::module_inception)]
 mod parser;
 
 enum ParsingError {
     GeneralError(String),
+    /// The error was already reported where it happened, so only recovery is left to do.
+    Reported,
 }
 
-pub fn parse(tokens: impl Iterator<Item = Token>) -> Vec<Stmt> {
-    let mut parser = Parser::new(tokens);
-    parser.parse()
+/// Switches for the dialect of Lox being parsed. The defaults are the standard language.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ParserOptions {
+    /// Treat `print` as the name of a function, called as `print(a, b)`, instead of as the
+    /// keyword starting a print statement.
+    pub print_is_function: bool,
+    pub language: LanguageOptions,
+}
+
+/// Which parts of the language scripts may use, for embedders that want a smaller one, such
+/// as a configuration language without loops. Everything is allowed by default; a script
+/// that uses a disallowed construct fails to parse.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LanguageOptions {
+    /// `class` declarations, and `extend` declarations adding to classes.
+    pub classes: bool,
+    /// `enum` declarations.
+    pub enums: bool,
+    /// `fun` declarations.
+    pub functions: bool,
+    pub for_loops: bool,
+    pub while_loops: bool,
+    /// Calls of functions, methods and classes.
+    pub calls: bool,
+    /// Names that declarations may reuse from an enclosing scope without the `shadowing`
+    /// warning.
+    pub allow_shadowing: &'static [&'static str],
+}
+
+impl Default for LanguageOptions {
+    fn default() -> Self {
+        Self {
+            classes: true,
+            enums: true,
+            functions: true,
+            for_loops: true,
+            while_loops: true,
+            calls: true,
+            allow_shadowing: &[],
+        }
+    }
+}
+
+impl LanguageOptions {
+    /// What a statement starting with `keyword` declares, if it is one these options rule
+    /// out.
+    fn disallowed(self, keyword: &TokenKind) -> Option<&'static str> {
+        let (allowed, what) = match keyword {
+            TokenKind::Class | TokenKind::Extend => (self.classes, "classes"),
+            TokenKind::Enum => (self.enums, "enums"),
+            TokenKind::Fun => (self.functions, "functions"),
+            TokenKind::For => (self.for_loops, "for-loops"),
+            TokenKind::While => (self.while_loops, "while-loops"),
+            _ => return None,
+        };
+
+        (!allowed).then_some(what)
+    }
+
+    fn not_allowed(what: &str) -> String {
+        format!("{} are not allowed in this context.", what)
+    }
+}
+
+impl ParserOptions {
+    /// Turns the tokens from the lexer into the ones this dialect means.
+    fn adjust(self, tokens: impl Iterator<Item = Token>) -> impl Iterator<Item = Token> {
+        tokens.map(move |mut token| {
+            if self.print_is_function && matches!(token.value, TokenKind::Print) {
+                token.value = TokenKind::Identifier(token.lexeme.clone());
+            }
+            token
+        })
+    }
+}
+
+/// Parses a whole program, collecting every syntax error found along the way with its
+/// location in `sources`. If there were any, the program is rejected as a whole. Warnings go
+/// to `sink`.
+pub fn parse(
+    tokens: impl Iterator<Item = Token>,
+    sources: &SourceMap,
+    options: ParserOptions,
+    sink: &mut DiagnosticSink,
+) -> Result<Vec<Stmt>, LoxError> {
+    let mut parser = Parser::new(options.adjust(tokens), sources, options.language);
+    let stmts = parser.parse();
+    for warning in parser.take_warnings() {
+        sink.report(warning);
+    }
+
+    let errors = parser.take_errors();
+    if errors.is_empty() {
+        Ok(stmts)
+    } else {
+        Err(LoxError::Parse(errors))
+    }
+}
+
+/// Parses a program one top-level declaration at a time, handing each over as soon as it is
+/// complete. Parsing stops at the first declaration with a syntax error, which is yielded in
+/// place of it along with any others found in the same declaration.
+pub struct Declarations<'a> {
+    parser: Parser<'a, Box<dyn Iterator<Item = Token> + 'a>>,
+    failed: bool,
+}
+
+impl<'a> Declarations<'a> {
+    pub fn new(
+        tokens: impl Iterator<Item = Token> + 'a,
+        sources: &'a SourceMap,
+        options: ParserOptions,
+    ) -> Self {
+        let tokens: Box<dyn Iterator<Item = Token> + 'a> = Box::new(options.adjust(tokens));
+        Self {
+            parser: Parser::new(tokens, sources, options.language),
+            failed: false,
+        }
+    }
+
+    /// Hands over the warnings found in the declarations parsed so far.
+    pub fn take_warnings(&mut self) -> Vec<Diagnostic> {
+        self.parser.take_warnings()
+    }
+}
+
+impl Iterator for Declarations<'_> {
+    type Item = Result<Stmt, LoxError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        if let Some(stmt) = self.parser.next_declaration() {
+            return Some(Ok(stmt));
+        }
+
+        let errors = self.parser.take_errors();
+        if errors.is_empty() {
+            None
+        } else {
+            self.failed = true;
+            Some(Err(LoxError::Parse(errors)))
+        }
+    }
+}
+
+/// Parses a lone expression with nothing following it, collecting every syntax error with its
+/// location in `sources` the way `parse` does. Warnings go to `sink`.
+pub fn parse_standalone_expression(
+    tokens: impl Iterator<Item = Token>,
+    sources: &SourceMap,
+    options: ParserOptions,
+    sink: &mut DiagnosticSink,
+) -> Result<Expr, LoxError> {
+    let mut parser = Parser::new(options.adjust(tokens), sources, options.language);
+    let expr = parser.standalone_expression();
+    for warning in parser.take_warnings() {
+        sink.report(warning);
+    }
+
+    let errors = parser.take_errors();
+    match expr {
+        Some(expr) if errors.is_empty() => Ok(expr),
+        _ => Err(LoxError::Parse(errors)),
+    }
+}
+
+/// Parses a lone expression, such as one typed at the debugger prompt.
+pub fn parse_expression(
+    tokens: impl Iterator<Item = Token>,
+    sources: &SourceMap,
+    options: ParserOptions,
+    sink: &mut DiagnosticSink,
+) -> Result<Expr, String> {
+    let mut parser = Parser::new(options.adjust(tokens), sources, options.language);
+    let expr = parser.parse_expression();
+    if let Some(error) = parser.take_errors().into_iter().next() {
+        return Err(error.message);
+    }
+    let expr = expr.map_err(|e| match e {
+        ParsingError::GeneralError(e) => e,
+        ParsingError::Reported => unreachable!("reported errors are collected above"),
+    })?;
+    // Only an expression that parsed has its warnings reported, since callers try other
+    // ways of parsing the code when it doesn't.
+    for warning in parser.take_warnings() {
+        sink.report(warning);
+    }
+
+    Ok(expr)
 }
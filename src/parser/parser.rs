@@ -1,24 +1,128 @@
 use crate::{
     ast::{
         expr::{BinOp, Expr, ExprKind, Literal, LogOp, UnOp},
-        stmt::Stmt,
+        pattern::{ArmBody, MatchArm, Pattern, PatternKind},
+        stmt::{Stmt, StmtKind},
     },
-    lexer::{Token, TokenKind},
-    parser::ParsingError,
+    interpreter::methods,
+    lexer::{self, Span, Token, TokenKind},
+    parser::{LanguageOptions, ParsingError},
+    source_map::SourceMap,
+    Diagnostic, ParseDiagnostic,
 };
-use std::iter::Peekable;
+use std::{iter::Peekable, mem};
 
-pub struct Parser<I: Iterator<Item = Token>> {
+/// How many syntax errors one statement may report. Past that, the rest are most likely
+/// caused by the first ones.
+const MAX_ERRORS_PER_STATEMENT: usize = 3;
+
+pub struct Parser<'a, I: Iterator<Item = Token>> {
     tokens: Peekable<I>,
+    sources: &'a SourceMap,
+    /// Span of the last token consumed, where the node being parsed ends.
+    previous: Span,
+    /// The syntax errors found so far, in the order they were found.
+    errors: Vec<ParseDiagnostic>,
+    warnings: Vec<Diagnostic>,
+    language: LanguageOptions,
+    /// How many blocks the parser is inside, so recovery knows a `}` closes one of them.
+    block_depth: usize,
+    /// The syntax errors reported in the innermost statement being parsed.
+    statement_errors: usize,
 }
 
-impl<I: Iterator<Item = Token>> Parser<I> {
-    pub fn new(tokens: I) -> Self {
+impl<'a, I: Iterator<Item = Token>> Parser<'a, I> {
+    pub fn new(tokens: I, sources: &'a SourceMap, language: LanguageOptions) -> Self {
         Self {
             tokens: tokens.peekable(),
+            sources,
+            previous: Span::default(),
+            errors: vec![],
+            warnings: vec![],
+            language,
+            block_depth: 0,
+            statement_errors: 0,
+        }
+    }
+
+    /// Hands over the syntax errors found so far.
+    pub fn take_errors(&mut self) -> Vec<ParseDiagnostic> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// Hands over the warnings found so far.
+    pub fn take_warnings(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Flags `if (x = 0)`-style conditions. Wrapping the assignment in an extra pair of
+    /// parentheses marks it as intentional.
+    fn warn_assignment_condition(&mut self, condition: &Expr) {
+        if let Some(name) = assigned_name(condition) {
+            self.warn_assignment(name);
         }
     }
 
+    /// Flags `(x = 0) or y`. The parentheses are required for the assignment to parse at all,
+    /// so only a second pair silences the warning.
+    fn warn_assignment_operand(&mut self, operand: &Expr) {
+        if let ExprKind::Grouping(inner) = &operand.kind {
+            if let Some(name) = assigned_name(inner) {
+                self.warn_assignment(name);
+            }
+        }
+    }
+
+    fn warn_assignment(&mut self, name: &Token) {
+        let warning = Diagnostic::warning(
+            "assignment-condition",
+            self.sources.location(name.span()),
+            name.line,
+            name.column,
+            format!(
+                "Assignment to '{}' used as a condition. Did you mean '=='?",
+                name.lexeme
+            ),
+        )
+        .with_help("Wrap it in an extra pair of parentheses to silence this warning.");
+        self.warnings.push(warning);
+    }
+
+    /// Flags `0 < x < 10` and `a == b == c`, which compare the result of the first comparison
+    /// rather than chaining them. A pair of parentheses around the first one says it's meant.
+    fn warn_chained_comparison(&mut self, left: &Expr, operator: &Token, right: &Expr) {
+        let chained = |op: &BinOp| match operator.value {
+            TokenKind::EqEq | TokenKind::Ne => matches!(op, BinOp::EqEq | BinOp::Ne),
+            _ => matches!(op, BinOp::Gt | BinOp::Ge | BinOp::Lt | BinOp::Le),
+        };
+        let ExprKind::Binary(op, _, middle) = &left.kind else {
+            return;
+        };
+        if !chained(op) {
+            return;
+        }
+
+        let [first, middle, last] =
+            [left.span, middle.span, right.span].map(|span| self.sources.snippet(span));
+        let message = if [&first, &middle, &last].iter().any(|text| text.is_empty()) {
+            "Chained comparisons are not supported; join the comparisons with 'and'.".to_string()
+        } else {
+            format!(
+                "Chained comparisons are not supported; write `{} and {} {} {}`.",
+                first, middle, operator.lexeme, last
+            )
+        };
+        let warning = Diagnostic::warning(
+            "chained-comparison",
+            self.sources.location(operator.span()),
+            operator.line,
+            operator.column,
+            message,
+        )
+        .with_help("Wrap the first comparison in parentheses if it is meant to be compared.");
+        self.warnings.push(warning);
+    }
+
     pub fn parse(&mut self) -> Vec<Stmt> {
         let mut statements = vec![];
 
@@ -31,6 +135,50 @@ impl<I: Iterator<Item = Token>> Parser<I> {
         statements
     }
 
+    /// Parses the next top-level declaration, so a program can be run while it is still
+    /// being read. Returns `None` once the tokens run out, or after a syntax error, which
+    /// `take_errors` then hands over; recovery stops at the declaration it happened in.
+    pub fn next_declaration(&mut self) -> Option<Stmt> {
+        while !self.is_at_end() {
+            let stmt = self.declaration();
+            // A block keeps the statements around a broken one, but it must not run.
+            if !self.errors.is_empty() {
+                return None;
+            }
+            if stmt.is_some() {
+                return stmt;
+            }
+        }
+
+        None
+    }
+
+    /// Parses the tokens as a single expression with nothing following it.
+    pub fn parse_expression(&mut self) -> Result<Expr, ParsingError> {
+        let expr = self.expression()?;
+
+        if self.is_at_end() {
+            Ok(expr)
+        } else {
+            Err(ParsingError::GeneralError(
+                "Expect end of expression.".into(),
+            ))
+        }
+    }
+
+    /// Parses the tokens as a single expression with nothing following it, reporting what
+    /// goes wrong instead of handing it back.
+    pub fn standalone_expression(&mut self) -> Option<Expr> {
+        match self.parse_expression() {
+            Ok(expr) => Some(expr),
+            Err(ParsingError::GeneralError(message)) => {
+                self.report(message);
+                None
+            }
+            Err(ParsingError::Reported) => None,
+        }
+    }
+
     fn peek_kind(&mut self) -> Option<&TokenKind> {
         if let Some(t) = self.tokens.peek() {
             Some(&t.value)
@@ -45,75 +193,224 @@ impl<I: Iterator<Item = Token>> Parser<I> {
 
             match token {
                 Some(t) => match t.value {
-                    TokenKind::Whitespace => continue,
-                    _ => return Some(t),
+                    TokenKind::Whitespace(_) | TokenKind::LineComment(_) => continue,
+                    _ => {
+                        self.previous = t.span();
+                        return Some(t);
+                    }
                 },
                 _ => return None,
             };
         }
     }
 
+    /// The span of the next token, where the node about to be parsed starts.
+    fn peek_span(&mut self) -> Span {
+        let previous = self.previous;
+        self.tokens.peek().map_or(previous, |t| t.span())
+    }
+
     fn is_at_end(&mut self) -> bool {
+        matches!(self.peek_kind(), Some(TokenKind::Eof) | None)
+    }
+
+    /// Rejects a comma before the first element of a list of `what`.
+    fn leading_comma(&mut self, what: &str) -> Result<(), ParsingError> {
         match self.peek_kind() {
-            Some(TokenKind::Eof) | None => true,
-            _ => false,
+            Some(TokenKind::Comma) => Err(ParsingError::GeneralError(format!(
+                "Expect {} before ','.",
+                what
+            ))),
+            _ => Ok(()),
         }
     }
 
-    fn sync(&mut self) {
-        loop {
-            let token = match self.advance() {
-                Some(t) => t,
-                None => return,
-            };
+    /// Consumes the comma after an element of a list of `what` closed by `close`, and says
+    /// whether another element follows. A comma right before `close` is allowed, so lists
+    /// spread over several lines can end with one; two commas in a row are not.
+    fn list_comma(&mut self, close: TokenKind, what: &str) -> Result<bool, ParsingError> {
+        if !matches!(self.peek_kind(), Some(TokenKind::Comma)) {
+            return Ok(false);
+        }
+        self.advance().unwrap();
 
-            if let TokenKind::Semicolon = token.value {
-                return;
-            }
+        match self.peek_kind() {
+            Some(TokenKind::Comma) => Err(ParsingError::GeneralError(format!(
+                "Expect {} between commas.",
+                what
+            ))),
+            Some(kind) if mem::discriminant(kind) == mem::discriminant(&close) => Ok(false),
+            _ => Ok(true),
+        }
+    }
+
+    /// Skips what is left of a statement after a syntax error: up to and including its `;`,
+    /// or up to the next statement keyword or the `}` of the block it is in. Brackets opened
+    /// while skipping are skipped as a whole, so a `;` or `}` inside them doesn't count.
+    fn sync(&mut self) {
+        let mut depth = 0usize;
+        let mut skipped = false;
+        let in_block = self.block_depth > 0;
 
+        loop {
             match self.peek_kind() {
-                Some(t) => match t {
+                None | Some(TokenKind::Eof) => return,
+                Some(TokenKind::RightBrace) if depth == 0 && in_block => return,
+                Some(TokenKind::Semicolon) if depth == 0 => {
+                    self.advance().unwrap();
+                    return;
+                }
+                Some(
                     TokenKind::Class
+                    | TokenKind::Extend
                     | TokenKind::Fun
                     | TokenKind::Var
+                    | TokenKind::Const
+                    | TokenKind::Enum
                     | TokenKind::For
                     | TokenKind::If
                     | TokenKind::While
                     | TokenKind::Print
-                    | TokenKind::Return => return,
-                    _ => (),
-                },
-                _ => return,
+                    | TokenKind::Return
+                    | TokenKind::Defer,
+                ) if depth == 0 && skipped => return,
+                Some(TokenKind::LeftBrace | TokenKind::LeftParen | TokenKind::LeftBracket) => {
+                    depth += 1
+                }
+                Some(TokenKind::RightBrace | TokenKind::RightParen | TokenKind::RightBracket) => {
+                    depth = depth.saturating_sub(1)
+                }
+                _ => (),
             }
+
+            self.advance();
+            skipped = true;
         }
     }
 
+    /// Records a syntax error at the next token.
+    fn report(&mut self, message: String) {
+        let span = self.peek_span();
+        self.report_at(span, message);
+    }
+
+    /// Records a syntax error at `span`, unless the statement it is in has already reported
+    /// as many as it may.
+    fn report_at(&mut self, span: Span, message: String) {
+        self.statement_errors += 1;
+        if self.statement_errors > MAX_ERRORS_PER_STATEMENT {
+            return;
+        }
+
+        let diagnostic = ParseDiagnostic::error(
+            "syntax",
+            self.sources.location(span),
+            span.line,
+            span.column,
+            message,
+        );
+        self.errors.push(diagnostic);
+    }
+
+    /// The doc comment of the declaration starting at `start`. Streamed sources aren't kept,
+    /// so declarations in them have none.
+    fn doc_comment(&self, start: Span) -> Option<String> {
+        lexer::doc_comment(self.sources.source(start.file), start.start)
+    }
+
     fn declaration(&mut self) -> Option<Stmt> {
-        let result = match self.peek_kind() {
+        let start = self.peek_span();
+        let enclosing_errors = mem::replace(&mut self.statement_errors, 0);
+        let result = self.declaration_kind();
+
+        let stmt = match result {
+            Ok(kind) => Some(Stmt {
+                kind,
+                span: start.to(self.previous),
+            }),
+            Err(e) => {
+                if let ParsingError::GeneralError(message) = e {
+                    self.report(message);
+                }
+                self.sync();
+                None
+            }
+        };
+
+        self.statement_errors = enclosing_errors;
+        stmt
+    }
+
+    /// Reports the statement about to be parsed if the language options rule it out. It is
+    /// still parsed as usual, so the rest of the program is checked too.
+    fn check_allowed(&mut self) {
+        let language = self.language;
+        let Some(what) = self
+            .peek_kind()
+            .and_then(|keyword| language.disallowed(keyword))
+        else {
+            return;
+        };
+
+        let span = self.peek_span();
+        self.not_allowed(span, what);
+    }
+
+    /// Records that the construct at `span` is one the language options rule out.
+    fn not_allowed(&mut self, span: Span, what: &str) {
+        let diagnostic = ParseDiagnostic::error(
+            "not-allowed",
+            self.sources.location(span),
+            span.line,
+            span.column,
+            LanguageOptions::not_allowed(what),
+        );
+        self.errors.push(diagnostic);
+    }
+
+    fn declaration_kind(&mut self) -> Result<StmtKind, ParsingError> {
+        match self.peek_kind() {
             Some(TokenKind::Var) => {
                 self.advance().unwrap();
                 self.var_declaration()
             }
+            Some(TokenKind::Const) => {
+                self.advance().unwrap();
+                self.const_declaration()
+            }
             Some(TokenKind::Fun) => {
+                self.check_allowed();
                 self.advance().unwrap();
                 self.function("function")
             }
-            _ => self.statement(),
-        };
-
-        match result {
-            Ok(v) => Some(v),
-            Err(ParsingError::GeneralError(e)) => {
-                eprintln!("Parser Error: {}", e);
-                self.sync();
-                None
+            Some(TokenKind::Enum) => {
+                self.check_allowed();
+                self.advance().unwrap();
+                self.enum_declaration()
+            }
+            Some(TokenKind::Class) => {
+                self.check_allowed();
+                self.advance().unwrap();
+                self.class_declaration()
+            }
+            Some(TokenKind::Extend) => {
+                self.check_allowed();
+                self.advance().unwrap();
+                self.extend_declaration()
             }
+            _ => self.statement().map(|stmt| stmt.kind),
         }
     }
 
-    fn function(&mut self, kind: &str) -> Result<Stmt, ParsingError> {
+    fn function(&mut self, kind: &str) -> Result<StmtKind, ParsingError> {
+        // A function's declaration starts at `fun`, a method's at its name.
+        let start = match kind {
+            "method" => self.peek_span(),
+            _ => self.previous,
+        };
+        let doc = self.doc_comment(start);
         let name = match self.peek_kind() {
-            Some(TokenKind::Identifier(id)) => self.advance().unwrap(),
+            Some(TokenKind::Identifier(_)) => self.advance().unwrap(),
             _ => return Err(ParsingError::GeneralError(format!("Expect {} name.", kind))),
         };
 
@@ -131,6 +428,7 @@ impl<I: Iterator<Item = Token>> Parser<I> {
 
         let mut params = vec![];
 
+        self.leading_comma("parameter name")?;
         match self.peek_kind() {
             Some(TokenKind::RightParen) => (),
             _ => loop {
@@ -142,13 +440,17 @@ impl<I: Iterator<Item = Token>> Parser<I> {
 
                 match self.peek_kind() {
                     Some(TokenKind::Identifier(_)) => params.push(self.advance().unwrap()),
+                    Some(TokenKind::Const) => {
+                        return Err(ParsingError::GeneralError(
+                            "Function parameters can't be declared const.".into(),
+                        ))
+                    }
                     _ => return Err(ParsingError::GeneralError("Expect parameter name.".into())),
                 }
 
-                match self.peek_kind() {
-                    Some(TokenKind::Comma) => self.advance().unwrap(),
-                    _ => break,
-                };
+                if !self.list_comma(TokenKind::RightParen, "parameter name")? {
+                    break;
+                }
             },
         };
 
@@ -165,7 +467,7 @@ impl<I: Iterator<Item = Token>> Parser<I> {
             Some(TokenKind::LeftBrace) => {
                 self.advance().unwrap();
                 let body = self.block()?;
-                Ok(Stmt::Function(name, params, body))
+                Ok(StmtKind::Function(name, params, body, doc))
             }
             _ => Err(ParsingError::GeneralError(format!(
                 "Expect '{{' before {} body",
@@ -174,10 +476,13 @@ impl<I: Iterator<Item = Token>> Parser<I> {
         }
     }
 
-    fn var_declaration(&mut self) -> Result<Stmt, ParsingError> {
+    fn var_declaration(&mut self) -> Result<StmtKind, ParsingError> {
         match self.peek_kind() {
             Some(TokenKind::Identifier(_)) => {
                 let name = self.advance().unwrap();
+                if let Some(TokenKind::Comma) = self.peek_kind() {
+                    return self.unpack_declaration(name);
+                }
 
                 let initializer = if let Some(TokenKind::Eq) = self.peek_kind() {
                     self.advance().unwrap();
@@ -189,7 +494,7 @@ impl<I: Iterator<Item = Token>> Parser<I> {
                 match self.peek_kind() {
                     Some(TokenKind::Semicolon) => {
                         self.advance().unwrap();
-                        Ok(Stmt::Var(name, initializer))
+                        Ok(StmtKind::Var(name, initializer))
                     }
                     _ => Err(ParsingError::GeneralError(
                         "Expect ';' after variable declaration.".into(),
@@ -200,25 +505,242 @@ impl<I: Iterator<Item = Token>> Parser<I> {
         }
     }
 
-    fn statement(&mut self) -> Result<Stmt, ParsingError> {
+    /// The rest of `var a, b = value;` after its first name.
+    fn unpack_declaration(&mut self, first: Token) -> Result<StmtKind, ParsingError> {
+        let mut names = vec![first];
+        while self.list_comma(TokenKind::Eq, "variable name")? {
+            match self.peek_kind() {
+                Some(TokenKind::Identifier(_)) => names.push(self.advance().unwrap()),
+                _ => return Err(ParsingError::GeneralError("Expect variable name.".into())),
+            }
+        }
+
+        match self.peek_kind() {
+            Some(TokenKind::Eq) => self.advance().unwrap(),
+            _ => {
+                return Err(ParsingError::GeneralError(
+                    "Expect '=' after the names to unpack.".into(),
+                ))
+            }
+        };
+        let initializer = self.expression()?;
+
+        match self.peek_kind() {
+            Some(TokenKind::Semicolon) => {
+                self.advance().unwrap();
+                Ok(StmtKind::Unpack(names, initializer))
+            }
+            _ => Err(ParsingError::GeneralError(
+                "Expect ';' after variable declaration.".into(),
+            )),
+        }
+    }
+
+    fn const_declaration(&mut self) -> Result<StmtKind, ParsingError> {
+        let name = match self.peek_kind() {
+            Some(TokenKind::Identifier(_)) => self.advance().unwrap(),
+            _ => return Err(ParsingError::GeneralError("Expect constant name.".into())),
+        };
+
+        match self.peek_kind() {
+            Some(TokenKind::Eq) => self.advance().unwrap(),
+            _ => {
+                return Err(ParsingError::GeneralError(format!(
+                    "Constant '{}' must be initialized.",
+                    name.lexeme
+                )))
+            }
+        };
+
+        let initializer = self.expression()?;
+
+        match self.peek_kind() {
+            Some(TokenKind::Semicolon) => {
+                self.advance().unwrap();
+                Ok(StmtKind::Const(name, initializer))
+            }
+            _ => Err(ParsingError::GeneralError(
+                "Expect ';' after constant declaration.".into(),
+            )),
+        }
+    }
+
+    fn class_declaration(&mut self) -> Result<StmtKind, ParsingError> {
+        let doc = self.doc_comment(self.previous);
+        let name = match self.peek_kind() {
+            Some(TokenKind::Identifier(_)) => self.advance().unwrap(),
+            _ => return Err(ParsingError::GeneralError("Expect class name.".into())),
+        };
+
+        let superclass = match self.peek_kind() {
+            Some(TokenKind::Lt) => {
+                self.advance().unwrap();
+                match self.peek_kind() {
+                    Some(TokenKind::Identifier(_)) => {
+                        let superclass = self.advance().unwrap();
+                        Some(Expr {
+                            span: superclass.span(),
+                            kind: ExprKind::Variable(superclass, None),
+                        })
+                    }
+                    _ => return Err(ParsingError::GeneralError("Expect superclass name.".into())),
+                }
+            }
+            _ => None,
+        };
+
+        let members = self.class_body("class", true)?;
+        Ok(StmtKind::Class(name, superclass, members, doc))
+    }
+
+    /// `extend ClassName { methods }`, adding methods to a class declared earlier.
+    fn extend_declaration(&mut self) -> Result<StmtKind, ParsingError> {
+        let class = match self.peek_kind() {
+            Some(TokenKind::Identifier(_)) => {
+                let name = self.advance().unwrap();
+                Expr {
+                    span: name.span(),
+                    kind: ExprKind::Variable(name, None),
+                }
+            }
+            _ => {
+                return Err(ParsingError::GeneralError(
+                    "Expect class name after 'extend'.".into(),
+                ))
+            }
+        };
+
+        let methods = self.class_body("extension", false)?;
+        Ok(StmtKind::Extend(class, methods))
+    }
+
+    /// The braces of a class or extension body and the members between them: methods, and
+    /// `var` declarations of fields if `fields` allows them.
+    fn class_body(&mut self, what: &str, fields: bool) -> Result<Vec<Stmt>, ParsingError> {
+        match self.peek_kind() {
+            Some(TokenKind::LeftBrace) => self.advance().unwrap(),
+            _ => {
+                return Err(ParsingError::GeneralError(format!(
+                    "Expect '{{' before {} body.",
+                    what
+                )))
+            }
+        };
+
+        let mut members = vec![];
+        loop {
+            let start = self.peek_span();
+            let kind = match self.peek_kind() {
+                Some(TokenKind::Identifier(_)) => self.function("method")?,
+                Some(TokenKind::Var) => {
+                    self.advance().unwrap();
+                    let declaration = self.var_declaration()?;
+                    if !fields {
+                        // Parsed anyway, so the rest of the body still lines up.
+                        self.report_at(
+                            start,
+                            format!("Only methods can be declared in the {} body.", what),
+                        );
+                        continue;
+                    }
+                    if let StmtKind::Unpack(..) = declaration {
+                        self.report_at(start, "Fields are declared one at a time.".into());
+                        continue;
+                    }
+                    declaration
+                }
+                _ => break,
+            };
+            members.push(Stmt {
+                kind,
+                span: start.to(self.previous),
+            });
+        }
+
+        match self.peek_kind() {
+            Some(TokenKind::RightBrace) => {
+                self.advance().unwrap();
+                Ok(members)
+            }
+            _ => Err(ParsingError::GeneralError(format!(
+                "Expect '}}' after {} body.",
+                what
+            ))),
+        }
+    }
+
+    fn enum_declaration(&mut self) -> Result<StmtKind, ParsingError> {
+        let name = match self.peek_kind() {
+            Some(TokenKind::Identifier(_)) => self.advance().unwrap(),
+            _ => return Err(ParsingError::GeneralError("Expect enum name.".into())),
+        };
+
+        match self.peek_kind() {
+            Some(TokenKind::LeftBrace) => self.advance().unwrap(),
+            _ => {
+                return Err(ParsingError::GeneralError(
+                    "Expect '{' after enum name.".into(),
+                ))
+            }
+        };
+
+        let mut variants: Vec<Token> = vec![];
+        self.leading_comma("enum variant")?;
+        while let Some(TokenKind::Identifier(_)) = self.peek_kind() {
+            let variant = self.advance().unwrap();
+
+            if methods::is_enum_method(&variant.lexeme) {
+                return Err(ParsingError::GeneralError(format!(
+                    "'{}' can't be a variant name; it is a method of every enum.",
+                    variant.lexeme
+                )));
+            }
+            if variants.iter().any(|v| v.lexeme == variant.lexeme) {
+                return Err(ParsingError::GeneralError(format!(
+                    "Enum '{}' already has a variant named '{}'.",
+                    name.lexeme, variant.lexeme
+                )));
+            }
+            variants.push(variant);
+
+            if !self.list_comma(TokenKind::RightBrace, "enum variant")? {
+                break;
+            }
+        }
+
         match self.peek_kind() {
+            Some(TokenKind::RightBrace) => {
+                self.advance().unwrap();
+                Ok(StmtKind::Enum(name, variants))
+            }
+            _ => Err(ParsingError::GeneralError(
+                "Expect '}' after enum variants.".into(),
+            )),
+        }
+    }
+
+    fn statement(&mut self) -> Result<Stmt, ParsingError> {
+        let start = self.peek_span();
+        let kind = match self.peek_kind() {
             Some(TokenKind::Print) => {
                 self.advance().unwrap();
                 self.print_statement()
             }
             Some(TokenKind::LeftBrace) => {
                 self.advance().unwrap();
-                Ok(Stmt::Block(self.block()?))
+                Ok(StmtKind::Block(self.block()?))
             }
             Some(TokenKind::If) => {
                 self.advance().unwrap();
                 self.if_statement()
             }
             Some(TokenKind::While) => {
+                self.check_allowed();
                 self.advance().unwrap();
                 self.while_statement()
             }
             Some(TokenKind::For) => {
+                self.check_allowed();
                 self.advance().unwrap();
                 self.for_statement()
             }
@@ -226,46 +748,114 @@ impl<I: Iterator<Item = Token>> Parser<I> {
                 let token = self.advance().unwrap();
                 self.return_statement(token)
             }
+            Some(TokenKind::Defer) => {
+                self.advance().unwrap();
+                self.defer_statement()
+            }
+            Some(TokenKind::Else) => Err(ParsingError::GeneralError(
+                "'else' without a matching 'if'.".into(),
+            )),
+            // A lone `;` is an empty statement, the same as `{}`.
+            Some(TokenKind::Semicolon) => {
+                self.advance().unwrap();
+                Ok(StmtKind::Block(vec![]))
+            }
             _ => self.expression_statement(),
+        }?;
+
+        Ok(Stmt {
+            kind,
+            span: start.to(self.previous),
+        })
+    }
+
+    /// Parses an `if` and every `else if` chained to it into one statement. An `else` goes
+    /// with the nearest `if`, since the branch of an inner `if` claims it first.
+    fn if_statement(&mut self) -> Result<StmtKind, ParsingError> {
+        let mut arms = vec![self.if_arm("if")?];
+        let mut else_branch = None;
+
+        while let Some(TokenKind::Else) = self.peek_kind() {
+            self.advance().unwrap();
+            if let Some(TokenKind::If) = self.peek_kind() {
+                self.advance().unwrap();
+                arms.push(self.if_arm("else if")?);
+            } else {
+                else_branch = Some(Box::new(self.branch("'else'")?));
+                break;
+            }
         }
+
+        Ok(StmtKind::If(arms, else_branch))
     }
 
-    fn if_statement(&mut self) -> Result<Stmt, ParsingError> {
+    /// Parses the condition and branch following `keyword`, `if` or `else if`.
+    fn if_arm(&mut self, keyword: &str) -> Result<(Expr, Stmt), ParsingError> {
         match self.peek_kind() {
             Some(TokenKind::LeftParen) => self.advance().unwrap(),
-            _ => return Err(ParsingError::GeneralError("Expect '(' after 'if'".into())),
+            _ => {
+                return Err(ParsingError::GeneralError(format!(
+                    "Expect '(' after '{}'",
+                    keyword
+                )))
+            }
         };
 
         let condition = self.expression()?;
+        self.warn_assignment_condition(&condition);
 
         match self.peek_kind() {
             Some(TokenKind::RightParen) => self.advance().unwrap(),
-            _ => return Err(ParsingError::GeneralError("Expect ')' after 'if'".into())),
-        };
-
-        let then_branch = self.statement()?;
-        let else_branch = match self.peek_kind() {
-            Some(TokenKind::Else) => {
-                self.advance().unwrap();
-                Some(self.statement()?)
+            _ => {
+                return Err(ParsingError::GeneralError(format!(
+                    "Expect ')' after '{}'",
+                    keyword
+                )))
             }
-            _ => None,
         };
 
-        Ok(Stmt::If(
-            condition,
-            Box::new(then_branch),
-            Box::new(else_branch),
-        ))
+        let branch = self.branch(&format!("the '{}' condition", keyword))?;
+        Ok((condition, branch))
+    }
+
+    /// Parses the statement of a branch, reporting a missing one as expected after `after`
+    /// rather than as whatever token is there instead.
+    fn branch(&mut self, after: &str) -> Result<Stmt, ParsingError> {
+        match self.peek_kind() {
+            Some(
+                TokenKind::RightParen
+                | TokenKind::RightBrace
+                | TokenKind::RightBracket
+                | TokenKind::Else
+                | TokenKind::Eof,
+            )
+            | None => Err(ParsingError::GeneralError(format!(
+                "Expect a statement after {}.",
+                after
+            ))),
+            _ => self.statement(),
+        }
+    }
+
+    /// Parses the statement after `defer`. A block may be followed by a `;`, as in
+    /// `defer { file.close(); };`.
+    fn defer_statement(&mut self) -> Result<StmtKind, ParsingError> {
+        let body = self.branch("'defer'")?;
+        if let (StmtKind::Block(_), Some(TokenKind::Semicolon)) = (&body.kind, self.peek_kind()) {
+            self.advance().unwrap();
+        }
+
+        Ok(StmtKind::Defer(Box::new(body)))
     }
 
-    fn while_statement(&mut self) -> Result<Stmt, ParsingError> {
+    fn while_statement(&mut self) -> Result<StmtKind, ParsingError> {
         match self.peek_kind() {
             Some(TokenKind::LeftParen) => self.advance().unwrap(),
             _ => return Err(ParsingError::GeneralError("Expect '(' after 'if'".into())),
         };
 
         let condition = self.expression()?;
+        self.warn_assignment_condition(&condition);
 
         match self.peek_kind() {
             Some(TokenKind::RightParen) => self.advance().unwrap(),
@@ -274,15 +864,16 @@ impl<I: Iterator<Item = Token>> Parser<I> {
 
         let body = self.statement()?;
 
-        Ok(Stmt::While(condition, Box::new(body)))
+        Ok(StmtKind::While(condition, Box::new(body)))
     }
 
-    fn for_statement(&mut self) -> Result<Stmt, ParsingError> {
+    fn for_statement(&mut self) -> Result<StmtKind, ParsingError> {
         match self.peek_kind() {
             Some(TokenKind::LeftParen) => self.advance().unwrap(),
             _ => return Err(ParsingError::GeneralError("Expect '(' after 'if'".into())),
         };
 
+        let initializer_start = self.peek_span();
         let initializer = match self.peek_kind() {
             Some(TokenKind::Semicolon) => {
                 self.advance().unwrap();
@@ -292,12 +883,34 @@ impl<I: Iterator<Item = Token>> Parser<I> {
                 self.advance().unwrap();
                 Some(self.var_declaration()?)
             }
-            _ => Some(self.expression_statement()?),
-        };
+            Some(TokenKind::Const) => {
+                self.advance().unwrap();
+                Some(self.const_declaration()?)
+            }
+            _ => {
+                let expr = self.expression()?;
+                if let ExprKind::Variable(name, _) = &expr.kind {
+                    if matches!(self.peek_kind(), Some(TokenKind::Identifier(word)) if word == "in")
+                    {
+                        self.advance().unwrap();
+                        return self.for_in_loop(name.clone());
+                    }
+                }
+                Some(self.finish_expression_statement(expr)?)
+            }
+        }
+        .map(|kind| Stmt {
+            kind,
+            span: initializer_start.to(self.previous),
+        });
 
-        let mut condition = match self.peek_kind() {
+        let condition = match self.peek_kind() {
             Some(TokenKind::Semicolon) => None,
-            _ => Some(self.expression()?),
+            _ => {
+                let condition = self.expression()?;
+                self.warn_assignment_condition(&condition);
+                Some(condition)
+            }
         };
 
         match self.peek_kind() {
@@ -327,49 +940,63 @@ impl<I: Iterator<Item = Token>> Parser<I> {
             }
         }
 
-        let mut body = self.statement()?;
+        let body = self.statement()?;
 
-        if let Some(increment) = increment {
-            body = Stmt::Block(vec![body, Stmt::Expression(increment)]);
-        }
+        Ok(StmtKind::For(
+            initializer.map(Box::new),
+            condition,
+            increment,
+            Box::new(body),
+        ))
+    }
 
-        if condition.is_none() {
-            condition.replace(Expr {
-                kind: ExprKind::Literal(Literal::Bool(true)),
-            });
+    /// The rest of `for (name in value) body` after its `in`.
+    fn for_in_loop(&mut self, name: Token) -> Result<StmtKind, ParsingError> {
+        let iterable = self.expression()?;
+
+        match self.peek_kind() {
+            Some(TokenKind::RightParen) => {
+                self.advance().unwrap();
+            }
+            _ => {
+                return Err(ParsingError::GeneralError(
+                    "Expect ')' after the value to loop over.".into(),
+                ))
+            }
         }
 
-        body = Stmt::While(condition.unwrap(), Box::new(body));
-
-        if let Some(initializer) = initializer {
-            body = Stmt::Block(vec![initializer, body]);
-        }
+        let body = self.statement()?;
 
-        Ok(body)
+        Ok(StmtKind::ForIn(name, iterable, Box::new(body)))
     }
 
-    fn print_statement(&mut self) -> Result<Stmt, ParsingError> {
+    fn print_statement(&mut self) -> Result<StmtKind, ParsingError> {
+        if let Some(TokenKind::Semicolon) = self.peek_kind() {
+            return Err(ParsingError::GeneralError(
+                "'print' requires an expression.".into(),
+            ));
+        }
         let value = self.expression()?;
 
         match self.peek_kind() {
             Some(TokenKind::Semicolon) => {
                 self.advance().unwrap();
-                Ok(Stmt::Print(value))
+                Ok(StmtKind::Print(value))
             }
             _ => Err(ParsingError::GeneralError("Expect ';' after value".into())),
         }
     }
 
-    fn return_statement(&mut self, keyword: Token) -> Result<Stmt, ParsingError> {
+    fn return_statement(&mut self, keyword: Token) -> Result<StmtKind, ParsingError> {
         let value = match self.peek_kind() {
             Some(TokenKind::Semicolon) => None,
-            _ => Some(self.expression()?),
+            _ => Some(self.return_values()?),
         };
 
         match self.peek_kind() {
             Some(TokenKind::Semicolon) => {
                 self.advance().unwrap();
-                Ok(Stmt::Return(keyword, value))
+                Ok(StmtKind::Return(keyword, value))
             }
             _ => Err(ParsingError::GeneralError(
                 "Expect ';' after return value".into(),
@@ -377,14 +1004,39 @@ impl<I: Iterator<Item = Token>> Parser<I> {
         }
     }
 
-    fn expression_statement(&mut self) -> Result<Stmt, ParsingError> {
+    /// The value of a return statement: one expression, or several packed into a tuple.
+    fn return_values(&mut self) -> Result<Expr, ParsingError> {
+        let first = self.expression()?;
+        if !matches!(self.peek_kind(), Some(TokenKind::Comma)) {
+            return Ok(first);
+        }
+
+        let mut values = vec![first];
+        while self.list_comma(TokenKind::Semicolon, "return value")? {
+            values.push(self.expression()?);
+        }
+        let span = values[0].span.to(values[values.len() - 1].span);
+
+        Ok(Expr {
+            kind: ExprKind::Tuple(values),
+            span,
+        })
+    }
+
+    fn expression_statement(&mut self) -> Result<StmtKind, ParsingError> {
         let expr = self.expression()?;
+        self.finish_expression_statement(expr)
+    }
 
+    /// The rest of an expression statement after its expression.
+    fn finish_expression_statement(&mut self, expr: Expr) -> Result<StmtKind, ParsingError> {
         match self.peek_kind() {
             Some(TokenKind::Semicolon) => {
                 self.advance().unwrap();
-                Ok(Stmt::Expression(expr))
+                Ok(StmtKind::Expression(expr))
             }
+            // A `match` standing alone ends in a brace, like a block, and needs no ';' after it.
+            _ if matches!(expr.kind, ExprKind::Match(..)) => Ok(StmtKind::Expression(expr)),
             _ => Err(ParsingError::GeneralError("Expect ';' after value".into())),
         }
     }
@@ -392,6 +1044,7 @@ impl<I: Iterator<Item = Token>> Parser<I> {
     fn block(&mut self) -> Result<Vec<Stmt>, ParsingError> {
         let mut stmts = vec![];
 
+        self.block_depth += 1;
         while !self.is_at_end() {
             match self.peek_kind() {
                 Some(TokenKind::RightBrace) => break,
@@ -402,6 +1055,7 @@ impl<I: Iterator<Item = Token>> Parser<I> {
                 }
             }
         }
+        self.block_depth -= 1;
 
         if let Some(TokenKind::RightBrace) = self.peek_kind() {
             self.advance().unwrap();
@@ -420,15 +1074,24 @@ impl<I: Iterator<Item = Token>> Parser<I> {
 
         match self.peek_kind() {
             Some(TokenKind::Eq) => {
-                self.advance().unwrap();
+                let equals = self.advance().unwrap();
                 let value = self.assignment()?;
+                let span = expr.span.to(value.span);
                 match expr.kind {
-                    ExprKind::Variable(name) => Ok(Expr {
-                        kind: ExprKind::Assign(name, Box::new(value)),
+                    ExprKind::Variable(name, _) => Ok(Expr {
+                        kind: ExprKind::Assign(name, Box::new(value), None),
+                        span,
                     }),
-                    _ => Err(ParsingError::GeneralError(
-                        "Invalid assignment target".into(),
-                    )),
+                    ExprKind::Get(object, name, false) => Ok(Expr {
+                        kind: ExprKind::Set(object, name, Box::new(value)),
+                        span,
+                    }),
+                    // Reported at the `=` rather than wherever parsing got to, and without
+                    // giving up on the statement, since what follows parsed fine.
+                    _ => {
+                        self.report_at(equals.span(), "Invalid assignment target.".into());
+                        Ok(value)
+                    }
                 }
             }
             _ => Ok(expr),
@@ -438,18 +1101,19 @@ impl<I: Iterator<Item = Token>> Parser<I> {
     fn or(&mut self) -> Result<Expr, ParsingError> {
         let mut expr = self.and()?;
 
-        loop {
-            match self.peek_kind() {
-                Some(TokenKind::Or) => {
-                    let operator_token = self.advance().unwrap();
-                    let operator = LogOp::try_from(operator_token.value).unwrap();
-                    let right = self.and()?;
-                    expr = Expr {
-                        kind: ExprKind::Logical(operator, Box::new(expr), Box::new(right)),
-                    };
-                }
-                _ => break,
+        while let Some(TokenKind::Or | TokenKind::QuestionQuestion) = self.peek_kind() {
+            let operator_token = self.advance().unwrap();
+            let operator = LogOp::try_from(operator_token.value).map_err(unexpected_operator)?;
+            let right = self.and()?;
+            if let LogOp::Or = operator {
+                self.warn_assignment_operand(&expr);
+                self.warn_assignment_operand(&right);
             }
+            let span = expr.span.to(right.span);
+            expr = Expr {
+                kind: ExprKind::Logical(operator, Box::new(expr), Box::new(right)),
+                span,
+            };
         }
 
         Ok(expr)
@@ -458,18 +1122,17 @@ impl<I: Iterator<Item = Token>> Parser<I> {
     fn and(&mut self) -> Result<Expr, ParsingError> {
         let mut expr = self.equality()?;
 
-        loop {
-            match self.peek_kind() {
-                Some(TokenKind::And) => {
-                    let operator_token = self.advance().unwrap();
-                    let operator = LogOp::try_from(operator_token.value).unwrap();
-                    let right = self.equality()?;
-                    expr = Expr {
-                        kind: ExprKind::Logical(operator, Box::new(expr), Box::new(right)),
-                    };
-                }
-                _ => break,
-            }
+        while let Some(TokenKind::And) = self.peek_kind() {
+            let operator_token = self.advance().unwrap();
+            let operator = LogOp::try_from(operator_token.value).map_err(unexpected_operator)?;
+            let right = self.equality()?;
+            self.warn_assignment_operand(&expr);
+            self.warn_assignment_operand(&right);
+            let span = expr.span.to(right.span);
+            expr = Expr {
+                kind: ExprKind::Logical(operator, Box::new(expr), Box::new(right)),
+                span,
+            };
         }
 
         Ok(expr)
@@ -478,17 +1141,15 @@ impl<I: Iterator<Item = Token>> Parser<I> {
     fn equality(&mut self) -> Result<Expr, ParsingError> {
         let mut expr = self.comparison()?;
 
-        loop {
-            match self.peek_kind() {
-                Some(TokenKind::Ne) | Some(TokenKind::EqEq) => {
-                    let operator_token = self.advance().unwrap();
-                    let bin_op = BinOp::try_from(operator_token.value).unwrap();
-                    let right = self.comparison()?;
-                    expr = Expr {
-                        kind: ExprKind::Binary(bin_op, Box::new(expr), Box::new(right)),
-                    }
-                }
-                _ => break,
+        while let Some(TokenKind::Ne | TokenKind::EqEq) = self.peek_kind() {
+            let operator_token = self.advance().unwrap();
+            let right = self.comparison()?;
+            self.warn_chained_comparison(&expr, &operator_token, &right);
+            let bin_op = BinOp::try_from(operator_token.value).map_err(unexpected_operator)?;
+            let span = expr.span.to(right.span);
+            expr = Expr {
+                kind: ExprKind::Binary(bin_op, Box::new(expr), Box::new(right)),
+                span,
             }
         }
 
@@ -498,21 +1159,18 @@ impl<I: Iterator<Item = Token>> Parser<I> {
     fn comparison(&mut self) -> Result<Expr, ParsingError> {
         let mut expr = self.term()?;
 
-        loop {
-            match self.peek_kind() {
-                Some(t) => match t {
-                    TokenKind::Gt | TokenKind::Ge | TokenKind::Lt | TokenKind::Le => {
-                        let operator_token = self.advance().unwrap();
-                        let bin_op = BinOp::try_from(operator_token.value).unwrap();
-                        let right = self.term()?;
-
-                        expr = Expr {
-                            kind: ExprKind::Binary(bin_op, Box::new(expr), Box::new(right)),
-                        }
-                    }
-                    _ => break,
-                },
-                _ => break,
+        while let Some(TokenKind::Gt | TokenKind::Ge | TokenKind::Lt | TokenKind::Le) =
+            self.peek_kind()
+        {
+            let operator_token = self.advance().unwrap();
+            let right = self.term()?;
+            self.warn_chained_comparison(&expr, &operator_token, &right);
+            let bin_op = BinOp::try_from(operator_token.value).map_err(unexpected_operator)?;
+
+            let span = expr.span.to(right.span);
+            expr = Expr {
+                kind: ExprKind::Binary(bin_op, Box::new(expr), Box::new(right)),
+                span,
             }
         }
 
@@ -522,18 +1180,15 @@ impl<I: Iterator<Item = Token>> Parser<I> {
     fn term(&mut self) -> Result<Expr, ParsingError> {
         let mut expr = self.factor()?;
 
-        loop {
-            match self.peek_kind() {
-                Some(TokenKind::Minus) | Some(TokenKind::Plus) => {
-                    let operator_token = self.advance().unwrap();
-                    let bin_op = BinOp::try_from(operator_token.value).unwrap();
+        while let Some(TokenKind::Minus | TokenKind::Plus) = self.peek_kind() {
+            let operator_token = self.advance().unwrap();
+            let bin_op = BinOp::try_from(operator_token.value).map_err(unexpected_operator)?;
 
-                    let right = self.factor()?;
-                    expr = Expr {
-                        kind: ExprKind::Binary(bin_op, Box::new(expr), Box::new(right)),
-                    }
-                }
-                _ => break,
+            let right = self.factor()?;
+            let span = expr.span.to(right.span);
+            expr = Expr {
+                kind: ExprKind::Binary(bin_op, Box::new(expr), Box::new(right)),
+                span,
             }
         }
 
@@ -543,18 +1198,15 @@ impl<I: Iterator<Item = Token>> Parser<I> {
     fn factor(&mut self) -> Result<Expr, ParsingError> {
         let mut expr = self.unary()?;
 
-        loop {
-            match self.peek_kind() {
-                Some(TokenKind::Slash) | Some(TokenKind::Star) => {
-                    let operator_token = self.advance().unwrap();
-                    let bin_op = BinOp::try_from(operator_token.value).unwrap();
+        while let Some(TokenKind::Slash | TokenKind::Star) = self.peek_kind() {
+            let operator_token = self.advance().unwrap();
+            let bin_op = BinOp::try_from(operator_token.value).map_err(unexpected_operator)?;
 
-                    let right = self.unary()?;
-                    expr = Expr {
-                        kind: ExprKind::Binary(bin_op, Box::new(expr), Box::new(right)),
-                    }
-                }
-                _ => break,
+            let right = self.unary()?;
+            let span = expr.span.to(right.span);
+            expr = Expr {
+                kind: ExprKind::Binary(bin_op, Box::new(expr), Box::new(right)),
+                span,
             }
         }
 
@@ -565,12 +1217,25 @@ impl<I: Iterator<Item = Token>> Parser<I> {
         match self.peek_kind() {
             Some(TokenKind::Bang) | Some(TokenKind::Minus) => {
                 let operator_token = self.advance().unwrap();
-                let un_op = UnOp::try_from(operator_token.value).unwrap();
+                let start = operator_token.span();
+                let un_op = UnOp::try_from(operator_token.value).map_err(unexpected_operator)?;
                 let right = self.unary()?;
                 Ok(Expr {
+                    span: start.to(right.span),
                     kind: ExprKind::Unary(un_op, Box::new(right)),
                 })
             }
+            Some(TokenKind::Plus) => {
+                // Reported, then parsed as if the `+` weren't there.
+                let plus = self.advance().unwrap();
+                self.report_at(
+                    plus.span(),
+                    "Unary '+' is not supported; numbers are positive without it, so leave it \
+                     out."
+                        .into(),
+                );
+                self.unary()
+            }
             _ => self.call(),
         }
     }
@@ -581,9 +1246,47 @@ impl<I: Iterator<Item = Token>> Parser<I> {
         loop {
             match self.peek_kind() {
                 Some(TokenKind::LeftParen) => {
-                    self.advance().unwrap();
+                    let paren = self.advance().unwrap();
+                    if !self.language.calls {
+                        self.not_allowed(paren.span(), "calls");
+                    }
                     expr = self.finish_call(expr)?
                 }
+                Some(TokenKind::Dot | TokenKind::QuestionDot) => {
+                    let optional = matches!(self.advance().unwrap().value, TokenKind::QuestionDot);
+                    let name = match self.peek_kind() {
+                        Some(TokenKind::Identifier(_)) => self.advance().unwrap(),
+                        _ => {
+                            return Err(ParsingError::GeneralError(
+                                "Expect property name after '.'.".into(),
+                            ))
+                        }
+                    };
+
+                    let span = expr.span.to(name.span());
+                    expr = Expr {
+                        kind: ExprKind::Get(Box::new(expr), name, optional),
+                        span,
+                    }
+                }
+                Some(TokenKind::LeftBracket) => {
+                    self.advance().unwrap();
+                    let index = self.expression()?;
+                    let bracket = match self.peek_kind() {
+                        Some(TokenKind::RightBracket) => self.advance().unwrap(),
+                        _ => {
+                            return Err(ParsingError::GeneralError(
+                                "Expect ']' after index.".into(),
+                            ))
+                        }
+                    };
+
+                    let span = expr.span.to(bracket.span());
+                    expr = Expr {
+                        kind: ExprKind::Index(Box::new(expr), Box::new(index)),
+                        span,
+                    }
+                }
                 _ => break,
             }
         }
@@ -594,6 +1297,7 @@ impl<I: Iterator<Item = Token>> Parser<I> {
     fn finish_call(&mut self, callee: Expr) -> Result<Expr, ParsingError> {
         let mut arguments = vec![];
 
+        self.leading_comma("argument")?;
         match self.peek_kind() {
             Some(TokenKind::RightParen) => (),
             _ => loop {
@@ -603,20 +1307,21 @@ impl<I: Iterator<Item = Token>> Parser<I> {
                     ));
                 }
 
-                arguments.push(self.expression()?);
-                match self.peek_kind() {
-                    Some(TokenKind::Comma) => {
-                        self.advance().unwrap();
-                    }
-                    _ => break,
+                match self.expression() {
+                    Ok(argument) => arguments.push(argument),
+                    Err(e) => self.recover_argument(e)?,
+                }
+                if !self.list_comma(TokenKind::RightParen, "argument")? {
+                    break;
                 }
             },
         }
 
         match self.peek_kind() {
             Some(TokenKind::RightParen) => {
-                self.advance().unwrap();
+                let paren = self.advance().unwrap();
                 Ok(Expr {
+                    span: callee.span.to(paren.span()),
                     kind: ExprKind::Call(Box::new(callee), arguments),
                 })
             }
@@ -626,36 +1331,79 @@ impl<I: Iterator<Item = Token>> Parser<I> {
         }
     }
 
+    /// Reports a broken argument and skips to the `,` or `)` after it, so the arguments
+    /// after it are still checked. Fails if the argument list ends some other way.
+    fn recover_argument(&mut self, error: ParsingError) -> Result<(), ParsingError> {
+        if let ParsingError::GeneralError(message) = error {
+            self.report(message);
+        }
+
+        let mut depth = 0usize;
+        loop {
+            match self.peek_kind() {
+                Some(TokenKind::Comma | TokenKind::RightParen) if depth == 0 => return Ok(()),
+                // The input can end inside a bracket the broken argument opened.
+                None | Some(TokenKind::Eof) => return Err(ParsingError::Reported),
+                Some(TokenKind::Semicolon)
+                | Some(TokenKind::RightBrace | TokenKind::RightBracket)
+                    if depth == 0 =>
+                {
+                    return Err(ParsingError::Reported)
+                }
+                Some(TokenKind::LeftBrace | TokenKind::LeftParen | TokenKind::LeftBracket) => {
+                    depth += 1
+                }
+                Some(TokenKind::RightBrace | TokenKind::RightParen | TokenKind::RightBracket) => {
+                    depth -= 1
+                }
+                _ => (),
+            }
+            self.advance();
+        }
+    }
+
     fn primary(&mut self) -> Result<Expr, ParsingError> {
+        // Tokens that end an expression are left for whatever encloses it, to recover at.
+        let found = match self.peek_kind() {
+            None | Some(TokenKind::Eof) => Some("end of input".to_string()),
+            Some(TokenKind::RightParen) => Some("')'".to_string()),
+            Some(TokenKind::RightBrace) => Some("'}'".to_string()),
+            Some(TokenKind::RightBracket) => Some("']'".to_string()),
+            Some(TokenKind::Semicolon) => Some("';'".to_string()),
+            Some(TokenKind::Comma) => Some("','".to_string()),
+            _ => None,
+        };
+        if let Some(found) = found {
+            return Err(ParsingError::GeneralError(format!(
+                "Expect expression, found {}.",
+                found
+            )));
+        }
+
         let token = match self.advance() {
             Some(token) => token,
             None => return Err(ParsingError::GeneralError("Unexpected EOF".to_string())),
         };
 
-        let expr = match token.value {
-            TokenKind::False => Expr {
-                kind: ExprKind::Literal(Literal::Bool(false)),
-            },
-            TokenKind::True => Expr {
-                kind: ExprKind::Literal(Literal::Bool(true)),
-            },
-            TokenKind::Nil => Expr {
-                kind: ExprKind::Literal(Literal::Nil),
-            },
-            TokenKind::Number(num) => Expr {
-                kind: ExprKind::Literal(Literal::Number(num)),
-            },
-            TokenKind::String(s) => Expr {
-                kind: ExprKind::Literal(Literal::String(s.to_string())),
-            },
+        let start = token.span();
+        let kind = match token.value {
+            TokenKind::False => ExprKind::Literal(Literal::Bool(false)),
+            TokenKind::True => ExprKind::Literal(Literal::Bool(true)),
+            TokenKind::Nil => ExprKind::Literal(Literal::Nil),
+            TokenKind::Number(num) => ExprKind::Literal(Literal::Number(num)),
+            TokenKind::String(s) => ExprKind::Literal(Literal::String(s.as_str().into())),
             TokenKind::LeftParen => {
+                // The `)` is taken along, so recovery doesn't mistake it for an enclosing one.
+                if let Some(TokenKind::RightParen) = self.peek_kind() {
+                    let paren = self.advance().unwrap();
+                    self.report_at(paren.span(), "Expect expression inside parentheses.".into());
+                    return Err(ParsingError::Reported);
+                }
                 let expr = self.expression()?;
                 match self.peek_kind() {
                     Some(TokenKind::RightParen) => {
                         self.advance().unwrap();
-                        Expr {
-                            kind: ExprKind::Grouping(Box::new(expr)),
-                        }
+                        ExprKind::Grouping(Box::new(expr))
                     }
                     _ => {
                         return Err(ParsingError::GeneralError(
@@ -664,17 +1412,335 @@ impl<I: Iterator<Item = Token>> Parser<I> {
                     }
                 }
             }
-            TokenKind::Identifier(_) => Expr {
-                kind: ExprKind::Variable(token),
+            TokenKind::Identifier(_) => ExprKind::Variable(token, None),
+            TokenKind::This => ExprKind::This(token, None),
+            TokenKind::Super => {
+                match self.peek_kind() {
+                    Some(TokenKind::Dot) => self.advance().unwrap(),
+                    _ => {
+                        return Err(ParsingError::GeneralError(
+                            "Expect '.' after 'super'.".into(),
+                        ))
+                    }
+                };
+                match self.peek_kind() {
+                    Some(TokenKind::Identifier(_)) => {
+                        ExprKind::Super(self.advance().unwrap(), None)
+                    }
+                    _ => {
+                        return Err(ParsingError::GeneralError(
+                            "Expect superclass method name.".into(),
+                        ))
+                    }
+                }
+            }
+            TokenKind::Match => self.match_expression()?,
+            TokenKind::LeftBrace => {
+                let mut entries = vec![];
+
+                self.leading_comma("map entry")?;
+                if !matches!(self.peek_kind(), Some(TokenKind::RightBrace)) {
+                    loop {
+                        let key = self.expression()?;
+                        match self.peek_kind() {
+                            Some(TokenKind::Colon) => self.advance().unwrap(),
+                            _ => {
+                                return Err(ParsingError::GeneralError(
+                                    "Expect ':' after map key.".into(),
+                                ))
+                            }
+                        };
+                        let value = self.expression()?;
+                        entries.push((key, value));
+
+                        if !self.list_comma(TokenKind::RightBrace, "map entry")? {
+                            break;
+                        }
+                    }
+                }
+
+                match self.peek_kind() {
+                    Some(TokenKind::RightBrace) => {
+                        self.advance().unwrap();
+                        ExprKind::Map(entries)
+                    }
+                    _ => {
+                        return Err(ParsingError::GeneralError(
+                            "Expect '}' after map entries.".into(),
+                        ))
+                    }
+                }
+            }
+            TokenKind::LeftBracket => {
+                let mut elements = vec![];
+
+                self.leading_comma("array element")?;
+                if !matches!(self.peek_kind(), Some(TokenKind::RightBracket)) {
+                    loop {
+                        elements.push(self.expression()?);
+                        if !self.list_comma(TokenKind::RightBracket, "array element")? {
+                            break;
+                        }
+                    }
+                }
+
+                match self.peek_kind() {
+                    Some(TokenKind::RightBracket) => {
+                        self.advance().unwrap();
+                        ExprKind::Array(elements)
+                    }
+                    _ => {
+                        return Err(ParsingError::GeneralError(
+                            "Expect ']' after array elements.".into(),
+                        ))
+                    }
+                }
+            }
+            _ => {
+                self.report_at(start, format!("Unexpected token '{}'.", token.lexeme));
+                return Err(ParsingError::Reported);
+            }
+        };
+
+        Ok(Expr {
+            kind,
+            span: start.to(self.previous),
+        })
+    }
+
+    /// Parses the rest of `match (value) { pattern -> body; ... }` after the `match` keyword.
+    fn match_expression(&mut self) -> Result<ExprKind, ParsingError> {
+        match self.peek_kind() {
+            Some(TokenKind::LeftParen) => self.advance().unwrap(),
+            _ => {
+                return Err(ParsingError::GeneralError(
+                    "Expect '(' after 'match'.".into(),
+                ))
+            }
+        };
+        let value = self.expression()?;
+        match self.peek_kind() {
+            Some(TokenKind::RightParen) => self.advance().unwrap(),
+            _ => {
+                return Err(ParsingError::GeneralError(
+                    "Expect ')' after match value.".into(),
+                ))
+            }
+        };
+        match self.peek_kind() {
+            Some(TokenKind::LeftBrace) => self.advance().unwrap(),
+            _ => {
+                return Err(ParsingError::GeneralError(
+                    "Expect '{' before match arms.".into(),
+                ))
+            }
+        };
+
+        let mut arms = vec![];
+        while !matches!(
+            self.peek_kind(),
+            Some(TokenKind::RightBrace) | Some(TokenKind::Eof) | None
+        ) {
+            let pattern = self.pattern()?;
+            match self.peek_kind() {
+                Some(TokenKind::Arrow) => self.advance().unwrap(),
+                _ => {
+                    return Err(ParsingError::GeneralError(
+                        "Expect '->' after pattern.".into(),
+                    ))
+                }
+            };
+
+            // A brace after the arrow always opens a block, so a map literal as the value of
+            // an arm has to be wrapped in parentheses.
+            let body = match self.peek_kind() {
+                Some(TokenKind::LeftBrace) => {
+                    self.advance().unwrap();
+                    let body = ArmBody::Block(self.block()?);
+                    if let Some(TokenKind::Semicolon) = self.peek_kind() {
+                        self.advance().unwrap();
+                    }
+                    body
+                }
+                _ => {
+                    let body = ArmBody::Expr(self.expression()?);
+                    match self.peek_kind() {
+                        Some(TokenKind::Semicolon) => {
+                            self.advance().unwrap();
+                        }
+                        Some(TokenKind::RightBrace) => (),
+                        _ => {
+                            return Err(ParsingError::GeneralError(
+                                "Expect ';' after match arm.".into(),
+                            ))
+                        }
+                    }
+                    body
+                }
+            };
+
+            arms.push(MatchArm { pattern, body });
+        }
+
+        match self.peek_kind() {
+            Some(TokenKind::RightBrace) => {
+                self.advance().unwrap();
+                Ok(ExprKind::Match(Box::new(value), arms))
+            }
+            _ => Err(ParsingError::GeneralError(
+                "Expect '}' after match arms.".into(),
+            )),
+        }
+    }
+
+    /// Parses a dotted name such as `Color.Red` in a pattern, whose first name is `first`.
+    fn pattern_path(&mut self, first: Token) -> Result<Expr, ParsingError> {
+        let mut expr = Expr {
+            span: first.span(),
+            kind: ExprKind::Variable(first, None),
+        };
+
+        while let Some(TokenKind::Dot) = self.peek_kind() {
+            self.advance().unwrap();
+            let name = match self.peek_kind() {
+                Some(TokenKind::Identifier(_)) => self.advance().unwrap(),
+                _ => {
+                    return Err(ParsingError::GeneralError(
+                        "Expect property name after '.'.".into(),
+                    ))
+                }
+            };
+
+            let span = expr.span.to(name.span());
+            expr = Expr {
+                kind: ExprKind::Get(Box::new(expr), name, false),
+                span,
+            }
+        }
+
+        Ok(expr)
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, ParsingError> {
+        let token = match self.advance() {
+            Some(token) => token,
+            None => return Err(ParsingError::GeneralError("Unexpected EOF".to_string())),
+        };
+
+        let start = token.span();
+        let kind = match token.value {
+            TokenKind::False => PatternKind::Literal(Literal::Bool(false)),
+            TokenKind::True => PatternKind::Literal(Literal::Bool(true)),
+            TokenKind::Nil => PatternKind::Literal(Literal::Nil),
+            TokenKind::Number(num) => PatternKind::Literal(Literal::Number(num)),
+            TokenKind::Minus => match self.peek_kind() {
+                Some(TokenKind::Number(num)) => {
+                    let num = -*num;
+                    self.advance().unwrap();
+                    PatternKind::Literal(Literal::Number(num))
+                }
+                _ => {
+                    return Err(ParsingError::GeneralError(
+                        "Expect a number after '-' in pattern.".into(),
+                    ))
+                }
             },
+            TokenKind::String(s) => PatternKind::Literal(Literal::String(s.as_str().into())),
+            TokenKind::Identifier(name) if name == "_" => PatternKind::Wildcard,
+            TokenKind::Identifier(_) if matches!(self.peek_kind(), Some(TokenKind::Dot)) => {
+                PatternKind::Value(self.pattern_path(token)?)
+            }
+            TokenKind::Identifier(_) => PatternKind::Binding(token),
+            TokenKind::LeftBracket => {
+                let mut elements = vec![];
+
+                self.leading_comma("pattern")?;
+                if !matches!(self.peek_kind(), Some(TokenKind::RightBracket)) {
+                    loop {
+                        elements.push(self.pattern()?);
+                        if !self.list_comma(TokenKind::RightBracket, "pattern")? {
+                            break;
+                        }
+                    }
+                }
+
+                match self.peek_kind() {
+                    Some(TokenKind::RightBracket) => {
+                        self.advance().unwrap();
+                        PatternKind::Array(elements)
+                    }
+                    _ => {
+                        return Err(ParsingError::GeneralError(
+                            "Expect ']' after array pattern.".into(),
+                        ))
+                    }
+                }
+            }
+            TokenKind::LeftBrace => {
+                let mut entries = vec![];
+
+                self.leading_comma("map pattern entry")?;
+                if !matches!(self.peek_kind(), Some(TokenKind::RightBrace)) {
+                    loop {
+                        let key = match self.advance().map(|token| token.value) {
+                            Some(TokenKind::Identifier(key) | TokenKind::String(key)) => key,
+                            _ => {
+                                return Err(ParsingError::GeneralError(
+                                    "Expect a name or string as map pattern key.".into(),
+                                ))
+                            }
+                        };
+                        match self.peek_kind() {
+                            Some(TokenKind::Colon) => self.advance().unwrap(),
+                            _ => {
+                                return Err(ParsingError::GeneralError(
+                                    "Expect ':' after map pattern key.".into(),
+                                ))
+                            }
+                        };
+                        entries.push((key, self.pattern()?));
+
+                        if !self.list_comma(TokenKind::RightBrace, "map pattern entry")? {
+                            break;
+                        }
+                    }
+                }
+
+                match self.peek_kind() {
+                    Some(TokenKind::RightBrace) => {
+                        self.advance().unwrap();
+                        PatternKind::Map(entries)
+                    }
+                    _ => {
+                        return Err(ParsingError::GeneralError(
+                            "Expect '}' after map pattern.".into(),
+                        ))
+                    }
+                }
+            }
             _ => {
                 return Err(ParsingError::GeneralError(format!(
-                    "Unexpected token {:?}",
-                    token
+                    "Expect a pattern, found '{}'.",
+                    token.lexeme
                 )))
             }
         };
 
-        Ok(expr)
+        Ok(Pattern {
+            kind,
+            span: start.to(self.previous),
+        })
+    }
+}
+
+fn unexpected_operator(e: &str) -> ParsingError {
+    ParsingError::GeneralError(format!("Internal error: {}.", e))
+}
+
+/// The variable or field an assignment expression assigns to, if `expr` is one.
+fn assigned_name(expr: &Expr) -> Option<&Token> {
+    match &expr.kind {
+        ExprKind::Assign(name, ..) | ExprKind::Set(_, name, _) => Some(name),
+        _ => None,
     }
 }
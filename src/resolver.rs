@@ -0,0 +1,741 @@
+use std::collections::HashMap;
+
+use crate::{
+    ast::{
+        expr::{Expr, ExprKind},
+        pattern::{ArmBody, Pattern, PatternKind},
+        stmt::{Stmt, StmtKind},
+    },
+    diagnostics::DiagnosticSink,
+    lexer::{Span, Token},
+    source_map::SourceMap,
+    Diagnostic, LoxError,
+};
+
+/// Whether to warn about declarations that shadow a binding from an enclosing scope.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shadowing {
+    /// Don't, as at the REPL, where reusing a name is usually intended.
+    Ignore,
+    /// Do, except for the names in `allow`.
+    Warn { allow: &'static [&'static str] },
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum FunctionType {
+    None,
+    Function,
+    Method,
+    Initializer,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ClassType {
+    None,
+    Class,
+    Subclass,
+    /// An `extend` body. Whether the class has a superclass is only known when it runs.
+    Extension,
+}
+
+struct Binding {
+    token: Token,
+    defined: bool,
+}
+
+struct PendingDeclaration {
+    token: Token,
+    is_function: bool,
+}
+
+#[derive(Default)]
+struct Scope {
+    bindings: HashMap<String, Binding>,
+    /// Names declared further down in this block that haven't been reached yet.
+    pending: HashMap<String, PendingDeclaration>,
+}
+
+pub struct Resolver<'a> {
+    sources: &'a SourceMap,
+    sink: &'a mut DiagnosticSink,
+    scopes: Vec<Scope>,
+    current_function: FunctionType,
+    current_class: ClassType,
+    /// Whether the code being resolved is the body of a `defer` in the current function,
+    /// which runs after the function has finished and so has nothing to return from.
+    in_defer: bool,
+    /// How many scopes enclose the function being resolved, not counting its own.
+    function_scopes: usize,
+    shadowing: Shadowing,
+    /// The globals declared so far, which declarations in scopes can shadow too.
+    globals: HashMap<String, Token>,
+    errors: Vec<Diagnostic>,
+}
+
+/// Resolves every local variable in `stmts` to the number of scopes between its use and its
+/// declaration, collecting scoping mistakes with their location in `sources` along the way.
+/// If there were any the program shouldn't be run. Warnings go to `sink`, including those
+/// about shadowed bindings if `shadowing` asks for them.
+pub fn resolve(
+    stmts: &mut [Stmt],
+    sources: &SourceMap,
+    shadowing: Shadowing,
+    sink: &mut DiagnosticSink,
+) -> Result<(), LoxError> {
+    let mut resolver = Resolver::new(sources, sink);
+    resolver.shadowing = shadowing;
+    resolver.resolve_stmts(stmts);
+    resolver.finish()
+}
+
+/// Resolves a lone expression, such as one typed at the REPL, as if it were a statement at
+/// the top level.
+pub fn resolve_expression(
+    expr: &mut Expr,
+    sources: &SourceMap,
+    sink: &mut DiagnosticSink,
+) -> Result<(), LoxError> {
+    let mut resolver = Resolver::new(sources, sink);
+    resolver.resolve_expr(expr);
+    resolver.finish()
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(sources: &'a SourceMap, sink: &'a mut DiagnosticSink) -> Self {
+        Self {
+            sources,
+            sink,
+            scopes: vec![],
+            current_function: FunctionType::None,
+            current_class: ClassType::None,
+            in_defer: false,
+            function_scopes: 0,
+            shadowing: Shadowing::Ignore,
+            globals: HashMap::new(),
+            errors: vec![],
+        }
+    }
+
+    /// Turns the errors found so far into the result of the whole resolution.
+    fn finish(self) -> Result<(), LoxError> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(LoxError::Resolve(self.errors))
+        }
+    }
+
+    fn error(&mut self, span: Span, message: String) {
+        let location = self.sources.location(span);
+        self.errors.push(Diagnostic::error(
+            "resolve",
+            location,
+            span.line,
+            span.column,
+            message,
+        ));
+    }
+
+    fn error_with_help(&mut self, span: Span, message: &str, help: &str) {
+        self.error(span, message.into());
+        if let Some(error) = self.errors.last_mut() {
+            error.help = Some(help.into());
+        }
+    }
+
+    fn warning(&mut self, code: &'static str, span: Span, message: String) {
+        let location = self.sources.location(span);
+        self.sink.report(Diagnostic::warning(
+            code,
+            location,
+            span.line,
+            span.column,
+            message,
+        ));
+    }
+
+    fn resolve_stmts(&mut self, stmts: &mut [Stmt]) {
+        for stmt in stmts {
+            self.resolve_stmt(stmt);
+        }
+    }
+
+    fn resolve_stmt(&mut self, stmt: &mut Stmt) {
+        match &mut stmt.kind {
+            StmtKind::Expression(expr) | StmtKind::Print(expr) => self.resolve_expr(expr),
+            StmtKind::Var(name, initializer) => {
+                self.check_shadowing(name, initializer.as_ref());
+                self.declare(name);
+                if let Some(initializer) = initializer {
+                    self.resolve_expr(initializer);
+                }
+                self.define(name);
+            }
+            StmtKind::Const(name, initializer) => {
+                self.check_shadowing(name, Some(initializer));
+                self.declare(name);
+                self.resolve_expr(initializer);
+                self.define(name);
+            }
+            StmtKind::Unpack(names, initializer) => {
+                for name in names.iter() {
+                    self.check_shadowing(name, Some(initializer));
+                    self.declare(name);
+                }
+                self.resolve_expr(initializer);
+                for name in names.iter() {
+                    self.define(name);
+                }
+            }
+            StmtKind::Enum(name, _) => {
+                self.check_shadowing(name, None);
+                self.declare(name);
+                self.define(name);
+            }
+            StmtKind::Class(name, superclass, methods, _) => {
+                let enclosing_class = self.current_class;
+                self.current_class = ClassType::Class;
+                self.check_shadowing(name, None);
+                self.declare(name);
+                self.define(name);
+
+                if let Some(superclass) = superclass {
+                    if let ExprKind::Variable(superclass_name, _) = &superclass.kind {
+                        if superclass_name.lexeme == name.lexeme {
+                            self.error(
+                                superclass.span,
+                                "A class can't inherit from itself.".into(),
+                            );
+                        }
+                    }
+                    self.current_class = ClassType::Subclass;
+                    self.resolve_expr(superclass);
+                    self.begin_scope(&[]);
+                    self.define_implicit("super", name);
+                }
+
+                self.resolve_methods(name, methods);
+
+                if superclass.is_some() {
+                    self.end_scope();
+                }
+                self.current_class = enclosing_class;
+            }
+            StmtKind::Extend(class, methods) => {
+                self.resolve_expr(class);
+                let ExprKind::Variable(name, _) = &class.kind else {
+                    return;
+                };
+
+                let enclosing_class = self.current_class;
+                self.current_class = ClassType::Extension;
+                // The interpreter binds `super` for every extension, to nil if the class has
+                // no superclass.
+                self.begin_scope(&[]);
+                self.define_implicit("super", name);
+                self.resolve_methods(name, methods);
+                self.end_scope();
+                self.current_class = enclosing_class;
+            }
+            StmtKind::Block(stmts) => {
+                self.begin_scope(stmts);
+                self.resolve_stmts(stmts);
+                self.end_scope();
+            }
+            StmtKind::If(arms, else_branch) => {
+                for (condition, branch) in arms {
+                    self.resolve_expr(condition);
+                    self.resolve_stmt(branch);
+                }
+                if let Some(else_branch) = else_branch {
+                    self.resolve_stmt(else_branch);
+                }
+            }
+            StmtKind::While(condition, body) => {
+                self.resolve_expr(condition);
+                self.resolve_stmt(body);
+            }
+            // Scoped the way the interpreter runs it: the initializer in a scope around the
+            // loop, and the body with the increment in one of their own.
+            StmtKind::For(initializer, condition, increment, body) => {
+                if let Some(initializer) = initializer {
+                    self.begin_scope(std::slice::from_ref(&**initializer));
+                    self.resolve_stmt(initializer);
+                }
+                if let Some(condition) = condition {
+                    self.resolve_expr(condition);
+                }
+                if increment.is_some() {
+                    self.begin_scope(&[]);
+                }
+                self.resolve_stmt(body);
+                if let Some(increment) = increment {
+                    self.resolve_expr(increment);
+                    self.end_scope();
+                }
+                if initializer.is_some() {
+                    self.end_scope();
+                }
+            }
+            // The name is bound in a scope around the body, one for each pass.
+            StmtKind::ForIn(name, iterable, body) => {
+                self.resolve_expr(iterable);
+                self.begin_scope(&[]);
+                self.check_shadowing(name, None);
+                self.declare(name);
+                self.define(name);
+                self.resolve_stmt(body);
+                self.end_scope();
+            }
+            StmtKind::Function(name, params, body, _) => {
+                self.check_shadowing(name, None);
+                self.declare(name);
+                self.define(name);
+                self.resolve_function(name, params, body, FunctionType::Function);
+            }
+            StmtKind::Defer(body) => {
+                let enclosing = std::mem::replace(&mut self.in_defer, true);
+                self.resolve_stmt(body);
+                self.in_defer = enclosing;
+            }
+            StmtKind::Return(keyword, value) => {
+                if self.current_function == FunctionType::None {
+                    self.error(keyword.span(), "Can't return from top-level code.".into());
+                } else if self.in_defer {
+                    self.error(
+                        keyword.span(),
+                        "Can't return from a deferred statement.".into(),
+                    );
+                }
+                if let Some(value) = value {
+                    if self.current_function == FunctionType::Initializer {
+                        self.error_with_help(
+                            keyword.span(),
+                            "Can't return a value from an initializer.",
+                            "An initializer always returns 'this'; use 'return;' to leave it early.",
+                        );
+                    }
+                    self.resolve_expr(value);
+                }
+            }
+        }
+    }
+
+    /// Resolves the methods of `class` and the initializers of its fields, in a scope binding
+    /// `this`.
+    fn resolve_methods(&mut self, class: &Token, methods: &mut [Stmt]) {
+        self.begin_scope(&[]);
+        self.define_implicit("this", class);
+        for method in methods {
+            match &mut method.kind {
+                StmtKind::Function(method_name, params, body, _) => {
+                    let function_type = if method_name.lexeme == "init" {
+                        FunctionType::Initializer
+                    } else {
+                        FunctionType::Method
+                    };
+                    self.resolve_function(method_name, params, body, function_type);
+                }
+                StmtKind::Var(_, Some(initializer)) => {
+                    // An initializer runs on its own, not as part of any function around it.
+                    let enclosing_function = self.current_function;
+                    self.current_function = FunctionType::None;
+                    self.resolve_expr(initializer);
+                    self.current_function = enclosing_function;
+                }
+                _ => (),
+            }
+        }
+        self.end_scope();
+    }
+
+    fn resolve_function(
+        &mut self,
+        name: &Token,
+        params: &[Token],
+        body: &mut [Stmt],
+        function_type: FunctionType,
+    ) {
+        let enclosing_function = self.current_function;
+        let enclosing_scopes = self.function_scopes;
+        let enclosing_defer = std::mem::replace(&mut self.in_defer, false);
+        self.current_function = function_type;
+        self.function_scopes = self.scopes.len();
+
+        self.begin_scope(body);
+        for param in params {
+            if param.lexeme == name.lexeme {
+                self.warning(
+                    "parameter-shadows-function",
+                    param.span(),
+                    format!(
+                        "Parameter '{}' on line {} shadows the name of its own function, declared at line {}.",
+                        param.lexeme, param.line, name.line
+                    ),
+                );
+            }
+            self.declare(param);
+            self.define(param);
+        }
+        self.resolve_stmts(body);
+        self.end_scope();
+
+        self.current_function = enclosing_function;
+        self.function_scopes = enclosing_scopes;
+        self.in_defer = enclosing_defer;
+    }
+
+    fn resolve_expr(&mut self, expr: &mut Expr) {
+        match &mut expr.kind {
+            ExprKind::Array(elements) => {
+                for element in elements {
+                    self.resolve_expr(element);
+                }
+            }
+            ExprKind::Map(entries) => {
+                for (key, value) in entries {
+                    self.resolve_expr(key);
+                    self.resolve_expr(value);
+                }
+            }
+            ExprKind::Tuple(values) => {
+                for value in values {
+                    self.resolve_expr(value);
+                }
+            }
+            ExprKind::Binary(_, lhs, rhs) | ExprKind::Logical(_, lhs, rhs) => {
+                self.resolve_expr(lhs);
+                self.resolve_expr(rhs);
+            }
+            ExprKind::Call(callee, arguments) => {
+                self.resolve_expr(callee);
+                for argument in arguments {
+                    self.resolve_expr(argument);
+                }
+            }
+            ExprKind::Get(object, _, _) => self.resolve_expr(object),
+            ExprKind::Index(value, index) => {
+                self.resolve_expr(value);
+                self.resolve_expr(index);
+            }
+            ExprKind::Set(object, _, value) => {
+                self.resolve_expr(value);
+                self.resolve_expr(object);
+            }
+            ExprKind::This(keyword, depth) => {
+                if self.current_class == ClassType::None {
+                    self.error_with_help(
+                        keyword.span(),
+                        "Can't use 'this' outside of a class.",
+                        "'this' is the instance a method was called on, so only methods and \
+                         field initializers have one.",
+                    );
+                    return;
+                }
+                *depth = self.resolve_implicit("this");
+            }
+            ExprKind::Super(_, depth) => {
+                // The expression runs from `super` to the method name; point at the keyword.
+                let keyword = Span {
+                    end: expr.span.start + "super".len(),
+                    ..expr.span
+                };
+                match self.current_class {
+                    ClassType::None => self.error_with_help(
+                        keyword,
+                        "Can't use 'super' outside of a class.",
+                        "'super' reaches the superclass's methods, so only methods of a \
+                         subclass can use it.",
+                    ),
+                    ClassType::Class => self.error_with_help(
+                        keyword,
+                        "Can't use 'super' in a class with no superclass.",
+                        "Declare a superclass with `class Name < Superclass`, or call the method \
+                         through 'this'.",
+                    ),
+                    ClassType::Subclass | ClassType::Extension => {
+                        *depth = self.resolve_implicit("super")
+                    }
+                }
+            }
+            ExprKind::Grouping(expr) | ExprKind::Unary(_, expr) => self.resolve_expr(expr),
+            ExprKind::Literal(_) => (),
+            ExprKind::Variable(name, depth) => {
+                if let Some(binding) = self
+                    .scopes
+                    .last()
+                    .and_then(|scope| scope.bindings.get(&name.lexeme))
+                {
+                    if !binding.defined {
+                        let message = format!(
+                            "Can't read local variable '{}' in its own initializer (declared at line {}).",
+                            name.lexeme, binding.token.line
+                        );
+                        self.error(name.span(), message);
+                    }
+                }
+
+                *depth = self.resolve_local(name);
+            }
+            ExprKind::Assign(name, value, depth) => {
+                self.resolve_expr(value);
+                *depth = self.resolve_local(name);
+            }
+            ExprKind::Match(value, arms) => {
+                self.resolve_expr(value);
+                for arm in arms.iter_mut() {
+                    self.resolve_pattern_values(&mut arm.pattern);
+                }
+
+                // Each arm gets its own scope holding what its pattern binds. A block body
+                // shares that scope rather than opening another.
+                for arm in arms {
+                    match &mut arm.body {
+                        ArmBody::Expr(body) => {
+                            self.begin_scope(&[]);
+                            self.declare_pattern(&arm.pattern);
+                            self.resolve_expr(body);
+                        }
+                        ArmBody::Block(stmts) => {
+                            self.begin_scope(stmts);
+                            self.declare_pattern(&arm.pattern);
+                            self.resolve_stmts(stmts);
+                        }
+                    }
+                    self.end_scope();
+                }
+            }
+        }
+    }
+
+    /// Resolves the `Color.Red` style values in a pattern, which are evaluated outside the
+    /// arm's scope.
+    fn resolve_pattern_values(&mut self, pattern: &mut Pattern) {
+        match &mut pattern.kind {
+            PatternKind::Value(value) => self.resolve_expr(value),
+            PatternKind::Array(elements) => {
+                for element in elements {
+                    self.resolve_pattern_values(element);
+                }
+            }
+            PatternKind::Map(entries) => {
+                for (_, pattern) in entries {
+                    self.resolve_pattern_values(pattern);
+                }
+            }
+            PatternKind::Wildcard | PatternKind::Literal(_) | PatternKind::Binding(_) => (),
+        }
+    }
+
+    fn declare_pattern(&mut self, pattern: &Pattern) {
+        for name in pattern.bindings() {
+            self.declare(name);
+            self.define(name);
+        }
+    }
+
+    fn resolve_local(&mut self, name: &Token) -> Option<usize> {
+        for (index, scope) in self.scopes.iter().enumerate().rev() {
+            let depth = self.scopes.len() - 1 - index;
+            if scope.bindings.contains_key(&name.lexeme) {
+                return Some(depth);
+            }
+
+            if let Some(declaration) = scope.pending.get(&name.lexeme) {
+                // A function can call one declared later in a block around it, so local
+                // functions can be mutually recursive. The later one exists by the time the
+                // call runs unless the first is called before the second is declared.
+                if declaration.is_function && index < self.function_scopes {
+                    return Some(depth);
+                }
+
+                let message = format!(
+                    "'{}' is used before its declaration at line {}.",
+                    name.lexeme, declaration.token.line
+                );
+                self.error(name.span(), message);
+                return None;
+            }
+        }
+
+        None
+    }
+
+    /// The distance to the scope binding `this` or `super`, which `define_implicit` put there.
+    fn resolve_implicit(&self, name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .position(|scope| scope.bindings.contains_key(name))
+    }
+
+    fn begin_scope(&mut self, stmts: &[Stmt]) {
+        let mut scope = Scope::default();
+
+        for stmt in stmts {
+            if let StmtKind::Unpack(names, _) = &stmt.kind {
+                for name in names {
+                    scope.pending.entry(name.lexeme.clone()).or_insert_with(|| {
+                        PendingDeclaration {
+                            token: name.clone(),
+                            is_function: false,
+                        }
+                    });
+                }
+            }
+            if let StmtKind::Var(name, _)
+            | StmtKind::Const(name, _)
+            | StmtKind::Function(name, ..)
+            | StmtKind::Enum(name, _)
+            | StmtKind::Class(name, ..) = &stmt.kind
+            {
+                scope
+                    .pending
+                    .entry(name.lexeme.clone())
+                    .or_insert_with(|| PendingDeclaration {
+                        token: name.clone(),
+                        is_function: matches!(stmt.kind, StmtKind::Function(..)),
+                    });
+            }
+        }
+
+        self.scopes.push(scope);
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// Warns if declaring `name` in the innermost scope would shadow a binding from one
+    /// around it, naming where both were declared. Parameters and pattern bindings are left
+    /// alone, as is a declaration whose `initializer` mentions the name: that is deliberate
+    /// rebinding, and reading the name there is an error of its own.
+    fn check_shadowing(&mut self, name: &Token, initializer: Option<&Expr>) {
+        let Shadowing::Warn { allow } = self.shadowing else {
+            return;
+        };
+        let Some((_, enclosing)) = self.scopes.split_last() else {
+            return;
+        };
+        if allow.contains(&name.lexeme.as_str())
+            || initializer.is_some_and(|initializer| mentions(initializer, &name.lexeme))
+        {
+            return;
+        }
+
+        let Some(shadowed) = enclosing
+            .iter()
+            .rev()
+            .find_map(|scope| {
+                scope
+                    .bindings
+                    .get(&name.lexeme)
+                    .map(|binding| &binding.token)
+            })
+            .or_else(|| self.globals.get(&name.lexeme))
+        else {
+            return;
+        };
+
+        let message = format!(
+            "'{}' shadows the '{}' declared at {}.",
+            name.lexeme,
+            name.lexeme,
+            self.sources.location(shadowed.span())
+        );
+        let location = self.sources.location(name.span());
+        self.sink.report(
+            Diagnostic::warning("shadowing", location, name.line, name.column, message).with_help(
+                "Rename one of them if this is a typo, or run with --allow shadowing if it \
+                 is on purpose.",
+            ),
+        );
+    }
+
+    fn declare(&mut self, name: &Token) {
+        let scope = match self.scopes.last_mut() {
+            Some(scope) => scope,
+            None => {
+                self.globals
+                    .entry(name.lexeme.clone())
+                    .or_insert_with(|| name.clone());
+                return;
+            }
+        };
+
+        scope.pending.remove(&name.lexeme);
+
+        if let Some(existing) = scope.bindings.get(&name.lexeme) {
+            let message = format!(
+                "Already a variable named '{}' in this scope (declared at line {}).",
+                name.lexeme, existing.token.line
+            );
+            self.error(name.span(), message);
+            return;
+        }
+
+        scope.bindings.insert(
+            name.lexeme.clone(),
+            Binding {
+                token: name.clone(),
+                defined: false,
+            },
+        );
+    }
+
+    /// Binds `this` or `super` in the innermost scope. They have no declaration of their own,
+    /// so they are attributed to the class they belong to.
+    fn define_implicit(&mut self, name: &str, class: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.bindings.insert(
+                name.to_string(),
+                Binding {
+                    token: class.clone(),
+                    defined: true,
+                },
+            );
+        }
+    }
+
+    fn define(&mut self, name: &Token) {
+        if let Some(binding) = self
+            .scopes
+            .last_mut()
+            .and_then(|scope| scope.bindings.get_mut(&name.lexeme))
+        {
+            binding.defined = true;
+        }
+    }
+}
+
+/// Whether `expr` reads or assigns the variable `name` anywhere outside a nested block.
+fn mentions(expr: &Expr, name: &str) -> bool {
+    match &expr.kind {
+        ExprKind::Variable(variable, _) => variable.lexeme == name,
+        ExprKind::Assign(variable, value, _) => variable.lexeme == name || mentions(value, name),
+        ExprKind::Array(elements) | ExprKind::Tuple(elements) => {
+            elements.iter().any(|element| mentions(element, name))
+        }
+        ExprKind::Map(entries) => entries
+            .iter()
+            .any(|(key, value)| mentions(key, name) || mentions(value, name)),
+        ExprKind::Binary(_, lhs, rhs) | ExprKind::Logical(_, lhs, rhs) => {
+            mentions(lhs, name) || mentions(rhs, name)
+        }
+        ExprKind::Call(callee, arguments) => {
+            mentions(callee, name) || arguments.iter().any(|argument| mentions(argument, name))
+        }
+        ExprKind::Get(object, _, _) => mentions(object, name),
+        ExprKind::Index(value, index) => mentions(value, name) || mentions(index, name),
+        ExprKind::Set(object, _, value) => mentions(object, name) || mentions(value, name),
+        ExprKind::Grouping(expr) | ExprKind::Unary(_, expr) => mentions(expr, name),
+        ExprKind::Match(value, arms) => {
+            mentions(value, name)
+                || arms.iter().any(|arm| match &arm.body {
+                    ArmBody::Expr(body) => mentions(body, name),
+                    ArmBody::Block(_) => false,
+                })
+        }
+        ExprKind::This(..) | ExprKind::Super(..) | ExprKind::Literal(_) => false,
+    }
+}
@@ -1,47 +1,1005 @@
+use std::{
+    cell::RefCell,
+    io::{self, BufRead},
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
 use crate::{
-    interpreter::{interpreter::Interpreter, InterpreterErrorKind},
-    lexer, parser, LoxError, LoxErrorType,
+    ast::{
+        expr::{Expr, Literal},
+        lower,
+        stmt::{Stmt, StmtKind},
+    },
+    diagnostics::{DiagnosticOptions, DiagnosticSink},
+    interpreter::{
+        callable::{self, LoxCallable, PrintNative},
+        interpreter::{DebugHook, Interpreter, Strictness},
+        map::LoxMap,
+        native::NativeError,
+        pretty,
+        stats::ExecStats,
+    },
+    lexer::{self, Span, Token},
+    modules::{Freshness, ModuleCache},
+    optimizer,
+    parser::{self, LanguageOptions, ParserOptions},
+    resolver::{self, Shadowing},
+    source_map::{FileId, SourceMap},
+    Diagnostic, LoxError, RuntimeDiagnostic, TraceFrame,
 };
 
+/// Lox code run before anything else, defining the utilities it's simpler to write in Lox.
+const PRELUDE: &str = include_str!("prelude.lox");
+
+/// Names the REPL binds its most recent results to, newest first.
+const RESULT_NAMES: [&str; 3] = ["_", "_2", "_3"];
+
+/// Compiles and runs Lox code against one interpreter, so definitions carry over from one
+/// run to the next.
 pub struct Runner {
     interpreter: Interpreter,
+    /// Values of the last expressions entered at the REPL, newest first.
+    results: Vec<Literal>,
+    repl_inputs: usize,
+    parser_options: ParserOptions,
+    /// Whether to lower sugar such as `for` loops before running code.
+    desugar: bool,
+    /// Whether top-level functions and classes are defined before a script starts running.
+    hoisting: bool,
+    /// Whether the constants a script opens with are worked out before it runs.
+    fold_constants: bool,
+    /// The most bytes a string literal in the code may take.
+    max_string_literal: usize,
+    modules: ModuleCache,
+    diagnostics: DiagnosticSink,
+}
+
+impl Default for Runner {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Runner {
     pub fn new() -> Runner {
-        Self {
-            interpreter: Interpreter::new(),
+        Self::with_interpreter(Interpreter::new())
+    }
+
+    /// Runs code in an interpreter configured through `Interpreter::builder`. The prelude
+    /// runs first unless the builder left it out.
+    pub fn with_interpreter(interpreter: Interpreter) -> Runner {
+        let mut runner = Self {
+            interpreter,
+            results: vec![],
+            repl_inputs: 0,
+            parser_options: ParserOptions::default(),
+            desugar: false,
+            hoisting: false,
+            fold_constants: false,
+            max_string_literal: lexer::DEFAULT_MAX_STRING_LITERAL,
+            modules: ModuleCache::new(),
+            diagnostics: DiagnosticSink::default(),
+        };
+        if runner.interpreter.prelude {
+            runner.run_prelude();
         }
+
+        runner
+    }
+
+    /// Runs the prelude, as `<prelude>`. It ships inside the binary, so failing to run it is
+    /// a bug in rlox rather than in a script, and panics with the error.
+    fn run_prelude(&mut self) {
+        if let Err(e) = self.run("<prelude>", PRELUDE) {
+            panic!("The prelude failed to run:\n{}", e);
+        }
+        if let Some(warning) = self.diagnostics.diagnostics().first() {
+            panic!(
+                "The prelude has warnings: {} {}",
+                warning.location, warning.message
+            );
+        }
+        // Scripts shouldn't be charged for the prelude's work.
+        self.interpreter.take_stats();
+        self.interpreter.refuel();
+    }
+
+    /// Restricts the code run afterwards to the parts of the language `language` allows.
+    /// Code using anything else fails to parse.
+    pub fn set_language(&mut self, language: LanguageOptions) {
+        self.parser_options.language = language;
+    }
+
+    /// Switches to the dialect where `print` is a function rather than a statement: code run
+    /// afterwards calls `print(a, b)`, which prints its arguments separated by spaces, and
+    /// `print a;` is a syntax error:
+    ///
+    /// ```
+    /// use rlox::Runner;
+    ///
+    /// let statement = "print \"a\";";
+    /// let call = "print(\"a\", 1, nil);";
+    ///
+    /// let mut runner = Runner::new();
+    /// assert!(runner.run("statement.lox", statement).is_ok());
+    /// assert!(runner.run("call.lox", call).is_err());
+    ///
+    /// let mut runner = Runner::new();
+    /// runner.set_print_is_function(true);
+    /// assert!(runner.run("statement.lox", statement).is_err());
+    /// assert!(runner.run("call.lox", call).is_ok());
+    /// ```
+    pub fn set_print_is_function(&mut self, enabled: bool) {
+        self.parser_options.print_is_function = enabled;
+        if enabled {
+            self.define_global(
+                "print",
+                Literal::Callable(LoxCallable::Other(Rc::new(PrintNative))),
+            );
+        }
+    }
+
+    /// Lowers `for` loops into `while` loops, with `rlox::lower_for_loops`, before running
+    /// code compiled afterwards. Either way the code does the same:
+    ///
+    /// ```
+    /// use rlox::{Interpreter, OutputBuffer, Runner};
+    ///
+    /// let output = OutputBuffer::new();
+    /// let interpreter = Interpreter::builder()
+    ///     .output(Box::new(output.clone()))
+    ///     .build();
+    /// let mut runner = Runner::with_interpreter(interpreter);
+    /// runner.set_desugar(true);
+    /// runner.run("loop.lox", "for (var i = 0; i < 3; i = i + 1) print i;").unwrap();
+    /// assert_eq!(output.contents(), "0\n1\n2\n");
+    /// ```
+    pub fn set_desugar(&mut self, enabled: bool) {
+        self.desugar = enabled;
+    }
+
+    /// Defines the functions and classes a script declares at the top level before the
+    /// script starts, so it can call them above their declarations. Reference Lox runs a
+    /// script strictly from top to bottom, and so does rlox unless this is enabled.
+    ///
+    /// ```
+    /// use rlox::Runner;
+    ///
+    /// let script = "print greet(); fun greet() { return \"hi\"; }";
+    ///
+    /// let mut runner = Runner::new();
+    /// assert!(runner.run("strict.lox", script).is_err());
+    ///
+    /// runner.set_hoisting(true);
+    /// assert!(runner.run("hoisted.lox", script).is_ok());
+    /// ```
+    ///
+    /// Each declaration still binds its name where it appears, so a variable of the same name
+    /// declared before it is overwritten there and one declared after it wins from then on. A
+    /// class whose superclass isn't a class hoisted along with it is defined where it appears,
+    /// as usual. Code run at the REPL or streamed with `run_streaming` isn't hoisted.
+    pub fn set_hoisting(&mut self, enabled: bool) {
+        self.hoisting = enabled;
+    }
+
+    /// Works out the values of the variables a script opens with before running it, when
+    /// their initializers use only literals, the variables before them and pure natives.
+    /// Scripts do the same either way; an initializer that fails is left to fail as the script
+    /// runs.
+    ///
+    /// ```
+    /// use rlox::Runner;
+    ///
+    /// let code = "var SECONDS_PER_DAY = 60 * 60 * 24;\nvar WEEK = SECONDS_PER_DAY * 7;\n";
+    ///
+    /// let mut runner = Runner::new();
+    /// runner.set_fold_constants(true);
+    /// let stmts = runner.lowered("constants.lox", code).unwrap();
+    /// assert_eq!(
+    ///     rlox::to_source(&stmts),
+    ///     "var SECONDS_PER_DAY = 86400;\nvar WEEK = 604800;\n"
+    /// );
+    /// ```
+    pub fn set_fold_constants(&mut self, enabled: bool) {
+        self.fold_constants = enabled;
+    }
+
+    /// Lets assignments to undefined names define them as globals, as the REPL does with
+    /// `--implicit-globals`. Only assignments outside any function do; scripts are strict by
+    /// default.
+    ///
+    /// ```
+    /// use rlox::Runner;
+    ///
+    /// let mut runner = Runner::new();
+    /// assert!(runner.run("strict.lox", "total = 1;").is_err());
+    ///
+    /// runner.set_implicit_globals(true);
+    /// runner.run("lenient.lox", "total = 1;").unwrap();
+    /// assert_eq!(runner.interpreter().get_global_number("total").unwrap(), 1.0);
+    /// ```
+    pub fn set_implicit_globals(&mut self, enabled: bool) {
+        self.interpreter.set_implicit_globals(enabled);
+    }
+
+    /// Lets some operations on operands of the wrong type go on with a warning instead of
+    /// failing the script, for quick scripts where a stand-in value beats stopping. See
+    /// `Strictness::Lenient` for which ones. Scripts are strict by default.
+    ///
+    /// ```
+    /// use rlox::Runner;
+    ///
+    /// let mut runner = Runner::new();
+    /// assert!(runner.run("strict.lox", "var total = nil + 5;").is_err());
+    ///
+    /// runner.set_lenient(true);
+    /// runner.run("lenient.lox", "var total = nil + 5;").unwrap();
+    /// assert_eq!(runner.interpreter().get_global_number("total").unwrap(), 5.0);
+    /// ```
+    pub fn set_lenient(&mut self, enabled: bool) {
+        let strictness = match enabled {
+            true => Strictness::Lenient,
+            false => Strictness::Strict,
+        };
+        self.interpreter.set_strictness(strictness);
+    }
+
+    /// Makes string literals longer than `bytes` between their quotes an error in the code
+    /// compiled afterwards. Without this they may take up to
+    /// `lexer::DEFAULT_MAX_STRING_LITERAL` bytes.
+    ///
+    /// ```
+    /// use rlox::{LoxError, Runner};
+    ///
+    /// let mut runner = Runner::new();
+    /// runner.set_max_string_literal(4);
+    /// assert!(runner.run("short.lox", "print \"four\";").is_ok());
+    /// match runner.run("long.lox", "print \"fives\";") {
+    ///     Err(LoxError::Lex(errors)) => assert_eq!(
+    ///         errors[0].message,
+    ///         "String literal is 5 bytes long, over the limit of 4 bytes. Load data this large \
+    ///          from a file instead."
+    ///     ),
+    ///     other => panic!("expected a lex error, got {:?}", other),
+    /// }
+    /// ```
+    pub fn set_max_string_literal(&mut self, bytes: usize) {
+        self.max_string_literal = bytes;
+    }
+
+    /// Decides which warnings and notes are kept from code compiled afterwards, whether they
+    /// are printed and whether warnings keep code from running.
+    pub fn set_diagnostic_options(&mut self, options: DiagnosticOptions) {
+        self.diagnostics = DiagnosticSink::new(options);
+    }
+
+    /// The warnings and notes kept from the code compiled so far.
+    pub fn diagnostics(&self) -> &DiagnosticSink {
+        &self.diagnostics
+    }
+
+    /// Defines the global `name` as a native that takes `arity` arguments and runs `fun`. The
+    /// native gets the interpreter, so it can call back into Lox with
+    /// `Interpreter::call_function`.
+    pub fn define_native(
+        &mut self,
+        name: &str,
+        arity: usize,
+        fun: impl Fn(&mut Interpreter, &[Literal]) -> Result<Literal, NativeError> + 'static,
+    ) {
+        self.interpreter.define_native(name, arity, fun);
+    }
+
+    /// Like `define_native`, for a native that only computes its result from its arguments.
+    /// See `Interpreter::define_pure_native`.
+    pub fn define_pure_native(
+        &mut self,
+        name: &str,
+        arity: usize,
+        fun: impl Fn(&mut Interpreter, &[Literal]) -> Result<Literal, NativeError> + 'static,
+    ) {
+        self.interpreter.define_pure_native(name, arity, fun);
+    }
+
+    /// Makes `value` available to the code run afterwards as the global `name`. The last
+    /// definition wins: defining a name again, from the host or with a top-level `var` in a
+    /// script, replaces the earlier value, constants included.
+    pub fn define_global(&mut self, name: &str, value: impl Into<Literal>) {
+        self.interpreter.define_global(name, value);
+    }
+
+    /// Defines the global `name` as a map holding `entries`, in order.
+    pub fn define_global_map<K, V>(&mut self, name: &str, entries: impl IntoIterator<Item = (K, V)>)
+    where
+        K: Into<String>,
+        V: Into<Literal>,
+    {
+        let mut map = LoxMap::new();
+        for (key, value) in entries {
+            map.insert(key.into().into(), value.into());
+        }
+
+        self.define_global(name, Literal::Map(Rc::new(RefCell::new(map))));
+    }
+
+    /// Ends the line the last script left unfinished, so that what comes next, such as the
+    /// REPL prompt, starts on a line of its own.
+    pub fn finish_output_line(&mut self) {
+        self.interpreter.finish_output_line();
+    }
+
+    /// What `help()` prints for the global `name`, or for a method when `name` is written as
+    /// `Class.method`, doc comment included. `None` if there is no such global or method.
+    ///
+    /// ```
+    /// use rlox::Runner;
+    ///
+    /// let mut runner = Runner::new();
+    /// runner
+    ///     .run("shapes.lox", "/// A square.\nclass Square {\n  /// Its area.\n  area() {}\n}")
+    ///     .unwrap();
+    /// assert_eq!(runner.describe("Square").unwrap(), "Square (arity 0), methods: area\nA square.");
+    /// assert_eq!(
+    ///     runner.describe("Square.area").unwrap(),
+    ///     "fun area(), declared at line 4\nIts area."
+    /// );
+    /// assert!(runner.describe("Circle").is_none());
+    /// ```
+    pub fn describe(&self, name: &str) -> Option<String> {
+        let (global, method) = match name.split_once('.') {
+            Some((class, method)) => (class, Some(method)),
+            None => (name, None),
+        };
+        let value = self.interpreter.get_global(global)?;
+
+        let value = match (method, value) {
+            (None, value) => value,
+            (Some(method), Literal::Callable(LoxCallable::Class(class))) => {
+                Literal::Callable(LoxCallable::Function(class.find_method(method)?))
+            }
+            (Some(_), _) => return None,
+        };
+        Some(callable::describe(&value))
+    }
+
+    /// The interpreter the code runs in, for inspecting what it left behind.
+    pub fn interpreter(&self) -> &Interpreter {
+        &self.interpreter
+    }
+
+    pub fn set_debug_hook(&mut self, hook: Box<dyn DebugHook>) {
+        self.interpreter.set_debug_hook(hook);
+    }
+
+    /// Hands over the interpreter's stats for the code run since they were last taken.
+    pub fn take_stats(&mut self) -> ExecStats {
+        self.interpreter.take_stats()
+    }
+
+    /// Runs `code`, reporting errors in it as coming from `name`.
+    pub fn run(&mut self, name: &str, code: &str) -> Result<(), LoxError> {
+        let file = self.interpreter.sources.add(name, code);
+        self.run_source(file)
+    }
+
+    /// Runs the script at `path` as a module, unless it was loaded before and hasn't changed
+    /// since. Returns whether it ran.
+    ///
+    /// Modules share the globals of everything else run here, so running a changed module
+    /// again replaces the globals it defines. Values taken from the old version before that,
+    /// such as one of its functions stored in another variable, keep the old code. If the
+    /// changed file fails to compile, the error is returned and the previous version stays in
+    /// effect; the next load tries the file again.
+    ///
+    /// ```
+    /// use std::fs;
+    ///
+    /// use rlox::Runner;
+    ///
+    /// let path = std::env::temp_dir().join(format!("rlox-load-{}.lox", std::process::id()));
+    /// fs::write(&path, "var version = 1;").unwrap();
+    ///
+    /// let mut runner = Runner::new();
+    /// assert!(runner.load(&path).unwrap());
+    /// assert!(!runner.load(&path).unwrap());
+    ///
+    /// // A write in the same tick as the last one may leave the modification time as it
+    /// // was, so say that the file changed.
+    /// fs::write(&path, "var version = 2;").unwrap();
+    /// runner.invalidate(&path);
+    /// assert!(runner.load(&path).unwrap());
+    /// assert_eq!(runner.interpreter().get_global_number("version").unwrap(), 2.0);
+    ///
+    /// fs::write(&path, "var version = ;").unwrap();
+    /// runner.invalidate(&path);
+    /// assert!(runner.load(&path).is_err());
+    /// assert_eq!(runner.interpreter().get_global_number("version").unwrap(), 2.0);
+    ///
+    /// fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn load(&mut self, path: &Path) -> Result<bool, LoxError> {
+        let path = path.canonicalize().map_err(|e| module_error(path, e))?;
+
+        let program = match self.modules.freshness(&path) {
+            Ok(Freshness::Fresh) => return Ok(false),
+            Ok(Freshness::Invalidated(program)) => program,
+            Ok(Freshness::Changed(code)) => {
+                let file = self
+                    .interpreter
+                    .sources
+                    .add(path.display().to_string(), &*code);
+                let program = Rc::new(self.compile(file, self.shadowing())?);
+                self.modules.store(path.clone(), Rc::clone(&program), &code);
+                program
+            }
+            Err(e) => return Err(module_error(&path, e)),
+        };
+
+        log_event!("loading module {}", path.display());
+        self.modules.mark_fresh(&path);
+        let result = self.execute(&program);
+        if result.is_err() {
+            // A module that stopped part way runs in full on the next load.
+            self.modules.invalidate(&path);
+        }
+
+        result.map(|()| true)
+    }
+
+    /// Makes the next `load` of `path` run the module even if the file hasn't changed.
+    /// Returns whether the module had been loaded.
+    pub fn invalidate(&mut self, path: &Path) -> bool {
+        path.canonicalize()
+            .is_ok_and(|path| self.modules.invalidate(&path))
+    }
+
+    /// The canonical paths of every module loaded so far, sorted.
+    pub fn modules(&self) -> Vec<PathBuf> {
+        let mut paths = self.modules.paths();
+        paths.sort();
+        paths
+    }
+
+    /// Runs the script `reader` yields while it is still being read, reporting errors in it as
+    /// coming from `name`. Each top-level declaration runs as soon as it has been parsed, and
+    /// neither the source nor the parsed program is kept, so a script of any size starts
+    /// printing right away and runs in the memory its largest declaration needs.
+    ///
+    /// Unlike `run`, which runs nothing unless the whole script compiles, this stops at the
+    /// first lexing, parsing or resolving error with everything before it already run.
+    /// Runtime errors can't quote the source either, though they still say where they
+    /// happened.
+    ///
+    /// ```
+    /// use rlox::{Interpreter, OutputBuffer, Runner};
+    ///
+    /// let output = OutputBuffer::new();
+    /// let interpreter = Interpreter::builder()
+    ///     .output(Box::new(output.clone()))
+    ///     .build();
+    /// let mut runner = Runner::with_interpreter(interpreter);
+    ///
+    /// let script = "print 1;\nprint 2;\nprint 3 +;\nprint 4;\n";
+    /// let error = runner.run_streaming("script.lox", script.as_bytes()).unwrap_err();
+    /// assert!(error.to_string().contains("script.lox:3:10"));
+    /// assert_eq!(output.contents(), "1\n2\n");
+    /// ```
+    pub fn run_streaming(&mut self, name: &str, reader: impl BufRead) -> Result<(), LoxError> {
+        let file = self.interpreter.sources.add(name, "");
+        // The parser and resolver only need names to locate errors, and taking a copy of them
+        // leaves the interpreter free to run each declaration.
+        let sources = self.interpreter.sources.names_only();
+        // The first source that couldn't be read or lexed. The tokens end there.
+        let failure = RefCell::new(None);
+        let tokens = lexer::tokenize_reader(reader, file).map_while(|token| {
+            let error = match token {
+                Ok(token) => match lexer::error_message(&token) {
+                    None => return Some(token),
+                    Some(message) => LoxError::Lex(vec![Diagnostic::error(
+                        "lex",
+                        sources.location(token.span()),
+                        token.line,
+                        token.column,
+                        message,
+                    )]),
+                },
+                Err(e) => LoxError::Io(e),
+            };
+            failure.replace(Some(error));
+            None
+        });
+
+        self.diagnostics.start();
+        log_event!("streaming script {}", name);
+        let mut declarations = parser::Declarations::new(tokens, &sources, self.parser_options);
+        self.as_script(|runner| loop {
+            let declaration = declarations.next();
+            for warning in declarations.take_warnings() {
+                runner.diagnostics.report(warning);
+            }
+
+            let mut stmts = match declaration {
+                Some(Ok(stmt)) => vec![stmt],
+                // Whatever the parser made of the tokens cut short, the unreadable source is
+                // what went wrong.
+                Some(Err(e)) => return Err(failure.take().unwrap_or(e)),
+                None => match failure.take() {
+                    Some(e) => return Err(e),
+                    None => return Ok(()),
+                },
+            };
+            if runner.desugar {
+                stmts = lower::lower_for_loops(stmts);
+            }
+            let shadowing = runner.shadowing();
+            resolver::resolve(&mut stmts, &sources, shadowing, &mut runner.diagnostics)?;
+            runner.diagnostics.check_denied()?;
+
+            for stmt in &stmts {
+                runner.execute_statement(stmt)?;
+            }
+            // Output appears as the script runs rather than once it is done.
+            runner.interpreter.flush_output();
+        })?;
+
+        log_event!("script finished");
+        Ok(())
+    }
+
+    /// Runs a program built in Rust, such as with the `Stmt` and `Expr` constructors or read
+    /// with `from_sexpr`, instead of parsed from source. It is resolved first, like parsed
+    /// code, and shares the globals of everything else run here.
+    pub fn run_program(&mut self, mut stmts: Vec<Stmt>) -> Result<(), LoxError> {
+        self.diagnostics.start();
+        resolver::resolve(
+            &mut stmts,
+            &self.interpreter.sources,
+            self.shadowing(),
+            &mut self.diagnostics,
+        )?;
+        self.diagnostics.check_denied()?;
+        self.execute(&stmts)
+    }
+
+    /// The statements `run` would execute for `code`, after the passes enabled here, such as
+    /// desugaring and constant folding. Nothing is run.
+    pub fn lowered(&mut self, name: &str, code: &str) -> Result<Vec<Stmt>, LoxError> {
+        let file = self.interpreter.sources.add(name, code);
+        self.compile(file, self.shadowing())
+    }
+
+    fn run_source(&mut self, file: FileId) -> Result<(), LoxError> {
+        let stmts = self.compile(file, self.shadowing())?;
+        self.execute(&stmts)
     }
 
-    pub fn run(&mut self, code: &str) -> Result<(), LoxError> {
-        // let tokens = lexer::tokenize(content);
-        //
-        // for token in tokens {
-        //     println!("{:?}", token);
-        // }
+    /// Names the next input typed at the REPL. Each input is its own pseudo-file, so
+    /// locations in it start from line 1.
+    pub fn next_repl_name(&mut self) -> String {
+        self.repl_inputs += 1;
+        format!("<repl-{}>", self.repl_inputs)
+    }
+
+    /// Runs `code` like `run`, then reports how long parsing and execution took and the
+    /// interpreter's stats for the run, as a `timing` note.
+    pub fn run_timed(&mut self, name: &str, code: &str) -> Result<(), LoxError> {
+        let file = self.interpreter.sources.add(name, code);
+
+        let start = Instant::now();
+        let stmts = self.compile(file, self.shadowing())?;
+        let parse_time = start.elapsed();
 
-        let tokens = lexer::tokenize(code);
+        self.interpreter.take_stats();
+        let start = Instant::now();
+        let result = self.execute(&stmts);
+        let execution_time = start.elapsed();
+
+        let stats = self.interpreter.take_stats();
+        self.diagnostics.report(Diagnostic::note(
+            "timing",
+            name.to_string(),
+            0,
+            0,
+            format_timing(parse_time, execution_time, &stats),
+        ));
+
+        result
+    }
 
-        let stmts = parser::parse(tokens);
+    /// Warns about shadowed bindings in scripts, except for the names the language options
+    /// allow.
+    fn shadowing(&self) -> Shadowing {
+        Shadowing::Warn {
+            allow: self.parser_options.language.allow_shadowing,
+        }
+    }
+
+    fn compile(&mut self, file: FileId, shadowing: Shadowing) -> Result<Vec<Stmt>, LoxError> {
+        self.diagnostics.start();
+        let tokens = lex(&self.interpreter.sources, file, self.max_string_literal)?;
+        let mut stmts = parser::parse(
+            tokens.into_iter(),
+            &self.interpreter.sources,
+            self.parser_options,
+            &mut self.diagnostics,
+        )?;
+        if self.desugar {
+            stmts = lower::lower_for_loops(stmts);
+        }
         // let printed_ast = ast::pretty_print(&expr);
         // println!("{}", printed_ast);
 
-        for stmt in stmts {
-            if let Err(e) = self.interpreter.execute(&stmt) {
-                match e {
-                    InterpreterErrorKind::General(s) => {
-                        eprintln!("Interpreter Error: {}", s);
-                        return Err(LoxError {
-                            error_type: LoxErrorType::RuntimeError,
-                            line: 0,
-                        });
-                    }
-                    _ => (),
+        resolver::resolve(
+            &mut stmts,
+            &self.interpreter.sources,
+            shadowing,
+            &mut self.diagnostics,
+        )?;
+        self.diagnostics.check_denied()?;
+        if self.fold_constants {
+            optimizer::fold_global_constants(&mut stmts, &mut self.interpreter);
+        }
+
+        Ok(stmts)
+    }
+
+    fn execute(&mut self, stmts: &[Stmt]) -> Result<(), LoxError> {
+        log_event!("running script with {} statements", stmts.len());
+
+        self.as_script(|runner| {
+            let mut hoisted = match runner.hoisting {
+                true => runner.hoist(stmts)?,
+                false => vec![],
+            }
+            .into_iter();
+            for stmt in stmts {
+                match hoisted.next().flatten() {
+                    Some(value) => runner.interpreter.define_hoisted(stmt, value),
+                    None => runner.execute_statement(stmt)?,
                 }
-            };
+            }
+            Ok(())
+        })?;
+
+        log_event!("script finished");
+        Ok(())
+    }
+
+    /// Defines the top-level functions and classes in `stmts` ahead of running them.
+    fn hoist(&mut self, stmts: &[Stmt]) -> Result<Vec<Option<Literal>>, LoxError> {
+        self.interpreter.hoist(stmts).map_err(|e| {
+            let message = e.message().unwrap_or("Couldn't hoist a declaration.");
+            self.runtime_error(message, e.span())
+        })
+    }
+
+    /// Runs `run` as a script of its own: what it defers at the top level runs once it is
+    /// done, whether it succeeded or not. An error raised by a deferred statement fails the
+    /// script if nothing else did, and is reported as a diagnostic if something did.
+    fn as_script<T>(
+        &mut self,
+        run: impl FnOnce(&mut Self) -> Result<T, LoxError>,
+    ) -> Result<T, LoxError> {
+        self.interpreter.enter_defer_scope();
+        let result = run(self);
+        let deferred = self.interpreter.exit_defer_scope(result.is_err());
+        for warning in self.interpreter.take_warnings() {
+            self.diagnostics.report(warning);
+        }
+        self.interpreter.flush_output();
+
+        match deferred {
+            Err(e) => match e.message() {
+                Some(message) => Err(self.runtime_error(message, e.span())),
+                None => result,
+            },
+            Ok(()) => result,
+        }
+    }
+
+    fn execute_statement(&mut self, stmt: &Stmt) -> Result<(), LoxError> {
+        let result = self.interpreter.execute(stmt);
+        for warning in self.interpreter.take_warnings() {
+            self.diagnostics.report(warning);
+        }
+
+        if let Err(e) = result {
+            if let Some(message) = e.message() {
+                log_event!("script stopped by runtime error: {}", message);
+                return Err(self.runtime_error(message, e.span()));
+            }
+        }
+
+        // A denied warning raised while running stops the script there.
+        self.diagnostics.check_denied()
+    }
+
+    /// Describes a runtime error along with the calls that were in progress when it happened.
+    fn runtime_error(&mut self, message: &str, span: Option<Span>) -> LoxError {
+        // The caller reports the error on stderr, after what the script printed so far.
+        self.interpreter.flush_output();
+        let trace = self.interpreter.take_error_trace();
+        let sources = &self.interpreter.sources;
+        let position = span.or_else(|| trace.first().map(|(_, span)| *span));
+
+        LoxError::Runtime(RuntimeDiagnostic {
+            message: message.to_string(),
+            location: span.map(|span| sources.location(span)),
+            line: position.map_or(0, |span| span.line),
+            column: position.map_or(0, |span| span.column),
+            trace: trace
+                .iter()
+                .map(|(function, span)| TraceFrame {
+                    function: function.clone(),
+                    location: sources.location(*span),
+                })
+                .collect(),
+        })
+    }
+
+    /// Runs a line typed at the REPL. A line holding a single expression, with or without
+    /// its semicolon, has its value echoed and bound to `_`, while the previous two results
+    /// move to `_2` and `_3`. Otherwise each declaration on the line echoes what it bound,
+    /// such as `x = 42`.
+    ///
+    /// An input that fails leaves the globals as they were before it: whatever it defined is
+    /// removed and whatever it assigned gets its old value back.
+    pub fn run_repl_line(&mut self, line: &str) -> Result<(), LoxError> {
+        let name = self.next_repl_name();
+        let file = self.interpreter.sources.add(name, line);
+        let source = line.trim_end().trim_end_matches(';');
+
+        self.all_or_nothing(|runner| {
+            runner.diagnostics.start();
+            match parser::parse_expression(
+                lexer::tokenize_with_limit(source, file, runner.max_string_literal),
+                &runner.interpreter.sources,
+                runner.parser_options,
+                &mut runner.diagnostics,
+            ) {
+                Ok(expr) => runner.run_expression(expr, false),
+                Err(_) => runner.run_repl_statements(file),
+            }
+        })
+    }
+
+    /// Runs the expression after a `:pp` at the REPL, echoing its value the way
+    /// `prettyPrint()` would. The value is kept in `_` like any other result.
+    pub fn run_repl_pretty(&mut self, code: &str) -> Result<(), LoxError> {
+        let name = self.next_repl_name();
+        let file = self.interpreter.sources.add(name, code);
+        let source = code.trim_end().trim_end_matches(';');
+
+        let tokens = lexer::tokenize_with_limit(source, file, self.max_string_literal);
+        self.diagnostics.start();
+        match parser::parse_expression(
+            tokens,
+            &self.interpreter.sources,
+            self.parser_options,
+            &mut self.diagnostics,
+        ) {
+            Ok(expr) => self.all_or_nothing(|runner| runner.run_expression(expr, true)),
+            Err(message) => Err(LoxError::Parse(vec![Diagnostic::error(
+                "syntax",
+                self.interpreter.sources.name(file).to_string(),
+                1,
+                1,
+                format!(":pp needs an expression: {}", message),
+            )])),
+        }
+    }
+
+    /// Runs the statements typed at the REPL, echoing each binding a declaration made.
+    fn run_repl_statements(&mut self, file: FileId) -> Result<(), LoxError> {
+        let stmts = self.compile(file, Shadowing::Ignore)?;
+
+        self.as_script(|runner| {
+            for stmt in &stmts {
+                runner.execute_statement(stmt)?;
+                for name in declared_names(stmt) {
+                    runner.echo_binding(&name.lexeme)?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Echoes the global `name` and its value, on a line of its own.
+    fn echo_binding(&mut self, name: &str) -> Result<(), LoxError> {
+        let Some(value) = self.interpreter.get_global(name) else {
+            return Ok(());
+        };
+
+        self.interpreter.finish_output_line();
+        let echo = format!("{} = {}", name, value);
+        self.interpreter
+            .print(&echo)
+            .map_err(|e| self.runtime_error(e.message().unwrap_or_default(), None))
+    }
+
+    /// Runs `run` with the globals journaled, rolling back its changes to them if it fails.
+    fn all_or_nothing(
+        &mut self,
+        run: impl FnOnce(&mut Self) -> Result<(), LoxError>,
+    ) -> Result<(), LoxError> {
+        self.interpreter.globals.borrow_mut().start_journal();
+        let result = run(self);
+
+        let mut globals = self.interpreter.globals.borrow_mut();
+        match result {
+            Ok(()) => globals.commit(),
+            Err(_) => globals.roll_back(),
+        }
+
+        result
+    }
+
+    /// Evaluates `code` as a single expression in the global scope and hands back its value.
+    /// Anything else, a trailing `;` included, is a syntax error, reported as coming from
+    /// `name`.
+    ///
+    /// ```
+    /// use rlox::Runner;
+    ///
+    /// let mut runner = Runner::new();
+    /// runner.define_global("width", 3.0);
+    /// assert_eq!(runner.evaluate("area", "width * 2").unwrap().to_string(), "6");
+    /// assert!(runner.evaluate("area", "width * 2;").is_err());
+    /// ```
+    pub fn evaluate(&mut self, name: &str, code: &str) -> Result<Literal, LoxError> {
+        let file = self.interpreter.sources.add(name, code);
+        let tokens = lex(&self.interpreter.sources, file, self.max_string_literal)?;
+        self.diagnostics.start();
+        let expr = parser::parse_standalone_expression(
+            tokens.into_iter(),
+            &self.interpreter.sources,
+            self.parser_options,
+            &mut self.diagnostics,
+        )?;
+
+        let value = self.evaluate_expression(expr)?;
+        self.interpreter.flush_output();
+        Ok(value)
+    }
+
+    fn evaluate_expression(&mut self, mut expr: Expr) -> Result<Literal, LoxError> {
+        resolver::resolve_expression(&mut expr, &self.interpreter.sources, &mut self.diagnostics)?;
+        self.diagnostics.check_denied()?;
+
+        // A `match` arm's block can defer statements too.
+        self.as_script(
+            |runner| match runner.interpreter.evaluate_top_level(&expr) {
+                Ok(value) => Ok(value),
+                Err(e) => match e.message() {
+                    Some(message) => Err(runner.runtime_error(message, e.span())),
+                    None => Ok(Literal::Nil),
+                },
+            },
+        )
+    }
+
+    fn run_expression(&mut self, expr: Expr, pretty: bool) -> Result<(), LoxError> {
+        let value = self.evaluate_expression(expr)?;
+
+        // The echo goes on a line of its own even if the expression wrote part of one.
+        self.interpreter.finish_output_line();
+        let echoed = if pretty {
+            self.interpreter
+                .print(&pretty::pretty(&value, pretty::DEFAULT_MAX_DEPTH))
+        } else {
+            self.interpreter.print(&value)
+        };
+        if let Err(e) = echoed {
+            return Err(self.runtime_error(e.message().unwrap_or_default(), None));
+        }
+        self.interpreter.flush_output();
+
+        self.results.insert(0, value);
+        self.results.truncate(RESULT_NAMES.len());
+
+        // Defining afresh each time keeps the names working even if a script redeclared them.
+        for (name, value) in RESULT_NAMES.iter().zip(&self.results) {
+            self.interpreter.define_global(name, value.clone());
         }
 
         Ok(())
     }
 }
+
+/// The names a declaration binds, in order, or none for any other statement.
+fn declared_names(stmt: &Stmt) -> &[Token] {
+    match &stmt.kind {
+        StmtKind::Var(name, _)
+        | StmtKind::Const(name, _)
+        | StmtKind::Function(name, ..)
+        | StmtKind::Class(name, ..)
+        | StmtKind::Enum(name, _) => std::slice::from_ref(name),
+        StmtKind::Unpack(names, _) => names,
+        _ => &[],
+    }
+}
+
+/// An error reading the module at `path`, naming the file.
+fn module_error(path: &Path, e: io::Error) -> LoxError {
+    let message = format!("Could not load module '{}': {}", path.display(), e);
+    LoxError::Io(io::Error::new(e.kind(), message))
+}
+
+/// Tokenizes the source registered as `file`, reporting every token the lexer couldn't make
+/// sense of. If there were any, the source is rejected as a whole.
+pub fn lex(
+    sources: &SourceMap,
+    file: FileId,
+    max_string_literal: usize,
+) -> Result<Vec<Token>, LoxError> {
+    let tokens: Vec<Token> =
+        lexer::tokenize_with_limit(sources.source(file), file, max_string_literal).collect();
+
+    let diagnostics: Vec<Diagnostic> = tokens
+        .iter()
+        .filter_map(|token| {
+            lexer::error_message(token).map(|message| {
+                Diagnostic::error(
+                    "lex",
+                    sources.location(token.span()),
+                    token.line,
+                    token.column,
+                    message.to_string(),
+                )
+            })
+        })
+        .collect();
+
+    if diagnostics.is_empty() {
+        Ok(tokens)
+    } else {
+        Err(LoxError::Lex(diagnostics))
+    }
+}
+
+/// Formats the report printed by `Runner::run_timed`, with millisecond precision.
+fn format_timing(parse_time: Duration, execution_time: Duration, stats: &ExecStats) -> String {
+    format!(
+        "parse: {:.3} ms, execution: {:.3} ms\n{}",
+        parse_time.as_secs_f64() * 1000.0,
+        execution_time.as_secs_f64() * 1000.0,
+        stats
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timings_have_millisecond_precision() {
+        let stats = ExecStats {
+            statements: 12,
+            expressions: 30,
+            calls: 4,
+            max_call_depth: 2,
+            environments: 5,
+        };
+        assert_eq!(
+            format_timing(
+                Duration::from_micros(1_500),
+                Duration::from_nanos(2_000_400),
+                &stats
+            ),
+            "parse: 1.500 ms, execution: 2.000 ms\n\
+             statements: 12, expressions: 30, calls: 4, max call depth: 2, environments: 5"
+        );
+    }
+
+    #[test]
+    fn long_runs_stay_in_milliseconds() {
+        assert!(format_timing(
+            Duration::ZERO,
+            Duration::from_secs(3),
+            &ExecStats::default()
+        )
+        .starts_with("parse: 0.000 ms, execution: 3000.000 ms\n"));
+    }
+}
@@ -0,0 +1,98 @@
+use crate::lexer::Span;
+
+/// Identifies one of the sources registered with a `SourceMap`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FileId(usize);
+
+impl FileId {
+    /// The pseudo-file of code built in Rust rather than parsed from source, such as the
+    /// statements passed to `Runner::run_program`. It has no text, and locations in it are
+    /// rendered as just `<generated>`.
+    pub const GENERATED: FileId = FileId(usize::MAX);
+}
+
+struct SourceFile {
+    name: String,
+    source: String,
+}
+
+/// Every piece of source code loaded so far, so spans can be traced back to where they came
+/// from long after the code was parsed.
+#[derive(Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, name: impl Into<String>, source: impl Into<String>) -> FileId {
+        self.files.push(SourceFile {
+            name: name.into(),
+            source: source.into(),
+        });
+        FileId(self.files.len() - 1)
+    }
+
+    /// A copy holding only the names of the sources, which is enough to locate spans but not
+    /// to quote them.
+    pub fn names_only(&self) -> SourceMap {
+        let files = self
+            .files
+            .iter()
+            .map(|file| SourceFile {
+                name: file.name.clone(),
+                source: String::new(),
+            })
+            .collect();
+
+        SourceMap { files }
+    }
+
+    pub fn name(&self, file: FileId) -> &str {
+        if file == FileId::GENERATED {
+            return "<generated>";
+        }
+        self.files.get(file.0).map_or("<unknown>", |f| &f.name)
+    }
+
+    pub fn source(&self, file: FileId) -> &str {
+        self.files.get(file.0).map_or("", |f| &f.source)
+    }
+
+    /// The source text `span` covers, on one line and shortened if it is long, for quoting in
+    /// error messages. It is empty if the text wasn't kept, as for a streamed script.
+    pub fn snippet(&self, span: Span) -> String {
+        const MAX_CHARS: usize = 60;
+
+        let text = self
+            .source(span.file)
+            .get(span.start..span.end)
+            .unwrap_or_default();
+        // Only as much as is quoted is collapsed, since the span may cover megabytes.
+        let collapsed: Vec<char> = text
+            .split_whitespace()
+            .enumerate()
+            .flat_map(|(i, word)| (i > 0).then_some(' ').into_iter().chain(word.chars()))
+            .take(MAX_CHARS + 1)
+            .collect();
+
+        if collapsed.len() > MAX_CHARS {
+            let shortened: String = collapsed[..MAX_CHARS - 3].iter().collect();
+            format!("{}...", shortened)
+        } else {
+            collapsed.into_iter().collect()
+        }
+    }
+
+    /// Renders where `span` starts as `name:line:column`, or just the name for generated code,
+    /// which has no lines.
+    pub fn location(&self, span: Span) -> String {
+        if span.file == FileId::GENERATED {
+            return self.name(span.file).to_string();
+        }
+        format!("{}:{}:{}", self.name(span.file), span.line, span.column)
+    }
+}
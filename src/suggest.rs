@@ -0,0 +1,79 @@
+//! "Did you mean" suggestions for misspelled names.
+
+/// The number of single-character insertions, deletions and substitutions that turn `a`
+/// into `b`, counting characters rather than bytes.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    // The distances from the prefix of `a` seen so far to each prefix of `b`.
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &b_char) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a_char != b_char);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+/// The candidate closest to `name`, if any is close enough to be a likely typo: at most a
+/// third of `name`'s length away, and at least one edit. Ties go to the earliest candidate.
+pub fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let limit = (name.chars().count() / 3).max(1);
+
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (levenshtein(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levenshtein_counts_edits() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("foo", "foo"), 0);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("abc", ""), 3);
+        assert_eq!(levenshtein("fo", "foo"), 1);
+        assert_eq!(levenshtein("foo", "fo"), 1);
+        assert_eq!(levenshtein("cat", "cut"), 1);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("flaw", "lawn"), 2);
+    }
+
+    #[test]
+    fn levenshtein_counts_characters_not_bytes() {
+        assert_eq!(levenshtein("héllo", "hello"), 1);
+        assert_eq!(levenshtein("日本", "日本語"), 1);
+    }
+
+    #[test]
+    fn closest_picks_the_nearest_likely_typo() {
+        assert_eq!(closest("fo", ["bar", "foo"]), Some("foo"));
+        assert_eq!(
+            closest("count", ["counter", "cont", "amount"]),
+            Some("cont")
+        );
+        assert_eq!(closest("lenght", ["length", "height"]), Some("length"));
+        // Ties go to the first candidate.
+        assert_eq!(closest("ab", ["ac", "bb"]), Some("ac"));
+    }
+
+    #[test]
+    fn closest_rejects_distant_and_identical_names() {
+        assert_eq!(closest("x", ["x"]), None);
+        assert_eq!(closest("foo", ["bar", "baz"]), None);
+        assert_eq!(closest("total", ["tally"]), None);
+        assert_eq!(closest("a", std::iter::empty()), None);
+    }
+}
@@ -0,0 +1,548 @@
+//! Helpers for testing Lox code from Rust, built with the `testing` feature.
+//!
+//! ```
+//! use rlox::testing::{assert_output, assert_runtime_error, run_lox};
+//!
+//! assert_output("print 1 + 2;\nprint \"done\";", &["3", "done"]);
+//! assert_runtime_error("print 1 + nil;", "Operands must be");
+//!
+//! let result = run_lox("var total = 0;\nfor (var i = 1; i <= 3; i = i + 1) total = total + i;");
+//! assert!(result.error.is_none());
+//! assert_eq!(result.global("total").unwrap().to_string(), "6");
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::{
+    ast::{
+        pattern::{ArmBody, MatchArm, Pattern, PatternKind},
+        transform::{walk_expr, Fold},
+    },
+    BinOp, Diagnostic, DiagnosticOptions, Expr, ExprKind, Interpreter, Literal, LogOp, LoxError,
+    OutputBuffer, Runner, Span, Stmt, StmtKind, Token, TokenKind, UnOp,
+};
+
+/// What running a script left behind.
+#[derive(Debug)]
+pub struct RunResult {
+    /// Everything the script printed, a line at a time. An unfinished last line is included.
+    pub output: Vec<String>,
+    /// The globals the script defined, by name. Natives are left out unless the script
+    /// redefined them.
+    pub globals: BTreeMap<String, Literal>,
+    /// The warnings and notes reported along the way.
+    pub diagnostics: Vec<Diagnostic>,
+    /// Why the script failed, if it did.
+    pub error: Option<LoxError>,
+}
+
+impl RunResult {
+    pub fn global(&self, name: &str) -> Option<&Literal> {
+        self.globals.get(name)
+    }
+}
+
+/// Runs `source` as `test.lox` in a fresh interpreter, capturing what it printed instead of
+/// writing it to stdout. Diagnostics are kept rather than printed.
+///
+/// The interpreter is deterministic, so scripts print the same on every run: the clock is
+/// frozen, `random()` is seeded with 0 and `readLine()` has no input to read.
+pub fn run_lox(source: &str) -> RunResult {
+    let (mut runner, output) = test_runner();
+
+    let natives = snapshot(&runner);
+    let error = runner.run("test.lox", source).err();
+
+    let globals = snapshot(&runner)
+        .into_iter()
+        .filter(|(name, value)| match natives.get(name) {
+            Some(native) => !native.is_same(value),
+            None => true,
+        })
+        .collect();
+
+    RunResult {
+        output: output.contents().lines().map(String::from).collect(),
+        globals,
+        diagnostics: runner.diagnostics().diagnostics().to_vec(),
+        error,
+    }
+}
+
+/// Runs `source` the way `run_lox` does and returns everything it printed, exactly as it
+/// was written, or the error it failed with.
+pub fn run_output(source: &str) -> Result<String, LoxError> {
+    let (mut runner, output) = test_runner();
+    runner.run("test.lox", source)?;
+    Ok(output.contents())
+}
+
+/// The syntax error `source` fails with, as the command line would print it.
+#[track_caller]
+pub fn syntax_error(source: &str) -> String {
+    match run_output(source) {
+        Err(error @ LoxError::Parse(_)) => error.to_string(),
+        other => panic!("expected a syntax error for {:?}, got {:?}", source, other),
+    }
+}
+
+/// The runtime error `source` fails with: its location and message, without the stack
+/// trace.
+#[track_caller]
+pub fn runtime_error(source: &str) -> String {
+    match run_output(source) {
+        Err(LoxError::Runtime(diagnostic)) => match diagnostic.location {
+            Some(location) => format!("{} {}", location, diagnostic.message),
+            None => diagnostic.message,
+        },
+        other => panic!("expected a runtime error for {:?}, got {:?}", source, other),
+    }
+}
+
+/// A runner for `run_lox` and `run_output`, and the buffer its output goes to.
+fn test_runner() -> (Runner, OutputBuffer) {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .frozen_time()
+        .seed(0)
+        .inputs(vec![])
+        .output(Box::new(output.clone()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.set_diagnostic_options(DiagnosticOptions {
+        print: None,
+        ..DiagnosticOptions::default()
+    });
+
+    (runner, output)
+}
+
+fn snapshot(runner: &Runner) -> BTreeMap<String, Literal> {
+    let globals = runner.interpreter().globals();
+    globals
+        .names()
+        .into_iter()
+        .filter_map(|name| Some((name.clone(), globals.get(&name)?)))
+        .collect()
+}
+
+/// Runs `source` and checks that it succeeds, printing exactly `expected`, one line each.
+#[track_caller]
+pub fn assert_output(source: &str, expected: &[&str]) {
+    let result = run_lox(source);
+    if let Some(error) = &result.error {
+        panic!(
+            "expected {:?} to run, but it failed with:\n{}",
+            source, error
+        );
+    }
+    assert_eq!(result.output, expected, "output of {:?}", source);
+}
+
+/// Runs `source` and checks that it fails at runtime with a message containing `message`.
+#[track_caller]
+pub fn assert_runtime_error(source: &str, message: &str) {
+    match run_lox(source).error {
+        Some(LoxError::Runtime(diagnostic)) => assert!(
+            diagnostic.message.contains(message),
+            "expected the runtime error of {:?} to mention {:?}, but it was {:?}",
+            source,
+            message,
+            diagnostic.message
+        ),
+        other => panic!(
+            "expected {:?} to fail at runtime, but got {:?}",
+            source, other
+        ),
+    }
+}
+
+/// Builds random syntax trees, for checking that code which takes programs apart and puts
+/// them back together, such as `to_source`, keeps them intact. The same seed always gives the
+/// same program.
+///
+/// Everything generated is something the parser could have produced, apart from the
+/// parentheses precedence calls for, so compare trees with `without_groupings` applied to
+/// both. Names are drawn from small pools, so most programs wouldn't resolve or run; they are
+/// only meant to be well-formed. A new kind of node belongs here too, so that it gets
+/// round-tripped along with the rest.
+///
+/// ```
+/// use rlox::testing::{without_groupings, AstGenerator};
+///
+/// let program = AstGenerator::new(7).program();
+/// let reparsed = rlox::parse("generated.lox", &rlox::to_source(&program)).unwrap();
+/// assert_eq!(without_groupings(reparsed), without_groupings(program));
+/// ```
+pub struct AstGenerator {
+    rng: fastrand::Rng,
+    max_depth: usize,
+}
+
+const NAMES: &[&str] = &[
+    "a", "b", "count", "total", "item", "value", "node", "left", "right", "Point", "Shape",
+];
+const VARIANTS: &[&str] = &["Red", "Green", "Blue", "North", "South"];
+const WORDS: &[&str] = &[
+    "",
+    "hello",
+    "a b",
+    "Lox",
+    "x + 1",
+    "{}",
+    "héllo",
+    "tab\there",
+];
+const DOCS: &[&str] = &["Adds two numbers.", "Returns nil.", "", "Example: `f(1)`."];
+
+impl AstGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: fastrand::Rng::with_seed(seed),
+            max_depth: 4,
+        }
+    }
+
+    /// Sets how deeply statements and expressions can nest, 4 by default. Programs grow
+    /// quickly with it.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// A program of up to six declarations.
+    pub fn program(&mut self) -> Vec<Stmt> {
+        let depth = self.max_depth;
+        (0..self.rng.usize(1..=6))
+            .map(|_| self.declaration(depth))
+            .collect()
+    }
+
+    fn name(&mut self) -> Token {
+        Token::identifier(NAMES[self.rng.usize(..NAMES.len())])
+    }
+
+    fn names(&mut self, most: usize) -> Vec<Token> {
+        (0..self.rng.usize(..=most)).map(|_| self.name()).collect()
+    }
+
+    fn doc(&mut self) -> Option<String> {
+        if self.rng.bool() {
+            return None;
+        }
+        // Blank lines are kept inside a doc comment, but not at its ends.
+        let lines = match self.rng.usize(..3) {
+            0 => vec![DOCS[0]],
+            1 => vec![DOCS[1], DOCS[3]],
+            _ => vec![DOCS[0], DOCS[2], DOCS[3]],
+        };
+        Some(lines.join("\n"))
+    }
+
+    fn declaration(&mut self, depth: usize) -> Stmt {
+        if depth == 0 {
+            return self.statement(depth);
+        }
+
+        let kind = match self.rng.usize(..12) {
+            0 => StmtKind::Var(self.name(), self.rng.bool().then(|| self.expr(depth - 1))),
+            1 => StmtKind::Const(self.name(), self.expr(depth - 1)),
+            2 => self.function(depth - 1),
+            3 => {
+                let superclass = self
+                    .rng
+                    .bool()
+                    .then(|| Expr::generated(ExprKind::Variable(self.name(), None)));
+                let members = (0..self.rng.usize(..=3))
+                    .map(|_| match self.rng.bool() {
+                        true => Stmt::generated(StmtKind::Var(
+                            self.name(),
+                            self.rng.bool().then(|| self.expr(depth - 1)),
+                        )),
+                        false => Stmt::generated(self.function(depth - 1)),
+                    })
+                    .collect();
+                StmtKind::Class(self.name(), superclass, members, self.doc())
+            }
+            4 => {
+                let methods = (0..self.rng.usize(..=2))
+                    .map(|_| Stmt::generated(self.function(depth - 1)))
+                    .collect();
+                StmtKind::Extend(Expr::variable(&self.name().lexeme), methods)
+            }
+            5 => {
+                let mut variants = VARIANTS.to_vec();
+                self.rng.shuffle(&mut variants);
+                variants.truncate(self.rng.usize(..=3));
+                let variants = variants.into_iter().map(Token::identifier).collect();
+                StmtKind::Enum(self.name(), variants)
+            }
+            6 => {
+                let names = (0..self.rng.usize(2..=3)).map(|_| self.name()).collect();
+                StmtKind::Unpack(names, self.expr(depth - 1))
+            }
+            _ => return self.statement(depth),
+        };
+        Stmt::generated(kind)
+    }
+
+    fn function(&mut self, depth: usize) -> StmtKind {
+        let body = self.declarations(depth);
+        StmtKind::Function(self.name(), self.names(3), body, self.doc())
+    }
+
+    fn declarations(&mut self, depth: usize) -> Vec<Stmt> {
+        (0..self.rng.usize(..=3))
+            .map(|_| self.declaration(depth))
+            .collect()
+    }
+
+    fn statement(&mut self, depth: usize) -> Stmt {
+        if depth == 0 {
+            return self.simple_statement(depth);
+        }
+
+        let kind = match self.rng.usize(..8) {
+            0 => StmtKind::Block(self.declarations(depth - 1)),
+            1 => {
+                let arms = (0..self.rng.usize(1..=3))
+                    .map(|_| (self.expr(depth - 1), self.branch(depth - 1)))
+                    .collect();
+                let else_branch = self.rng.bool().then(|| Box::new(self.branch(depth - 1)));
+                StmtKind::If(arms, else_branch)
+            }
+            2 => StmtKind::While(self.expr(depth - 1), Box::new(self.branch(depth - 1))),
+            3 => {
+                let initializer = match self.rng.usize(..4) {
+                    0 => None,
+                    1 => Some(StmtKind::Var(
+                        self.name(),
+                        self.rng.bool().then(|| self.expr(depth - 1)),
+                    )),
+                    2 => Some(StmtKind::Const(self.name(), self.expr(depth - 1))),
+                    _ => Some(StmtKind::Expression(self.expr(depth - 1))),
+                };
+                StmtKind::For(
+                    initializer.map(|kind| Box::new(Stmt::generated(kind))),
+                    self.rng.bool().then(|| self.expr(depth - 1)),
+                    self.rng.bool().then(|| self.expr(depth - 1)),
+                    Box::new(self.branch(depth - 1)),
+                )
+            }
+            4 => StmtKind::Defer(Box::new(self.branch(depth - 1))),
+            5 => StmtKind::ForIn(
+                self.name(),
+                self.expr(depth - 1),
+                Box::new(self.branch(depth - 1)),
+            ),
+            _ => return self.simple_statement(depth),
+        };
+        Stmt::generated(kind)
+    }
+
+    /// A statement without statements inside it.
+    fn simple_statement(&mut self, depth: usize) -> Stmt {
+        let depth = depth.saturating_sub(1);
+        match self.rng.usize(..3) {
+            0 => Stmt::print(self.expr(depth)),
+            1 => Stmt::expression(self.expr(depth)),
+            _ => Stmt::return_value(match self.rng.usize(..4) {
+                0 | 1 => None,
+                2 => Some(self.expr(depth)),
+                _ => {
+                    let values = (0..self.rng.usize(2..=3))
+                        .map(|_| self.expr(depth))
+                        .collect();
+                    Some(Expr::generated(ExprKind::Tuple(values)))
+                }
+            }),
+        }
+    }
+
+    /// The statement of an `if`, `else` or loop. An `if` there could take an `else` meant
+    /// for the one around it, so it only ever comes inside a block.
+    fn branch(&mut self, depth: usize) -> Stmt {
+        match self.rng.bool() {
+            true if depth > 0 => Stmt::block(self.declarations(depth - 1)),
+            _ => self.simple_statement(depth),
+        }
+    }
+
+    fn exprs(&mut self, most: usize, depth: usize) -> Vec<Expr> {
+        (0..self.rng.usize(..=most))
+            .map(|_| self.expr(depth))
+            .collect()
+    }
+
+    fn literal(&mut self) -> Literal {
+        match self.rng.usize(..4) {
+            // Quarters print exactly, and nothing the parser reads is negative.
+            0 => Literal::Number(self.rng.u32(..400) as f64 / 4.0),
+            1 => Literal::String(WORDS[self.rng.usize(..WORDS.len())].into()),
+            2 => Literal::Bool(self.rng.bool()),
+            _ => Literal::Nil,
+        }
+    }
+
+    fn expr(&mut self, depth: usize) -> Expr {
+        if depth == 0 {
+            let kind = match self.rng.usize(..3) {
+                0 => ExprKind::Literal(self.literal()),
+                1 => ExprKind::Variable(self.name(), None),
+                _ => ExprKind::This(Token::generated(TokenKind::This, "this"), None),
+            };
+            return Expr::generated(kind);
+        }
+
+        let depth = depth - 1;
+        let binary_ops = [
+            BinOp::Plus,
+            BinOp::Minus,
+            BinOp::Multiply,
+            BinOp::Divide,
+            BinOp::Gt,
+            BinOp::Ge,
+            BinOp::Lt,
+            BinOp::Le,
+            BinOp::EqEq,
+            BinOp::Ne,
+        ];
+        let logical_ops = [LogOp::And, LogOp::Or, LogOp::Coalesce];
+        let kind = match self.rng.usize(..15) {
+            0 | 1 => ExprKind::Binary(
+                binary_ops[self.rng.usize(..binary_ops.len())],
+                Box::new(self.expr(depth)),
+                Box::new(self.expr(depth)),
+            ),
+            2 => ExprKind::Logical(
+                logical_ops[self.rng.usize(..logical_ops.len())],
+                Box::new(self.expr(depth)),
+                Box::new(self.expr(depth)),
+            ),
+            3 => {
+                let op = match self.rng.bool() {
+                    true => UnOp::BinNeg,
+                    false => UnOp::LogNeg,
+                };
+                ExprKind::Unary(op, Box::new(self.expr(depth)))
+            }
+            4 => ExprKind::Grouping(Box::new(self.expr(depth))),
+            5 => ExprKind::Call(Box::new(self.postfix_target(depth)), self.exprs(3, depth)),
+            6 => ExprKind::Get(
+                Box::new(self.postfix_target(depth)),
+                self.name(),
+                self.rng.bool(),
+            ),
+            7 => ExprKind::Set(
+                Box::new(self.postfix_target(depth)),
+                self.name(),
+                Box::new(self.expr(depth)),
+            ),
+            8 => ExprKind::Assign(self.name(), Box::new(self.expr(depth)), None),
+            9 => ExprKind::Array(self.exprs(3, depth)),
+            10 => ExprKind::Map(
+                (0..self.rng.usize(..=2))
+                    .map(|_| (self.expr(depth), self.expr(depth)))
+                    .collect(),
+            ),
+            11 => ExprKind::Super(self.name(), None),
+            12 => {
+                let arms = (0..self.rng.usize(1..=3))
+                    .map(|_| MatchArm {
+                        pattern: self.pattern(depth),
+                        body: match self.rng.bool() {
+                            true => ArmBody::Expr(self.expr(depth)),
+                            false => ArmBody::Block(self.declarations(depth)),
+                        },
+                    })
+                    .collect();
+                ExprKind::Match(Box::new(self.expr(depth)), arms)
+            }
+            13 => ExprKind::Index(
+                Box::new(self.postfix_target(depth)),
+                Box::new(self.expr(depth)),
+            ),
+            _ => ExprKind::Literal(self.literal()),
+        };
+        Expr::generated(kind)
+    }
+
+    /// What a call, index, property access or assignment to a property is made on. A number
+    /// would run into the `.` after it, so it is grouped like anything looser than a call.
+    fn postfix_target(&mut self, depth: usize) -> Expr {
+        let expr = self.expr(depth);
+        match &expr.kind {
+            ExprKind::Variable(..)
+            | ExprKind::This(..)
+            | ExprKind::Super(..)
+            | ExprKind::Call(..)
+            | ExprKind::Get(..)
+            | ExprKind::Index(..)
+            | ExprKind::Grouping(_)
+            | ExprKind::Array(_)
+            | ExprKind::Map(_)
+            | ExprKind::Match(..)
+            | ExprKind::Literal(Literal::String(_) | Literal::Bool(_) | Literal::Nil) => expr,
+            _ => Expr::generated(ExprKind::Grouping(Box::new(expr))),
+        }
+    }
+
+    fn pattern(&mut self, depth: usize) -> Pattern {
+        let choice = match depth {
+            0 => self.rng.usize(..4),
+            _ => self.rng.usize(..7),
+        };
+        let kind = match choice {
+            0 => PatternKind::Wildcard,
+            1 => match self.rng.bool() {
+                // Unlike expressions, patterns take negative numbers.
+                true => PatternKind::Literal(Literal::Number(self.rng.i32(-50..50) as f64)),
+                false => PatternKind::Literal(self.literal()),
+            },
+            2 => PatternKind::Binding(self.name()),
+            3 => {
+                let mut path = Expr::variable(&self.name().lexeme);
+                for _ in 0..self.rng.usize(1..=2) {
+                    path = Expr::get(path, VARIANTS[self.rng.usize(..VARIANTS.len())]);
+                }
+                PatternKind::Value(path)
+            }
+            4 | 5 => PatternKind::Array(
+                (0..self.rng.usize(..=3))
+                    .map(|_| self.pattern(depth - 1))
+                    .collect(),
+            ),
+            _ => PatternKind::Map(
+                (0..self.rng.usize(..=2))
+                    .map(|_| {
+                        let key = match self.rng.bool() {
+                            true => self.name().lexeme,
+                            false => WORDS[self.rng.usize(..WORDS.len())].to_string(),
+                        };
+                        (key, self.pattern(depth - 1))
+                    })
+                    .collect(),
+            ),
+        };
+        Pattern {
+            kind,
+            span: Span::generated(),
+        }
+    }
+}
+
+/// `stmts` with every parenthesized expression replaced by what is inside the parentheses.
+pub fn without_groupings(stmts: Vec<Stmt>) -> Vec<Stmt> {
+    Ungroup.fold_stmts(stmts)
+}
+
+struct Ungroup;
+
+impl Fold for Ungroup {
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        match expr.kind {
+            ExprKind::Grouping(inner) => self.fold_expr(*inner),
+            _ => walk_expr(self, expr),
+        }
+    }
+}
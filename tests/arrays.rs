@@ -0,0 +1,95 @@
+//! Array literals and the array methods.
+
+use rlox::testing::{run_output, runtime_error};
+
+#[test]
+fn arrays_grow_and_shrink_in_place() {
+    let code = "var a = [1, 2, 3];\na.push(4);\nprint a;\nprint a.pop();\na.insert(0, 0);\na.insert(4, 9);\nprint a;\nprint a.remove(1);\nprint a;\nprint a.len();\nprint [].len();\n";
+    assert_eq!(
+        run_output(code).unwrap(),
+        "[1, 2, 3, 4]\n4\n[0, 1, 2, 3, 9]\n1\n[0, 2, 3, 9]\n4\n0\n"
+    );
+}
+
+#[test]
+fn arrays_are_shared_not_copied() {
+    assert_eq!(
+        run_output("var a = [1];\nvar b = a;\nb.push(2);\nprint a;\n").unwrap(),
+        "[1, 2]\n"
+    );
+}
+
+#[test]
+fn index_of_finds_the_first_match() {
+    assert_eq!(
+        run_output(
+            "var a = [\"x\", \"y\", \"x\"];\nprint a.indexOf(\"x\");\nprint a.indexOf(\"z\");\n"
+        )
+        .unwrap(),
+        "0\n-1\n"
+    );
+}
+
+#[test]
+fn map_and_filter_take_functions_and_methods() {
+    let code = "fun double(x) { return x * 2; }\nfun odd(x) { return x - (x / 2).floor() * 2 == 1; }\nvar a = [1, 2, 3];\nprint a.map(double);\nprint a.filter(odd);\nprint a;\n";
+    assert_eq!(run_output(code).unwrap(), "[2, 4, 6]\n[1, 3]\n[1, 2, 3]\n");
+
+    let code = "class Scale {\n  init(k) { this.k = k; }\n  apply(x) { return x * this.k; }\n}\nprint [1, 2].map(Scale(10).apply);\n";
+    assert_eq!(run_output(code).unwrap(), "[10, 20]\n");
+}
+
+#[test]
+fn bad_positions_and_arguments_are_specific() {
+    assert_eq!(
+        runtime_error("[].pop();"),
+        "test.lox:1:1 Can't pop from an empty array."
+    );
+    assert_eq!(
+        runtime_error("[1].insert(3, 0);"),
+        "test.lox:1:1 Argument 1 to 'insert' must be at most 1, got 3."
+    );
+    assert_eq!(
+        runtime_error("[1].insert(-1, 0);"),
+        "test.lox:1:1 Argument 1 to 'insert' must be a non-negative integer, got -1."
+    );
+    assert_eq!(
+        runtime_error("[1].remove(5);"),
+        "test.lox:1:1 Argument 1 to 'remove' must be at most 0, got 5."
+    );
+    assert_eq!(
+        runtime_error("[1].map(1);"),
+        "test.lox:1:1 Argument 1 to 'map' must be a Function, got Number."
+    );
+}
+
+#[test]
+fn sort_orders_numbers_and_strings() {
+    let code = "var a = [3, 1, 2];\na.sort();\nprint a;\nvar b = [\"pear\", \"apple\"];\nb.sort();\nprint b;\n";
+    assert_eq!(run_output(code).unwrap(), "[1, 2, 3]\n[apple, pear]\n");
+}
+
+#[test]
+fn sort_with_a_comparator_is_stable() {
+    let code = "var a = [\"bb\", \"a\", \"cc\", \"d\"];\nfun byLength(x, y) { return x.len() - y.len(); }\na.sort(byLength);\nprint a;\n";
+    assert_eq!(run_output(code).unwrap(), "[a, d, bb, cc]\n");
+}
+
+#[test]
+fn an_inconsistent_comparator_doesnt_panic() {
+    let code = "var a = [];\nfor (var i = 0; i < 2000; i = i + 1) a.push(i);\nvar flip = false;\nfun cmp(x, y) { flip = !flip; if (flip) return 1; return -1; }\na.sort(cmp);\nprint a.len();\n";
+    assert_eq!(run_output(code).unwrap(), "2000\n");
+
+    let code = "var a = [3, 0 / 0, 1, 0 / 0, 2];\na.sort();\nprint a.len();\n";
+    assert_eq!(run_output(code).unwrap(), "5\n");
+}
+
+#[test]
+fn a_failing_comparator_stops_the_sort() {
+    let code = "var a = [];\nfor (var i = 0; i < 2000; i = i + 1) a.push(2000 - i);\nvar calls = 0;\nfun cmp(x, y) {\n  calls = calls + 1;\n  if (calls == 50) return nil;\n  return x - y;\n}\na.sort(cmp);\n";
+    assert_eq!(
+        runtime_error(code),
+        "test.lox:9:1 Sort comparator must return a Number, got Nil."
+    );
+    assert!(runtime_error("var a = [1, \"b\"];\na.sort();\n").contains("Can't sort"));
+}
@@ -0,0 +1,188 @@
+//! Assignments used as expressions: the value they yield and the order they evaluate in.
+
+use rlox::{
+    testing::{run_output, runtime_error, syntax_error},
+    DiagnosticOptions, Runner,
+};
+
+/// The assignment-condition warnings `code` gets, as located messages without their help.
+fn warnings(code: &str) -> Vec<String> {
+    let mut runner = Runner::new();
+    runner.set_diagnostic_options(DiagnosticOptions {
+        print: None,
+        ..DiagnosticOptions::default()
+    });
+    // Whether the code runs doesn't matter here, only what it was warned about.
+    let _ = runner.run("test.lox", code);
+    runner
+        .diagnostics()
+        .diagnostics()
+        .iter()
+        .filter(|diagnostic| diagnostic.code == "assignment-condition")
+        .map(|diagnostic| format!("{} {}", diagnostic.location, diagnostic.message))
+        .collect()
+}
+
+#[test]
+fn an_assignment_yields_the_assigned_value() {
+    let code = "
+        var a = 1;
+        print a = 2;
+        print a;
+
+        class Box {}
+        var box = Box();
+        print box.value = \"packed\";
+        print box.value;
+    ";
+    assert_eq!(run_output(code).unwrap(), "2\n2\npacked\npacked\n");
+}
+
+#[test]
+fn chained_assignments_assign_right_to_left() {
+    let code = "
+        var a;
+        var b;
+        var c;
+        print a = b = c = 3;
+        print a + b + c;
+
+        class Point {}
+        var p = Point();
+        a = p.x = b = p.y = \"origin\";
+        print a;
+        print p.x;
+        print b;
+        print p.y;
+    ";
+    assert_eq!(
+        run_output(code).unwrap(),
+        "3\n9\norigin\norigin\norigin\norigin\n"
+    );
+}
+
+#[test]
+fn a_chained_assignment_stops_at_the_first_failure() {
+    let code = "
+        var a = \"unchanged\";
+        const k = 1;
+        a = k = 2;
+    ";
+    assert_eq!(
+        runtime_error(code),
+        "test.lox:4:13 Cannot assign to constant 'k'."
+    );
+
+    let code = "
+        var a = \"unchanged\";
+        var b = \"unchanged\";
+        fun fail() { return nil.field; }
+        a = b = fail();
+    ";
+    assert!(runtime_error(code).starts_with("test.lox:4:"));
+}
+
+#[test]
+fn property_assignments_evaluate_the_object_before_the_value() {
+    let code = "
+        class Box {}
+        var box = Box();
+        fun log(label, value) { print label; return value; }
+
+        log(\"object\", box).field = log(\"value\", 1);
+        print box.field;
+    ";
+    assert_eq!(run_output(code).unwrap(), "object\nvalue\n1\n");
+}
+
+#[test]
+fn assignments_in_call_arguments_run_left_to_right() {
+    let code = "
+        fun pair(first, second) { return first + \",\" + second; }
+        var a = \"\";
+        print pair(a = a + \"x\", a = a + \"y\");
+        print a;
+    ";
+    assert_eq!(run_output(code).unwrap(), "x,xy\nxy\n");
+}
+
+#[test]
+fn assignments_in_conditions_yield_the_assigned_value() {
+    let code = "
+        var left = 2;
+        fun readNext() {
+          if (left == 0) return nil;
+          left = left - 1;
+          if (left == 1) return \"one\";
+          return \"two\";
+        }
+
+        var line;
+        while ((line = readNext()) != nil) print line;
+        print line;
+
+        var found;
+        if ((found = \"yes\")) print found;
+        if ((found = nil)) print \"unreachable\"; else print found;
+
+        var i = 0;
+        for (; (i = i + 1) < 3;) print i;
+
+        var a;
+        var b;
+        print (a = false) or (b = \"fallback\");
+        print a;
+        print b;
+    ";
+    assert_eq!(
+        run_output(code).unwrap(),
+        "one\ntwo\nnil\nyes\nnil\n1\n2\nfallback\nfalse\nfallback\n"
+    );
+}
+
+#[test]
+fn only_variables_and_properties_can_be_assigned_to() {
+    for code in ["var a; var b; a + b = 1;", "var a; (a) = 1;", "1 = 1;"] {
+        let error = syntax_error(code);
+        assert!(error.contains("Invalid assignment target."), "{}", error);
+    }
+
+    // Reported at the `=`, and the statements after it are still checked.
+    assert_eq!(
+        syntax_error("var a;\nvar b;\na = b + 1 = 2;\nprint;\n"),
+        "Parser Error: test.lox:3:11 Invalid assignment target.\n\
+Parser Error: test.lox:4:6 'print' requires an expression."
+    );
+}
+
+#[test]
+fn an_unparenthesized_assignment_in_a_condition_is_flagged() {
+    assert_eq!(
+        warnings("var x;\nif (x = 0) print x;\n"),
+        ["test.lox:2:5 Assignment to 'x' used as a condition. Did you mean '=='?"]
+    );
+    assert_eq!(
+        warnings("var x;\nwhile (x = nil) {}\nfor (; x = nil;) {}\n"),
+        [
+            "test.lox:2:8 Assignment to 'x' used as a condition. Did you mean '=='?",
+            "test.lox:3:8 Assignment to 'x' used as a condition. Did you mean '=='?",
+        ]
+    );
+    assert_eq!(
+        warnings("var a;\nvar b;\nif (a = b = 0) print a;\n"),
+        ["test.lox:3:5 Assignment to 'a' used as a condition. Did you mean '=='?"]
+    );
+    assert_eq!(
+        warnings("class C {}\nvar c = C();\nif (c.ready = true) print c;\n"),
+        ["test.lox:3:7 Assignment to 'ready' used as a condition. Did you mean '=='?"]
+    );
+}
+
+#[test]
+fn a_parenthesized_assignment_in_a_condition_is_not_flagged() {
+    assert!(warnings("var x;\nif ((x = 0)) print x;\n").is_empty());
+    assert!(warnings("var x;\nwhile ((x = nil) != nil) {}\n").is_empty());
+    assert!(warnings("var x;\nfor (; (x = nil);) {}\n").is_empty());
+    assert!(warnings("class C {}\nvar c = C();\nif ((c.ready = true)) print c;\n").is_empty());
+    assert!(warnings("var x;\nprint x = 1;\nvar y = x = 2;\n").is_empty());
+}
@@ -0,0 +1,58 @@
+//! The `assignment-condition` warning for `=` written where `==` was probably meant.
+
+use rlox::{DiagnosticOptions, Interpreter, OutputBuffer, Runner};
+
+/// The warnings running `code` as a script reports, as they would be printed.
+fn warnings(code: &str) -> Vec<String> {
+    let interpreter = Interpreter::builder()
+        .output(Box::new(OutputBuffer::new()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.set_diagnostic_options(DiagnosticOptions {
+        print: None,
+        ..DiagnosticOptions::default()
+    });
+    runner.run("test.lox", code).unwrap();
+    runner
+        .diagnostics()
+        .diagnostics()
+        .iter()
+        .map(|diagnostic| format!("[{}] {}", diagnostic.code, diagnostic))
+        .collect()
+}
+
+#[test]
+fn assignments_as_conditions_are_warned_about() {
+    assert_eq!(
+        warnings("var x = 1;\nif (x = 0) print 1;\n"),
+        [
+            "[assignment-condition] test.lox:2:5 Assignment to 'x' used as a condition. Did you \
+          mean '=='? Wrap it in an extra pair of parentheses to silence this warning."
+        ]
+    );
+
+    let code = "var x = true;\nwhile (x = false) {}\nfor (; x = false;) {}\nprint (x = 1) or 2;\nprint nil and (x = 1);\n";
+    let lines: Vec<_> = warnings(code)
+        .iter()
+        .map(|warning| warning.split(' ').nth(1).unwrap().to_string())
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            "test.lox:2:8",
+            "test.lox:3:8",
+            "test.lox:4:8",
+            "test.lox:5:16"
+        ]
+    );
+}
+
+#[test]
+fn extra_parentheses_silence_the_warning() {
+    assert!(warnings("var x;\nwhile ((x = nil)) print 1;\nif ((x = 1)) {}\n").is_empty());
+}
+
+#[test]
+fn assignment_statements_are_left_alone() {
+    assert!(warnings("var a;\nvar b = 1;\na = b;\nvar c = a = 2;\n").is_empty());
+}
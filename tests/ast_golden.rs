@@ -0,0 +1,160 @@
+//! Golden tests for the textual AST format. Each `tests/ast/*.lox` fixture is parsed and
+//! compared with the `.sexpr` file next to it, and every `.sexpr` file has to read back
+//! into the same text.
+//!
+//! After an intended change to the format or the parser, regenerate the files with
+//! `UPDATE_GOLDEN=1 cargo test --test ast_golden` and review the diff.
+
+use std::{env, fs, path::PathBuf};
+
+use rlox::inspect::{self, Format, Options};
+
+fn fixtures() -> Vec<PathBuf> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/ast");
+    let mut fixtures: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lox"))
+        .collect();
+    fixtures.sort();
+    fixtures
+}
+
+fn render(path: &PathBuf) -> String {
+    let options = Options {
+        path: None,
+        format: Format::Sexpr,
+        tokens: false,
+        stmt: None,
+        desugar: false,
+        resolve: false,
+    };
+    let code = fs::read_to_string(path).unwrap();
+    let name = path.file_name().unwrap().to_string_lossy();
+    match inspect::inspect(&name, &code, &options) {
+        Ok(sexpr) => sexpr,
+        Err(e) => panic!("{} doesn't parse: {:?}", path.display(), e),
+    }
+}
+
+#[test]
+fn fixtures_match_golden_files() {
+    let update = env::var_os("UPDATE_GOLDEN").is_some();
+    let mut mismatched = vec![];
+
+    for path in fixtures() {
+        let actual = render(&path);
+        let golden = path.with_extension("sexpr");
+        if update {
+            fs::write(&golden, &actual).unwrap();
+            continue;
+        }
+
+        let expected = fs::read_to_string(&golden)
+            .unwrap_or_else(|e| panic!("can't read {}: {}", golden.display(), e));
+        if actual != expected {
+            eprintln!(
+                "--- {}\n{}--- actual\n{}",
+                golden.display(),
+                expected,
+                actual
+            );
+            mismatched.push(golden);
+        }
+    }
+
+    assert!(
+        mismatched.is_empty(),
+        "golden files out of date: {:?}; rerun with UPDATE_GOLDEN=1 if the change is intended",
+        mismatched
+    );
+}
+
+/// `tests/ast/resolved/*.lox` fixtures, shown with their variables resolved.
+#[test]
+fn resolved_fixtures_match_golden_files() {
+    let update = env::var_os("UPDATE_GOLDEN").is_some();
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/ast/resolved");
+    let options = Options {
+        path: None,
+        format: Format::Sexpr,
+        tokens: false,
+        stmt: None,
+        desugar: false,
+        resolve: true,
+    };
+
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|ext| ext != "lox") {
+            continue;
+        }
+        let code = fs::read_to_string(&path).unwrap();
+        let name = path.file_name().unwrap().to_string_lossy();
+        let actual = inspect::inspect(&name, &code, &options)
+            .unwrap_or_else(|e| panic!("{} doesn't resolve: {:?}", path.display(), e));
+
+        let golden = path.with_extension("sexpr");
+        if update {
+            fs::write(&golden, &actual).unwrap();
+        } else {
+            assert_eq!(
+                actual,
+                fs::read_to_string(&golden).unwrap(),
+                "{} is out of date; rerun with UPDATE_GOLDEN=1 if the change is intended",
+                golden.display()
+            );
+        }
+    }
+}
+
+#[test]
+fn golden_files_round_trip() {
+    for path in fixtures() {
+        let golden = path.with_extension("sexpr");
+        let Ok(text) = fs::read_to_string(&golden) else {
+            continue;
+        };
+        match inspect::reformat_sexpr(&text) {
+            Ok(reformatted) => assert_eq!(reformatted, text, "{}", golden.display()),
+            Err(e) => panic!("{} doesn't read back: {}", golden.display(), e),
+        }
+    }
+}
+
+#[test]
+fn reading_accepts_comments_and_any_layout() {
+    let text = "; a comment\n(version 1)\n(print\n   (+ 1   ; one\n 2))\n";
+    assert_eq!(
+        inspect::reformat_sexpr(text).unwrap(),
+        "(version 1)\n(print (+ 1 2))\n"
+    );
+}
+
+#[test]
+fn reading_rejects_malformed_documents() {
+    for (text, error) in [
+        (
+            "(print 1)",
+            "Expect the document to start with (version N).",
+        ),
+        (
+            "(version 2)",
+            "Unsupported AST format version 2; expected 1.",
+        ),
+        ("(version 1)\n(print 1", "Unclosed '('."),
+        ("(version 1)\n(print \"\\q\")", "Invalid escape '\\q'."),
+        ("(version 1)\n(loop)", "Expect a statement, found (loop)."),
+        (
+            "(version 1)\n(print #<Array>)",
+            "Expect an expression, found #<Array>.",
+        ),
+    ] {
+        assert_eq!(
+            inspect::reformat_sexpr(text).unwrap_err(),
+            error,
+            "{}",
+            text
+        );
+    }
+}
@@ -0,0 +1,91 @@
+//! Running several scripts in one invocation, `rlox lib.lox main.lox`, against one interpreter.
+
+use std::process::{Command, Output};
+
+use rlox::cli::{self, Command as CliCommand, Mode};
+
+fn lox(scripts: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_lox"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(
+            scripts
+                .iter()
+                .map(|script| format!("tests/batch/{}", script)),
+        )
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn later_scripts_see_earlier_definitions() {
+    let output = lox(&["lib.lox", "main.lox"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "hello, lib\nhello, main\n"
+    );
+}
+
+#[test]
+fn a_syntax_error_stops_the_batch_before_it_runs() {
+    let output = lox(&["broken.lox", "main.lox"]);
+    assert_eq!(output.status.code(), Some(65));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "");
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.starts_with(
+            "Stopped at tests/batch/broken.lox, so tests/batch/main.lox was not run.\n"
+        ),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("tests/batch/broken.lox:2:"), "{}", stderr);
+}
+
+#[test]
+fn a_runtime_error_stops_the_batch_where_it_happens() {
+    let output = lox(&["lib.lox", "failing.lox", "main.lox", "main.lox"]);
+    assert_eq!(output.status.code(), Some(70));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "before\n");
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.starts_with(
+            "Stopped at tests/batch/failing.lox, so tests/batch/main.lox, tests/batch/main.lox \
+             were not run.\n"
+        ),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("tests/batch/failing.lox:2:7"), "{}", stderr);
+}
+
+#[test]
+fn several_scripts_are_parsed_into_a_batch() {
+    let parse = |args: &[&str]| cli::parse_args(args.iter().map(|arg| arg.to_string()).collect());
+
+    match parse(&["--time", "lib.lox", "--no-prelude", "main.lox"]) {
+        Ok(CliCommand::Run(config)) => {
+            assert_eq!(
+                config.mode,
+                Mode::Files(vec!["lib.lox".into(), "main.lox".into()])
+            );
+            assert!(config.time);
+            assert!(!config.prelude);
+        }
+        other => panic!("expected a run, got {:?}", other),
+    }
+    match parse(&["main.lox"]) {
+        Ok(CliCommand::Run(config)) => assert_eq!(config.mode, Mode::File("main.lox".into())),
+        other => panic!("expected a run, got {:?}", other),
+    }
+    assert_eq!(
+        parse(&["--debug", "lib.lox", "main.lox"]),
+        Err("--debug, --extract-docs and --test take a single script file.".to_string())
+    );
+    assert_eq!(
+        parse(&["lib.lox", "main.lox", "-e", "print 1;"]),
+        Err("-e can't be combined with a script file.".to_string())
+    );
+}
@@ -0,0 +1,55 @@
+//! Arithmetic, comparison and equality operators, and the values they produce.
+
+use rlox::testing::{run_output, runtime_error};
+
+#[test]
+fn arithmetic_follows_precedence() {
+    assert_eq!(
+        run_output(
+            "print 1 + 2 * 3 - 4 / 2;\nprint (1 + 2) * 3;\nprint 1 / 0;\nprint 0.1 + 0.2;\n"
+        )
+        .unwrap(),
+        "5\n9\ninf\n0.30000000000000004\n"
+    );
+}
+
+#[test]
+fn comparisons_and_equality() {
+    let code = "print 1 < 2;\nprint 2 <= 2;\nprint 3 > 4;\nprint 3 >= 4;\n\
+                print 1 == 1;\nprint \"a\" == \"a\";\nprint nil == nil;\nprint 1 == \"1\";\nprint nil != false;\n";
+    assert_eq!(
+        run_output(code).unwrap(),
+        "true\ntrue\nfalse\nfalse\ntrue\ntrue\ntrue\nfalse\ntrue\n"
+    );
+}
+
+#[test]
+fn concatenation_makes_a_new_string() {
+    // Neither operand changes, however the variables holding them are shared.
+    let code = "var a = \"x\";\nvar b = a;\na = a + \"y\";\nprint a;\nprint b;\n\
+                var s = \"ab\";\nvar t = s + s;\ns = s + \"!\";\nprint t;\nprint s;\n";
+    assert_eq!(run_output(code).unwrap(), "xy\nx\nabab\nab!\n");
+}
+
+#[test]
+fn concatenation_in_a_loop() {
+    let code =
+        "var s = \"\";\nfor (var i = 0; i < 1000; i = i + 1) s = s + \"ab\";\nprint s.len();\n";
+    assert_eq!(run_output(code).unwrap(), "2000\n");
+}
+
+#[test]
+fn mismatched_operands_fail() {
+    assert_eq!(
+        runtime_error("print \"a\" + 1;"),
+        "test.lox:1:7 Operands must be two numbers or two strings."
+    );
+    assert_eq!(
+        runtime_error("print 1 < \"b\";"),
+        "test.lox:1:7 Operands must be numbers, but the right operand is a String."
+    );
+    assert_eq!(
+        runtime_error("print \"a\" < \"b\";"),
+        "test.lox:1:7 Operands must be numbers, but they are a String and a String."
+    );
+}
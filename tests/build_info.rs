@@ -0,0 +1,97 @@
+//! The interpreter's version and build metadata, as scripts, embedders and `--version` see it.
+
+use std::process::Command;
+
+use rlox::{testing::run_output, Capability, Interpreter, OutputBuffer, Runner};
+
+#[test]
+fn version_returns_the_crate_version() {
+    assert_eq!(rlox::VERSION, env!("CARGO_PKG_VERSION"));
+    assert_eq!(
+        run_output("print version();").unwrap(),
+        format!("{}\n", env!("CARGO_PKG_VERSION"))
+    );
+}
+
+#[test]
+fn interpreter_info_describes_the_interpreter() {
+    let code = "
+        var info = interpreterInfo();
+        print info.keys();
+        print info.get(\"version\") == version();
+        print info.get(\"backend\");
+        print info.get(\"capabilities\");
+    ";
+    assert_eq!(
+        run_output(code).unwrap(),
+        "[version, gitHash, backend, capabilities]\ntrue\ntree-walk\n[clock, stdin]\n"
+    );
+
+    let hash = run_output("print interpreterInfo().get(\"gitHash\");").unwrap();
+    match rlox::build_info().git_hash {
+        Some(expected) => assert_eq!(hash, format!("{}\n", expected)),
+        None => assert_eq!(hash, "nil\n"),
+    }
+}
+
+#[test]
+fn interpreter_info_lists_the_capabilities_granted() {
+    let code = "print interpreterInfo().get(\"capabilities\");";
+    let printed = |builder: rlox::InterpreterBuilder| {
+        let output = OutputBuffer::new();
+        let mut runner = Runner::with_interpreter(builder.output(Box::new(output.clone())).build());
+        runner.run("test.lox", code).unwrap();
+        output.contents()
+    };
+
+    assert_eq!(printed(Interpreter::sandboxed()), "[]\n");
+    assert_eq!(
+        printed(Interpreter::sandboxed().allow(Capability::Environment)),
+        "[env]\n"
+    );
+    assert_eq!(
+        printed(Interpreter::full().deny(Capability::Network)),
+        "[clock, stdin, fs, env, run]\n"
+    );
+}
+
+#[test]
+fn build_info_lists_the_capabilities_the_build_can_grant() {
+    let info = rlox::build_info();
+    assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+    for capability in Capability::ALL {
+        assert_eq!(
+            info.capabilities.contains(capability),
+            !capability.natives().is_empty(),
+            "{}",
+            capability
+        );
+    }
+    assert_eq!(
+        info.capabilities.contains(Capability::Network),
+        cfg!(feature = "http")
+    );
+}
+
+#[test]
+fn the_version_option_prints_the_build_info() {
+    let output = Command::new(env!("CARGO_BIN_EXE_lox"))
+        .arg("--version")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.starts_with(&format!("rlox {}", env!("CARGO_PKG_VERSION"))),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("backend: tree-walk\n"), "{}", stdout);
+    assert!(
+        stdout.contains("capabilities: clock, stdin, fs, env"),
+        "{}",
+        stdout
+    );
+    assert_eq!(stdout, format!("{}\n", rlox::cli::version()));
+}
@@ -0,0 +1,182 @@
+//! Capabilities, the natives they bring, and the sandboxed and full presets.
+
+use rlox::{Capability, CapabilitySet, Interpreter, LoxError, OutputBuffer, Runner};
+
+/// Runs `code` in the interpreter `builder` makes, returning what it printed.
+fn run_in(builder: rlox::InterpreterBuilder, code: &str) -> Result<String, LoxError> {
+    let output = OutputBuffer::new();
+    let mut runner = Runner::with_interpreter(builder.output(Box::new(output.clone())).build());
+    runner.run("test.lox", code)?;
+    Ok(output.contents())
+}
+
+/// The message of the runtime error running `code` fails with.
+fn runtime_message(builder: rlox::InterpreterBuilder, code: &str) -> String {
+    match run_in(builder, code) {
+        Err(LoxError::Runtime(diagnostic)) => diagnostic.message,
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
+
+#[test]
+fn every_capability_registers_its_natives() {
+    let mut natives: Vec<&str> = Capability::ALL
+        .into_iter()
+        .flat_map(Capability::natives)
+        .collect();
+    natives.sort();
+
+    let mut expected = vec!["clock", "exec", "getEnv", "now", "readFile", "readLine"];
+    if cfg!(feature = "http") {
+        expected.extend(["fetch", "fetchPost"]);
+    }
+    expected.sort();
+    assert_eq!(natives, expected);
+}
+
+#[test]
+fn a_sandboxed_interpreter_has_none_of_the_gated_natives() {
+    let interpreter = Interpreter::sandboxed().build();
+    assert_eq!(interpreter.capabilities(), CapabilitySet::none());
+    for capability in Capability::ALL {
+        for native in capability.natives() {
+            assert!(
+                interpreter.get_global(native).is_none(),
+                "{} is defined",
+                native
+            );
+        }
+    }
+
+    let message = runtime_message(Interpreter::sandboxed(), "print clock();");
+    assert!(
+        message.starts_with("Undefined variable 'clock'."),
+        "{}",
+        message
+    );
+    // The natives that don't reach outside are all there.
+    assert_eq!(
+        run_in(Interpreter::sandboxed(), "print format(\"{}\", 1);").unwrap(),
+        "1\n"
+    );
+}
+
+#[test]
+fn a_full_interpreter_has_every_gated_native() {
+    let interpreter = Interpreter::full().build();
+    for capability in Capability::ALL {
+        for native in capability.natives() {
+            assert!(
+                interpreter.get_global(native).is_some(),
+                "{} is missing",
+                native
+            );
+        }
+    }
+}
+
+#[test]
+fn interpreters_get_the_clock_and_stdin_by_default() {
+    let interpreter = Interpreter::new();
+    assert_eq!(interpreter.capabilities(), CapabilitySet::default());
+    assert!(interpreter.get_global("clock").is_some());
+    assert!(interpreter.get_global("readLine").is_some());
+    assert!(interpreter.get_global("readFile").is_none());
+    assert!(interpreter.get_global("getEnv").is_none());
+    assert!(interpreter.get_global("exec").is_none());
+}
+
+#[test]
+fn the_sandbox_runs_out_of_fuel() {
+    let message = runtime_message(
+        Interpreter::sandboxed(),
+        "var i = 0; while (true) i = i + 1;",
+    );
+    assert_eq!(
+        message,
+        "The script would execute statement number 10000001, over the max_statements limit of \
+         10000000."
+    );
+}
+
+#[test]
+fn the_sandbox_caps_memory() {
+    let message = runtime_message(
+        Interpreter::sandboxed(),
+        "var s = \"ab\"; while (true) s = s + s;",
+    );
+    assert!(
+        message.contains("over the max_string_length limit of 1000000"),
+        "{}",
+        message
+    );
+
+    // As deep as the recursion gets before the limit stops it, on the stack the `lox`
+    // binary's main thread has.
+    let message = std::thread::Builder::new()
+        .stack_size(8 << 20)
+        .spawn(|| {
+            runtime_message(
+                Interpreter::sandboxed(),
+                "fun deep(n) { return deep(n + 1) + 1; } deep(0);",
+            )
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+    assert!(
+        message.contains("over the max_environments limit of 200"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn presets_compose_with_further_builder_calls() {
+    let builder = Interpreter::sandboxed()
+        .allow(Capability::Filesystem)
+        .fuel(Some(20));
+    let interpreter = builder.build();
+    assert!(interpreter.get_global("readFile").is_some());
+    assert!(interpreter.get_global("getEnv").is_none());
+    assert!(interpreter.get_global("clock").is_none());
+
+    let code = "var contents = readFile(\"tests/capabilities.rs\"); print contents.len() > 0;";
+    let builder = Interpreter::sandboxed()
+        .allow(Capability::Filesystem)
+        .fuel(Some(20));
+    assert_eq!(run_in(builder, code).unwrap(), "true\n");
+
+    let counting = "var n = 0; while (n < 30) n = n + 1;";
+    let message = runtime_message(Interpreter::sandboxed().fuel(Some(20)), counting);
+    assert!(
+        message.contains("over the max_statements limit of 20"),
+        "{}",
+        message
+    );
+    run_in(Interpreter::sandboxed().fuel(Some(100)), counting).unwrap();
+
+    let interpreter = Interpreter::full().deny(Capability::Processes).build();
+    assert!(interpreter.get_global("exec").is_none());
+    assert!(interpreter.get_global("readFile").is_some());
+}
+
+#[test]
+fn get_env_reads_the_environment() {
+    let code = "print getEnv(\"CARGO_PKG_NAME\"); print getEnv(\"RLOX_SURELY_UNSET_VARIABLE\");";
+    let builder = Interpreter::builder().allow(Capability::Environment);
+    assert_eq!(run_in(builder, code).unwrap(), "rlox\nnil\n");
+}
+
+#[test]
+fn read_file_reports_what_went_wrong() {
+    let message = runtime_message(
+        Interpreter::builder().allow(Capability::Filesystem),
+        "readFile(\"no/such/file.txt\");",
+    );
+    assert!(
+        message.starts_with("readFile(\"no/such/file.txt\") failed: "),
+        "{}",
+        message
+    );
+}
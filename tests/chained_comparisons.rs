@@ -0,0 +1,62 @@
+//! Comparisons written as if they chained, like `0 < x < 10`.
+
+use rlox::{testing::runtime_error, DiagnosticOptions, Runner};
+
+/// The chained comparison warnings `code` gets, as located messages without their help.
+fn warnings(code: &str) -> Vec<String> {
+    let mut runner = Runner::new();
+    runner.set_diagnostic_options(DiagnosticOptions {
+        print: None,
+        ..DiagnosticOptions::default()
+    });
+    // Whether the code runs doesn't matter here, only what it was warned about.
+    let _ = runner.run("test.lox", code);
+    runner
+        .diagnostics()
+        .diagnostics()
+        .iter()
+        .filter(|diagnostic| diagnostic.code == "chained-comparison")
+        .map(|diagnostic| format!("{} {}", diagnostic.location, diagnostic.message))
+        .collect()
+}
+
+#[test]
+fn chained_comparisons_are_flagged() {
+    assert_eq!(
+        warnings("var x = 5;\nif (0 < x < 10) print x;\n"),
+        ["test.lox:2:11 Chained comparisons are not supported; write `0 < x and x < 10`."]
+    );
+    assert_eq!(
+        warnings("var a = 1;\nvar b = 1;\nvar c = 1;\nprint a == b == c;\n"),
+        ["test.lox:4:14 Chained comparisons are not supported; write `a == b and b == c`."]
+    );
+    assert_eq!(
+        warnings("var lo = 1;\nvar hi = 3;\nprint lo <= hi + 1 > 2;\n"),
+        ["test.lox:3:20 Chained comparisons are not supported; write `lo <= hi + 1 and hi + 1 > 2`."]
+    );
+}
+
+#[test]
+fn comparing_a_comparison_on_purpose_is_not_flagged() {
+    assert!(warnings("var x = 5;\nprint (0 < x) == true;\n").is_empty());
+    assert!(warnings("var x = 5;\nprint 0 < x == true;\n").is_empty());
+    assert!(warnings("var x = 5;\nprint (0 < x) < 10;\n").is_empty());
+    assert!(warnings("var x = 5;\nprint 0 < x and x < 10;\n").is_empty());
+}
+
+#[test]
+fn comparing_a_bool_names_the_operand_types() {
+    assert_eq!(
+        runtime_error("var x = 5;\nprint (0 < x) < 10;\n"),
+        "test.lox:2:7 Operands must be numbers, but the left operand is a Bool. \
+Comparisons can't be chained; join them with 'and' instead."
+    );
+    assert_eq!(
+        runtime_error("print 1 - \"one\";\n"),
+        "test.lox:1:7 Operands must be numbers, but the right operand is a String."
+    );
+    assert_eq!(
+        runtime_error("print nil * true;\n"),
+        "test.lox:1:7 Operands must be numbers, but they are a Nil and a Bool."
+    );
+}
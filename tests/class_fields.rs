@@ -0,0 +1,54 @@
+//! Fields declared with `var` in a class body.
+
+use rlox::testing::{assert_output, syntax_error};
+
+#[test]
+fn fields_start_out_with_their_defaults() {
+    assert_output(
+        "class Counter {\n  var count = 0;\n  var label;\n}\n\
+         var c = Counter();\nprint c.count;\nprint c.label;\n\
+         c.count = c.count + 1;\nprint c.count;\nprint Counter().count;\n",
+        &["0", "nil", "1", "0"],
+    );
+}
+
+#[test]
+fn init_runs_after_the_defaults() {
+    assert_output(
+        "class Point {\n  var x = 0;\n  var y = 0;\n  init(x) { print this.x; this.x = x; }\n}\n\
+         var p = Point(3);\nprint p.x;\nprint p.y;\n",
+        &["0", "3", "0"],
+    );
+}
+
+#[test]
+fn initializers_run_with_this_bound() {
+    assert_output(
+        "class Box {\n  var width = 2;\n  var area = this.width * this.square();\n\
+         square() { return this.width; }\n}\nprint Box().area;\n",
+        &["4"],
+    );
+    assert_output(
+        "var made = 0;\nclass Ticket {\n  var number = made = made + 1;\n}\n\
+         print Ticket().number;\nprint Ticket().number;\n",
+        &["1", "2"],
+    );
+}
+
+#[test]
+fn superclass_fields_are_initialized_first() {
+    assert_output(
+        "class Base {\n  var order = \"base\";\n}\n\
+         class Derived < Base {\n  var order = this.order + \" derived\";\n}\n\
+         print Derived().order;\n",
+        &["base derived"],
+    );
+}
+
+#[test]
+fn extensions_cannot_declare_fields() {
+    assert_eq!(
+        syntax_error("class A {}\nextend A {\n  var x = 1;\n}\n"),
+        "Parser Error: test.lox:3:3 Only methods can be declared in the extension body."
+    );
+}
@@ -0,0 +1,231 @@
+//! Parsing the command line into what the `lox` binary should do.
+
+use rlox::{
+    cli::{self, Command, Config, Mode},
+    Capability, CapabilitySet, DiagnosticFormat, DiagnosticOptions,
+};
+
+fn parse(args: &[&str]) -> Result<Command, String> {
+    cli::parse_args(args.iter().map(|arg| arg.to_string()).collect())
+}
+
+/// The config for `args`, which must describe a run.
+fn config(args: &[&str]) -> Config {
+    match parse(args) {
+        Ok(Command::Run(config)) => config,
+        other => panic!("{:?} didn't parse to a run: {:?}", args, other),
+    }
+}
+
+fn error(args: &[&str]) -> String {
+    parse(args).unwrap_err()
+}
+
+#[test]
+fn no_arguments_start_the_repl_with_the_defaults() {
+    assert_eq!(
+        config(&[]),
+        Config {
+            mode: Mode::Repl,
+            debug: false,
+            extract_docs: false,
+            test: false,
+            time: false,
+            init: true,
+            implicit_globals: false,
+            prelude: true,
+            capabilities: CapabilitySet::default(),
+            diagnostics: DiagnosticOptions::default(),
+        }
+    );
+}
+
+#[test]
+fn paths_pick_the_mode() {
+    assert_eq!(config(&["a.lox"]).mode, Mode::File("a.lox".into()));
+    assert_eq!(
+        config(&["a.lox", "b.lox"]).mode,
+        Mode::Files(vec!["a.lox".into(), "b.lox".into()])
+    );
+    assert_eq!(
+        config(&["-e", "print 1;"]).mode,
+        Mode::Inline("print 1;".into())
+    );
+}
+
+#[test]
+fn help_and_version_win_over_everything_else() {
+    assert_eq!(parse(&["-h"]), Ok(Command::Help));
+    assert_eq!(parse(&["a.lox", "--help"]), Ok(Command::Help));
+    assert_eq!(parse(&["-V"]), Ok(Command::Version));
+    assert_eq!(parse(&["--version", "--bogus"]), Ok(Command::Version));
+}
+
+#[test]
+fn script_flags_are_recorded() {
+    let config = config(&["--time", "--no-prelude", "a.lox"]);
+    assert!(config.time);
+    assert!(!config.prelude);
+
+    assert!(self::config(&["--debug", "a.lox"]).debug);
+    assert!(self::config(&["--extract-docs", "a.lox"]).extract_docs);
+    assert!(self::config(&["--test", "a.lox"]).test);
+    assert!(self::config(&["--time", "-e", "print 1;"]).time);
+}
+
+#[test]
+fn repl_flags_are_recorded() {
+    let config = config(&["--no-init", "--implicit-globals"]);
+    assert!(!config.init);
+    assert!(config.implicit_globals);
+}
+
+#[test]
+fn allow_flags_grant_capabilities() {
+    let config = config(&["--allow-run", "--allow-read", "--allow-env", "a.lox"]);
+    assert_eq!(
+        config.capabilities,
+        CapabilitySet::default()
+            .with(Capability::Processes)
+            .with(Capability::Filesystem)
+            .with(Capability::Environment)
+    );
+}
+
+#[test]
+fn allow_net_depends_on_the_http_feature() {
+    let result = parse(&["--allow-net", "a.lox"]);
+    if cfg!(feature = "http") {
+        match result {
+            Ok(Command::Run(config)) => assert!(config.capabilities.contains(Capability::Network)),
+            other => panic!("--allow-net didn't parse: {:?}", other),
+        }
+    } else {
+        assert!(result.unwrap_err().contains("without the http feature"));
+    }
+}
+
+#[test]
+fn diagnostic_options_are_recorded() {
+    let config = config(&[
+        "--deny-warnings",
+        "--allow",
+        "unused-variable",
+        "--allow",
+        "shadowing",
+        "--diagnostic-format",
+        "json",
+        "a.lox",
+    ]);
+    assert!(config.diagnostics.deny_warnings);
+    assert_eq!(config.diagnostics.allow, ["unused-variable", "shadowing"]);
+    assert_eq!(config.diagnostics.print, Some(DiagnosticFormat::Json));
+
+    for (name, format) in [
+        ("plain", DiagnosticFormat::Plain),
+        ("color", DiagnosticFormat::Colored),
+    ] {
+        assert_eq!(
+            self::config(&["--diagnostic-format", name])
+                .diagnostics
+                .print,
+            Some(format)
+        );
+    }
+}
+
+#[test]
+fn options_missing_their_value_are_errors() {
+    assert_eq!(error(&["-e"]), "-e needs the code to run.");
+    assert_eq!(error(&["--allow"]), "--allow needs a warning code.");
+    assert_eq!(
+        error(&["--diagnostic-format"]),
+        "--diagnostic-format needs a format."
+    );
+    assert_eq!(
+        error(&["--diagnostic-format", "xml"]),
+        "Unknown diagnostic format 'xml'; use plain, color or json."
+    );
+}
+
+#[test]
+fn unknown_options_are_errors() {
+    assert_eq!(error(&["--trace", "a.lox"]), "Unknown option '--trace'.");
+}
+
+#[test]
+fn inline_code_conflicts() {
+    assert_eq!(
+        error(&["-e", "1;", "-e", "2;"]),
+        "-e can only be given once."
+    );
+    assert_eq!(
+        error(&["-e", "print 1;", "a.lox"]),
+        "-e can't be combined with a script file."
+    );
+    assert_eq!(
+        error(&["--debug", "-e", "1;"]),
+        "--debug needs a script file."
+    );
+    assert_eq!(
+        error(&["--extract-docs", "-e", "1;"]),
+        "--extract-docs needs a script file."
+    );
+    assert_eq!(
+        error(&["--test", "-e", "1;"]),
+        "--test needs a script file."
+    );
+}
+
+#[test]
+fn repl_conflicts() {
+    for flag in ["--debug", "--time", "--allow-read"] {
+        assert_eq!(
+            error(&[flag]),
+            "--debug, --time and the --allow-* options need a script or -e."
+        );
+    }
+    assert_eq!(
+        error(&["--extract-docs"]),
+        "--extract-docs needs a script file."
+    );
+    assert_eq!(error(&["--test"]), "--test needs a script file.");
+    assert_eq!(
+        error(&["--no-init", "a.lox"]),
+        "--no-init only applies to the REPL."
+    );
+    assert_eq!(
+        error(&["--implicit-globals", "-e", "1;"]),
+        "--implicit-globals only applies to the REPL."
+    );
+}
+
+#[test]
+fn single_script_flags_reject_several_scripts() {
+    for flag in ["--debug", "--extract-docs", "--test"] {
+        assert_eq!(
+            error(&[flag, "a.lox", "b.lox"]),
+            "--debug, --extract-docs and --test take a single script file."
+        );
+    }
+}
+
+#[test]
+fn flags_that_do_not_combine() {
+    assert_eq!(
+        error(&["--extract-docs", "--time", "a.lox"]),
+        "--extract-docs doesn't run the script, so it takes no other options."
+    );
+    assert_eq!(
+        error(&["--test", "--time", "a.lox"]),
+        "--test can't be combined with --debug, --extract-docs or --time."
+    );
+    assert_eq!(
+        error(&["--debug", "--allow-env", "a.lox"]),
+        "--debug can't be combined with the --allow-* options."
+    );
+    assert_eq!(
+        error(&["--debug", "--time", "a.lox"]),
+        "--debug and --time can't be combined."
+    );
+}
@@ -0,0 +1,105 @@
+//! Closures capturing the variables of the scopes around them.
+
+use rlox::testing::{assert_output, run_lox};
+
+#[test]
+fn closures_over_one_variable_share_it() {
+    assert_output(
+        "var increment;\nvar read;\n\
+         fun makeCounter() {\n  var count = 0;\n  \
+         fun inc() { count = count + 1; }\n  fun get() { return count; }\n  \
+         increment = inc;\n  read = get;\n}\n\
+         makeCounter();\nincrement();\nincrement();\nprint read();\n\
+         makeCounter();\nprint read();\n",
+        &["2", "0"],
+    );
+}
+
+#[test]
+fn captured_locals_outlive_their_function() {
+    // The frame of each call is reused once it returns, unless a closure still holds on.
+    assert_output(
+        "fun remember(value) {\n  fun recall() { return value; }\n  return recall;\n}\n\
+         var a = remember(\"a\");\nvar b = remember(\"b\");\nfun other(x) { return x; }\n\
+         other(\"c\");\nprint a();\nprint b();\n",
+        &["a", "b"],
+    );
+}
+
+#[test]
+fn closures_escaping_a_loop_keep_that_iterations_locals() {
+    assert_output(
+        "var saved = [];\n\
+         for (var i = 0; i < 3; i = i + 1) {\n  var copy = i;\n  \
+         fun get() { return copy; }\n  saved.push(get);\n}\n\
+         while (saved.len() > 0) print saved.remove(0)();\n",
+        &["0", "1", "2"],
+    );
+}
+
+#[test]
+fn grandchildren_capture_their_grandparents_locals() {
+    assert_output(
+        "fun outer() {\n  var x = \"outer\";\n  \
+         fun middle() {\n    fun inner() {\n      x = x + \"!\";\n      return x;\n    }\n    \
+         return inner;\n  }\n  var f = middle();\n  f();\n  print x;\n  return f;\n}\n\
+         var f = outer();\nprint f();\n",
+        &["outer!", "outer!!"],
+    );
+}
+
+#[test]
+fn local_functions_in_a_block_can_call_siblings_declared_later() {
+    assert_output(
+        "{\n  fun isEven(n) {\n    if (n == 0) return true;\n    return isOdd(n - 1);\n  }\n  \
+         fun isOdd(n) {\n    if (n == 0) return false;\n    return isEven(n - 1);\n  }\n  \
+         print isEven(10);\n  print isOdd(7);\n}\n",
+        &["true", "true"],
+    );
+}
+
+#[test]
+fn local_functions_in_a_function_body_can_call_siblings_declared_later() {
+    assert_output(
+        "fun outer() {\n  fun first() { return second(); }\n  \
+         fun second() { return \"second\"; }\n  return first();\n}\nprint outer();\n",
+        &["second"],
+    );
+}
+
+#[test]
+fn closures_see_later_assignments_to_what_they_capture() {
+    assert_output(
+        "{\n  var x = 1;\n  fun get() { return x; }\n  x = 2;\n  print get();\n}\n",
+        &["2"],
+    );
+}
+
+#[test]
+fn each_call_of_a_factory_gets_its_own_variables() {
+    assert_output(
+        "fun makeCounter() {\n  var count = 0;\n  \
+         fun increment() {\n    count = count + 1;\n    return count;\n  }\n  \
+         return increment;\n}\n\
+         var counter = makeCounter();\nprint counter();\nprint counter();\n\
+         print makeCounter()();\n",
+        &["1", "2", "1"],
+    );
+}
+
+#[test]
+fn a_local_function_can_shadow_the_one_it_is_declared_in() {
+    let result = run_lox(
+        "fun shadow() {\n  fun shadow() { return \"inner\"; }\n  return shadow();\n}\n\
+         print shadow();\n",
+    );
+
+    assert!(result.error.is_none(), "{:?}", result.error);
+    assert_eq!(result.output, ["inner"]);
+    let codes: Vec<_> = result
+        .diagnostics
+        .iter()
+        .map(|d| (d.code, d.line))
+        .collect();
+    assert_eq!(codes, [("shadowing", 2)]);
+}
@@ -0,0 +1,54 @@
+//! Columns in errors, which tell apart problems on the same line.
+
+use rlox::{
+    testing::{run_output, runtime_error},
+    Interpreter, LoxError, OutputBuffer, Runner,
+};
+
+#[test]
+fn two_syntax_errors_on_one_line() {
+    let diagnostics = match run_output("var a = 1; var = 2; print ;\n") {
+        Err(LoxError::Parse(diagnostics)) => diagnostics,
+        other => panic!("expected a parse error, got {:?}", other),
+    };
+    let positions: Vec<_> = diagnostics.iter().map(|d| (d.line, d.column)).collect();
+    assert_eq!(positions, [(1, 16), (1, 27)]);
+    assert_eq!(diagnostics[0].location, "test.lox:1:16");
+    assert_eq!(diagnostics[1].location, "test.lox:1:27");
+}
+
+#[test]
+fn the_failing_statement_of_two_on_one_line() {
+    assert_eq!(
+        runtime_error("var a = 1;\tprint a; print a + nil;\n"),
+        "test.lox:1:27 Operands must be two numbers or two strings."
+    );
+
+    let error = Runner::new()
+        .run("test.lox", "var é = \"ü\"; print é + 1;\n")
+        .unwrap_err();
+    assert_eq!((error.line(), error.column()), (1, 20));
+}
+
+#[test]
+fn warnings_have_columns_too() {
+    let output = OutputBuffer::new();
+    let mut runner = Runner::with_interpreter(
+        Interpreter::builder()
+            .output(Box::new(output.clone()))
+            .build(),
+    );
+    runner.set_diagnostic_options(rlox::DiagnosticOptions {
+        print: None,
+        ..rlox::DiagnosticOptions::default()
+    });
+    runner
+        .run("test.lox", "var x = 1; if (x = 2) print x;\n")
+        .unwrap();
+    assert_eq!(output.contents(), "2\n");
+    let warning = &runner.diagnostics().diagnostics()[0];
+    assert_eq!((warning.line, warning.column), (1, 16));
+
+    let json = rlox::DiagnosticFormat::Json.render(warning);
+    assert!(json.contains("\"line\":1,\"column\":16,"), "{}", json);
+}
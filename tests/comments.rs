@@ -0,0 +1,181 @@
+//! Attaching comments to statements, and the formatter and doc extractor that use them.
+
+use rlox::{attach_comments, CommentMap, Stmt};
+
+/// The statements parsed from `code` and its comments attached to them.
+fn attach(code: &str) -> (Vec<Stmt>, CommentMap) {
+    let stmts = rlox::parse("test.lox", code).unwrap();
+    let comments = attach_comments(code, &stmts);
+    (stmts, comments)
+}
+
+fn texts(comments: &[rlox::Comment]) -> Vec<&str> {
+    comments
+        .iter()
+        .map(|comment| comment.text.as_str())
+        .collect()
+}
+
+#[test]
+fn a_block_of_comments_leads_the_statement_below_it() {
+    let code = "
+        // Settings.
+        //
+        // Change them with care.
+
+        var retries = 3;
+        var delay = 10;
+    ";
+    let (stmts, comments) = attach(code);
+
+    assert_eq!(
+        texts(comments.leading(&stmts[0])),
+        [" Settings.", "", " Change them with care."]
+    );
+    assert!(comments.leading(&stmts[1]).is_empty());
+    assert!(comments.orphans().is_empty());
+}
+
+#[test]
+fn a_comment_after_code_trails_the_statement_on_its_line() {
+    let code = "
+        var retries = 3; // Enough for a flaky network.
+        fun wait(n) { // Blocks the caller.
+          print n; // Not really waiting.
+        }
+    ";
+    let (stmts, comments) = attach(code);
+
+    let trailing = |stmt| comments.trailing(stmt).map(|comment| comment.text.as_str());
+    assert_eq!(trailing(&stmts[0]), Some(" Enough for a flaky network."));
+    assert_eq!(trailing(&stmts[1]), Some(" Blocks the caller."));
+    let rlox::StmtKind::Function(_, _, body, _) = &stmts[1].kind else {
+        panic!("expected a function, got {:?}", stmts[1]);
+    };
+    assert_eq!(trailing(&body[0]), Some(" Not really waiting."));
+}
+
+#[test]
+fn comments_between_functions_lead_the_next_one() {
+    let code = "
+        fun first() {}
+        // Second, after first.
+        /// Does the second thing.
+        fun second() {}
+
+        // Third.
+        fun third() {}
+    ";
+    let (stmts, comments) = attach(code);
+
+    assert!(comments.leading(&stmts[0]).is_empty());
+    assert_eq!(
+        texts(comments.leading(&stmts[1])),
+        [" Second, after first.", "/ Does the second thing."]
+    );
+    assert_eq!(
+        comments.doc(&stmts[1]).as_deref(),
+        Some("Does the second thing.")
+    );
+    assert_eq!(texts(comments.leading(&stmts[2])), [" Third."]);
+    assert_eq!(comments.doc(&stmts[2]), None);
+}
+
+#[test]
+fn comments_with_no_statement_after_them_are_orphans() {
+    let code = "
+        fun unfinished() {
+          print 1;
+          // TODO: the rest.
+        }
+        print 2;
+        // The end.
+    ";
+    let (stmts, comments) = attach(code);
+
+    assert_eq!(texts(comments.orphans()), [" TODO: the rest.", " The end."]);
+    assert!(comments.leading(&stmts[1]).is_empty());
+    assert!(comments.trailing(&stmts[1]).is_none());
+}
+
+#[test]
+fn comments_in_strings_are_not_comments() {
+    let (stmts, comments) = attach("print \"// not a comment\";");
+    assert!(comments.trailing(&stmts[0]).is_none());
+    assert!(comments.orphans().is_empty());
+}
+
+#[test]
+fn the_formatter_keeps_comments_where_they_were() {
+    let code = "\
+// Settings.
+var retries = 3; // Enough.
+
+/// Waits a while.
+fun wait(n) { // Blocks.
+  if (n > 0) {
+    // Count down.
+    wait(n - 1);
+  } // Done counting.
+  else print n;
+  // Nothing else.
+}
+
+class Timer {
+  // Milliseconds.
+  var delay = 10;
+  /// Starts the timer.
+  start() {}
+  // More to come.
+}
+// The end.
+";
+    let expected = "\
+// Settings.
+var retries = 3; // Enough.
+/// Waits a while.
+fun wait(n) { // Blocks.
+  if (n > 0) {
+    // Count down.
+    wait(n - 1);
+  } // Done counting.
+  else
+    print n;
+  // Nothing else.
+}
+class Timer {
+  // Milliseconds.
+  var delay = 10;
+  /// Starts the timer.
+  start() {}
+  // More to come.
+}
+// The end.
+";
+    let formatted = rlox::format_source("test.lox", code).unwrap();
+    assert_eq!(formatted, expected);
+    assert_eq!(
+        rlox::format_source("test.lox", &formatted).unwrap(),
+        expected
+    );
+}
+
+#[test]
+fn the_doc_extractor_reads_doc_comments_from_the_attached_comments() {
+    let code = "
+        /// A timer.
+        class Timer {
+          // Not documentation.
+          start() {}
+          /// Stops it.
+          stop() {}
+        }
+        /// Separated by a blank line.
+
+        fun undocumented() {}
+    ";
+    assert_eq!(
+        rlox::extract_docs("timer.lox", code).unwrap(),
+        "# timer.lox\n\n## `class Timer`\n\nA timer.\n\n### `Timer.stop()`\n\nStops it.\n"
+    );
+}
@@ -0,0 +1,184 @@
+//! Folding the constants a script opens with before it runs.
+
+use rlox::{Interpreter, OutputBuffer, Runner};
+
+/// The runner the tests use, folding constants or not, with a pure and an impure host native.
+fn runner(fold: bool) -> (Runner, OutputBuffer) {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.set_fold_constants(fold);
+    runner.define_pure_native("square", 1, |_, args| match &args[0] {
+        rlox::Literal::Number(n) => Ok((n * n).into()),
+        other => Err(format!("square() needs a Number, not a {}.", other.type_name()).into()),
+    });
+    runner.define_native("counter", 0, |_, _| Ok(1.into()));
+    (runner, output)
+}
+
+/// `code` as it is run with constants folded, printed back as source.
+fn folded(code: &str) -> String {
+    let (mut runner, _) = runner(true);
+    rlox::to_source(&runner.lowered("test.lox", code).unwrap())
+}
+
+#[test]
+fn opening_constants_are_folded() {
+    let code = "\
+var SECONDS_PER_DAY = 60 * 60 * 24;
+var WEEK = SECONDS_PER_DAY * 7;
+const LABEL = \"per week: \" + format(\"{}\", WEEK);
+var WORD = join(chars(\"abc\"), \"-\");
+var AREA = square(3) + square(4);
+var EMPTY;
+var UNSET = EMPTY == nil and !false;
+print WEEK;
+";
+    assert_eq!(
+        folded(code),
+        "\
+var SECONDS_PER_DAY = 86400;
+var WEEK = 604800;
+const LABEL = \"per week: 604800\";
+var WORD = \"a-b-c\";
+var AREA = 25;
+var EMPTY;
+var UNSET = true;
+print WEEK;
+"
+    );
+}
+
+#[test]
+fn folding_stops_at_the_first_statement_it_cant_fold() {
+    let code = "\
+var A = 1 + 1;
+var B = counter();
+var C = A + 1;
+";
+    assert_eq!(
+        folded(code),
+        "var A = 2;\nvar B = counter();\nvar C = A + 1;\n"
+    );
+
+    let code = "var A = 1 + 1;\nprint A;\nvar C = A + 1;\n";
+    assert_eq!(folded(code), "var A = 2;\nprint A;\nvar C = A + 1;\n");
+
+    // Arrays and maps are made anew by each run of their declaration.
+    let code = "var LETTERS = chars(\"ab\");\nvar N = 1 + 1;\n";
+    assert_eq!(
+        folded(code),
+        "var LETTERS = chars(\"ab\");\nvar N = 1 + 1;\n"
+    );
+}
+
+#[test]
+fn natives_the_script_declares_over_are_not_called() {
+    let code = "var V = version();\nfun version() { return \"mine\"; }\n";
+    assert_eq!(
+        folded(code),
+        "var V = version();\nfun version() {\n  return \"mine\";\n}\n"
+    );
+}
+
+#[test]
+fn failing_initializers_are_left_to_fail_at_runtime() {
+    let code = "var OK = 2 * 3;\nvar BAD = square(\"three\");\nvar AFTER = 1 + 1;\n";
+    assert_eq!(
+        folded(code),
+        "var OK = 6;\nvar BAD = square(\"three\");\nvar AFTER = 1 + 1;\n"
+    );
+
+    let (mut runner, _) = runner(true);
+    let error = runner.run("test.lox", code).unwrap_err().to_string();
+    assert!(
+        error.contains("test.lox:2:11 square() needs a Number, not a String."),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn folding_doesnt_change_what_scripts_do() {
+    let programs = [
+        "var A = 60 * 60 * 24; var B = A / 2; print A; print B;",
+        "var S = \"n=\" + format(\"{}\", 3); print S; S = \"changed\"; print S;",
+        "var X = square(12); print X; var Y = counter(); print X + Y;",
+        "var A = 1; var A = A + 1; print A;",
+        "var Z = 1 / 0; print Z; print -Z;",
+        "var BAD = square(nil); print \"unreachable\";",
+        "var E = nil + 1;",
+        "var N = version(); print N == version();",
+        "var V = version(); fun version() { return \"mine\"; } print V;",
+        "var T = true and \"yes\"; var F = nil or 0; print T; print F;",
+    ];
+
+    for program in programs {
+        let run = |fold: bool| {
+            let (mut runner, output) = runner(fold);
+            let result = runner.run("test.lox", program).map_err(|e| e.to_string());
+            (result, output.contents())
+        };
+        assert_eq!(run(true), run(false), "{}", program);
+    }
+}
+
+#[test]
+fn numbers_that_arent_finite_are_left_as_written() {
+    let code = "\
+var INF = 1 / 0;
+var NAN = 0 / 0;
+var NEG = -INF;
+var BIG = INF > 1;
+";
+    let source = folded(code);
+    assert_eq!(
+        source,
+        "\
+var INF = 1 / 0;
+var NAN = 0 / 0;
+var NEG = -INF;
+var BIG = true;
+"
+    );
+    assert!(rlox::parse("folded.lox", &source).is_ok());
+}
+
+#[test]
+fn folding_does_no_work_the_script_can_see() {
+    let (mut runner, _) = runner(true);
+    runner
+        .lowered("test.lox", "var A = square(3) + 60 * 60;")
+        .unwrap();
+
+    assert_eq!(runner.take_stats(), rlox::ExecStats::default());
+}
+
+#[test]
+fn lenient_operations_are_left_to_warn_when_they_run() {
+    let (mut runner, output) = runner(true);
+    runner.set_diagnostic_options(rlox::DiagnosticOptions {
+        print: None,
+        ..rlox::DiagnosticOptions::default()
+    });
+    runner.set_lenient(true);
+
+    let code = "var N = nil + 1;\nvar M = N * 2;\nprint M;\n";
+    assert_eq!(
+        rlox::to_source(&runner.lowered("lowered.lox", code).unwrap()),
+        code
+    );
+    assert!(runner.diagnostics().diagnostics().is_empty());
+
+    runner.run("test.lox", code).unwrap();
+    assert_eq!(output.contents(), "2\n");
+    let warnings: Vec<_> = runner
+        .diagnostics()
+        .diagnostics()
+        .iter()
+        .map(|diagnostic| (diagnostic.code, diagnostic.location.as_str()))
+        .collect();
+    assert_eq!(warnings, [("lenient-operation", "test.lox:1:9")]);
+}
@@ -0,0 +1,76 @@
+//! `const` declarations, which can't be assigned to after they are initialized.
+
+use rlox::{
+    testing::{run_output, runtime_error, syntax_error},
+    Interpreter, LoxError, OutputBuffer, Runner,
+};
+
+#[test]
+fn reassigning_a_constant_fails() {
+    assert_eq!(
+        runtime_error("const x = 5;\nx = 6;\n"),
+        "test.lox:2:1 Cannot assign to constant 'x'."
+    );
+    assert_eq!(
+        runtime_error("const x = 5;\n{\n  x = 6;\n}\n"),
+        "test.lox:3:3 Cannot assign to constant 'x'."
+    );
+    assert_eq!(
+        runtime_error("fun f() {\n  const z = 1;\n  z = 2;\n}\nf();\n"),
+        "test.lox:3:3 Cannot assign to constant 'z'."
+    );
+}
+
+#[test]
+fn a_constant_can_be_shadowed() {
+    let code =
+        "const x = 5;\n{\n  const x = 1;\n  print x;\n  var y = x + 1;\n  print y;\n}\nprint x;\n";
+    assert_eq!(run_output(code).unwrap(), "1\n2\n5\n");
+}
+
+#[test]
+fn a_constant_needs_an_initializer() {
+    assert_eq!(
+        syntax_error("const x;"),
+        "Parser Error: test.lox:1:8 Constant 'x' must be initialized."
+    );
+}
+
+#[test]
+fn parameters_cant_be_constant() {
+    assert_eq!(
+        syntax_error("fun f(const a) {}"),
+        "Parser Error: test.lox:1:7 Function parameters can't be declared const."
+    );
+}
+
+#[test]
+fn constants_in_for_initializers() {
+    assert_eq!(
+        run_output("var n = 0;\nfor (const step = 2; n < 6; n = n + step) print n;\n").unwrap(),
+        "0\n2\n4\n"
+    );
+    assert_eq!(
+        runtime_error("for (const i = 0; i < 2; i = i + 1) print i;\n"),
+        "test.lox:1:26 Cannot assign to constant 'i'."
+    );
+}
+
+#[test]
+fn repl_constants_stay_constant_across_inputs() {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.run_repl_line("const limit = 1;").unwrap();
+
+    match runner.run_repl_line("limit = 2;") {
+        Err(LoxError::Runtime(diagnostic)) => {
+            assert_eq!(diagnostic.message, "Cannot assign to constant 'limit'.")
+        }
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+    runner.run_repl_line("print limit;").unwrap();
+    assert_eq!(output.contents(), "limit = 1\n1\n");
+}
@@ -0,0 +1,70 @@
+//! `Debug` for environments and functions, which stops at the closure's own scope.
+
+use rlox::Runner;
+
+/// The debug print of the global `name` after running `code`.
+fn debug_global(code: &str, name: &str) -> String {
+    let mut runner = Runner::new();
+    runner.run("test.lox", code).unwrap();
+    let value = runner.interpreter().get_global(name).unwrap();
+    format!("{:?}", value)
+}
+
+#[test]
+fn a_closure_shows_its_own_scope_and_counts_the_rest() {
+    let code = "
+        fun outer() {
+          var a = 1;
+          fun middle() {
+            var b = \"two\";
+            fun inner() {
+              var c = 3;
+              fun leaf() { return a + b + c; }
+              return leaf;
+            }
+            return inner;
+          }
+          return middle;
+        }
+        var leaf = outer()()();
+    ";
+
+    assert_eq!(
+        debug_global(code, "leaf"),
+        "Callable(Function(LoxFunction { name: \"leaf\", line: 8, params: [], closure: \
+         Environment { values: {\"c\": Number(3.0), \"leaf\": <fn leaf>}, \
+         enclosing: ...(3 enclosing scopes) }, .. }))"
+    );
+}
+
+#[test]
+fn a_function_capturing_itself_prints_without_recursing() {
+    let code = "
+        fun countdown(n) {
+          var again = countdown;
+          fun step() { return again(n - 1); }
+          return step;
+        }
+        var step = countdown(3);
+    ";
+
+    let debug = debug_global(code, "step");
+    assert!(
+        debug.contains("closure: Environment { values: {\"again\": <fn countdown>, \"n\": Number(3.0), \"step\": <fn step>}, enclosing: ...(1 enclosing scope) }"),
+        "{}",
+        debug
+    );
+    assert!(debug.len() < 300, "{}", debug);
+}
+
+#[test]
+fn the_globals_show_callables_by_name() {
+    let code = "fun f() { return f; } var self = f;";
+    let mut runner = Runner::new();
+    runner.run("test.lox", code).unwrap();
+    let debug = format!("{:?}", runner.interpreter().get_global("self").unwrap());
+
+    assert!(debug.contains("\"f\": <fn f>"), "{}", debug);
+    assert!(debug.contains("\"self\": <fn f>"), "{}", debug);
+    assert!(!debug.contains("enclosing"), "{}", debug);
+}
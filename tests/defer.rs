@@ -0,0 +1,159 @@
+//! `defer` statements, which run when the function or script registering them finishes.
+
+use rlox::{
+    testing::{run_output, runtime_error},
+    DiagnosticOptions, Interpreter, LoxError, OutputBuffer, Runner,
+};
+
+/// Runs `code`, returning what it printed, how it failed if it did, and the codes and
+/// messages of the diagnostics reported along the way.
+fn run_to_end(code: &str) -> (String, Option<String>, Vec<String>) {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.set_diagnostic_options(DiagnosticOptions {
+        print: None,
+        ..DiagnosticOptions::default()
+    });
+
+    let error = match runner.run("test.lox", code) {
+        Ok(()) => None,
+        Err(LoxError::Runtime(diagnostic)) => Some(format!(
+            "{} {}",
+            diagnostic.location.unwrap_or_default(),
+            diagnostic.message
+        )),
+        Err(e) => panic!("expected {:?} to run, got {:?}", code, e),
+    };
+    let diagnostics = runner
+        .diagnostics()
+        .diagnostics()
+        .iter()
+        .map(|diagnostic| format!("[{}] {}", diagnostic.code, diagnostic))
+        .collect();
+    (output.contents(), error, diagnostics)
+}
+
+#[test]
+fn deferred_statements_run_last_first_when_the_function_ends() {
+    let code = "fun work() {\n  defer print \"first registered\";\n  defer { print \"second registered\"; };\n  print \"working\";\n}\nwork();\nprint \"after\";\n";
+    assert_eq!(
+        run_output(code).unwrap(),
+        "working\nsecond registered\nfirst registered\nafter\n"
+    );
+}
+
+#[test]
+fn deferred_statements_run_on_an_early_return() {
+    let code = "fun open(early) {\n  print \"open\";\n  defer print \"close\";\n  if (early) return \"early\";\n  print \"read\";\n  return \"done\";\n}\nprint open(true);\nprint open(false);\n";
+    assert_eq!(
+        run_output(code).unwrap(),
+        "open\nclose\nearly\nopen\nread\nclose\ndone\n"
+    );
+}
+
+#[test]
+fn deferred_statements_run_when_an_error_unwinds_through() {
+    let code = "fun inner() {\n  defer print \"inner cleanup\";\n  return -\"text\";\n}\nfun outer() {\n  defer print \"outer cleanup\";\n  inner();\n  print \"not reached\";\n}\nouter();\n";
+    let (output, error, diagnostics) = run_to_end(code);
+
+    assert_eq!(output, "inner cleanup\nouter cleanup\n");
+    assert_eq!(
+        error.unwrap(),
+        "test.lox:3:11 Operand of '-' must be a number, but it is a String (from expression `\"text\"`)."
+    );
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+#[test]
+fn a_failing_deferred_statement_doesnt_hide_the_original_error() {
+    let code = "fun work() {\n  defer print \"still runs\";\n  defer print nil + 1;\n  print -\"text\";\n}\nwork();\n";
+    let (output, error, diagnostics) = run_to_end(code);
+
+    assert_eq!(output, "still runs\n");
+    assert_eq!(
+        error.unwrap(),
+        "test.lox:4:10 Operand of '-' must be a number, but it is a String (from expression `\"text\"`)."
+    );
+    assert_eq!(diagnostics.len(), 1);
+    assert!(
+        diagnostics[0].starts_with("[deferred-error] test.lox:3:15 Deferred statement failed:"),
+        "{}",
+        diagnostics[0]
+    );
+}
+
+#[test]
+fn a_failing_deferred_statement_fails_a_function_that_succeeded() {
+    let code = "fun work() {\n  defer print \"still runs\";\n  defer print nil + 1;\n  return 1;\n}\nprint work();\n";
+    let (output, error, diagnostics) = run_to_end(code);
+
+    assert_eq!(output, "still runs\n");
+    assert!(error.unwrap().starts_with("test.lox:3:15 "));
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+#[test]
+fn top_level_deferred_statements_run_when_the_script_ends() {
+    assert_eq!(
+        run_output("defer print \"end\";\nprint \"start\";\n").unwrap(),
+        "start\nend\n"
+    );
+
+    let (output, error, _) =
+        run_to_end("defer print \"end\";\nprint -\"text\";\nprint \"skipped\";\n");
+    assert_eq!(output, "end\n");
+    assert!(error.unwrap().starts_with("test.lox:2:8 "));
+}
+
+#[test]
+fn deferred_statements_see_the_scope_they_were_registered_in() {
+    let code = "fun count() {\n  for (var i = 0; i < 3; i = i + 1) {\n    var n = i;\n    defer print n;\n  }\n  print \"counted\";\n}\ncount();\n";
+    assert_eq!(run_output(code).unwrap(), "counted\n2\n1\n0\n");
+}
+
+#[test]
+fn deferred_statements_run_after_a_returned_call() {
+    let code = "fun next() { print \"next\"; return 1; }\nfun first() {\n  defer print \"first done\";\n  return next();\n}\nprint first();\n";
+    assert_eq!(run_output(code).unwrap(), "next\nfirst done\n1\n");
+
+    let code = "fun count(n) {\n  defer print n;\n  if (n == 0) return 0;\n  return count(n - 1);\n}\ncount(2);\n";
+    assert_eq!(run_output(code).unwrap(), "0\n1\n2\n");
+}
+
+#[test]
+fn a_resource_deferred_closed_stays_open_for_the_returned_call() {
+    let code = "var open = false;\nfun close() { open = false; print \"closed\"; }\nfun parse() { print open; return \"parsed\"; }\nfun load() {\n  open = true;\n  defer close();\n  return parse();\n}\nprint load();\n";
+    assert_eq!(run_output(code).unwrap(), "true\nclosed\nparsed\n");
+}
+
+#[test]
+fn deferred_statements_cant_return() {
+    let code = "fun f() {\n  defer { return 1; }\n}\n";
+    match run_output(code) {
+        Err(LoxError::Resolve(diagnostics)) => {
+            assert_eq!(
+                diagnostics[0].message,
+                "Can't return from a deferred statement."
+            )
+        }
+        other => panic!("expected a resolve error, got {:?}", other),
+    }
+    // A function declared in a deferred block returns from itself.
+    assert_eq!(
+        run_output("defer { fun f() { return 2; } print f(); }\n").unwrap(),
+        "2\n"
+    );
+}
+
+#[test]
+fn defer_needs_a_statement() {
+    assert!(matches!(
+        run_output("fun f() { defer }"),
+        Err(LoxError::Parse(_))
+    ));
+    assert!(runtime_error("fun f() { defer print x; }\nf();")
+        .starts_with("test.lox:1:23 Undefined variable 'x'."));
+}
@@ -0,0 +1,62 @@
+//! Statements with nothing in them: empty statements, `print;`, `()` and their kin.
+
+use rlox::testing::{run_output, syntax_error};
+
+#[test]
+fn empty_statements_do_nothing() {
+    let cases = [
+        (";", ""),
+        (";;print 1;", "1\n"),
+        ("print 1;;", "1\n"),
+        ("{ ; }", ""),
+        ("fun f() { ; return 2; }\nprint f();", "2\n"),
+        ("if (true) ; else print 2;", ""),
+        ("if (false) ; else print 2;", "2\n"),
+        ("var i = 0;\nwhile ((i = i + 1) < 3) ;\nprint i;", "3\n"),
+        ("for (var i = 0; i < 3; i = i + 1) ;", ""),
+    ];
+    for (code, expected) in cases {
+        assert_eq!(run_output(code).unwrap(), expected, "{:?}", code);
+    }
+}
+
+#[test]
+fn missing_expressions_are_reported_where_they_belong() {
+    let cases = [
+        ("print;", "1:6 'print' requires an expression."),
+        ("print\n;", "2:1 'print' requires an expression."),
+        ("{ print; }", "1:8 'print' requires an expression."),
+        ("();", "1:2 Expect expression inside parentheses."),
+        ("var x = ();", "1:10 Expect expression inside parentheses."),
+        ("print () + 1;", "1:8 Expect expression inside parentheses."),
+        (
+            "var a = (1 + ());",
+            "1:15 Expect expression inside parentheses.",
+        ),
+        ("f(());", "1:4 Expect expression inside parentheses."),
+        ("print (;", "1:8 Expect expression, found ';'."),
+        ("var x = ;", "1:9 Expect expression, found ';'."),
+        ("x = ;", "1:5 Expect expression, found ';'."),
+        ("if () print 1;", "1:5 Expect expression, found ')'."),
+        ("print 1 +;", "1:10 Expect expression, found ';'."),
+    ];
+    for (code, expected) in cases {
+        assert_eq!(
+            syntax_error(code),
+            format!("Parser Error: test.lox:{}", expected),
+            "{:?}",
+            code
+        );
+    }
+}
+
+#[test]
+fn each_missing_expression_is_reported_once() {
+    assert_eq!(
+        syntax_error("f((), 1 +);\nprint;"),
+        "\
+Parser Error: test.lox:1:4 Expect expression inside parentheses.
+Parser Error: test.lox:1:10 Expect expression, found ')'.
+Parser Error: test.lox:2:6 'print' requires an expression."
+    );
+}
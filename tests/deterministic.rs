@@ -0,0 +1,60 @@
+//! The interpreter's deterministic mode: a frozen clock, a seeded `random()` and scripted
+//! input.
+
+use rlox::{
+    testing::{assert_output, run_lox},
+    Interpreter, InterpreterBuilder, OutputBuffer, Runner,
+};
+
+/// What `code` prints in an interpreter configured by `configure`.
+fn run(configure: impl FnOnce(InterpreterBuilder) -> InterpreterBuilder, code: &str) -> String {
+    let output = OutputBuffer::new();
+    let interpreter = configure(Interpreter::builder().output(Box::new(output.clone()))).build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.run("test.lox", code).unwrap();
+    output.contents()
+}
+
+#[test]
+fn frozen_time_starts_at_the_epoch_and_ticks_once_a_second() {
+    assert_eq!(
+        run(
+            |builder| builder.frozen_time(),
+            "print clock();\nprint clock();\nprint now();"
+        ),
+        "0\n1\n2000\n"
+    );
+}
+
+#[test]
+fn the_same_seed_gives_the_same_numbers() {
+    let code = "for (var i = 0; i < 5; i = i + 1) print random();";
+    let first = run(|builder| builder.seed(42), code);
+
+    assert_eq!(first, run(|builder| builder.seed(42), code));
+    assert_ne!(first, run(|builder| builder.seed(43), code));
+    assert!(first
+        .lines()
+        .all(|n| (0.0..1.0).contains(&n.parse::<f64>().unwrap())));
+}
+
+#[test]
+fn read_line_returns_the_inputs_then_nil() {
+    assert_eq!(
+        run(
+            |builder| builder.inputs(vec!["first".into(), "second".into()]),
+            "print readLine();\nprint readLine();\nprint readLine();"
+        ),
+        "first\nsecond\nnil\n"
+    );
+}
+
+#[test]
+fn the_test_harness_is_deterministic() {
+    let code = "print clock();\nprint clock();\nprint readLine();\nprint random();";
+    let first = run_lox(code).output;
+
+    assert_eq!(first[..3], ["0", "1", "nil"]);
+    assert_eq!(first, run_lox(code).output);
+    assert_output(code, &first.iter().map(String::as_str).collect::<Vec<_>>());
+}
@@ -0,0 +1,135 @@
+//! The command-line flags that control warnings: `--deny-warnings`, `--allow <code>` and
+//! `--diagnostic-format`.
+
+use std::process::Command;
+
+/// A script with a `shadowing` warning on its inner `a`, which prints 2 if it runs.
+const SHADOWING: &str = "var a = 1; { var a = 2; print a; }";
+
+const WARNING: &str = "<inline>:1:18 'a' shadows the 'a' declared at <inline>:1:5. Rename one \
+                       of them if this is a typo, or run with --allow shadowing if it is on \
+                       purpose.";
+
+/// Runs `code` with `flags`, returning the exit status, stdout and stderr.
+fn lox(flags: &[&str], code: &str) -> (i32, String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_lox"))
+        .args(flags)
+        .arg("-e")
+        .arg(code)
+        .output()
+        .unwrap();
+    (
+        output.status.code().unwrap(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn warnings_are_printed_and_the_script_runs() {
+    assert_eq!(
+        lox(&[], SHADOWING),
+        (
+            0,
+            "2\n".into(),
+            format!("Warning[shadowing]: {}\n", WARNING)
+        )
+    );
+}
+
+#[test]
+fn deny_warnings_stops_the_script() {
+    assert_eq!(
+        lox(&["--deny-warnings"], SHADOWING),
+        (
+            65,
+            String::new(),
+            format!("Denied Warning[shadowing]: {}\n", WARNING)
+        )
+    );
+}
+
+#[test]
+fn deny_warnings_lets_a_clean_script_run() {
+    assert_eq!(
+        lox(&["--deny-warnings"], "print 1;"),
+        (0, "1\n".into(), String::new())
+    );
+}
+
+#[test]
+fn allowed_codes_are_dropped() {
+    let silent = (0, "2\n".into(), String::new());
+    assert_eq!(lox(&["--allow", "shadowing"], SHADOWING), silent);
+    assert_eq!(
+        lox(&["--deny-warnings", "--allow", "shadowing"], SHADOWING),
+        silent
+    );
+}
+
+#[test]
+fn allowing_another_code_keeps_the_warning() {
+    let (status, _, stderr) = lox(
+        &["--deny-warnings", "--allow", "unused-variable"],
+        SHADOWING,
+    );
+    assert_eq!(status, 65);
+    assert!(
+        stderr.starts_with("Denied Warning[shadowing]"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn json_puts_each_diagnostic_on_a_line_of_its_own() {
+    let json = "{\"severity\":\"warning\",\"code\":\"shadowing\",\"location\":\"<inline>:1:18\",\
+                \"line\":1,\"column\":18,\"message\":\"'a' shadows the 'a' declared at \
+                <inline>:1:5.\",\"help\":\"Rename one of them if this is a typo, or run with \
+                --allow shadowing if it is on purpose.\"}\n";
+
+    assert_eq!(
+        lox(&["--diagnostic-format", "json"], SHADOWING),
+        (0, "2\n".into(), json.into())
+    );
+    assert_eq!(
+        lox(
+            &["--diagnostic-format", "json", "--deny-warnings"],
+            SHADOWING
+        ),
+        (65, String::new(), json.into())
+    );
+    assert_eq!(
+        lox(&["--diagnostic-format", "json"], "var = 1;"),
+        (
+            65,
+            String::new(),
+            "{\"severity\":\"error\",\"code\":\"syntax\",\"location\":\"<inline>:1:5\",\
+             \"line\":1,\"column\":5,\"message\":\"Expect variable name.\",\"help\":null}\n"
+                .into()
+        )
+    );
+}
+
+#[test]
+fn color_highlights_the_severity() {
+    assert_eq!(
+        lox(&["--diagnostic-format", "color"], SHADOWING),
+        (
+            0,
+            "2\n".into(),
+            format!("\x1b[1;33mWarning[shadowing]\x1b[0m: {}\n", WARNING)
+        )
+    );
+}
+
+#[test]
+fn an_unknown_format_is_a_usage_error() {
+    let (status, stdout, stderr) = lox(&["--diagnostic-format", "xml"], SHADOWING);
+    assert_eq!((status, stdout.as_str()), (64, ""));
+    assert!(
+        stderr.starts_with("Unknown diagnostic format 'xml'; use plain, color or json.\n"),
+        "{}",
+        stderr
+    );
+}
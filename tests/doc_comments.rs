@@ -0,0 +1,147 @@
+//! `///` doc comments on functions and classes, as `help()`, `:doc` and `--extract-docs`
+//! show them.
+
+use rlox::{
+    cli::{self, Command},
+    testing::assert_output,
+    Runner,
+};
+
+#[test]
+fn consecutive_doc_lines_are_joined() {
+    assert_output(
+        "/// Adds two numbers.\n///\n/// Both must be numbers.\nfun add(a, b) { return a + b; }\nhelp(add);",
+        &[
+            "fun add(a, b), declared at line 4",
+            "Adds two numbers.",
+            "",
+            "Both must be numbers.",
+        ],
+    );
+}
+
+#[test]
+fn doc_comments_must_touch_the_declaration() {
+    assert_output(
+        "/// Detached.\n\nfun f() {}\nhelp(f);",
+        &["fun f(), declared at line 3"],
+    );
+    assert_output(
+        "/// Detached.\n// An ordinary comment.\nfun f() {}\nhelp(f);",
+        &["fun f(), declared at line 3"],
+    );
+    assert_output(
+        "// Ordinary.\nfun f() {}\nhelp(f);",
+        &["fun f(), declared at line 2"],
+    );
+}
+
+#[test]
+fn classes_and_methods_have_docs() {
+    let code = "/// A point in the plane.\nclass Point {\n  init(x, y) { this.x = x; this.y = y; }\n\n  /// How far the point is from the origin,\n  /// squared.\n  norm() { return this.x * this.x + this.y * this.y; }\n}\n";
+    assert_output(
+        &format!("{}help(Point);\nhelp(Point(1, 2).norm);", code),
+        &[
+            "Point (arity 2), methods: init, norm",
+            "A point in the plane.",
+            "fun norm(), declared at line 7",
+            "How far the point is from the origin,",
+            "squared.",
+        ],
+    );
+
+    let mut runner = Runner::new();
+    runner.run("point.lox", code).unwrap();
+    assert_eq!(
+        runner.describe("Point.norm").unwrap(),
+        "fun norm(), declared at line 7\nHow far the point is from the origin,\nsquared."
+    );
+    assert_eq!(
+        runner.describe("Point.init").unwrap(),
+        "fun init(x, y), declared at line 3"
+    );
+    assert!(runner.describe("Point.missing").is_none());
+    assert!(runner.describe("norm").is_none());
+}
+
+#[test]
+fn the_prelude_is_documented() {
+    let description = Runner::new().describe("clamp").unwrap();
+    assert!(
+        description.starts_with("fun clamp(value, low, high), declared at line "),
+        "{}",
+        description
+    );
+    assert!(
+        description.ends_with("\nReturns `value` limited to the range from `low` to `high`."),
+        "{}",
+        description
+    );
+}
+
+#[test]
+fn documented_declarations_are_extracted_as_markdown() {
+    let code = "\
+/// Adds two numbers.
+fun add(a, b) { return a + b; }
+
+fun undocumented() {}
+
+/// A shape.
+class Shape {}
+
+class Square < Shape {
+  init(side) { this.side = side; }
+
+  /// The area of the square.
+  area() { return this.side * this.side; }
+}
+
+class Plain { method() {} }
+";
+    assert_eq!(
+        rlox::extract_docs("shapes.lox", code).unwrap(),
+        "\
+# shapes.lox
+
+## `fun add(a, b)`
+
+Adds two numbers.
+
+## `class Shape`
+
+A shape.
+
+## `class Square < Shape`
+
+### `Square.area()`
+
+The area of the square.
+"
+    );
+
+    assert_eq!(
+        rlox::extract_docs("empty.lox", "fun f() {}").unwrap(),
+        "# empty.lox\n\nNothing is documented.\n"
+    );
+    assert!(rlox::extract_docs("broken.lox", "fun f( {}").is_err());
+}
+
+#[test]
+fn extracting_docs_needs_a_script() {
+    let parse = |args: &[&str]| cli::parse_args(args.iter().map(|arg| arg.to_string()).collect());
+
+    match parse(&["--extract-docs", "lib.lox"]) {
+        Ok(Command::Run(config)) => assert!(config.extract_docs),
+        other => panic!("expected a run, got {:?}", other),
+    }
+    assert_eq!(
+        parse(&["--extract-docs"]),
+        Err("--extract-docs needs a script file.".to_string())
+    );
+    assert_eq!(
+        parse(&["--extract-docs", "-e", "print 1;"]),
+        Err("--extract-docs needs a script file.".to_string())
+    );
+    assert!(parse(&["--extract-docs", "--time", "lib.lox"]).is_err());
+}
@@ -0,0 +1,71 @@
+//! `enum` declarations and their variants.
+
+use rlox::testing::{run_output, runtime_error};
+
+const DECLARATIONS: &str = "enum Color { Red, Green, Blue }\nenum Light { Red, Off }\n";
+
+/// Runs `code` after the `Color` and `Light` declarations.
+fn with_enums(code: &str) -> String {
+    run_output(&format!("{}{}", DECLARATIONS, code)).unwrap()
+}
+
+#[test]
+fn variants_print_with_their_enum() {
+    assert_eq!(
+        with_enums("print Color.Red;\nprint Color;\nprint Color.values();\nprint [Light.Off];"),
+        "Color.Red\n<enum Color>\n[Color.Red, Color.Green, Color.Blue]\n[Light.Off]\n"
+    );
+}
+
+#[test]
+fn variants_are_equal_only_to_themselves() {
+    assert_eq!(
+        with_enums(
+            "var c = Color.Red;\nprint c == Color.Red;\nprint Color.Red != Color.Green;\n\
+             print Color.Red == Light.Red;\nprint Color.Red == \"Color.Red\";\n\
+             print Color.Red == 0;\nprint Color == Color;\nprint Color == Light;"
+        ),
+        "true\ntrue\nfalse\nfalse\nfalse\ntrue\nfalse\n"
+    );
+}
+
+#[test]
+fn declaring_an_enum_again_makes_new_variants() {
+    assert_eq!(
+        run_output("enum E { A }\nvar old = E.A;\nenum E { A }\nprint old == E.A;\nprint old;")
+            .unwrap(),
+        "false\nE.A\n"
+    );
+}
+
+#[test]
+fn membership_and_introspection() {
+    assert_eq!(
+        with_enums(
+            "print Color.contains(Color.Blue);\nprint Color.contains(Light.Red);\n\
+             print Color.contains(\"Red\");\nprint Color.Blue.name();\nprint Color.Blue.ordinal();"
+        ),
+        "true\nfalse\nfalse\nBlue\n2\n"
+    );
+}
+
+#[test]
+fn variants_work_in_match() {
+    assert_eq!(
+        with_enums(
+            "fun action(light) {\n  return match (light) {\n    Color.Red -> \"stop\";\n    \
+             Light.Red -> \"wrong red\";\n    Color.Green -> \"go\";\n    _ -> \"wait\";\n  };\n}\n\
+             print action(Color.Red);\nprint action(Light.Red);\nprint action(Color.Green);\n\
+             print action(Color.Blue);"
+        ),
+        "stop\nwrong red\ngo\nwait\n"
+    );
+}
+
+#[test]
+fn unknown_variants_are_errors() {
+    assert_eq!(
+        runtime_error(&format!("{}print Color.Purple;", DECLARATIONS)),
+        "test.lox:3:7 Enum Color has no variant 'Purple'."
+    );
+}
@@ -0,0 +1,44 @@
+//! Call environments are recycled once a call returns, but never while a closure made in the
+//! call still holds on to one.
+
+use rlox::testing::assert_output;
+
+#[test]
+fn captured_frames_survive_calls_that_recycle_others() {
+    // Every third call captures its frame; the others hand theirs back to the pool, and the
+    // calls in between would overwrite any captured frame that was wrongly recycled.
+    assert_output(
+        "var saved = [];\n\
+         fun visit(n) {\n  var label = \"call \" + n.toString();\n  \
+         if (n - (n / 3).floor() * 3 == 0) {\n    \
+         fun get() { return label; }\n    saved.push(get);\n  }\n  return label;\n}\n\
+         fun churn(n) { var local = n * 100; return local; }\n\
+         for (var i = 0; i < 10; i = i + 1) {\n  visit(i);\n  churn(i);\n}\n\
+         while (saved.len() > 0) print saved.remove(0)();\n",
+        &["call 0", "call 3", "call 6", "call 9"],
+    );
+}
+
+#[test]
+fn a_captured_frame_stays_live_after_later_calls() {
+    assert_output(
+        "fun counter() {\n  var count = 0;\n  fun next() { count = count + 1; return count; }\n  \
+         return next;\n}\n\
+         fun noise(a, b) { var c = a + b; return c; }\n\
+         var first = counter();\nnoise(1, 2);\nvar second = counter();\nnoise(3, 4);\n\
+         first();\nfirst();\nnoise(5, 6);\nprint first();\nprint second();\n",
+        &["3", "1"],
+    );
+}
+
+#[test]
+fn a_frame_released_mid_recursion_isnt_shared() {
+    assert_output(
+        "fun build(n) {\n  if (n == 0) return nil;\n  var rest = build(n - 1);\n  \
+         fun head() { return n; }\n  fun tail() { return rest; }\n  \
+         return [head, tail];\n}\n\
+         var list = build(3);\n\
+         while (list != nil) {\n  print list.remove(0)();\n  list = list.remove(0)();\n}\n",
+        &["3", "2", "1"],
+    );
+}
@@ -0,0 +1,141 @@
+//! Which `LoxError` a failing script ends in, what it lists and the exit status it maps
+//! to.
+
+use rlox::{CapabilitySet, DiagnosticOptions, Interpreter, LoxError, OutputBuffer, Runner};
+
+/// Runs `code` as a script, returning what it printed and how it ended.
+fn run(code: &str) -> (String, Result<(), LoxError>) {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .no_prelude()
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.set_diagnostic_options(DiagnosticOptions {
+        print: None,
+        ..DiagnosticOptions::default()
+    });
+    let result = runner.run("test.lox", code);
+    (output.contents(), result)
+}
+
+#[test]
+fn a_missing_script_is_an_io_error() {
+    let error = rlox::run_file(
+        "/nonexistent/rlox/script.lox".into(),
+        false,
+        CapabilitySet::default(),
+        false,
+        DiagnosticOptions::default(),
+    )
+    .unwrap_err();
+
+    assert!(matches!(error, LoxError::Io(_)), "{:?}", error);
+    assert_eq!(error.exit_code(), 66);
+}
+
+#[test]
+fn lexing_errors_are_all_listed_and_nothing_runs() {
+    let (output, result) = run("print 1;\nvar a = #;\nvar b = @;");
+
+    match result {
+        Err(error) => {
+            let LoxError::Lex(diagnostics) = &error else {
+                panic!("expected a {} error, got {:?}", "lex", error)
+            };
+            assert_eq!(
+                diagnostics.iter().map(|d| d.line).collect::<Vec<_>>(),
+                [2, 3]
+            );
+            assert_eq!(error.exit_code(), 65);
+        }
+        Ok(()) => panic!("the script ran to the end"),
+    }
+    assert_eq!(output, "");
+}
+
+#[test]
+fn parsing_errors_are_all_listed_and_nothing_runs() {
+    let (output, result) = run("print 1;\nvar = 2;\nprint 3;\nfun f( {}\nprint 4;");
+
+    match result {
+        Err(error) => {
+            let LoxError::Parse(diagnostics) = &error else {
+                panic!("expected a {} error, got {:?}", "parse", error)
+            };
+            assert_eq!(
+                diagnostics.iter().map(|d| d.line).collect::<Vec<_>>(),
+                [2, 4]
+            );
+            assert_eq!(error.exit_code(), 65);
+        }
+        Ok(()) => panic!("the script ran to the end"),
+    }
+    assert_eq!(output, "");
+}
+
+#[test]
+fn resolving_errors_stop_the_script_before_it_runs() {
+    let (output, result) = run("print 1;\n{ var a = a; }\nreturn 2;");
+
+    match result {
+        Err(error) => {
+            let LoxError::Resolve(diagnostics) = &error else {
+                panic!("expected a {} error, got {:?}", "resolve", error)
+            };
+            assert_eq!(diagnostics.len(), 2);
+            assert_eq!(error.exit_code(), 65);
+        }
+        Ok(()) => panic!("the script ran to the end"),
+    }
+    assert_eq!(output, "");
+}
+
+#[test]
+fn denied_warnings_stop_the_script_before_it_runs() {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .no_prelude()
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.set_diagnostic_options(DiagnosticOptions {
+        deny_warnings: true,
+        print: None,
+        ..DiagnosticOptions::default()
+    });
+
+    let error = runner
+        .run("test.lox", "print 1;\nvar a = 1;\n{ var a = 2; }")
+        .unwrap_err();
+
+    assert!(matches!(error, LoxError::DeniedWarnings(_)), "{:?}", error);
+    assert_eq!(error.exit_code(), 65);
+    assert_eq!(output.contents(), "");
+}
+
+#[test]
+fn a_runtime_error_stops_the_script_with_a_backtrace() {
+    let (output, result) =
+        run("fun fail() { return 1 + nil; }\nprint \"before\";\nfail();\nprint \"after\";");
+
+    match result {
+        Err(error) => {
+            let LoxError::Runtime(diagnostic) = &error else {
+                panic!("expected a {} error, got {:?}", "runtime", error)
+            };
+            assert_eq!(diagnostic.line, 1);
+            assert_eq!(
+                diagnostic
+                    .trace
+                    .iter()
+                    .map(|frame| frame.function.as_str())
+                    .collect::<Vec<_>>(),
+                ["<fn fail>", "script"]
+            );
+            assert_eq!(error.exit_code(), 70);
+        }
+        Ok(()) => panic!("the script ran to the end"),
+    }
+    assert_eq!(output, "before\n");
+}
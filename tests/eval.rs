@@ -0,0 +1,107 @@
+//! Evaluating lone expressions against bindings supplied by the host.
+
+use rlox::{Literal, LoxError};
+
+fn bindings() -> Vec<(&'static str, Literal)> {
+    vec![
+        ("price", Literal::Number(2.5)),
+        ("count", Literal::Number(4.0)),
+        ("name", Literal::String("lox".into())),
+    ]
+}
+
+fn parse_errors(result: Result<Literal, LoxError>) -> Vec<String> {
+    match result {
+        Err(LoxError::Parse(diagnostics)) => diagnostics
+            .iter()
+            .map(|diagnostic| format!("{} {}", diagnostic.location, diagnostic.message))
+            .collect(),
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+}
+
+#[test]
+fn bindings_are_looked_up() {
+    let bindings = bindings();
+    assert_eq!(
+        rlox::eval_expr("price * count", &bindings)
+            .unwrap()
+            .to_string(),
+        "10"
+    );
+    assert_eq!(
+        rlox::eval_pure_expr("name + \"!\"", &bindings)
+            .unwrap()
+            .to_string(),
+        "lox!"
+    );
+    assert_eq!(
+        rlox::eval_pure_expr("count > 3 and name == \"lox\"", &bindings)
+            .unwrap()
+            .to_string(),
+        "true"
+    );
+}
+
+#[test]
+fn nothing_outlives_an_evaluation() {
+    let bindings = bindings();
+    assert_eq!(
+        rlox::eval_expr("count = count + 1", &bindings)
+            .unwrap()
+            .to_string(),
+        "5"
+    );
+    assert_eq!(
+        rlox::eval_expr("count", &bindings).unwrap().to_string(),
+        "4"
+    );
+    assert_eq!(bindings[1].1.to_string(), "4");
+}
+
+#[test]
+fn calls_are_rejected_in_pure_mode() {
+    assert!(rlox::eval_expr("clock() >= 0", &[]).is_ok());
+
+    assert_eq!(
+        parse_errors(rlox::eval_pure_expr("clock() >= 0", &[])),
+        ["<expr>:1:6 calls are not allowed in this context."]
+    );
+    assert_eq!(
+        parse_errors(rlox::eval_pure_expr("1 + (price)(2)", &bindings())),
+        ["<expr>:1:12 calls are not allowed in this context."]
+    );
+}
+
+#[test]
+fn statements_are_not_expressions() {
+    assert_eq!(
+        parse_errors(rlox::eval_expr("price * 2;", &bindings())),
+        ["<expr>:1:10 Expect end of expression."]
+    );
+    assert_eq!(
+        parse_errors(rlox::eval_expr("var total = price", &bindings())),
+        ["<expr>:1:1 Unexpected token 'var'."]
+    );
+    assert_eq!(
+        parse_errors(rlox::eval_expr("price; count", &bindings())),
+        ["<expr>:1:6 Expect end of expression."]
+    );
+}
+
+#[test]
+fn runtime_errors_point_into_the_snippet() {
+    match rlox::eval_expr("count +\n  totl", &bindings()) {
+        Err(LoxError::Runtime(diagnostic)) => {
+            assert_eq!(diagnostic.location.as_deref(), Some("<expr>:2:3"));
+            assert_eq!(diagnostic.line, 2);
+            assert_eq!(diagnostic.message, "Undefined variable 'totl'.");
+        }
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+
+    match rlox::eval_expr("count + name", &bindings()) {
+        Err(LoxError::Runtime(diagnostic)) => assert_eq!(diagnostic.line, 1),
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
@@ -0,0 +1,105 @@
+//! The `exec()` native, run against the standard Unix tools.
+
+#![cfg(unix)]
+
+use rlox::{Interpreter, LoxError, OutputBuffer, Runner};
+
+/// Runs `code` with `exec()` allowed, returning what it printed.
+fn run(code: &str) -> Result<String, LoxError> {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .allow_processes()
+        .output(Box::new(output.clone()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.run("test.lox", code)?;
+    Ok(output.contents())
+}
+
+fn runtime_message(code: &str) -> String {
+    match run(code) {
+        Err(LoxError::Runtime(diagnostic)) => diagnostic.message,
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
+
+#[test]
+fn echo_output_is_captured() {
+    assert_eq!(
+        run("var result = exec(\"echo\", [\"hello\", \"world\"]);\n\
+             print result.get(\"status\");\nprint result.get(\"stdout\");\n\
+             print result.get(\"stderr\") == \"\";")
+        .unwrap(),
+        "0\nhello world\n\ntrue\n"
+    );
+}
+
+#[test]
+fn arguments_are_not_interpreted_by_a_shell() {
+    assert_eq!(
+        run("print exec(\"echo\", [\"$HOME; echo injected\"]).get(\"stdout\");").unwrap(),
+        "$HOME; echo injected\n\n"
+    );
+}
+
+#[test]
+fn status_and_stderr_are_reported() {
+    assert_eq!(
+        run(
+            "var result = exec(\"sh\", [\"-c\", \"echo out; echo err >&2; exit 3\"]);\n\
+             print result.get(\"status\");\nprint result.get(\"stdout\").len();\n\
+             print result.get(\"stderr\").len();"
+        )
+        .unwrap(),
+        "3\n4\n4\n"
+    );
+}
+
+#[test]
+fn options_set_the_working_directory() {
+    assert_eq!(
+        run("print exec(\"pwd\", [], {\"cwd\": \"/\"}).get(\"stdout\");").unwrap(),
+        "/\n\n"
+    );
+}
+
+#[test]
+fn a_program_past_its_timeout_is_stopped() {
+    assert_eq!(
+        runtime_message("exec(\"sleep\", [\"5\"], {\"timeout\": 0.2});"),
+        "exec(\"sleep\") failed: timed out after 0.2s."
+    );
+}
+
+#[test]
+fn a_missing_program_is_an_error_with_the_os_message() {
+    let message = runtime_message("exec(\"rlox-no-such-program\", []);");
+    assert!(
+        message.starts_with(
+            "exec(\"rlox-no-such-program\") failed: could not start 'rlox-no-such-program': "
+        ),
+        "{}",
+        message
+    );
+    assert!(message.contains("No such file or directory"), "{}", message);
+}
+
+#[test]
+fn too_much_output_is_an_error() {
+    assert_eq!(
+        runtime_message("exec(\"head\", [\"-c\", \"11000000\", \"/dev/zero\"]);"),
+        "exec(\"head\") failed: it printed more than 10 MB."
+    );
+}
+
+#[test]
+fn invalid_options_are_errors() {
+    assert_eq!(
+        runtime_message("exec(\"true\", [], {\"timeout\": -1});"),
+        "The timeout for exec() must be a positive number of seconds, got -1."
+    );
+    assert_eq!(
+        runtime_message("exec(\"true\", [], {\"shell\": true});"),
+        "Unknown option 'shell' for exec(); the options are 'timeout' and 'cwd'."
+    );
+}
@@ -0,0 +1,123 @@
+//! `rlox --test script.lox`, which checks what a script prints against its `// expect:`
+//! comments.
+
+use std::process::{Command, Output};
+
+use rlox::{Outcome, TestReport};
+
+fn lox_test(script: &str) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_lox"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["--test", &format!("tests/expect/{}", script)])
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn a_script_printing_what_it_expects_passes() {
+    let output = lox_test("passing.lox");
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        stdout(&output),
+        "tests/expect/passing.lox:\n\
+         \x20 ok    line 5: hello, lox\n\
+         \x20 ok    line 6: 3\n\
+         \x20 ok    line 9: // expect: inside a string is just text\n\
+         3 of 3 expectations passed.\n"
+    );
+}
+
+#[test]
+fn a_wrong_expectation_shows_what_was_printed_instead() {
+    let output = lox_test("wrong.lox");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stdout(&output),
+        "tests/expect/wrong.lox:\n\
+         \x20 ok    line 1: 1\n\
+         \x20 FAIL  line 2:\n\
+         \x20         expected: 3\n\
+         \x20           actual: 2\n\
+         \x20 ok    line 3: 3\n\
+         2 of 3 expectations passed.\n"
+    );
+}
+
+#[test]
+fn output_nothing_expects_fails() {
+    let output = lox_test("extra.lox");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stdout(&output),
+        "tests/expect/extra.lox:\n\
+         \x20 ok    line 1: first\n\
+         \x20 FAIL  unexpected output: second\n\
+         1 of 1 expectations passed; 1 line of output was unexpected.\n"
+    );
+}
+
+#[test]
+fn a_runtime_error_fails_the_expectation_it_cut_short() {
+    let output = lox_test("failing.lox");
+    assert_eq!(output.status.code(), Some(1));
+    let report = stdout(&output);
+    assert!(
+        report.contains(
+            "  FAIL  line 2:\n          expected: never\n            actual: error: \
+             tests/expect/failing.lox:2:7 Operands must be"
+        ),
+        "{}",
+        report
+    );
+    assert!(
+        report.contains(
+            "  FAIL  line 3:\n          expected: after\n            actual: not reached\n"
+        ),
+        "{}",
+        report
+    );
+    assert!(
+        report.ends_with("1 of 3 expectations passed; the script failed.\n"),
+        "{}",
+        report
+    );
+}
+
+#[test]
+fn a_runtime_error_after_the_last_expectation_still_fails() {
+    let expectations = vec![rlox::Expectation {
+        line: 1,
+        value: "done".to_string(),
+    }];
+    let error = rlox::RuntimeDiagnostic {
+        message: "Boom.".to_string(),
+        location: Some("test.lox:2:1".to_string()),
+        line: 2,
+        column: 1,
+        trace: vec![],
+    };
+    let report = TestReport::new("test.lox", expectations, "done\n", Some(error));
+
+    assert_eq!(report.results[0].1, Outcome::Passed);
+    assert!(!report.passed());
+    assert_eq!(
+        report.to_string(),
+        "test.lox:\n  ok    line 1: done\n  FAIL  after the last expectation: error: \
+         test.lox:2:1 Boom.\n1 of 1 expectations passed; the script failed.\n"
+    );
+}
+
+#[test]
+fn a_script_that_does_not_compile_is_not_a_test_failure() {
+    let output = Command::new(env!("CARGO_BIN_EXE_lox"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["--test", "tests/batch/broken.lox"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(65));
+    assert_eq!(stdout(&output), "");
+}
@@ -0,0 +1,151 @@
+//! `extend` declarations adding methods to classes that already exist.
+
+use rlox::{
+    testing::{run_output, runtime_error, syntax_error},
+    DiagnosticOptions, Runner, Severity,
+};
+
+#[test]
+fn existing_instances_get_the_new_methods() {
+    let code = "\
+class Counter {
+  init() { this.count = 0; }
+  add() { this.count = this.count + 1; return this; }
+}
+var counter = Counter();
+counter.add();
+extend Counter {
+  twice() { return this.add().add(); }
+  report() { print this.count; }
+}
+counter.twice().report();
+Counter().report();
+";
+    assert_eq!(run_output(code).unwrap(), "3\n0\n");
+}
+
+#[test]
+fn subclasses_inherit_additions_to_their_superclass() {
+    let code = "\
+class Animal { name() { return \"animal\"; } }
+class Dog < Animal { name() { return \"dog\"; } }
+var dog = Dog();
+extend Animal {
+  describe() { return \"a \" + this.name(); }
+}
+print dog.describe();
+print Dog().describe();
+print Animal().describe();
+";
+    assert_eq!(run_output(code).unwrap(), "a dog\na dog\na animal\n");
+}
+
+#[test]
+fn super_reaches_the_superclass_of_the_extended_class() {
+    let code = "\
+class A { greet() { return \"A\"; } }
+class B < A {}
+extend B {
+  greet() { return super.greet() + \"B\"; }
+}
+print B().greet();
+";
+    assert_eq!(run_output(code).unwrap(), "AB\n");
+
+    let code = "\
+class A {}
+extend A {
+  greet() { return super.greet(); }
+}
+A().greet();
+";
+    assert_eq!(
+        runtime_error(code),
+        "test.lox:3:20 Can't use 'super' in a class with no superclass."
+    );
+}
+
+#[test]
+fn replacing_a_method_warns_and_takes_effect() {
+    let code = "\
+class Greeter {
+  greet() { return \"hello\"; }
+}
+var greeter = Greeter();
+extend Greeter {
+  greet() { return \"hi\"; }
+}
+print greeter.greet();
+";
+    let mut runner = Runner::new();
+    runner.set_diagnostic_options(DiagnosticOptions {
+        print: None,
+        ..DiagnosticOptions::default()
+    });
+    runner.run("test.lox", code).unwrap();
+
+    let warnings = runner.diagnostics().diagnostics();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].severity, Severity::Warning);
+    assert_eq!(warnings[0].code, "extension-replaces-method");
+    assert_eq!(
+        warnings[0].to_string(),
+        "test.lox:6:3 Extension replaces method 'greet' of class Greeter, declared at line 2."
+    );
+
+    // Overriding an inherited method is no replacement.
+    let mut runner = Runner::new();
+    runner
+        .run(
+            "test.lox",
+            "class A { f() {} }\nclass B < A {}\nextend B { f() {} }\n",
+        )
+        .unwrap();
+    assert!(runner.diagnostics().diagnostics().is_empty());
+}
+
+#[test]
+fn denied_replacement_stops_the_script() {
+    let mut runner = Runner::new();
+    runner.set_diagnostic_options(DiagnosticOptions {
+        deny_warnings: true,
+        print: None,
+        ..DiagnosticOptions::default()
+    });
+    let error = runner
+        .run(
+            "test.lox",
+            "class A { f() {} }\nextend A { f() {} }\nvar after = 1;\n",
+        )
+        .unwrap_err();
+    assert_eq!(error.diagnostics()[0].code, "extension-replaces-method");
+    assert!(runner.interpreter().get_global("after").is_none());
+}
+
+#[test]
+fn only_classes_can_be_extended() {
+    assert_eq!(
+        runtime_error("var n = 1;\nextend n { f() {} }\n"),
+        "test.lox:2:8 Can only extend a class, but 'n' is a Number."
+    );
+    assert_eq!(
+        runtime_error("extend Missing { f() {} }\n"),
+        "test.lox:1:8 Undefined variable 'Missing'."
+    );
+}
+
+#[test]
+fn malformed_extensions() {
+    assert_eq!(
+        syntax_error("extend { f() {} }\n"),
+        "Parser Error: test.lox:1:8 Expect class name after 'extend'."
+    );
+    assert_eq!(
+        syntax_error("class A {}\nextend A f() {}\n"),
+        "Parser Error: test.lox:2:10 Expect '{' before extension body."
+    );
+    assert_eq!(
+        syntax_error("class A {}\nextend A { f() {}\n"),
+        "Parser Error: test.lox:3:1 Expect '}' after extension body."
+    );
+}
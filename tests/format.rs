@@ -0,0 +1,36 @@
+//! The `format()` native and its placeholders.
+
+use rlox::testing::{run_output, runtime_error};
+
+#[test]
+fn placeholders_are_filled_in_order_and_by_position() {
+    assert_eq!(
+        run_output("print format(\"{} and {}, {1} then {0} {{}}\", 1, \"two\");").unwrap(),
+        "1 and two, two then 1 {}\n"
+    );
+    assert_eq!(
+        run_output("print format(\"{:.2}|{0:.0}\", 3.14159);").unwrap(),
+        "3.14|3\n"
+    );
+}
+
+#[test]
+fn precision_is_capped() {
+    assert_eq!(
+        run_output("print format(\"{:.100}\", 1).len();").unwrap(),
+        "102\n"
+    );
+    assert_eq!(
+        runtime_error("print format(\"{0:.99999999999}\", 1);"),
+        "test.lox:1:7 Cannot fill '{0:.99999999999}' in format template \"{0:.99999999999}\": \
+         99999999999 decimal places is too many; the limit is 100."
+    );
+}
+
+#[test]
+fn printf_formats_and_prints() {
+    assert_eq!(
+        run_output("printf(\"{} = {:.1}\", \"x\", 2);\nprintf(\"done\");").unwrap(),
+        "x = 2.0\ndone\n"
+    );
+}
@@ -0,0 +1,110 @@
+//! Snapshots of the debugging formats: the token table, token descriptions in syntax errors
+//! and the one-line statements the debugger pauses at.
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use rlox::{inspect, Runner};
+
+const SCRIPT: &str =
+    "var name = \"lox\";\nfun greet(who) {\n  print \"hi \" + who;\n}\ngreet(name);\n";
+
+#[test]
+fn token_table() {
+    let options = inspect::Options {
+        path: None,
+        format: inspect::Format::Sexpr,
+        tokens: true,
+        stmt: None,
+        desugar: false,
+        resolve: false,
+    };
+    let table = inspect::inspect("greet.lox", SCRIPT, &options).unwrap();
+    assert_eq!(
+        table,
+        "\
+LINE:COL  TOKEN       LEXEME
+1:1       Var         var
+1:5       Identifier  name
+1:10      Eq          =
+1:12      String      \"lox\"
+1:17      Semicolon   ;
+2:1       Fun         fun
+2:5       Identifier  greet
+2:10      LeftParen   (
+2:11      Identifier  who
+2:14      RightParen  )
+2:16      LeftBrace   {
+3:3       Print       print
+3:9       String      \"hi \"
+3:15      Plus        +
+3:17      Identifier  who
+3:20      Semicolon   ;
+4:1       RightBrace  }
+5:1       Identifier  greet
+5:6       LeftParen   (
+5:7       Identifier  name
+5:11      RightParen  )
+5:12      Semicolon   ;
+6:1       Eof
+"
+    );
+}
+
+#[test]
+fn unexpected_tokens_are_described() {
+    let mut runner = Runner::new();
+    let error = runner
+        .run("bad.lox", "var x = 1;\nprint (x + :);")
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Parser Error: bad.lox:2:12 Unexpected token ':'."
+    );
+
+    let error = runner.run("bad.lox", "print 1 + var;").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Parser Error: bad.lox:1:11 Unexpected token 'var'."
+    );
+}
+
+#[test]
+fn debugger_shows_the_statement_it_paused_at() {
+    let path = std::env::temp_dir().join(format!("rlox-formatters-{}.lox", std::process::id()));
+    let script = format!(
+        "{}fun long(a) {{ return a + 1000000 + 2000000 + 3000000 + 4000000 + 5000000; }}\n",
+        SCRIPT
+    );
+    std::fs::write(&path, script).unwrap();
+
+    let mut debugger = Command::new(env!("CARGO_BIN_EXE_lox"))
+        .arg("--debug")
+        .arg(&path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    debugger
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"step\nstep\nstep\nstep\nstep\ncontinue\n")
+        .unwrap();
+    let output = debugger.wait_with_output().unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "\
+Paused at line 1: (define name \"lox\")
+(debug) Paused at line 2: (fun greet (who) (print (+ \"hi \" (var who))))
+(debug) Paused at line 5: (expr (call (var greet) (var name)))
+(debug) Paused at line 3: (print (+ \"hi \" (var who)))
+(debug) hi lox
+Paused at line 6: (fun long (a) (return (+ (+ (+ (+ (+ (var a) 1000000) 200000 ...
+(debug) "
+    );
+}
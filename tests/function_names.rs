@@ -0,0 +1,106 @@
+//! `fnName()`, `fnArity()` and how each kind of callable prints.
+
+use rlox::testing::{run_output, runtime_error};
+
+#[test]
+fn functions() {
+    assert_eq!(
+        run_output(
+            "fun add(a, b) { return a + b; }\nfun none() {}\n\
+             print add;\nprint fnName(add);\nprint fnArity(add);\nprint fnArity(none);\n\
+             var alias = add;\nprint fnName(alias);"
+        )
+        .unwrap(),
+        "<fn add>\nadd\n2\n0\nadd\n"
+    );
+}
+
+#[test]
+fn closures() {
+    assert_eq!(
+        run_output(
+            "fun outer() { fun inner(x) { return x; } return inner; }\n\
+             print outer();\nprint fnName(outer());\nprint fnArity(outer());"
+        )
+        .unwrap(),
+        "<fn inner>\ninner\n1\n"
+    );
+}
+
+#[test]
+fn classes_report_their_initializer_arity() {
+    assert_eq!(
+        run_output(
+            "class Point { init(x, y) {} }\nclass Empty {}\nclass Child < Point {}\n\
+             print Point;\nprint fnName(Point);\nprint fnArity(Point);\n\
+             print fnArity(Empty);\nprint fnArity(Child);"
+        )
+        .unwrap(),
+        "Point\nPoint\n2\n0\n2\n"
+    );
+}
+
+#[test]
+fn bound_methods() {
+    assert_eq!(
+        run_output(
+            "class Greeter { greet(name, greeting) {} }\nvar method = Greeter().greet;\n\
+             print method;\nprint fnName(method);\nprint fnArity(method);"
+        )
+        .unwrap(),
+        "<fn greet>\ngreet\n2\n"
+    );
+}
+
+#[test]
+fn natives() {
+    assert_eq!(
+        run_output(
+            "print clock;\nprint fnName(clock);\nprint fnArity(clock);\n\
+             print fnName(fnName);\nprint fnArity(fnName);"
+        )
+        .unwrap(),
+        "<native fn clock>\nclock\n0\nfnName\n1\n"
+    );
+}
+
+#[test]
+fn variadic_natives_report_a_range() {
+    // The most arguments is nil when there is no limit.
+    assert_eq!(
+        run_output("print fnArity(prettyPrint);\nprint fnArity(format);").unwrap(),
+        "[1, 2]\n[1, nil]\n"
+    );
+}
+
+#[test]
+fn primitive_methods() {
+    assert_eq!(
+        run_output(
+            "var len = \"abc\".len;\nprint len;\nprint fnName(len);\nprint fnArity(len);\n\
+             print fnArity([].push);"
+        )
+        .unwrap(),
+        "<native method len>\nlen\n0\n1\n"
+    );
+}
+
+#[test]
+fn prelude_functions() {
+    assert_eq!(
+        run_output("print fnName(reduce);\nprint fnArity(reduce);").unwrap(),
+        "reduce\n3\n"
+    );
+}
+
+#[test]
+fn non_callables_are_errors() {
+    assert_eq!(
+        runtime_error("fnName(1);"),
+        "test.lox:1:1 fnName() needs a function or class, not a Number."
+    );
+    assert_eq!(
+        runtime_error("fnArity(nil);"),
+        "test.lox:1:1 fnArity() needs a function or class, not a Nil."
+    );
+}
@@ -0,0 +1,78 @@
+//! Programs built in Rust and run without any source text.
+
+use rlox::{BinOp, Expr, Interpreter, LoxError, OutputBuffer, Runner, Stmt};
+
+fn runner() -> (Runner, OutputBuffer) {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .build();
+    (Runner::with_interpreter(interpreter), output)
+}
+
+/// `fun square(n) { return n * n; }` and a loop printing the squares of 1 to 3.
+fn squares() -> Vec<Stmt> {
+    vec![
+        Stmt::function(
+            "square",
+            &["n"],
+            vec![Stmt::return_value(Some(Expr::binary(
+                Expr::variable("n"),
+                BinOp::Multiply,
+                Expr::variable("n"),
+            )))],
+        ),
+        Stmt::var("i", Some(Expr::literal(1))),
+        Stmt::while_loop(
+            Expr::binary(Expr::variable("i"), BinOp::Le, Expr::literal(3)),
+            Stmt::block(vec![
+                Stmt::print(Expr::call(
+                    Expr::variable("square"),
+                    vec![Expr::variable("i")],
+                )),
+                Stmt::expression(Expr::assign(
+                    "i",
+                    Expr::binary(Expr::variable("i"), BinOp::Plus, Expr::literal(1)),
+                )),
+            ]),
+        ),
+    ]
+}
+
+#[test]
+fn built_programs_run() {
+    let (mut runner, output) = runner();
+    runner.run_program(squares()).unwrap();
+    assert_eq!(output.contents(), "1\n4\n9\n");
+    assert_eq!(runner.interpreter().get_global_number("i").unwrap(), 4.0);
+}
+
+#[test]
+fn read_programs_run() {
+    let text = rlox::to_sexpr(&squares());
+    let program = rlox::from_sexpr(&text).unwrap();
+
+    let (mut runner, output) = runner();
+    runner.run_program(program).unwrap();
+    assert_eq!(output.contents(), "1\n4\n9\n");
+}
+
+#[test]
+fn diagnostics_point_at_generated_code() {
+    let (mut runner, _) = runner();
+    match runner.run_program(vec![Stmt::return_value(None)]) {
+        Err(LoxError::Resolve(errors)) => {
+            assert_eq!(errors[0].location, "<generated>");
+            assert_eq!(errors[0].message, "Can't return from top-level code.");
+        }
+        other => panic!("expected a resolve error, got {:?}", other),
+    }
+
+    let program = rlox::from_sexpr("(version 1)\n(print (+ 1 nil))\n").unwrap();
+    match runner.run_program(program) {
+        Err(LoxError::Runtime(diagnostic)) => {
+            assert_eq!(diagnostic.location.as_deref(), Some("<generated>"));
+        }
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
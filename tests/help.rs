@@ -0,0 +1,51 @@
+//! What `help()` prints for each kind of value.
+
+use rlox::testing::assert_output;
+
+#[test]
+fn functions_show_their_signature() {
+    assert_output(
+        "fun greet(name, greeting) { return greeting + name; }\nhelp(greet);",
+        &["fun greet(name, greeting), declared at line 1"],
+    );
+}
+
+#[test]
+fn classes_show_their_arity_and_methods() {
+    assert_output(
+        "class Shape { area() { return 0; } describe() {} }\n\
+         class Square < Shape { init(side) { this.side = side; } area() { return this.side * this.side; } }\n\
+         class Empty {}\nhelp(Square);\nhelp(Empty);",
+        &[
+            "Square (arity 1), methods: init, area, describe",
+            "Empty (arity 0)",
+        ],
+    );
+}
+
+#[test]
+fn natives_show_their_arity_and_doc() {
+    assert_output(
+        "help(clock);\nhelp(prettyPrint);",
+        &[
+            "<native fn clock> (arity 0): Returns the number of seconds since the Unix epoch.",
+            "<native fn prettyPrint> (arity 1 to 2): Prints a value with nested arrays and maps \
+             indented, down to an optional depth.",
+        ],
+    );
+}
+
+#[test]
+fn plain_values_show_their_type_and_value() {
+    assert_output(
+        "help(42);\nhelp(\"lox\");\nhelp(nil);\nhelp(true);\nhelp([1, \"a\"]);\nhelp({\"k\": 1});",
+        &[
+            "Number 42",
+            "String lox",
+            "Nil nil",
+            "Bool true",
+            "Array [1, a]",
+            "Map {k: 1}",
+        ],
+    );
+}
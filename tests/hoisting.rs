@@ -0,0 +1,121 @@
+//! Hoisting, which defines a script's top-level functions and classes before it runs.
+
+use rlox::{
+    testing::{run_output, runtime_error},
+    Interpreter, LoxError, OutputBuffer, Runner,
+};
+
+/// Runs `code` with hoisting enabled and returns what it printed.
+fn run_hoisted(code: &str) -> Result<String, LoxError> {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.set_hoisting(true);
+    runner.run("test.lox", code)?;
+    Ok(output.contents())
+}
+
+#[test]
+fn functions_are_not_hoisted_by_default() {
+    let error = runtime_error("sayHi();\nfun sayHi() { print \"hi\"; }");
+    assert!(
+        error.starts_with("test.lox:1:1 Undefined variable 'sayHi'."),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn hoisted_functions_can_be_called_above_their_declaration() {
+    let output = run_hoisted("sayHi();\nfun sayHi() { print \"hi\"; }").unwrap();
+    assert_eq!(output, "hi\n");
+}
+
+#[test]
+fn hoisted_functions_can_call_each_other() {
+    let code = "
+        print isEven(10);
+        print isOdd(7);
+
+        fun isEven(n) { if (n == 0) return true; return isOdd(n - 1); }
+        fun isOdd(n) { if (n == 0) return false; return isEven(n - 1); }
+    ";
+    assert_eq!(run_hoisted(code).unwrap(), "true\ntrue\n");
+}
+
+#[test]
+fn hoisted_functions_close_over_the_globals() {
+    let code = "
+        var greeting = \"hello\";
+        print greet();
+        greeting = \"goodbye\";
+        print greet();
+
+        fun greet() { return greeting; }
+    ";
+    assert_eq!(run_hoisted(code).unwrap(), "hello\ngoodbye\n");
+}
+
+#[test]
+fn hoisted_classes_can_be_used_above_their_declaration() {
+    let code = "
+        print Circle(2).area();
+        print Shape().describe();
+
+        class Shape { describe() { return \"a shape\"; } }
+        class Circle < Shape {
+          init(radius) { this.radius = radius; }
+          area() { return 3 * this.radius * this.radius; }
+        }
+    ";
+    assert_eq!(run_hoisted(code).unwrap(), "12\na shape\n");
+}
+
+#[test]
+fn classes_with_a_superclass_that_is_not_hoisted_are_defined_in_place() {
+    let code = "
+        var Base = nil;
+        class Placeholder {}
+        Base = Placeholder;
+        class Derived < Base {}
+        print Derived;
+    ";
+    assert_eq!(run_hoisted(code).unwrap(), "Derived\n");
+
+    let error = match run_hoisted("print Derived;\nvar Base = nil;\nclass Derived < Base {}") {
+        Err(LoxError::Runtime(diagnostic)) => diagnostic.message,
+        other => panic!("expected a runtime error, got {:?}", other),
+    };
+    assert!(
+        error.starts_with("Undefined variable 'Derived'."),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn a_later_variable_replaces_a_hoisted_function_from_its_declaration_on() {
+    let code = "
+        print f();
+        var f = 1;
+        print f;
+
+        fun f() { return \"function\"; }
+        print f();
+    ";
+    assert_eq!(run_hoisted(code).unwrap(), "function\n1\nfunction\n");
+}
+
+#[test]
+fn a_declaration_rebinds_the_hoisted_function_where_it_appears() {
+    let code = "
+        var f = 1;
+        print f;
+        fun f() { return 2; }
+        print f();
+    ";
+    assert_eq!(run_hoisted(code).unwrap(), "1\n2\n");
+    assert_eq!(run_output(code).unwrap(), "1\n2\n");
+}
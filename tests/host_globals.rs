@@ -0,0 +1,161 @@
+//! Hosts reading the globals a script defined, and defining globals for scripts to use.
+
+use rlox::{GlobalError, Interpreter, Literal, OutputBuffer, Runner};
+
+const CONFIG: &str = "var timeout = 2.5;\nvar host = \"example.com\";\nvar verbose = true;\n\
+                      var retries = nil;\nfun backoff(n) { return n * timeout; }\n";
+
+fn configured() -> Runner {
+    let (mut runner, _) = runner();
+    runner.run("config.lox", CONFIG).unwrap();
+    runner
+}
+
+#[test]
+fn typed_reads_return_the_values() {
+    let runner = configured();
+    let interpreter = runner.interpreter();
+
+    assert_eq!(interpreter.get_global_number("timeout"), Ok(2.5));
+    assert_eq!(
+        interpreter.get_global_string("host"),
+        Ok("example.com".to_string())
+    );
+    assert_eq!(interpreter.get_global_bool("verbose"), Ok(true));
+}
+
+#[test]
+fn untyped_reads_return_any_value() {
+    let runner = configured();
+    let interpreter = runner.interpreter();
+
+    assert!(matches!(
+        interpreter.get_global("retries"),
+        Some(Literal::Nil)
+    ));
+    assert_eq!(
+        interpreter.get_global("backoff").unwrap().to_string(),
+        "<fn backoff>"
+    );
+    assert!(interpreter.get_global("missing").is_none());
+}
+
+#[test]
+fn names_lists_the_script_globals_and_the_natives() {
+    let runner = configured();
+    let names = runner.interpreter().globals().names();
+
+    for name in ["backoff", "host", "retries", "timeout", "verbose", "clock"] {
+        assert!(names.contains(&name.to_string()), "{} is missing", name);
+    }
+    let mut sorted = names.clone();
+    sorted.sort();
+    assert_eq!(names, sorted);
+}
+
+#[test]
+fn wrong_types_name_the_actual_type() {
+    let runner = configured();
+    let interpreter = runner.interpreter();
+
+    let error = interpreter.get_global_number("host").unwrap_err();
+    assert_eq!(
+        error,
+        GlobalError::TypeMismatch {
+            name: "host".into(),
+            expected: "Number",
+            actual: "String",
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "Global 'host' is a String, not a Number."
+    );
+    assert_eq!(
+        interpreter
+            .get_global_bool("retries")
+            .unwrap_err()
+            .to_string(),
+        "Global 'retries' is a Nil, not a Bool."
+    );
+    assert_eq!(
+        interpreter.get_global_string("missing").unwrap_err(),
+        GlobalError::Undefined("missing".into())
+    );
+    assert_eq!(
+        interpreter
+            .get_global_string("missing")
+            .unwrap_err()
+            .to_string(),
+        "Undefined global 'missing'."
+    );
+}
+
+#[test]
+fn locals_are_not_globals() {
+    let mut runner = configured();
+    runner
+        .run("more.lox", "{ var inner = 1; }\nfun f() { var local = 2; }")
+        .unwrap();
+
+    assert!(runner.interpreter().get_global("inner").is_none());
+    assert!(runner.interpreter().get_global("local").is_none());
+}
+
+/// A runner with no prelude whose prints land in the returned buffer.
+fn runner() -> (Runner, OutputBuffer) {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .no_prelude()
+        .build();
+    (Runner::with_interpreter(interpreter), output)
+}
+
+#[test]
+fn defined_globals_are_visible_to_scripts() {
+    let (mut runner, output) = runner();
+    runner.define_global("user_name", "alice");
+    runner.define_global("threshold", 0.75);
+    runner.define_global("admin", false);
+    runner.define_global_map("config", [("greeting", "Hello"), ("theme", "dark")]);
+
+    runner
+        .run(
+            "template.lox",
+            "print config.get(\"greeting\") + \", \" + user_name;\n\
+             print threshold * 2;\nprint admin;\nprint config;",
+        )
+        .unwrap();
+
+    assert_eq!(
+        output.contents(),
+        "Hello, alice\n1.5\nfalse\n{greeting: Hello, theme: dark}\n"
+    );
+}
+
+#[test]
+fn the_last_definition_wins() {
+    let (mut runner, output) = runner();
+    runner.define_global("limit", 1);
+    runner.define_global("limit", 2);
+    runner
+        .run("first.lox", "print limit;\nvar limit = 3;")
+        .unwrap();
+    assert_eq!(runner.interpreter().get_global_number("limit"), Ok(3.0));
+
+    runner.define_global("limit", "four");
+    runner.run("second.lox", "print limit;").unwrap();
+
+    assert_eq!(output.contents(), "2\nfour\n");
+}
+
+#[test]
+fn the_host_can_replace_a_constant() {
+    let (mut runner, output) = runner();
+    runner.run("first.lox", "const version = 1;").unwrap();
+    runner.define_global("version", 2);
+    runner.run("second.lox", "print version;").unwrap();
+
+    assert_eq!(output.contents(), "2\n");
+}
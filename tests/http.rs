@@ -0,0 +1,132 @@
+//! `fetch()` and `fetchPost()` against a server run by the test itself.
+
+#![cfg(feature = "http")]
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use rlox::{testing::run_output, Interpreter, LoxError, OutputBuffer, Runner};
+
+/// Serves one connection on a free local port with `response`, or with nothing at all when
+/// it is `None`, and hands back the request it read.
+fn serve_once(response: Option<&'static str>) -> (String, JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut request = String::new();
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if let Some(value) = line.strip_prefix("Content-Length: ") {
+                length = value.trim().parse().unwrap();
+            }
+            request.push_str(&line);
+            if line == "\r\n" {
+                break;
+            }
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        request.push_str(&String::from_utf8(body).unwrap());
+
+        match response {
+            Some(response) => reader.get_mut().write_all(response.as_bytes()).unwrap(),
+            // Keep the connection open past the client's timeout.
+            None => thread::sleep(Duration::from_secs(1)),
+        }
+        request
+    });
+    (url, server)
+}
+
+/// Runs `code` in an interpreter with network access, with the global `url` set.
+fn fetch(url: &str, code: &str) -> Result<String, LoxError> {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .allow_network()
+        .output(Box::new(output.clone()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.define_global("url", url);
+    runner.run("test.lox", code)?;
+    Ok(output.contents())
+}
+
+#[test]
+fn a_successful_response_gives_status_headers_and_body() {
+    let (url, server) = serve_once(Some(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 5\r\n\r\nhello",
+    ));
+    let output = fetch(
+        &url,
+        "var response = fetch(url + \"/greeting?x=1\");\nprint response.get(\"status\");\n\
+         print response.get(\"headers\").get(\"content-type\");\nprint response.get(\"body\");\n",
+    )
+    .unwrap();
+
+    assert_eq!(output, "200\ntext/plain\nhello\n");
+    assert!(server
+        .join()
+        .unwrap()
+        .starts_with("GET /greeting?x=1 HTTP/1.1\r\n"));
+}
+
+#[test]
+fn a_404_is_a_response_not_an_error() {
+    let (url, server) = serve_once(Some(
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 9\r\n\r\nnot found",
+    ));
+    let output = fetch(
+        &url,
+        "var response = fetch(url);\nprint response.get(\"status\");\nprint response.get(\"body\");\n",
+    )
+    .unwrap();
+
+    assert_eq!(output, "404\nnot found\n");
+    server.join().unwrap();
+}
+
+#[test]
+fn posts_send_their_body() {
+    let (url, server) = serve_once(Some("HTTP/1.1 201 Created\r\nContent-Length: 0\r\n\r\n"));
+    let output = fetch(
+        &url,
+        "print fetchPost(url + \"/items\", \"payload\").get(\"status\");\n",
+    )
+    .unwrap();
+
+    assert_eq!(output, "201\n");
+    let request = server.join().unwrap();
+    assert!(
+        request.starts_with("POST /items HTTP/1.1\r\n"),
+        "{}",
+        request
+    );
+    assert!(request.ends_with("\r\n\r\npayload"), "{}", request);
+}
+
+#[test]
+fn a_silent_server_times_out() {
+    let (url, server) = serve_once(None);
+    match fetch(&url, "fetch(url, {\"timeout\": 0.2});") {
+        Err(LoxError::Runtime(diagnostic)) => assert_eq!(
+            diagnostic.message,
+            format!("GET {} failed: timed out after 0.2s.", url)
+        ),
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+    server.join().unwrap();
+}
+
+#[test]
+fn scripts_dont_get_fetch_unless_the_embedder_allows_it() {
+    assert!(run_output("print fetch;").is_err());
+}
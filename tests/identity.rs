@@ -0,0 +1,108 @@
+//! Object identity and lifetime: `identityHash()`, `same()` and `WeakRef()`.
+
+use rlox::{
+    testing::{assert_output, assert_runtime_error},
+    Interpreter, Literal, OutputBuffer, Runner,
+};
+
+#[test]
+fn clones_of_a_literal_share_its_identity() {
+    let array: Literal = vec![1, 2].into();
+    let copy = array.clone();
+    assert!(array.is_same(&copy));
+    assert_eq!(array.identity_hash(), copy.identity_hash());
+
+    let lookalike: Literal = vec![1, 2].into();
+    assert!(!array.is_same(&lookalike));
+    assert_ne!(array.identity_hash(), lookalike.identity_hash());
+
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.define_global("a", array.clone());
+    runner.define_global("b", copy);
+    runner.define_global("c", lookalike);
+    runner
+        .run(
+            "test.lox",
+            "print same(a, b);\nprint same(a, c);\nprint identityHash(a) == identityHash(b);\n",
+        )
+        .unwrap();
+    assert_eq!(output.contents(), "true\nfalse\ntrue\n");
+
+    runner.run("test.lox", "var h = identityHash(a);").unwrap();
+    let hash = runner.interpreter().get_global_number("h").unwrap();
+    assert_eq!(hash, array.identity_hash() as f64);
+}
+
+#[test]
+fn hashes_are_stable_within_a_run() {
+    let code = "\
+class Box {}
+var box = Box();
+var list = [];
+var map = {};
+fun f() {}
+var hashes = [identityHash(box), identityHash(list), identityHash(map), identityHash(f)];
+box.value = 1;
+list.push(box);
+map.set(\"k\", list);
+print hashes.pop() == identityHash(f);
+print hashes.pop() == identityHash(map);
+print hashes.pop() == identityHash(list);
+print hashes.pop() == identityHash(box);
+print identityHash(box) == identityHash(Box());
+print identityHash(\"text\") == identityHash(\"te\" + \"xt\");
+print identityHash(3) == identityHash(1 + 2);
+print identityHash(true) == identityHash(1);
+";
+    assert_output(
+        code,
+        &[
+            "true", "true", "true", "true", "false", "true", "true", "false",
+        ],
+    );
+}
+
+#[test]
+fn same_is_identity_rather_than_equality() {
+    let code = "\
+fun f() {}
+print f == f;
+print same(f, f);
+var a = [1];
+print same(a, a);
+print same(a, [1]);
+print same(\"x\", \"x\");
+print 0 == -0;
+print same(0, -0);
+var nan = 0 / 0;
+print nan == nan;
+print same(nan, nan);
+print same(nil, false);
+";
+    assert_output(
+        code,
+        &[
+            "false", "true", "true", "false", "true", "true", "false", "false", "true", "false",
+        ],
+    );
+}
+
+#[test]
+fn weak_refs_let_go_of_collected_objects() {
+    let code = "\
+class Node {}
+var node = Node();
+var ref = WeakRef(node);
+print same(ref.get(), node);
+node = nil;
+print ref.get();
+print same(WeakRef(clock).get(), clock);
+";
+    assert_output(code, &["true", "nil", "true"]);
+
+    assert_runtime_error("WeakRef(1);", "WeakRef() needs an object, not a Number.");
+}
@@ -0,0 +1,140 @@
+//! `if` statements with `else if` chains: which branch runs, where a dangling `else` goes,
+//! and the errors for chains that don't parse.
+
+use rlox::{
+    inspect,
+    testing::{assert_output, syntax_error},
+};
+
+fn tree(code: &str) -> String {
+    let options = inspect::Options {
+        path: None,
+        format: inspect::Format::Sexpr,
+        tokens: false,
+        stmt: None,
+        desugar: false,
+        resolve: false,
+    };
+    inspect::inspect("chain.lox", code, &options).unwrap()
+}
+
+/// A chain testing `x` against 1 to `arms`, printing the number that matched or `else`.
+fn chain(arms: usize) -> String {
+    let mut code = String::from("if (x == 1) print 1;");
+    for n in 2..=arms {
+        code.push_str(&format!(" else if (x == {n}) print {n};"));
+    }
+    code.push_str(" else print \"else\";");
+    code
+}
+
+#[test]
+fn dangling_else_goes_with_the_nearest_if() {
+    let code = "if (a) if (b) print 1; else print 2;";
+    assert_eq!(
+        tree(code),
+        "(version 1)\n(if (var a) (if (var b) (print 1) (print 2)))\n"
+    );
+
+    for (a, b, expected) in [
+        (true, true, &["1"][..]),
+        (true, false, &["2"]),
+        (false, true, &[]),
+        (false, false, &[]),
+    ] {
+        assert_output(&format!("var a = {}; var b = {}; {}", a, b, code), expected);
+    }
+}
+
+#[test]
+fn else_after_a_nested_if_belongs_to_the_outer_chain() {
+    let code = "if (a) if (b) print 1; else print 2; else if (c) print 3; else print 4;";
+    assert_eq!(
+        tree(code),
+        "(version 1)\n(if (var a) (if (var b) (print 1) (print 2)) (var c) (print 3) (print 4))\n"
+    );
+    let code = format!("var a = false; var b = true; var c = false; {}", code);
+    assert_output(&code, &["4"]);
+}
+
+#[test]
+fn braces_around_the_inner_if_move_the_else_out() {
+    let code = "var a = true; var b = false; if (a) { if (b) print 1; } else print 2;";
+    assert_output(code, &[]);
+}
+
+#[test]
+fn chains_are_one_flat_statement() {
+    let code = chain(10);
+    let tree = tree(&code);
+    assert_eq!(tree.lines().count(), 2 + 20 + 1, "{}", tree);
+    assert_eq!(tree.matches("(if").count(), 1, "{}", tree);
+}
+
+#[test]
+fn ten_arm_chains_take_the_first_true_branch() {
+    let chain = chain(10);
+    for x in 1..=10 {
+        let code = format!("var x = {}; {}", x, chain);
+        assert_output(&code, &[&x.to_string()]);
+    }
+    assert_output(&format!("var x = 11; {}", chain), &["else"]);
+}
+
+#[test]
+fn later_conditions_are_not_evaluated_once_one_is_true() {
+    let code = "
+        fun check(n) { print n; return n == 2; }
+        if (check(1)) print \"one\";
+        else if (check(2)) print \"two\";
+        else if (check(3)) print \"three\";
+    ";
+    assert_output(code, &["1", "2", "two"]);
+}
+
+#[test]
+fn branches_scope_their_blocks() {
+    let code = "
+        var x = \"global\";
+        if (false) { var x = \"first\"; }
+        else if (true) { var x = \"second\"; print x; }
+        print x;
+    ";
+    assert_output(code, &["second", "global"]);
+}
+
+#[test]
+fn garbage_after_the_last_else_is_reported_there() {
+    assert_eq!(
+        syntax_error("if (true) print 1; else if (false) print 2; else )"),
+        "Parser Error: test.lox:1:50 Expect a statement after 'else'."
+    );
+    assert_eq!(
+        syntax_error("if (true) print 1; else print 2; else print 3;"),
+        "Parser Error: test.lox:1:34 'else' without a matching 'if'."
+    );
+    assert_eq!(
+        syntax_error("if (true) print 1; else\n"),
+        "Parser Error: test.lox:2:1 Expect a statement after 'else'."
+    );
+}
+
+#[test]
+fn malformed_arms_name_the_else_if() {
+    assert_eq!(
+        syntax_error("if (true) print 1; else if (false) print 2; else if false) print 3;"),
+        "Parser Error: test.lox:1:53 Expect '(' after 'else if'"
+    );
+    assert_eq!(
+        syntax_error("if (true) print 1; else if (false print 2;"),
+        "Parser Error: test.lox:1:35 Expect ')' after 'else if'"
+    );
+    assert_eq!(
+        syntax_error("if (true) print 1; else if (false) else print 2;"),
+        "Parser Error: test.lox:1:36 Expect a statement after the 'else if' condition."
+    );
+    assert_eq!(
+        syntax_error("if (true) } else print 2;"),
+        "Parser Error: test.lox:1:11 Expect a statement after the 'if' condition."
+    );
+}
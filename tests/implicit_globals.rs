@@ -0,0 +1,76 @@
+//! Assigning to undefined names, strictly and with implicit globals allowed.
+
+use rlox::{Interpreter, LoxError, OutputBuffer, Runner};
+
+fn runner(implicit_globals: bool) -> (Runner, OutputBuffer) {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.set_implicit_globals(implicit_globals);
+    (runner, output)
+}
+
+fn runtime_error(result: Result<(), LoxError>) -> String {
+    match result {
+        Err(LoxError::Runtime(diagnostic)) => diagnostic.message,
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
+
+#[test]
+fn assignments_are_strict_by_default() {
+    let (mut runner, _) = runner(false);
+    assert_eq!(
+        runtime_error(runner.run("test.lox", "total = 1;")),
+        "Undefined variable 'total'."
+    );
+    assert!(runner.interpreter().globals().get("total").is_none());
+}
+
+#[test]
+fn top_level_assignments_define_globals_when_allowed() {
+    let (mut runner, output) = runner(true);
+    runner
+        .run(
+            "test.lox",
+            "total = 1;\nprint total;\n{ var local = 2; count = local; }\nprint count;\n",
+        )
+        .unwrap();
+    assert_eq!(output.contents(), "1\n2\n");
+    assert_eq!(
+        runner.interpreter().get_global_number("count").unwrap(),
+        2.0
+    );
+
+    // Once defined, later input sees it like any other global.
+    runner.run("test.lox", "total = total + 1;").unwrap();
+    assert_eq!(
+        runner.interpreter().get_global_number("total").unwrap(),
+        2.0
+    );
+}
+
+#[test]
+fn functions_never_define_globals_implicitly() {
+    let (mut runner, _) = runner(true);
+    assert_eq!(
+        runtime_error(runner.run("test.lox", "fun f() { leaked = 1; }\nf();\n")),
+        "Undefined variable 'leaked'."
+    );
+    assert_eq!(
+        runtime_error(runner.run("test.lox", "class Box { init() { leaked = 1; } }\nBox();\n")),
+        "Undefined variable 'leaked'."
+    );
+    assert!(runner.interpreter().globals().get("leaked").is_none());
+}
+
+#[test]
+fn constants_stay_constant() {
+    let (mut runner, _) = runner(true);
+    assert_eq!(
+        runtime_error(runner.run("test.lox", "const limit = 1;\nlimit = 2;\n")),
+        "Cannot assign to constant 'limit'."
+    );
+}
@@ -0,0 +1,164 @@
+//! The `rlox-ast` options and what `inspect` renders for each of them.
+
+use rlox::{
+    inspect::{self, Command, Format, InspectError, Options},
+    LoxError,
+};
+
+const SCRIPT: &str = "var a = 1;\nprint a + 2;\n";
+
+fn options(args: &[&str]) -> Options {
+    match inspect::parse_args(args.iter().map(|arg| arg.to_string()).collect()) {
+        Ok(Command::Inspect(options)) => options,
+        other => panic!("{:?} didn't parse to an inspection: {:?}", args, other),
+    }
+}
+
+fn parse_error(args: &[&str]) -> String {
+    inspect::parse_args(args.iter().map(|arg| arg.to_string()).collect()).unwrap_err()
+}
+
+fn inspect(code: &str, args: &[&str]) -> String {
+    inspect::inspect("test.lox", code, &options(args)).unwrap()
+}
+
+#[test]
+fn no_options_read_stdin_as_sexpr() {
+    assert_eq!(
+        options(&[]),
+        Options {
+            path: None,
+            format: Format::Sexpr,
+            tokens: false,
+            stmt: None,
+            desugar: false,
+            resolve: false,
+        }
+    );
+    assert_eq!(options(&["-"]).path, None);
+    assert_eq!(options(&["a.lox"]).path, Some("a.lox".into()));
+    assert_eq!(
+        inspect(SCRIPT, &[]),
+        "(version 1)\n(define a 1)\n(print (+ (var a) 2))\n"
+    );
+}
+
+#[test]
+fn tokens_dumps_the_token_stream() {
+    assert_eq!(
+        inspect("print a;\n", &["--tokens"]),
+        "\
+LINE:COL  TOKEN       LEXEME
+1:1       Print       print
+1:7       Identifier  a
+1:8       Semicolon   ;
+2:1       Eof
+"
+    );
+}
+
+#[test]
+fn stmt_picks_one_top_level_statement() {
+    assert_eq!(
+        inspect(SCRIPT, &["--stmt", "2"]),
+        "(version 1)\n(print (+ (var a) 2))\n"
+    );
+
+    match inspect::inspect("test.lox", SCRIPT, &options(&["--stmt", "3"])) {
+        Err(
+            error @ InspectError::NoSuchStatement {
+                requested: 3,
+                count: 2,
+            },
+        ) => {
+            assert_eq!(error.exit_code(), 64)
+        }
+        other => panic!("expected a missing statement, got {:?}", other),
+    }
+}
+
+#[test]
+fn json_format() {
+    assert_eq!(
+        inspect(SCRIPT, &["--format", "json", "--stmt", "1"]),
+        "[{\"type\":\"Var\",\"span\":{\"line\":1,\"column\":1,\"start\":0,\"end\":10},\
+         \"name\":\"a\",\"initializer\":{\"type\":\"Literal\",\
+         \"span\":{\"line\":1,\"column\":9,\"start\":8,\"end\":9},\"value\":1}}]\n"
+    );
+}
+
+#[test]
+fn dot_format() {
+    assert_eq!(
+        inspect(SCRIPT, &["--format", "dot", "--stmt", "1"]),
+        "\
+digraph ast {
+  node [shape=box];
+  n0 [label=\"Var [1:1]\\nname: a\"];
+  n1 [label=\"Literal [1:9]\\nvalue: 1\"];
+  n0 -> n1 [label=\"initializer\"];
+}
+"
+    );
+}
+
+#[test]
+fn desugar_lowers_for_loops() {
+    assert_eq!(
+        inspect("for (var i = 0; i < 2; i = i + 1) print i;", &["--desugar"]),
+        "(version 1)\n(block (define i 0)\n  \
+         (while (< (var i) 2) (block (print (var i)) (expr (= i (+ (var i) 1))))))\n"
+    );
+}
+
+#[test]
+fn resolve_shows_scope_distances() {
+    let output = inspect(
+        "fun f(x) { return x; }\n{ var y = 1; { print y; } }",
+        &["--resolve"],
+    );
+    assert!(output.contains("x@0"), "{}", output);
+    assert!(output.contains("y@1"), "{}", output);
+}
+
+#[test]
+fn syntax_errors_exit_65() {
+    match inspect::inspect("test.lox", "print ;", &options(&[])) {
+        Err(error @ InspectError::Lox(LoxError::Parse(_))) => {
+            assert_eq!(error.exit_code(), 65)
+        }
+        other => panic!("expected a syntax error, got {:?}", other),
+    }
+}
+
+#[test]
+fn invalid_options_are_errors() {
+    assert_eq!(parse_error(&["--format", "xml"]), "Unknown format 'xml'.");
+    assert_eq!(
+        parse_error(&["--format"]),
+        "--format needs sexpr, json or dot."
+    );
+    for stmt in [&["--stmt"][..], &["--stmt", "0"], &["--stmt", "one"]] {
+        assert_eq!(
+            parse_error(stmt),
+            "--stmt needs a statement number, starting from 1."
+        );
+    }
+    assert_eq!(parse_error(&["--trace"]), "Unknown option '--trace'.");
+    assert_eq!(
+        parse_error(&["a.lox", "b.lox"]),
+        "Only one script can be inspected at a time."
+    );
+    assert_eq!(
+        parse_error(&["--tokens", "--stmt", "1"]),
+        "--tokens can't be combined with --format, --stmt, --desugar or --resolve."
+    );
+    assert_eq!(
+        parse_error(&["--resolve", "--format", "json"]),
+        "--resolve only works with the sexpr format."
+    );
+    assert_eq!(
+        inspect::parse_args(vec!["x.lox".into(), "--help".into()]),
+        Ok(Command::Help)
+    );
+}
@@ -0,0 +1,62 @@
+//! Arguments that have to be integers all follow the same rules and say so the same way.
+
+use rlox::testing::{assert_output, assert_runtime_error};
+
+#[test]
+fn fractions_negatives_and_non_numbers_are_rejected_everywhere() {
+    let cases = [
+        ("[1, 2].insert(1.5, 0);", "insert", "1", "1.5"),
+        ("[1, 2].remove(-1);", "remove", "1", "-1"),
+        ("print (255).toString(0.5);", "toString", "1", "0.5"),
+        ("print (1).toFixed(\"2\");", "toFixed", "1", "2"),
+        ("print (1).toPrecision(nil);", "toPrecision", "1", "nil"),
+        ("prettyPrint([1], 1.5);", "prettyPrint", "2", "1.5"),
+    ];
+    for (code, method, position, value) in cases {
+        assert_runtime_error(
+            code,
+            &format!(
+                "Argument {} to '{}' must be a non-negative integer, got {}.",
+                position, method, value
+            ),
+        );
+    }
+
+    assert_runtime_error(
+        "[1].remove(0 / 0);",
+        "Argument 1 to 'remove' must be a non-negative integer, got NaN.",
+    );
+}
+
+#[test]
+fn out_of_range_integers_name_the_limit() {
+    assert_runtime_error(
+        "[1, 2].insert(3, 0);",
+        "Argument 1 to 'insert' must be at most 2, got 3.",
+    );
+    assert_runtime_error(
+        "[1, 2].remove(100000000000 * 1000000000);",
+        "Argument 1 to 'remove' must be at most 1, got 100000000000000000000.",
+    );
+    assert_runtime_error(
+        "print (255).toString(37);",
+        "Argument 1 to 'toString' must be at most 36, got 37.",
+    );
+    assert_runtime_error(
+        "print (255).toString(1);",
+        "Argument 1 to 'toString' must be at least 2, got 1.",
+    );
+    assert_runtime_error(
+        "print (1).toPrecision(0);",
+        "Argument 1 to 'toPrecision' must be at least 1, got 0.",
+    );
+    assert_runtime_error("[].remove(0);", "Can't remove from an empty array.");
+}
+
+#[test]
+fn negative_zero_is_zero() {
+    assert_output(
+        "var a = [1, 2];\na.insert(-0, 0);\nprint a;\nprint a.remove(-0);\nprint (255).toString(16);\n",
+        &["[0, 1, 2]", "0", "ff"],
+    );
+}
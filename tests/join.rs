@@ -0,0 +1,58 @@
+//! The `join()` native.
+
+use rlox::testing::{run_output, runtime_error};
+
+#[test]
+fn joins_strings_with_the_separator() {
+    assert_eq!(
+        run_output(
+            "print join([\"a\", \"b\", \"c\"], \", \");\nprint join([\"a\", \"b\"], \"\");\n\
+             print join([\"only\"], \"-\");"
+        )
+        .unwrap(),
+        "a, b, c\nab\nonly\n"
+    );
+}
+
+#[test]
+fn an_empty_array_joins_to_the_empty_string() {
+    assert_eq!(
+        run_output("var s = join([], \", \");\nprint s.len();\nprint s == \"\";").unwrap(),
+        "0\ntrue\n"
+    );
+}
+
+#[test]
+fn arrays_built_in_a_loop() {
+    let code = "var pieces = [];\nfor (var i = 0; i < 1000; i = i + 1) pieces.push(\"ab\");\n\
+                print join(pieces, \"\").len();\nprint join(pieces, \",\").len();";
+    assert_eq!(run_output(code).unwrap(), "2000\n2999\n");
+}
+
+#[test]
+fn non_string_elements_are_an_error() {
+    assert_eq!(
+        runtime_error("print join([1, \"a\"], \",\");"),
+        "test.lox:1:7 join() can only join Strings, but element 0 is a Number."
+    );
+    assert_eq!(
+        runtime_error("print join([\"a\", \"b\", nil], \",\");"),
+        "test.lox:1:7 join() can only join Strings, but element 2 is a Nil."
+    );
+    assert_eq!(
+        runtime_error("print join([\"a\", [\"b\"]], \",\");"),
+        "test.lox:1:7 join() can only join Strings, but element 1 is a Array."
+    );
+}
+
+#[test]
+fn the_arguments_must_be_an_array_and_a_string() {
+    assert_eq!(
+        runtime_error("join(\"ab\", \",\");"),
+        "test.lox:1:1 join() needs an Array to join, not a String."
+    );
+    assert_eq!(
+        runtime_error("join([\"a\"], 1);"),
+        "test.lox:1:1 join() needs a String separator, not a Number."
+    );
+}
@@ -0,0 +1,78 @@
+//! `jsonEncode()` and `jsonDecode()`, which turn Lox values into JSON text and back.
+
+use rlox::{
+    testing::{run_output, runtime_error},
+    Interpreter, LoxError, OutputBuffer, Runner,
+};
+
+/// Runs `code` with the global `json` bound to `text`, so the JSON can use the escapes Lox
+/// string literals don't have.
+fn run_with_json(text: &str, code: &str) -> Result<String, LoxError> {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.define_global("json", text);
+    runner.run("test.lox", code)?;
+    Ok(output.contents())
+}
+
+#[test]
+fn nested_values_round_trip() {
+    let code = "var data = {\"name\": \"lox\", \"tags\": [\"a\", \"b\"], \"nested\": {\"n\": 1.5, \"ok\": true, \"none\": nil}, \"list\": [[1, 2], {}], \"z\": -0.25};\n\
+                var text = jsonEncode(data);\nprint text;\nvar back = jsonDecode(text);\n\
+                print jsonEncode(back) == text;\nprint back;\n";
+    assert_eq!(
+        run_output(code).unwrap(),
+        "{\"name\":\"lox\",\"tags\":[\"a\",\"b\"],\"nested\":{\"n\":1.5,\"ok\":true,\"none\":null},\"list\":[[1,2],{}],\"z\":-0.25}\n\
+         true\n\
+         {name: lox, tags: [a, b], nested: {n: 1.5, ok: true, none: nil}, list: [[1, 2], {}], z: -0.25}\n"
+    );
+}
+
+#[test]
+fn decoding_keeps_key_order_and_reads_escapes() {
+    assert_eq!(
+        run_with_json(
+            " {\"b\": 1, \"a\": [null, false, \"\\u00e9\\t\\\"\"]} ",
+            "var value = jsonDecode(json);\nprint value.keys();\nprint jsonEncode(value);\n"
+        )
+        .unwrap(),
+        "[b, a]\n{\"b\":1,\"a\":[null,false,\"\u{e9}\\t\\\"\"]}\n"
+    );
+}
+
+#[test]
+fn values_without_a_json_form_are_named_by_path() {
+    assert_eq!(
+        runtime_error("jsonEncode(0 / 0);"),
+        "test.lox:1:1 Cannot encode $ as JSON: NaN has no JSON representation."
+    );
+    assert_eq!(
+        runtime_error("fun h() {}\njsonEncode({\"config\": {\"handler\": h}});"),
+        "test.lox:2:1 Cannot encode $.config.handler as JSON: a function has no JSON representation."
+    );
+    assert_eq!(
+        runtime_error("jsonEncode([1, clock]);"),
+        "test.lox:1:1 Cannot encode $[1] as JSON: a function has no JSON representation."
+    );
+}
+
+#[test]
+fn malformed_text_reports_its_offset() {
+    assert_eq!(
+        runtime_error("jsonDecode(\"[1, 2] x\");"),
+        "test.lox:1:1 Invalid JSON at byte 7: unexpected text after the JSON value."
+    );
+    match run_with_json("{\"a\": }", "jsonDecode(json);") {
+        Err(LoxError::Runtime(diagnostic)) => {
+            assert!(
+                diagnostic.message.starts_with("Invalid JSON at byte 6:"),
+                "{}",
+                diagnostic.message
+            )
+        }
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
@@ -0,0 +1,116 @@
+//! Compiling the same script with the whole language and with parts of it switched off.
+
+use rlox::{DiagnosticOptions, Interpreter, LanguageOptions, LoxError, OutputBuffer, Runner};
+
+const SCRIPT: &str = "\
+enum Color { Red, Green }
+class Point {
+  init(x) { this.x = x; }
+}
+fun total(n) {
+  var sum = 0;
+  for (var i = 0; i < n; i = i + 1) {
+    while (false) {}
+    sum = sum + i;
+  }
+  return sum;
+}
+print total(4);
+";
+
+/// Everything allowed except what `restrict` switches off.
+fn restricted(restrict: impl FnOnce(&mut LanguageOptions)) -> LanguageOptions {
+    let mut language = LanguageOptions::default();
+    restrict(&mut language);
+    language
+}
+
+/// The line and message of each problem `compile()` finds in `SCRIPT` under `language`.
+fn problems(language: LanguageOptions) -> Vec<(usize, String)> {
+    match rlox::compile("config.lox", SCRIPT, language) {
+        Ok(()) => vec![],
+        Err(LoxError::Parse(diagnostics)) => diagnostics
+            .into_iter()
+            .map(|diagnostic| (diagnostic.line, diagnostic.message))
+            .collect(),
+        Err(other) => panic!("expected a parse error, got {:?}", other),
+    }
+}
+
+#[test]
+fn everything_is_allowed_by_default() {
+    assert_eq!(problems(LanguageOptions::default()), []);
+}
+
+/// Checks that switching off one construct reports it, and only it, at `line`.
+fn reported_alone(restrict: impl FnOnce(&mut LanguageOptions), line: usize, what: &str) {
+    assert_eq!(
+        problems(restricted(restrict)),
+        [(line, format!("{} are not allowed in this context.", what))]
+    );
+}
+
+#[test]
+fn each_construct_is_reported_at_its_keyword() {
+    reported_alone(|l| l.enums = false, 1, "enums");
+    reported_alone(|l| l.classes = false, 2, "classes");
+    reported_alone(|l| l.functions = false, 5, "functions");
+    reported_alone(|l| l.for_loops = false, 7, "for-loops");
+    reported_alone(|l| l.while_loops = false, 8, "while-loops");
+}
+
+#[test]
+fn every_disallowed_construct_is_reported() {
+    let nothing = LanguageOptions {
+        classes: false,
+        enums: false,
+        functions: false,
+        for_loops: false,
+        while_loops: false,
+        ..LanguageOptions::default()
+    };
+
+    assert_eq!(
+        problems(nothing)
+            .into_iter()
+            .map(|(line, _)| line)
+            .collect::<Vec<_>>(),
+        [1, 2, 5, 7, 8]
+    );
+}
+
+#[test]
+fn other_errors_are_still_found() {
+    let no_loops = restricted(|l| l.while_loops = false);
+
+    match rlox::compile("config.lox", "while (true) {}\nvar = 1;", no_loops) {
+        Err(LoxError::Parse(diagnostics)) => assert_eq!(
+            diagnostics.iter().map(|d| d.line).collect::<Vec<_>>(),
+            [1, 2]
+        ),
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+}
+
+#[test]
+fn the_runner_refuses_to_run_a_disallowed_script() {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .no_prelude()
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.set_diagnostic_options(DiagnosticOptions {
+        print: None,
+        ..DiagnosticOptions::default()
+    });
+
+    runner.run("config.lox", SCRIPT).unwrap();
+    assert_eq!(output.contents(), "6\n");
+
+    runner.set_language(restricted(|l| l.for_loops = false));
+    let error = runner.run("config.lox", SCRIPT).unwrap_err();
+
+    assert!(matches!(error, LoxError::Parse(_)), "{:?}", error);
+    assert_eq!(output.contents(), "6\n");
+}
@@ -0,0 +1,138 @@
+//! Lenient mode, where some operations on operands of the wrong type warn and go on.
+
+use rlox::{
+    testing::runtime_error, DiagnosticOptions, Interpreter, LoxError, OutputBuffer, Runner,
+};
+
+/// Runs `code` in lenient mode, returning what it printed and the lenient-operation warnings
+/// as located messages with their help.
+fn run_lenient(code: &str) -> Result<(String, Vec<String>), LoxError> {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.set_diagnostic_options(DiagnosticOptions {
+        print: None,
+        ..DiagnosticOptions::default()
+    });
+    runner.set_lenient(true);
+    runner.run("test.lox", code)?;
+
+    let warnings = runner
+        .diagnostics()
+        .diagnostics()
+        .iter()
+        .filter(|diagnostic| diagnostic.code == "lenient-operation")
+        .map(|diagnostic| {
+            format!(
+                "{} {} {}",
+                diagnostic.location,
+                diagnostic.message,
+                diagnostic.help.as_deref().unwrap_or_default()
+            )
+        })
+        .collect();
+    Ok((output.contents(), warnings))
+}
+
+#[test]
+fn nil_counts_as_zero_in_arithmetic() {
+    let code = "var missing;\nprint missing + 5;\n";
+    assert_eq!(
+        runtime_error(code),
+        "test.lox:2:7 Operands must be two numbers or two strings."
+    );
+
+    let (output, warnings) = run_lenient(code).unwrap();
+    assert_eq!(output, "5\n");
+    assert_eq!(
+        warnings,
+        ["test.lox:2:7 Operands must be two numbers or two strings. \
+Lenient mode went on by treating nil as 0."]
+    );
+
+    let code = "var missing;\nprint 10 - missing;\nprint missing * 3;\nprint -missing;\n";
+    assert!(runtime_error(code).starts_with("test.lox:2:7 "));
+    let (output, warnings) = run_lenient(code).unwrap();
+    assert_eq!(output, "10\n0\n0\n");
+    assert_eq!(warnings.len(), 3);
+    assert!(
+        warnings[2].starts_with("test.lox:4:8 Operand of '-' must be a number, but it is nil."),
+        "{}",
+        warnings[2]
+    );
+}
+
+#[test]
+fn numbers_added_to_strings_are_converted() {
+    let code = "print \"total: \" + 2.5;\nprint 3 + \" items\";\n";
+    assert_eq!(
+        runtime_error(code),
+        "test.lox:1:7 Operands must be two numbers or two strings."
+    );
+
+    let (output, warnings) = run_lenient(code).unwrap();
+    assert_eq!(output, "total: 2.5\n3 items\n");
+    assert_eq!(
+        warnings,
+        [
+            "test.lox:1:7 Operands must be two numbers or two strings. \
+Lenient mode went on by converting the number to a string.",
+            "test.lox:2:7 Operands must be two numbers or two strings. \
+Lenient mode went on by converting the number to a string.",
+        ]
+    );
+}
+
+#[test]
+fn comparisons_of_mixed_types_are_false() {
+    let code = "print 1 < \"2\";\nprint nil >= 0;\nprint \"b\" > 1;\n";
+    assert_eq!(
+        runtime_error(code),
+        "test.lox:1:7 Operands must be numbers, but the right operand is a String."
+    );
+
+    let (output, warnings) = run_lenient(code).unwrap();
+    assert_eq!(output, "false\nfalse\nfalse\n");
+    assert_eq!(
+        warnings[0],
+        "test.lox:1:7 Operands must be numbers, but the right operand is a String. \
+Lenient mode went on by comparing the values as false."
+    );
+    assert_eq!(warnings.len(), 3);
+}
+
+#[test]
+fn other_type_errors_still_fail() {
+    for code in [
+        "print true + 1;",
+        "print nil + nil;",
+        "print \"a\" - 1;",
+        "print \"a\" < \"b\";",
+        "print -\"a\";",
+    ] {
+        assert!(
+            matches!(run_lenient(code), Err(LoxError::Runtime(_))),
+            "{}",
+            code
+        );
+    }
+}
+
+#[test]
+fn each_place_is_warned_about_once() {
+    let code = "
+        var total;
+        for (var i = 0; i < 3; i = i + 1) {
+          total = total + 1;
+          print \"#\" + i;
+        }
+        print total;
+    ";
+    let (output, warnings) = run_lenient(code).unwrap();
+    assert_eq!(output, "#0\n#1\n#2\n3\n");
+    assert_eq!(warnings.len(), 2, "{:?}", warnings);
+    assert!(warnings[0].starts_with("test.lox:4:19 "), "{}", warnings[0]);
+    assert!(warnings[1].starts_with("test.lox:5:17 "), "{}", warnings[1]);
+}
@@ -0,0 +1,148 @@
+//! The limits an embedder can put on string length, collection size and live environments,
+//! which fire exactly when a value would grow past them.
+
+use rlox::{Interpreter, Limits, LoxError, OutputBuffer, Runner};
+
+/// Runs `code` under `limits`, returning what it printed or the message it failed with.
+fn run(limits: Limits, code: &str) -> Result<String, String> {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .limits(limits)
+        .no_prelude()
+        .output(Box::new(output.clone()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    match runner.run("test.lox", code) {
+        Ok(()) => Ok(output.contents()),
+        Err(LoxError::Runtime(diagnostic)) => Err(diagnostic.message),
+        Err(e) => panic!("expected {:?} to run, got {}", code, e),
+    }
+}
+
+fn strings(max: usize) -> Limits {
+    Limits {
+        max_string_length: Some(max),
+        ..Limits::default()
+    }
+}
+
+fn collections(max: usize) -> Limits {
+    Limits {
+        max_collection_len: Some(max),
+        ..Limits::default()
+    }
+}
+
+fn environments(max: usize) -> Limits {
+    Limits {
+        max_environments: Some(max),
+        ..Limits::default()
+    }
+}
+
+#[test]
+fn nothing_is_limited_by_default() {
+    assert_eq!(
+        Limits::default(),
+        Limits {
+            max_string_length: None,
+            max_collection_len: None,
+            max_environments: None,
+            max_statements: None,
+        }
+    );
+    assert_eq!(
+        run(
+            Limits::default(),
+            "var s = \"ab\";\nfor (var i = 0; i < 10; i = i + 1) s = s + s;\nprint s.len();"
+        ),
+        Ok("2048\n".into())
+    );
+}
+
+#[test]
+fn concatenation_stops_past_the_string_length() {
+    assert_eq!(
+        run(strings(4), "print \"ab\" + \"cd\";"),
+        Ok("abcd\n".into())
+    );
+    assert_eq!(
+        run(strings(4), "print \"ab\" + \"cde\";"),
+        Err("The string would be 5 characters long, over the max_string_length limit of 4.".into())
+    );
+    // Characters are counted, not bytes.
+    assert_eq!(
+        run(strings(2), "print \"\u{e9}\" + \"\u{e9}\";"),
+        Ok("\u{e9}\u{e9}\n".into())
+    );
+}
+
+#[test]
+fn pushing_stops_past_the_collection_length() {
+    assert_eq!(
+        run(collections(3), "var a = [1, 2];\na.push(3);\nprint a;"),
+        Ok("[1, 2, 3]\n".into())
+    );
+    assert_eq!(
+        run(collections(3), "var a = [1, 2, 3];\na.push(4);"),
+        Err("The array would have 4 elements, over the max_collection_len limit of 3.".into())
+    );
+    assert_eq!(
+        run(collections(3), "var a = [1, 2, 3];\na.insert(0, 0);"),
+        Err("The array would have 4 elements, over the max_collection_len limit of 3.".into())
+    );
+}
+
+#[test]
+fn literals_count_against_the_collection_length() {
+    assert_eq!(run(collections(2), "print [1, 2];"), Ok("[1, 2]\n".into()));
+    assert_eq!(
+        run(collections(2), "print [1, 2, 3];"),
+        Err("The array would have 3 elements, over the max_collection_len limit of 2.".into())
+    );
+    assert_eq!(
+        run(collections(1), "print {\"a\": 1, \"b\": 2};"),
+        Err("The map would have 2 elements, over the max_collection_len limit of 1.".into())
+    );
+}
+
+#[test]
+fn map_inserts_stop_past_the_collection_length() {
+    assert_eq!(
+        run(
+            collections(2),
+            "var m = {\"a\": 1, \"b\": 2};\nm.set(\"a\", 3);\nprint m;"
+        ),
+        Ok("{a: 3, b: 2}\n".into())
+    );
+    assert_eq!(
+        run(
+            collections(2),
+            "var m = {\"a\": 1, \"b\": 2};\nm.set(\"c\", 3);"
+        ),
+        Err("The map would have 3 elements, over the max_collection_len limit of 2.".into())
+    );
+}
+
+#[test]
+fn environments_stop_at_their_limit() {
+    // The globals are one environment and each block another.
+    assert_eq!(run(environments(3), "{ { print 1; } }"), Ok("1\n".into()));
+    assert_eq!(
+        run(environments(3), "{ { { print 1; } } }"),
+        Err("There would be 4 environments, over the max_environments limit of 3.".into())
+    );
+}
+
+#[test]
+fn environments_limit_recursion() {
+    let code = "fun depth(n) { if (n == 0) return 0; return 1 + depth(n - 1); }\nprint depth(";
+    assert_eq!(
+        run(environments(11), &format!("{}9);", code)),
+        Ok("9\n".into())
+    );
+    assert_eq!(
+        run(environments(11), &format!("{}10);", code)),
+        Err("There would be 12 environments, over the max_environments limit of 11.".into())
+    );
+}
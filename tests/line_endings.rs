@@ -0,0 +1,83 @@
+//! Scripts saved with Windows (`\r\n`) or old Mac (`\r`) line endings.
+
+use rlox::{
+    inspect,
+    testing::{run_output, runtime_error, syntax_error},
+    Interpreter, OutputBuffer, Runner,
+};
+
+const SCRIPT: &str = "\
+// Greets everyone.
+var greeting = \"hello
+there\";
+fun greet(who) {
+  print greeting + \" \" + who;
+}
+greet(\"lox\");
+";
+
+fn crlf(code: &str) -> String {
+    code.replace('\n', "\r\n")
+}
+
+fn cr(code: &str) -> String {
+    code.replace('\n', "\r")
+}
+
+fn inspect(code: &str, tokens: bool) -> String {
+    let options = inspect::Options {
+        path: None,
+        format: inspect::Format::Sexpr,
+        tokens,
+        stmt: None,
+        desugar: false,
+        resolve: false,
+    };
+    inspect::inspect("test.lox", code, &options).unwrap()
+}
+
+#[test]
+fn strings_hold_plain_newlines() {
+    let expected = "hello\nthere lox\n";
+    assert_eq!(run_output(SCRIPT).unwrap(), expected);
+    assert_eq!(run_output(&crlf(SCRIPT)).unwrap(), expected);
+    assert_eq!(run_output(&cr(SCRIPT)).unwrap(), expected);
+}
+
+#[test]
+fn streamed_scripts_read_the_same() {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner
+        .run_streaming("test.lox", crlf(SCRIPT).as_bytes())
+        .unwrap();
+    assert_eq!(output.contents(), "hello\nthere lox\n");
+}
+
+#[test]
+fn errors_are_on_the_same_lines() {
+    let code = "var a = 1;\n// nothing here\nprint a + nil;\n";
+    let expected = "test.lox:3:7 Operands must be two numbers or two strings.";
+    assert_eq!(runtime_error(code), expected);
+    assert_eq!(runtime_error(&crlf(code)), expected);
+    assert_eq!(runtime_error(&cr(code)), expected);
+
+    let code = "var a = \"one\ntwo\";\nprint a +;\n";
+    let expected = "Parser Error: test.lox:3:10 Expect expression, found ';'.";
+    assert_eq!(syntax_error(code), expected);
+    assert_eq!(syntax_error(&crlf(code)), expected);
+    assert_eq!(syntax_error(&cr(code)), expected);
+}
+
+#[test]
+fn inspected_output_has_no_carriage_returns() {
+    for tokens in [false, true] {
+        let expected = inspect(SCRIPT, tokens);
+        assert_eq!(inspect(&crlf(SCRIPT), tokens), expected);
+        assert_eq!(inspect(&cr(SCRIPT), tokens), expected);
+        assert!(!expected.contains('\r'));
+    }
+}
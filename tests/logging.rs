@@ -0,0 +1,63 @@
+//! Interpreter events sent to the `log` facade, with the `log` feature on.
+
+#![cfg(feature = "log")]
+
+use std::sync::Mutex;
+
+use log::{LevelFilter, Log, Metadata, Record};
+use rlox::{DiagnosticOptions, Interpreter, OutputBuffer, Runner};
+
+/// Keeps every record from this crate, as `LEVEL message`.
+struct Capture(Mutex<Vec<String>>);
+
+impl Log for Capture {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.target().starts_with("rlox")
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", record.level(), record.args()));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: Capture = Capture(Mutex::new(vec![]));
+
+#[test]
+fn a_script_reports_its_calls_definitions_and_errors() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(LevelFilter::Debug);
+
+    let interpreter = Interpreter::builder()
+        .output(Box::new(OutputBuffer::new()))
+        .no_prelude()
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.set_diagnostic_options(DiagnosticOptions {
+        print: None,
+        ..DiagnosticOptions::default()
+    });
+    let code = "fun add(a, b) {\n  return a + b;\n}\nprint add(1, 2);\nadd(1, nil);\n";
+    assert!(runner.run("test.lox", code).is_err());
+
+    let events = LOGGER.0.lock().unwrap().clone();
+    assert_eq!(
+        events,
+        [
+            "DEBUG running script with 3 statements",
+            "DEBUG [line 1] defined function 'add' with 2 parameters",
+            "DEBUG [line 4] calling <fn add> (arity 2) with 2 arguments",
+            "DEBUG created environment at depth 1",
+            "DEBUG [line 5] calling <fn add> (arity 2) with 2 arguments",
+            "DEBUG reusing environment at depth 1",
+            "DEBUG error propagating out of <fn add>: Operands must be two numbers or two strings.",
+            "DEBUG script stopped by runtime error: Operands must be two numbers or two strings.",
+        ]
+    );
+}
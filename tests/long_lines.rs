@@ -0,0 +1,88 @@
+//! Sources with lines and string literals megabytes long, as minified or generated code has.
+
+use rlox::{
+    testing::{run_output, runtime_error, syntax_error},
+    LoxError, Runner,
+};
+
+const MEGABYTE: usize = 1024 * 1024;
+
+/// Many statements on a single line of more than two megabytes.
+fn long_line(last: &str) -> String {
+    let mut code = String::from("var total = 0;");
+    while code.len() < 2 * MEGABYTE {
+        code.push_str(" total = total + 1;");
+    }
+    code.push(' ');
+    code.push_str(last);
+    code
+}
+
+#[test]
+fn a_long_line_runs() {
+    let output = run_output(&long_line("print total > 100000;")).unwrap();
+    assert_eq!(output, "true\n");
+}
+
+#[test]
+fn errors_on_a_long_line_stay_short() {
+    let error = runtime_error(&long_line("print total - \"one\";"));
+    assert!(error.starts_with("test.lox:1:"), "{}", error);
+    assert!(error.len() < 200, "{}", error);
+
+    let error = syntax_error(&long_line("print total +;"));
+    assert!(error.contains("test.lox:1:"), "{}", error);
+    assert!(error.len() < 200, "{}", error);
+}
+
+#[test]
+fn quoted_expressions_are_cut_short() {
+    let key = "a".repeat(3 * MEGABYTE);
+    let error = runtime_error(&format!("var m = {{}};\nm.get(\"{}\")();", key));
+    assert_eq!(
+        error,
+        format!(
+            "test.lox:2:1 Attempted to call nil (from expression `m.get(\"{}...`).",
+            "a".repeat(50)
+        )
+    );
+}
+
+#[test]
+fn huge_string_literals_within_the_limit_are_read() {
+    let text = "ab".repeat(2 * MEGABYTE);
+    let output = run_output(&format!("var s = \"{}\";\nprint s.len();", text)).unwrap();
+    assert_eq!(output, format!("{}\n", 4 * MEGABYTE));
+}
+
+#[test]
+fn string_literals_over_the_limit_are_rejected() {
+    let mut runner = Runner::new();
+    runner.set_max_string_literal(MEGABYTE);
+
+    let code = format!(
+        "var s = \"{}\";\nvar t = \"{}\";",
+        "x".repeat(MEGABYTE),
+        "x".repeat(3 * MEGABYTE)
+    );
+    let errors = match runner.run("test.lox", &code) {
+        Err(LoxError::Lex(errors)) => errors,
+        other => panic!("expected a lex error, got {:?}", other.map(|_| ())),
+    };
+    let errors: Vec<String> = errors
+        .iter()
+        .map(|error| format!("{} {}", error.location, error.message))
+        .collect();
+    assert_eq!(
+        errors,
+        [format!(
+            "test.lox:2:9 String literal is {} bytes long, over the limit of {} bytes. Load data \
+             this large from a file instead.",
+            3 * MEGABYTE,
+            MEGABYTE
+        )]
+    );
+
+    let code = format!("var s = \"{}\";\nprint s.len();", "x".repeat(MEGABYTE));
+    assert!(runner.run("test.lox", &code).is_ok());
+}
@@ -0,0 +1,66 @@
+//! Numbers as map keys, and the values that can't be keys.
+
+use rlox::testing::{assert_output, assert_runtime_error};
+
+#[test]
+fn equal_numbers_are_the_same_key() {
+    assert_output(
+        "var m = {0: \"zero\"};\nprint m.get(-0);\nm.set(1.0, \"one\");\nprint m.get(1);\nprint m.len();",
+        &["zero", "one", "2"],
+    );
+    assert_output(
+        "var m = {1: \"a\", 1.0: \"b\"};\nprint m.len();\nprint m.get(2 - 1);",
+        &["1", "b"],
+    );
+}
+
+#[test]
+fn numbers_and_strings_are_different_keys() {
+    assert_output(
+        "var m = {1: \"number\", \"1\": \"string\"};\nprint m.len();\nprint m.get(1);\nprint m.get(\"1\");\nprint m.has(\"2\");",
+        &["2", "number", "string", "false"],
+    );
+}
+
+#[test]
+fn keys_come_back_with_their_types() {
+    assert_output(
+        "var m = {2: \"two\", \"x\": 1};\nvar keys = m.keys();\nprint keys.pop() + \"!\";\nprint keys.pop() + 1;",
+        &["x!", "3"],
+    );
+    assert_output("print {1.5: true, \"a\": 2};", &["{1.5: true, a: 2}"]);
+}
+
+#[test]
+fn nan_is_not_a_key() {
+    let nan = "var nan = 0 / 0;\n";
+    for code in [
+        "var m = {nan: 1};",
+        "var m = {};\nm.set(nan, 1);",
+        "var m = {};\nm.has(nan);",
+        "var m = {};\nm.remove(nan);",
+    ] {
+        assert_runtime_error(&format!("{}{}", nan, code), "NaN can't be a map key");
+    }
+}
+
+#[test]
+fn other_values_are_not_keys() {
+    assert_runtime_error(
+        "var m = {true: 1};",
+        "Map keys must be Strings or Numbers, got Bool.",
+    );
+    assert_runtime_error(
+        "var m = {};\nm.set(nil, 1);",
+        "Map keys must be Strings or Numbers, got Nil.",
+    );
+}
+
+#[test]
+fn json_has_no_number_keys() {
+    assert_runtime_error(
+        "jsonEncode({1: 2});",
+        "the number key 1 has no JSON representation",
+    );
+    assert_output("print jsonEncode({\"1\": 2});", &["{\"1\":2}"]);
+}
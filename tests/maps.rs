@@ -0,0 +1,59 @@
+//! Map literals and methods, and the insertion order maps keep.
+
+use rlox::testing::{assert_output, assert_runtime_error};
+
+#[test]
+fn entries_keep_the_order_they_were_inserted_in() {
+    assert_output(
+        "var m = {\"b\": 1, \"a\": 2};\nm.set(\"c\", 3);\nprint m;\nprint m.keys();\nprint m.values();",
+        &["{b: 1, a: 2, c: 3}", "[b, a, c]", "[1, 2, 3]"],
+    );
+}
+
+#[test]
+fn removing_and_reinserting_moves_a_key_to_the_end() {
+    assert_output(
+        "var m = {\"b\": 1, \"a\": 2, \"c\": 3};\nprint m.remove(\"b\");\nm.set(\"b\", 4);\nprint m;",
+        &["1", "{a: 2, c: 3, b: 4}"],
+    );
+}
+
+#[test]
+fn overwriting_a_key_keeps_its_place() {
+    assert_output(
+        "var m = {\"a\": 1, \"b\": 2};\nm.set(\"a\", 5);\nprint m;",
+        &["{a: 5, b: 2}"],
+    );
+}
+
+#[test]
+fn merge_takes_the_right_hand_side_on_conflicts() {
+    assert_output(
+        "var m = {\"a\": 1, \"b\": 2};\nprint m.merge({\"a\": 0, \"z\": 9});\nprint m;",
+        &["{a: 0, b: 2, z: 9}", "{a: 1, b: 2}"],
+    );
+}
+
+#[test]
+fn lookups_of_missing_keys_give_nil() {
+    assert_output(
+        "var m = {\"a\": 1};\nprint m.get(\"x\");\nprint m.has(\"x\");\nprint m.has(\"a\");\nprint m.remove(\"x\");\nprint m.len();",
+        &["nil", "false", "true", "nil", "1"],
+    );
+}
+
+#[test]
+fn maps_are_shared_not_copied() {
+    assert_output(
+        "var m = {};\nvar n = m;\nn.set(\"k\", 1);\nprint m;",
+        &["{k: 1}"],
+    );
+}
+
+#[test]
+fn only_strings_and_numbers_are_keys() {
+    assert_runtime_error(
+        "var m = {[1]: 2};",
+        "Map keys must be Strings or Numbers, got Array.",
+    );
+}
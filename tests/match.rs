@@ -0,0 +1,101 @@
+//! `match` expressions and their patterns.
+
+use rlox::{
+    testing::{run_output, runtime_error},
+    LoxError,
+};
+
+const CLASSIFY: &str = "fun classify(v) {\n  return match (v) {\n    1 -> \"one\";\n    \
+                        \"a\" -> \"letter\";\n    [x, y] -> x + y;\n    {name: n} -> n;\n    \
+                        _ -> \"other\";\n  };\n}\n";
+
+/// What `classify` returns for each of `values`, a line each.
+fn classify(values: &[&str]) -> String {
+    let calls: String = values
+        .iter()
+        .map(|value| format!("print classify({});\n", value))
+        .collect();
+    run_output(&format!("{}{}", CLASSIFY, calls)).unwrap()
+}
+
+#[test]
+fn literal_patterns() {
+    assert_eq!(
+        classify(&["1", "\"a\"", "2", "\"b\""]),
+        "one\nletter\nother\nother\n"
+    );
+}
+
+#[test]
+fn array_patterns_match_exactly_their_length() {
+    assert_eq!(
+        classify(&["[2, 3]", "[\"x\", \"y\"]", "[1]", "[1, 2, 3]"]),
+        "5\nxy\nother\nother\n"
+    );
+}
+
+#[test]
+fn map_patterns_need_at_least_their_keys() {
+    assert_eq!(
+        classify(&["{\"name\": \"ada\", \"age\": 36}", "{\"age\": 36}"]),
+        "ada\nother\n"
+    );
+}
+
+#[test]
+fn the_first_matching_arm_wins() {
+    assert_eq!(
+        run_output("print match (1) { _ -> \"wildcard\"; 1 -> \"one\"; };").unwrap(),
+        "wildcard\n"
+    );
+}
+
+#[test]
+fn bindings_are_local_to_the_arm() {
+    assert_eq!(
+        run_output("var x = 10;\nprint match (3) { x -> x * 2; };\nprint x;").unwrap(),
+        "6\n10\n"
+    );
+}
+
+#[test]
+fn literal_patterns_of_other_kinds() {
+    let code = "enum Shape { Circle, Square }\nfun f(v) { return match (v) {\n\
+                nil -> \"nothing\"; true -> \"yes\"; -1 -> \"minus one\"; \
+                Shape.Circle -> \"round\"; [] -> \"empty\"; _ -> \"other\"; }; }\n\
+                print f(nil);\nprint f(true);\nprint f(-1);\nprint f(Shape.Circle);\n\
+                print f(Shape.Square);\nprint f([]);\nprint f(false);";
+    assert_eq!(
+        run_output(code).unwrap(),
+        "nothing\nyes\nminus one\nround\nother\nempty\nother\n"
+    );
+}
+
+#[test]
+fn block_arms_can_return_from_the_function() {
+    let code = "fun f(v) {\n  match (v) {\n    0 -> { return \"zero\"; }\n    \
+                _ -> { print \"block\"; }\n  }\n  return \"after\";\n}\n\
+                print f(0);\nprint f(1);\nprint match (1) { _ -> { 1; } };";
+    assert_eq!(run_output(code).unwrap(), "zero\nblock\nafter\nnil\n");
+}
+
+#[test]
+fn running_out_of_arms_is_an_error() {
+    assert_eq!(
+        runtime_error("var v = 1;\nmatch (v) { 2 -> 3; };"),
+        "test.lox:2:1 No match arm matches the Number 1."
+    );
+}
+
+#[test]
+fn binding_a_name_twice_in_a_pattern_is_an_error() {
+    match run_output("print match ([1, 1]) { [a, a] -> a; };") {
+        Err(e @ LoxError::Resolve(_)) => assert!(
+            e.to_string()
+                .contains("Already a variable named 'a' in this scope"),
+            "{}",
+            e
+        ),
+        other => panic!("expected a resolver error, got {:?}", other),
+    }
+}
@@ -0,0 +1,47 @@
+//! Method lookups stay right while they are being cached.
+
+use rlox::testing::assert_output;
+
+#[test]
+fn extending_a_superclass_reaches_subclasses_that_already_called_it() {
+    assert_output(
+        "class A { greet() { return \"a\"; } }\nclass B < A {}\nclass C < B {}\n\
+         var c = C();\nprint c.greet();\n\
+         extend A { greet() { return \"extended\"; } }\nprint c.greet();\n",
+        &["a", "extended"],
+    );
+    assert_output(
+        "class A { greet() { return \"a\"; } }\nclass B < A {}\nvar b = B();\nprint b.greet();\n\
+         extend B { greet() { return \"b \" + super.greet(); } }\nprint b.greet();\n\
+         extend A { greet() { return \"new a\"; } }\nprint b.greet();\n",
+        &["a", "b a", "b new a"],
+    );
+}
+
+#[test]
+fn overrides_shadow_the_inherited_method() {
+    assert_output(
+        "class A { name() { return \"a\"; } describe() { return this.name(); } }\n\
+         class B < A { name() { return \"b\"; } }\n\
+         for (var i = 0; i < 3; i = i + 1) {\n  print A().describe() + B().describe();\n}\n",
+        &["ab", "ab", "ab"],
+    );
+}
+
+#[test]
+fn fields_still_win_over_methods() {
+    assert_output(
+        "class A { value() { return \"method\"; } }\nvar a = A();\nprint a.value();\n\
+         a.value = \"field\";\nprint a.value;\nprint A().value();\n",
+        &["method", "field", "method"],
+    );
+}
+
+#[test]
+fn classes_made_in_a_loop_each_keep_their_own_methods() {
+    assert_output(
+        "for (var i = 0; i < 3; i = i + 1) {\n  var n = i;\n  class Local { get() { return n; } }\n  \
+         print Local().get();\n}\n",
+        &["0", "1", "2"],
+    );
+}
@@ -0,0 +1,158 @@
+//! Loading scripts as modules with `Runner::load`, and loading them again after they change.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use rlox::{DiagnosticOptions, Interpreter, LoxError, OutputBuffer, Runner};
+
+/// A module file of its own for each test, removed when the test ends.
+struct ModuleFile(PathBuf);
+
+impl ModuleFile {
+    fn new(test: &str, code: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("rlox-modules-{}-{}.lox", test, std::process::id()));
+        fs::write(&path, code).unwrap();
+        Self(path)
+    }
+
+    /// Replaces the module's code. The runner is told about it explicitly rather than left
+    /// to notice a new modification time, which may not have ticked over yet.
+    fn rewrite(&self, runner: &mut Runner, code: &str) {
+        fs::write(&self.0, code).unwrap();
+        assert!(runner.invalidate(&self.0));
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for ModuleFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+fn runner() -> (Runner, OutputBuffer) {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .no_prelude()
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.set_diagnostic_options(DiagnosticOptions {
+        print: None,
+        ..DiagnosticOptions::default()
+    });
+    (runner, output)
+}
+
+#[test]
+fn an_unchanged_module_runs_once() {
+    let (mut runner, output) = runner();
+    let module = ModuleFile::new("once", "print \"loading\";\nvar version = 1;");
+
+    assert!(runner.load(module.path()).unwrap());
+    assert!(!runner.load(module.path()).unwrap());
+
+    assert_eq!(output.contents(), "loading\n");
+    assert_eq!(runner.modules(), [module.path().canonicalize().unwrap()]);
+}
+
+#[test]
+fn an_invalidated_module_runs_again() {
+    let (mut runner, output) = runner();
+    let module = ModuleFile::new("invalidated", "print \"loading\";");
+
+    assert!(!runner.invalidate(module.path()));
+    runner.load(module.path()).unwrap();
+    assert!(runner.invalidate(module.path()));
+    assert!(runner.load(module.path()).unwrap());
+
+    assert_eq!(output.contents(), "loading\nloading\n");
+}
+
+#[test]
+fn a_changed_module_replaces_its_globals_and_keeps_old_bindings() {
+    let (mut runner, output) = runner();
+    let module = ModuleFile::new(
+        "reload",
+        "var version = 1;\nvar retired = \"still here\";\n\
+         fun describe() { return \"version one\"; }",
+    );
+    runner.load(module.path()).unwrap();
+    runner.run("main.lox", "var saved = describe;").unwrap();
+
+    module.rewrite(
+        &mut runner,
+        "var version = 2;\nfun describe() { return \"version two\"; }",
+    );
+    assert!(runner.load(module.path()).unwrap());
+
+    runner
+        .run(
+            "main.lox",
+            "print version;\nprint describe();\nprint saved();\nprint retired;",
+        )
+        .unwrap();
+    // The old function held in `saved` keeps its code, and what the new version no longer
+    // defines is left as it was.
+    assert_eq!(
+        output.contents(),
+        "2\nversion two\nversion one\nstill here\n"
+    );
+}
+
+#[test]
+fn a_module_that_fails_to_compile_leaves_the_earlier_version_in_place() {
+    let (mut runner, _) = runner();
+    let module = ModuleFile::new("broken", "var version = 1;");
+    runner.load(module.path()).unwrap();
+
+    module.rewrite(&mut runner, "var version = ;");
+    let error = runner.load(module.path()).unwrap_err();
+    assert!(matches!(error, LoxError::Parse(_)), "{:?}", error);
+    assert_eq!(
+        runner.interpreter().get_global_number("version").unwrap(),
+        1.0
+    );
+
+    // The broken file is tried again on the next load, and runs once it is fixed.
+    assert!(runner.load(module.path()).is_err());
+    module.rewrite(&mut runner, "var version = 3;");
+    assert!(runner.load(module.path()).unwrap());
+    assert_eq!(
+        runner.interpreter().get_global_number("version").unwrap(),
+        3.0
+    );
+}
+
+#[test]
+fn a_module_that_stops_part_way_runs_in_full_next_time() {
+    let (mut runner, output) = runner();
+    let module = ModuleFile::new(
+        "runtime-error",
+        "print \"start\";\nvar ready = ready == nil;\nprint 1 + ready;",
+    );
+    runner.run("main.lox", "var ready;").unwrap();
+
+    let error = runner.load(module.path()).unwrap_err();
+    assert!(matches!(error, LoxError::Runtime(_)), "{:?}", error);
+    assert!(runner.load(module.path()).is_err());
+
+    assert_eq!(output.contents(), "start\nstart\n");
+}
+
+#[test]
+fn a_missing_module_is_an_io_error() {
+    let (mut runner, _) = runner();
+    let path = std::env::temp_dir().join("rlox-modules-missing/none.lox");
+
+    let error = runner.load(&path).unwrap_err();
+    assert!(matches!(error, LoxError::Io(_)), "{:?}", error);
+    assert!(!runner.invalidate(&path));
+    assert!(runner.modules().is_empty());
+}
@@ -0,0 +1,49 @@
+//! The `??` operator, and the values `and` and `or` produce, which it is easily confused with.
+
+use rlox::{testing::run_output, LoxError};
+
+#[test]
+fn only_nil_is_replaced() {
+    assert_eq!(
+        run_output("print false ?? 1;\nprint 0 ?? 1;\nprint \"\" ?? 1;\nprint nil ?? 2;\nvar unset;\nprint unset ?? \"default\";\n")
+            .unwrap(),
+        "false\n0\n\n2\ndefault\n"
+    );
+}
+
+#[test]
+fn or_replaces_anything_falsey() {
+    assert_eq!(
+        run_output("print false or 1;\nprint nil or 1;\n").unwrap(),
+        "1\n1\n"
+    );
+    assert_eq!(run_output("print false ?? 1;\n").unwrap(), "false\n");
+}
+
+#[test]
+fn and_and_or_return_an_operand() {
+    assert_eq!(
+        run_output("print 1 and 2;\nprint nil and 2;\nprint false or nil;\nprint \"\" or 1;\nprint 0 or 1;\n")
+            .unwrap(),
+        "2\nnil\nnil\n\n0\n"
+    );
+}
+
+#[test]
+fn the_right_side_runs_only_for_nil() {
+    let code = "fun side(v) { print \"evaluated\"; return v; }\nprint 5 ?? side(6);\nprint false ?? side(7);\nprint nil ?? side(8);\n";
+    assert_eq!(run_output(code).unwrap(), "5\nfalse\nevaluated\n8\n");
+}
+
+#[test]
+fn chains_group_to_the_left() {
+    assert_eq!(
+        run_output("print nil ?? nil ?? 3;\nprint nil ?? false ?? 3;\n").unwrap(),
+        "3\nfalse\n"
+    );
+}
+
+#[test]
+fn a_lone_question_mark_is_not_an_operator() {
+    assert!(matches!(run_output("print 1 ? 2;"), Err(LoxError::Lex(_))));
+}
@@ -0,0 +1,43 @@
+//! Errors for using nil as an object or a function name the expression that was nil.
+
+use rlox::testing::runtime_error;
+
+#[test]
+fn reading_a_property_of_a_nil_variable() {
+    assert_eq!(
+        runtime_error("var user = nil;\nprint user.name;"),
+        "test.lox:2:7 Attempted to read property 'name' of nil (from expression `user.name`)."
+    );
+}
+
+#[test]
+fn reading_a_property_of_nil_returned_mid_chain() {
+    let code = "class User { init() { this.profile = nil; } }\n\
+                var users = {\"ada\": User()};\n\
+                print users.get(\"ada\").profile.name;";
+    assert_eq!(
+        runtime_error(code),
+        "test.lox:3:7 Attempted to read property 'name' of nil \
+         (from expression `users.get(\"ada\").profile.name`)."
+    );
+}
+
+#[test]
+fn calling_a_method_on_nil() {
+    assert_eq!(
+        runtime_error("var user = nil;\nuser.greet();"),
+        "test.lox:2:1 Attempted to read property 'greet' of nil (from expression `user.greet`)."
+    );
+}
+
+#[test]
+fn calling_nil() {
+    assert_eq!(
+        runtime_error("var callback = nil;\ncallback(1);"),
+        "test.lox:2:1 Attempted to call nil (from expression `callback`)."
+    );
+    assert_eq!(
+        runtime_error("fun find() { return nil; }\nfind()();"),
+        "test.lox:2:1 Attempted to call nil (from expression `find()`)."
+    );
+}
@@ -0,0 +1,160 @@
+//! Generated corpora of token soup and random syntax trees, run to make sure that whatever
+//! goes wrong comes back as a `LoxError` rather than a panic.
+
+use rlox::{BinOp, Expr, Interpreter, Limits, Literal, LogOp, OutputBuffer, Runner, Stmt, UnOp};
+
+const WORDS: &[&str] = &[
+    "var", "fun", "class", "if", "else", "while", "for", "return", "print", "const", "enum",
+    "match", "defer", "this", "super", "nil", "true", "false", "and", "or", "(", ")", "{", "}",
+    "[", "]", ",", ".", ";", ":", "+", "-", "*", "/", "=", "==", "!=", "!", "<", ">", "<=", ">=",
+    "??", "?.", "=>", "_", "x", "y", "f", "len", "push", "0", "1", "2.5", "-3", "\"s\"", "\"",
+    "\"\\q\"", "#", "@", "é", "\u{0}", "\n", "//", "/*",
+];
+
+/// A fresh interpreter with limits tight enough that runaway generated code stops quickly.
+fn runner() -> Runner {
+    let interpreter = Interpreter::builder()
+        .output(Box::new(OutputBuffer::new()))
+        .limits(Limits {
+            max_string_length: Some(10_000),
+            max_collection_len: Some(1_000),
+            max_environments: Some(50),
+            max_statements: Some(10_000),
+        })
+        .build();
+    Runner::with_interpreter(interpreter)
+}
+
+/// Runs `test` on a thread with the stack the `lox` binary's main thread has, so that deep
+/// recursion hits the environment limit before the stack runs out.
+fn on_big_stack(test: impl FnOnce() + Send + 'static) {
+    std::thread::Builder::new()
+        .stack_size(8 << 20)
+        .spawn(test)
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+#[test]
+fn token_soup_never_panics() {
+    on_big_stack(|| {
+        let mut rng = fastrand::Rng::with_seed(144);
+        for _ in 0..3_000 {
+            let length = rng.usize(1..40);
+            let code: Vec<&str> = (0..length)
+                .map(|_| WORDS[rng.usize(..WORDS.len())])
+                .collect();
+            let code = code.join(if rng.bool() { " " } else { "" });
+
+            // Whether it runs or fails doesn't matter, only that it returns. The seed is fixed,
+            // so a program that panics or hangs comes back every run until it is fixed.
+            let _ = runner().run("soup.lox", &code);
+        }
+    });
+}
+
+fn random_expr(rng: &mut fastrand::Rng, depth: usize) -> Expr {
+    const BINARY: [BinOp; 11] = [
+        BinOp::Plus,
+        BinOp::Minus,
+        BinOp::Multiply,
+        BinOp::Divide,
+        BinOp::Eq,
+        BinOp::Gt,
+        BinOp::Lt,
+        BinOp::Ge,
+        BinOp::Le,
+        BinOp::EqEq,
+        BinOp::Ne,
+    ];
+    const LOGICAL: [LogOp; 3] = [LogOp::And, LogOp::Or, LogOp::Coalesce];
+
+    let leaf = depth == 0 || rng.u8(..4) == 0;
+    if leaf {
+        return match rng.u8(..6) {
+            0 => Expr::literal(Literal::Nil),
+            1 => Expr::literal(rng.bool()),
+            2 => Expr::literal(rng.i32(-3..3) as f64),
+            3 => Expr::literal("s"),
+            4 => Expr::variable(["x", "f", "missing"][rng.usize(..3)]),
+            _ => Expr::literal(0.5),
+        };
+    }
+
+    match rng.u8(..6) {
+        0 => Expr::binary(
+            random_expr(rng, depth - 1),
+            BINARY[rng.usize(..BINARY.len())],
+            random_expr(rng, depth - 1),
+        ),
+        1 => Expr::logical(
+            random_expr(rng, depth - 1),
+            LOGICAL[rng.usize(..LOGICAL.len())],
+            random_expr(rng, depth - 1),
+        ),
+        2 => Expr::unary(
+            if rng.bool() {
+                UnOp::BinNeg
+            } else {
+                UnOp::LogNeg
+            },
+            random_expr(rng, depth - 1),
+        ),
+        3 => Expr::call(
+            random_expr(rng, depth - 1),
+            (0..rng.usize(..3))
+                .map(|_| random_expr(rng, depth - 1))
+                .collect(),
+        ),
+        4 => Expr::get(random_expr(rng, depth - 1), "len"),
+        _ => Expr::assign("x", random_expr(rng, depth - 1)),
+    }
+}
+
+fn random_stmt(rng: &mut fastrand::Rng, depth: usize) -> Stmt {
+    match rng.u8(..if depth == 0 { 3 } else { 7 }) {
+        0 => Stmt::print(random_expr(rng, 3)),
+        1 => Stmt::expression(random_expr(rng, 3)),
+        2 => Stmt::var("x", Some(random_expr(rng, 2))),
+        3 => Stmt::block(
+            (0..rng.usize(..3))
+                .map(|_| random_stmt(rng, depth - 1))
+                .collect(),
+        ),
+        4 => Stmt::if_else(
+            random_expr(rng, 2),
+            random_stmt(rng, depth - 1),
+            rng.bool().then(|| random_stmt(rng, depth - 1)),
+        ),
+        5 => Stmt::while_loop(random_expr(rng, 2), random_stmt(rng, depth - 1)),
+        _ => Stmt::function(
+            "f",
+            &["x"][..rng.usize(..2)],
+            vec![
+                random_stmt(rng, depth - 1),
+                Stmt::return_value(Some(random_expr(rng, 2))),
+            ],
+        ),
+    }
+}
+
+#[test]
+fn random_syntax_trees_never_panic() {
+    on_big_stack(|| {
+        let mut rng = fastrand::Rng::with_seed(144);
+        for _ in 0..2_000 {
+            let mut program = vec![
+                Stmt::var("x", Some(Expr::literal(1))),
+                Stmt::function(
+                    "f",
+                    &["x"],
+                    vec![Stmt::return_value(Some(Expr::variable("x")))],
+                ),
+            ];
+            program.extend((0..rng.usize(1..6)).map(|_| random_stmt(&mut rng, 3)));
+
+            let _ = runner().run_program(program);
+        }
+    });
+}
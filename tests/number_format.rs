@@ -0,0 +1,145 @@
+//! How numbers are written out by `print`, `toString` and everything else that shows them.
+
+use rlox::{format_number, testing::run_output};
+
+/// Values and how they are written, from the simple to the edges of what a double holds.
+const TABLE: [(f64, &str); 50] = [
+    (0.0, "0"),
+    (-0.0, "-0"),
+    (1.0, "1"),
+    (-1.0, "-1"),
+    (42.0, "42"),
+    (100.0, "100"),
+    (123456789.0, "123456789"),
+    (0.5, "0.5"),
+    (-0.5, "-0.5"),
+    (0.1, "0.1"),
+    (0.2, "0.2"),
+    (0.1 + 0.2, "0.30000000000000004"),
+    (0.3, "0.3"),
+    (1.0 / 3.0, "0.3333333333333333"),
+    (2.0 / 3.0, "0.6666666666666666"),
+    (6.02214076, "6.02214076"),
+    (std::f64::consts::PI, "3.141592653589793"),
+    (std::f64::consts::E, "2.718281828459045"),
+    (1.5, "1.5"),
+    (2.25, "2.25"),
+    (-273.15, "-273.15"),
+    (1e-3, "0.001"),
+    (1e-6, "0.000001"),
+    (1e-7, "0.0000001"),
+    (1.5e-10, "0.00000000015"),
+    (1e15, "1000000000000000"),
+    (1e16, "10000000000000000"),
+    (9007199254740991.0, "9007199254740991"),
+    (9007199254740993.0, "9007199254740992"),
+    (123456.789, "123456.789"),
+    (1e20, "100000000000000000000"),
+    (-1e20, "-100000000000000000000"),
+    (999999999999999900000.0, "999999999999999900000"),
+    (1e21, "1e21"),
+    (-1e21, "-1e21"),
+    (1.5e21, "1.5e21"),
+    (1e22, "1e22"),
+    (1.2345e25, "1.2345e25"),
+    (1e100, "1e100"),
+    (1e300, "1e300"),
+    (-1e300, "-1e300"),
+    (f64::MAX, "1.7976931348623157e308"),
+    (1e-20, "0.00000000000000000001"),
+    (f64::EPSILON, "0.0000000000000002220446049250313"),
+    (100.0 / 7.0, "14.285714285714286"),
+    (0.1 * 3.0, "0.30000000000000004"),
+    (1.1 * 1.1, "1.2100000000000002"),
+    (f64::INFINITY, "inf"),
+    (f64::NEG_INFINITY, "-inf"),
+    (f64::NAN, "NaN"),
+];
+
+#[test]
+fn numbers_are_written_as_the_table_says() {
+    for (value, expected) in TABLE {
+        assert_eq!(format_number(value), expected, "{:?}", value);
+    }
+}
+
+#[test]
+fn written_numbers_parse_back_to_the_same_number() {
+    for (value, _) in TABLE {
+        let parsed: f64 = format_number(value).parse().unwrap();
+        if value.is_nan() {
+            assert!(parsed.is_nan());
+        } else {
+            assert_eq!(parsed.to_bits(), value.to_bits(), "{:?}", value);
+        }
+    }
+}
+
+#[test]
+fn print_to_string_and_json_agree() {
+    let code = "
+        var big = 1000000 * 1000000 * 1000000 * 1000;
+        var values = [0.1 + 0.2, big, 0 * -1, 1 / 4];
+        print 0.1 + 0.2;
+        print (0.1 + 0.2).toString();
+        print big;
+        print big.toString();
+        print 0 * -1;
+        print (0 * -1).toString();
+        print values;
+        print jsonEncode(values);
+    ";
+    assert_eq!(
+        run_output(code).unwrap(),
+        "0.30000000000000004\n0.30000000000000004\n1e21\n1e21\n-0\n-0\n\
+         [0.30000000000000004, 1e21, -0, 0.25]\n[0.30000000000000004,1e21,-0,0.25]\n"
+    );
+}
+
+#[test]
+fn printed_numbers_are_lox_literals() {
+    for (value, written) in TABLE.into_iter().filter(|(value, _)| value.is_finite()) {
+        assert_eq!(
+            run_output(&format!("print {};", written)).unwrap(),
+            format!("{}\n", written),
+            "{:?}",
+            value
+        );
+    }
+}
+
+#[test]
+fn exponents_need_digits() {
+    assert_eq!(
+        run_output("print 2e3 + 2E3 + 2e+3 + 2e-3;").unwrap(),
+        "6000.002\n"
+    );
+    // Without digits after it, the `e` is a name of its own.
+    assert_eq!(run_output("var e = 1; print 3 - e;").unwrap(), "2\n");
+    assert!(run_output("print 3e;").is_err());
+    assert!(run_output("print 3e+;").is_err());
+}
+
+#[test]
+fn source_and_sexpr_printers_write_numbers_the_same_way() {
+    let code = "print 1000000000000000000000;\nvar small = 0.0000001;\nvar huge = 1.5e300;\n";
+    let program = rlox::parse("numbers.lox", code).unwrap();
+
+    let source = rlox::to_source(&program);
+    assert_eq!(
+        source,
+        "print 1e21;\nvar small = 0.0000001;\nvar huge = 1.5e300;\n"
+    );
+    assert_eq!(rlox::parse("printed.lox", &source).unwrap(), program);
+
+    let sexpr = rlox::to_sexpr(&program);
+    for written in ["1e21", "0.0000001", "1.5e300"] {
+        assert!(
+            sexpr.contains(written),
+            "{} missing from\n{}",
+            written,
+            sexpr
+        );
+    }
+    assert_eq!(rlox::from_sexpr(&sexpr).unwrap(), program);
+}
@@ -0,0 +1,121 @@
+//! `toFixed`, `toPrecision` and `toString` with a base.
+
+use rlox::testing::{run_output, runtime_error};
+
+/// What printing each of `expressions` in turn prints.
+fn print_each(expressions: &[&str]) -> String {
+    let code: String = expressions
+        .iter()
+        .map(|expression| format!("print {};\n", expression))
+        .collect();
+    run_output(&code).unwrap()
+}
+
+#[test]
+fn to_fixed_rounds_half_to_even_on_the_stored_value() {
+    assert_eq!(
+        print_each(&[
+            "(2.5).toFixed(0)",
+            "(3.5).toFixed(0)",
+            "(1.005).toFixed(2)",
+            "(123.456).toFixed(0)",
+            "(1).toFixed(3)",
+        ]),
+        "2\n4\n1.00\n123\n1.000\n"
+    );
+}
+
+#[test]
+fn to_fixed_drops_the_sign_of_zero_only() {
+    assert_eq!(
+        print_each(&[
+            "(-0).toFixed(2)",
+            "(-0.001).toFixed(2)",
+            "(-1.5).toFixed(1)"
+        ]),
+        "0.00\n-0.00\n-1.5\n"
+    );
+}
+
+#[test]
+fn to_fixed_writes_large_numbers_out_in_full() {
+    assert_eq!(
+        print_each(&[
+            "(1000000000000000000000).toFixed(2)",
+            "(1).toFixed(100).len()"
+        ]),
+        "1000000000000000000000.00\n102\n"
+    );
+}
+
+#[test]
+fn to_precision_switches_to_exponent_notation() {
+    assert_eq!(
+        print_each(&[
+            "(123.456).toPrecision(5)",
+            "(123.456).toPrecision(3)",
+            "(123.456).toPrecision(1)",
+            "(0.000123).toPrecision(2)",
+            "(0.0000001).toPrecision(2)",
+            "(1000000000000000000000).toPrecision(3)",
+            "(9.99).toPrecision(2)",
+            "(-0).toPrecision(3)",
+        ]),
+        "123.46\n123\n1e+2\n0.00012\n1.0e-7\n1.00e+21\n10\n0.00\n"
+    );
+}
+
+#[test]
+fn to_string_with_a_base() {
+    assert_eq!(
+        print_each(&[
+            "(255).toString(16)",
+            "(-255).toString(2)",
+            "(35).toString(36)",
+            "(0).toString(2)",
+            "(-0).toString(8)",
+        ]),
+        "ff\n-11111111\nz\n0\n0\n"
+    );
+}
+
+#[test]
+fn non_finite_numbers_are_errors() {
+    assert_eq!(
+        runtime_error("print (1 / 0).toFixed(2);"),
+        "test.lox:1:7 Cannot use 'toFixed' on inf; it needs a finite number."
+    );
+    assert_eq!(
+        runtime_error("print (-1 / 0).toPrecision(2);"),
+        "test.lox:1:7 Cannot use 'toPrecision' on -inf; it needs a finite number."
+    );
+    assert_eq!(
+        runtime_error("print (0 / 0).toString(2);"),
+        "test.lox:1:7 Cannot use 'toString' on NaN; it needs a finite number."
+    );
+}
+
+#[test]
+fn digit_counts_are_bounded() {
+    assert_eq!(
+        runtime_error("print (1).toFixed(101);"),
+        "test.lox:1:7 Argument 1 to 'toFixed' must be at most 100, got 101."
+    );
+    assert_eq!(
+        runtime_error("print (1).toPrecision(101);"),
+        "test.lox:1:7 Argument 1 to 'toPrecision' must be at most 100, got 101."
+    );
+}
+
+#[test]
+fn only_whole_numbers_have_other_bases() {
+    assert_eq!(
+        runtime_error("print (1.5).toString(2);"),
+        "test.lox:1:7 Only integers can be formatted in another base, got 1.5."
+    );
+    assert_eq!(
+        runtime_error("print (100000000000000000000).toString(2);"),
+        "test.lox:1:7 100000000000000000000 is too large to format exactly in another base; \
+         the limit is 9007199254740991."
+    );
+}
@@ -0,0 +1,52 @@
+//! Optional chaining with `?.`, which gives nil instead of failing when the receiver is nil.
+
+use rlox::testing::{run_output, runtime_error};
+
+const BOX: &str = "class Box {\n  init(v) { this.v = v; this.inner = nil; }\n  get() { return this.v; }\n}\nvar box = Box(3);\n";
+
+#[test]
+fn a_nil_receiver_gives_nil() {
+    assert_eq!(
+        run_output("var a;\nprint a?.b;\nprint a?.b();\nprint nil?.len();\n").unwrap(),
+        "nil\nnil\nnil\n"
+    );
+}
+
+#[test]
+fn the_rest_of_the_chain_is_skipped() {
+    let code = "fun side() { print \"evaluated\"; return 1; }\nvar a;\nprint a?.b.c(side());\nprint a?.b.c.d;\n";
+    assert_eq!(run_output(code).unwrap(), "nil\nnil\n");
+}
+
+#[test]
+fn a_present_receiver_is_used_as_usual() {
+    let code = format!(
+        "{}print box?.v;\nprint box?.get();\nprint \"abc\"?.len();\n",
+        BOX
+    );
+    assert_eq!(run_output(&code).unwrap(), "3\n3\n3\n");
+}
+
+#[test]
+fn chains_mix_optional_and_plain_segments() {
+    let code = format!(
+        "{}print box.inner?.v;\nprint box.inner?.v.w.x;\nprint box?.get().toString();\n",
+        BOX
+    );
+    assert_eq!(run_output(&code).unwrap(), "nil\nnil\n3\n");
+
+    // Only the segment after `?.` is optional.
+    let code = format!("{}print box?.inner.v;\n", BOX);
+    assert_eq!(
+        runtime_error(&code),
+        "test.lox:6:7 Attempted to read property 'v' of nil (from expression `box?.inner.v`)."
+    );
+}
+
+#[test]
+fn non_instances_still_fail() {
+    assert_eq!(
+        runtime_error("var n = 1;\nprint n?.b;\n"),
+        "test.lox:2:7 Number has no method 'b'"
+    );
+}
@@ -0,0 +1,131 @@
+//! Script output is flushed before an error is reported, and the REPL starts each echo on
+//! a line of its own.
+
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    rc::Rc,
+};
+
+use rlox::{DiagnosticOptions, Interpreter, LoxError, OutputBuffer, Runner};
+
+/// Output that only becomes visible when it is flushed, like stdout piped to a file.
+#[derive(Clone, Default)]
+struct Buffered {
+    pending: Rc<RefCell<Vec<u8>>>,
+    flushed: Rc<RefCell<Vec<u8>>>,
+}
+
+impl Buffered {
+    fn visible(&self) -> String {
+        String::from_utf8(self.flushed.borrow().clone()).unwrap()
+    }
+}
+
+impl Write for Buffered {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let pending = std::mem::take(&mut *self.pending.borrow_mut());
+        self.flushed.borrow_mut().extend(pending);
+        Ok(())
+    }
+}
+
+fn runner(output: Box<dyn Write>) -> Runner {
+    let interpreter = Interpreter::builder().output(output).no_prelude().build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.set_diagnostic_options(DiagnosticOptions {
+        print: None,
+        ..DiagnosticOptions::default()
+    });
+    runner
+}
+
+#[test]
+fn output_is_flushed_before_a_runtime_error_is_returned() {
+    let output = Buffered::default();
+    let mut runner = runner(Box::new(output.clone()));
+
+    let error = runner
+        .run(
+            "test.lox",
+            "print \"before\";\nwrite(\"partial\");\nprint 1 + nil;",
+        )
+        .unwrap_err();
+
+    // Whoever reports the error on stderr does so after everything the script printed.
+    assert!(matches!(error, LoxError::Runtime(_)), "{:?}", error);
+    assert_eq!(output.visible(), "before\npartial");
+}
+
+#[test]
+fn output_is_flushed_when_a_script_finishes() {
+    let output = Buffered::default();
+    let mut runner = runner(Box::new(output.clone()));
+
+    runner.run("test.lox", "write(1);\nwrite(2);").unwrap();
+
+    assert_eq!(output.visible(), "12");
+}
+
+#[test]
+fn write_leaves_the_line_open() {
+    let output = OutputBuffer::new();
+    let mut runner = runner(Box::new(output.clone()));
+
+    runner
+        .run(
+            "test.lox",
+            "write(\"a\");\nwrite(1);\nwrite(nil);\nprint \"!\";",
+        )
+        .unwrap();
+
+    assert_eq!(output.contents(), "a1nil!\n");
+}
+
+#[test]
+fn an_unfinished_line_is_ended_before_the_prompt() {
+    let output = OutputBuffer::new();
+    let mut runner = runner(Box::new(output.clone()));
+
+    runner.run_repl_line("write(\"no newline\");").unwrap();
+    runner.finish_output_line();
+    runner.run_repl_line("print \"done\";").unwrap();
+    runner.finish_output_line();
+
+    // The echoed nil starts a line of its own, and a finished line gets no blank line after it.
+    assert_eq!(output.contents(), "no newline\nnil\ndone\n");
+}
+
+#[test]
+fn an_unfinished_line_is_ended_before_an_error_is_reported() {
+    let output = OutputBuffer::new();
+    let mut runner = runner(Box::new(output.clone()));
+
+    let result = runner.run_repl_line("write(\"half\"); print 1 + nil;");
+    runner.finish_output_line();
+
+    assert!(result.is_err());
+    assert_eq!(output.contents(), "half\n");
+}
+
+#[test]
+fn repl_echoes_start_on_their_own_line() {
+    let output = OutputBuffer::new();
+    let mut runner = runner(Box::new(output.clone()));
+
+    runner
+        .run_repl_line("fun f() { write(\"side effect\"); return 2; }")
+        .unwrap();
+    runner.run_repl_line("f()").unwrap();
+    runner.run_repl_line("var x = f();").unwrap();
+
+    assert_eq!(
+        output.contents(),
+        "f = <fn f>\nside effect\n2\nside effect\nx = 2\n"
+    );
+}
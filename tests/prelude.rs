@@ -0,0 +1,73 @@
+//! The globals written in Lox that are defined before any other code runs.
+
+use rlox::{
+    testing::{assert_output, assert_runtime_error, runtime_error},
+    Interpreter, LoxError, Runner,
+};
+
+#[test]
+fn prelude_functions_are_callable() {
+    assert_output(
+        "fun double(n) { return n * 2; }\nfun even(n) { return n - (n / 2).floor() * 2 == 0; }\n\
+         fun add(a, b) { return a + b; }\n\
+         print map([1, 2, 3], double);\nprint filter([1, 2, 3, 4], even);\n\
+         print reduce([1, 2, 3], add, 10);\nprint reduce([], add, \"empty\");\n\
+         print clamp(15, 0, 10);\nprint clamp(-1, 0, 10);\nprint clamp(5, 0, 10);\nprint abs(-2.5);",
+        &["[2, 4, 6]", "[2, 4]", "16", "empty", "10", "0", "5", "2.5"],
+    );
+}
+
+#[test]
+fn queues_are_first_in_first_out() {
+    assert_output(
+        "var q = Queue();\nprint q.isEmpty();\nq.enqueue(\"a\");\nq.enqueue(\"b\");\n\
+         print q.peek();\nprint q.len();\nprint q.dequeue();\nprint q.dequeue();\n\
+         print q.dequeue();\nprint q.isEmpty();",
+        &["true", "a", "2", "a", "b", "nil", "true"],
+    );
+}
+
+#[test]
+fn user_definitions_shadow_the_prelude() {
+    assert_output(
+        "fun map(array, f) { return \"mine\"; }\nprint map([1], abs);",
+        &["mine"],
+    );
+    assert_output("var clamp = 3;\nprint clamp;", &["3"]);
+    assert_output(
+        "class Queue { init() { this.kind = \"custom\"; } }\nprint Queue().kind;",
+        &["custom"],
+    );
+    assert_output(
+        "fun f() { var abs = \"local\"; return abs; }\nprint f();\nprint abs(-1);",
+        &["local", "1"],
+    );
+}
+
+#[test]
+fn errors_inside_the_prelude_point_at_it() {
+    let error = runtime_error("abs(\"five\");");
+    assert!(error.starts_with("<prelude>:"), "{}", error);
+    assert!(error.ends_with(" String has no method 'abs'"), "{}", error);
+    assert_runtime_error("reduce([1], clamp, 0);", "Expected 3 arguments but got 2.");
+}
+
+#[test]
+fn the_prelude_can_be_left_out() {
+    let mut runner = Runner::with_interpreter(Interpreter::builder().no_prelude().build());
+    for name in ["map", "filter", "reduce", "clamp", "abs", "Queue"] {
+        assert!(runner.interpreter().get_global(name).is_none(), "{}", name);
+    }
+    match runner.run("test.lox", "print reduce;") {
+        Err(LoxError::Runtime(diagnostic)) => {
+            assert_eq!(diagnostic.message, "Undefined variable 'reduce'.")
+        }
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
+
+#[test]
+fn the_prelude_does_not_count_towards_stats() {
+    let mut runner = Runner::new();
+    assert_eq!(runner.take_stats().statements, 0);
+}
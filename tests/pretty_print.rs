@@ -0,0 +1,122 @@
+//! `prettyPrint()`, `:pp` at the REPL, and printing values that contain themselves.
+
+use rlox::{testing::run_output, Interpreter, OutputBuffer, Runner};
+
+#[test]
+fn deep_nesting_is_indented() {
+    assert_eq!(
+        run_output("prettyPrint([1, [2, [3, [4]]]]);").unwrap(),
+        "\
+[
+  1,
+  [
+    2,
+    [
+      3,
+      [
+        4
+      ]
+    ]
+  ]
+]
+"
+    );
+}
+
+#[test]
+fn mixed_types() {
+    let code = "class Point {}\nfun pair() { return 1, \"b\"; }\n\
+                prettyPrint({\"name\": \"lox\", \"tags\": [\"a\", nil, true, 1.5], \
+                \"empty\": [], \"none\": {}, \"pair\": pair(), \"class\": Point, \
+                \"point\": Point(), \"native\": clock});\nprettyPrint(\"top level\");";
+    assert_eq!(
+        run_output(code).unwrap(),
+        "\
+{
+  \"name\": \"lox\",
+  \"tags\": [
+    \"a\",
+    nil,
+    true,
+    1.5
+  ],
+  \"empty\": [],
+  \"none\": {},
+  \"pair\": (
+    1,
+    \"b\"
+  ),
+  \"class\": Point,
+  \"point\": Point instance,
+  \"native\": <native fn clock>
+}
+top level
+"
+    );
+}
+
+#[test]
+fn cycles_are_cut_short() {
+    let code = "var a = [1];\na.push(a);\nprettyPrint(a);\nprint a;\n\
+                var m = {\"name\": \"m\"};\nvar b = [m];\nm.set(\"list\", b);\n\
+                prettyPrint(m);\nprint b;";
+    assert_eq!(
+        run_output(code).unwrap(),
+        "\
+[
+  1,
+  <cycle>
+]
+[1, <cycle>]
+{
+  \"name\": \"m\",
+  \"list\": [
+    <cycle>
+  ]
+}
+[{name: m, list: <cycle>}]
+"
+    );
+}
+
+#[test]
+fn shared_values_are_not_cycles() {
+    assert_eq!(
+        run_output("var shared = [1];\nprint [shared, shared];").unwrap(),
+        "[[1], [1]]\n"
+    );
+}
+
+#[test]
+fn nesting_past_the_max_depth_is_elided() {
+    assert_eq!(
+        run_output("prettyPrint([[[1]], {\"k\": {\"j\": 1}}], 2);\nprettyPrint([1], 0);").unwrap(),
+        "\
+[
+  [
+    [...]
+  ],
+  {
+    \"k\": {...}
+  }
+]
+[...]
+"
+    );
+}
+
+#[test]
+fn the_repl_pretty_prints_after_pp() {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.run_repl_pretty("{\"a\": [1]}").unwrap();
+    runner.run_repl_line("print _;").unwrap();
+
+    assert_eq!(
+        output.contents(),
+        "{\n  \"a\": [\n    1\n  ]\n}\n{a: [1]}\n"
+    );
+}
@@ -0,0 +1,48 @@
+//! Methods called on strings and numbers, which dispatch through the primitive method table.
+
+use rlox::testing::{run_output, runtime_error};
+
+#[test]
+fn string_methods() {
+    let code = "print \"hello\".len();\nprint \"a,b,c\".split(\",\");\nprint \" x \".trim();\nprint \"abc\".contains(\"b\");\nprint \"abc\".replace(\"b\", \"x\");\n";
+    assert_eq!(run_output(code).unwrap(), "5\n[a, b, c]\nx\ntrue\naxc\n");
+}
+
+#[test]
+fn methods_chain() {
+    let code = "var s = \"  one two three \";\nprint s.trim().split(\" \").len();\n";
+    assert_eq!(run_output(code).unwrap(), "3\n");
+}
+
+#[test]
+fn number_methods() {
+    assert_eq!(
+        run_output("print (3.7).floor();\nprint (-2).abs();\nprint (12).toString();\nprint (12).toString().len();\n")
+            .unwrap(),
+        "3\n2\n12\n2\n"
+    );
+}
+
+#[test]
+fn unknown_methods_name_the_type() {
+    assert_eq!(
+        runtime_error("print \"abc\".foo();"),
+        "test.lox:1:7 String has no method 'foo'"
+    );
+    assert_eq!(
+        runtime_error("print (1).foo();"),
+        "test.lox:1:7 Number has no method 'foo'"
+    );
+}
+
+#[test]
+fn arguments_are_checked() {
+    assert_eq!(
+        runtime_error("print \"a\".split();"),
+        "test.lox:1:7 Expected 1 arguments but got 0."
+    );
+    assert_eq!(
+        runtime_error("print \"a\".split(1);"),
+        "test.lox:1:7 Argument 1 to 'split' must be a String, got Number."
+    );
+}
@@ -0,0 +1,80 @@
+//! The dialect where `print` is a function, run against the standard one.
+
+use rlox::{DiagnosticOptions, Interpreter, LoxError, OutputBuffer, Runner};
+
+/// Runs `code` with `print` as a statement and as a function, returning both outcomes.
+fn both(code: &str) -> [Result<String, LoxError>; 2] {
+    [false, true].map(|print_is_function| {
+        let output = OutputBuffer::new();
+        let interpreter = Interpreter::builder()
+            .output(Box::new(output.clone()))
+            .no_prelude()
+            .build();
+        let mut runner = Runner::with_interpreter(interpreter);
+        runner.set_diagnostic_options(DiagnosticOptions {
+            print: None,
+            ..DiagnosticOptions::default()
+        });
+        runner.set_print_is_function(print_is_function);
+        runner.run("test.lox", code).map(|()| output.contents())
+    })
+}
+
+#[test]
+fn a_parenthesised_value_prints_the_same_either_way() {
+    let [statement, function] = both("print(\"a\");\nvar x = 2;\nprint(x * 3);");
+
+    assert_eq!(statement.unwrap(), "a\n6\n");
+    assert_eq!(function.unwrap(), "a\n6\n");
+}
+
+#[test]
+fn the_statement_form_only_parses_in_the_standard_dialect() {
+    let [statement, function] = both("print \"a\";");
+
+    assert_eq!(statement.unwrap(), "a\n");
+    assert!(
+        matches!(function, Err(LoxError::Parse(_))),
+        "{:?}",
+        function
+    );
+}
+
+#[test]
+fn the_function_takes_any_number_of_arguments() {
+    let [statement, function] = both("print(1, \"x\", nil, true);\nprint();");
+
+    assert!(statement.is_err());
+    assert_eq!(function.unwrap(), "1 x nil true\n\n");
+}
+
+#[test]
+fn the_function_is_a_value() {
+    let [statement, function] = both("var p = print;\np(\"via p\");\nprint(p);");
+
+    assert!(statement.is_err());
+    assert_eq!(function.unwrap(), "via p\n<native fn print>\n");
+}
+
+#[test]
+fn the_function_returns_nil() {
+    let [_, function] = both("var result = print(\"side effect\");\nprint(result);");
+
+    assert_eq!(function.unwrap(), "side effect\nnil\n");
+}
+
+#[test]
+fn the_repl_uses_the_dialect_too() {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .no_prelude()
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.set_print_is_function(true);
+
+    runner.run_repl_line("print(1, 2);").unwrap();
+
+    // The call is an expression, so the REPL echoes its value like any other.
+    assert_eq!(output.contents(), "1 2\nnil\n");
+}
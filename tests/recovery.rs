@@ -0,0 +1,49 @@
+//! Error recovery: each `tests/recovery/*.lox` program is broken, and lists the exact syntax
+//! errors it should produce in `// expect: line:col message` comments. Extra errors fail
+//! the test as much as missing ones.
+
+use std::{fs, path::PathBuf};
+
+use rlox::{LanguageOptions, LoxError};
+
+const EXPECT: &str = "// expect: ";
+
+#[test]
+fn broken_programs_report_exactly_the_expected_errors() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/recovery");
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lox"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty());
+
+    let mut failures = vec![];
+    for path in paths {
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let code = fs::read_to_string(&path).unwrap();
+        let expected: Vec<String> = code
+            .lines()
+            .filter_map(|line| line.strip_prefix(EXPECT))
+            .map(|error| format!("{}:{}", name, error))
+            .collect();
+
+        let actual: Vec<String> = match rlox::compile(&name, &code, LanguageOptions::default()) {
+            Err(LoxError::Parse(errors)) => errors
+                .iter()
+                .map(|error| format!("{} {}", error.location, error.message))
+                .collect(),
+            other => vec![format!("no syntax errors: {:?}", other.err())],
+        };
+
+        if actual != expected {
+            failures.push(format!(
+                "{}\n  expected: {:#?}\n  actual: {:#?}",
+                name, expected, actual
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,117 @@
+//! The reflection natives: `fields()`, `methods()`, `getField()`, `setField()` and
+//! `className()`, and a serializer written in Lox on top of them.
+
+use rlox::testing::{assert_output, runtime_error};
+
+const SHAPES: &str = "\
+class Shape {
+  init(name) { this.name = name; }
+  describe() { return this.name + \" with area \" + this.area().toString(); }
+  area() { return 0; }
+}
+
+class Rect < Shape {
+  init(width, height) {
+    super.init(\"rect\");
+    this.width = width;
+    this.height = height;
+  }
+  area() { return this.width * this.height; }
+}
+
+var rect = Rect(3, 4);
+";
+
+/// `SHAPES` followed by `code`.
+fn with_shapes(code: &str) -> String {
+    format!("{}{}", SHAPES, code)
+}
+
+#[test]
+fn fields_come_back_in_the_order_they_were_first_set() {
+    assert_output(
+        &with_shapes("print fields(rect);\nrect.name = \"renamed\";\nprint fields(rect);\n"),
+        &["[name, width, height]", "[name, width, height]"],
+    );
+}
+
+#[test]
+fn methods_list_a_class_and_its_superclasses() {
+    assert_output(
+        &with_shapes("print methods(rect);\nprint methods(Rect);\nprint methods(Shape);\n"),
+        &[
+            "[init, area, describe]",
+            "[init, area, describe]",
+            "[init, describe, area]",
+        ],
+    );
+}
+
+#[test]
+fn class_name_names_the_instances_own_class() {
+    assert_output(
+        &with_shapes("print className(rect);\nprint className(Shape(\"blob\"));\n"),
+        &["Rect", "Shape"],
+    );
+}
+
+#[test]
+fn fields_can_be_read_and_written_by_name() {
+    assert_output(
+        &with_shapes(
+            "print getField(rect, \"width\");\nprint getField(rect, \"depth\", 1);\n\
+             setField(rect, \"depth\", 5);\nprint rect.depth;\nprint getField(rect, \"depth\", 1);\n\
+             print fields(rect);\n",
+        ),
+        &["3", "1", "5", "5", "[name, width, height, depth]"],
+    );
+}
+
+#[test]
+fn a_serializer_can_be_written_in_lox() {
+    assert_output(
+        &with_shapes(
+            "fun toMap(instance) {
+  var map = {\"class\": className(instance)};
+  var names = fields(instance);
+  while (names.len() > 0) {
+    var name = names.remove(0);
+    map.set(name, getField(instance, name));
+  }
+  return map;
+}
+setField(rect, \"depth\", 5);
+print toMap(rect);
+print jsonEncode(toMap(Shape(\"blob\")));
+",
+        ),
+        &[
+            "{class: Rect, name: rect, width: 3, height: 4, depth: 5}",
+            "{\"class\":\"Shape\",\"name\":\"blob\"}",
+        ],
+    );
+}
+
+#[test]
+fn a_missing_field_without_a_default_is_an_error() {
+    assert_eq!(
+        runtime_error(&with_shapes("getField(rect, \"depth\");")),
+        "test.lox:17:1 Rect instance has no field 'depth'."
+    );
+}
+
+#[test]
+fn arguments_of_the_wrong_type_are_errors() {
+    assert_eq!(
+        runtime_error("fields(1);"),
+        "test.lox:1:1 fields() needs an instance, not a Number."
+    );
+    assert_eq!(
+        runtime_error("methods(\"Shape\");"),
+        "test.lox:1:1 methods() needs a class or an instance, not a String."
+    );
+    assert_eq!(
+        runtime_error(&with_shapes("setField(rect, 1, 2);")),
+        "test.lox:17:1 setField() needs the field name as a String, not a Number."
+    );
+}
@@ -0,0 +1,159 @@
+//! Lines typed at the REPL and what they echo.
+
+use rlox::{Interpreter, LoxError, OutputBuffer, Runner};
+
+/// Runs `lines` at the REPL one after another, returning what was printed.
+fn repl(lines: &[&str]) -> String {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    for line in lines {
+        runner.run_repl_line(line).unwrap();
+    }
+    output.contents()
+}
+
+#[test]
+fn declarations_echo_what_they_bound() {
+    assert_eq!(
+        repl(&[
+            "fun compute() { return 6 * 7; }",
+            "var x = compute();",
+            "const name = \"lox\";",
+            "class Point {}",
+            "enum Color { Red, Green }",
+            "var unset;",
+        ]),
+        "\
+compute = <fn compute>
+x = 42
+name = lox
+Point = Point
+Color = <enum Color>
+unset = nil
+"
+    );
+}
+
+#[test]
+fn each_declaration_on_a_line_gets_its_own_echo() {
+    assert_eq!(
+        repl(&["var a = 1; var b = a + 1; print a + b;"]),
+        "a = 1\nb = 2\n3\n"
+    );
+}
+
+#[test]
+fn other_statements_echo_nothing() {
+    assert_eq!(
+        repl(&[
+            "var i = 0;",
+            "while (i < 3) i = i + 1;",
+            "{ var inner = 1; }",
+            "if (i == 3) print \"done\";",
+            "i",
+        ]),
+        "i = 0\ndone\n3\n"
+    );
+}
+
+#[test]
+fn scripts_echo_nothing() {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.run("test.lox", "var x = 1;\nfun f() {}\n").unwrap();
+    assert_eq!(output.contents(), "");
+}
+
+/// Runs `lines` at the REPL one after another, returning what was printed and the message of
+/// each line that failed.
+fn repl_with_errors(lines: &[&str]) -> (String, Vec<String>) {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    let mut errors = vec![];
+    for line in lines {
+        match runner.run_repl_line(line) {
+            Ok(()) => (),
+            Err(LoxError::Runtime(diagnostic)) => errors.push(diagnostic.message),
+            Err(e) => panic!("expected {:?} to run, got {:?}", line, e),
+        }
+    }
+    (output.contents(), errors)
+}
+
+#[test]
+fn a_failing_declaration_defines_nothing() {
+    let (output, errors) = repl_with_errors(&["var x = undefinedFn();", "print x;"]);
+    assert_eq!(output, "");
+    assert_eq!(
+        errors,
+        [
+            "Undefined variable 'undefinedFn'.",
+            "Undefined variable 'x'."
+        ]
+    );
+}
+
+#[test]
+fn a_failing_line_restores_what_it_assigned() {
+    let (output, errors) = repl_with_errors(&[
+        "var x = 1;",
+        "x = 2; x = nil + 1;",
+        "print x;",
+        "const limit = 3;",
+        "var limit = 4; print -\"no\";",
+        "limit = 5;",
+    ]);
+    // Declarations echo as they run, before the line fails.
+    assert_eq!(output, "x = 1\n1\nlimit = 3\nlimit = 4\n");
+    assert_eq!(errors.len(), 3, "{:?}", errors);
+    // The failed redeclaration didn't stick, so `limit` is still a constant.
+    assert_eq!(errors[2], "Cannot assign to constant 'limit'.");
+}
+
+#[test]
+fn a_failing_block_undoes_every_global_it_defined() {
+    let (output, errors) = repl_with_errors(&[
+        "var kept = 1;",
+        "{ kept = 2; } var a = 1; var b = 2; fun f() {} print nil + 1;",
+        "print kept;",
+        "print a;",
+        "print f;",
+    ]);
+    assert_eq!(output, "kept = 1\na = 1\nb = 2\nf = <fn f>\n1\n");
+    assert_eq!(
+        &errors[1..],
+        ["Undefined variable 'a'.", "Undefined variable 'f'."]
+    );
+}
+
+#[test]
+fn results_chain_through_underscore() {
+    assert_eq!(
+        repl(&["6 * 7", "_ / 2", "_ + _2", "print _3;"]),
+        "42\n21\n63\n42\n"
+    );
+}
+
+#[test]
+fn statements_and_errors_leave_underscore_alone() {
+    let (output, errors) = repl_with_errors(&["1 + 1", "print 5;", "nil + 1", "_"]);
+    assert_eq!(output, "2\n5\n2\n");
+    assert_eq!(errors.len(), 1);
+}
+
+#[test]
+fn a_declared_underscore_doesnt_stop_results() {
+    assert_eq!(
+        repl(&["var _ = \"mine\";", "print _;", "3", "_ * 2"]),
+        "_ = mine\nmine\n3\n6\n"
+    );
+}
@@ -0,0 +1,97 @@
+//! Misuses of names, `this`, `super` and `return` caught before anything runs: each
+//! `tests/resolve/*.lox` program lists the exact errors it should produce in
+//! `// expect: line:col message` comments, each followed by a `// help: ...` comment with the
+//! help the error comes with.
+
+use std::{fs, path::PathBuf};
+
+use rlox::testing::{assert_output, run_lox};
+use rlox::{LanguageOptions, LoxError};
+
+const EXPECT: &str = "// expect: ";
+const HELP: &str = "// help: ";
+
+#[test]
+fn misuses_report_exactly_the_expected_errors() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/resolve");
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lox"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty());
+
+    let mut failures = vec![];
+    for path in paths {
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let code = fs::read_to_string(&path).unwrap();
+        let expected: Vec<String> = code
+            .lines()
+            .map(str::trim)
+            .filter_map(|line| match line.strip_prefix(EXPECT) {
+                Some(error) => Some(format!("{}:{}", name, error)),
+                None => line
+                    .strip_prefix(HELP)
+                    .map(|help| format!("  help: {}", help)),
+            })
+            .collect();
+
+        let actual: Vec<String> = match rlox::compile(&name, &code, LanguageOptions::default()) {
+            Err(LoxError::Resolve(errors)) => errors
+                .iter()
+                .flat_map(|error| {
+                    let help = error.help.as_ref().map(|help| format!("  help: {}", help));
+                    [Some(format!("{} {}", error.location, error.message)), help]
+                })
+                .flatten()
+                .collect(),
+            other => vec![format!("no resolve errors: {:?}", other.err())],
+        };
+
+        if actual != expected {
+            failures.push(format!(
+                "{}\n  expected: {:#?}\n  actual: {:#?}",
+                name, expected, actual
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn nested_functions_in_methods_still_see_this_and_super() {
+    assert_output(
+        "class Base {\n  name() { return \"base\"; }\n}\n\
+         class Derived < Base {\n  name() { return \"derived\"; }\n  \
+         describe() {\n    fun outer() {\n      fun inner() {\n        \
+         return this.name() + \" of \" + super.name();\n      }\n      return inner;\n    }\n    \
+         return outer()();\n  }\n}\nprint Derived().describe();\n",
+        &["derived of base"],
+    );
+}
+
+#[test]
+fn top_level_functions_can_call_ones_declared_later() {
+    assert_output(
+        "fun isEven(n) { if (n == 0) return true; return isOdd(n - 1); }\n\
+         fun isOdd(n) { if (n == 0) return false; return isEven(n - 1); }\n\
+         print isEven(4);\n",
+        &["true"],
+    );
+}
+
+#[test]
+fn a_parameter_named_after_its_function_is_warned_about() {
+    let result = run_lox("fun f(f) { return f; }\nprint f(1);\n");
+    assert!(result.error.is_none(), "{:?}", result.error);
+    assert_eq!(result.output, ["1"]);
+    assert_eq!(result.diagnostics.len(), 1);
+    assert_eq!(result.diagnostics[0].code, "parameter-shadows-function");
+    assert_eq!(
+        result.diagnostics[0].to_string(),
+        "test.lox:1:7 Parameter 'f' on line 1 shadows the name of its own function, declared at \
+         line 1."
+    );
+}
@@ -0,0 +1,136 @@
+//! Printing syntax trees as source with `to_source` and parsing them back.
+
+use std::{fs, path::Path};
+
+use rlox::{
+    testing::{without_groupings, AstGenerator},
+    BinOp, Expr, Stmt,
+};
+
+fn reparse(stmts: &[Stmt]) -> Vec<Stmt> {
+    let source = rlox::to_source(stmts);
+    rlox::parse("printed.lox", &source)
+        .unwrap_or_else(|e| panic!("printed source doesn't parse: {:?}\n{}", e, source))
+}
+
+#[test]
+fn generated_programs_survive_printing() {
+    for seed in 0..2000 {
+        let program = AstGenerator::new(seed).program();
+        let reparsed = reparse(&program);
+        assert!(
+            without_groupings(reparsed) == without_groupings(program.clone()),
+            "seed {} printed as\n{}",
+            seed,
+            rlox::to_source(&program)
+        );
+    }
+}
+
+#[test]
+fn deeply_nested_programs_survive_printing() {
+    for seed in 0..100 {
+        let program = AstGenerator::new(seed).max_depth(7).program();
+        let reparsed = reparse(&program);
+        assert!(
+            without_groupings(reparsed) == without_groupings(program.clone()),
+            "seed {} printed as\n{}",
+            seed,
+            rlox::to_source(&program)
+        );
+    }
+}
+
+/// Every `.lox` file under `dir` that parses, with its contents.
+fn corpus(dir: &Path, files: &mut Vec<(String, String)>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            corpus(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "lox") {
+            let code = fs::read_to_string(&path).unwrap();
+            if rlox::parse("corpus.lox", &code).is_ok() {
+                files.push((path.display().to_string(), code));
+            }
+        }
+    }
+}
+
+#[test]
+fn source_files_survive_printing() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut files = vec![];
+    for dir in ["demo", "src", "tests"] {
+        corpus(&root.join(dir), &mut files);
+    }
+    assert!(files.len() > 10, "only found {} files", files.len());
+
+    for (path, code) in files {
+        let parsed = rlox::parse(&path, &code).unwrap();
+        let printed = rlox::to_source(&parsed);
+        let reparsed = reparse(&parsed);
+        // Parentheses in the source are kept as they are, so nothing needs ignoring.
+        assert!(reparsed == parsed, "{} printed as\n{}", path, printed);
+        assert_eq!(rlox::to_source(&reparsed), printed, "{}", path);
+    }
+}
+
+#[test]
+fn operands_are_parenthesized_where_precedence_needs_it() {
+    let number = |n: i32| Expr::literal(n);
+    let program = vec![
+        Stmt::print(Expr::binary(
+            Expr::binary(number(1), BinOp::Plus, number(2)),
+            BinOp::Multiply,
+            number(3),
+        )),
+        Stmt::print(Expr::binary(
+            number(1),
+            BinOp::Minus,
+            Expr::binary(number(2), BinOp::Minus, number(3)),
+        )),
+        Stmt::print(Expr::binary(
+            Expr::binary(number(1), BinOp::Minus, number(2)),
+            BinOp::Minus,
+            number(3),
+        )),
+        Stmt::expression(Expr::assign("a", Expr::assign("b", number(1)))),
+    ];
+
+    assert_eq!(
+        rlox::to_source(&program),
+        "print (1 + 2) * 3;\nprint 1 - (2 - 3);\nprint 1 - 2 - 3;\na = b = 1;\n"
+    );
+}
+
+#[test]
+fn statements_come_out_laid_out() {
+    let code = "/// Counts down.\nfun countdown(n) { while (n > 0) { print n; n = n - 1; } \
+                if (n == 0) print \"done\"; else { print \"?\"; } }";
+    let stmts = rlox::parse("test.lox", code).unwrap();
+
+    assert_eq!(
+        rlox::to_source(&stmts),
+        "/// Counts down.\n\
+         fun countdown(n) {\n  \
+           while (n > 0) {\n    \
+             print n;\n    \
+             n = n - 1;\n  \
+           }\n  \
+           if (n == 0)\n    \
+             print \"done\";\n  \
+           else {\n    \
+             print \"?\";\n  \
+           }\n\
+         }\n"
+    );
+}
+
+#[test]
+fn maps_starting_a_statement_are_parenthesized() {
+    let stmts = rlox::parse("test.lox", "({\"a\": 1}).a;").unwrap();
+    let printed = rlox::to_source(&stmts);
+
+    assert_eq!(printed, "({\"a\": 1}).a;\n");
+    assert!(rlox::parse("test.lox", &printed).unwrap() == stmts);
+}
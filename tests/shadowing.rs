@@ -0,0 +1,145 @@
+//! The `shadowing` warning for declarations that reuse a name from an enclosing scope.
+
+use rlox::{
+    Diagnostic, DiagnosticOptions, Interpreter, LanguageOptions, LoxError, OutputBuffer, Runner,
+};
+
+fn quiet_runner() -> Runner {
+    let interpreter = Interpreter::builder()
+        .output(Box::new(OutputBuffer::new()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.set_diagnostic_options(DiagnosticOptions {
+        print: None,
+        ..DiagnosticOptions::default()
+    });
+    runner
+}
+
+/// The warnings running `code` as a script reports.
+fn warnings(code: &str) -> Vec<Diagnostic> {
+    let mut runner = quiet_runner();
+    runner.run("test.lox", code).unwrap();
+    runner.diagnostics().diagnostics().to_vec()
+}
+
+#[test]
+fn a_local_shadowing_a_parameter_names_both_declarations() {
+    let code = "\
+fun total(values) {
+  var sum = 0;
+  for (var i = 0; i < values.len(); i = i + 1) {
+    var values = i;
+    sum = sum + values;
+  }
+  return sum;
+}
+";
+    let warnings = warnings(code);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].code, "shadowing");
+    assert_eq!(
+        warnings[0].to_string(),
+        "test.lox:4:9 'values' shadows the 'values' declared at test.lox:1:11. Rename one of \
+         them if this is a typo, or run with --allow shadowing if it is on purpose."
+    );
+}
+
+#[test]
+fn locals_can_shadow_globals_and_other_locals() {
+    let code = "\
+var limit = 10;
+fun check() {
+  var limit = 5;
+  {
+    fun limit() {}
+  }
+}
+";
+    let lines: Vec<_> = warnings(code)
+        .iter()
+        .map(|warning| (warning.line, warning.message.clone()))
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            (
+                3,
+                "'limit' shadows the 'limit' declared at test.lox:1:5.".to_string()
+            ),
+            (
+                5,
+                "'limit' shadows the 'limit' declared at test.lox:3:7.".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn unrelated_scopes_and_parameters_do_not_warn() {
+    let code = "\
+var x = 1;
+fun f(x) { return x; }
+{ var y = 1; }
+{ var y = 2; }
+fun g() { var z = 1; return z; }
+fun h() { var z = 2; return z; }
+";
+    assert_eq!(warnings(code), []);
+}
+
+#[test]
+fn rebinding_from_the_shadowed_name_is_left_to_the_resolver_error() {
+    let mut runner = quiet_runner();
+    let result = runner.run(
+        "test.lox",
+        "var count = 1;\n{\n  var count = count + 1;\n}\n",
+    );
+    match result {
+        Err(LoxError::Resolve(errors)) => assert!(
+            errors[0]
+                .message
+                .starts_with("Can't read local variable 'count' in its own initializer"),
+            "{:?}",
+            errors
+        ),
+        other => panic!("expected a resolve error, got {:?}", other),
+    }
+    assert!(runner.diagnostics().diagnostics().is_empty());
+}
+
+#[test]
+fn allowed_names_can_be_shadowed() {
+    let mut runner = quiet_runner();
+    runner.set_language(LanguageOptions {
+        allow_shadowing: &["i"],
+        ..LanguageOptions::default()
+    });
+    runner
+        .run(
+            "test.lox",
+            "for (var i = 0; i < 2; i = i + 1) for (var i = 0; i < 2; i = i + 1) {}\n\
+             var j = 0;\n{ var j = 1; }\n",
+        )
+        .unwrap();
+
+    let warnings = runner.diagnostics().diagnostics();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].line, 3);
+
+    let allowed = LanguageOptions {
+        allow_shadowing: &["j"],
+        ..LanguageOptions::default()
+    };
+    assert!(rlox::compile("test.lox", "var j = 0;\n{ var j = 1; }\n", allowed).is_ok());
+}
+
+#[test]
+fn the_repl_does_not_warn() {
+    let mut runner = quiet_runner();
+    runner.run_repl_line("var x = 1;").unwrap();
+    runner
+        .run_repl_line("var y = 1; { var y = 2; } fun f(a) { var a2 = a; { var a2 = 0; } }")
+        .unwrap();
+    assert!(runner.diagnostics().diagnostics().is_empty());
+}
@@ -0,0 +1,42 @@
+//! Strings and functions are shared between the variables holding them, but a string
+//! built from another is always a value of its own.
+
+use rlox::testing::run_output;
+
+#[test]
+fn appending_to_a_parameter_leaves_the_argument_alone() {
+    let code = "fun shout(s) { s = s + \"!\"; return s; }\n\
+                var word = \"hey\";\nvar loud = shout(word);\nprint word;\nprint loud;\n";
+    assert_eq!(run_output(code).unwrap(), "hey\nhey!\n");
+}
+
+#[test]
+fn a_captured_string_is_rebound_not_changed() {
+    let code = "var greeting = \"hi\";\n\
+                fun make() { var s = greeting; fun add() { s = s + \"!\"; return s; } return add; }\n\
+                var add = make();\nadd();\nprint add();\nprint greeting;\n";
+    assert_eq!(run_output(code).unwrap(), "hi!!\nhi\n");
+}
+
+#[test]
+fn strings_in_collections_are_independent_of_the_variable() {
+    let code = "var s = \"a\";\nvar items = [s, s];\nvar m = {\"k\": s};\ns = s + \"b\";\n\
+                items.push(s + \"c\");\nprint items;\nprint m;\nprint s;\n";
+    assert_eq!(run_output(code).unwrap(), "[a, a, abc]\n{k: a}\nab\n");
+}
+
+#[test]
+fn shared_strings_stay_equal_by_value() {
+    let code = "var a = \"lox\";\nvar b = a;\nvar c = \"l\" + \"ox\";\n\
+                print a == b;\nprint a == c;\nb = b + \"\";\nprint a == b;\n";
+    assert_eq!(run_output(code).unwrap(), "true\ntrue\ntrue\n");
+}
+
+#[test]
+fn a_shared_function_keeps_one_closure() {
+    // Copies of a function value call the same closure. Functions still never compare
+    // equal, even to themselves.
+    let code = "fun counter() { var n = 0; fun next() { n = n + 1; return n; } return next; }\n\
+                var a = counter();\nvar b = a;\na();\nprint b();\nprint a == b;\n";
+    assert_eq!(run_output(code).unwrap(), "2\nfalse\n");
+}
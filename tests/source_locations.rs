@@ -0,0 +1,68 @@
+//! Errors name the file, or REPL input, that the failing code came from.
+
+use std::fs;
+
+use rlox::{Interpreter, LoxError, OutputBuffer, Runner, RuntimeDiagnostic};
+
+fn runner() -> Runner {
+    let interpreter = Interpreter::builder()
+        .output(Box::new(OutputBuffer::new()))
+        .build();
+    Runner::with_interpreter(interpreter)
+}
+
+fn runtime_error(result: Result<(), LoxError>) -> RuntimeDiagnostic {
+    match result {
+        Err(LoxError::Runtime(diagnostic)) => diagnostic,
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
+
+#[test]
+fn an_error_in_a_loaded_module_points_into_the_module() {
+    let path =
+        std::env::temp_dir().join(format!("rlox-source-locations-{}.lox", std::process::id()));
+    fs::write(
+        &path,
+        "// Helpers.\nfun half(n) {\n  return n / \"2\";\n}\n",
+    )
+    .unwrap();
+    let module = path.canonicalize().unwrap().display().to_string();
+
+    let mut runner = runner();
+    runner.load(&path).unwrap();
+    let error = runtime_error(runner.run("main.lox", "print 1;\nhalf(4);"));
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(error.location, Some(format!("{}:3:10", module)));
+    assert_eq!(
+        error
+            .trace
+            .iter()
+            .map(|frame| frame.location.as_str())
+            .collect::<Vec<_>>(),
+        [format!("{}:3:3", module).as_str(), "main.lox:2:1"]
+    );
+}
+
+#[test]
+fn an_error_in_a_function_from_an_earlier_repl_line_points_at_that_line() {
+    let mut runner = runner();
+    runner.run_repl_line("var x = 1;").unwrap();
+    runner
+        .run_repl_line("fun broken() {\n  return -\"x\";\n}")
+        .unwrap();
+    runner.run_repl_line("print x;").unwrap();
+
+    let error = runtime_error(runner.run_repl_line("broken();"));
+
+    assert_eq!(error.location.as_deref(), Some("<repl-2>:2:11"));
+    assert_eq!(
+        error
+            .trace
+            .iter()
+            .map(|frame| frame.location.as_str())
+            .collect::<Vec<_>>(),
+        ["<repl-2>:2:3", "<repl-4>:1:1"]
+    );
+}
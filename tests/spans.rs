@@ -0,0 +1,61 @@
+//! The source spans the parser records on statements and expressions.
+
+use rlox::{ExprKind, Span, StmtKind};
+
+/// The text `span` covers in `code`.
+fn text(code: &str, span: Span) -> &str {
+    &code[span.start..span.end]
+}
+
+#[test]
+fn a_binary_expression_spans_its_lines() {
+    let code = "var total = 1 +\n  2 *\n  3;";
+    let stmts = rlox::parse("test.lox", code).unwrap();
+    let StmtKind::Var(_, Some(value)) = &stmts[0].kind else {
+        panic!("expected a var statement, got {:?}", stmts[0]);
+    };
+
+    assert_eq!(text(code, value.span()), "1 +\n  2 *\n  3");
+    assert_eq!((value.span().line, value.span().column), (1, 13));
+    let ExprKind::Binary(_, lhs, rhs) = &value.kind else {
+        panic!("expected a binary expression, got {:?}", value);
+    };
+    assert_eq!(text(code, lhs.span()), "1");
+    assert_eq!(text(code, rhs.span()), "2 *\n  3");
+    assert_eq!((rhs.span().line, rhs.span().column), (2, 3));
+    assert_eq!(text(code, stmts[0].span()), code);
+}
+
+#[test]
+fn a_block_spans_its_braces() {
+    let code = "print 0;\n{\n  var a = 1;\n  print a;\n}\nprint 2;";
+    let stmts = rlox::parse("test.lox", code).unwrap();
+    let block = &stmts[1];
+
+    assert_eq!(text(code, block.span()), "{\n  var a = 1;\n  print a;\n}");
+    assert_eq!((block.span().line, block.span().column), (2, 1));
+    let StmtKind::Block(inner) = &block.kind else {
+        panic!("expected a block, got {:?}", block);
+    };
+    assert_eq!(text(code, inner[0].span()), "var a = 1;");
+    assert_eq!(text(code, inner[1].span()), "print a;");
+    assert_eq!((inner[1].span().line, inner[1].span().column), (4, 3));
+}
+
+#[test]
+fn a_call_spans_its_arguments() {
+    let code = "greet(\"lox\", 1 + 2);";
+    let stmts = rlox::parse("test.lox", code).unwrap();
+    let StmtKind::Expression(call) = &stmts[0].kind else {
+        panic!("expected an expression statement, got {:?}", stmts[0]);
+    };
+
+    assert_eq!(text(code, call.span()), "greet(\"lox\", 1 + 2)");
+    let ExprKind::Call(callee, arguments) = &call.kind else {
+        panic!("expected a call, got {:?}", call);
+    };
+    assert_eq!(text(code, callee.span()), "greet");
+    assert_eq!(text(code, arguments[0].span()), "\"lox\"");
+    assert_eq!(text(code, arguments[1].span()), "1 + 2");
+    assert_eq!(arguments[1].span().column, 14);
+}
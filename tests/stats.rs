@@ -0,0 +1,91 @@
+//! The counts of work done that interpreters keep for embedders.
+
+use rlox::{DiagnosticOptions, ExecStats, Interpreter, OutputBuffer, Runner, Severity};
+
+fn runner() -> Runner {
+    let interpreter = Interpreter::builder()
+        .output(Box::new(OutputBuffer::new()))
+        .build();
+    Runner::with_interpreter(interpreter)
+}
+
+#[test]
+fn a_call_is_counted() {
+    let mut runner = runner();
+    runner
+        .run(
+            "test.lox",
+            "var a = 1;\nfun f(x) { return x + a; }\nprint f(a);\n",
+        )
+        .unwrap();
+
+    assert_eq!(
+        *runner.interpreter().stats(),
+        ExecStats {
+            statements: 4,
+            expressions: 7,
+            calls: 1,
+            max_call_depth: 1,
+            environments: 1,
+        }
+    );
+}
+
+#[test]
+fn recursion_and_tail_calls_are_counted() {
+    let code = "\
+fun down(n) { if (n > 0) { down(n - 1); } }
+down(3);
+fun tail(n) { if (n == 0) return 0; return tail(n - 1); }
+tail(3);
+";
+    let mut runner = runner();
+    runner.run("test.lox", code).unwrap();
+
+    // Tail calls reuse their frame, so only `down` goes deeper.
+    assert_eq!(
+        runner.take_stats(),
+        ExecStats {
+            statements: 22,
+            expressions: 58,
+            calls: 8,
+            max_call_depth: 4,
+            environments: 8,
+        }
+    );
+}
+
+#[test]
+fn taking_the_stats_starts_them_over() {
+    let mut runner = runner();
+    runner.run("first.lox", "{ var a = 1; }\n").unwrap();
+    assert_eq!(runner.take_stats().environments, 1);
+    assert_eq!(*runner.interpreter().stats(), ExecStats::default());
+
+    runner.run("second.lox", "print 1 + 2;\n").unwrap();
+    let stats = runner.take_stats();
+    assert_eq!((stats.statements, stats.expressions), (1, 3));
+}
+
+#[test]
+fn timed_runs_report_through_the_diagnostics() {
+    let mut runner = runner();
+    runner.set_diagnostic_options(DiagnosticOptions {
+        print: None,
+        ..DiagnosticOptions::default()
+    });
+    runner.run_timed("test.lox", "print 1;\n").unwrap();
+
+    let diagnostics = runner.diagnostics().diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Note);
+    assert_eq!(diagnostics[0].code, "timing");
+    assert!(
+        diagnostics[0].message.starts_with("parse: ")
+            && diagnostics[0].message.ends_with(
+                "\nstatements: 1, expressions: 1, calls: 0, max call depth: 0, environments: 0"
+            ),
+        "{}",
+        diagnostics[0].message
+    );
+}
@@ -0,0 +1,127 @@
+//! Running scripts with `Runner::run_streaming` while they are being read.
+
+use std::io::{self, BufRead, BufReader, Read};
+
+use rlox::{Interpreter, LoxError, OutputBuffer, Runner};
+
+/// Streams `code` as `stream.lox` and returns what it printed along with how it ended.
+fn stream(code: &str) -> (String, Result<(), LoxError>) {
+    stream_from(code.as_bytes())
+}
+
+fn stream_from(reader: impl BufRead) -> (String, Result<(), LoxError>) {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    let result = runner.run_streaming("stream.lox", reader);
+    (output.contents(), result)
+}
+
+/// A script made up on the fly, one line per read: `lines` statements adding up a counter,
+/// then one printing it.
+struct Generated {
+    lines: usize,
+    written: usize,
+}
+
+impl Read for Generated {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let line = match self.written {
+            0 => "var total = 0;\n".to_string(),
+            n if n <= self.lines => format!("total = total + {};\n", n % 10),
+            n if n == self.lines + 1 => "print total;\n".to_string(),
+            _ => return Ok(0),
+        };
+        self.written += 1;
+        buf[..line.len()].copy_from_slice(line.as_bytes());
+        Ok(line.len())
+    }
+}
+
+/// A reader that fails once the text before the failure has been read.
+struct Broken(&'static [u8]);
+
+impl Read for Broken {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.0.is_empty() {
+            return Err(io::Error::other("disk on fire"));
+        }
+        self.0.read(buf)
+    }
+}
+
+#[test]
+fn runs_like_batch_mode() {
+    let code =
+        "fun greet(who) {\n  return \"hi \" + who;\n}\nclass A { name() { return \"a\"; } }\n\
+                var s = \"two\nlines\";\nprint greet(A().name());\nprint s;\n\
+                for (var i = 0; i < 2; i = i + 1) print i;";
+    let (output, result) = stream(code);
+    assert!(result.is_ok(), "{:?}", result);
+    assert_eq!(output, "hi a\ntwo\nlines\n0\n1\n");
+}
+
+#[test]
+fn huge_generated_script_finishes() {
+    let lines = 200_000;
+    let (output, result) = stream_from(BufReader::new(Generated { lines, written: 0 }));
+    assert!(result.is_ok(), "{:?}", result);
+    let total: usize = (1..=lines).map(|n| n % 10).sum();
+    assert_eq!(output, format!("{}\n", total));
+}
+
+#[test]
+fn parse_error_stops_after_what_came_before() {
+    let (output, result) = stream("print 1;\n{\n  print 2;\n  print (;\n}\nprint 3;\n");
+    assert_eq!(output, "1\n");
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "Parser Error: stream.lox:4:10 Expect expression, found ';'."
+    );
+}
+
+#[test]
+fn lex_error_is_reported_over_the_cut_off_statement() {
+    let (output, result) = stream("print 1;\nprint 2 @ 3;\nprint 4;\n");
+    assert_eq!(output, "1\n");
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "Lexer Error: stream.lox:2:9 Unexpected character '@' (U+0040)."
+    );
+
+    let (output, result) = stream("print \"a\";\nprint \"unterminated;\nprint 2;\n");
+    assert_eq!(output, "a\n");
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "Lexer Error: stream.lox:2:7 Unterminated string."
+    );
+}
+
+#[test]
+fn resolve_and_runtime_errors_are_located() {
+    let (output, result) = stream("print 1;\nfun f() {\n  var a = a;\n}\n");
+    assert_eq!(output, "1\n");
+    assert!(
+        result.unwrap_err().to_string().contains("stream.lox:3:11"),
+        "resolve errors are located"
+    );
+
+    let (output, result) = stream("print 1;\n\nvar x = nil;\nx();\nprint 2;\n");
+    assert_eq!(output, "1\n");
+    let error = result.unwrap_err().to_string();
+    assert!(
+        error.starts_with("Interpreter Error: stream.lox:4:1 Attempted to call nil."),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn read_error_stops_the_script() {
+    let (output, result) = stream_from(BufReader::new(Broken(b"print 1;\nprint 2;\n")));
+    assert_eq!(output, "1\n2\n");
+    let error = result.unwrap_err().to_string();
+    assert!(error.contains("disk on fire"), "{}", error);
+}
@@ -0,0 +1,163 @@
+//! Strings as sequences of characters: `chars()`, indexing, `for`-`in` loops and `len()` all
+//! agree on what a character is.
+
+use rlox::testing::{run_output, runtime_error};
+
+#[test]
+fn chars_splits_a_string_into_one_character_strings() {
+    assert_eq!(
+        run_output("print chars(\"hello\");").unwrap(),
+        "[h, e, l, l, o]\n"
+    );
+    assert_eq!(run_output("print chars(\"\");").unwrap(), "[]\n");
+    assert_eq!(run_output("print chars(\"\").len();").unwrap(), "0\n");
+}
+
+#[test]
+fn characters_are_unicode_scalar_values() {
+    let code = "
+        var s = \"héllo\";
+        print s.len();
+        print chars(s);
+        print chars(s).len() == s.len();
+    ";
+    assert_eq!(run_output(code).unwrap(), "5\n[h, é, l, l, o]\ntrue\n");
+}
+
+#[test]
+fn multibyte_characters_are_never_split() {
+    let code = "
+        var s = \"a🦀b👋🏽c\";
+        print s.len();
+        var cs = chars(s);
+        print cs.len();
+        print cs;
+        print join(cs, \"\") == s;
+        var lengths = \"\";
+        while (cs.len() > 0) lengths = lengths + cs.remove(0).len().toString();
+        print lengths;
+    ";
+    // The waving hand and its skin tone modifier are two scalar values.
+    assert_eq!(
+        run_output(code).unwrap(),
+        "6\n6\n[a, 🦀, b, 👋, 🏽, c]\ntrue\n111111\n"
+    );
+}
+
+#[test]
+fn chars_needs_a_string() {
+    assert_eq!(
+        runtime_error("chars(12);"),
+        "test.lox:1:1 chars() needs a String, not a Number."
+    );
+}
+
+#[test]
+fn indexing_a_string_gives_one_character_strings() {
+    let code = "
+        var s = \"héllo\";
+        print s[0];
+        print s[1];
+        print s[s.len() - 1];
+        print s[1].len();
+    ";
+    assert_eq!(run_output(code).unwrap(), "h\né\no\n1\n");
+}
+
+#[test]
+fn every_index_below_len_is_valid_around_emoji() {
+    let code = "
+        var s = \"a🦀b👋🏽c\";
+        var rebuilt = \"\";
+        for (var i = 0; i < s.len(); i = i + 1) rebuilt = rebuilt + s[i] + \"|\";
+        print rebuilt;
+    ";
+    assert_eq!(run_output(code).unwrap(), "a|🦀|b|👋|🏽|c|\n");
+}
+
+#[test]
+fn looping_over_a_string_goes_through_its_characters() {
+    let code = "
+        for (c in \"a🦀é\") print c;
+        var count = 0;
+        for (c in \"\") count = count + 1;
+        print count;
+    ";
+    assert_eq!(run_output(code).unwrap(), "a\n🦀\né\n0\n");
+}
+
+#[test]
+fn arrays_and_tuples_index_and_loop_the_same_way() {
+    let code = "
+        var xs = [10, 20, 30];
+        print xs[2];
+        for (x in xs) print x;
+        fun pair() { return \"a\", \"b\"; }
+        print pair()[1];
+        for (p in pair()) print p;
+    ";
+    assert_eq!(run_output(code).unwrap(), "30\n10\n20\n30\nb\na\nb\n");
+}
+
+#[test]
+fn a_loop_goes_through_the_elements_the_array_had_when_it_started() {
+    let code = "
+        var xs = [1, 2];
+        for (x in xs) xs.push(x * 10);
+        print xs;
+    ";
+    assert_eq!(run_output(code).unwrap(), "[1, 2, 10, 20]\n");
+}
+
+#[test]
+fn the_loop_variable_is_new_on_each_pass() {
+    let code = "
+        var fs = [];
+        for (c in \"ab\") {
+            fun f() { return c; }
+            fs.push(f);
+        }
+        print fs[0]() + fs[1]();
+    ";
+    assert_eq!(run_output(code).unwrap(), "ab\n");
+}
+
+#[test]
+fn indexes_past_the_end_share_the_index_error() {
+    assert_eq!(
+        runtime_error("\"a🦀\"[2];"),
+        "test.lox:1:1 Index must be at most 1, got 2."
+    );
+    assert_eq!(
+        runtime_error("[1, 2][5];"),
+        "test.lox:1:1 Index must be at most 1, got 5."
+    );
+    assert_eq!(
+        runtime_error("\"abc\"[1.5];"),
+        "test.lox:1:1 Index must be a non-negative integer, got 1.5."
+    );
+    assert_eq!(
+        runtime_error("\"abc\"[-1];"),
+        "test.lox:1:1 Index must be a non-negative integer, got -1."
+    );
+    assert_eq!(
+        runtime_error("\"abc\"[\"1\"];"),
+        "test.lox:1:1 Index must be a non-negative integer, got 1."
+    );
+    assert_eq!(
+        runtime_error("\"\"[0];"),
+        "test.lox:1:1 Can't index an empty String."
+    );
+}
+
+#[test]
+fn only_strings_arrays_and_tuples_can_be_indexed_or_looped_over() {
+    assert_eq!(
+        runtime_error("12[0];"),
+        "test.lox:1:1 Can only index a String, Array or Tuple, not a Number."
+    );
+    assert_eq!(
+        runtime_error("for (x in {\"a\": 1}) print x;"),
+        "test.lox:1:1 Can only loop over a String, Array or Tuple, not a Map."
+    );
+}
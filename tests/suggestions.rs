@@ -0,0 +1,83 @@
+//! "Did you mean" suggestions on undefined variables, and the declaration lines diagnostics
+//! point back to.
+
+use rlox::{testing::runtime_error, Runner};
+
+#[test]
+fn suggests_a_local_with_its_declaration_line() {
+    let error = runtime_error("{\n  var total = 1;\n  print totl;\n}\n");
+    assert_eq!(
+        error,
+        "test.lox:3:9 Undefined variable 'totl'. Did you mean 'total' (declared at line 2)?"
+    );
+}
+
+#[test]
+fn suggests_a_global_function_and_class() {
+    let error = runtime_error("fun greet() {}\n\ngret();\n");
+    assert_eq!(
+        error,
+        "test.lox:3:1 Undefined variable 'gret'. Did you mean 'greet' (declared at line 1)?"
+    );
+
+    let error = runtime_error("class Point {}\nvar p = Pont();\n");
+    assert_eq!(
+        error,
+        "test.lox:2:9 Undefined variable 'Pont'. Did you mean 'Point' (declared at line 1)?"
+    );
+}
+
+#[test]
+fn suggests_parameters() {
+    let error =
+        runtime_error("fun area(width, height) {\n  return wdth * height;\n}\narea(1, 2);\n");
+    assert_eq!(
+        error,
+        "test.lox:2:10 Undefined variable 'wdth'. Did you mean 'width' (declared at line 1)?"
+    );
+}
+
+#[test]
+fn suggests_natives_without_a_line() {
+    let error = runtime_error("print clok();\n");
+    assert_eq!(
+        error,
+        "test.lox:1:7 Undefined variable 'clok'. Did you mean 'clock'?"
+    );
+}
+
+#[test]
+fn suggests_on_assignment() {
+    let error = runtime_error("var count = 0;\ncont = 1;\n");
+    assert_eq!(
+        error,
+        "test.lox:2:1 Undefined variable 'cont'. Did you mean 'count' (declared at line 1)?"
+    );
+}
+
+#[test]
+fn no_suggestion_without_a_close_name() {
+    let error = runtime_error("var apple = 1;\nprint zebra;\n");
+    assert_eq!(error, "test.lox:2:7 Undefined variable 'zebra'.");
+
+    let error = runtime_error("const limit = 1;\nlimit = 2;\n");
+    assert_eq!(error, "test.lox:2:1 Cannot assign to constant 'limit'.");
+}
+
+#[test]
+fn shadowing_lint_names_both_lines() {
+    let mut runner = Runner::new();
+    runner
+        .run("test.lox", "fun f(a) {}\n\nfun walk(\n  walk\n) {}\n")
+        .unwrap();
+    let messages: Vec<&str> = runner
+        .diagnostics()
+        .diagnostics()
+        .iter()
+        .map(|diagnostic| diagnostic.message.as_str())
+        .collect();
+    assert_eq!(
+        messages,
+        ["Parameter 'walk' on line 4 shadows the name of its own function, declared at line 3."]
+    );
+}
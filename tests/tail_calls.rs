@@ -0,0 +1,40 @@
+//! `return f(...)` runs in a loop rather than growing the stack.
+
+use rlox::testing::assert_output;
+
+#[test]
+fn a_million_tail_calls_dont_overflow() {
+    assert_output(
+        "fun count(n) { if (n == 0) return \"done\"; return count(n - 1); }\nprint count(1000000);\n",
+        &["done"],
+    );
+}
+
+#[test]
+fn mutual_recursion_runs_in_the_loop_too() {
+    assert_output(
+        "fun isEven(n) { if (n == 0) return true; return isOdd(n - 1); }\n\
+         fun isOdd(n) { if (n == 0) return false; return isEven(n - 1); }\n\
+         print isEven(100001);\nprint isOdd(100001);\n",
+        &["false", "true"],
+    );
+}
+
+#[test]
+fn closures_made_before_a_tail_call_keep_their_frame() {
+    assert_output(
+        "var saved = [];\n\
+         fun collect(n) {\n  fun get() { return n; }\n  saved.push(get);\n  \
+         if (n == 0) return saved.len();\n  return collect(n - 1);\n}\n\
+         print collect(3);\nwhile (saved.len() > 0) print saved.remove(0)();\n",
+        &["4", "3", "2", "1", "0"],
+    );
+}
+
+#[test]
+fn calls_that_arent_in_tail_position_still_return_to_the_caller() {
+    assert_output(
+        "fun sum(n) { if (n == 0) return 0; return n + sum(n - 1); }\nprint sum(10);\n",
+        &["55"],
+    );
+}
@@ -0,0 +1,33 @@
+//! The `now()`, `formatTime()` and `parseTime()` natives.
+
+use rlox::testing::{assert_output, assert_runtime_error};
+
+#[test]
+fn the_frozen_clock_formats_from_the_epoch() {
+    assert_output(
+        "print formatTime(now(), \"%Y-%m-%d %H:%M:%S\");\nprint formatTime(now(), \"%S\");",
+        &["1970-01-01 00:00:00", "01"],
+    );
+}
+
+#[test]
+fn parse_time_reads_what_format_time_wrote() {
+    assert_output(
+        "var t = parseTime(\"2024-02-29 13:45:00\", \"%Y-%m-%d %H:%M:%S\");\n\
+         print formatTime(t + 11 * 60 * 60 * 1000, \"%Y-%m-%d %H:%M\");\n\
+         print parseTime(\"2023-02-29\", \"%Y-%m-%d\");",
+        &["2024-03-01 00:45", "nil"],
+    );
+}
+
+#[test]
+fn invalid_patterns_are_runtime_errors() {
+    assert_runtime_error(
+        "formatTime(0, \"%Y-%B\");",
+        "Unknown directive '%B' in time pattern \"%Y-%B\"; use %Y %m %d %H %M %S or %%.",
+    );
+    assert_runtime_error(
+        "parseTime(\"2024\", \"%Y%\");",
+        "Time pattern \"%Y%\" ends in a lone '%'",
+    );
+}
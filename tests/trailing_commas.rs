@@ -0,0 +1,115 @@
+//! Every comma-separated list may end with a comma before its closing delimiter, but not
+//! start with one or have two in a row.
+
+use rlox::testing::{run_output, syntax_error};
+
+#[test]
+fn parameters() {
+    let code = "fun add(\n  a,\n  b,\n) {\n  return a + b;\n}\nprint add(1, 2);";
+    assert_eq!(run_output(code).unwrap(), "3\n");
+    assert_eq!(
+        run_output("fun add(a, b) { return a + b; } print add(1, 2);").unwrap(),
+        "3\n"
+    );
+}
+
+#[test]
+fn arguments() {
+    let code = "fun add(a, b) { return a + b; }\nprint add(\n  1,\n  2,\n);";
+    assert_eq!(run_output(code).unwrap(), "3\n");
+    assert_eq!(run_output("print clock(  ) >= 0;").unwrap(), "true\n");
+}
+
+#[test]
+fn array_literals() {
+    assert_eq!(run_output("print [1, 2,];").unwrap(), "[1, 2]\n");
+    assert_eq!(run_output("print [1, 2];").unwrap(), "[1, 2]\n");
+}
+
+#[test]
+fn map_literals() {
+    assert_eq!(run_output("print {\"a\": 1,};").unwrap(), "{a: 1}\n");
+    assert_eq!(run_output("print {\"a\": 1};").unwrap(), "{a: 1}\n");
+}
+
+#[test]
+fn enum_variants() {
+    assert_eq!(run_output("enum E { A, B, } print E.B;").unwrap(), "E.B\n");
+    assert_eq!(run_output("enum E { A, B } print E.B;").unwrap(), "E.B\n");
+}
+
+#[test]
+fn patterns() {
+    let code = |pattern: &str| {
+        format!(
+            "print match ([1, {{\"k\": 2}}]) {{ {} -> x + y; _ -> 0; }};",
+            pattern
+        )
+    };
+    assert_eq!(run_output(&code("[x, {k: y,},]")).unwrap(), "3\n");
+    assert_eq!(run_output(&code("[x, {k: y}]")).unwrap(), "3\n");
+}
+
+#[test]
+fn double_commas_are_rejected() {
+    for (code, error) in [
+        (
+            "fun f(a,, b) {}",
+            "1:9 Expect parameter name between commas.",
+        ),
+        (
+            "print clock(1,, 2);",
+            "1:15 Expect argument between commas.",
+        ),
+        ("print [1,,];", "1:10 Expect array element between commas."),
+        (
+            "print {\"a\": 1,,};",
+            "1:15 Expect map entry between commas.",
+        ),
+        (
+            "enum E { A,, B }",
+            "1:12 Expect enum variant between commas.",
+        ),
+        (
+            "print match (1) { [a,,] -> 1 };",
+            "1:22 Expect pattern between commas.",
+        ),
+        (
+            "print match (1) { {a: b,,} -> 1 };",
+            "1:25 Expect map pattern entry between commas.",
+        ),
+    ] {
+        assert_eq!(
+            syntax_error(code),
+            format!("Parser Error: test.lox:{}", error),
+            "{}",
+            code
+        );
+    }
+}
+
+#[test]
+fn leading_commas_are_rejected() {
+    for (code, error) in [
+        ("fun f(, a) {}", "1:7 Expect parameter name before ','."),
+        ("print clock(,);", "1:13 Expect argument before ','."),
+        ("print [, 1];", "1:8 Expect array element before ','."),
+        ("print {,};", "1:8 Expect map entry before ','."),
+        ("enum E { , A }", "1:10 Expect enum variant before ','."),
+        (
+            "print match (1) { [,] -> 1 };",
+            "1:20 Expect pattern before ','.",
+        ),
+        (
+            "print match (1) { {,} -> 1 };",
+            "1:20 Expect map pattern entry before ','.",
+        ),
+    ] {
+        assert_eq!(
+            syntax_error(code),
+            format!("Parser Error: test.lox:{}", error),
+            "{}",
+            code
+        );
+    }
+}
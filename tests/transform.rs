@@ -0,0 +1,140 @@
+//! Passes over the syntax tree built on `Fold`, and the `for`-loop lowering that ships with
+//! it.
+
+use rlox::{
+    lower_for_loops, to_source, walk_expr, walk_pattern, walk_stmt, BinOp, Expr, ExprKind, Fold,
+    Interpreter, OutputBuffer, Pattern, PatternKind, Runner, Stmt, StmtKind,
+};
+
+/// Programs using every shape of `for` loop.
+const LOOPS: [&str; 6] = [
+    "for (var i = 0; i < 3; i = i + 1) print i;",
+    "var i = 10; for (; i > 7;) { print i; i = i - 1; } print i;",
+    "var fns = []; for (var i = 0; i < 3; i = i + 1) { fun f() { return i; } fns.push(f); }
+     for (var j = 0; j < 3; j = j + 1) print fns.pop()();",
+    "fun count(n) { var total = 0; for (var i = 1; i <= n; i = i + 1) total = total + i;
+     return total; } print count(100);",
+    "for (var i = 0; i < 2; i = i + 1) for (var i = 5; i < 7; i = i + 1) print i;",
+    "fun first(limit) { for (var n = 0;; n = n + 1) if (n * n > limit) return n; }
+     print first(50);",
+];
+
+fn runner(output: &OutputBuffer) -> Runner {
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .build();
+    Runner::with_interpreter(interpreter)
+}
+
+/// Runs `program`, with its loops lowered if `desugar` is set.
+fn run(program: &str, desugar: bool) -> (Result<(), String>, String) {
+    let output = OutputBuffer::new();
+    let mut runner = runner(&output);
+    runner.set_desugar(desugar);
+    let result = runner.run("loop.lox", program).map_err(|e| e.to_string());
+    (result, output.contents())
+}
+
+/// Whether a `for` statement is left anywhere in `stmts`.
+fn has_for_loop(stmts: &[Stmt]) -> bool {
+    struct FindFor(bool);
+
+    impl Fold for FindFor {
+        fn fold_stmt(&mut self, stmt: Stmt) -> Stmt {
+            self.0 |= matches!(stmt.kind, StmtKind::For(..));
+            walk_stmt(self, stmt)
+        }
+    }
+
+    let mut finder = FindFor(false);
+    finder.fold_stmts(stmts.to_vec());
+    finder.0
+}
+
+#[test]
+fn lowered_loops_do_the_same() {
+    for program in LOOPS {
+        let (result, output) = run(program, false);
+        assert!(result.is_ok(), "{}: {:?}", program, result);
+        assert_eq!((result, output), run(program, true), "{}", program);
+    }
+    assert_eq!(run(LOOPS[0], true).1, "0\n1\n2\n");
+}
+
+#[test]
+fn lowering_leaves_no_for_loops() {
+    for program in LOOPS {
+        let stmts = rlox::parse("loop.lox", program).unwrap();
+        assert!(has_for_loop(&stmts), "{}", program);
+        assert!(!has_for_loop(&lower_for_loops(stmts)), "{}", program);
+    }
+}
+
+#[test]
+fn lowering_writes_the_while_loop_out() {
+    let stmts = rlox::parse("loop.lox", "for (var i = 0; i < 3; i = i + 1) print i;").unwrap();
+
+    assert_eq!(
+        to_source(&lower_for_loops(stmts)),
+        "{\n  var i = 0;\n  while (i < 3) {\n    print i;\n    i = i + 1;\n  }\n}\n"
+    );
+}
+
+/// A pass written outside the crate: swaps `+` and `-`.
+struct SwapPlusMinus;
+
+impl Fold for SwapPlusMinus {
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        let expr = walk_expr(self, expr);
+        let kind = match expr.kind {
+            ExprKind::Binary(BinOp::Plus, lhs, rhs) => ExprKind::Binary(BinOp::Minus, lhs, rhs),
+            ExprKind::Binary(BinOp::Minus, lhs, rhs) => ExprKind::Binary(BinOp::Plus, lhs, rhs),
+            kind => kind,
+        };
+        Expr {
+            kind,
+            span: expr.span,
+        }
+    }
+}
+
+#[test]
+fn embedders_can_write_their_own_passes() {
+    let code = "fun f(a, b) { return a + b; }\n\
+                print f(5, 3) - 1;\n\
+                print match (10 - 4) { 14 -> \"folded\"; _ -> \"missed\"; };";
+    let stmts = SwapPlusMinus.fold_stmts(rlox::parse("pass.lox", code).unwrap());
+
+    let output = OutputBuffer::new();
+    runner(&output).run_program(stmts).unwrap();
+    // The pattern's `14` isn't rewritten, but the value matched against it is: 10 + 4.
+    assert_eq!(output.contents(), "3\nfolded\n");
+}
+
+#[test]
+fn passes_can_rewrite_patterns() {
+    /// Turns every binding pattern into a wildcard.
+    struct ForgetBindings;
+
+    impl Fold for ForgetBindings {
+        fn fold_pattern(&mut self, pattern: Pattern) -> Pattern {
+            let pattern = walk_pattern(self, pattern);
+            let kind = match pattern.kind {
+                PatternKind::Binding(_) => PatternKind::Wildcard,
+                kind => kind,
+            };
+            Pattern {
+                kind,
+                span: pattern.span,
+            }
+        }
+    }
+
+    let code = "print match ([1, 2]) { [x, 2] -> \"two\"; _ -> \"other\"; };";
+    let stmts = ForgetBindings.fold_stmts(rlox::parse("pass.lox", code).unwrap());
+
+    assert_eq!(
+        to_source(&stmts),
+        "print match ([1, 2]) {\n  [_, 2] -> \"two\";\n  _ -> \"other\";\n};\n"
+    );
+}
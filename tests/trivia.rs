@@ -0,0 +1,95 @@
+//! Lexing with whitespace and comments kept, as tools working on the source need.
+
+use std::fs;
+
+use rlox::{inspect, tokenize_trivia, TokenKind};
+
+fn round_trip(source: &str) -> String {
+    tokenize_trivia(source).map(|token| token.lexeme).collect()
+}
+
+#[test]
+fn lexemes_join_back_into_the_source() {
+    let sources = [
+        "",
+        "   ",
+        "print 1; // done",
+        "// only a comment\n",
+        "var café = \"line one\r\nline two\";\r\nprint café;\r\n",
+        // Not in normal form, which identifiers are normalized to.
+        "var cafe\u{301} = 1;",
+        "print \"unterminated",
+        "var x = 1 @ 2;\t\t# ?\n",
+        "fun f() {\n\treturn 1.50;\n}\n\n\n",
+        "a\rb\r\rc",
+    ];
+    for source in sources {
+        assert_eq!(round_trip(source), source);
+    }
+
+    for entry in fs::read_dir("demo").unwrap() {
+        let path = entry.unwrap().path();
+        let source = fs::read_to_string(&path).unwrap();
+        assert_eq!(round_trip(&source), source, "{}", path.display());
+    }
+}
+
+#[test]
+fn trivia_gets_tokens_of_its_own() {
+    let tokens: Vec<_> = tokenize_trivia("var x;  // the x\n  x = 1;").collect();
+    let kinds: Vec<String> = tokens
+        .iter()
+        .map(|token| match &token.value {
+            TokenKind::Whitespace(text) => format!("Whitespace({:?})", text),
+            TokenKind::LineComment(text) => format!("LineComment({:?})", text),
+            kind => format!("{:?}", kind).split('(').next().unwrap().to_string(),
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            "Var",
+            "Whitespace(\" \")",
+            "Identifier",
+            "Semicolon",
+            "Whitespace(\"  \")",
+            "LineComment(\" the x\")",
+            "Whitespace(\"\\n  \")",
+            "Identifier",
+            "Whitespace(\" \")",
+            "Eq",
+            "Whitespace(\" \")",
+            "Number",
+            "Semicolon",
+        ]
+    );
+
+    let comment = &tokens[5];
+    assert_eq!((comment.line, comment.column), (1, 9));
+    assert_eq!((comment.start, comment.length), (8, 8));
+    let x = &tokens[7];
+    assert_eq!((x.line, x.column), (2, 3));
+}
+
+#[test]
+fn normal_lexing_still_skips_trivia() {
+    let options = inspect::Options {
+        path: None,
+        format: inspect::Format::Sexpr,
+        tokens: true,
+        stmt: None,
+        desugar: false,
+        resolve: false,
+    };
+    let table = inspect::inspect("test.lox", "print 1; // one\n", &options).unwrap();
+    assert_eq!(
+        table,
+        "\
+LINE:COL  TOKEN      LEXEME
+1:1       Print      print
+1:7       Number     1
+1:8       Semicolon  ;
+2:1       Eof
+"
+    );
+}
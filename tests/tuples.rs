@@ -0,0 +1,137 @@
+//! Returning several values at once with `return a, b;` and unpacking them with `var a, b = ...;`.
+
+use rlox::{
+    testing::{run_output, runtime_error, syntax_error},
+    Interpreter, Literal, OutputBuffer, Runner,
+};
+
+#[test]
+fn returned_values_are_unpacked_in_order() {
+    let code = "
+        fun divmod(a, b) {
+          var q = 0;
+          while (a >= b) {
+            a = a - b;
+            q = q + 1;
+          }
+          return q, a;
+        }
+        var q, r = divmod(7, 2);
+        print q;
+        print r;
+    ";
+    assert_eq!(run_output(code).unwrap(), "3\n1\n");
+}
+
+#[test]
+fn a_lone_variable_receives_the_whole_tuple() {
+    let code = "
+        fun pair() { return 3, 1; }
+        var both = pair();
+        print both;
+        var a, b = both;
+        print a + b;
+    ";
+    assert_eq!(run_output(code).unwrap(), "(3, 1)\n4\n");
+    assert_eq!(
+        run_output("fun f() { return \"a\", [1, 2], nil; } print f();").unwrap(),
+        "(a, [1, 2], nil)\n"
+    );
+}
+
+#[test]
+fn unpacking_needs_a_tuple_of_the_same_length() {
+    let code = "fun pair() { return 1, 2; }\nvar a, b, c = pair();\n";
+    assert_eq!(
+        runtime_error(code),
+        "test.lox:2:15 Expected 3 values to unpack, but got 2."
+    );
+    assert_eq!(
+        runtime_error("var a, b = 12;"),
+        "test.lox:1:12 Only tuples can be unpacked, but tried to unpack a Number."
+    );
+    assert_eq!(
+        syntax_error("var a, b;"),
+        "Parser Error: test.lox:1:9 Expect '=' after the names to unpack."
+    );
+}
+
+#[test]
+fn both_lists_can_end_with_a_comma() {
+    let code = "
+        fun pair() { return 1, 2,; }
+        var a, b, = pair();
+        print a + b;
+    ";
+    assert_eq!(run_output(code).unwrap(), "3\n");
+    assert_eq!(
+        syntax_error("var a,, b = 1;"),
+        "Parser Error: test.lox:1:7 Expect variable name between commas."
+    );
+    assert_eq!(
+        syntax_error("fun f() { return 1,, 2; }"),
+        "Parser Error: test.lox:1:20 Expect return value between commas."
+    );
+}
+
+#[test]
+fn a_tuple_is_passed_as_a_single_argument() {
+    let code = "
+        fun pair() { return \"x\", 2; }
+        fun first(t) {
+          var a, b = t;
+          return a;
+        }
+        fun same(t) { return t; }
+        print first(pair());
+        print same(pair());
+        print same(pair()) == pair();
+    ";
+    assert_eq!(run_output(code).unwrap(), "x\n(x, 2)\ntrue\n");
+}
+
+#[test]
+fn natives_can_return_tuples() {
+    let output = OutputBuffer::new();
+    let interpreter = Interpreter::builder()
+        .output(Box::new(output.clone()))
+        .build();
+    let mut runner = Runner::with_interpreter(interpreter);
+    runner.define_native("minmax", 2, |_, args| match (&args[0], &args[1]) {
+        (Literal::Number(a), Literal::Number(b)) => Ok(Literal::Tuple(
+            vec![Literal::Number(a.min(*b)), Literal::Number(a.max(*b))].into(),
+        )),
+        _ => Err("minmax() needs two Numbers.".to_string().into()),
+    });
+
+    let code = "var lo, hi = minmax(9, 4);\nprint lo;\nprint hi;\nprint minmax(1, 2);\n";
+    runner.run("test.lox", code).unwrap();
+    assert_eq!(output.contents(), "4\n9\n(1, 2)\n");
+}
+
+#[test]
+fn tuples_are_equal_element_by_element() {
+    let code = "
+        fun pair(a, b) { return a, b; }
+        print pair(1, \"a\") == pair(1, \"a\");
+        print pair(1, 2) == pair(2, 1);
+        print pair(1, 2) != pair(1, 2);
+        fun triple() { return 1, 2, 3; }
+        print pair(1, 2) == triple();
+        print pair(1, 2) == [1, 2];
+        var xs = [1];
+        print pair(xs, nil) == pair(xs, nil);
+        print pair([1], nil) == pair([1], nil);
+    ";
+    assert_eq!(
+        run_output(code).unwrap(),
+        "true\nfalse\nfalse\nfalse\nfalse\ntrue\nfalse\n"
+    );
+}
+
+#[test]
+fn tuples_print_back_as_source() {
+    let code = "fun f() {\n  return 1, \"two\", 3;\n}\nvar a, b, c = f();\n";
+    let stmts = rlox::parse("test.lox", code).unwrap();
+    assert_eq!(rlox::to_source(&stmts), code);
+}
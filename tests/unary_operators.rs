@@ -0,0 +1,56 @@
+//! Unary `-` and `!`, how tightly they bind, and the unary `+` Lox doesn't have.
+
+use rlox::testing::{assert_output, runtime_error, syntax_error};
+
+#[test]
+fn unary_plus_is_rejected() {
+    assert_eq!(
+        syntax_error("var x = 1;\nprint +x;\n"),
+        "Parser Error: test.lox:2:7 Unary '+' is not supported; numbers are positive without \
+         it, so leave it out."
+    );
+    assert_eq!(
+        syntax_error("print 1 + +\"a\";\n"),
+        "Parser Error: test.lox:1:11 Unary '+' is not supported; numbers are positive without \
+         it, so leave it out."
+    );
+}
+
+#[test]
+fn negating_a_non_number_names_its_type() {
+    assert_eq!(
+        runtime_error("var name = \"lox\";\nprint -name;\n"),
+        "test.lox:2:8 Operand of '-' must be a number, but it is a String (from expression \
+         `name`)."
+    );
+    assert_eq!(
+        runtime_error("print 1 + -nil;\n"),
+        "test.lox:1:12 Operand of '-' must be a number, but it is a Nil (from expression `nil`)."
+    );
+}
+
+#[test]
+fn negation_binds_looser_than_calls_and_properties() {
+    assert_output(
+        "class Point { init() { this.y = 3; } }\nvar p = Point();\nprint -p.y;\n\
+         fun f(n) { return n * 2; }\nprint -f(1);\nprint -f(1) * 3;",
+        &["-3", "-2", "-6"],
+    );
+    assert_eq!(
+        runtime_error("class Box { init() { this.label = \"a\"; } }\nprint -Box().label;\n"),
+        "test.lox:2:8 Operand of '-' must be a number, but it is a String (from expression \
+         `Box().label`)."
+    );
+}
+
+#[test]
+fn unary_operators_nest() {
+    assert_output(
+        "var x = 2;\nprint --x;\nprint -(-x);\nprint !-x;\nprint !!x;\nprint -x - -x;\nprint !-0;",
+        &["2", "2", "false", "true", "0", "false"],
+    );
+    assert_eq!(
+        runtime_error("print -!true;\n"),
+        "test.lox:1:8 Operand of '-' must be a number, but it is a Bool (from expression `!true`)."
+    );
+}
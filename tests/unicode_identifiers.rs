@@ -0,0 +1,48 @@
+//! Identifiers follow the Unicode XID rules and are compared after NFC normalization.
+
+use rlox::{testing::run_output, LoxError};
+
+/// The lexing error `code` fails with.
+fn lex_error(code: &str) -> String {
+    match run_output(code) {
+        Err(e @ LoxError::Lex(_)) => e.to_string(),
+        other => panic!("expected a lexing error for {:?}, got {:?}", code, other),
+    }
+}
+
+#[test]
+fn precomposed_and_combining_forms_are_one_variable() {
+    // "café" with U+00E9, then with "e" and U+0301.
+    let code = "var caf\u{e9} = 1;\ncafe\u{301} = cafe\u{301} + 1;\nprint caf\u{e9};\n";
+    assert_eq!(run_output(code).unwrap(), "2\n");
+}
+
+#[test]
+fn letters_from_any_script_make_identifiers() {
+    let code = "var \u{3b1}\u{3b2}\u{3b3} = 1;\nvar \u{540d}\u{524d} = 2;\nvar _x1 = 3;\n\
+                print \u{3b1}\u{3b2}\u{3b3} + \u{540d}\u{524d} + _x1;\n";
+    assert_eq!(run_output(code).unwrap(), "6\n");
+}
+
+#[test]
+fn emoji_are_rejected_with_their_code_point() {
+    let error = lex_error("var \u{1f600} = 1;");
+    assert!(
+        error.contains("Unexpected character '\u{1f600}' (U+1F600)."),
+        "{}",
+        error
+    );
+
+    let error = lex_error("var smile\u{1f600} = 1;");
+    assert!(error.contains("(U+1F600)"), "{}", error);
+}
+
+#[test]
+fn unicode_punctuation_is_rejected() {
+    let error = lex_error("print 1 \u{2212} 1;");
+    assert!(
+        error.contains("Unexpected character '\u{2212}' (U+2212)."),
+        "{}",
+        error
+    );
+}